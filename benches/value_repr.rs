@@ -0,0 +1,203 @@
+//! Сравнение представлений значений: перечисление `Value` и `NanBox`.
+//!
+//! Запуск: `cargo bench --features nan-boxing --bench value_repr`
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ponos_rs::ponos::nanbox::{self, Heap, NanBox};
+use ponos_rs::ponos::value::Value;
+
+const ITERATIONS: u32 = 10;
+const LOOP_LEN: usize = 1_000_000;
+
+fn measure(mut f: impl FnMut()) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn report(name: &str, value: Duration, nanbox: Duration) {
+    println!(
+        "{:<22} Value: {:>10.3?}   NanBox: {:>10.3?}   x{:.2}",
+        name,
+        value,
+        nanbox,
+        value.as_secs_f64() / nanbox.as_secs_f64()
+    );
+}
+
+/// Цикл `пока i < N: i = i + 1` на стековой машине
+fn arithmetic() {
+    let value = measure(|| {
+        let mut stack: Vec<Value> = vec![Value::Number(0.0)];
+        loop {
+            stack.push(stack[0].clone());
+            stack.push(Value::Number(LOOP_LEN as f64));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            let less = match (a, b) {
+                (Value::Number(x), Value::Number(y)) => x < y,
+                _ => unreachable!(),
+            };
+            if !less {
+                break;
+            }
+            stack.push(stack[0].clone());
+            stack.push(Value::Number(1.0));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            stack[0] = match (a, b) {
+                (Value::Number(x), Value::Number(y)) => Value::Number(x + y),
+                _ => unreachable!(),
+            };
+        }
+        black_box(stack);
+    });
+
+    let boxed = measure(|| {
+        let mut heap = Heap::new();
+        let mut stack: Vec<NanBox> = vec![NanBox::number(0.0)];
+        loop {
+            stack.push(stack[0]);
+            stack.push(NanBox::number(LOOP_LEN as f64));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            if heap.less(a, b) != Ok(NanBox::TRUE) {
+                break;
+            }
+            stack.push(stack[0]);
+            stack.push(NanBox::number(1.0));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            stack[0] = heap.add(a, b).unwrap();
+        }
+        black_box(stack);
+    });
+
+    report("арифметика", value, boxed);
+}
+
+/// Тот же цикл на целых: так его выполняет VM для целочисленных литералов
+fn int_arithmetic() {
+    let value = measure(|| {
+        let mut stack: Vec<Value> = vec![Value::Int(0)];
+        loop {
+            stack.push(stack[0].clone());
+            stack.push(Value::Int(LOOP_LEN as i64));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            let less = match (a, b) {
+                (Value::Int(x), Value::Int(y)) => x < y,
+                _ => unreachable!(),
+            };
+            if !less {
+                break;
+            }
+            stack.push(stack[0].clone());
+            stack.push(Value::Int(1));
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            stack[0] = match (a, b) {
+                (Value::Int(x), Value::Int(y)) => Value::Int(x + y),
+                _ => unreachable!(),
+            };
+        }
+        black_box(stack);
+    });
+
+    let boxed = measure(|| {
+        let mut heap = Heap::new();
+        let limit = NanBox::int(LOOP_LEN as i64).unwrap();
+        let one = NanBox::int(1).unwrap();
+        let mut stack: Vec<NanBox> = vec![NanBox::int(0).unwrap()];
+        loop {
+            stack.push(stack[0]);
+            stack.push(limit);
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            if heap.less(a, b) != Ok(NanBox::TRUE) {
+                break;
+            }
+            stack.push(stack[0]);
+            stack.push(one);
+            let b = stack.pop().unwrap();
+            let a = stack.pop().unwrap();
+            stack[0] = heap.add(a, b).unwrap();
+        }
+        black_box(stack);
+    });
+
+    report("целая_арифметика", value, boxed);
+}
+
+/// Копирование стека со смешанными значениями (как при GetLocal/GetGlobal)
+fn stack_clone() {
+    let values: Vec<Value> = (0..1000)
+        .map(|i| match i % 4 {
+            0 => Value::Number(i as f64),
+            1 => Value::Boolean(i % 2 == 0),
+            2 => Value::Int(i),
+            _ => Value::String(format!("строка{}", i).into()),
+        })
+        .collect();
+
+    let mut heap = Heap::new();
+    let boxed_values: Vec<NanBox> = values.iter().cloned().map(|v| heap.encode(v)).collect();
+
+    let value = measure(|| {
+        for _ in 0..1000 {
+            black_box(values.clone());
+        }
+    });
+    let boxed = measure(|| {
+        for _ in 0..1000 {
+            black_box(boxed_values.clone());
+        }
+    });
+
+    report("копирование_стека", value, boxed);
+}
+
+/// Чтение поля экземпляра
+fn property_get() {
+    let mut fields = HashMap::new();
+    fields.insert("значение".to_string(), Value::Number(1.0));
+    let instance = nanbox::new_instance("Счетчик", fields);
+
+    let value = measure(|| {
+        for _ in 0..LOOP_LEN / 10 {
+            if let Value::Instance(rc) = &instance {
+                black_box(rc.borrow().fields.get("значение").cloned());
+            }
+        }
+    });
+
+    let mut heap = Heap::new();
+    let receiver = heap.encode(instance.clone());
+    let boxed = measure(|| {
+        for _ in 0..LOOP_LEN / 10 {
+            black_box(heap.get_property(receiver, "значение").unwrap());
+        }
+    });
+
+    report("чтение_поля", value, boxed);
+}
+
+fn main() {
+    println!(
+        "size_of::<Value>() = {}, size_of::<NanBox>() = {}",
+        std::mem::size_of::<Value>(),
+        std::mem::size_of::<NanBox>()
+    );
+
+    arithmetic();
+    int_arithmetic();
+    stack_clone();
+    property_get();
+}
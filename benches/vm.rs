@@ -0,0 +1,177 @@
+//! Бенчмарки виртуальной машины Ponos.
+//!
+//! Запуск: `cargo bench --bench vm [фильтр]`
+//!
+//! Каждый сценарий выполняется несколько раз на свежем экземпляре `Ponos`,
+//! в отчет попадают минимальное и среднее время.
+
+use ponos_rs::ponos::Ponos;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 10;
+
+/// Арифметический цикл без вызовов: нагрузка целиком на цикл диспетчеризации
+const ARITHMETIC: &str = r#"
+    пер i = 0;
+    пер сумма = 0;
+    пока i < 200000
+        сумма = сумма + i * 2 - i / 2 % 7;
+        i = i + 1;
+    конец
+"#;
+
+/// Арифметика на локальных переменных внутри функции
+const LOCAL_ARITHMETIC: &str = r#"
+    функ посчитать(n: число): число
+        пер i = 0;
+        пер сумма = 0;
+        пока i < n
+            сумма = сумма + i * i;
+            i = i + 1;
+        конец
+        возврат сумма;
+    конец
+
+    пер результат = посчитать(200000);
+"#;
+
+/// Много вызовов маленькой функции с большим телом вызывающего кода
+const CALLS: &str = r#"
+    функ инкремент(x: число): число
+        возврат x + 1;
+    конец
+
+    пер i = 0;
+    пока i < 100000
+        i = инкремент(i);
+    конец
+"#;
+
+/// Рекурсивные вызовы: стоимость создания кадра доминирует
+const FIB: &str = r#"
+    функ фиб(n: число): число
+        если n < 2
+            возврат n;
+        конец
+        возврат фиб(n - 1) + фиб(n - 2);
+    конец
+
+    пер результат = фиб(22);
+"#;
+
+/// Вызовы метода экземпляра
+const METHODS: &str = r#"
+    класс Счетчик
+        значение: число
+
+        конструктор()
+            это.значение = 0;
+        конец
+
+        функ шаг()
+            это.значение = это.значение + 1;
+        конец
+    конец
+
+    пер счетчик = Счетчик();
+    пер i = 0;
+    пока i < 50000
+        счетчик.шаг();
+        i = i + 1;
+    конец
+"#;
+
+/// Сборка строки из чисел конкатенацией: форматирование чисел и копирование строк
+const NUMBER_CONCAT: &str = r#"
+    пер с = "";
+    пер i = 0;
+    пока i < 20000
+        с = с + i;
+        i = i + 1;
+    конец
+"#;
+
+/// Длинная строка в локальной переменной: `с = с + часть` дописывает часть
+/// в буфер переменной, время растёт линейно, а не квадратично
+const LOCAL_CONCAT: &str = r#"
+    функ собрать(n: число): строка
+        пер с = "";
+        пер i = 0;
+        пока i < n
+            с = с + "строка из нескольких слов;";
+            i = i + 1;
+        конец
+        возврат с;
+    конец
+
+    пер с = собрать(50000);
+"#;
+
+/// Та же строка через построитель: части дописываются в один буфер
+const STRING_BUILDER: &str = r#"
+    пер п = построитель_строк();
+    пер i = 0;
+    пока i < 20000
+        п.добавить(i);
+        i = i + 1;
+    конец
+    пер с = п.в_строку();
+"#;
+
+/// Строки в массивах, переменных и ключах словаря: значения копируются при
+/// каждом чтении и записи
+const STRING_VALUES: &str = r#"
+    пер слова = ["альфа длинное слово", "бета длинное слово", "гамма длинное слово"];
+    пер счёт = {};
+    пер слово = "";
+    пер i = 0;
+    пока i < 50000
+        слово = слова[i % 3];
+        счёт[слово] = "значение " + слово;
+        i = i + 1;
+    конец
+"#;
+
+fn bench(filter: Option<&str>, name: &str, source: &str) {
+    if let Some(filter) = filter
+        && !name.contains(filter)
+    {
+        return;
+    }
+
+    let mut total = Duration::ZERO;
+    let mut best = Duration::MAX;
+
+    for _ in 0..ITERATIONS {
+        let mut ponos = Ponos::new();
+        let start = Instant::now();
+        ponos.run_source(source.to_string()).unwrap();
+        let elapsed = start.elapsed();
+
+        total += elapsed;
+        best = best.min(elapsed);
+    }
+
+    println!(
+        "{:<24} мин: {:>10.3?}   среднее: {:>10.3?}",
+        name,
+        best,
+        total / ITERATIONS
+    );
+}
+
+fn main() {
+    // cargo bench передает служебные флаги вроде --bench, пропускаем их
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let filter = filter.as_deref();
+
+    bench(filter, "арифметика", ARITHMETIC);
+    bench(filter, "арифметика_локальная", LOCAL_ARITHMETIC);
+    bench(filter, "вызовы_функций", CALLS);
+    bench(filter, "рекурсия_фибоначчи", FIB);
+    bench(filter, "вызовы_методов", METHODS);
+    bench(filter, "конкатенация_чисел", NUMBER_CONCAT);
+    bench(filter, "конкатенация_локальная", LOCAL_CONCAT);
+    bench(filter, "построитель_строк", STRING_BUILDER);
+    bench(filter, "строковые_значения", STRING_VALUES);
+}
@@ -1,2 +1,4 @@
 // Публичный API для тестов и внешнего использования
 pub mod ponos;
+
+pub use ponos::{Generator, Ponos, PonosError, PonosParseError, PonosParser, VM, Value};
@@ -1,34 +1,399 @@
-use ponos_rs::ponos;
-use ponos_rs::ponos::native::system;
+use ponos_rs::Ponos;
+use ponos_rs::ponos::ast_json;
+use ponos_rs::ponos::formatter;
+use ponos_rs::ponos::manifest::Manifest;
+use ponos_rs::ponos::native::{SAFE_MODULES, system};
+use ponos_rs::ponos::opcode;
+use ponos_rs::ponos::parser::PonosParser;
+use ponos_rs::ponos::repl;
+use ponos_rs::ponos::spec;
+use ponos_rs::ponos::test_runner;
+use ponos_rs::ponos::tutorial::{self, Lesson};
+use ponos_rs::ponos::vm::Limits;
 use rustyline::error::ReadlineError;
 use rustyline::{DefaultEditor, Result};
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Once};
+
+/// Параметры интерпретатора, заданные флагами командной строки
+#[derive(Default, Debug, PartialEq)]
+struct Options {
+    stdlib: Option<PathBuf>,
+    /// Каталоги поиска модулей из флагов --module-path, в порядке указания
+    module_paths: Vec<PathBuf>,
+    /// Вывести после выполнения отчёт профилировщика (--profile)
+    profile: bool,
+    /// Печатать каждую выполняемую инструкцию (--trace)
+    trace: bool,
+    /// Сверять стековый эффект каждой инструкции (--strict-stack)
+    strict_stack: bool,
+    /// Разрешить только безопасные нативные модули (--sandbox)
+    sandbox: bool,
+    /// Пределы ресурсов VM (--max-instructions, --max-depth, --max-stack)
+    limits: Limits,
+    /// Считать предупреждения ошибками (--deny-warnings)
+    deny_warnings: bool,
+}
+
+/// Переменная окружения со списком каталогов поиска модулей (через `:`)
+const MODULE_PATH_VAR: &str = "PONOS_PATH";
+
+/// Подкоманда интерпретатора
+#[derive(Debug, PartialEq)]
+enum Command {
+    Repl,
+    /// Запустить скрипт; `args` — аргументы после имени скрипта
+    Run {
+        script: PathBuf,
+        args: Vec<String>,
+    },
+    Compile(PathBuf),
+    /// Вывести AST файла в JSON
+    Parse(PathBuf),
+    Check(PathBuf),
+    /// Запустить тесты; без пути — во всём проекте
+    Test(Option<PathBuf>),
+    /// Отформатировать файл или каталог; `check` — только проверить
+    Fmt {
+        path: PathBuf,
+        check: bool,
+    },
+    Tutorial(usize),
+    Spec(Option<PathBuf>),
+    Help,
+}
+
+const USAGE: &str = "\
+Использование: ponos [--stdlib каталог] [--module-path каталог]... [--profile] [--trace]
+              [--strict-stack] [--sandbox] [--max-instructions N] [--max-depth N] [--max-stack N]
+              [--deny-warnings] [команда] [аргументы]
+
+Команды:
+  run файл.pns [арг...]   запустить скрипт (аргументы — в система.аргументы())
+  run                     запустить точку входа проекта (entry в понос.toml)
+  repl                    интерактивный режим (по умолчанию без аргументов);
+                          служебные команды (:vars, :type, :load...) — :help
+  compile файл.pns        вывести байткод программы, не выполняя её
+  parse --ast-json файл   вывести AST программы в JSON (для редакторов и линтеров)
+  check путь              проверить файл или каталог .pns, не выполняя код
+  test [путь]             запустить тесты (@Тест или функ тест_...) в файле,
+                          каталоге или во всём проекте
+  fmt [--check] путь      отформатировать файл или каталог .pns на месте;
+                          с --check только сообщить, какие файлы изменятся
+  учёба [номер]           интерактивный учебник
+  spec [файл.md]          проверить примеры спецификации и вывести справочник
+  help                    эта справка
+
+Модули, не найденные рядом с импортирующим файлом, ищутся в каталогах
+sources из понос.toml, затем в каталогах --module-path и PONOS_PATH.
+С --profile после выполнения в stderr выводятся самые горячие функции:
+число вызовов и инструкций, собственное и полное время. С --trace каждая
+выполняемая инструкция печатается в stderr вместе с кадром и верхушкой стека.
+С --strict-stack VM сверяет стековый эффект каждой инструкции и аварийно
+завершается, если инструкция залезла в стек вызывающей функции или оставила
+на стеке не столько значений, сколько положено (для отладки генератора).
+С --sandbox программе недоступны модули файловой системы, сети, процессов и
+системы (стд/фс, стд/сеть, стд/система...): их импорт — ошибка загрузки. Из
+стд/json доступны только сериализовать и десериализовать, без файлов.
+--max-instructions прерывает программу (в REPL — строку), выполнившую больше
+N инструкций. --max-depth и --max-stack ограничивают глубину вызовов и размер
+стека значений: превышение бросает ОшибкаЛимита, которую можно перехватить.
+Ctrl-C прерывает выполняемый код исключением ПрерываниеПользователя.
+run и check предупреждают о неиспользуемых переменных и импортах,
+недостижимом коде и закрытых функциях модуля, которые никто не вызывает;
+с --deny-warnings предупреждение — ошибка: скрипт не запускается, check
+завершается с кодом 1.
+
+Вместо `ponos run файл.pns` можно писать просто `ponos файл.pns`.";
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args();
+    let program = args.next().unwrap_or_else(|| "ponos".to_string());
+    let (options, command) = match parse_command(args.collect()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    // Устанавливаем аргументы командной строки для доступа из Ponos программ
-    system::set_cli_args(args.clone());
+    match command {
+        Command::Repl => _ = run_repl(&options),
+        Command::Run { script, args } => {
+            // Программа видит себя так же, как в других языках:
+            // [интерпретатор, скрипт, аргументы скрипта...]
+            let mut cli_args = vec![program, script.display().to_string()];
+            cli_args.extend(args);
+            system::set_cli_args(cli_args);
+            run_file(script, &options);
+        }
+        Command::Compile(file) => run_compile(file, &options),
+        Command::Parse(file) => run_parse(file),
+        Command::Check(path) => run_check(path, &options),
+        Command::Test(path) => run_tests(path, &options),
+        Command::Fmt { path, check } => run_fmt(path, check),
+        Command::Tutorial(start) => _ = run_tutorial(start),
+        Command::Spec(output) => run_spec(output),
+        Command::Help => println!("{}", USAGE),
+    }
+}
+
+/// Разобрать аргументы командной строки (без имени самого интерпретатора).
+/// Флаги интерпретатора стоят перед именем скрипта; всё, что идёт после
+/// него, передаётся программе без изменений
+fn parse_command(args: Vec<String>) -> std::result::Result<(Options, Command), String> {
+    let mut options = Options::default();
+    let mut iter = args.into_iter();
+    let mut subcommand = None;
+    let mut fmt_check = false;
+
+    let positional = loop {
+        let Some(arg) = iter.next() else {
+            break None;
+        };
+        match arg.as_str() {
+            "--stdlib" => {
+                let path = iter
+                    .next()
+                    .ok_or_else(|| "Флаг --stdlib требует путь к каталогу".to_string())?;
+                options.stdlib = Some(PathBuf::from(path));
+            }
+            "--module-path" => {
+                let path = iter
+                    .next()
+                    .ok_or_else(|| "Флаг --module-path требует путь к каталогу".to_string())?;
+                options.module_paths.push(PathBuf::from(path));
+            }
+            "--profile" => options.profile = true,
+            "--trace" => options.trace = true,
+            "--strict-stack" => options.strict_stack = true,
+            "--sandbox" => options.sandbox = true,
+            "--deny-warnings" => options.deny_warnings = true,
+            "--max-instructions" => {
+                options.limits.max_instructions = Some(limit_value(&arg, iter.next())?);
+            }
+            "--max-depth" => options.limits.max_call_depth = Some(limit_value(&arg, iter.next())?),
+            "--max-stack" => options.limits.max_stack = Some(limit_value(&arg, iter.next())?),
+            "-h" | "--help" => return Ok((options, Command::Help)),
+            "--check" if subcommand.as_deref() == Some("fmt") => fmt_check = true,
+            // Пока JSON — единственный формат вывода AST
+            "--ast-json" if subcommand.as_deref() == Some("parse") => {}
+            flag if flag.starts_with("--") => {
+                return Err(format!("Неизвестный флаг {}", flag));
+            }
+            "run" | "repl" | "compile" | "parse" | "check" | "test" | "fmt" | "учёба" | "spec"
+            | "help"
+                if subcommand.is_none() =>
+            {
+                subcommand = Some(arg);
+            }
+            _ => break Some(arg),
+        }
+    };
+
+    let file = |name: &str, file: Option<String>| {
+        file.map(PathBuf::from)
+            .ok_or_else(|| format!("Команда {} требует имя файла", name))
+    };
+    let no_extra = |name: &str, iter: &mut std::vec::IntoIter<String>| match iter.next() {
+        Some(extra) => Err(format!("Лишний аргумент команды {}: {}", name, extra)),
+        None => Ok(()),
+    };
+
+    let command = match subcommand.as_deref() {
+        None => match positional {
+            None => Command::Repl,
+            Some(script) => Command::Run {
+                script: PathBuf::from(script),
+                args: iter.collect(),
+            },
+        },
+        Some("run") => Command::Run {
+            script: match positional {
+                Some(script) => PathBuf::from(script),
+                None => project_entry().ok_or_else(|| {
+                    "Команда run требует имя файла или понос.toml с ключом entry".to_string()
+                })?,
+            },
+            args: iter.collect(),
+        },
+        Some("repl") => {
+            if let Some(extra) = positional {
+                return Err(format!("Лишний аргумент команды repl: {}", extra));
+            }
+            Command::Repl
+        }
+        Some(name @ ("compile" | "parse" | "check")) => {
+            let path = file(name, positional)?;
+            no_extra(name, &mut iter)?;
+            match name {
+                "compile" => Command::Compile(path),
+                "parse" => Command::Parse(path),
+                _ => Command::Check(path),
+            }
+        }
+        Some("fmt") => {
+            let path = file("fmt", positional)?;
+            // --check можно указать и после пути
+            let mut rest = iter.peekable();
+            if rest.next_if(|arg| arg == "--check").is_some() {
+                fmt_check = true;
+            }
+            if let Some(extra) = rest.next() {
+                return Err(format!("Лишний аргумент команды fmt: {}", extra));
+            }
+            Command::Fmt {
+                path,
+                check: fmt_check,
+            }
+        }
+        Some("учёба") => {
+            let start = match positional {
+                Some(n) => n
+                    .parse::<usize>()
+                    .map_err(|_| format!("Номер урока должен быть числом: {}", n))?,
+                None => 1,
+            };
+            Command::Tutorial(start)
+        }
+        Some("test") => {
+            no_extra("test", &mut iter)?;
+            Command::Test(positional.map(PathBuf::from))
+        }
+        Some("spec") => {
+            no_extra("spec", &mut iter)?;
+            Command::Spec(positional.map(PathBuf::from))
+        }
+        _ => Command::Help,
+    };
+
+    Ok((options, command))
+}
+
+/// Значение флага предела ресурсов: положительное целое
+fn limit_value<T: std::str::FromStr>(
+    flag: &str,
+    value: Option<String>,
+) -> std::result::Result<T, String> {
+    value
+        .filter(|value| value.parse::<u64>().is_ok_and(|n| n > 0))
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("Флаг {} требует положительное целое число", flag))
+}
+
+/// Найти и прочитать манифест проекта; ошибка в нём — предупреждение
+fn find_manifest(dir: &Path) -> Option<Manifest> {
+    Manifest::find(dir).and_then(|path| match Manifest::load(&path) {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            eprintln!("Предупреждение: {}", e);
+            None
+        }
+    })
+}
 
-    if args.len() == 1 {
-        _ = run_repl();
-    } else if args.len() >= 2 {
-        run_file(args[1].clone());
+/// Создать интерпретатор: импорты разрешаются по манифесту проекта и
+/// каталогам поиска, флаг --stdlib имеет приоритет над ключом манифеста
+fn make_ponos(options: &Options, script_dir: &Path) -> Ponos {
+    let mut ponos = Ponos::new();
+
+    if let Some(manifest) = find_manifest(script_dir) {
+        ponos.use_manifest(&manifest);
+    }
+    let env_paths = env::var_os(MODULE_PATH_VAR);
+    let env_paths = env_paths.iter().flat_map(env::split_paths);
+    for dir in options.module_paths.iter().cloned().chain(env_paths) {
+        // Пустой элемент (например, в "a::b") ничего не добавляет
+        if !dir.as_os_str().is_empty() {
+            ponos.add_module_path(dir);
+        }
+    }
+    if let Some(stdlib) = options.stdlib.clone() {
+        ponos.set_stdlib_path(stdlib);
+    }
+    if options.sandbox {
+        ponos.sandbox(SAFE_MODULES);
+    }
+    ponos.set_limits(options.limits);
+    ponos.set_strict_stack(options.strict_stack);
+    ponos.set_interrupt_flag(interrupt_flag());
+
+    ponos
+}
+
+/// Флаг прерывания выполнения, который поднимает Ctrl-C
+static INTERRUPT: LazyLock<Arc<AtomicBool>> = LazyLock::new(Arc::default);
+
+/// Флаг прерывания для VM. При первом вызове ставится обработчик Ctrl-C:
+/// выполняемый код получает ПрерываниеПользователя, а если флаг ещё поднят
+/// (программа ждёт в нативной функции и прерывание не заметила), повторный
+/// Ctrl-C завершает процесс
+fn interrupt_flag() -> Arc<AtomicBool> {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        LazyLock::force(&INTERRUPT);
+        install_interrupt_handler();
+    });
+    INTERRUPT.clone()
+}
+
+#[cfg(unix)]
+fn install_interrupt_handler() {
+    extern "C" fn on_interrupt(_signal: libc::c_int) {
+        if INTERRUPT.swap(true, Ordering::Relaxed) {
+            // Стандартный код завершения по SIGINT
+            unsafe { libc::_exit(130) };
+        }
     }
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn install_interrupt_handler() {}
+
+/// Точка входа проекта (ключ `entry` манифеста в текущем каталоге или выше)
+fn project_entry() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+    find_manifest(&current_dir)?.entry
 }
 
-fn run_repl() -> Result<()> {
-    let mut ponos = ponos::Ponos::new();
+fn run_repl(options: &Options) -> Result<()> {
+    let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let mut ponos = make_ponos(options, &current_dir);
 
     let mut rl = DefaultEditor::new()?;
 
     loop {
+        // система.завершить в REPL завершает сессию с указанным кодом
+        if let Some(code) = ponos.exit_code() {
+            std::process::exit(code);
+        }
         let readline = rl.readline(">> ");
         match readline {
-            Ok(line) => {
-                ponos.run_source(line);
-            }
+            Ok(line) => match repl::Command::parse(&line) {
+                Some(Ok(command)) => {
+                    let fresh = || make_ponos(options, &current_dir);
+                    match quietly(|| repl::execute(&mut ponos, command, fresh)) {
+                        Ok(output) => println!("{}", output.trim_end()),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+                Some(Err(e)) => eprintln!("{}", e),
+                // Значение выражения печатается, операторы ничего не выводят
+                None => match quietly(|| repl::eval_line(&mut ponos, &line)) {
+                    Ok(Some(value)) => println!("{}", value),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("{}", e),
+                },
+            },
             Err(ReadlineError::Interrupted) => {
                 println!("CTRL-C");
                 break;
@@ -47,9 +412,442 @@ fn run_repl() -> Result<()> {
     Ok(())
 }
 
-fn run_file(file_name: String) {
-    let file_content = fs::read_to_string(file_name).expect("Cannot open file");
+fn read_source(file_path: &Path) -> String {
+    match fs::read_to_string(file_path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Не удалось открыть {}: {}", file_path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Каталог скрипта: от него ищутся манифест и относительные импорты
+fn script_dir(file_path: &Path) -> PathBuf {
+    file_path
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn run_file(file_path: PathBuf, options: &Options) {
+    let file_content = read_source(&file_path);
+    let mut ponos = make_ponos(options, &script_dir(&file_path));
+    let warnings = ponos.lint(&file_content, Some(file_path.clone()));
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    if options.deny_warnings && !warnings.is_empty() {
+        eprintln!(
+            "Предупреждений: {}, запуск отменён (--deny-warnings)",
+            warnings.len()
+        );
+        std::process::exit(1);
+    }
+    if options.profile {
+        ponos.enable_profiler();
+    }
+    ponos.set_trace(options.trace);
+    let result = quietly(|| ponos.run_source_with_file(file_content, Some(file_path)));
+    // Программа, не дошедшая до выполнения, профиля не оставляет
+    if let Some(profile) = ponos.take_profile().filter(|p| !p.functions.is_empty()) {
+        eprint!("{}", profile.report());
+    }
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if let Some(code) = ponos.exit_code() {
+        std::process::exit(code);
+    }
+}
+
+/// Вывести байткод программы и таблицу констант
+fn run_compile(file_path: PathBuf, options: &Options) {
+    let source = read_source(&file_path);
+    let mut ponos = make_ponos(options, &script_dir(&file_path));
+    let ctx = match ponos.compile(&source, Some(file_path)) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    print!(
+        "{}",
+        opcode::listing(&ctx.opcodes, &ctx.constants, &ctx.lines)
+    );
+}
+
+/// Разобрать файл и вывести AST в JSON. Синтаксическая ошибка — в stderr,
+/// код выхода 1
+fn run_parse(file_path: PathBuf) {
+    let source = read_source(&file_path);
+    let mut program = match PonosParser::new().parse(source.clone()) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}", e.format(&source, &file_path.display().to_string()));
+            std::process::exit(1);
+        }
+    };
+    program.file_path = Some(file_path);
+    println!("{:#}", ast_json::program_to_json(&program));
+}
+
+/// Проверить файл или все файлы .pns в дереве каталогов, не выполняя их,
+/// и вывести найденные ошибки. Код выхода 1, если ошибки есть
+fn run_check(path: PathBuf, options: &Options) {
+    let files = source_files(path);
+
+    let (mut failed, mut warned) = (0, 0);
+    for file in &files {
+        let source = read_source(file);
+        // Каждый файл проверяется отдельным интерпретатором, чтобы импорты
+        // одного файла не влияли на разрешение имён в другом
+        let mut ponos = make_ponos(options, &script_dir(file));
+        let diagnostics = ponos.check(&source, Some(file.clone()));
+        let warnings = ponos.lint(&source, Some(file.clone()));
+        if !diagnostics.is_empty() || (options.deny_warnings && !warnings.is_empty()) {
+            failed += 1;
+        }
+        if !warnings.is_empty() {
+            warned += 1;
+        }
+        for diagnostic in diagnostics.iter().chain(&warnings) {
+            eprintln!("{}\n", diagnostic);
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("Файлов с ошибками: {} из {}", failed, files.len());
+        std::process::exit(1);
+    }
+    if warned > 0 {
+        println!(
+            "Проверено файлов: {}, ошибок не найдено, с предупреждениями: {}",
+            files.len(),
+            warned
+        );
+    } else {
+        println!("Проверено файлов: {}, ошибок не найдено", files.len());
+    }
+}
+
+/// Отформатировать файлы на месте. С `check` файлы не меняются: выводятся
+/// неотформатированные, и код выхода 1, если такие есть
+fn run_fmt(path: PathBuf, check: bool) {
+    let files = source_files(path);
+
+    let (mut changed, mut failed) = (0, 0);
+    for file in &files {
+        let source = read_source(file);
+        let formatted = match formatter::format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}: {}\n", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+        if check {
+            println!("Требует форматирования: {}", file.display());
+        } else if let Err(e) = fs::write(file, formatted) {
+            eprintln!("Не удалось записать {}: {}", file.display(), e);
+            failed += 1;
+        } else {
+            println!("Отформатирован: {}", file.display());
+        }
+    }
+
+    if failed > 0 || (check && changed > 0) {
+        std::process::exit(1);
+    }
+    if changed == 0 {
+        println!("Файлов: {}, все уже отформатированы", files.len());
+    }
+}
+
+/// Запустить тесты из файла, каталога или, без пути, из корня проекта
+/// (каталога понос.toml, иначе текущего). Код выхода 1, если есть провалы
+fn run_tests(path: Option<PathBuf>, options: &Options) {
+    let path = path.unwrap_or_else(|| {
+        let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        find_manifest(&current_dir).map_or(current_dir, |manifest| manifest.root)
+    });
+
+    let (mut passed, mut failed) = (0, 0);
+    for file in source_files(path) {
+        let source = read_source(&file);
+        let dir = script_dir(&file);
+        let results =
+            quietly(|| test_runner::run_file(&source, &file, || make_ponos(options, &dir)));
+        let results = match results {
+            Ok(results) if results.is_empty() => continue,
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("{}\n", e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        println!("{}", file.display());
+        for result in results {
+            match result.outcome {
+                Ok(()) => {
+                    passed += 1;
+                    println!("  ок      {}", result.name);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("  ПРОВАЛ  {}", result.name);
+                    for line in e.lines().chain(result.output.lines()) {
+                        println!("          {}", line);
+                    }
+                }
+            }
+        }
+    }
+
+    if passed + failed == 0 {
+        println!("Тесты не найдены");
+        return;
+    }
+    println!(
+        "Тестов: {}, пройдено: {}, провалено: {}",
+        passed + failed,
+        passed,
+        failed
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Файл или все файлы .pns в дереве каталогов, по алфавиту
+fn source_files(path: PathBuf) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path];
+    }
+    let mut files = Vec::new();
+    if let Err(e) = collect_sources(&path, &mut files) {
+        eprintln!("Не удалось прочитать каталог {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    files.sort();
+    files
+}
+
+/// Собрать файлы .pns из дерева каталогов, пропуская скрытые каталоги и target
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.') || name == "target");
+        if path.is_dir() {
+            if !hidden {
+                collect_sources(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "pns") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Интерактивный учебник: урок за уроком принимает решение и проверяет его
+fn run_tutorial(start: usize) -> Result<()> {
+    let lessons = tutorial::lessons();
+    let mut rl = DefaultEditor::new()?;
+
+    println!("Учебник Ponos: {} уроков.", lessons.len());
+    println!("Вводите решение построчно, затем команды:");
+    println!("  :проверить  :решение  :сначала  :пропустить  :выход");
+
+    let mut index = start.clamp(1, lessons.len()) - 1;
+    'lessons: while let Some(lesson) = lessons.get(index) {
+        println!("\n=== Урок {}: {} ===", lesson.number, lesson.title);
+        println!("{}\n", lesson.description);
+
+        let mut solution = String::new();
+        loop {
+            let line = match rl.readline("учёба> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break 'lessons,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    break 'lessons;
+                }
+            };
+
+            match line.trim() {
+                ":выход" => break 'lessons,
+                ":пропустить" => break,
+                ":сначала" => solution.clear(),
+                ":решение" => println!("{}", lesson.solution),
+                ":проверить" => match check_quietly(lesson, &solution) {
+                    Ok(()) => {
+                        println!("Верно!");
+                        break;
+                    }
+                    Err(e) => println!("Пока неверно: {}", e),
+                },
+                _ => {
+                    solution.push_str(&line);
+                    solution.push('\n');
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    if index >= lessons.len() {
+        println!("Учебник пройден!");
+    }
+    Ok(())
+}
+
+/// Проверить примеры спецификации и вывести справочник семантики в Markdown:
+/// в файл, если он указан, иначе в stdout. При расхождении примера с
+/// реализацией документ не создаётся
+fn run_spec(output: Option<PathBuf>) {
+    let sections = spec::sections();
+
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let failures = spec::check_all(&sections);
+    std::panic::set_hook(hook);
+
+    if !failures.is_empty() {
+        for failure in &failures {
+            eprintln!("{}\n", failure);
+        }
+        eprintln!("Примеров не прошло: {}", failures.len());
+        std::process::exit(1);
+    }
+
+    let document = spec::render_markdown(&sections);
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, document) {
+                eprintln!("Не удалось записать {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", document),
+    }
+}
+
+/// Проверить решение, не печатая стандартное сообщение о панике VM
+/// (текст ошибки возвращается в Err)
+fn check_quietly(lesson: &Lesson, solution: &str) -> std::result::Result<(), String> {
+    quietly(|| lesson.check(solution))
+}
+
+/// Выполнить действие без стандартного сообщения о панике: ошибки VM
+/// перехватываются интерпретатором и возвращаются в Err
+fn quietly<T>(action: impl FnOnce() -> T) -> T {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = action();
+    std::panic::set_hook(hook);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<(Options, Command), String> {
+        parse_command(args.iter().map(|s| s.to_string()).collect())
+    }
 
-    let mut ponos = ponos::Ponos::new();
-    ponos.run_source(file_content);
+    #[test]
+    fn script_args_follow_script_name() {
+        let run = |script: &str, args: &[&str]| Command::Run {
+            script: PathBuf::from(script),
+            args: args.iter().map(|s| s.to_string()).collect(),
+        };
+        assert_eq!(parse(&[]).unwrap().1, Command::Repl);
+        assert_eq!(
+            parse(&["run", "а.pns", "--stdlib", "run"]).unwrap().1,
+            run("а.pns", &["--stdlib", "run"])
+        );
+        assert_eq!(
+            parse(&["а.pns", "1", "2"]).unwrap().1,
+            run("а.pns", &["1", "2"])
+        );
+
+        let (options, command) = parse(&["--stdlib", "стд", "run", "а.pns"]).unwrap();
+        assert_eq!(options.stdlib, Some(PathBuf::from("стд")));
+        assert_eq!(command, run("а.pns", &[]));
+
+        let (options, _) =
+            parse(&["--module-path", "один", "--module-path", "два", "а.pns"]).unwrap();
+        assert_eq!(
+            options.module_paths,
+            vec![PathBuf::from("один"), PathBuf::from("два")]
+        );
+        assert!(parse(&["--module-path"]).is_err());
+
+        let (options, command) = parse(&["run", "--profile", "а.pns"]).unwrap();
+        assert!(options.profile);
+        assert!(!options.trace);
+        assert_eq!(command, run("а.pns", &[]));
+
+        let (options, _) = parse(&["--sandbox", "а.pns"]).unwrap();
+        assert!(options.sandbox);
+        assert!(!options.strict_stack);
+
+        let (options, command) = parse(&["run", "--strict-stack", "а.pns"]).unwrap();
+        assert!(options.strict_stack);
+        assert_eq!(command, run("а.pns", &[]));
+
+        let (options, _) =
+            parse(&["--max-instructions", "1000", "--max-depth", "50", "а.pns"]).unwrap();
+        assert_eq!(options.limits.max_instructions, Some(1000));
+        assert_eq!(options.limits.max_call_depth, Some(50));
+        assert_eq!(options.limits.max_stack, None);
+        assert!(parse(&["--max-stack", "0", "а.pns"]).is_err());
+        assert!(parse(&["--max-depth"]).is_err());
+
+        let (options, _) = parse(&["check", "--deny-warnings", "каталог"]).unwrap();
+        assert!(options.deny_warnings);
+    }
+
+    #[test]
+    fn subcommands_validate_arguments() {
+        assert_eq!(
+            parse(&["compile", "а.pns"]).unwrap().1,
+            Command::Compile(PathBuf::from("а.pns"))
+        );
+        assert_eq!(parse(&["учёба", "3"]).unwrap().1, Command::Tutorial(3));
+        assert_eq!(parse(&["spec"]).unwrap().1, Command::Spec(None));
+        assert_eq!(parse(&["test"]).unwrap().1, Command::Test(None));
+        assert!(parse(&["test", "а.pns", "б.pns"]).is_err());
+        assert!(parse(&["run"]).is_err());
+        assert!(parse(&["check", "а.pns", "б.pns"]).is_err());
+        let fmt = |check| Command::Fmt {
+            path: PathBuf::from("а.pns"),
+            check,
+        };
+        assert_eq!(parse(&["fmt", "а.pns"]).unwrap().1, fmt(false));
+        assert_eq!(parse(&["fmt", "--check", "а.pns"]).unwrap().1, fmt(true));
+        assert_eq!(parse(&["fmt", "а.pns", "--check"]).unwrap().1, fmt(true));
+        assert!(parse(&["--check", "а.pns"]).is_err());
+        assert_eq!(
+            parse(&["parse", "--ast-json", "а.pns"]).unwrap().1,
+            Command::Parse(PathBuf::from("а.pns"))
+        );
+        assert!(parse(&["--неизвестный", "а.pns"]).is_err());
+    }
 }
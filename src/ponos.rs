@@ -1,22 +1,52 @@
-mod ast;
-mod generator;
+pub mod ast;
+pub mod ast_json;
+pub mod error;
+pub mod exceptions;
+pub mod formatter;
+pub mod generator;
+pub mod lints;
+pub mod manifest;
 mod module;
 mod name_resolver;
+#[cfg(feature = "nan-boxing")]
+pub mod nanbox;
 pub mod native;
-mod opcode;
-mod parser;
-mod span;
+pub mod opcode;
+pub mod parser;
+pub mod portable;
+pub mod profiler;
+pub mod reactor;
+pub mod repl;
+pub mod semantic;
+pub mod span;
+pub mod spec;
 pub mod stdlib;
 mod symbol_table;
-mod value;
-mod vm;
+pub mod test_runner;
+pub mod tutorial;
+pub mod typechecker;
+pub mod value;
+pub mod verifier;
+pub mod vm;
 
-pub use parser::{ParseErrorKind, PonosParseError};
+pub use error::PonosError;
+pub use generator::Generator;
+pub use parser::{ParseErrorKind, PonosParseError, PonosParser};
+pub use value::Value;
+pub use vm::VM;
 
-use module::{ModuleResolver, merge_module_ast};
+use generator::ModuleUnit;
+use module::{LoadedModule, ModuleResolver, into_module_block};
 use name_resolver::NameResolver;
+use semantic::SemanticChecker;
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use symbol_table::SymbolTable;
+use typechecker::TypeChecker;
 
 pub struct Ponos {
     parser: parser::PonosParser,
@@ -25,21 +55,96 @@ pub struct Ponos {
     module_resolver: ModuleResolver,
     name_resolver: NameResolver,
     symbol_table: SymbolTable,
+    /// Пространства имён загруженных модулей: имя -> файл модуля. Глобальные
+    /// переменные модуля называются `пространство::имя`, поэтому под одним
+    /// именем может быть загружен только один модуль
+    module_namespaces: HashMap<String, PathBuf>,
+    /// Модули, код верхнего уровня которых уже выполнен: файл -> пространство
+    /// имён его глобальных переменных. Модуль, импортированный под другим
+    /// именем, второй раз не выполняется
+    initialized_modules: HashMap<PathBuf, String>,
+    /// Встроенные глобальные имена: их не показывает `globals()`
+    builtin_globals: HashSet<String>,
+}
+
+impl Default for Ponos {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Ponos {
     pub fn new() -> Self {
         let parser = parser::PonosParser::new();
         let vm = vm::VM::new();
+        let builtin_globals = vm.defined_globals().into_iter().collect();
 
-        return Ponos {
-            parser: parser,
-            vm: vm,
+        Ponos {
+            parser,
+            vm,
             generator: generator::Generator::new(),
             module_resolver: ModuleResolver::new(),
             name_resolver: NameResolver::new(),
             symbol_table: SymbolTable::new(),
-        };
+            module_namespaces: HashMap::new(),
+            initialized_modules: HashMap::new(),
+            builtin_globals,
+        }
+    }
+
+    /// Использовать модули `стд/...` из каталога вместо встроенных.
+    /// Если модуля в каталоге нет, загружается встроенная версия
+    pub fn set_stdlib_path(&mut self, stdlib_path: PathBuf) {
+        self.module_resolver.set_stdlib_path(stdlib_path);
+    }
+
+    /// Искать модули, не найденные рядом с импортирующим файлом, ещё и в
+    /// этом каталоге. Каталоги просматриваются в порядке добавления
+    pub fn add_module_path(&mut self, dir: PathBuf) {
+        self.module_resolver.add_search_path(dir);
+    }
+
+    /// Запускать недоверенный код: импортировать можно только перечисленные
+    /// нативные модули (например, `native::SAFE_MODULES`), импорт остальных —
+    /// ошибка загрузки программы. Элемент `"модуль.функция"` разрешает одну
+    /// функцию модуля. Модули на Ponos импортируются как обычно
+    pub fn sandbox<I, S>(&mut self, allowed_modules: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.module_resolver
+            .native_registry_mut()
+            .allow_only(allowed_modules);
+    }
+
+    /// Ограничить ресурсы, которые может занять код: число инструкций за
+    /// запуск, глубину вызовов и размер стека. Пределы действуют и в потоках,
+    /// запущенных программой
+    pub fn set_limits(&mut self, limits: vm::Limits) {
+        self.vm.set_limits(limits);
+    }
+
+    /// Прерывать выполняемый код, когда поднят `flag`: код получает
+    /// исключение ПрерываниеПользователя. Флаг можно поднять из обработчика
+    /// сигнала или из другого потока
+    pub fn set_interrupt_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.vm.set_interrupt_flag(flag);
+    }
+
+    /// Разрешать импорты по манифесту проекта: каталог stdlib, каталоги
+    /// исходников и зависимости
+    pub fn use_manifest(&mut self, manifest: &manifest::Manifest) {
+        if let Some(stdlib) = &manifest.stdlib {
+            self.set_stdlib_path(stdlib.clone());
+        }
+        for dir in &manifest.sources {
+            self.add_module_path(dir.clone());
+        }
+        for (name, path) in &manifest.dependencies {
+            self.module_resolver
+                .add_dependency(name.clone(), path.clone());
+        }
     }
 
     /// Вернуть AST, не запуская его (для тестов и инструментов)
@@ -47,43 +152,80 @@ impl Ponos {
         self.parser.parse(source)
     }
 
-    pub fn run_source(&mut self, source: String) {
-        self.run_source_with_file(source, None);
+    /// Запустить программу. Возвращает значение последнего выражения, если
+    /// программа им заканчивается
+    pub fn run_source(&mut self, source: String) -> Result<Option<Value>, PonosError> {
+        self.run_source_with_file(source, None)
     }
 
     /// Запустить исходный код с указанием файла (для импортов)
-    pub fn run_source_with_file(&mut self, source: String, file_path: Option<PathBuf>) {
+    pub fn run_source_with_file(
+        &mut self,
+        source: String,
+        file_path: Option<PathBuf>,
+    ) -> Result<Option<Value>, PonosError> {
         if cfg!(debug_assertions) {
             println!("source:\n{}", source);
         }
+        let name = file_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .unwrap_or("<источник>")
+            .to_string();
 
         // 1. Парсинг
-        let mut ast = match self.parser.parse(source.clone()) {
-            Ok(ast) => ast,
-            Err(e) => {
-                eprintln!("Ошибка парсинга:");
-                eprintln!("{}", e.format(&source, file_path.as_ref().and_then(|p| p.to_str()).unwrap_or("<источник>")));
-                std::process::exit(1);
-            }
-        };
+        let mut ast = self
+            .parser
+            .parse(source.clone())
+            .map_err(|e| PonosError::Parse(e.format(&source, &name)))?;
 
         if cfg!(debug_assertions) {
             println!("ast:\n{:#?}", ast);
         }
 
+        ast.file_path = file_path.clone();
+
         // 2. Обработка импортов и загрузка модулей
-        self.process_imports(&mut ast, file_path.as_deref());
+        self.guarded(|ponos| ponos.process_imports(&ast, file_path.as_deref()))
+            .and_then(|result| result)
+            .map_err(PonosError::Import)?;
 
         // 3. Разрешение имён (преобразование FieldAccess в ModuleAccess)
         self.name_resolver
             .resolve(&mut ast, &self.symbol_table)
-            .expect("Ошибка разрешения имён");
+            .map_err(|e| PonosError::Resolve(format!("{}: {}", name, e)))?;
+
+        // 4. Семантическая проверка и проверка типов по аннотациям
+        let errors = self.static_errors(&ast, &source, &name);
+        if !errors.is_empty() {
+            return Err(PonosError::Check(errors));
+        }
 
         if cfg!(debug_assertions) {
             println!("ast после разрешения имён:\n{:#?}", ast);
         }
-        // 4. Генерация байткода
-        let mut ctx = self.generator.generate(ast::AstNode::Program(ast));
+
+        // Точка входа вызывается после кода верхнего уровня
+        match entry_point_call(&ast) {
+            Ok(Some(call)) => ast.statements.push(call),
+            Ok(None) => {}
+            Err(span) => {
+                let message = format!("Функция '{}' не должна требовать аргументов", ENTRY_POINT);
+                return Err(PonosError::Check(vec![
+                    PonosParseError::new(ParseErrorKind::Custom(message), span)
+                        .format(&source, &name),
+                ]));
+            }
+        }
+
+        // Значение программы — значение её последнего выражения
+        let has_value = matches!(ast.statements.last(), Some(ast::Statement::Expression(_)));
+
+        // 5. Генерация байткода
+        let ctx = self
+            .generator
+            .generate(ast::AstNode::Program(ast))
+            .map_err(|e| PonosError::Codegen(e.format(&source, &name)))?;
 
         if cfg!(debug_assertions) {
             println!("opcodes:\n{:#?}", ctx.opcodes);
@@ -91,101 +233,891 @@ impl Ponos {
         if cfg!(debug_assertions) {
             println!("constants:\n{:#?}", ctx.constants);
         }
-        // 5. Выполнение
-        self.vm.execute(ctx.opcodes, &mut ctx.constants);
+        // 6. Выполнение
+        self.guarded(|ponos| {
+            ponos.vm.stack.clear();
+            ponos
+                .vm
+                .execute_with_lines(ctx.opcodes, &ctx.constants, ctx.lines, ctx.source_name)?;
 
-        if cfg!(debug_assertions) {
-            println!("vm stack:\n{:#?}", self.vm.stack);
+            // После завершения программы stdout может быть уже закрыт
+            if cfg!(debug_assertions) && ponos.vm.exit_code().is_none() {
+                println!("vm stack:\n{:#?}", ponos.vm.stack);
+            }
+            Ok(if has_value {
+                ponos.vm.stack.pop()
+            } else {
+                None
+            })
+        })
+        .and_then(|result| result)
+        .map_err(PonosError::Runtime)
+    }
+
+    /// Скомпилировать программу в байткод, не выполняя её. Импортируемые
+    /// модули при этом загружаются: разрешение имён опирается на их экспорты
+    pub fn compile(
+        &mut self,
+        source: &str,
+        file_path: Option<PathBuf>,
+    ) -> Result<generator::GenContext, String> {
+        let name = file_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .unwrap_or("<источник>")
+            .to_string();
+        let mut ast = self
+            .parser
+            .parse(source.to_string())
+            .map_err(|e| e.format(source, &name))?;
+        ast.file_path = file_path.clone();
+
+        self.guarded(|ponos| ponos.process_imports(&ast, file_path.as_deref()))??;
+        self.name_resolver.resolve(&mut ast, &self.symbol_table)?;
+        let errors = self.static_errors(&ast, source, &name);
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
         }
+        self.generator
+            .generate(ast::AstNode::Program(ast))
+            .map_err(|e| e.format(source, &name))
     }
 
-    /// Обработать импорты в AST: загрузить модули и зарегистрировать их
-    fn process_imports(&mut self, ast: &mut ast::Program, from_file: Option<&std::path::Path>) {
-        use ast::Statement;
-        use span::Span;
-        use symbol_table::Symbol;
+    /// Проверить программу, не выполняя её: разбор, загрузка импортов,
+    /// разрешение имён, проверка типов и генерация кода. Возвращает все найденные
+    /// ошибки; пустой список — программа корректна
+    pub fn check(&mut self, source: &str, file_path: Option<PathBuf>) -> Vec<String> {
+        let name = file_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .unwrap_or("<источник>")
+            .to_string();
+        let mut ast = match self.parser.parse(source.to_string()) {
+            Ok(ast) => ast,
+            Err(e) => return vec![e.format(source, &name)],
+        };
+        ast.file_path = file_path.clone();
+
+        // Проверка не выполняет модули, поэтому и не занимает их пространства имён
+        let loaded_namespaces = self.module_namespaces.clone();
+        let mut diagnostics = Vec::new();
+        let mut seen = ImportedNamespaces::new();
+        for import in imports_of(&ast) {
+            let path = &import.path;
+            match self.import_module(&import, file_path.as_deref(), &mut seen) {
+                Ok(_) => {}
+                Err(ImportError::Collision(e)) => diagnostics.push(e.format(source, &name)),
+                Err(ImportError::Load(e)) => diagnostics.push(format!(
+                    "{}: не удалось загрузить модуль '{}': {}",
+                    name, path, e
+                )),
+                Err(ImportError::Resolve(e)) => {
+                    diagnostics.push(format!("{}: в модуле '{}': {}", name, path, e))
+                }
+            }
+        }
+        self.module_namespaces = loaded_namespaces;
+        match self.name_resolver.resolve(&mut ast, &self.symbol_table) {
+            Ok(()) => diagnostics.extend(self.static_errors(&ast, source, &name)),
+            Err(e) => diagnostics.push(format!("{}: {}", name, e)),
+        }
+        // Генератор отвергает конструкции, которые не удаётся скомпилировать
+        if diagnostics.is_empty()
+            && let Err(e) = self.generator.generate(ast::AstNode::Program(ast))
+        {
+            diagnostics.push(e.format(source, &name));
+        }
+        diagnostics
+    }
+
+    /// Предупреждения о коде, который, скорее всего, написан по ошибке:
+    /// неиспользуемые переменные и импорты, недостижимый код, забытые
+    /// закрытые функции модуля. Программу с синтаксической ошибкой не
+    /// проверяет: о ней сообщат `check` и запуск
+    pub fn lint(&mut self, source: &str, file_path: Option<PathBuf>) -> Vec<String> {
+        let name = file_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .unwrap_or("<источник>")
+            .to_string();
+        let Ok(ast) = self.parser.parse(source.to_string()) else {
+            return Vec::new();
+        };
+        lints::lint(&ast)
+            .into_iter()
+            .map(|warning| warning.format(source, &name))
+            .collect()
+    }
+
+    /// Выполнить фрагмент кода и вернуть значение последнего выражения.
+    /// Ошибки парсинга, разрешения имён и выполнения возвращаются как `Err`;
+    /// глобальные переменные сохраняются между вызовами
+    pub fn eval(&mut self, source: &str) -> Result<Value, String> {
+        self.eval_with_file(source, None)
+    }
+
+    /// То же, что `eval`, но импорты разрешаются относительно файла
+    /// `file_path`, а ошибки ссылаются на него
+    pub fn eval_with_file(
+        &mut self,
+        source: &str,
+        file_path: Option<PathBuf>,
+    ) -> Result<Value, String> {
+        let name = file_path
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .unwrap_or("<источник>")
+            .to_string();
+        let mut ast = self
+            .parser
+            .parse(source.to_string())
+            .map_err(|e| e.format(source, &name))?;
+        ast.file_path = file_path.clone();
+
+        self.guarded(|ponos| ponos.process_imports(&ast, file_path.as_deref()))??;
+        self.name_resolver.resolve(&mut ast, &self.symbol_table)?;
+        let errors = self.static_errors(&ast, source, &name);
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        let ctx = self
+            .generator
+            .generate(ast::AstNode::Program(ast))
+            .map_err(|e| e.format(source, &name))?;
+
+        self.guarded(|ponos| {
+            ponos.vm.stack.clear();
+            ponos
+                .vm
+                .execute_with_lines(ctx.opcodes, &ctx.constants, ctx.lines, ctx.source_name)?;
+            Ok(ponos.vm.stack.pop().unwrap_or(Value::Nil))
+        })
+        .and_then(|result| result)
+    }
+
+    /// Перечитать модуль, загруженный под пространством имён `namespace`, и
+    /// выполнить его заново. Глобальные переменные модуля заменяются новыми
+    /// значениями; модули, которые он импортирует, не перезагружаются
+    pub fn reload_module(&mut self, namespace: &str) -> Result<(), String> {
+        let path = self
+            .module_namespaces
+            .get(namespace)
+            .cloned()
+            .ok_or_else(|| format!("Модуль '{}' не загружен", namespace))?;
+        if is_native(&path) {
+            return Err(format!(
+                "Модуль '{}' встроен в интерпретатор, его нельзя перезагрузить",
+                namespace
+            ));
+        }
+
+        self.module_resolver.forget(&path);
+        self.module_namespaces.remove(namespace);
+        if self
+            .initialized_modules
+            .get(&path)
+            .is_some_and(|first| first == namespace)
+        {
+            self.initialized_modules.remove(&path);
+        }
+        self.symbol_table.remove(namespace);
+        self.vm.clear_namespace(namespace);
+
+        let import = ast::ImportStatement {
+            path: path.display().to_string(),
+            alias: Some(namespace.to_string()),
+            filter: ast::ImportFilter::All,
+            is_exported: false,
+            span: span::Span::default(),
+        };
+        let blocks = match self.import_module(&import, None, &mut ImportedNamespaces::new()) {
+            Ok(blocks) => blocks,
+            Err(ImportError::Load(e) | ImportError::Resolve(e)) => return Err(e),
+            Err(ImportError::Collision(e)) => return Err(e.message),
+        };
+        self.guarded(|ponos| ponos.run_module_blocks(blocks, &import.path))?
+    }
+
+    /// Код завершения, если программа вызвала система.завершить. После этого
+    /// код больше не выполняется: `eval` и `run_source` ничего не делают
+    pub fn exit_code(&self) -> Option<i32> {
+        self.vm.exit_code()
+    }
+
+    /// Глобальные переменные, определённые программой, без встроенных имён и
+    /// переменных модулей, по алфавиту
+    pub fn globals(&mut self) -> Vec<(String, Value)> {
+        let mut names: Vec<String> = self
+            .vm
+            .defined_globals()
+            .into_iter()
+            .filter(|name| !name.contains("::") && !self.builtin_globals.contains(name))
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| self.vm.get_global(&name).map(|value| (name, value)))
+            .collect()
+    }
+
+    /// Загруженные модули: пространство имён и файл, по алфавиту
+    pub fn modules(&self) -> Vec<(String, PathBuf)> {
+        let mut modules: Vec<_> = self
+            .module_namespaces
+            .iter()
+            .map(|(namespace, path)| (namespace.clone(), path.clone()))
+            .collect();
+        modules.sort();
+        modules
+    }
+
+    /// Статический тип выражения, не выполняя его. Типы глобальных
+    /// переменных берутся из их текущих значений
+    pub fn type_of(&mut self, source: &str) -> Result<typechecker::Type, String> {
+        let source = source.trim().to_string();
+        let mut ast = self
+            .parser
+            .parse(source.clone())
+            .map_err(|e| e.format(&source, "<источник>"))?;
+        self.name_resolver.resolve(&mut ast, &self.symbol_table)?;
+        let [ast::Statement::Expression(expr)] = ast.statements.as_slice() else {
+            return Err("Ожидается одно выражение".to_string());
+        };
+        let known: Vec<_> = self
+            .globals()
+            .iter()
+            .map(|(name, value)| (name.clone(), typechecker::Type::of_value(value)))
+            .collect();
+        Ok(TypeChecker::new(&self.symbol_table).type_of(expr, &known))
+    }
+
+    /// Вызвать глобальную функцию по имени (для функций модулей — `модуль::имя`)
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value, String> {
+        let callee = self
+            .vm
+            .get_global(name)
+            .ok_or_else(|| format!("Функция '{}' не найдена", name))?;
+        self.guarded(|ponos| ponos.vm.call_value(callee, args))?
+    }
+
+    /// Получить значение глобальной переменной
+    pub fn get_global(&mut self, name: &str) -> Option<Value> {
+        self.vm.get_global(name)
+    }
+
+    /// Определить глобальную переменную, доступную коду Ponos
+    pub fn set_global(&mut self, name: &str, value: impl Into<Value>) {
+        self.vm.set_global(name, value.into());
+    }
+
+    /// Собирать вывод `вывести()` в буфер вместо stdout
+    pub fn capture_output(&mut self) {
+        self.vm.capture_output();
+    }
+
+    /// Забрать собранный вывод; после этого программа снова печатает в stdout
+    pub fn take_output(&mut self) -> Option<String> {
+        self.vm.take_output()
+    }
+
+    /// Считать вызовы, инструкции и время по функциям Ponos
+    pub fn enable_profiler(&mut self) {
+        self.vm.enable_profiler();
+    }
+
+    /// Забрать статистику профилировщика; профилирование при этом выключается
+    pub fn take_profile(&mut self) -> Option<profiler::Profile> {
+        self.vm.take_profile()
+    }
+
+    /// Печатать в stderr каждую выполняемую инструкцию (для отладки VM)
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.vm.set_trace(enabled);
+    }
+
+    /// Печатать ли в stderr отчёт о необработанном исключении (по умолчанию
+    /// да). Без отчёта стек вызовов возвращается в тексте ошибки
+    pub fn set_report_errors(&mut self, enabled: bool) {
+        self.vm.set_report_errors(enabled);
+    }
+
+    /// Сверять стековый эффект каждой инструкции (для отладки генератора)
+    pub fn set_strict_stack(&mut self, enabled: bool) {
+        self.vm.set_strict_stack(enabled);
+    }
+
+    /// Зарегистрировать Rust-функцию или замыкание под глобальным именем.
+    /// Замыкание может хранить состояние хоста; изменяемое состояние
+    /// оборачивается в `Cell`/`RefCell`
+    pub fn register_function<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        self.vm.register_and_define(name, func);
+    }
+
+    /// Зарегистрировать Rust-функцию, которой нужен доступ к VM: через
+    /// `NativeContext` она может вызывать переданные ей функции Ponos
+    pub fn register_function_with_context<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&mut vm::NativeContext, &[Value]) -> Result<Value, String> + 'static,
+    {
+        self.vm.register_and_define_with_context(name, func);
+    }
 
-        // Собираем все импорты из AST
-        let mut imports = Vec::new();
-        for stmt in &ast.statements {
-            if let Statement::Import(import) = stmt {
-                imports.push((import.path.clone(), import.alias.clone()));
+    /// Выполнить действие, превратив панику VM в ошибку. После ошибки
+    /// состояние выполнения сбрасывается, чтобы интерпретатор можно было
+    /// использовать дальше
+    fn guarded<T>(&mut self, action: impl FnOnce(&mut Self) -> T) -> Result<T, String> {
+        match panic::catch_unwind(AssertUnwindSafe(|| action(self))) {
+            Ok(result) => Ok(result),
+            Err(payload) => {
+                self.vm.reset();
+                Err(panic_message(payload))
             }
         }
+    }
 
-        // Загружаем каждый модуль
-        for (path, alias) in imports {
-            match self.module_resolver.load_module(
-                &path,
-                alias.clone(),
+    /// Обработать импорты в AST: загрузить модули и зарегистрировать их.
+    /// Ошибки в коде модуля возвращаются как `Err`
+    fn process_imports(
+        &mut self,
+        ast: &ast::Program,
+        from_file: Option<&std::path::Path>,
+    ) -> Result<(), String> {
+        let mut seen = ImportedNamespaces::new();
+        for import in imports_of(ast) {
+            let path = &import.path;
+            match self.import_module(&import, from_file, &mut seen) {
+                Ok(blocks) => self.run_module_blocks(blocks, path)?,
+                Err(ImportError::Load(e)) => {
+                    return Err(format!("Ошибка загрузки модуля '{}': {}", path, e));
+                }
+                Err(ImportError::Resolve(e)) => {
+                    return Err(format!("Ошибка в модуле '{}':\n{}", path, e));
+                }
+                Err(ImportError::Collision(e)) => {
+                    let name = from_file.and_then(|p| p.to_str()).unwrap_or("<источник>");
+                    return Err(e.format(&ast.source, name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Скомпилировать и выполнить блоки модулей. Модуль компилируется и
+    /// выполняется отдельно от основной программы
+    fn run_module_blocks(
+        &mut self,
+        blocks: Vec<ast::ModuleBlock>,
+        path: &str,
+    ) -> Result<(), String> {
+        for block in blocks {
+            let name = block
+                .file_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| path.to_string());
+            let source = block.source.clone();
+            let unit = self
+                .generator
+                .generate_module(block)
+                .map_err(|e| e.format(&source, &name))?;
+            self.load_module_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    /// Загрузить модуль, зарегистрировать его пространство имён и разрешить
+    /// имена в его коде. Возвращает блоки, готовые к компиляции: сначала
+    /// реэкспортируемые модули, затем сам модуль
+    fn import_module(
+        &mut self,
+        import: &ast::ImportStatement,
+        from_file: Option<&std::path::Path>,
+        seen: &mut ImportedNamespaces,
+    ) -> Result<Vec<ast::ModuleBlock>, ImportError> {
+        use span::Span;
+        use symbol_table::Symbol;
+
+        let path = import.path.as_str();
+        let mut loaded_module = self
+            .module_resolver
+            .load_module(
+                path,
+                import.alias.clone(),
                 from_file,
                 &mut self.symbol_table,
-            ) {
-                Ok(loaded_module) => {
-                    if cfg!(debug_assertions) {
-                        println!(
-                            "Загружен модуль: {} (пространство имён: {})",
-                            path, loaded_module.namespace
-                        );
-                    }
-
-                    // Если это нативный модуль, регистрируем его функции в VM
-                    if loaded_module
-                        .file_path
-                        .to_str()
-                        .unwrap_or("")
-                        .starts_with("<native:")
-                    {
-                        let native_registry = self.module_resolver.native_registry();
-                        if let Err(e) = native_registry.register_module_in_vm(
-                            &path,
-                            &loaded_module.namespace,
-                            &mut self.vm,
-                        ) {
-                            eprintln!(
-                                "Предупреждение: не удалось зарегистрировать нативные функции для '{}': {}",
-                                path, e
-                            );
-                        }
-                    }
-
-                    // Регистрируем пространство имён как Symbol::Module в текущей области
-                    let module_symbol = Symbol::new_module(
-                        loaded_module.namespace.clone(),
-                        loaded_module.scope_id,
-                        Span::default(),
-                    );
-
-                    if let Err(e) = self.symbol_table.define(module_symbol) {
-                        eprintln!(
-                            "Предупреждение: не удалось зарегистрировать модуль '{}': {}",
-                            loaded_module.namespace, e
-                        );
-                    }
-
-                    // Добавляем AST модуля в основной AST
-                    merge_module_ast(ast, loaded_module);
+            )
+            .and_then(|module| {
+                ModuleResolver::apply_filter(module, &import.filter, &mut self.symbol_table)
+            })
+            .map_err(ImportError::Load)?;
+
+        if cfg!(debug_assertions) {
+            println!(
+                "Загружен модуль: {} (пространство имён: {})",
+                path, loaded_module.namespace
+            );
+        }
+
+        // Реэкспортируемые модули доступны только через этот модуль, поэтому
+        // их пространства имён в текущей области не регистрируются
+        let mut blocks = Vec::new();
+        for dependency in std::mem::take(&mut loaded_module.dependencies) {
+            if self.bind_namespace(&dependency, import.span)? {
+                continue;
+            }
+            self.register_native_module(&dependency);
+            self.initialized_modules
+                .entry(dependency.file_path.clone())
+                .or_insert_with(|| dependency.namespace.clone());
+            let name = dependency.file_path.display().to_string();
+            blocks.append(&mut self.prepare_module_blocks(dependency, &name)?);
+        }
+
+        // Тот же модуль под тем же именем второй раз не загружается
+        if let Some((other, first)) = seen.get(&loaded_module.namespace)
+            && *other != loaded_module.file_path
+        {
+            return Err(ImportError::Collision(NamespaceCollision {
+                message: collision_message(&loaded_module.namespace, other),
+                span: import.span,
+                first: Some(*first),
+            }));
+        }
+        seen.insert(
+            loaded_module.namespace.clone(),
+            (loaded_module.file_path.clone(), import.span),
+        );
+        let already_loaded = self.bind_namespace(&loaded_module, import.span)?;
+
+        // Файл уже выполнен под другим именем: новое имя ссылается на
+        // глобальные переменные первого
+        let initialized_as = self
+            .initialized_modules
+            .get(&loaded_module.file_path)
+            .filter(|_| !already_loaded)
+            .cloned();
+        if let Some(first) = &initialized_as {
+            loaded_module.scope_id = self.alias_module_scope(loaded_module.scope_id, first);
+        }
+
+        if !already_loaded {
+            self.register_native_module(&loaded_module);
+            self.record_module_exports(&loaded_module);
+        }
+
+        // Регистрируем пространство имён как Symbol::Module в текущей области.
+        // Модуль, уже загруженный другим файлом, в ней может быть ещё не виден
+        let current_scope = self.symbol_table.current_scope();
+        if self
+            .symbol_table
+            .lookup_in_scope(current_scope, &loaded_module.namespace)
+            .is_none()
+        {
+            let module_symbol = Symbol::new_module(
+                loaded_module.namespace.clone(),
+                loaded_module.scope_id,
+                Span::default(),
+            );
+            if let Err(e) = self.symbol_table.define(module_symbol) {
+                eprintln!(
+                    "Предупреждение: не удалось зарегистрировать модуль '{}': {}",
+                    loaded_module.namespace, e
+                );
+            }
+        }
+
+        // Символы из списка `показать` доступны и без префикса модуля
+        if let ast::ImportFilter::Show(names) = &import.filter {
+            for name in names {
+                let Some(symbol) = self
+                    .symbol_table
+                    .lookup_in_scope(loaded_module.scope_id, name)
+                else {
+                    continue;
+                };
+                let mut symbol = symbol.clone();
+                // Реэкспортированный символ уже знает модуль, где он определён
+                let namespace = symbol
+                    .imported_from
+                    .clone()
+                    .unwrap_or_else(|| loaded_module.namespace.clone());
+                symbol.imported_from = Some(namespace.clone());
+                // Повторный импорт того же имени в сессии (REPL, eval) не ошибка
+                let already_imported = self
+                    .symbol_table
+                    .lookup(name)
+                    .is_some_and(|existing| existing.imported_from == symbol.imported_from);
+                if !already_imported {
+                    self.symbol_table
+                        .define(symbol)
+                        .map_err(|e| ImportError::Load(format!("Импорт '{}': {}", name, e)))?;
+                }
+                self.generator
+                    .import_name(name, format!("{}::{}", namespace, name));
+            }
+        }
+
+        if !already_loaded && initialized_as.is_none() {
+            if !is_native(&loaded_module.file_path) {
+                self.initialized_modules.insert(
+                    loaded_module.file_path.clone(),
+                    loaded_module.namespace.clone(),
+                );
+            }
+            blocks.append(&mut self.prepare_module_blocks(loaded_module, path)?);
+        }
+        Ok(blocks)
+    }
+
+    /// Загрузить собственные импорты модуля и разрешить имена в его коде.
+    /// Возвращает блоки в порядке выполнения: сначала импортированные
+    /// модули, затем сам модуль. Пространство имён модуля уже занято, поэтому
+    /// модуль, импортирующий его в ответ (цикл), видит его экспорты, а
+    /// обращения к ним выполняются, когда код уже определён
+    fn prepare_module_blocks(
+        &mut self,
+        loaded_module: LoadedModule,
+        path: &str,
+    ) -> Result<Vec<ast::ModuleBlock>, ImportError> {
+        let previous_scope = self.symbol_table.enter_module_scope();
+        let blocks = self.import_private(&loaded_module).and_then(|mut blocks| {
+            blocks.push(self.resolve_module_block(loaded_module, path)?);
+            Ok(blocks)
+        });
+        self.symbol_table.leave_module_scope(previous_scope);
+        blocks
+    }
+
+    /// Импорты модуля без `экспорт`: их пространства имён видны только коду
+    /// модуля
+    fn import_private(
+        &mut self,
+        module: &LoadedModule,
+    ) -> Result<Vec<ast::ModuleBlock>, ImportError> {
+        let mut seen = ImportedNamespaces::new();
+        let mut blocks = Vec::new();
+        for import in imports_of(&module.ast) {
+            if import.is_exported {
+                continue;
+            }
+            match self.import_module(&import, Some(&module.file_path), &mut seen) {
+                Ok(mut imported) => blocks.append(&mut imported),
+                Err(ImportError::Load(e)) => {
+                    return Err(ImportError::Load(format!(
+                        "модуль '{}' импортирует '{}': {}",
+                        module.namespace, import.path, e
+                    )));
                 }
-                Err(e) => {
-                    eprintln!("Ошибка загрузки модуля '{}': {}", path, e);
+                Err(ImportError::Collision(collision)) => {
+                    let name = module.file_path.display().to_string();
+                    return Err(ImportError::Resolve(
+                        collision.format(&module.ast.source, &name),
+                    ));
                 }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Область видимости для повторного имени модуля: те же символы, но
+    /// обращения к ним идут к глобальным переменным пространства `first`
+    fn alias_module_scope(
+        &mut self,
+        scope_id: symbol_table::ScopeId,
+        first: &str,
+    ) -> symbol_table::ScopeId {
+        let symbols: Vec<_> = self
+            .symbol_table
+            .get_scope(scope_id)
+            .symbols()
+            .values()
+            .cloned()
+            .collect();
+        let alias_scope = self.symbol_table.new_child_scope();
+        for mut symbol in symbols {
+            symbol.imported_from = symbol.imported_from.or_else(|| Some(first.to_string()));
+            self.symbol_table
+                .define_in_scope(alias_scope, symbol)
+                .expect("символы одной области уникальны");
+        }
+        alias_scope
+    }
+
+    /// Занять пространство имён модулем. `true`, если этот модуль уже
+    /// загружен под этим именем и выполнять его снова не нужно
+    fn bind_namespace(
+        &mut self,
+        module: &LoadedModule,
+        import_span: span::Span,
+    ) -> Result<bool, ImportError> {
+        match self.module_namespaces.get(&module.namespace) {
+            Some(path) if *path == module.file_path => Ok(true),
+            Some(path) => Err(ImportError::Collision(NamespaceCollision {
+                message: collision_message(&module.namespace, path),
+                span: import_span,
+                first: None,
+            })),
+            None => {
+                self.module_namespaces
+                    .insert(module.namespace.clone(), module.file_path.clone());
+                Ok(false)
             }
         }
+    }
+
+    /// Сообщить VM экспорты модуля для стд/рефлексия. Реэкспортированный
+    /// символ указывает на глобальную переменную модуля, где он определён
+    fn record_module_exports(&mut self, module: &LoadedModule) {
+        let exports = module
+            .exports
+            .iter()
+            .map(|name| {
+                let namespace = self
+                    .symbol_table
+                    .lookup_in_scope(module.scope_id, name)
+                    .and_then(|symbol| symbol.imported_from.as_deref())
+                    .unwrap_or(&module.namespace);
+                (name.clone(), format!("{}::{}", namespace, name))
+            })
+            .collect();
+        self.vm.record_module_exports(&module.namespace, exports);
+    }
+
+    /// Зарегистрировать в VM функции нативного модуля; для модулей на Ponos
+    /// ничего не делает
+    fn register_native_module(&mut self, module: &LoadedModule) {
+        let Some(path) = module
+            .file_path
+            .to_str()
+            .and_then(|p| p.strip_prefix("<native:"))
+            .and_then(|p| p.strip_suffix('>'))
+        else {
+            return;
+        };
+        let native_registry = self.module_resolver.native_registry();
+        if let Err(e) = native_registry.register_module_in_vm(path, &module.namespace, &mut self.vm)
+        {
+            eprintln!(
+                "Предупреждение: не удалось зарегистрировать нативные функции для '{}': {}",
+                path, e
+            );
+        }
+    }
 
-        // КРИТИЧНО: Переупорядочиваем statements чтобы ModuleBlocks были первыми
-        // Это нужно чтобы глобальные переменные модулей определились до их использования
-        let mut module_blocks = Vec::new();
-        let mut other_statements = Vec::new();
+    /// Разрешить имена в коде модуля и проверить его перед компиляцией
+    fn resolve_module_block(
+        &mut self,
+        loaded_module: LoadedModule,
+        path: &str,
+    ) -> Result<ast::ModuleBlock, ImportError> {
+        use ast::Statement;
+
+        let mut module_ast = ast::Program::new(vec![Statement::ModuleBlock(into_module_block(
+            loaded_module,
+        ))]);
+        self.name_resolver
+            .resolve(&mut module_ast, &self.symbol_table)
+            .map_err(ImportError::Resolve)?;
+
+        if let Some(Statement::ModuleBlock(block)) = module_ast.statements.first() {
+            let name = block
+                .file_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| path.to_string());
+            let source = block.source.clone();
+            let errors = self.static_errors(&module_ast, &source, &name);
+            if !errors.is_empty() {
+                return Err(ImportError::Resolve(errors.join("\n")));
+            }
+        }
+
+        match module_ast.statements.pop() {
+            Some(Statement::ModuleBlock(block)) => Ok(block),
+            _ => unreachable!("программа модуля состоит из одного блока"),
+        }
+    }
+
+    /// Семантическая проверка и проверка типов программы перед генерацией
+    /// кода; ошибки форматируются с подсветкой кода
+    fn static_errors(&self, ast: &ast::Program, source: &str, name: &str) -> Vec<String> {
+        let is_global = |name: &str| self.vm.is_global_defined(name);
+        let semantic = SemanticChecker::new(&self.symbol_table, &is_global)
+            .check(ast)
+            .into_iter()
+            .map(|error| error.format(source, name));
+        let types = TypeChecker::new(&self.symbol_table)
+            .check(ast)
+            .into_iter()
+            .map(|error| error.format(source, name));
+        semantic.chain(types).collect()
+    }
+
+    /// Загрузить скомпилированный модуль в VM: зарегистрировать отложенные
+    /// функции, выполнить код верхнего уровня и проверить таблицу экспортов
+    fn load_module_unit(&mut self, unit: ModuleUnit) -> Result<(), String> {
+        if cfg!(debug_assertions) {
+            println!(
+                "модуль {}: opcodes:\n{:#?}\nэкспорт: {:?}",
+                unit.namespace, unit.opcodes, unit.exports
+            );
+        }
+
+        // Функция компилируется при первом обращении; ошибка компиляции
+        // бросается как Ошибка в месте обращения
+        for lazy in unit.lazy_functions {
+            let name = lazy.mangled_name.clone();
+            self.vm.define_lazy_global(
+                &name,
+                Box::new(move || {
+                    let function = lazy.mangled_name.clone();
+                    lazy.materialize().map_err(|e| {
+                        format!("Ошибка компиляции функции {}: {}", function, e.message)
+                    })
+                }),
+            );
+        }
+
+        // Код модуля не должен оставлять значений на стеке основной программы
+        let depth = self.vm.stack.len();
+        let source_name = unit
+            .file_path
+            .as_deref()
+            .map(|path| Rc::from(path.display().to_string()));
+        self.vm
+            .execute_with_lines(unit.opcodes, &unit.constants, unit.lines, source_name)?;
+        self.vm.stack.truncate(depth);
+        if self.vm.exit_code().is_some() {
+            // Модуль завершил программу, не дойдя до определения экспортов
+            return Ok(());
+        }
 
-        for stmt in ast.statements.drain(..) {
-            match stmt {
-                Statement::ModuleBlock(_) => module_blocks.push(stmt),
-                _ => other_statements.push(stmt),
+        for export in &unit.exports {
+            if !self.vm.has_global(export) {
+                let location = unit
+                    .file_path
+                    .as_ref()
+                    .map(|path| format!(" ({})", path.display()))
+                    .unwrap_or_default();
+                eprintln!(
+                    "Предупреждение: модуль '{}'{} не определил экспортируемый символ '{}'",
+                    unit.namespace, location, export
+                );
             }
         }
+        Ok(())
+    }
+}
+
+/// Почему не удалось импортировать модуль
+enum ImportError {
+    /// Модуль не найден или не разобран
+    Load(String),
+    /// Ошибка разрешения имён в коде модуля
+    Resolve(String),
+    /// Пространство имён уже занято другим модулем
+    Collision(NamespaceCollision),
+}
+
+/// Пространства имён, импортированные программой: имя -> файл модуля и
+/// место импорта (для сообщения о конфликте)
+type ImportedNamespaces = HashMap<String, (PathBuf, span::Span)>;
+
+struct NamespaceCollision {
+    message: String,
+    /// Импорт, вызвавший конфликт
+    span: span::Span,
+    /// Первый импорт под тем же именем, если он в этой же программе
+    first: Option<span::Span>,
+}
+
+impl NamespaceCollision {
+    fn format(&self, source: &str, name: &str) -> String {
+        let error = |message: &str, span| {
+            PonosParseError::new(ParseErrorKind::Custom(message.to_string()), span)
+                .format(source, name)
+        };
+        let mut text = error(&self.message, self.span);
+        if let Some(first) = self.first {
+            text.push('\n');
+            text.push_str(&error("Первый импорт под этим именем", first));
+        }
+        text
+    }
+}
+
+/// Имя функции — точки входа программы
+const ENTRY_POINT: &str = "главная";
+
+/// Вызов `главная()`, который выполняется после кода верхнего уровня, если
+/// программа определяет эту функцию и не вызывает её сама. `Err` с местом
+/// объявления — главной нужны аргументы
+fn entry_point_call(ast: &ast::Program) -> Result<Option<ast::Statement>, span::Span> {
+    use ast::{Expression, Statement};
+
+    let Some(entry) = ast.statements.iter().find_map(|statement| match statement {
+        Statement::FuncDecl(decl) if decl.name == ENTRY_POINT => Some(decl),
+        _ => None,
+    }) else {
+        return Ok(None);
+    };
+    if entry
+        .params
+        .iter()
+        .any(|param| param.default.is_none() && !param.is_rest)
+    {
+        return Err(entry.span);
+    }
+    let called = ast.statements.iter().any(|statement| {
+        matches!(statement, Statement::Expression(Expression::Call(call))
+            if matches!(&call.callee, Expression::Identifier(name, _) if name == ENTRY_POINT))
+    });
+    if called {
+        return Ok(None);
+    }
+    Ok(Some(Statement::Expression(Expression::Call(Box::new(
+        ast::CallExpr {
+            callee: Expression::Identifier(ENTRY_POINT.to_string(), entry.span),
+            arguments: Vec::new(),
+            span: entry.span,
+        },
+    )))))
+}
+
+fn is_native(path: &std::path::Path) -> bool {
+    path.to_str().is_some_and(|p| p.starts_with("<native:"))
+}
+
+fn collision_message(namespace: &str, path: &std::path::Path) -> String {
+    format!(
+        "Пространство имён '{}' уже занято модулем {}; укажите другое имя через 'как'",
+        namespace,
+        path.display()
+    )
+}
+
+/// Импорты верхнего уровня программы
+fn imports_of(ast: &ast::Program) -> Vec<ast::ImportStatement> {
+    ast.statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            ast::Statement::Import(import) => Some(import.clone()),
+            _ => None,
+        })
+        .collect()
+}
 
-        // Сначала ModuleBlocks, потом остальное
-        ast.statements.extend(module_blocks);
-        ast.statements.extend(other_statements);
+/// Текст сообщения паники (panic! с форматированием дает String, с литералом — &str)
+pub(crate) fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "Неизвестная ошибка выполнения".to_string(),
+        },
     }
 }
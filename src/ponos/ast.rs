@@ -1,13 +1,27 @@
 use crate::ponos::span::Span;
+use std::path::PathBuf;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum AstNode {
     Program(Program),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Program {
     pub statements: Vec<Statement>,
+    pub file_path: Option<PathBuf>, // Файл, из которого разобрана программа (для диагностики)
+    pub source: Rc<str>,            // Исходный текст (для диагностики)
+}
+
+impl Program {
+    /// Программа без сведений об источнике (для AST, построенного вручную)
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Program {
+            statements,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,12 +43,35 @@ pub enum Statement {
     Expression(Expression),
 }
 
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VarDecl(s) => s.span,
+            Statement::FuncDecl(s) => s.span,
+            Statement::ClassDecl(s) => s.span,
+            Statement::InterfaceDecl(s) => s.span,
+            Statement::AnnotationDecl(s) => s.span,
+            Statement::Import(s) => s.span,
+            Statement::ModuleBlock(s) => s.span,
+            Statement::If(s) => s.span,
+            Statement::While(s) => s.span,
+            Statement::ForEach(s) => s.span,
+            Statement::Return(s) => s.span,
+            Statement::Try(s) => s.span,
+            Statement::Throw(s) => s.span,
+            Statement::Assignment(s) => s.span,
+            Statement::Expression(e) => e.span(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct VarDecl {
     pub name: String,
     pub type_annotation: Option<String>,
     pub initializer: Option<Expression>,
     pub is_exported: bool,
+    pub is_const: bool, // `конст`: переменной нельзя присвоить новое значение
     pub span: Span,
 }
 
@@ -42,6 +79,7 @@ pub struct VarDecl {
 pub struct FuncDecl {
     pub name: String,
     pub params: Vec<Parameter>,
+    pub return_type: Option<String>, // Аннотация типа результата: функ f(): число
     pub body: Vec<Statement>,
     pub annotations: Vec<Annotation>,
     pub is_exported: bool,
@@ -52,6 +90,8 @@ pub struct FuncDecl {
 pub struct Parameter {
     pub name: String,
     pub type_annotation: Option<String>,
+    pub default: Option<Expression>, // Значение по умолчанию: функ f(x = 10)
+    pub is_rest: bool,               // `...имя`: собирает оставшиеся аргументы в массив
     pub span: Span,
 }
 
@@ -100,9 +140,19 @@ pub struct ReturnStatement {
 
 #[derive(Debug, Clone)]
 pub struct TryStatement {
-    pub try_body: Vec<Statement>,   // Блок кода попытки
-    pub catch_var: Option<String>,  // Имя переменной для исключения (опционально)
-    pub catch_body: Vec<Statement>, // Блок обработчика
+    pub try_body: Vec<Statement>,             // Блок кода попытки
+    pub catches: Vec<CatchClause>,            // Ветки `перехват` по порядку; пусто — без перехвата
+    pub finally_body: Option<Vec<Statement>>, // Блок `наконец`, выполняется при любом выходе
+    pub span: Span,
+}
+
+/// Ветка `перехват [имя[: Класс]]`. Ветка с классом перехватывает только его
+/// экземпляры (и экземпляры наследников); неподошедшее исключение бросается дальше
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub var: Option<String>,       // Имя переменной для исключения (опционально)
+    pub class: Option<Expression>, // Класс исключения; None — любое исключение
+    pub body: Vec<Statement>,
     pub span: Span,
 }
 
@@ -145,7 +195,16 @@ pub enum ClassMember {
         name: String,
         type_annotation: Option<String>,
     },
+    /// `статический имя[: тип] [= значение];` — поле самого класса,
+    /// инициализируется при объявлении класса
+    StaticField {
+        name: String,
+        type_annotation: Option<String>,
+        initializer: Option<Expression>,
+    },
     Method(FuncDecl),
+    /// `статический функ ...` — метод класса без `это`
+    StaticMethod(FuncDecl),
     Constructor(ConstructorDecl),
 }
 
@@ -168,6 +227,7 @@ pub struct InterfaceDecl {
 pub struct MethodSignature {
     pub name: String,
     pub params: Vec<Parameter>,
+    pub return_type: Option<String>,
     pub span: Span,
 }
 
@@ -185,14 +245,49 @@ pub struct AnnotationDecl {
 pub struct ImportStatement {
     pub path: String,
     pub alias: Option<String>, // Переименование: использовать "модуль" как псевдоним
+    pub filter: ImportFilter,  // Списки показать/скрыть
+    pub is_exported: bool,     // Реэкспорт: экспорт использовать "модуль";
     pub span: Span,
 }
 
+/// Какие экспорты модуля доступны после импорта
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ImportFilter {
+    /// Все экспорты
+    #[default]
+    All,
+    /// Только перечисленные: использовать "модуль" показать а, б;
+    Show(Vec<String>),
+    /// Все, кроме перечисленных: использовать "модуль" скрыть в;
+    Hide(Vec<String>),
+}
+
+impl ImportFilter {
+    /// Доступен ли экспорт с этим именем
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            ImportFilter::All => true,
+            ImportFilter::Show(names) => names.iter().any(|n| n == name),
+            ImportFilter::Hide(names) => !names.iter().any(|n| n == name),
+        }
+    }
+
+    /// Имена, перечисленные в списке
+    pub fn names(&self) -> &[String] {
+        match self {
+            ImportFilter::All => &[],
+            ImportFilter::Show(names) | ImportFilter::Hide(names) => names,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ModuleBlock {
     pub namespace: String,          // Имя пространства имен
     pub statements: Vec<Statement>, // Statements модуля
     pub span: Span,
+    pub file_path: Option<PathBuf>, // Файл модуля (None для встроенных и нативных)
+    pub source: Rc<str>,            // Исходный текст модуля
 }
 
 // Выражения
@@ -200,6 +295,7 @@ pub struct ModuleBlock {
 #[derive(Debug, Clone)]
 pub enum Expression {
     // Литералы
+    Integer(i64, Span), // Литерал без дробной части
     Number(f64, Span),
     String(String, Span),
     Boolean(bool, Span),
@@ -237,11 +333,15 @@ pub enum Expression {
     // Специальные
     This(Span),
     Super(String, Span), // Super(method_name, span)
+
+    // Развёртывание массива в аргументы вызова: ф(...массив)
+    Spread(Box<Expression>, Span),
 }
 
 impl Expression {
     pub fn span(&self) -> Span {
         match self {
+            Expression::Integer(_, s) => *s,
             Expression::Number(_, s) => *s,
             Expression::String(_, s) => *s,
             Expression::Boolean(_, s) => *s,
@@ -259,6 +359,7 @@ impl Expression {
             Expression::DictLiteral(e) => e.span,
             Expression::This(s) => *s,
             Expression::Super(_, s) => *s,
+            Expression::Spread(_, s) => *s,
         }
     }
 }
@@ -291,21 +392,24 @@ pub enum BinaryOperator {
     // Логические
     And, // и
     Or,  // или
+
+    Coalesce, // ?? — левое значение, если оно не ничто, иначе правое
 }
 
 impl BinaryOperator {
     /// Возвращает приоритет оператора (больше = выше приоритет)
     pub fn precedence(&self) -> u8 {
         match self {
-            BinaryOperator::Or => 1,
-            BinaryOperator::And => 2,
-            BinaryOperator::Equal | BinaryOperator::NotEqual => 3,
+            BinaryOperator::Coalesce => 1,
+            BinaryOperator::Or => 2,
+            BinaryOperator::And => 3,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 4,
             BinaryOperator::Less
             | BinaryOperator::LessEqual
             | BinaryOperator::Greater
-            | BinaryOperator::GreaterEqual => 4,
-            BinaryOperator::Add | BinaryOperator::Subtract => 5,
-            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 6,
+            | BinaryOperator::GreaterEqual => 5,
+            BinaryOperator::Add | BinaryOperator::Subtract => 6,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 7,
         }
     }
 
@@ -313,6 +417,26 @@ impl BinaryOperator {
     pub fn is_left_associative(&self) -> bool {
         true
     }
+
+    /// Запись оператора в исходном коде
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "и",
+            BinaryOperator::Or => "или",
+            BinaryOperator::Coalesce => "??",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -328,6 +452,16 @@ pub enum UnaryOperator {
     Not,    // !
 }
 
+impl UnaryOperator {
+    /// Запись оператора в исходном коде
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Not => "!",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CallExpr {
     pub callee: Expression,
@@ -344,14 +478,16 @@ pub struct FieldAccessExpr {
 
 #[derive(Debug, Clone)]
 pub struct ModuleAccessExpr {
-    pub namespace: String, // Имя пространства имен (математика, мат)
-    pub symbol: String,    // Имя символа (корень, ПИ)
+    pub namespace: String,      // Имя пространства имен (математика, мат)
+    pub symbol: String,         // Имя символа (корень, ПИ)
+    pub origin: Option<String>, // Модуль, где символ определён, если он реэкспортирован
     pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct LambdaExpr {
     pub params: Vec<Parameter>,
+    pub return_type: Option<String>,
     pub body: Vec<Statement>,
     pub span: Span,
 }
@@ -0,0 +1,443 @@
+//! Представление AST в JSON (`ponos parse --ast-json`).
+//!
+//! Каждый узел — объект с полем `kind` (имя варианта AST: `VarDecl`, `If`,
+//! `Binary`...) и полями узла в snake_case. Позиции `span` — байтовые
+//! смещения `{"start": .., "end": ..}` в исходном тексте. Операторы
+//! записываются так же, как в исходном коде: `"+"`, `"и"`, `"!"`.
+
+use crate::ponos::ast::*;
+use crate::ponos::span::Span;
+use serde_json::{Value as JsonValue, json};
+
+/// Программа: `{"kind": "Program", "file": .., "statements": [..]}`
+pub fn program_to_json(program: &Program) -> JsonValue {
+    json!({
+        "kind": "Program",
+        "file": program.file_path.as_ref().map(|path| path.display().to_string()),
+        "statements": statements(&program.statements),
+    })
+}
+
+fn span(span: Span) -> JsonValue {
+    json!({ "start": span.start, "end": span.end })
+}
+
+fn statements(statements: &[Statement]) -> JsonValue {
+    statements.iter().map(statement_to_json).collect()
+}
+
+fn optional(expr: &Option<Expression>) -> JsonValue {
+    expr.as_ref().map_or(JsonValue::Null, expression_to_json)
+}
+
+fn parameters(params: &[Parameter]) -> JsonValue {
+    params
+        .iter()
+        .map(|param| {
+            json!({
+                "name": param.name,
+                "type_annotation": param.type_annotation,
+                "default": optional(&param.default),
+                "rest": param.is_rest,
+                "span": span(param.span),
+            })
+        })
+        .collect()
+}
+
+fn annotations(annotations: &[Annotation]) -> JsonValue {
+    annotations
+        .iter()
+        .map(|annotation| {
+            let args: Vec<JsonValue> = annotation
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    AnnotationArgument::Positional(value) => json!({
+                        "name": null,
+                        "value": expression_to_json(value),
+                    }),
+                    AnnotationArgument::Named { name, value } => json!({
+                        "name": name,
+                        "value": expression_to_json(value),
+                    }),
+                })
+                .collect();
+            json!({
+                "name": annotation.name,
+                "args": args,
+                "span": span(annotation.span),
+            })
+        })
+        .collect()
+}
+
+fn function(decl: &FuncDecl) -> JsonValue {
+    json!({
+        "kind": "FuncDecl",
+        "name": decl.name,
+        "params": parameters(&decl.params),
+        "return_type": decl.return_type,
+        "body": statements(&decl.body),
+        "annotations": annotations(&decl.annotations),
+        "exported": decl.is_exported,
+        "span": span(decl.span),
+    })
+}
+
+fn class_member(member: &ClassMember) -> JsonValue {
+    match member {
+        ClassMember::Field {
+            name,
+            type_annotation,
+        } => json!({
+            "kind": "Field",
+            "name": name,
+            "type_annotation": type_annotation,
+        }),
+        ClassMember::StaticField {
+            name,
+            type_annotation,
+            initializer,
+        } => json!({
+            "kind": "StaticField",
+            "name": name,
+            "type_annotation": type_annotation,
+            "initializer": optional(initializer),
+        }),
+        ClassMember::Method(method) => {
+            let mut json = function(method);
+            json["kind"] = json!("Method");
+            json
+        }
+        ClassMember::StaticMethod(method) => {
+            let mut json = function(method);
+            json["kind"] = json!("StaticMethod");
+            json
+        }
+        ClassMember::Constructor(constructor) => json!({
+            "kind": "Constructor",
+            "params": parameters(&constructor.params),
+            "body": statements(&constructor.body),
+            "span": span(constructor.span),
+        }),
+    }
+}
+
+/// Оператор в JSON
+pub fn statement_to_json(statement: &Statement) -> JsonValue {
+    match statement {
+        Statement::VarDecl(decl) => json!({
+            "kind": "VarDecl",
+            "name": decl.name,
+            "type_annotation": decl.type_annotation,
+            "initializer": optional(&decl.initializer),
+            "exported": decl.is_exported,
+            "const": decl.is_const,
+            "span": span(decl.span),
+        }),
+        Statement::FuncDecl(decl) => function(decl),
+        Statement::ClassDecl(decl) => json!({
+            "kind": "ClassDecl",
+            "name": decl.name,
+            "extends": decl.extends,
+            "implements": decl.implements,
+            "members": decl.members.iter().map(class_member).collect::<Vec<_>>(),
+            "annotations": annotations(&decl.annotations),
+            "exported": decl.is_exported,
+            "span": span(decl.span),
+        }),
+        Statement::InterfaceDecl(decl) => {
+            let methods: Vec<JsonValue> = decl
+                .methods
+                .iter()
+                .map(|method| {
+                    json!({
+                        "name": method.name,
+                        "params": parameters(&method.params),
+                        "return_type": method.return_type,
+                        "span": span(method.span),
+                    })
+                })
+                .collect();
+            json!({
+                "kind": "InterfaceDecl",
+                "name": decl.name,
+                "methods": methods,
+                "exported": decl.is_exported,
+                "span": span(decl.span),
+            })
+        }
+        Statement::AnnotationDecl(decl) => json!({
+            "kind": "AnnotationDecl",
+            "name": decl.name,
+            "body": statements(&decl.body),
+            "exported": decl.is_exported,
+            "span": span(decl.span),
+        }),
+        Statement::Import(import) => {
+            let (show, hide) = match &import.filter {
+                ImportFilter::All => (JsonValue::Null, JsonValue::Null),
+                ImportFilter::Show(names) => (json!(names), JsonValue::Null),
+                ImportFilter::Hide(names) => (JsonValue::Null, json!(names)),
+            };
+            json!({
+                "kind": "Import",
+                "path": import.path,
+                "alias": import.alias,
+                "show": show,
+                "hide": hide,
+                "exported": import.is_exported,
+                "span": span(import.span),
+            })
+        }
+        Statement::ModuleBlock(module) => json!({
+            "kind": "ModuleBlock",
+            "namespace": module.namespace,
+            "file": module.file_path.as_ref().map(|path| path.display().to_string()),
+            "statements": statements(&module.statements),
+            "span": span(module.span),
+        }),
+        Statement::If(statement) => json!({
+            "kind": "If",
+            "condition": expression_to_json(&statement.condition),
+            "then_branch": statements(&statement.then_branch),
+            "else_branch": statement.else_branch.as_deref().map(statements),
+            "span": span(statement.span),
+        }),
+        Statement::While(statement) => json!({
+            "kind": "While",
+            "condition": expression_to_json(&statement.condition),
+            "body": statements(&statement.body),
+            "span": span(statement.span),
+        }),
+        Statement::ForEach(statement) => json!({
+            "kind": "ForEach",
+            "element_name": statement.element_name,
+            "index_name": statement.index_name,
+            "iterable": expression_to_json(&statement.iterable),
+            "body": statements(&statement.body),
+            "span": span(statement.span),
+        }),
+        Statement::Return(statement) => json!({
+            "kind": "Return",
+            "value": optional(&statement.value),
+            "span": span(statement.span),
+        }),
+        Statement::Try(statement) => json!({
+            "kind": "Try",
+            "try_body": statements(&statement.try_body),
+            "catches": statement.catches.iter().map(|clause| json!({
+                "var": clause.var,
+                "class": optional(&clause.class),
+                "body": statements(&clause.body),
+                "span": span(clause.span),
+            })).collect::<Vec<_>>(),
+            "finally_body": statement.finally_body.as_deref().map(statements),
+            "span": span(statement.span),
+        }),
+        Statement::Throw(statement) => json!({
+            "kind": "Throw",
+            "expression": expression_to_json(&statement.expression),
+            "span": span(statement.span),
+        }),
+        Statement::Assignment(statement) => {
+            let target = match &statement.target {
+                AssignmentTarget::Identifier(name) => json!({
+                    "kind": "Identifier",
+                    "name": name,
+                }),
+                AssignmentTarget::FieldAccess(object, field) => json!({
+                    "kind": "FieldAccess",
+                    "object": expression_to_json(object),
+                    "field": field,
+                }),
+                AssignmentTarget::Index(object, index) => json!({
+                    "kind": "Index",
+                    "object": expression_to_json(object),
+                    "index": expression_to_json(index),
+                }),
+            };
+            json!({
+                "kind": "Assignment",
+                "target": target,
+                "value": expression_to_json(&statement.value),
+                "span": span(statement.span),
+            })
+        }
+        Statement::Expression(expr) => json!({
+            "kind": "Expression",
+            "expression": expression_to_json(expr),
+            "span": span(expr.span()),
+        }),
+    }
+}
+
+/// Выражение в JSON
+pub fn expression_to_json(expr: &Expression) -> JsonValue {
+    match expr {
+        Expression::Integer(value, s) => json!({
+            "kind": "Integer",
+            "value": value,
+            "span": span(*s),
+        }),
+        Expression::Number(value, s) => json!({
+            "kind": "Number",
+            "value": value,
+            "span": span(*s),
+        }),
+        Expression::String(value, s) => json!({
+            "kind": "String",
+            "value": value,
+            "span": span(*s),
+        }),
+        Expression::Boolean(value, s) => json!({
+            "kind": "Boolean",
+            "value": value,
+            "span": span(*s),
+        }),
+        Expression::Nil(s) => json!({ "kind": "Nil", "span": span(*s) }),
+        Expression::Identifier(name, s) => json!({
+            "kind": "Identifier",
+            "name": name,
+            "span": span(*s),
+        }),
+        Expression::Binary(binary) => json!({
+            "kind": "Binary",
+            "operator": binary.operator.symbol(),
+            "left": expression_to_json(&binary.left),
+            "right": expression_to_json(&binary.right),
+            "span": span(binary.span),
+        }),
+        Expression::Unary(unary) => json!({
+            "kind": "Unary",
+            "operator": unary.operator.symbol(),
+            "operand": expression_to_json(&unary.operand),
+            "span": span(unary.span),
+        }),
+        Expression::Call(call) => json!({
+            "kind": "Call",
+            "callee": expression_to_json(&call.callee),
+            "arguments": call.arguments.iter().map(expression_to_json).collect::<Vec<_>>(),
+            "span": span(call.span),
+        }),
+        Expression::FieldAccess(access) => json!({
+            "kind": "FieldAccess",
+            "object": expression_to_json(&access.object),
+            "field": access.field,
+            "span": span(access.span),
+        }),
+        Expression::ModuleAccess(access) => json!({
+            "kind": "ModuleAccess",
+            "namespace": access.namespace,
+            "symbol": access.symbol,
+            "origin": access.origin,
+            "span": span(access.span),
+        }),
+        Expression::Lambda(lambda) => json!({
+            "kind": "Lambda",
+            "params": parameters(&lambda.params),
+            "return_type": lambda.return_type,
+            "body": statements(&lambda.body),
+            "span": span(lambda.span),
+        }),
+        Expression::Index(index) => json!({
+            "kind": "Index",
+            "object": expression_to_json(&index.object),
+            "index": expression_to_json(&index.index),
+            "span": span(index.span),
+        }),
+        Expression::Range(range) => json!({
+            "kind": "Range",
+            "start": range.start.as_deref().map(expression_to_json),
+            "end": range.end.as_deref().map(expression_to_json),
+            "span": span(range.span),
+        }),
+        Expression::ArrayLiteral(array) => json!({
+            "kind": "Array",
+            "elements": array.elements.iter().map(expression_to_json).collect::<Vec<_>>(),
+            "span": span(array.span),
+        }),
+        Expression::DictLiteral(dict) => {
+            let pairs: Vec<JsonValue> = dict
+                .pairs
+                .iter()
+                .map(|(key, value)| {
+                    json!({
+                        "key": expression_to_json(key),
+                        "value": expression_to_json(value),
+                    })
+                })
+                .collect();
+            json!({ "kind": "Dict", "pairs": pairs, "span": span(dict.span) })
+        }
+        Expression::This(s) => json!({ "kind": "This", "span": span(*s) }),
+        Expression::Super(method, s) => json!({
+            "kind": "Super",
+            "method": method,
+            "span": span(*s),
+        }),
+        Expression::Spread(value, s) => json!({
+            "kind": "Spread",
+            "value": expression_to_json(value),
+            "span": span(*s),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ponos::parser::PonosParser;
+
+    fn parse(source: &str) -> JsonValue {
+        let program = PonosParser::new().parse(source.to_string()).unwrap();
+        program_to_json(&program)
+    }
+
+    #[test]
+    fn test_statements_and_expressions_are_tagged_with_kind() {
+        let json = parse("пер x: число = -1 + 2;\nесли x > 0 вывести(x); иначе x = 0; конец\n");
+        let statements = &json["statements"];
+
+        assert_eq!(statements[0]["kind"], "VarDecl");
+        assert_eq!(statements[0]["type_annotation"], "число");
+        assert_eq!(statements[0]["span"], json!({ "start": 0, "end": 30 }));
+        let initializer = &statements[0]["initializer"];
+        assert_eq!(initializer["operator"], "+");
+        assert_eq!(initializer["left"]["kind"], "Unary");
+        assert_eq!(initializer["right"]["value"], 2.0);
+
+        let branch = &statements[1];
+        assert_eq!(branch["kind"], "If");
+        assert_eq!(branch["then_branch"][0]["expression"]["kind"], "Call");
+        assert_eq!(branch["else_branch"][0]["target"]["name"], "x");
+    }
+
+    #[test]
+    fn test_declarations_keep_names_and_annotations() {
+        let json = parse(
+            "использовать \"стд/тест\" как т показать утверждать;\n\
+             @Тест(имя = \"сумма\")\n\
+             экспорт функ f(а: число): число возврат а; конец\n\
+             класс Б наследует А\n    x: число\n    конструктор(x) это.x = x; конец\nконец\n",
+        );
+        let statements = &json["statements"];
+
+        assert_eq!(statements[0]["alias"], "т");
+        assert_eq!(statements[0]["show"], json!(["утверждать"]));
+        assert_eq!(statements[0]["hide"], JsonValue::Null);
+
+        let function = &statements[1];
+        assert_eq!(function["exported"], true);
+        assert_eq!(function["params"][0]["type_annotation"], "число");
+        assert_eq!(function["annotations"][0]["args"][0]["name"], "имя");
+
+        let members = &statements[2]["members"];
+        assert_eq!(
+            members[0],
+            json!({ "kind": "Field", "name": "x", "type_annotation": "число" })
+        );
+        assert_eq!(members[1]["body"][0]["target"]["kind"], "FieldAccess");
+    }
+}
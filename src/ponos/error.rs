@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// Ошибка запуска программы с указанием этапа, на котором она возникла.
+/// Сообщения уже отформатированы: с подсветкой исходного кода, где она есть
+#[derive(Debug, Clone, PartialEq)]
+pub enum PonosError {
+    /// Синтаксическая ошибка
+    Parse(String),
+    /// Модуль не найден, не разобран или завершился с ошибкой
+    Import(String),
+    /// Имя не объявлено или не найдено в модуле
+    Resolve(String),
+    /// Ошибки семантической проверки и проверки типов
+    Check(Vec<String>),
+    /// Конструкцию не удалось скомпилировать
+    Codegen(String),
+    /// Ошибка выполнения или необработанное исключение
+    Runtime(String),
+}
+
+impl fmt::Display for PonosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PonosError::Parse(message)
+            | PonosError::Import(message)
+            | PonosError::Resolve(message)
+            | PonosError::Codegen(message)
+            | PonosError::Runtime(message) => write!(f, "{}", message),
+            PonosError::Check(errors) => write!(f, "{}", errors.join("\n\n")),
+        }
+    }
+}
+
+impl std::error::Error for PonosError {}
@@ -0,0 +1,179 @@
+use crate::ponos::opcode::{LineTable, OpCode};
+use crate::ponos::value::{Class, Function, Instance, Value, ValueKey};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Встроенная иерархия исключений. Ошибки выполнения VM и нативных функций
+// бросаются экземплярами этих классов; программа может наследовать от них
+// свои классы ошибок. `исключение "текст"` по-прежнему бросает строку
+
+/// Базовый класс всех ошибок выполнения
+pub const ERROR: &str = "Ошибка";
+/// Операция применена к значению неподходящего типа
+pub const TYPE_ERROR: &str = "ОшибкаТипа";
+/// Индекс или границы среза вне диапазона
+pub const INDEX_ERROR: &str = "ОшибкаИндекса";
+/// Ошибка файловой системы или сети
+pub const IO_ERROR: &str = "ОшибкаИО";
+/// Превышен предел ресурсов VM: глубина вызовов или размер стека
+pub const LIMIT_ERROR: &str = "ОшибкаЛимита";
+/// Выполнение прервано по запросу пользователя (Ctrl-C)
+pub const INTERRUPT: &str = "ПрерываниеПользователя";
+/// Некорректный JSON или значение, которое нельзя записать в JSON
+pub const JSON_ERROR: &str = "ОшибкаJSON";
+
+/// Поле с текстом ошибки
+pub const MESSAGE_FIELD: &str = "сообщение";
+/// Поле со стеком вызовов на момент броска: массив строк вида
+/// `функция (файл, строка N)`
+pub const STACK_FIELD: &str = "стек";
+/// Поля ОшибкаJSON: строка и столбец ошибки разбора (`ничто` для ошибок
+/// сериализации)
+pub const LINE_FIELD: &str = "строка";
+pub const COLUMN_FIELD: &str = "столбец";
+
+/// Наследники `Ошибка` и их собственные поля
+const SUBCLASSES: [(&str, &[&str]); 6] = [
+    (TYPE_ERROR, &[]),
+    (INDEX_ERROR, &[]),
+    (IO_ERROR, &[]),
+    (LIMIT_ERROR, &[]),
+    (INTERRUPT, &[]),
+    (JSON_ERROR, &[LINE_FIELD, COLUMN_FIELD]),
+];
+
+/// Классы встроенных ошибок
+#[derive(Debug)]
+pub struct ErrorClasses {
+    base: Rc<Class>,
+    classes: HashMap<&'static str, Rc<Class>>,
+}
+
+impl Default for ErrorClasses {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ErrorClasses {
+    pub fn new() -> Self {
+        let mut methods = HashMap::new();
+        methods.insert("конструктор".to_string(), Rc::new(constructor()));
+        let base = Rc::new(Class {
+            name: ERROR.to_string(),
+            methods,
+            fields: vec![MESSAGE_FIELD.to_string(), STACK_FIELD.to_string()],
+            parent: None,
+            statics: RefCell::default(),
+        });
+
+        let mut classes = HashMap::new();
+        classes.insert(ERROR, base.clone());
+        for (name, fields) in SUBCLASSES {
+            let class = Class {
+                name: name.to_string(),
+                methods: HashMap::new(),
+                fields: fields.iter().map(|field| field.to_string()).collect(),
+                parent: Some(base.clone()),
+                statics: RefCell::default(),
+            };
+            classes.insert(name, Rc::new(class));
+        }
+        ErrorClasses { base, classes }
+    }
+
+    /// Все классы с их глобальными именами
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Rc<Class>)> {
+        self.classes.iter().map(|(name, class)| (*name, class))
+    }
+
+    /// Экземпляр встроенного класса ошибки с пустым стеком; стек заполняется
+    /// при броске. Собственные поля класса равны `ничто`
+    pub fn instantiate(&self, class: &str, message: String) -> Value {
+        let class = self
+            .classes
+            .get(class)
+            .unwrap_or_else(|| panic!("Неизвестный класс ошибки {}", class))
+            .clone();
+        let mut fields: HashMap<_, _> = class
+            .fields
+            .iter()
+            .map(|field| (field.clone(), Value::Nil))
+            .collect();
+        fields.insert(MESSAGE_FIELD.to_string(), Value::String(message.into()));
+        fields.insert(STACK_FIELD.to_string(), empty_array());
+        Value::Instance(Rc::new(RefCell::new(Instance { class, fields })))
+    }
+
+    /// Экземпляр `Ошибка` или класса, унаследованного от неё
+    pub fn error_instance(&self, value: &Value) -> Option<Rc<RefCell<Instance>>> {
+        match value {
+            Value::Instance(instance) if instance.borrow().class.is_subclass_of(&self.base) => {
+                Some(instance.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Текст исключения для сообщений об ошибках: для экземпляров ошибок —
+    /// `Класс: сообщение`
+    pub fn describe(&self, exception: &Value) -> String {
+        if let Some(instance) = self.error_instance(exception) {
+            let instance = instance.borrow();
+            return match instance.fields.get(MESSAGE_FIELD) {
+                Some(message) => format!("{}: {}", instance.class.name, message),
+                None => instance.class.name.clone(),
+            };
+        }
+        match exception {
+            Value::String(s) => s.to_string(),
+            // Структурированные ошибки стандартной библиотеки: {"тип", "сообщение", ...}
+            Value::Dict(dict) => {
+                let dict = dict.borrow();
+                let field = |name: &str| match dict.get(&ValueKey::String(name.to_string().into()))
+                {
+                    Some(Value::String(s)) => Some(s.clone()),
+                    _ => None,
+                };
+                match (field("тип"), field("сообщение")) {
+                    (Some(kind), Some(message)) => format!("{}: {}", kind, message),
+                    (None, Some(message)) => message.to_string(),
+                    _ => format!("{:?}", exception),
+                }
+            }
+            other => format!("{:?}", other),
+        }
+    }
+}
+
+fn empty_array() -> Value {
+    Value::Array(Rc::new(RefCell::new(Vec::new())))
+}
+
+// конструктор(сообщение): это.сообщение = сообщение; это.стек = [];
+fn constructor() -> Function {
+    Function {
+        arity: 1,
+        entry_points: Vec::new(),
+        variadic: false,
+        opcodes: vec![
+            OpCode::GetLocal(1),
+            OpCode::GetLocal(0),
+            OpCode::SetProperty(0),
+            OpCode::Pop,
+            OpCode::Array(0),
+            OpCode::GetLocal(0),
+            OpCode::SetProperty(1),
+            OpCode::Pop,
+            OpCode::GetLocal(0),
+            OpCode::Return_,
+        ],
+        constants: vec![Value::from(MESSAGE_FIELD), Value::from(STACK_FIELD)],
+        name: "конструктор".to_string(),
+        upvalue_count: 0,
+        upvalue_descriptors: Vec::new(),
+        source_name: None,
+        lines: LineTable::default(),
+    }
+}
@@ -0,0 +1,909 @@
+//! Форматирование исходного кода (`ponos fmt`).
+//!
+//! Код разбирается штатным парсером и печатается заново по AST: отступ в
+//! четыре пробела, по оператору на строке, пробелы вокруг бинарных
+//! операторов и минимум скобок. Комментарии парсер пропускает, поэтому они
+//! собираются отдельно и возвращаются перед ближайшим следующим оператором;
+//! пустые строки между операторами сохраняются (не более одной подряд).
+
+use crate::ponos::ast::*;
+use crate::ponos::parser::PonosParser;
+use crate::ponos::span::Span;
+
+const INDENT: &str = "    ";
+
+/// Отформатировать исходный код. Err — код не разобран; текст ошибки уже
+/// содержит подсветку места
+pub fn format_source(source: &str) -> Result<String, String> {
+    let program = parse(source)?;
+    let comments = collect_comments(source);
+
+    let mut printer = Printer {
+        source,
+        comments: &comments,
+        next_comment: 0,
+        out: String::new(),
+        indent: 0,
+        last_end: 0,
+        block_start: true,
+    };
+    printer.block(&program.statements, source.len());
+    let formatted = printer.out;
+
+    // Форматирование не должно менять смысл программы: сверяем AST без позиций
+    let reparsed =
+        parse(&formatted).map_err(|e| format!("Форматирование дало некорректный код: {}", e))?;
+    if strip_spans(&format!("{:?}", program.statements))
+        != strip_spans(&format!("{:?}", reparsed.statements))
+        || collect_comments(&formatted).len() != comments.len()
+    {
+        return Err("Форматирование изменило бы программу; файл оставлен как есть".to_string());
+    }
+    Ok(formatted)
+}
+
+fn parse(source: &str) -> Result<Program, String> {
+    PonosParser::new()
+        .parse(source.to_string())
+        .map_err(|e| e.format(source, "<fmt>"))
+}
+
+/// Убрать из отладочного вывода AST позиции `Span { start: .., end: .. }`
+fn strip_spans(debug: &str) -> String {
+    const MARKER: &str = "Span { start: ";
+    let mut result = String::with_capacity(debug.len());
+    let mut rest = debug;
+    while let Some(position) = rest.find(MARKER) {
+        result.push_str(&rest[..position]);
+        rest = &rest[position..];
+        rest = &rest[rest.find('}').map_or(rest.len(), |end| end + 1)..];
+    }
+    result.push_str(rest);
+    result
+}
+
+struct Comment {
+    span: Span,
+    /// Комментарий стоит в начале строки, а не после кода
+    own_line: bool,
+}
+
+/// Найти комментарии в исходном коде, пропуская строковые литералы
+fn collect_comments(source: &str) -> Vec<Comment> {
+    let bytes = source.as_bytes();
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(source.len(), |n| i + n);
+                comments.push(comment(source, i, end));
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..]
+                    .find("*/")
+                    .map_or(source.len(), |n| i + 2 + n + 2);
+                comments.push(comment(source, i, end));
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    comments
+}
+
+fn comment(source: &str, start: usize, end: usize) -> Comment {
+    let line_start = source[..start].rfind('\n').map_or(0, |n| n + 1);
+    Comment {
+        span: Span::new(start, end),
+        own_line: source[line_start..start].trim().is_empty(),
+    }
+}
+
+/// Начало оператора в исходном коде. У веток `иначе если` позиции нет,
+/// поэтому берётся начало условия
+fn statement_start(statement: &Statement) -> usize {
+    match statement {
+        Statement::If(s) if s.span == Span::default() => s.condition.span().start,
+        _ => statement_span(statement).start,
+    }
+}
+
+/// Позиция оператора вместе с аннотациями перед ним
+fn statement_span(statement: &Statement) -> Span {
+    let annotations = match statement {
+        Statement::FuncDecl(decl) => &decl.annotations,
+        Statement::ClassDecl(decl) => &decl.annotations,
+        _ => return statement.span(),
+    };
+    annotations
+        .first()
+        .map_or(statement.span(), |a| a.span.merge(statement.span()))
+}
+
+/// Уровень связывания выражения: чем больше, тем крепче
+fn binding(expr: &Expression) -> u8 {
+    match expr {
+        Expression::Lambda(_) => 0,
+        Expression::Binary(e) => e.operator.precedence(),
+        Expression::Unary(_) => 8,
+        _ => 9,
+    }
+}
+
+/// Строковый литерал для строки без исходного текста (путь импорта)
+fn quote(value: &str) -> String {
+    let mut text = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\n' => text.push_str("\\n"),
+            '\t' => text.push_str("\\t"),
+            '\r' => text.push_str("\\r"),
+            '\0' => text.push_str("\\0"),
+            '\\' => text.push_str("\\\\"),
+            '"' => text.push_str("\\\""),
+            c if c.is_control() => text.push_str(&format!("\\u{{{:X}}}", c as u32)),
+            _ => text.push(c),
+        }
+    }
+    text.push('"');
+    text
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    comments: &'a [Comment],
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    /// Конец последнего напечатанного оператора или комментария в исходнике
+    last_end: usize,
+    /// Ещё ничего не напечатано в текущем блоке
+    block_start: bool,
+}
+
+impl Printer<'_> {
+    /// Начать новую строку с текущим отступом
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+    }
+
+    /// Пустая строка, если она была в исходнике перед позицией `start`.
+    /// Оператор без точки с запятой захватывает в свою позицию переводы
+    /// строк после него, поэтому промежуток отсчитывается от конца кода
+    fn blank_line_before(&mut self, start: usize) {
+        let from = self.source[..self.last_end.min(start)].trim_end().len();
+        let gap = &self.source[from..start];
+        let mut lines = gap.split('\n');
+        lines.next();
+        lines.next_back();
+        if !self.block_start && lines.any(|line| line.trim().is_empty()) {
+            self.newline();
+        }
+    }
+
+    /// Напечатать комментарии, стоящие в исходнике до позиции `position`
+    fn comments_before(&mut self, position: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= position {
+                break;
+            }
+            self.next_comment += 1;
+            let text = comment.span.text(self.source).trim_end();
+            if !comment.own_line && self.out.ends_with('\n') {
+                // Комментарий после кода остаётся в конце той же строки
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(text);
+            } else {
+                self.blank_line_before(comment.span.start);
+                self.line(text);
+            }
+            self.newline();
+            self.last_end = comment.span.end;
+            self.block_start = false;
+        }
+    }
+
+    /// Напечатать блок операторов; `end` — позиция в исходнике, до которой
+    /// комментарии относятся к этому блоку
+    fn block(&mut self, statements: &[Statement], end: usize) {
+        self.block_start = true;
+        for statement in statements {
+            let start = statement_start(statement);
+            self.comments_before(start);
+            self.blank_line_before(start);
+            self.statement(statement);
+            self.last_end = self.last_end.max(statement_span(statement).end);
+            self.block_start = false;
+        }
+        self.comments_before(end);
+        self.block_start = false;
+    }
+
+    /// Блок с отступом, за которым идёт строка `конец`
+    fn indented_block(&mut self, statements: &[Statement], end: usize) {
+        self.indent += 1;
+        self.block(statements, end);
+        self.indent -= 1;
+    }
+
+    fn end(&mut self) {
+        self.line("конец");
+        self.newline();
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VarDecl(decl) => {
+                let keyword = if decl.is_const {
+                    "конст"
+                } else {
+                    "пер"
+                };
+                let mut text = format!("{}{} {}", export(decl.is_exported), keyword, decl.name);
+                if let Some(ty) = &decl.type_annotation {
+                    text.push_str(&format!(": {}", ty));
+                }
+                if let Some(value) = &decl.initializer {
+                    text.push_str(&format!(" = {}", self.expression(value)));
+                }
+                text.push(';');
+                self.line(&text);
+                self.newline();
+            }
+            Statement::FuncDecl(decl) => self.function(decl, "функ "),
+            Statement::ClassDecl(decl) => self.class(decl),
+            Statement::InterfaceDecl(decl) => {
+                self.line(&format!(
+                    "{}интерфейс {}",
+                    export(decl.is_exported),
+                    decl.name
+                ));
+                self.newline();
+                self.indent += 1;
+                for method in &decl.methods {
+                    let params = self.parameters(&method.params);
+                    let text = format!(
+                        "функ {}({}){};",
+                        method.name,
+                        params,
+                        return_type(&method.return_type)
+                    );
+                    self.line(&text);
+                    self.newline();
+                }
+                self.indent -= 1;
+                self.comments_before(decl.span.end);
+                self.end();
+            }
+            Statement::AnnotationDecl(decl) => {
+                self.line(&format!(
+                    "{}аннотация {}",
+                    export(decl.is_exported),
+                    decl.name
+                ));
+                self.newline();
+                self.indented_block(&decl.body, decl.span.end);
+                self.end();
+            }
+            Statement::Import(import) => {
+                let mut text = format!(
+                    "{}использовать {}",
+                    export(import.is_exported),
+                    quote(&import.path)
+                );
+                if let Some(alias) = &import.alias {
+                    text.push_str(&format!(" как {}", alias));
+                }
+                match &import.filter {
+                    ImportFilter::All => {}
+                    ImportFilter::Show(names) => {
+                        text.push_str(&format!(" показать {}", names.join(", ")))
+                    }
+                    ImportFilter::Hide(names) => {
+                        text.push_str(&format!(" скрыть {}", names.join(", ")))
+                    }
+                }
+                text.push(';');
+                self.line(&text);
+                self.newline();
+            }
+            Statement::ModuleBlock(module) => {
+                // Парсер таких блоков не создаёт: они появляются при загрузке модулей
+                for statement in &module.statements {
+                    self.statement(statement);
+                }
+            }
+            Statement::If(statement) => {
+                self.line("");
+                self.if_chain(statement);
+            }
+            Statement::While(statement) => {
+                let text = format!("пока {}", self.expression(&statement.condition));
+                self.line(&text);
+                self.newline();
+                self.indented_block(&statement.body, statement.span.end);
+                self.end();
+            }
+            Statement::ForEach(statement) => {
+                let index = match &statement.index_name {
+                    Some(index) => format!(", {}", index),
+                    None => String::new(),
+                };
+                let text = format!(
+                    "для каждого {}{} из {}",
+                    statement.element_name,
+                    index,
+                    self.expression(&statement.iterable)
+                );
+                self.line(&text);
+                self.newline();
+                self.indented_block(&statement.body, statement.span.end);
+                self.end();
+            }
+            Statement::Return(statement) => {
+                let text = match &statement.value {
+                    Some(value) => format!("возврат {};", self.expression(value)),
+                    None => "возврат;".to_string(),
+                };
+                self.line(&text);
+                self.newline();
+            }
+            Statement::Try(statement) => {
+                self.line("попытка");
+                self.newline();
+                let finally_start = statement
+                    .finally_body
+                    .as_ref()
+                    .and_then(|body| body.first())
+                    .map_or(statement.span.end, statement_start);
+                // Граница каждой ветки — начало следующей
+                let mut starts = vec![finally_start];
+                for clause in statement.catches.iter().rev() {
+                    let next = *starts.last().unwrap();
+                    starts.push(clause.body.first().map_or(next, statement_start));
+                }
+                starts.reverse();
+                self.indented_block(&statement.try_body, starts[0]);
+                for (clause, end) in statement.catches.iter().zip(&starts[1..]) {
+                    let header = match (&clause.var, &clause.class) {
+                        (Some(name), Some(class)) => {
+                            format!("перехват {}: {}", name, self.expression(class))
+                        }
+                        (Some(name), None) => format!("перехват {}", name),
+                        _ => "перехват".to_string(),
+                    };
+                    self.line(&header);
+                    self.newline();
+                    self.indented_block(&clause.body, *end);
+                }
+                if let Some(finally_body) = &statement.finally_body {
+                    self.line("наконец");
+                    self.newline();
+                    self.indented_block(finally_body, statement.span.end);
+                }
+                self.end();
+            }
+            Statement::Throw(statement) => {
+                let text = format!("исключение {};", self.expression(&statement.expression));
+                self.line(&text);
+                self.newline();
+            }
+            Statement::Assignment(statement) => {
+                let target = match &statement.target {
+                    AssignmentTarget::Identifier(name) => name.clone(),
+                    AssignmentTarget::FieldAccess(object, field) => {
+                        format!("{}.{}", self.operand(object), field)
+                    }
+                    AssignmentTarget::Index(object, index) => {
+                        format!("{}[{}]", self.operand(object), self.expression(index))
+                    }
+                };
+                let text = format!("{} = {};", target, self.expression(&statement.value));
+                self.line(&text);
+                self.newline();
+            }
+            Statement::Expression(expr) => {
+                let text = format!("{};", self.expression(expr));
+                self.line(&text);
+                self.newline();
+            }
+        }
+    }
+
+    /// `если ... иначе если ... иначе ... конец`; отступ строки уже напечатан
+    fn if_chain(&mut self, statement: &IfStatement) {
+        let text = format!("если {}", self.expression(&statement.condition));
+        self.out.push_str(&text);
+        self.newline();
+        let else_start = statement
+            .else_branch
+            .as_ref()
+            .and_then(|branch| branch.first())
+            .map_or(statement.span.end, statement_start);
+        self.indented_block(&statement.then_branch, else_start);
+        match statement.else_branch.as_deref() {
+            Some([Statement::If(nested)]) => {
+                self.line("иначе ");
+                self.if_chain(nested);
+                return;
+            }
+            Some(branch) => {
+                self.line("иначе");
+                self.newline();
+                self.indented_block(branch, statement.span.end);
+            }
+            None => {}
+        }
+        self.end();
+    }
+
+    fn function(&mut self, decl: &FuncDecl, keyword: &str) {
+        for annotation in &decl.annotations {
+            let text = self.annotation(annotation);
+            self.line(&text);
+            self.newline();
+        }
+        let params = self.parameters(&decl.params);
+        self.line(&format!(
+            "{}{}{}({}){}",
+            export(decl.is_exported),
+            keyword,
+            decl.name,
+            params,
+            return_type(&decl.return_type)
+        ));
+        self.newline();
+        self.indented_block(&decl.body, decl.span.end);
+        self.end();
+    }
+
+    fn annotation(&mut self, annotation: &Annotation) -> String {
+        if annotation.args.is_empty() {
+            return format!("@{}", annotation.name);
+        }
+        let args: Vec<String> = annotation
+            .args
+            .iter()
+            .map(|arg| match arg {
+                AnnotationArgument::Positional(value) => self.expression(value),
+                AnnotationArgument::Named { name, value } => {
+                    format!("{} = {}", name, self.expression(value))
+                }
+            })
+            .collect();
+        format!("@{}({})", annotation.name, args.join(", "))
+    }
+
+    fn class(&mut self, decl: &ClassDecl) {
+        for annotation in &decl.annotations {
+            let text = self.annotation(annotation);
+            self.line(&text);
+            self.newline();
+        }
+        let mut header = format!("{}класс {}", export(decl.is_exported), decl.name);
+        if let Some(parent) = &decl.extends {
+            header.push_str(&format!(" наследует {}", parent));
+        }
+        if !decl.implements.is_empty() {
+            header.push_str(&format!(" реализует {}", decl.implements.join(", ")));
+        }
+        self.line(&header);
+        self.newline();
+
+        self.indent += 1;
+        self.block_start = true;
+        for member in &decl.members {
+            match member {
+                ClassMember::Field {
+                    name,
+                    type_annotation,
+                } => {
+                    let text = match type_annotation {
+                        Some(ty) => format!("{}: {}", name, ty),
+                        None => name.clone(),
+                    };
+                    self.line(&text);
+                    self.newline();
+                }
+                ClassMember::StaticField {
+                    name,
+                    type_annotation,
+                    initializer,
+                } => {
+                    let mut text = format!("статический {}", name);
+                    if let Some(ty) = type_annotation {
+                        text.push_str(&format!(": {}", ty));
+                    }
+                    if let Some(value) = initializer {
+                        text.push_str(&format!(" = {}", self.expression(value)));
+                    }
+                    text.push(';');
+                    self.line(&text);
+                    self.newline();
+                }
+                ClassMember::Method(method) => {
+                    self.comments_before(method.span.start);
+                    self.blank_line_before(method.span.start);
+                    self.function(method, "функ ");
+                    self.last_end = method.span.end;
+                }
+                ClassMember::StaticMethod(method) => {
+                    self.comments_before(method.span.start);
+                    self.blank_line_before(method.span.start);
+                    self.function(method, "статический функ ");
+                    self.last_end = method.span.end;
+                }
+                ClassMember::Constructor(constructor) => {
+                    self.comments_before(constructor.span.start);
+                    self.blank_line_before(constructor.span.start);
+                    let params = self.parameters(&constructor.params);
+                    self.line(&format!("конструктор({})", params));
+                    self.newline();
+                    self.indented_block(&constructor.body, constructor.span.end);
+                    self.end();
+                    self.last_end = constructor.span.end;
+                }
+            }
+            self.block_start = false;
+        }
+        self.comments_before(decl.span.end);
+        self.indent -= 1;
+        self.end();
+    }
+
+    /// Выражение как операнд постфиксной операции: `(а + б).поле`
+    fn operand(&mut self, expr: &Expression) -> String {
+        let text = self.expression(expr);
+        if binding(expr) < 8 {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) -> String {
+        match expr {
+            Expression::Integer(_, span)
+            | Expression::Number(_, span)
+            | Expression::String(_, span) => span.text(self.source).to_string(),
+            Expression::Boolean(true, _) => "истина".to_string(),
+            Expression::Boolean(false, _) => "ложь".to_string(),
+            Expression::Nil(_) => "ничто".to_string(),
+            Expression::Identifier(name, _) => name.clone(),
+            Expression::Binary(binary) => {
+                let precedence = binary.operator.precedence();
+                let mut left = self.expression(&binary.left);
+                if binding(&binary.left) < precedence {
+                    left = format!("({})", left);
+                }
+                let mut right = self.expression(&binary.right);
+                if binding(&binary.right) <= precedence {
+                    right = format!("({})", right);
+                }
+                format!("{} {} {}", left, binary.operator.symbol(), right)
+            }
+            Expression::Unary(unary) => {
+                let mut operand = self.expression(&unary.operand);
+                if binding(&unary.operand) < 8 {
+                    operand = format!("({})", operand);
+                }
+                format!("{}{}", unary.operator.symbol(), operand)
+            }
+            Expression::Call(call) => {
+                let callee = self.operand(&call.callee);
+                let arguments = self.list(&call.arguments);
+                format!("{}({})", callee, arguments)
+            }
+            Expression::FieldAccess(access) => {
+                format!("{}.{}", self.operand(&access.object), access.field)
+            }
+            Expression::ModuleAccess(access) => {
+                format!("{}.{}", access.namespace, access.symbol)
+            }
+            Expression::Lambda(lambda) => self.lambda(lambda),
+            Expression::Index(index) => {
+                let object = self.operand(&index.object);
+                let index = self.expression(&index.index);
+                format!("{}[{}]", object, index)
+            }
+            Expression::Range(range) => {
+                let start = match &range.start {
+                    Some(start) => self.expression(start),
+                    None => String::new(),
+                };
+                let end = match &range.end {
+                    Some(end) => self.expression(end),
+                    None => String::new(),
+                };
+                format!("{}:{}", start, end)
+            }
+            Expression::ArrayLiteral(array) => {
+                let first = array.elements.first().map(Expression::span);
+                let multiline = self.starts_on_new_line(array.span, first);
+                self.layout(multiline, '[', ']', |printer| {
+                    array
+                        .elements
+                        .iter()
+                        .map(|e| printer.expression(e))
+                        .collect()
+                })
+            }
+            Expression::DictLiteral(dict) => {
+                let first = dict.pairs.first().map(|(key, _)| key.span());
+                let multiline = self.starts_on_new_line(dict.span, first);
+                self.layout(multiline, '{', '}', |printer| {
+                    dict.pairs
+                        .iter()
+                        .map(|(key, value)| {
+                            format!("{}: {}", printer.expression(key), printer.expression(value))
+                        })
+                        .collect()
+                })
+            }
+            Expression::This(_) => "это".to_string(),
+            Expression::Super(method, _) => format!("родитель.{}", method),
+            Expression::Spread(value, _) => format!("...{}", self.expression(value)),
+        }
+    }
+
+    /// Первый элемент коллекции стоял в исходнике на новой строке
+    fn starts_on_new_line(&self, literal: Span, first: Option<Span>) -> bool {
+        first.is_some_and(|first| {
+            self.source[literal.start..first.start.max(literal.start)].contains('\n')
+        })
+    }
+
+    /// Коллекция в строку или, если так было в исходнике, по элементу на строке
+    fn layout(
+        &mut self,
+        multiline: bool,
+        open: char,
+        close: char,
+        items: impl FnOnce(&mut Self) -> Vec<String>,
+    ) -> String {
+        if !multiline {
+            return format!("{}{}{}", open, items(self).join(", "), close);
+        }
+        self.indent += 1;
+        let items = items(self);
+        let item_indent = INDENT.repeat(self.indent);
+        self.indent -= 1;
+
+        let mut text = String::from(open);
+        for (i, item) in items.iter().enumerate() {
+            text.push('\n');
+            text.push_str(&item_indent);
+            text.push_str(item);
+            if i + 1 < items.len() {
+                text.push(',');
+            }
+        }
+        text.push('\n');
+        text.push_str(&INDENT.repeat(self.indent));
+        text.push(close);
+        text
+    }
+
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<String> = items.iter().map(|item| self.expression(item)).collect();
+        items.join(", ")
+    }
+
+    fn parameters(&mut self, params: &[Parameter]) -> String {
+        let mut texts = Vec::new();
+        for param in params {
+            let mut text = if param.is_rest {
+                "...".to_string()
+            } else {
+                String::new()
+            };
+            text.push_str(&param.name);
+            if let Some(ty) = &param.type_annotation {
+                text.push_str(&format!(": {}", ty));
+            }
+            if let Some(default) = &param.default {
+                text.push_str(" = ");
+                text.push_str(&self.expression(default));
+            }
+            texts.push(text);
+        }
+        texts.join(", ")
+    }
+
+    /// Лямбда из одного оператора без комментариев печатается в строку,
+    /// остальные — блоком с отступом относительно текущей строки
+    fn lambda(&mut self, lambda: &LambdaExpr) -> String {
+        let params = self.parameters(&lambda.params);
+        let header = format!("функ({}){}", params, return_type(&lambda.return_type));
+        let has_comments = self.comments[self.next_comment..]
+            .iter()
+            .any(|c| c.span.start < lambda.span.end);
+        match lambda.body.as_slice() {
+            [] if !has_comments => return format!("{} конец", header),
+            [
+                statement @ (Statement::Return(_) | Statement::Expression(_) | Statement::Throw(_)),
+            ] if !has_comments => {
+                let mut inline = Printer {
+                    source: self.source,
+                    comments: &[],
+                    next_comment: 0,
+                    out: String::new(),
+                    indent: 0,
+                    last_end: 0,
+                    block_start: true,
+                };
+                inline.statement(statement);
+                if !inline.out.trim_end().contains('\n') {
+                    return format!("{} {} конец", header, inline.out.trim_end());
+                }
+            }
+            _ => {}
+        }
+
+        // Тело печатается в отдельный буфер и вставляется в текущую строку
+        let outer = std::mem::take(&mut self.out);
+        let (last_end, block_start) = (self.last_end, self.block_start);
+        self.indented_block(&lambda.body, lambda.span.end);
+        let body = std::mem::replace(&mut self.out, outer);
+        self.last_end = last_end.max(lambda.span.end);
+        self.block_start = block_start;
+
+        let mut text = header;
+        text.push('\n');
+        text.push_str(&body);
+        text.push_str(&INDENT.repeat(self.indent));
+        text.push_str("конец");
+        text
+    }
+}
+
+fn export(is_exported: bool) -> &'static str {
+    if is_exported { "экспорт " } else { "" }
+}
+
+fn return_type(return_type: &Option<String>) -> String {
+    match return_type {
+        Some(ty) => format!(": {}", ty),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_indentation_and_operators() {
+        assert!(format_source("пер = ;").is_err());
+
+        let source = "\
+функ f(а:число,б):число
+если а>б  возврат (а-б)*2;
+  иначе если а==б
+возврат -(а+б);
+иначе
+ возврат а-(б-1);конец
+конец
+пока x<3 x=x+1; конец
+";
+        let expected = "\
+функ f(а: число, б): число
+    если а > б
+        возврат (а - б) * 2;
+    иначе если а == б
+        возврат -(а + б);
+    иначе
+        возврат а - (б - 1);
+    конец
+конец
+пока x < 3
+    x = x + 1;
+конец
+";
+        assert_eq!(format_source(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_preserves_comments_and_blank_lines() {
+        let source = "\
+// заголовок
+пер а = 1; // после кода
+
+
+/* блок */
+функ f()
+    вывести(а);
+    // перед концом
+конец
+";
+        let expected = "\
+// заголовок
+пер а = 1; // после кода
+
+/* блок */
+функ f()
+    вывести(а);
+    // перед концом
+конец
+";
+        assert_eq!(format_source(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_keeps_blank_lines_after_statements_without_semicolons() {
+        let source = "использовать \"стд/тест\" как тест
+
+функ f()
+    пер а = 1
+
+    возврат а
+конец
+";
+        let expected = "использовать \"стд/тест\" как тест;
+
+функ f()
+    пер а = 1;
+
+    возврат а;
+конец
+";
+        assert_eq!(format_source(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_preserves_string_escapes() {
+        // `\"` не закрывает строку, поэтому `//` внутри неё — не комментарий
+        let source = "вывести(\"\\u{1F600} \\x41 \\\" // не комментарий\");\n";
+        assert_eq!(format_source(source).unwrap(), source);
+        assert_eq!(quote("а\u{7}б"), "\"а\\u{7}б\"");
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent() {
+        let source = "\
+использовать \"стд/тест\" как т показать утверждать;
+@Тест(имя = \"сложение\")
+функ тест_сложения()
+    применить(функ(а,б) возврат а+б; конец);
+    применить(функ(x)
+        пер y = x*2;
+        возврат y;
+    конец, 1);
+    попытка исключение \"ошибка\"; перехват е вывести(е); конец
+    для каждого э, и из [1, 2][0:] вывести({\"к\": э}[\"к\"], !истина); конец
+конец
+класс Б наследует А реализует И
+x: число
+конструктор(x) это.x = x; конец
+функ м() возврат родитель.м(); конец
+конец
+";
+        let once = format_source(source).unwrap();
+        assert_eq!(format_source(&once).unwrap(), once);
+        assert!(
+            once.contains("    применить(функ(а, б) возврат а + б; конец);\n"),
+            "{}",
+            once
+        );
+        assert!(
+            once.contains("    применить(функ(x)\n        пер y = x * 2;\n        возврат y;\n    конец, 1);\n"),
+            "{}",
+            once
+        );
+    }
+}
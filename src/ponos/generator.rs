@@ -1,24 +1,135 @@
-pub struct Generator {}
+pub struct Generator {
+    /// Имена, импортированные без префикса (`показать`): имя -> манглированное имя
+    imports: HashMap<String, String>,
+    /// Манглированные имена глобальных констант всех скомпилированных
+    /// фрагментов: семантическая проверка видит только текущую программу,
+    /// а присваивание константе из прошлого запуска (REPL) ловится здесь
+    const_globals: HashSet<String>,
+    /// Строки констант всех функций: одинаковые литералы и имена разделяют
+    /// один буфер
+    strings: HashMap<String, Rc<String>>,
+}
 
-use crate::ponos::ast::{Parameter, UnaryOperator};
-use crate::ponos::value::{Function, UpvalueDescriptor};
+use crate::ponos::ast::{BinaryOperator, FuncDecl, ModuleBlock, Parameter, UnaryOperator};
+use crate::ponos::value::{Closure, Function, UpvalueDescriptor};
 
-use super::ast::{AssignmentTarget, AstNode, ClassMember, Expression, Statement};
-use super::opcode::OpCode;
+use super::ast::{
+    AssignmentTarget, AstNode, CatchClause, ClassMember, Expression, Statement, TryStatement,
+};
+use super::opcode::{LineTable, OpCode, operand};
+use super::parser::{ParseErrorKind, PonosParseError};
+use super::span::{LineIndex, Span};
 use super::value::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+/// Ошибка генерации кода: конструкция, которую нельзя скомпилировать
+/// в данном месте программы
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl CodegenError {
+    fn new(span: Span, message: &str) -> Self {
+        CodegenError {
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    /// Форматирует ошибку с подсветкой исходного кода, как ошибки парсинга
+    pub fn format(&self, source: &str, filename: &str) -> String {
+        PonosParseError::new(ParseErrorKind::Custom(self.message.clone()), self.span)
+            .format(source, filename)
+    }
+}
+
+type CodegenResult<T = ()> = Result<T, CodegenError>;
+
+/// Имя локальной переменной с экземпляром в методах
+const THIS: &str = "это";
+
 #[derive(Clone)]
 pub struct GenContext {
     pub constants: Vec<Value>,
     pub opcodes: Vec<OpCode>,
     pub current_namespace: Option<String>, // Префикс для манглинга имен (было current_module)
+    pub source_name: Option<Rc<str>>,      // Файл компилируемого кода (для диагностики)
+    pub lines: LineTable,                  // Строки исходного кода для opcodes
     pub in_function: bool,
+    line_index: Rc<LineIndex>,
     local_slots: HashMap<String, usize>,
     next_local_slot: usize,
     parent_context: Option<Box<GenContext>>,
     upvalues: Vec<UpvalueInfo>,
+    handler_depth: usize, // Сколько обработчиков исключений установлено в функции
+    finally_scopes: Vec<FinallyScope>, // Блоки `наконец`, внутри которых идёт генерация
+}
+
+/// Блок `наконец`, который нужно выполнить перед `возврат` из его `попытка`
+#[derive(Clone)]
+struct FinallyScope {
+    body: Vec<Statement>,
+    handler_depth: usize, // Глубина обработчиков вне попытки
+}
+
+/// Отдельно скомпилированный модуль: собственный байткод и таблица экспортов.
+/// Связывание с остальной программой происходит по манглированным именам
+/// при загрузке в VM.
+pub struct ModuleUnit {
+    pub namespace: String,
+    pub file_path: Option<PathBuf>,
+    pub source: Rc<str>,
+    pub opcodes: Vec<OpCode>,
+    pub constants: Vec<Value>,
+    pub lines: LineTable,
+    pub exports: Vec<String>, // Манглированные имена экспортируемых символов
+    pub lazy_functions: Vec<LazyFunction>,
+}
+
+/// Функция верхнего уровня модуля, которая компилируется только при первом обращении
+pub struct LazyFunction {
+    pub mangled_name: String,
+    namespace: String,
+    source_name: Option<Rc<str>>,
+    line_index: Rc<LineIndex>,
+    decl: FuncDecl,
+}
+
+impl LazyFunction {
+    /// Скомпилировать функцию и обернуть ее в замыкание, как это сделал бы
+    /// OpCode::Closure (у функций верхнего уровня нет upvalues)
+    pub fn materialize(self) -> CodegenResult<Value> {
+        let mut generator = Generator::new();
+        let mut ctx = generator.make_context(false);
+        ctx.current_namespace = Some(self.namespace);
+        ctx.source_name = self.source_name;
+        ctx.line_index = self.line_index;
+
+        let function = match generator.compile_function(
+            &self.decl.name,
+            &self.decl.params,
+            &self.decl.body,
+            &mut ctx,
+            false,
+        )? {
+            Value::Function(f) => f,
+            other => unreachable!("compile_function вернула {:?}", other),
+        };
+
+        Ok(Value::Closure(Rc::new(Closure {
+            function,
+            upvalues: Vec::new(),
+        })))
+    }
+}
+
+/// Имя файла для диагностики
+fn source_name(path: &Path) -> Rc<str> {
+    Rc::from(path.display().to_string())
 }
 
 #[derive(Clone)]
@@ -28,105 +139,215 @@ struct UpvalueInfo {
     index: usize,
 }
 
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Generator {
     pub fn new() -> Self {
-        Generator {}
+        Generator {
+            imports: HashMap::new(),
+            const_globals: HashSet::new(),
+            strings: HashMap::new(),
+        }
+    }
+
+    /// Разрешить обращаться к символу модуля по имени без префикса
+    pub fn import_name(&mut self, name: &str, mangled_name: String) {
+        self.imports.insert(name.to_string(), mangled_name);
     }
 
-    pub fn generate(&mut self, node: AstNode) -> GenContext {
+    pub fn generate(&mut self, node: AstNode) -> CodegenResult<GenContext> {
         let mut context = self.make_context(false);
         match node {
             AstNode::Program(program) => {
-                for stmt in program.statements {
-                    self.emit_statement(stmt, &mut context);
+                context.source_name = program.file_path.as_deref().map(source_name);
+                context.line_index = Rc::new(LineIndex::new(&program.source));
+                let mut statements = program.statements;
+                // Значение последнего выражения программы остаётся на стеке:
+                // это её результат
+                let result = match statements.last() {
+                    Some(Statement::Expression(_)) => statements.pop(),
+                    _ => None,
+                };
+                for stmt in statements {
+                    self.emit_statement(stmt, &mut context)?;
+                }
+                if let Some(Statement::Expression(e)) = result {
+                    self.emit_expression(e, &mut context)?;
                 }
             }
         }
         context.opcodes.push(OpCode::Halt);
-        context
+        Ok(context)
     }
 
-        fn make_context(&self, in_function: bool) -> GenContext {
+    /// Скомпилировать модуль в отдельную единицу компиляции
+    pub fn generate_module(&mut self, module_block: ModuleBlock) -> CodegenResult<ModuleUnit> {
+        let mut ctx = self.make_context(false);
+        ctx.current_namespace = Some(module_block.namespace.clone());
+        ctx.source_name = module_block.file_path.as_deref().map(source_name);
+        ctx.line_index = Rc::new(LineIndex::new(&module_block.source));
+
+        let mut exports = Vec::new();
+        let mut lazy_functions = Vec::new();
+
+        for stmt in module_block.statements {
+            let exported_name = match &stmt {
+                Statement::VarDecl(decl) if decl.is_exported => Some(&decl.name),
+                Statement::FuncDecl(decl) if decl.is_exported => Some(&decl.name),
+                Statement::ClassDecl(decl) if decl.is_exported => Some(&decl.name),
+                _ => None,
+            };
+            if let Some(name) = exported_name {
+                exports.push(self.mangle_name(name, &ctx));
+            }
+
+            match stmt {
+                Statement::FuncDecl(decl) => lazy_functions.push(LazyFunction {
+                    mangled_name: self.mangle_name(&decl.name, &ctx),
+                    namespace: module_block.namespace.clone(),
+                    source_name: ctx.source_name.clone(),
+                    line_index: ctx.line_index.clone(),
+                    decl,
+                }),
+                other => self.emit_statement(other, &mut ctx)?,
+            }
+        }
+        ctx.opcodes.push(OpCode::Halt);
+
+        Ok(ModuleUnit {
+            namespace: module_block.namespace,
+            file_path: module_block.file_path,
+            source: module_block.source,
+            opcodes: ctx.opcodes,
+            constants: ctx.constants,
+            lines: ctx.lines,
+            exports,
+            lazy_functions,
+        })
+    }
+
+    fn make_context(&self, in_function: bool) -> GenContext {
         GenContext {
             constants: Vec::new(),
             opcodes: Vec::new(),
             current_namespace: None,
+            source_name: None,
+            lines: LineTable::default(),
             in_function,
+            line_index: Rc::default(),
             local_slots: HashMap::new(),
             next_local_slot: 0,
             parent_context: None,
             upvalues: Vec::new(),
+            handler_depth: 0,
+            finally_scopes: Vec::new(),
         }
     }
 
-    fn emit_statement(&mut self, stmt: Statement, ctx: &mut GenContext) {
+    /// Следующие инструкции относятся к строке, где начинается `span`.
+    /// У узлов, построенных без исходного кода, позиции нет
+    fn mark_line(&self, span: Span, ctx: &mut GenContext) {
+        if span != Span::default() {
+            let line = ctx.line_index.line(span.start);
+            ctx.lines.mark(ctx.opcodes.len(), line);
+        }
+    }
+
+    fn emit_statement(&mut self, stmt: Statement, ctx: &mut GenContext) -> CodegenResult {
+        self.mark_line(stmt.span(), ctx);
         match stmt {
             Statement::VarDecl(var_decl) => {
                 if ctx.in_function {
                     if var_decl.is_exported {
-                        panic!("Нельзя экспортировать переменную внутри функции");
+                        return Err(CodegenError::new(
+                            var_decl.span,
+                            "Нельзя экспортировать переменную внутри функции",
+                        ));
                     }
-                    self.emit_local_var_decl(var_decl.name, var_decl.initializer, ctx);
+                    self.emit_local_var_decl(var_decl.name, var_decl.initializer, ctx)?;
                 } else {
                     // Генерируем значение
                     if let Some(init) = var_decl.initializer {
-                        self.emit_expression(init, ctx);
+                        self.emit_expression(init, ctx)?;
                     } else {
                         let idx = self.intern_constant(Value::Nil, ctx);
-                        ctx.opcodes.push(OpCode::Constant(idx));
+                        ctx.opcodes.push(OpCode::Constant(operand(idx)));
                     }
 
                     // Применяем манглинг имени если есть пространство имен
                     let mangled_name = self.mangle_name(&var_decl.name, ctx);
                     let name_idx = self.intern_string(&mangled_name, ctx);
-                    ctx.opcodes.push(OpCode::DefineGlobal(name_idx));
+                    ctx.opcodes.push(OpCode::DefineGlobal(operand(name_idx)));
+                    if var_decl.is_const {
+                        self.const_globals.insert(mangled_name);
+                    } else {
+                        self.const_globals.remove(&mangled_name);
+                    }
 
                     // ExportSymbol больше не нужен - экспорты обрабатываются на этапе разрешения имен
                 }
             }
             Statement::Assignment(assign) => {
                 // Для Index нужен особый порядок, поэтому проверяем заранее
-                match &assign.target {
-                    AssignmentTarget::Index(object, index) => {
-                        // SetIndex ожидает на стеке: object (низ), index, value (верх)
-                        self.emit_expression((**object).clone(), ctx);
-                        self.emit_expression((**index).clone(), ctx);
-                        self.emit_expression(assign.value, ctx);
-                        ctx.opcodes.push(OpCode::SetIndex);
-                        return;
+                if let AssignmentTarget::Index(object, index) = &assign.target {
+                    // SetIndex ожидает на стеке: object (низ), index, value (верх)
+                    self.emit_expression((**object).clone(), ctx)?;
+                    self.emit_expression((**index).clone(), ctx)?;
+                    self.emit_expression(assign.value, ctx)?;
+                    ctx.opcodes.push(OpCode::SetIndex);
+                    return Ok(());
+                }
+
+                // x = x + значение: строка дописывается в переменную на месте,
+                // а не копируется при каждом сложении
+                if let AssignmentTarget::Identifier(name) = &assign.target
+                    && let Some(appended) = appended_value(name, &assign.value)
+                {
+                    if let Some(&slot) = ctx.local_slots.get(name) {
+                        self.emit_expression(appended.clone(), ctx)?;
+                        ctx.opcodes.push(OpCode::AppendLocal(operand(slot)));
+                        return Ok(());
+                    }
+                    if !ctx.in_function || self.resolve_upvalue(name, ctx).is_none() {
+                        let mangled_name = self.global_name(name, ctx);
+                        if !self.const_globals.contains(&mangled_name) {
+                            self.emit_expression(appended.clone(), ctx)?;
+                            let name_idx = self.intern_string(&mangled_name, ctx);
+                            ctx.opcodes.push(OpCode::AppendGlobal(operand(name_idx)));
+                            return Ok(());
+                        }
                     }
-                    _ => {}
                 }
 
                 // Для остальных случаев сначала значение
-                self.emit_expression(assign.value, ctx);
+                self.emit_expression(assign.value, ctx)?;
                 match assign.target {
                     AssignmentTarget::Identifier(name) => {
                         if let Some(slot) = ctx.local_slots.get(&name) {
-                            ctx.opcodes.push(OpCode::SetLocal(*slot));
+                            ctx.opcodes.push(OpCode::SetLocal(operand(*slot)));
                         } else if ctx.in_function {
                             if let Some(upvalue_idx) = self.resolve_upvalue(&name, ctx) {
-                                ctx.opcodes.push(OpCode::SetUpvalue(upvalue_idx));
+                                ctx.opcodes.push(OpCode::SetUpvalue(operand(upvalue_idx)));
                             } else {
-                                let mangled_name = self.mangle_name(&name, ctx);
-                                let name_idx = self.intern_string(&mangled_name, ctx);
-                                ctx.opcodes.push(OpCode::SetGlobal(name_idx));
+                                self.emit_set_global(&name, assign.span, ctx)?;
                             }
                         } else {
-                            let mangled_name = self.mangle_name(&name, ctx);
-                            let name_idx = self.intern_string(&mangled_name, ctx);
-                            ctx.opcodes.push(OpCode::SetGlobal(name_idx));
+                            self.emit_set_global(&name, assign.span, ctx)?;
                         }
                     }
                     AssignmentTarget::FieldAccess(object, field) => {
                         // Значение уже на стеке (строка 111)
                         // Вычислить объект
-                        self.emit_expression((*object).clone(), ctx);
+                        self.emit_expression((*object).clone(), ctx)?;
 
                         // Установить поле
                         let field_name_idx = self.intern_string(&field, ctx);
-                        ctx.opcodes.push(OpCode::SetProperty);
-                        ctx.opcodes.push(OpCode::Constant(field_name_idx));
+                        ctx.opcodes
+                            .push(OpCode::SetProperty(operand(field_name_idx)));
                     }
                     AssignmentTarget::Index(_, _) => {
                         // Уже обработано выше (строки 99-106)
@@ -134,7 +355,12 @@ impl Generator {
                     }
                 }
             }
-            Statement::Expression(e) => self.emit_expression(e, ctx),
+            Statement::Expression(e) => {
+                // Значение выражения-оператора не нужно: иначе каждый проход
+                // цикла оставлял бы его на стеке
+                self.emit_expression(e, ctx)?;
+                ctx.opcodes.push(OpCode::Pop);
+            }
             Statement::ModuleBlock(module_block) => {
                 // Сохраняем текущее пространство имен
                 let previous_namespace = ctx.current_namespace.clone();
@@ -144,67 +370,68 @@ impl Generator {
 
                 // Генерируем код для всех statements модуля
                 for stmt in module_block.statements {
-                    self.emit_statement(stmt, ctx);
+                    self.emit_statement(stmt, ctx)?;
                 }
 
                 // Восстанавливаем предыдущее пространство имен
                 ctx.current_namespace = previous_namespace;
             }
             Statement::If(if_statement) => {
-                self.emit_expression(if_statement.condition, ctx);
+                self.emit_expression(if_statement.condition, ctx)?;
                 let jmp_false = self.emit_jump(ctx, OpCode::JumpIfFalse(0));
                 for stmt in if_statement.then_branch {
-                    self.emit_statement(stmt, ctx);
+                    self.emit_statement(stmt, ctx)?;
                 }
 
                 let jmp_end = self.emit_jump(ctx, OpCode::Jump(0));
                 self.patch_jump(ctx, jmp_false);
-                match if_statement.else_branch {
-                    Some(block) => {
-                        for stmt in block {
-                            self.emit_statement(stmt, ctx);
-                        }
+                if let Some(block) = if_statement.else_branch {
+                    for stmt in block {
+                        self.emit_statement(stmt, ctx)?;
                     }
-                    None => {}
                 }
                 self.patch_jump(ctx, jmp_end);
             }
             Statement::While(while_statement) => {
                 let cond_pos = ctx.opcodes.len();
-                self.emit_expression(while_statement.condition, ctx);
+                self.emit_expression(while_statement.condition, ctx)?;
                 let jmp_false = self.emit_jump(ctx, OpCode::JumpIfFalse(0));
                 for stmt in while_statement.body {
-                    self.emit_statement(stmt, ctx);
+                    self.emit_statement(stmt, ctx)?;
                 }
-                ctx.opcodes.push(OpCode::Jump(cond_pos));
+                ctx.opcodes.push(OpCode::Jump(operand(cond_pos)));
                 self.patch_jump(ctx, jmp_false);
             }
             Statement::ForEach(foreach_stmt) => {
                 if !ctx.in_function {
-                    panic!("'для каждого' может использоваться только внутри функции");
+                    return Err(CodegenError::new(
+                        foreach_stmt.span,
+                        "'для каждого' может использоваться только внутри функции",
+                    ));
                 }
 
                 // 1. Вычислить коллекцию и сохранить в __iter
-                self.emit_expression(foreach_stmt.iterable, ctx);
+                self.emit_expression(foreach_stmt.iterable, ctx)?;
+                ctx.opcodes.push(OpCode::Iterable);
                 let iter_slot = ctx.next_local_slot;
                 ctx.next_local_slot += 1;
                 ctx.local_slots.insert("__iter".to_string(), iter_slot);
-                ctx.opcodes.push(OpCode::DefineLocal(iter_slot));
+                ctx.opcodes.push(OpCode::DefineLocal(operand(iter_slot)));
 
                 // 2. Получить длину коллекции через встроенную функцию длина()
                 let len_name = self.intern_string("длина", ctx);
-                ctx.opcodes.push(OpCode::GetGlobal(len_name));
-                ctx.opcodes.push(OpCode::GetLocal(iter_slot));
+                ctx.opcodes.push(OpCode::GetGlobal(operand(len_name)));
+                ctx.opcodes.push(OpCode::GetLocal(operand(iter_slot)));
                 ctx.opcodes.push(OpCode::Call(1));
 
                 let len_slot = ctx.next_local_slot;
                 ctx.next_local_slot += 1;
                 ctx.local_slots.insert("__len".to_string(), len_slot);
-                ctx.opcodes.push(OpCode::DefineLocal(len_slot));
+                ctx.opcodes.push(OpCode::DefineLocal(operand(len_slot)));
 
                 // 3. Инициализировать счетчик индекса = 0
                 let zero_idx = self.intern_constant(Value::Number(0.0), ctx);
-                ctx.opcodes.push(OpCode::Constant(zero_idx));
+                ctx.opcodes.push(OpCode::Constant(operand(zero_idx)));
 
                 let index_slot = ctx.next_local_slot;
                 ctx.next_local_slot += 1;
@@ -213,7 +440,7 @@ impl Generator {
                 } else {
                     ctx.local_slots.insert("__index".to_string(), index_slot);
                 }
-                ctx.opcodes.push(OpCode::DefineLocal(index_slot));
+                ctx.opcodes.push(OpCode::DefineLocal(operand(index_slot)));
 
                 // 3.5. Создать слот для элемента (но не инициализировать)
                 let element_slot = ctx.next_local_slot;
@@ -221,37 +448,37 @@ impl Generator {
                 ctx.local_slots
                     .insert(foreach_stmt.element_name.clone(), element_slot);
                 let nil_idx = self.intern_constant(Value::Nil, ctx);
-                ctx.opcodes.push(OpCode::Constant(nil_idx));
-                ctx.opcodes.push(OpCode::DefineLocal(element_slot));
+                ctx.opcodes.push(OpCode::Constant(operand(nil_idx)));
+                ctx.opcodes.push(OpCode::DefineLocal(operand(element_slot)));
 
                 // 4. Начало цикла: индекс < __len
                 let loop_start = ctx.opcodes.len();
-                ctx.opcodes.push(OpCode::GetLocal(index_slot));
-                ctx.opcodes.push(OpCode::GetLocal(len_slot));
+                ctx.opcodes.push(OpCode::GetLocal(operand(index_slot)));
+                ctx.opcodes.push(OpCode::GetLocal(operand(len_slot)));
                 ctx.opcodes.push(OpCode::Less);
 
                 let exit_jump = self.emit_jump(ctx, OpCode::JumpIfFalse(0));
 
                 // 5. Получить текущий элемент: элемент = __iter[индекс]
-                ctx.opcodes.push(OpCode::GetLocal(iter_slot));
-                ctx.opcodes.push(OpCode::GetLocal(index_slot));
+                ctx.opcodes.push(OpCode::GetLocal(operand(iter_slot)));
+                ctx.opcodes.push(OpCode::GetLocal(operand(index_slot)));
                 ctx.opcodes.push(OpCode::GetIndex);
-                ctx.opcodes.push(OpCode::SetLocal(element_slot));
+                ctx.opcodes.push(OpCode::SetLocal(operand(element_slot)));
 
                 // 6. Выполнить тело цикла
                 for stmt in foreach_stmt.body {
-                    self.emit_statement(stmt, ctx);
+                    self.emit_statement(stmt, ctx)?;
                 }
 
                 // 7. Инкремент индекса: индекс = индекс + 1
-                ctx.opcodes.push(OpCode::GetLocal(index_slot));
+                ctx.opcodes.push(OpCode::GetLocal(operand(index_slot)));
                 let one_idx = self.intern_constant(Value::Number(1.0), ctx);
-                ctx.opcodes.push(OpCode::Constant(one_idx));
+                ctx.opcodes.push(OpCode::Constant(operand(one_idx)));
                 ctx.opcodes.push(OpCode::Add);
-                ctx.opcodes.push(OpCode::SetLocal(index_slot));
+                ctx.opcodes.push(OpCode::SetLocal(operand(index_slot)));
 
                 // 8. Прыжок в начало цикла
-                ctx.opcodes.push(OpCode::Jump(loop_start));
+                ctx.opcodes.push(OpCode::Jump(operand(loop_start)));
 
                 // 9. Выход из цикла
                 self.patch_jump(ctx, exit_jump);
@@ -269,7 +496,10 @@ impl Generator {
             }
             Statement::FuncDecl(func_decl) => {
                 if func_decl.is_exported && ctx.in_function {
-                    panic!("Нельзя экспортировать функцию внутри другой функции");
+                    return Err(CodegenError::new(
+                        func_decl.span,
+                        "Нельзя экспортировать функцию внутри другой функции",
+                    ));
                 }
 
                 // Компилируем тело функции
@@ -279,7 +509,7 @@ impl Generator {
                     &func_decl.body,
                     ctx,
                     false,
-                );
+                )?;
 
                 // Получаем количество upvalues из скомпилированной функции
                 let upvalue_count = match &func_value {
@@ -291,48 +521,52 @@ impl Generator {
                 let fn_idx = self.intern_constant(func_value, ctx);
 
                 // Создаём замыкание с правильным количеством upvalues
-                ctx.opcodes.push(OpCode::Closure(fn_idx, upvalue_count));
+                ctx.opcodes
+                    .push(OpCode::Closure(operand(fn_idx), operand(upvalue_count)));
 
                 if ctx.in_function {
                     // Вложенная функция - определяем как локальную переменную
                     let slot = ctx.next_local_slot;
                     ctx.local_slots.insert(func_decl.name.clone(), slot);
                     ctx.next_local_slot += 1;
-                    ctx.opcodes.push(OpCode::DefineLocal(slot));
+                    ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
                 } else {
                     // Глобальная функция
                     let mangled_name = self.mangle_name(&func_decl.name, ctx);
                     let name_idx = self.intern_string(&mangled_name, ctx);
-                    ctx.opcodes.push(OpCode::DefineGlobal(name_idx));
+                    ctx.opcodes.push(OpCode::DefineGlobal(operand(name_idx)));
                 }
             }
             Statement::Return(ret_stmt) => {
                 if !ctx.in_function {
-                    panic!("'возврат' вне функции");
+                    return Err(CodegenError::new(ret_stmt.span, "'возврат' вне функции"));
                 }
 
                 if let Some(value) = &ret_stmt.value {
-                    self.emit_expression(value.clone(), ctx);
+                    self.emit_expression(value.clone(), ctx)?;
                 } else {
                     let nil_idx = self.intern_constant(Value::Nil, ctx);
-                    ctx.opcodes.push(OpCode::Constant(nil_idx));
+                    ctx.opcodes.push(OpCode::Constant(operand(nil_idx)));
                 }
 
+                if !ctx.finally_scopes.is_empty() {
+                    self.emit_pending_finally(ctx)?;
+                }
                 ctx.opcodes.push(OpCode::Return_);
             }
             Statement::Import(_) => {} // Импорт выполняет загрузчик модулей
             Statement::ClassDecl(class_decl) => {
                 // 1. Создать класс
                 let class_name_idx = self.intern_string(&class_decl.name, ctx);
-                ctx.opcodes.push(OpCode::Class);
-                ctx.opcodes.push(OpCode::Constant(class_name_idx));
+                ctx.opcodes.push(OpCode::Class(operand(class_name_idx)));
 
                 // 2. Установить наследование если есть
                 if let Some(ref parent_name) = class_decl.extends {
                     // Получить родительский класс
-                    let parent_mangled = self.mangle_name(parent_name, ctx);
+                    let parent_mangled = self.global_name(parent_name, ctx);
                     let parent_name_idx = self.intern_string(&parent_mangled, ctx);
-                    ctx.opcodes.push(OpCode::GetGlobal(parent_name_idx));
+                    ctx.opcodes
+                        .push(OpCode::GetGlobal(operand(parent_name_idx)));
 
                     // Установить родителя
                     ctx.opcodes.push(OpCode::Inherit);
@@ -349,12 +583,13 @@ impl Generator {
                                 &func_decl.body,
                                 ctx,
                                 true, // это метод
-                            );
+                            )?;
                             let fn_idx = self.intern_constant(func_value, ctx);
                             let method_name_idx = self.intern_string(&func_decl.name, ctx);
 
-                            ctx.opcodes.push(OpCode::Constant(fn_idx));
-                            ctx.opcodes.push(OpCode::DefineMethod(method_name_idx));
+                            ctx.opcodes.push(OpCode::Constant(operand(fn_idx)));
+                            ctx.opcodes
+                                .push(OpCode::DefineMethod(operand(method_name_idx)));
                         }
                         ClassMember::Constructor(ctor) => {
                             // Конструктор - специальный метод "конструктор"
@@ -364,12 +599,41 @@ impl Generator {
                                 &ctor.body,
                                 ctx,
                                 true, // это конструктор (метод)
-                            );
+                            )?;
                             let fn_idx = self.intern_constant(func_value, ctx);
                             let ctor_name_idx = self.intern_string("конструктор", ctx);
 
-                            ctx.opcodes.push(OpCode::Constant(fn_idx));
-                            ctx.opcodes.push(OpCode::DefineMethod(ctor_name_idx));
+                            ctx.opcodes.push(OpCode::Constant(operand(fn_idx)));
+                            ctx.opcodes
+                                .push(OpCode::DefineMethod(operand(ctor_name_idx)));
+                        }
+                        ClassMember::StaticMethod(func_decl) => {
+                            let func_value = self.compile_function(
+                                &func_decl.name,
+                                &func_decl.params,
+                                &func_decl.body,
+                                ctx,
+                                false, // без `это`
+                            )?;
+                            let fn_idx = self.intern_constant(func_value, ctx);
+                            let name_idx = self.intern_string(&func_decl.name, ctx);
+
+                            ctx.opcodes.push(OpCode::Constant(operand(fn_idx)));
+                            ctx.opcodes.push(OpCode::DefineStatic(operand(name_idx)));
+                        }
+                        ClassMember::StaticField {
+                            name, initializer, ..
+                        } => {
+                            // Значение вычисляется один раз при объявлении класса
+                            match initializer {
+                                Some(value) => self.emit_expression(value.clone(), ctx)?,
+                                None => {
+                                    let nil_idx = self.intern_constant(Value::Nil, ctx);
+                                    ctx.opcodes.push(OpCode::Constant(operand(nil_idx)));
+                                }
+                            }
+                            let name_idx = self.intern_string(name, ctx);
+                            ctx.opcodes.push(OpCode::DefineStatic(operand(name_idx)));
                         }
                         ClassMember::Field { .. } => {
                             // Поля объявляются, но инициализируются в конструкторе
@@ -381,7 +645,7 @@ impl Generator {
                 // 3. Определить класс как глобальную переменную
                 let mangled_name = self.mangle_name(&class_decl.name, ctx);
                 let name_idx = self.intern_string(&mangled_name, ctx);
-                ctx.opcodes.push(OpCode::DefineGlobal(name_idx));
+                ctx.opcodes.push(OpCode::DefineGlobal(operand(name_idx)));
             }
             Statement::InterfaceDecl(_) => {
                 // Заглушка для фазы 3
@@ -389,52 +653,206 @@ impl Generator {
             Statement::AnnotationDecl(_) => {
                 // Заглушка для фазы 4
             }
-            Statement::Try(try_stmt) => {
-                // Placeholder для адреса начала catch-блока
-                let handler_pos = ctx.opcodes.len();
-                ctx.opcodes.push(OpCode::PushExceptionHandler(0));
+            Statement::Try(try_stmt) => self.emit_try(*try_stmt, ctx)?,
+            Statement::Throw(throw_stmt) => {
+                self.emit_expression(throw_stmt.expression, ctx)?;
+                ctx.opcodes.push(OpCode::Throw);
+            }
+        }
+        Ok(())
+    }
 
-                // Тело try
-                for stmt in try_stmt.try_body {
-                    self.emit_statement(stmt, ctx);
-                }
+    fn emit_try(&mut self, try_stmt: TryStatement, ctx: &mut GenContext) -> CodegenResult {
+        let TryStatement {
+            try_body,
+            catches,
+            finally_body,
+            ..
+        } = try_stmt;
+
+        // Placeholder для адреса начала catch-блока
+        let handler_pos = self.push_handler(ctx);
+
+        // Тело try; `возврат` из него выполняет `наконец`
+        if let Some(body) = &finally_body {
+            ctx.finally_scopes.push(FinallyScope {
+                body: body.clone(),
+                handler_depth: ctx.handler_depth - 1,
+            });
+        }
+        self.emit_block(try_body, ctx)?;
+
+        // Успешное завершение: снимаем обработчик, выполняем `наконец`
+        // и перепрыгиваем catch
+        self.pop_handler(ctx);
+        let finally_scope = finally_body.as_ref().and_then(|_| ctx.finally_scopes.pop());
+        if let Some(body) = &finally_body {
+            self.emit_block(body.clone(), ctx)?;
+        }
+        let jump_over_catch = self.emit_jump(ctx, OpCode::Jump(0));
+
+        // Начало catch-блока; исключение на стеке
+        let catch_start = ctx.opcodes.len();
+        ctx.opcodes[handler_pos] = OpCode::PushExceptionHandler(operand(catch_start));
+
+        if catches.is_empty() {
+            // Без перехвата: выполняем `наконец` и бросаем исключение дальше
+            let body = finally_body.expect("попытка без перехвата и наконец");
+            self.emit_finally_rethrow(body, ctx)?;
+            self.patch_jump(ctx, jump_over_catch);
+            return Ok(());
+        }
 
-                // Успешное завершение: снимаем обработчик и перепрыгиваем catch
-                ctx.opcodes.push(OpCode::PopExceptionHandler);
-                let jump_over_catch = self.emit_jump(ctx, OpCode::Jump(0));
+        // Исключение из catch-блока тоже должно пройти через `наконец`
+        let rethrow_pos = finally_body.as_ref().map(|_| self.push_handler(ctx));
+        if let Some(scope) = finally_scope {
+            ctx.finally_scopes.push(scope);
+        }
 
-                // Начало catch-блока
-                let catch_start = ctx.opcodes.len();
-                ctx.opcodes[handler_pos] = OpCode::PushExceptionHandler(catch_start);
+        self.emit_catches(catches, ctx)?;
 
-                // Исключение будет на стеке: сохраняем в локальную переменную или удаляем
-                if let Some(var_name) = try_stmt.catch_var {
-                    let slot = if let Some(slot) = ctx.local_slots.get(&var_name) {
-                        *slot
-                    } else {
-                        let slot = ctx.next_local_slot;
-                        ctx.next_local_slot += 1;
-                        ctx.local_slots.insert(var_name, slot);
-                        slot
-                    };
-                    ctx.opcodes.push(OpCode::DefineLocal(slot));
-                } else {
-                    ctx.opcodes.push(OpCode::Pop);
-                }
+        if let (Some(rethrow_pos), Some(body)) = (rethrow_pos, finally_body) {
+            ctx.finally_scopes.pop();
+            self.pop_handler(ctx);
+            self.emit_block(body.clone(), ctx)?;
+            let jump_to_end = self.emit_jump(ctx, OpCode::Jump(0));
+
+            let rethrow_start = ctx.opcodes.len();
+            ctx.opcodes[rethrow_pos] = OpCode::PushExceptionHandler(operand(rethrow_start));
+            self.emit_finally_rethrow(body, ctx)?;
+            self.patch_jump(ctx, jump_to_end);
+        }
+
+        // Патчим прыжок через catch-блок
+        self.patch_jump(ctx, jump_over_catch);
+        Ok(())
+    }
+
+    /// Ветки `перехват`; исключение на стеке. Ветки с классом проверяются по
+    /// порядку, исключение, не подошедшее ни к одной, бросается дальше
+    fn emit_catches(&mut self, catches: Vec<CatchClause>, ctx: &mut GenContext) -> CodegenResult {
+        let exception = if catches.iter().any(|clause| clause.class.is_some()) {
+            let slot = self.hidden_local(ctx);
+            ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
+            Some(slot)
+        } else {
+            None
+        };
 
-                // Тело catch
-                for stmt in try_stmt.catch_body {
-                    self.emit_statement(stmt, ctx);
+        let mut jumps_to_end = Vec::new();
+        let mut matches_all = false;
+        for clause in catches {
+            let next_clause = match clause.class {
+                Some(class) => {
+                    let slot = exception.expect("слот исключения");
+                    ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+                    self.emit_expression(class, ctx)?;
+                    ctx.opcodes.push(OpCode::InstanceOf);
+                    Some(self.emit_jump(ctx, OpCode::JumpIfFalse(0)))
                 }
+                None => {
+                    matches_all = true;
+                    None
+                }
+            };
+            if let Some(slot) = exception {
+                ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+            }
 
-                // Патчим прыжок через catch-блок
-                self.patch_jump(ctx, jump_over_catch);
+            // Сохраняем исключение в локальную переменную или удаляем
+            if let Some(var_name) = clause.var {
+                let slot = if let Some(slot) = ctx.local_slots.get(&var_name) {
+                    *slot
+                } else {
+                    let slot = ctx.next_local_slot;
+                    ctx.next_local_slot += 1;
+                    ctx.local_slots.insert(var_name, slot);
+                    slot
+                };
+                ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
+            } else {
+                ctx.opcodes.push(OpCode::Pop);
             }
-            Statement::Throw(throw_stmt) => {
-                self.emit_expression(throw_stmt.expression, ctx);
-                ctx.opcodes.push(OpCode::Throw);
+
+            self.emit_block(clause.body, ctx)?;
+            if let Some(next_clause) = next_clause {
+                jumps_to_end.push(self.emit_jump(ctx, OpCode::Jump(0)));
+                self.patch_jump(ctx, next_clause);
             }
         }
+
+        // Ни одна ветка не подошла
+        if !matches_all {
+            let slot = exception.expect("слот исключения");
+            ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+            ctx.opcodes.push(OpCode::Throw);
+        }
+        for jump in jumps_to_end {
+            self.patch_jump(ctx, jump);
+        }
+        Ok(())
+    }
+
+    fn emit_block(&mut self, body: Vec<Statement>, ctx: &mut GenContext) -> CodegenResult {
+        for stmt in body {
+            self.emit_statement(stmt, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Установить обработчик исключений с адресом-заглушкой; возвращает
+    /// позицию инструкции для патча
+    fn push_handler(&mut self, ctx: &mut GenContext) -> usize {
+        ctx.handler_depth += 1;
+        ctx.opcodes.push(OpCode::PushExceptionHandler(0));
+        ctx.opcodes.len() - 1
+    }
+
+    fn pop_handler(&mut self, ctx: &mut GenContext) {
+        ctx.handler_depth -= 1;
+        ctx.opcodes.push(OpCode::PopExceptionHandler);
+    }
+
+    /// Безымянный слот для значения, которое нужно пронести через `наконец`
+    fn hidden_local(&mut self, ctx: &mut GenContext) -> usize {
+        let slot = ctx.next_local_slot;
+        ctx.next_local_slot += 1;
+        slot
+    }
+
+    /// Исключение на стеке: выполнить `наконец` и бросить его снова
+    fn emit_finally_rethrow(
+        &mut self,
+        body: Vec<Statement>,
+        ctx: &mut GenContext,
+    ) -> CodegenResult {
+        let slot = self.hidden_local(ctx);
+        ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
+        self.emit_block(body, ctx)?;
+        ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+        ctx.opcodes.push(OpCode::Throw);
+        Ok(())
+    }
+
+    /// Результат `возврат` на стеке: выполнить все охватывающие блоки
+    /// `наконец` изнутри наружу, сняв их обработчики исключений
+    fn emit_pending_finally(&mut self, ctx: &mut GenContext) -> CodegenResult {
+        let slot = self.hidden_local(ctx);
+        ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
+
+        let saved_depth = ctx.handler_depth;
+        let saved_scopes = ctx.finally_scopes.clone();
+        while let Some(scope) = ctx.finally_scopes.pop() {
+            while ctx.handler_depth > scope.handler_depth {
+                self.pop_handler(ctx);
+            }
+            self.emit_block(scope.body, ctx)?;
+        }
+        ctx.handler_depth = saved_depth;
+        ctx.finally_scopes = saved_scopes;
+
+        ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+        Ok(())
     }
 
     /// Разрешить локальную переменную
@@ -477,6 +895,31 @@ impl Generator {
         None
     }
 
+    fn emit_set_global(&mut self, name: &str, span: Span, ctx: &mut GenContext) -> CodegenResult {
+        let mangled_name = self.global_name(name, ctx);
+        if self.const_globals.contains(&mangled_name) {
+            return Err(CodegenError::new(
+                span,
+                &format!("Нельзя присвоить значение константе '{}'", name),
+            ));
+        }
+        let name_idx = self.intern_string(&mangled_name, ctx);
+        ctx.opcodes.push(OpCode::SetGlobal(operand(name_idx)));
+        Ok(())
+    }
+
+    /// Загрузить `это`: слот 0 в методе или upvalue в лямбде внутри метода
+    fn emit_this(&mut self, span: Span, error: &str, ctx: &mut GenContext) -> CodegenResult {
+        if let Some(slot) = self.resolve_local(THIS, ctx) {
+            ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
+        } else if let Some(upvalue_idx) = self.resolve_upvalue(THIS, ctx) {
+            ctx.opcodes.push(OpCode::GetUpvalue(operand(upvalue_idx)));
+        } else {
+            return Err(CodegenError::new(span, error));
+        }
+        Ok(())
+    }
+
     /// Добавить upvalue в текущий контекст
     fn add_upvalue(
         &mut self,
@@ -503,39 +946,44 @@ impl Generator {
         upvalue_idx
     }
 
-    fn emit_expression(&mut self, e: Expression, ctx: &mut GenContext) {
+    fn emit_expression(&mut self, e: Expression, ctx: &mut GenContext) -> CodegenResult {
+        self.mark_line(e.span(), ctx);
         match e {
+            Expression::Integer(n, _) => {
+                let idx = self.intern_constant(Value::Int(n), ctx);
+                ctx.opcodes.push(OpCode::Constant(operand(idx)))
+            }
             Expression::Number(n, _) => {
                 let idx = self.intern_constant(Value::Number(n), ctx);
-                ctx.opcodes.push(OpCode::Constant(idx))
+                ctx.opcodes.push(OpCode::Constant(operand(idx)))
             }
             Expression::String(s, _) => {
-                let idx = self.intern_constant(Value::String(s), ctx);
-                ctx.opcodes.push(OpCode::Constant(idx))
+                let idx = self.intern_string(&s, ctx);
+                ctx.opcodes.push(OpCode::Constant(operand(idx)))
             }
             Expression::Boolean(b, _) => {
                 let idx = self.intern_constant(Value::Boolean(b), ctx);
-                ctx.opcodes.push(OpCode::Constant(idx))
+                ctx.opcodes.push(OpCode::Constant(operand(idx)))
             }
             Expression::Nil(_) => {
                 let idx = self.intern_constant(Value::Nil, ctx);
-                ctx.opcodes.push(OpCode::Constant(idx))
+                ctx.opcodes.push(OpCode::Constant(operand(idx)))
             }
             Expression::Identifier(name, _) => {
                 if let Some(slot) = self.resolve_local(&name, ctx) {
-                    ctx.opcodes.push(OpCode::GetLocal(slot));
+                    ctx.opcodes.push(OpCode::GetLocal(operand(slot)));
                 } else if ctx.in_function {
                     if let Some(upvalue_idx) = self.resolve_upvalue(&name, ctx) {
-                        ctx.opcodes.push(OpCode::GetUpvalue(upvalue_idx));
+                        ctx.opcodes.push(OpCode::GetUpvalue(operand(upvalue_idx)));
                     } else {
-                        let mangled_name = self.mangle_name(&name, ctx);
+                        let mangled_name = self.global_name(&name, ctx);
                         let name_idx = self.intern_string(&mangled_name, ctx);
-                        ctx.opcodes.push(OpCode::GetGlobal(name_idx));
+                        ctx.opcodes.push(OpCode::GetGlobal(operand(name_idx)));
                     }
                 } else {
-                    let mangled_name = self.mangle_name(&name, ctx);
+                    let mangled_name = self.global_name(&name, ctx);
                     let name_idx = self.intern_string(&mangled_name, ctx);
-                    ctx.opcodes.push(OpCode::GetGlobal(name_idx));
+                    ctx.opcodes.push(OpCode::GetGlobal(operand(name_idx)));
                 }
             }
             Expression::Binary(binary_expr) => {
@@ -543,7 +991,7 @@ impl Generator {
                     crate::ponos::ast::BinaryOperator::And => {
                         // Логическое И с коротким замыканием:
                         // emit(left)
-                        self.emit_expression(binary_expr.left, ctx);
+                        self.emit_expression(binary_expr.left, ctx)?;
                         // Dup - дублировать для проверки
                         ctx.opcodes.push(OpCode::Dup);
                         // JumpIfFalse(end) - pop и проверка, если false переходим (на стеке останется left)
@@ -552,15 +1000,15 @@ impl Generator {
                         // Pop - удалить оригинальный left (дубликат был удален JumpIfFalse)
                         ctx.opcodes.push(OpCode::Pop);
                         // emit(right) - результат будет right
-                        self.emit_expression(binary_expr.right, ctx);
+                        self.emit_expression(binary_expr.right, ctx)?;
                         // end:
                         let end_addr = ctx.opcodes.len();
-                        ctx.opcodes[jump_addr] = OpCode::JumpIfFalse(end_addr);
+                        ctx.opcodes[jump_addr] = OpCode::JumpIfFalse(operand(end_addr));
                     }
                     crate::ponos::ast::BinaryOperator::Or => {
                         // Логическое ИЛИ с коротким замыканием:
                         // emit(left)
-                        self.emit_expression(binary_expr.left, ctx);
+                        self.emit_expression(binary_expr.left, ctx)?;
                         // Dup - дублировать для проверки
                         ctx.opcodes.push(OpCode::Dup);
                         // JumpIfTrue(end) - pop и проверка, если true переходим (на стеке останется left)
@@ -569,15 +1017,28 @@ impl Generator {
                         // Pop - удалить оригинальный left (дубликат был удален JumpIfTrue)
                         ctx.opcodes.push(OpCode::Pop);
                         // emit(right) - результат будет right
-                        self.emit_expression(binary_expr.right, ctx);
+                        self.emit_expression(binary_expr.right, ctx)?;
                         // end:
                         let end_addr = ctx.opcodes.len();
-                        ctx.opcodes[jump_addr] = OpCode::JumpIfTrue(end_addr);
+                        ctx.opcodes[jump_addr] = OpCode::JumpIfTrue(operand(end_addr));
+                    }
+                    crate::ponos::ast::BinaryOperator::Coalesce => {
+                        // left ?? right: right вычисляется, только если left — ничто
+                        self.emit_expression(binary_expr.left, ctx)?;
+                        ctx.opcodes.push(OpCode::Dup);
+                        let nil_idx = self.intern_constant(Value::Nil, ctx);
+                        ctx.opcodes.push(OpCode::Constant(operand(nil_idx)));
+                        ctx.opcodes.push(OpCode::Eql);
+                        // Не ничто: на стеке остаётся left
+                        let jump_addr = self.emit_jump(ctx, OpCode::JumpIfFalse(0));
+                        ctx.opcodes.push(OpCode::Pop);
+                        self.emit_expression(binary_expr.right, ctx)?;
+                        self.patch_jump(ctx, jump_addr);
                     }
                     _ => {
                         // Обычные бинарные операторы
-                        self.emit_expression(binary_expr.left, ctx);
-                        self.emit_expression(binary_expr.right, ctx);
+                        self.emit_expression(binary_expr.left, ctx)?;
+                        self.emit_expression(binary_expr.right, ctx)?;
                         let mut ops = match binary_expr.operator {
                             crate::ponos::ast::BinaryOperator::Add => vec![OpCode::Add],
                             crate::ponos::ast::BinaryOperator::Subtract => vec![OpCode::Sub],
@@ -604,7 +1065,7 @@ impl Generator {
                 }
             }
             Expression::Unary(unary_expr) => {
-                self.emit_expression(unary_expr.operand, ctx);
+                self.emit_expression(unary_expr.operand, ctx)?;
                 let op = match unary_expr.operator {
                     UnaryOperator::Negate => OpCode::Negate,
                     UnaryOperator::Not => OpCode::Not,
@@ -613,45 +1074,86 @@ impl Generator {
             }
             Expression::Call(call_expr) => {
                 // Генерируем callee
-                self.emit_expression(call_expr.callee, ctx);
-
-                // Генерируем аргументы
-                for arg in &call_expr.arguments {
-                    self.emit_expression(arg.clone(), ctx);
+                self.emit_expression(call_expr.callee, ctx)?;
+
+                if !call_expr
+                    .arguments
+                    .iter()
+                    .any(|arg| matches!(arg, Expression::Spread(..)))
+                {
+                    for arg in &call_expr.arguments {
+                        self.emit_expression(arg.clone(), ctx)?;
+                    }
+                    ctx.opcodes
+                        .push(OpCode::Call(operand(call_expr.arguments.len())));
+                    return Ok(());
                 }
 
-                // Вызов
-                ctx.opcodes.push(OpCode::Call(call_expr.arguments.len()));
+                // Аргументы с `...` передаются массивами: подряд идущие обычные
+                // аргументы собираются в один массив, развёрнутые — как есть
+                let mut pieces = 0;
+                let mut pending = 0;
+                for arg in call_expr.arguments {
+                    match arg {
+                        Expression::Spread(value, _) => {
+                            if pending > 0 {
+                                ctx.opcodes.push(OpCode::Array(pending));
+                                pieces += 1;
+                                pending = 0;
+                            }
+                            self.emit_expression(*value, ctx)?;
+                            pieces += 1;
+                        }
+                        arg => {
+                            self.emit_expression(arg, ctx)?;
+                            pending += 1;
+                        }
+                    }
+                }
+                if pending > 0 {
+                    ctx.opcodes.push(OpCode::Array(pending));
+                    pieces += 1;
+                }
+                ctx.opcodes.push(OpCode::CallSpread(pieces));
+            }
+            Expression::Spread(_, span) => {
+                return Err(CodegenError::new(
+                    span,
+                    "'...' допускается только в аргументах вызова",
+                ));
             }
             Expression::FieldAccess(field_access_expr) => {
                 // Специальный случай: super.method
-                if let Expression::Identifier(ref name, _) = field_access_expr.object {
-                    if name == "super" {
-                        // 1. Загрузить это (всегда в слоте 0 для методов)
-                        ctx.opcodes.push(OpCode::GetLocal(0));
-
-                        // 2. GetSuper (читает следующий опкод Constant)
-                        let method_name_idx = self.intern_string(&field_access_expr.field, ctx);
-                        ctx.opcodes.push(OpCode::GetSuper);
-                        ctx.opcodes.push(OpCode::Constant(method_name_idx));
-                        return;
-                    }
+                if let Expression::Identifier(ref name, _) = field_access_expr.object
+                    && name == "super"
+                {
+                    // 1. Загрузить это (всегда в слоте 0 для методов)
+                    ctx.opcodes.push(OpCode::GetLocal(0));
+
+                    // 2. Получить метод родительского класса
+                    let method_name_idx = self.intern_string(&field_access_expr.field, ctx);
+                    ctx.opcodes.push(OpCode::GetSuper(operand(method_name_idx)));
+                    return Ok(());
                 }
 
                 // Обычный field access
                 // 1. Вычислить объект
-                self.emit_expression(field_access_expr.object.clone(), ctx);
+                self.emit_expression(field_access_expr.object.clone(), ctx)?;
 
                 // 2. Получить поле/метод
                 let field_name_idx = self.intern_string(&field_access_expr.field, ctx);
-                ctx.opcodes.push(OpCode::GetProperty);
-                ctx.opcodes.push(OpCode::Constant(field_name_idx));
+                ctx.opcodes
+                    .push(OpCode::GetProperty(operand(field_name_idx)));
             }
             Expression::ModuleAccess(module_access) => {
                 // Генерируем загрузку символа из модуля с манглингом имен
-                let mangled_name = format!("{}::{}", module_access.namespace, module_access.symbol);
+                let namespace = module_access
+                    .origin
+                    .as_ref()
+                    .unwrap_or(&module_access.namespace);
+                let mangled_name = format!("{}::{}", namespace, module_access.symbol);
                 let name_idx = self.intern_string(&mangled_name, ctx);
-                ctx.opcodes.push(OpCode::GetGlobal(name_idx));
+                ctx.opcodes.push(OpCode::GetGlobal(operand(name_idx)));
             }
             Expression::Lambda(lambda_expr) => {
                 // Компилируем функцию и собираем upvalues
@@ -661,7 +1163,7 @@ impl Generator {
                     &lambda_expr.body,
                     ctx,
                     false,
-                );
+                )?;
 
                 // Получаем количество upvalues из скомпилированной функции
                 let upvalue_count = match &func_value {
@@ -671,82 +1173,63 @@ impl Generator {
 
                 let fn_idx = self.intern_constant(func_value, ctx);
 
-                ctx.opcodes.push(OpCode::Closure(fn_idx, upvalue_count));
+                ctx.opcodes
+                    .push(OpCode::Closure(operand(fn_idx), operand(upvalue_count)));
 
                 // Лямбда оставляет замыкание на стеке
             }
             Expression::Index(index_expr) => {
                 // Генерируем код для объекта
-                self.emit_expression(index_expr.object.clone(), ctx);
+                self.emit_expression(index_expr.object.clone(), ctx)?;
                 // Генерируем код для индекса
-                self.emit_expression(index_expr.index.clone(), ctx);
+                self.emit_expression(index_expr.index.clone(), ctx)?;
                 // Опкод получения элемента
                 ctx.opcodes.push(OpCode::GetIndex);
             }
             Expression::Range(range_expr) => {
                 // Вычисляем значения start и end и создаем Value::Range
-                let start_val = range_expr.start.as_ref().map(|e| {
-                    // Для простоты, пока что только числовые константы
-                    if let Expression::Number(n, _) = **e {
-                        n
-                    } else {
-                        panic!("Range expressions currently only support numeric constants");
-                    }
-                });
-
-                let end_val = range_expr.end.as_ref().map(|e| {
-                    if let Expression::Number(n, _) = **e {
-                        n
-                    } else {
-                        panic!("Range expressions currently only support numeric constants");
-                    }
-                });
+                let start_val = range_bound(&range_expr.start)?;
+                let end_val = range_bound(&range_expr.end)?;
 
                 // Создаем Range value и помещаем в константы
                 let range_value = Value::Range(start_val, end_val);
                 let idx = self.intern_constant(range_value, ctx);
-                ctx.opcodes.push(OpCode::Constant(idx));
-            }
-            Expression::This(_span) => {
-                // "это" всегда в слоте 0 внутри метода
-                if !ctx.in_function {
-                    panic!("'это' вне метода класса");
-                }
-                ctx.opcodes.push(OpCode::GetLocal(0));
+                ctx.opcodes.push(OpCode::Constant(operand(idx)));
             }
-            Expression::Super(method_name, _span) => {
+            Expression::This(span) => self.emit_this(span, "'это' вне метода класса", ctx)?,
+            Expression::Super(method_name, span) => {
                 // super.method_name → GetSuper
-                // GetSuper читает следующий опкод Constant(method_name_idx),
-                // берёт текущий экземпляр (GetLocal 0),
+                // GetSuper(method_name_idx) берёт текущий экземпляр (GetLocal 0),
                 // находит метод в родительском классе и создаёт BoundMethod
 
-                // 1. Загрузить это (всегда в слоте 0 для методов)
-                ctx.opcodes.push(OpCode::GetLocal(0));
+                // 1. Загрузить это
+                self.emit_this(span, "'супер' вне метода класса", ctx)?;
 
-                // 2. GetSuper (читает следующий опкод Constant)
+                // 2. Получить метод родительского класса
                 let method_name_idx = self.intern_string(&method_name, ctx);
-                ctx.opcodes.push(OpCode::GetSuper);
-                ctx.opcodes.push(OpCode::Constant(method_name_idx));
+                ctx.opcodes.push(OpCode::GetSuper(operand(method_name_idx)));
             }
             Expression::ArrayLiteral(array_literal) => {
                 // Генерируем код для каждого элемента массива
                 for elem in &array_literal.elements {
-                    self.emit_expression(elem.clone(), ctx);
+                    self.emit_expression(elem.clone(), ctx)?;
                 }
                 // Опкод создания массива
                 ctx.opcodes
-                    .push(OpCode::Array(array_literal.elements.len()));
+                    .push(OpCode::Array(operand(array_literal.elements.len())));
             }
             Expression::DictLiteral(dict_literal) => {
                 // Генерируем код для каждой пары (ключ, значение)
                 for (key, value) in &dict_literal.pairs {
-                    self.emit_expression(key.clone(), ctx);
-                    self.emit_expression(value.clone(), ctx);
+                    self.emit_expression(key.clone(), ctx)?;
+                    self.emit_expression(value.clone(), ctx)?;
                 }
                 // Опкод создания словаря
-                ctx.opcodes.push(OpCode::Dict(dict_literal.pairs.len()));
+                ctx.opcodes
+                    .push(OpCode::Dict(operand(dict_literal.pairs.len())));
             }
         }
+        Ok(())
     }
 
     fn emit_jump(&self, ctx: &mut GenContext, opcode: OpCode) -> usize {
@@ -763,6 +1246,15 @@ impl Generator {
         ctx.opcodes.len() - 1
     }
 
+    /// Имя глобальной переменной, к которой обращается идентификатор: символ,
+    /// импортированный из модуля без префикса, или имя из текущего пространства
+    fn global_name(&self, name: &str, ctx: &GenContext) -> String {
+        match self.imports.get(name) {
+            Some(mangled_name) if ctx.current_namespace.is_none() => mangled_name.clone(),
+            _ => self.mangle_name(name, ctx),
+        }
+    }
+
     /// Применить манглинг имени переменной с учетом текущего пространства имен
     fn mangle_name(&self, name: &str, ctx: &GenContext) -> String {
         if let Some(namespace) = &ctx.current_namespace {
@@ -777,12 +1269,12 @@ impl Generator {
         name: String,
         initializer: Option<Expression>,
         ctx: &mut GenContext,
-    ) {
+    ) -> CodegenResult {
         if let Some(init) = initializer {
-            self.emit_expression(init, ctx);
+            self.emit_expression(init, ctx)?;
         } else {
             let idx = self.intern_constant(Value::Nil, ctx);
-            ctx.opcodes.push(OpCode::Constant(idx));
+            ctx.opcodes.push(OpCode::Constant(operand(idx)));
         }
 
         let slot = if let Some(slot) = ctx.local_slots.get(&name) {
@@ -794,17 +1286,24 @@ impl Generator {
             slot
         };
 
-        ctx.opcodes.push(OpCode::DefineLocal(slot));
+        ctx.opcodes.push(OpCode::DefineLocal(operand(slot)));
+        Ok(())
     }
 
     fn intern_string(&mut self, value: &str, ctx: &mut GenContext) -> usize {
-        self.intern_constant(Value::String(value.to_string()), ctx)
+        let string = self
+            .strings
+            .entry(value.to_string())
+            .or_insert_with(|| Rc::new(value.to_string()))
+            .clone();
+        self.intern_constant(Value::String(string), ctx)
     }
 
     fn intern_constant(&mut self, value: Value, ctx: &mut GenContext) -> usize {
         // Ищем одинаковые константы (работает только для простых значений)
         if let Some(idx) = ctx.constants.iter().position(|v| {
             match (&value, v) {
+                (Value::Int(a), Value::Int(b)) => a == b,
                 (Value::Number(a), Value::Number(b)) => a == b,
                 (Value::String(a), Value::String(b)) => a == b,
                 (Value::Boolean(a), Value::Boolean(b)) => a == b,
@@ -826,9 +1325,9 @@ impl Generator {
 
         // Заменяем опкод на тот же тип, но с правильным адресом
         let patched_opcode = match ctx.opcodes[operand_pos] {
-            OpCode::Jump(_) => OpCode::Jump(jump_target),
-            OpCode::JumpIfTrue(_) => OpCode::JumpIfTrue(jump_target),
-            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(jump_target),
+            OpCode::Jump(_) => OpCode::Jump(operand(jump_target)),
+            OpCode::JumpIfTrue(_) => OpCode::JumpIfTrue(operand(jump_target)),
+            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(operand(jump_target)),
             _ => panic!("patch_jump вызван для не-jump опкода"),
         };
 
@@ -842,18 +1341,29 @@ impl Generator {
         body: &[Statement],
         parent_ctx: &mut GenContext,
         is_method: bool, // true для методов и конструкторов
-    ) -> Value {
+    ) -> CodegenResult<Value> {
         let mut func_ctx = GenContext {
             constants: Vec::new(),
             opcodes: Vec::new(),
             current_namespace: parent_ctx.current_namespace.clone(),
+            source_name: parent_ctx.source_name.clone(),
+            lines: LineTable::default(),
             in_function: true,
+            line_index: parent_ctx.line_index.clone(),
             local_slots: HashMap::new(),
             next_local_slot: if is_method { 1 } else { 0 }, // Для методов/конструкторов слот 0 - это 'это'
             parent_context: Some(Box::new(parent_ctx.clone())),
             upvalues: Vec::new(),
+            handler_depth: 0,
+            finally_scopes: Vec::new(),
         };
 
+        // `это` — локальная переменная метода в слоте 0; вложенные лямбды
+        // захватывают её как upvalue
+        if is_method {
+            func_ctx.local_slots.insert(THIS.to_string(), 0);
+        }
+
         // Регистрируем параметры как локальные переменные
         for param in params {
             let slot = func_ctx.next_local_slot;
@@ -861,9 +1371,24 @@ impl Generator {
             func_ctx.next_local_slot += 1;
         }
 
+        // Пролог: значения по умолчанию вычисляются по порядку, начиная с
+        // первого пропущенного аргумента; последняя точка входа — начало тела
+        let mut entry_points = Vec::new();
+        for param in params {
+            if let Some(default) = &param.default {
+                entry_points.push(func_ctx.opcodes.len());
+                self.emit_expression(default.clone(), &mut func_ctx)?;
+                let slot = func_ctx.local_slots[&param.name];
+                func_ctx.opcodes.push(OpCode::SetLocal(operand(slot)));
+            }
+        }
+        if !entry_points.is_empty() {
+            entry_points.push(func_ctx.opcodes.len());
+        }
+
         // Генерируем тело
         for stmt in body {
-            self.emit_statement(stmt.clone(), &mut func_ctx);
+            self.emit_statement(stmt.clone(), &mut func_ctx)?;
         }
 
         // Неявный return
@@ -873,10 +1398,18 @@ impl Generator {
         } else {
             // Для обычных функций возвращаем nil
             let nil_idx = self.intern_constant(Value::Nil, &mut func_ctx);
-            func_ctx.opcodes.push(OpCode::Constant(nil_idx));
+            func_ctx.opcodes.push(OpCode::Constant(operand(nil_idx)));
         }
         func_ctx.opcodes.push(OpCode::Return_);
 
+        // Компиляция шла с копией родительского контекста: upvalues, которые
+        // понадобились родителям для захвата через несколько уровней
+        // вложенности, переносим обратно
+        if let Some(parent) = func_ctx.parent_context.take() {
+            parent_ctx.upvalues = parent.upvalues;
+            parent_ctx.parent_context = parent.parent_context;
+        }
+
         // Собираем информацию об upvalues
         let upvalue_descriptors: Vec<UpvalueDescriptor> = func_ctx
             .upvalues
@@ -887,14 +1420,78 @@ impl Generator {
             })
             .collect();
 
-        Value::Function(Rc::new(Function {
+        Ok(Value::Function(Rc::new(Function {
             arity: params.len(),
+            entry_points,
+            variadic: params.last().is_some_and(|param| param.is_rest),
             opcodes: func_ctx.opcodes,
             constants: func_ctx.constants,
             name: name.to_string(),
             upvalue_count: upvalue_descriptors.len(),
             upvalue_descriptors,
-        }))
+            source_name: func_ctx.source_name,
+            lines: func_ctx.lines,
+        })))
+    }
+}
+
+/// Правая часть `x = x + значение`, если присваивание можно выполнить на
+/// месте. Значение вычисляется раньше обращения к `x`, поэтому в нём не
+/// должно быть вызовов: вызванная функция могла бы изменить `x`
+fn appended_value<'e>(target: &str, value: &'e Expression) -> Option<&'e Expression> {
+    let Expression::Binary(binary) = value else {
+        return None;
+    };
+    match &binary.left {
+        Expression::Identifier(name, _)
+            if name == target
+                && binary.operator == BinaryOperator::Add
+                && without_calls(&binary.right) =>
+        {
+            Some(&binary.right)
+        }
+        _ => None,
+    }
+}
+
+fn without_calls(expression: &Expression) -> bool {
+    match expression {
+        Expression::Integer(..)
+        | Expression::Number(..)
+        | Expression::String(..)
+        | Expression::Boolean(..)
+        | Expression::Nil(_)
+        | Expression::Identifier(..)
+        | Expression::ModuleAccess(_)
+        | Expression::This(_)
+        | Expression::Lambda(_) => true,
+        Expression::Binary(binary) => without_calls(&binary.left) && without_calls(&binary.right),
+        Expression::Unary(unary) => without_calls(&unary.operand),
+        Expression::FieldAccess(access) => without_calls(&access.object),
+        Expression::Index(index) => without_calls(&index.object) && without_calls(&index.index),
+        Expression::Range(range) => [&range.start, &range.end]
+            .into_iter()
+            .flatten()
+            .all(|bound| without_calls(bound)),
+        Expression::ArrayLiteral(array) => array.elements.iter().all(without_calls),
+        Expression::DictLiteral(dict) => dict
+            .pairs
+            .iter()
+            .all(|(key, value)| without_calls(key) && without_calls(value)),
+        Expression::Call(_) | Expression::Super(..) | Expression::Spread(..) => false,
+    }
+}
+
+/// Граница среза: пока поддерживаются только числовые константы
+fn range_bound(bound: &Option<Box<Expression>>) -> CodegenResult<Option<f64>> {
+    match bound.as_deref() {
+        None => Ok(None),
+        Some(Expression::Integer(n, _)) => Ok(Some(*n as f64)),
+        Some(Expression::Number(n, _)) => Ok(Some(*n)),
+        Some(other) => Err(CodegenError::new(
+            other.span(),
+            "Границы среза должны быть числовыми константами",
+        )),
     }
 }
 
@@ -916,23 +1513,20 @@ mod tests {
                 type_annotation: None,
                 initializer: Some(number_expr(42.0)),
                 is_exported: false,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         let mut generator = Generator::new();
-        let ctx = generator.generate(AstNode::Program(program));
+        let ctx = generator.generate(AstNode::Program(program)).unwrap();
 
         assert_eq!(
             ctx.opcodes,
             vec![OpCode::Constant(0), OpCode::DefineGlobal(1), OpCode::Halt]
         );
-        // TODO: Восстановить после добавления PartialEq для Value
-        // assert_eq!(
-        //     ctx.constants,
-        //     vec![Value::Number(42.0), Value::String("a".to_string())]
-        // );
-        assert_eq!(ctx.constants.len(), 2);
+        assert_eq!(ctx.constants, vec![Value::Number(42.0), Value::from("a")]);
     }
 
     #[test]
@@ -944,12 +1538,14 @@ mod tests {
                 type_annotation: None,
                 initializer: Some(number_expr(1.0)),
                 is_exported: true,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         let mut generator = Generator::new();
-        let ctx = generator.generate(AstNode::Program(program));
+        let ctx = generator.generate(AstNode::Program(program)).unwrap();
 
         // Теперь просто генерируется переменная, без опкодов модулей
         assert_eq!(
@@ -957,10 +1553,59 @@ mod tests {
             vec![OpCode::Constant(0), OpCode::DefineGlobal(1), OpCode::Halt]
         );
 
+        assert_eq!(ctx.constants, vec![Value::Number(1.0), Value::from("x")]);
+    }
+
+    #[test]
+    fn compiles_module_as_separate_unit_with_lazy_functions() {
+        let block = ModuleBlock {
+            namespace: "мат".to_string(),
+            statements: vec![
+                Statement::VarDecl(VarDecl {
+                    name: "ПИ".to_string(),
+                    type_annotation: None,
+                    initializer: Some(number_expr(3.0)),
+                    is_exported: true,
+                    is_const: false,
+                    span: Span::default(),
+                }),
+                Statement::FuncDecl(FuncDecl {
+                    name: "удвоить".to_string(),
+                    params: Vec::new(),
+                    return_type: None,
+                    body: Vec::new(),
+                    annotations: Vec::new(),
+                    is_exported: true,
+                    span: Span::default(),
+                }),
+            ],
+            span: Span::default(),
+            file_path: Some(PathBuf::from("мат.pns")),
+            source: Rc::from(""),
+        };
+
+        let mut generator = Generator::new();
+        let unit = generator.generate_module(block).unwrap();
+
+        // Функция не попадает в байткод модуля, она компилируется при первом обращении
         assert_eq!(
-            ctx.constants,
-            vec![Value::Number(1.0), Value::String("x".to_string())]
+            unit.opcodes,
+            vec![OpCode::Constant(0), OpCode::DefineGlobal(1), OpCode::Halt]
         );
+        assert_eq!(unit.exports, vec!["мат::ПИ", "мат::удвоить"]);
+        assert_eq!(unit.lazy_functions.len(), 1);
+        assert_eq!(unit.lazy_functions[0].mangled_name, "мат::удвоить");
+        assert_eq!(unit.file_path, Some(PathBuf::from("мат.pns")));
+
+        let lazy = unit.lazy_functions.into_iter().next().unwrap();
+        match lazy.materialize().unwrap() {
+            Value::Closure(closure) => {
+                assert_eq!(closure.function.name, "удвоить");
+                assert_eq!(closure.function.source_name.as_deref(), Some("мат.pns"));
+                assert!(closure.upvalues.is_empty());
+            }
+            other => panic!("Ожидалось замыкание, получено {:?}", other),
+        }
     }
 
     // Тесты импортов удалены, так как импорты теперь обрабатываются на этапе загрузки модулей
@@ -1004,12 +1649,206 @@ mod tests {
                 else_branch: Some(else_branch),
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         let mut generator = Generator::new();
-        let ctx = generator.generate(AstNode::Program(program));
+        let ctx = generator.generate(AstNode::Program(program)).unwrap();
 
         assert!(matches!(ctx.opcodes[3], OpCode::JumpIfFalse(7)));
         assert!(matches!(ctx.opcodes[6], OpCode::Jump(9)));
     }
+    #[test]
+    fn misplaced_constructs_are_errors_with_spans() {
+        let error = |source: &str| {
+            let program = crate::ponos::parser::PonosParser::new()
+                .parse(source.to_string())
+                .unwrap();
+            match Generator::new().generate(AstNode::Program(program)) {
+                Ok(_) => None,
+                Err(e) => Some((e.message, source[e.span.start..e.span.end].to_string())),
+            }
+        };
+
+        let (message, fragment) = error("пер x = 1;\nвозврат x;").unwrap();
+        assert_eq!(message, "'возврат' вне функции");
+        assert!(fragment.starts_with("возврат"), "{}", fragment);
+
+        let (message, fragment) = error("функ f(м, н) возврат м[н:2]; конец").unwrap();
+        assert_eq!(message, "Границы среза должны быть числовыми константами");
+        assert_eq!(fragment, "н");
+        assert_eq!(error("функ f(м) возврат м[1:2]; конец"), None);
+
+        let (message, fragment) = error("пер f = (функ() возврат это; конец);").unwrap();
+        assert_eq!(message, "'это' вне метода класса");
+        assert_eq!(fragment, "это");
+    }
+
+    fn compile(source: &str) -> GenContext {
+        let program = crate::ponos::parser::PonosParser::new()
+            .parse(source.to_string())
+            .unwrap();
+        Generator::new()
+            .generate(AstNode::Program(program))
+            .unwrap()
+    }
+
+    fn functions(constants: &[Value]) -> Vec<Rc<Function>> {
+        constants
+            .iter()
+            .filter_map(|constant| match constant {
+                Value::Function(function) => Some(function.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn this_in_method_lambdas_is_captured_as_upvalue() {
+        let ctx = compile(
+            "класс А\n\
+                 функ f()\n\
+                     возврат (функ() возврат (функ() возврат это; конец); конец);\n\
+                 конец\n\
+             конец",
+        );
+        let method = functions(&ctx.constants).pop().unwrap();
+        assert_eq!(method.name, "f");
+        let outer = functions(&method.constants).pop().unwrap();
+        let inner = functions(&outer.constants).pop().unwrap();
+
+        // Внешняя лямбда захватывает слот 0 метода, внутренняя — upvalue внешней
+        assert_eq!(outer.upvalue_count, 1);
+        assert!(outer.upvalue_descriptors[0].is_local);
+        assert_eq!(outer.upvalue_descriptors[0].index, 0);
+        assert_eq!(inner.upvalue_count, 1);
+        assert!(!inner.upvalue_descriptors[0].is_local);
+        assert!(inner.opcodes.contains(&OpCode::GetUpvalue(0)));
+        assert!(!inner.opcodes.contains(&OpCode::GetLocal(0)));
+    }
+
+    #[test]
+    fn opcodes_are_mapped_to_source_lines() {
+        let source =
+            "пер а = 1;\n\nфунк f(x)\n    если x\n        возврат а;\n    конец\nконец\nf(а);\n";
+        let program = crate::ponos::parser::PonosParser::new()
+            .parse(source.to_string())
+            .unwrap();
+        let ctx = Generator::new()
+            .generate(AstNode::Program(program))
+            .unwrap();
+
+        assert_eq!(ctx.lines.line_at(0), Some(1));
+        assert_eq!(ctx.lines.line_at(ctx.opcodes.len() - 1), Some(8));
+        let function = ctx
+            .constants
+            .iter()
+            .find_map(|constant| match constant {
+                Value::Function(function) => Some(function.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(function.lines.entries(), [(0, 4), (2, 5)]);
+        assert_eq!(function.lines.line_at(0), Some(4));
+        assert_eq!(function.lines.line_at(3), Some(5));
+    }
+
+    #[test]
+    fn self_concatenation_without_calls_appends_in_place() {
+        let ctx = compile(
+            "функ g()\n    возврат \"б\";\nконец\n\
+             функ f(с)\n    с = с + \"а\" + 1;\n    с = с + [с][0];\n    с = с + g();\n    возврат с;\nконец\n\
+             пер т = \"\";\nт = т + 1;",
+        );
+        let f = functions(&ctx.constants)
+            .into_iter()
+            .find(|function| function.name == "f")
+            .unwrap();
+        let appends = |opcodes: &[OpCode]| {
+            opcodes
+                .iter()
+                .filter(|opcode| matches!(opcode, OpCode::AppendLocal(_) | OpCode::AppendGlobal(_)))
+                .count()
+        };
+
+        // `с + "а" + 1` — это `(с + "а") + 1`, а `с + g()` вызывает функцию
+        assert_eq!(appends(&f.opcodes), 1);
+        assert!(f.opcodes.contains(&OpCode::AppendLocal(0)));
+        assert_eq!(appends(&ctx.opcodes), 1);
+
+        let program = crate::ponos::parser::PonosParser::new()
+            .parse("конст к = \"\";\nк = к + 1;".to_string())
+            .unwrap();
+        let error = Generator::new()
+            .generate(AstNode::Program(program))
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Нельзя присвоить значение константе 'к'");
+    }
+
+    #[test]
+    fn equal_strings_share_one_buffer_across_functions() {
+        let ctx = compile("функ f()\n    возврат \"текст\";\nконец\nпер т = \"текст\";");
+        let f = functions(&ctx.constants).pop().unwrap();
+        let text = |constants: &[Value]| {
+            constants
+                .iter()
+                .find_map(|constant| match constant {
+                    Value::String(s) if **s == "текст" => Some(s.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert!(Rc::ptr_eq(&text(&ctx.constants), &text(&f.constants)));
+    }
+
+    #[test]
+    fn member_opcodes_carry_name_index() {
+        let ctx = compile(
+            "класс А\n    х\n    функ получить()\n        возврат это.х;\n    конец\nконец\n\
+             класс Б наследует А\n    функ получить()\n        это.х = 1;\n        возврат родитель.получить();\n    конец\nконец",
+        );
+        let name = |constants: &[Value], idx: u32| match &constants[idx as usize] {
+            Value::String(s) => s.to_string(),
+            other => panic!("ожидалась строка, получено {:?}", other),
+        };
+        let classes: Vec<String> = ctx
+            .opcodes
+            .iter()
+            .filter_map(|opcode| match opcode {
+                OpCode::Class(idx) => Some(name(&ctx.constants, *idx)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(classes, ["А", "Б"]);
+
+        let b = functions(&ctx.constants).pop().unwrap();
+        let members: Vec<(OpCode, String)> = b
+            .opcodes
+            .iter()
+            .filter_map(|opcode| match opcode {
+                OpCode::GetProperty(idx) | OpCode::SetProperty(idx) | OpCode::GetSuper(idx) => {
+                    Some((*opcode, name(&b.constants, *idx)))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            members
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["х", "получить"]
+        );
+        assert!(matches!(members[0].0, OpCode::SetProperty(_)));
+        assert!(matches!(members[1].0, OpCode::GetSuper(_)));
+
+        // Операнд — в самой инструкции, и листинг показывает имя рядом с ней
+        let listing = crate::ponos::opcode::listing(&b.opcodes, &b.constants, &b.lines);
+        let line = listing
+            .lines()
+            .find(|line| line.contains("GetSuper"))
+            .unwrap();
+        assert!(line.ends_with("; \"получить\""), "{}", listing);
+    }
 }
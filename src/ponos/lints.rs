@@ -0,0 +1,494 @@
+use crate::ponos::ast::{
+    Annotation, AnnotationArgument, AssignmentTarget, ClassMember, Expression, ImportFilter,
+    Parameter, Program, Statement,
+};
+use crate::ponos::module::ModuleResolver;
+use crate::ponos::parser::{ParseErrorKind, PonosParseError};
+use crate::ponos::span::Span;
+use std::collections::HashSet;
+
+// Предупреждения о коде, который выполняется, но скорее всего написан по
+// ошибке: неиспользуемые локальные переменные и импорты, код после
+// `возврат` и закрытые функции модуля, которые никто не вызывает.
+//
+// Проверка идёт по AST до разрешения имён: `модуль.имя` здесь ещё обращение
+// к полю идентификатора модуля. Имя считается использованным, если оно
+// прочитано где-нибудь в области, в том числе во вложенной функции; имена,
+// начинающиеся с `_`, не проверяются
+
+/// Предупреждение с позицией в исходном коде
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub message: String,
+    pub span: Span,
+}
+
+impl LintWarning {
+    /// Форматирует предупреждение с подсветкой исходного кода
+    pub fn format(&self, source: &str, filename: &str) -> String {
+        PonosParseError::new(ParseErrorKind::Custom(self.message.clone()), self.span)
+            .format_warning(source, filename)
+    }
+}
+
+/// Найти предупреждения в программе, в порядке их позиций
+pub fn lint(program: &Program) -> Vec<LintWarning> {
+    let mut visitor = Visitor::default();
+    visitor.visit_block(&program.statements);
+
+    let mut warnings = Vec::new();
+    for block in &visitor.blocks {
+        unreachable_code(block, &mut warnings);
+    }
+    for body in &visitor.functions {
+        unused_locals(body, &mut warnings);
+    }
+    unused_imports(&program.statements, &visitor.names, &mut warnings);
+    unused_private_functions(&program.statements, &mut warnings);
+    warnings.sort_by_key(|warning| warning.span.start);
+    warnings
+}
+
+/// Операторы блока, стоящие после `возврат`
+fn unreachable_code(block: &[Statement], warnings: &mut Vec<LintWarning>) {
+    let Some(index) = block
+        .iter()
+        .position(|stmt| matches!(stmt, Statement::Return(_)))
+    else {
+        return;
+    };
+    if let Some(next) = block.get(index + 1) {
+        warnings.push(LintWarning {
+            message: "Недостижимый код: он стоит после 'возврат'".to_string(),
+            span: next.span(),
+        });
+    }
+}
+
+/// Переменные функции, которые нигде не читаются
+fn unused_locals(body: &[Statement], warnings: &mut Vec<LintWarning>) {
+    let mut used = Visitor::default();
+    used.visit_block(body);
+
+    let mut declared = Vec::new();
+    local_declarations(body, &mut declared);
+    for (name, span) in declared {
+        if !name.starts_with('_') && !used.names.contains(name) {
+            warnings.push(LintWarning {
+                message: format!("Локальная переменная '{}' не используется", name),
+                span,
+            });
+        }
+    }
+}
+
+/// Переменные, объявленные в теле функции, но не во вложенных функциях
+fn local_declarations<'a>(statements: &'a [Statement], declared: &mut Vec<(&'a str, Span)>) {
+    for stmt in statements {
+        match stmt {
+            Statement::VarDecl(decl) => declared.push((&decl.name, decl.span)),
+            Statement::If(if_stmt) => {
+                local_declarations(&if_stmt.then_branch, declared);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    local_declarations(else_branch, declared);
+                }
+            }
+            Statement::While(while_stmt) => local_declarations(&while_stmt.body, declared),
+            Statement::ForEach(foreach) => local_declarations(&foreach.body, declared),
+            Statement::Try(try_stmt) => {
+                local_declarations(&try_stmt.try_body, declared);
+                for clause in &try_stmt.catches {
+                    local_declarations(&clause.body, declared);
+                }
+                if let Some(finally_body) = &try_stmt.finally_body {
+                    local_declarations(finally_body, declared);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Импорты, пространство имён которых (и имена из списка `показать`) не
+/// встречается в программе. Реэкспорт используется модулями-импортёрами
+fn unused_imports(
+    statements: &[Statement],
+    used: &HashSet<String>,
+    warnings: &mut Vec<LintWarning>,
+) {
+    for stmt in statements {
+        let Statement::Import(import) = stmt else {
+            continue;
+        };
+        if import.is_exported {
+            continue;
+        }
+        let namespace = ModuleResolver::extract_namespace(&import.path, import.alias.clone());
+        let shown = match &import.filter {
+            ImportFilter::Show(names) => names.as_slice(),
+            _ => &[],
+        };
+        if !used.contains(&namespace) && !shown.iter().any(|name| used.contains(name)) {
+            warnings.push(LintWarning {
+                message: format!("Модуль '{}' импортирован, но не используется", import.path),
+                span: import.span,
+            });
+        }
+    }
+}
+
+/// Неэкспортированные функции модуля, которые не вызываются в нём самом.
+/// Файл считается модулем, если в нём есть экспорт. Тесты (`тест_...` и
+/// функции с аннотациями) вызывает не модуль, а тот, кто их запускает
+fn unused_private_functions(statements: &[Statement], warnings: &mut Vec<LintWarning>) {
+    let is_module = statements.iter().any(|stmt| match stmt {
+        Statement::VarDecl(decl) => decl.is_exported,
+        Statement::FuncDecl(func) => func.is_exported,
+        Statement::ClassDecl(class) => class.is_exported,
+        Statement::InterfaceDecl(interface) => interface.is_exported,
+        Statement::Import(import) => import.is_exported,
+        _ => false,
+    });
+    if !is_module {
+        return;
+    }
+
+    let names: Vec<HashSet<String>> = statements
+        .iter()
+        .map(|stmt| {
+            let mut visitor = Visitor::default();
+            visitor.visit_statement(stmt);
+            visitor.names
+        })
+        .collect();
+    for (index, stmt) in statements.iter().enumerate() {
+        let Statement::FuncDecl(func) = stmt else {
+            continue;
+        };
+        if func.is_exported
+            || !func.annotations.is_empty()
+            || func.name.starts_with('_')
+            || func.name.starts_with("тест_")
+        {
+            continue;
+        }
+        let called = names
+            .iter()
+            .enumerate()
+            .any(|(other, names)| other != index && names.contains(&func.name));
+        if !called {
+            warnings.push(LintWarning {
+                message: format!(
+                    "Функция '{}' не экспортируется и не используется в модуле",
+                    func.name
+                ),
+                span: func.span,
+            });
+        }
+    }
+}
+
+/// Обход AST: прочитанные имена, блоки операторов и тела функций
+#[derive(Default)]
+struct Visitor<'a> {
+    /// Идентификаторы, пространства имён модулей и имена в аннотациях типов
+    names: HashSet<String>,
+    blocks: Vec<&'a [Statement]>,
+    /// Тела функций, методов, конструкторов и лямбд
+    functions: Vec<&'a [Statement]>,
+}
+
+impl<'a> Visitor<'a> {
+    fn visit_block(&mut self, statements: &'a [Statement]) {
+        self.blocks.push(statements);
+        for stmt in statements {
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn visit_function(
+        &mut self,
+        params: &'a [Parameter],
+        return_type: Option<&str>,
+        body: &'a [Statement],
+    ) {
+        for param in params {
+            self.type_names(param.type_annotation.as_deref());
+            if let Some(default) = &param.default {
+                self.visit_expression(default);
+            }
+        }
+        self.type_names(return_type);
+        self.functions.push(body);
+        self.visit_block(body);
+    }
+
+    fn visit_annotations(&mut self, annotations: &'a [Annotation]) {
+        for annotation in annotations {
+            self.type_names(Some(&annotation.name));
+            for arg in &annotation.args {
+                match arg {
+                    AnnotationArgument::Positional(value)
+                    | AnnotationArgument::Named { value, .. } => self.visit_expression(value),
+                }
+            }
+        }
+    }
+
+    // Имена модулей и классов в записи типа (`мод.Тип`, `массив<Точка>`)
+    fn type_names(&mut self, text: Option<&str>) {
+        let Some(text) = text else {
+            return;
+        };
+        let words = text
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|word| !word.is_empty());
+        self.names.extend(words.map(str::to_string));
+    }
+
+    fn visit_statement(&mut self, stmt: &'a Statement) {
+        match stmt {
+            Statement::VarDecl(decl) => {
+                self.type_names(decl.type_annotation.as_deref());
+                if let Some(init) = &decl.initializer {
+                    self.visit_expression(init);
+                }
+            }
+            Statement::FuncDecl(func) => {
+                self.visit_annotations(&func.annotations);
+                self.visit_function(&func.params, func.return_type.as_deref(), &func.body);
+            }
+            Statement::ClassDecl(class) => {
+                self.visit_annotations(&class.annotations);
+                self.type_names(class.extends.as_deref());
+                for interface in &class.implements {
+                    self.type_names(Some(interface));
+                }
+                for member in &class.members {
+                    match member {
+                        ClassMember::Field {
+                            type_annotation, ..
+                        } => self.type_names(type_annotation.as_deref()),
+                        ClassMember::StaticField {
+                            type_annotation,
+                            initializer,
+                            ..
+                        } => {
+                            self.type_names(type_annotation.as_deref());
+                            if let Some(value) = initializer {
+                                self.visit_expression(value);
+                            }
+                        }
+                        ClassMember::Method(method) | ClassMember::StaticMethod(method) => {
+                            self.visit_annotations(&method.annotations);
+                            self.visit_function(
+                                &method.params,
+                                method.return_type.as_deref(),
+                                &method.body,
+                            );
+                        }
+                        ClassMember::Constructor(ctor) => {
+                            self.visit_function(&ctor.params, None, &ctor.body)
+                        }
+                    }
+                }
+            }
+            Statement::InterfaceDecl(interface) => {
+                for method in &interface.methods {
+                    for param in &method.params {
+                        self.type_names(param.type_annotation.as_deref());
+                    }
+                    self.type_names(method.return_type.as_deref());
+                }
+            }
+            // Тела объявлений аннотаций не выполняются
+            Statement::AnnotationDecl(_) | Statement::Import(_) => {}
+            Statement::ModuleBlock(block) => self.visit_block(&block.statements),
+            Statement::If(if_stmt) => {
+                self.visit_expression(&if_stmt.condition);
+                self.visit_block(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.visit_block(else_branch);
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.visit_expression(&while_stmt.condition);
+                self.visit_block(&while_stmt.body);
+            }
+            Statement::ForEach(foreach) => {
+                self.visit_expression(&foreach.iterable);
+                self.visit_block(&foreach.body);
+            }
+            Statement::Return(ret) => {
+                if let Some(value) = &ret.value {
+                    self.visit_expression(value);
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.visit_block(&try_stmt.try_body);
+                for clause in &try_stmt.catches {
+                    if let Some(class) = &clause.class {
+                        self.visit_expression(class);
+                    }
+                    self.visit_block(&clause.body);
+                }
+                if let Some(finally_body) = &try_stmt.finally_body {
+                    self.visit_block(finally_body);
+                }
+            }
+            Statement::Throw(throw) => self.visit_expression(&throw.expression),
+            Statement::Assignment(assign) => {
+                self.visit_expression(&assign.value);
+                // Присваивание переменной её не читает
+                match &assign.target {
+                    AssignmentTarget::Identifier(_) => {}
+                    AssignmentTarget::FieldAccess(object, _) => self.visit_expression(object),
+                    AssignmentTarget::Index(object, index) => {
+                        self.visit_expression(object);
+                        self.visit_expression(index);
+                    }
+                }
+            }
+            Statement::Expression(expr) => self.visit_expression(expr),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &'a Expression) {
+        match expr {
+            Expression::Integer(..)
+            | Expression::Number(..)
+            | Expression::String(..)
+            | Expression::Boolean(..)
+            | Expression::Nil(_)
+            | Expression::This(_)
+            | Expression::Super(..) => {}
+            Expression::Identifier(name, _) => {
+                self.names.insert(name.clone());
+            }
+            Expression::ModuleAccess(access) => {
+                self.names.insert(access.namespace.clone());
+            }
+            Expression::Binary(binary) => {
+                self.visit_expression(&binary.left);
+                self.visit_expression(&binary.right);
+            }
+            Expression::Unary(unary) => self.visit_expression(&unary.operand),
+            Expression::Call(call) => {
+                self.visit_expression(&call.callee);
+                for arg in &call.arguments {
+                    self.visit_expression(arg);
+                }
+            }
+            Expression::FieldAccess(access) => self.visit_expression(&access.object),
+            Expression::Lambda(lambda) => {
+                self.visit_function(&lambda.params, lambda.return_type.as_deref(), &lambda.body)
+            }
+            Expression::Index(index) => {
+                self.visit_expression(&index.object);
+                self.visit_expression(&index.index);
+            }
+            Expression::Range(range) => {
+                for bound in [&range.start, &range.end].into_iter().flatten() {
+                    self.visit_expression(bound);
+                }
+            }
+            Expression::ArrayLiteral(array) => {
+                for element in &array.elements {
+                    self.visit_expression(element);
+                }
+            }
+            Expression::DictLiteral(dict) => {
+                for (key, value) in &dict.pairs {
+                    self.visit_expression(key);
+                    self.visit_expression(value);
+                }
+            }
+            Expression::Spread(value, _) => self.visit_expression(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ponos::parser::PonosParser;
+
+    fn warnings(source: &str) -> Vec<String> {
+        let program = PonosParser::new().parse(source.to_string()).unwrap();
+        lint(&program)
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    #[test]
+    fn used_code_has_no_warnings() {
+        let source = r#"
+            использовать "стд/строки" как строки;
+            использовать "стд/массивы" показать сортировать;
+            функ f(а)
+                пер счёт = 0;
+                пер _черновик = 1;
+                пер шаг = 2;
+                функ добавить()
+                    счёт = счёт + шаг;
+                конец
+                добавить();
+                возврат строки.верхний_регистр(а) + счёт;
+            конец
+            вывести(сортировать([2, 1]));
+        "#;
+        assert_eq!(warnings(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unused_and_unreachable_code_is_reported() {
+        let source = r#"
+            использовать "стд/строки" как строки;
+            функ f(а)
+                пер лишняя = а;
+                пер только_запись = 0;
+                только_запись = 1;
+                если а > 0
+                    возврат 1;
+                    вывести(а);
+                конец
+                возврат 2;
+            конец
+        "#;
+        assert_eq!(
+            warnings(source),
+            vec![
+                "Модуль 'стд/строки' импортирован, но не используется",
+                "Локальная переменная 'лишняя' не используется",
+                "Локальная переменная 'только_запись' не используется",
+                "Недостижимый код: он стоит после 'возврат'",
+            ]
+        );
+    }
+
+    #[test]
+    fn private_functions_are_checked_only_in_modules() {
+        let module = r#"
+            экспорт функ площадь(а)
+                возврат квадрат(а);
+            конец
+            функ квадрат(а)
+                возврат а * а;
+            конец
+            функ забытая(н)
+                возврат забытая(н - 1);
+            конец
+            функ тест_площадь()
+                вывести(площадь(2));
+            конец
+        "#;
+        assert_eq!(
+            warnings(module),
+            vec!["Функция 'забытая' не экспортируется и не используется в модуле"]
+        );
+
+        let script = "функ забытая() возврат 1; конец";
+        assert_eq!(warnings(script), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,188 @@
+//! Манифест проекта `понос.toml`
+//!
+//! Поддерживается подмножество TOML: секции `[имя]`, пары `ключ = "значение"`,
+//! однострочные массивы строк `ключ = ["а", "б"]` и комментарии `#`.
+//! Неизвестные ключи и секции игнорируются.
+//!
+//! ```toml
+//! entry = "главный.pns"         # что запускает `ponos run` без имени файла
+//! sources = ["исходники", "библиотеки"]
+//! stdlib = "вендор/стд"
+//!
+//! [dependencies]
+//! графика = "../графика"        # использовать "графика/холст";
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Имя файла манифеста
+pub const MANIFEST_FILE: &str = "понос.toml";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    /// Каталог, в котором лежит манифест
+    pub root: PathBuf,
+    /// Каталог, переопределяющий встроенные модули `стд/...` (ключ `stdlib`)
+    pub stdlib: Option<PathBuf>,
+    /// Файл, который запускается в каталоге проекта (ключ `entry`)
+    pub entry: Option<PathBuf>,
+    /// Каталоги, в которых ищутся модули проекта (ключ `sources`)
+    pub sources: Vec<PathBuf>,
+    /// Зависимости из секции `[dependencies]`: имя и путь к каталогу или файлу
+    /// модуля. Импорт `"имя/модуль"` ищет модуль в каталоге зависимости
+    pub dependencies: Vec<(String, PathBuf)>,
+}
+
+impl Manifest {
+    /// Найти манифест в каталоге `start` или в одном из родительских
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(MANIFEST_FILE))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Прочитать манифест из файла
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Не удалось прочитать манифест {}: {}", path.display(), e))?;
+        let root = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Self::parse(&source, root)
+    }
+
+    /// Разобрать текст манифеста. Относительные пути считаются от `root`
+    pub fn parse(source: &str, root: PathBuf) -> Result<Manifest, String> {
+        let mut manifest = Manifest {
+            root,
+            ..Manifest::default()
+        };
+        let mut section = String::new();
+
+        for (line_no, raw_line) in source.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                format!(
+                    "{}:{}: ожидалась строка вида ключ = \"значение\"",
+                    MANIFEST_FILE,
+                    line_no + 1
+                )
+            })?;
+            let error = |expected: &str| {
+                format!(
+                    "{}:{}: значение должно быть {}",
+                    MANIFEST_FILE,
+                    line_no + 1,
+                    expected
+                )
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match (section.as_str(), key) {
+                ("", "sources") => {
+                    let dirs = parse_string_array(value)
+                        .ok_or_else(|| error("массивом строк в кавычках"))?;
+                    manifest.sources = dirs.iter().map(|d| manifest.root.join(d)).collect();
+                }
+                _ => {
+                    let value = parse_string(value).ok_or_else(|| error("строкой в кавычках"))?;
+                    let path = manifest.root.join(value);
+                    match (section.as_str(), key) {
+                        ("", "stdlib") => manifest.stdlib = Some(path),
+                        ("", "entry") => manifest.entry = Some(path),
+                        ("dependencies", name) => {
+                            manifest.dependencies.push((name.to_string(), path))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(|v| v.to_string())
+}
+
+fn parse_string_array(value: &str) -> Option<Vec<String>> {
+    let items = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if items.is_empty() {
+        return Some(Vec::new());
+    }
+    items
+        .split(',')
+        .map(str::trim)
+        // Допускается запятая после последнего элемента
+        .filter(|item| !item.is_empty())
+        .map(parse_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stdlib_key() {
+        let manifest = Manifest::parse(
+            "# настройки проекта\nstdlib = \"вендор/стд\"\n",
+            PathBuf::from("/проект"),
+        )
+        .unwrap();
+        assert_eq!(manifest.stdlib, Some(PathBuf::from("/проект/вендор/стд")));
+    }
+
+    #[test]
+    fn test_unknown_keys_and_sections_are_ignored() {
+        let manifest = Manifest::parse(
+            "имя = \"пример\"\n[зависимости]\nstdlib = \"не/отсюда\"\n",
+            PathBuf::from("/проект"),
+        )
+        .unwrap();
+        assert_eq!(manifest.stdlib, None);
+    }
+
+    #[test]
+    fn test_parse_project_keys() {
+        let manifest = Manifest::parse(
+            "entry = \"главный.pns\"\nsources = [\"исходники\", \"библиотеки\",]\n\
+             [dependencies]\nграфика = \"../графика\"\n",
+            PathBuf::from("/проект"),
+        )
+        .unwrap();
+        assert_eq!(manifest.entry, Some(PathBuf::from("/проект/главный.pns")));
+        assert_eq!(
+            manifest.sources,
+            vec![
+                PathBuf::from("/проект/исходники"),
+                PathBuf::from("/проект/библиотеки")
+            ]
+        );
+        assert_eq!(
+            manifest.dependencies,
+            vec![("графика".to_string(), PathBuf::from("/проект/../графика"))]
+        );
+        assert!(Manifest::parse("sources = \"один\"\n", PathBuf::new()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_line_is_error() {
+        let result = Manifest::parse("stdlib = путь\n", PathBuf::from("/проект"));
+        assert!(result.unwrap_err().contains(":1:"));
+    }
+}
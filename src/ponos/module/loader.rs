@@ -1,17 +1,25 @@
 use crate::ponos::stdlib;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Загрузчик модулей
 /// Отвечает за разрешение путей модулей и предотвращение циклических зависимостей
 pub struct ModuleLoader {
-    /// Путь к директории stdlib (стандартная библиотека)
+    /// Путь к директории stdlib (стандартная библиотека).
+    /// Модули `стд/...` из этого каталога имеют приоритет над встроенными
     stdlib_path: Option<PathBuf>,
 
     /// Текущий рабочий каталог для разрешения относительных путей
     current_dir: PathBuf,
 
+    /// Каталоги исходников проекта: в них ищутся модули, не найденные рядом
+    /// с импортирующим файлом
+    search_paths: Vec<PathBuf>,
+
+    /// Зависимости проекта: имя -> каталог или файл модуля
+    dependencies: HashMap<String, PathBuf>,
+
     /// Стек загружаемых модулей для обнаружения циклов
     loading_stack: Vec<PathBuf>,
 
@@ -25,21 +33,43 @@ impl ModuleLoader {
         ModuleLoader {
             stdlib_path: None,
             current_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            search_paths: Vec::new(),
+            dependencies: HashMap::new(),
             loading_stack: Vec::new(),
             resolved_cache: HashSet::new(),
         }
     }
 
+    #[allow(dead_code)]
     /// Создать загрузчик с указанным путём к stdlib
     pub fn with_stdlib(stdlib_path: PathBuf) -> Self {
         ModuleLoader {
             stdlib_path: Some(stdlib_path),
             current_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            search_paths: Vec::new(),
+            dependencies: HashMap::new(),
             loading_stack: Vec::new(),
             resolved_cache: HashSet::new(),
         }
     }
 
+    /// Установить каталог stdlib, переопределяющий встроенные модули
+    pub fn set_stdlib_path(&mut self, stdlib_path: PathBuf) {
+        self.stdlib_path = Some(stdlib_path);
+    }
+
+    /// Добавить каталог поиска модулей; каталоги просматриваются в порядке добавления
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.search_paths.push(dir);
+    }
+
+    /// Зарегистрировать зависимость: импорт `"имя"` загружает файл `path`,
+    /// а `"имя/модуль"` — модуль из каталога `path`
+    pub fn add_dependency(&mut self, name: String, path: PathBuf) {
+        self.dependencies.insert(name, path);
+    }
+
+    #[allow(dead_code)]
     /// Установить текущий рабочий каталог
     pub fn set_current_dir(&mut self, dir: PathBuf) {
         self.current_dir = dir;
@@ -48,7 +78,8 @@ impl ModuleLoader {
     /// Разрешить путь к модулю
     ///
     /// Поддерживает:
-    /// - Встроенные stdlib модули (приоритет 1)
+    /// - Модули `стд/...` из локального каталога stdlib (приоритет 0)
+    /// - Встроенные stdlib модули (приоритет 1, запасной вариант для `стд/...`)
     /// - Относительные пути: `./модуль`, `../модуль`
     /// - Абсолютные пути: `/путь/к/модулю`
     /// - Stdlib пути: `стандарт/мат` (если stdlib_path установлен)
@@ -58,13 +89,20 @@ impl ModuleLoader {
         from_file: Option<&Path>,
     ) -> Result<PathBuf, String> {
         // Нормализуем путь (удаляем .pns если есть)
-        let normalized = if module_path.ends_with(".pns") {
-            &module_path[..module_path.len() - 4]
-        } else {
-            module_path
-        };
+        let normalized = module_path.strip_suffix(".pns").unwrap_or(module_path);
+
+        // 0. Локальный каталог stdlib переопределяет встроенные модули;
+        // если модуля в нём нет, используется встроенная версия
+        if let Some(stdlib) = &self.stdlib_path
+            && let Some(name) = normalized.strip_prefix("стд/")
+            && let Ok(path) = self.resolve_stdlib_path(name, stdlib)
+        {
+            return path
+                .canonicalize()
+                .map_err(|e| format!("Не удалось канонизировать путь {}: {}", path.display(), e));
+        }
 
-        // 0. НОВОЕ: Проверяем встроенные stdlib модули (высший приоритет)
+        // 1. Проверяем встроенные stdlib модули
         if stdlib::is_embedded_stdlib(normalized) {
             // Возвращаем виртуальный путь для embedded модулей
             return Ok(PathBuf::from(format!("{}.pns", normalized)));
@@ -81,14 +119,60 @@ impl ModuleLoader {
         }
 
         // 3. Проверяем stdlib путь
-        if let Some(stdlib) = &self.stdlib_path {
-            if let Ok(path) = self.resolve_stdlib_path(module_path, stdlib) {
-                return Ok(path);
-            }
+        if let Some(stdlib) = &self.stdlib_path
+            && let Ok(path) = self.resolve_stdlib_path(module_path, stdlib)
+        {
+            return Ok(path);
         }
 
-        // 4. По умолчанию - относительно текущего каталога
+        // 4. Зависимости проекта
+        if let Some(path) = self.resolve_dependency_path(normalized) {
+            return path;
+        }
+
+        // 5. По умолчанию - относительно текущего каталога, затем каталоги поиска
         self.resolve_default_path(module_path, from_file)
+            .or_else(|e| self.resolve_search_path(module_path).ok_or(e))
+    }
+
+    /// Разрешить импорт из зависимости проекта. `None`, если первый
+    /// компонент пути — не имя зависимости
+    fn resolve_dependency_path(&self, module_path: &str) -> Option<Result<PathBuf, String>> {
+        let (name, rest) = match module_path.split_once('/') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (module_path, None),
+        };
+        let root = self.dependencies.get(name)?;
+        let path = match rest {
+            Some(rest) => root.join(rest),
+            None => root.clone(),
+        };
+        let pns_path = if path.extension().is_some() {
+            path
+        } else {
+            path.with_extension("pns")
+        };
+        Some(pns_path.canonicalize().map_err(|_| {
+            format!(
+                "Модуль '{}' не найден в зависимости '{}' ({})",
+                module_path,
+                name,
+                root.display()
+            )
+        }))
+    }
+
+    /// Найти модуль в каталогах поиска
+    fn resolve_search_path(&self, module_path: &str) -> Option<PathBuf> {
+        self.search_paths.iter().find_map(|dir| {
+            let path = dir.join(module_path);
+            let pns_path = if path.extension().is_some() {
+                path
+            } else {
+                path.with_extension("pns")
+            };
+            pns_path.canonicalize().ok()
+        })
     }
 
     /// Разрешить абсолютный путь
@@ -230,7 +314,8 @@ impl ModuleLoader {
         if self.loading_stack.contains(&canonical_path) {
             let cycle = self.format_cycle(&canonical_path);
             return Err(format!(
-                "Обнаружена циклическая зависимость модулей:\n{}",
+                "Обнаружена циклическая зависимость модулей:\n{}\
+                 Модули не могут реэкспортировать друг друга; взаимный импорт без 'экспорт' допустим",
                 cycle
             ));
         }
@@ -258,6 +343,7 @@ impl ModuleLoader {
         self.resolved_cache.insert(path_to_use);
     }
 
+    #[allow(dead_code)]
     /// Проверить, загружается ли модуль в данный момент
     pub fn is_loading(&self, path: &Path) -> bool {
         // Для embedded модулей используем путь как есть
@@ -274,6 +360,7 @@ impl ModuleLoader {
         self.loading_stack.contains(&path_to_use)
     }
 
+    #[allow(dead_code)]
     /// Проверить, был ли модуль уже загружен
     pub fn is_loaded(&self, path: &Path) -> bool {
         // Для embedded модулей используем путь как есть
@@ -304,14 +391,24 @@ impl ModuleLoader {
         cycle_str
     }
 
+    /// Путь встроенного модуля (`стд/...`), если `path` указывает на него,
+    /// а не на файл
+    pub fn embedded_module(&self, path: &Path) -> Option<String> {
+        // Виртуальные пути встроенных модулей всегда относительные, а файлы
+        // из локального каталога stdlib разрешаются в абсолютные пути
+        if path.is_absolute() {
+            return None;
+        }
+        Self::extract_stdlib_module_path(path).filter(|module| stdlib::is_embedded_stdlib(module))
+    }
+
     /// Прочитать содержимое файла модуля
     pub fn read_module_file(&self, path: &Path) -> Result<String, String> {
-        // Попытаться извлечь путь модуля из полного пути
-        if let Some(module_path) = Self::extract_stdlib_module_path(path) {
-            // Проверить встроенные модули
-            if let Some(source) = stdlib::get_embedded_source(&module_path) {
-                return Ok(source.to_string());
-            }
+        if let Some(source) = self
+            .embedded_module(path)
+            .and_then(|module| stdlib::get_embedded_source(&module))
+        {
+            return Ok(source.to_string());
         }
 
         // Иначе читать из файловой системы
@@ -447,4 +544,43 @@ mod tests {
         // Очистка
         fs::remove_file(temp_path).ok();
     }
+
+    #[test]
+    fn test_stdlib_override_takes_priority_over_embedded() {
+        let stdlib_dir = env::temp_dir().join("ponos_stdlib_override");
+        fs::create_dir_all(&stdlib_dir).unwrap();
+        fs::write(stdlib_dir.join("математика.pns"), "экспорт пер ПИ = 3;").unwrap();
+
+        let mut loader = ModuleLoader::new();
+        loader.set_stdlib_path(stdlib_dir.clone());
+
+        let path = loader.resolve_path("стд/математика", None).unwrap();
+        assert!(path.is_absolute());
+        assert_eq!(
+            loader.read_module_file(&path).unwrap(),
+            "экспорт пер ПИ = 3;"
+        );
+
+        // Очистка
+        fs::remove_dir_all(stdlib_dir).ok();
+    }
+
+    #[test]
+    fn test_stdlib_override_falls_back_to_embedded() {
+        let stdlib_dir = env::temp_dir().join("ponos_stdlib_override_empty");
+        fs::create_dir_all(&stdlib_dir).unwrap();
+
+        let mut loader = ModuleLoader::new();
+        loader.set_stdlib_path(stdlib_dir.clone());
+
+        let path = loader.resolve_path("стд/математика", None).unwrap();
+        assert_eq!(path, PathBuf::from("стд/математика.pns"));
+        assert_eq!(
+            loader.read_module_file(&path).unwrap(),
+            stdlib::get_embedded_source("стд/математика").unwrap()
+        );
+
+        // Очистка
+        fs::remove_dir_all(stdlib_dir).ok();
+    }
 }
@@ -3,12 +3,13 @@ mod resolver;
 
 pub use resolver::{LoadedModule, ModuleResolver};
 
-use crate::ponos::ast::{ModuleBlock, Program, Statement};
+use crate::ponos::ast::{ModuleBlock, Program};
 use crate::ponos::span::Span;
 use crate::ponos::symbol_table::ScopeId;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+#[allow(dead_code)]
 /// Информация об импорте
 #[derive(Debug, Clone)]
 pub struct Import {
@@ -20,6 +21,7 @@ pub struct Import {
     pub span: Span,
 }
 
+#[allow(dead_code)]
 impl Import {
     /// Создать новый импорт
     pub fn new(module_path: String, alias: Option<String>, span: Span) -> Self {
@@ -31,6 +33,7 @@ impl Import {
     }
 }
 
+#[allow(dead_code)]
 /// Информация о модуле
 /// Метаданные модуля + ссылка на его область видимости в SymbolTable
 #[derive(Debug, Clone)]
@@ -51,6 +54,7 @@ pub struct Module {
     pub imports: Vec<Import>,
 }
 
+#[allow(dead_code)]
 impl Module {
     /// Создать новый модуль
     pub fn new(name: String, file_path: PathBuf, ast: Program, scope_id: ScopeId) -> Self {
@@ -74,6 +78,7 @@ impl Module {
     }
 }
 
+#[allow(dead_code)]
 /// Реестр модулей
 /// Управляет загруженными модулями
 #[derive(Debug)]
@@ -82,6 +87,7 @@ pub struct ModuleRegistry {
     modules: HashMap<String, Module>,
 }
 
+#[allow(dead_code)]
 impl ModuleRegistry {
     /// Создать новый реестр модулей
     pub fn new() -> Self {
@@ -121,25 +127,18 @@ impl ModuleRegistry {
     }
 }
 
-/// Присоединить AST модуля к основному AST
+/// Обернуть AST загруженного модуля в ModuleBlock
 ///
-/// Оборачивает statements модуля в ModuleBlock и добавляет в основной AST
-///
-/// # Параметры
-/// - `main_ast`: Основной AST программы
-/// - `loaded_module`: Загруженный модуль с AST и пространством имен
-pub fn merge_module_ast(main_ast: &mut Program, loaded_module: LoadedModule) {
-    // Создаем ModuleBlock для модуля
-    let module_block = ModuleBlock {
+/// ModuleBlock компилируется генератором как отдельная единица компиляции
+/// с пространством имен модуля
+pub fn into_module_block(loaded_module: LoadedModule) -> ModuleBlock {
+    ModuleBlock {
         namespace: loaded_module.namespace,
         statements: loaded_module.ast.statements,
         span: Span::default(),
-    };
-
-    // Добавляем ModuleBlock в основной AST
-    main_ast
-        .statements
-        .push(Statement::ModuleBlock(module_block));
+        file_path: loaded_module.ast.file_path,
+        source: loaded_module.ast.source,
+    }
 }
 
 #[cfg(test)]
@@ -157,9 +156,7 @@ mod tests {
 
     #[test]
     fn test_module_creation() {
-        let program = Program {
-            statements: Vec::new(),
-        };
+        let program = Program::new(Vec::new());
         let module = Module::new(
             "test_module".to_string(),
             PathBuf::from("/path/to/test.pns"),
@@ -172,9 +169,7 @@ mod tests {
 
     #[test]
     fn test_module_add_import() {
-        let program = Program {
-            statements: Vec::new(),
-        };
+        let program = Program::new(Vec::new());
         let mut module = Module::new(
             "test_module".to_string(),
             PathBuf::from("/path/to/test.pns"),
@@ -199,9 +194,7 @@ mod tests {
     fn test_module_registry_register() {
         let mut registry = ModuleRegistry::new();
 
-        let program = Program {
-            statements: Vec::new(),
-        };
+        let program = Program::new(Vec::new());
         let module = Module::new(
             "test_module".to_string(),
             PathBuf::from("/path/to/test.pns"),
@@ -220,9 +213,7 @@ mod tests {
     fn test_module_registry_get() {
         let mut registry = ModuleRegistry::new();
 
-        let program = Program {
-            statements: Vec::new(),
-        };
+        let program = Program::new(Vec::new());
         let module = Module::new(
             "test_module".to_string(),
             PathBuf::from("/path/to/test.pns"),
@@ -274,9 +265,7 @@ mod tests {
             .unwrap();
 
         // Создаём модуль с этой областью
-        let program = Program {
-            statements: Vec::new(),
-        };
+        let program = Program::new(Vec::new());
         let module = Module::new(
             "test_module".to_string(),
             PathBuf::from("/path/to/test.pns"),
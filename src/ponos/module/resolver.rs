@@ -1,11 +1,14 @@
 use super::loader::ModuleLoader;
-use crate::ponos::ast::{Program, Statement};
+use crate::ponos::ast::{ImportFilter, Program, Statement};
 use crate::ponos::native::NativeModuleRegistry;
 use crate::ponos::parser::PonosParser;
 use crate::ponos::span::Span;
+use crate::ponos::stdlib;
 use crate::ponos::symbol_table::{ScopeId, Symbol, SymbolKind, SymbolTable};
+use crate::ponos::typechecker::{Type, signature_of};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 /// Загруженный модуль с метаданными
 #[derive(Debug, Clone)]
@@ -20,6 +23,9 @@ pub struct LoadedModule {
     pub file_path: PathBuf,
     /// ID области видимости модуля в SymbolTable
     pub scope_id: ScopeId,
+    /// Реэкспортируемые модули (вложенные раньше внешних). Их нужно
+    /// выполнить до этого модуля, если они ещё не загружены
+    pub dependencies: Vec<LoadedModule>,
 }
 
 /// Разрешитель модулей - загружает модули и преобразует их в LoadedModule
@@ -45,6 +51,7 @@ impl ModuleResolver {
         }
     }
 
+    #[allow(dead_code)]
     /// Создать разрешитель с указанным путём к stdlib
     pub fn with_stdlib(stdlib_path: PathBuf) -> Self {
         ModuleResolver {
@@ -55,11 +62,36 @@ impl ModuleResolver {
         }
     }
 
+    /// Переопределить встроенные модули `стд/...` файлами из каталога
+    pub fn set_stdlib_path(&mut self, stdlib_path: PathBuf) {
+        self.loader.set_stdlib_path(stdlib_path);
+    }
+
+    /// Забыть загруженный модуль: следующий импорт прочитает файл заново
+    pub fn forget(&mut self, module_path: &Path) {
+        self.loaded_modules.remove(module_path);
+    }
+
+    /// Добавить каталог поиска модулей
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.loader.add_search_path(dir);
+    }
+
+    /// Зарегистрировать зависимость проекта
+    pub fn add_dependency(&mut self, name: String, path: PathBuf) {
+        self.loader.add_dependency(name, path);
+    }
+
     /// Получить ссылку на реестр нативных модулей
     pub fn native_registry(&self) -> &NativeModuleRegistry {
         &self.native_registry
     }
 
+    /// Получить изменяемую ссылку на реестр нативных модулей
+    pub fn native_registry_mut(&mut self) -> &mut NativeModuleRegistry {
+        &mut self.native_registry
+    }
+
     /// Загрузить модуль по пути импорта
     ///
     /// # Параметры
@@ -87,30 +119,32 @@ impl ModuleResolver {
 
         // 3. Проверяем кэш
         if let Some(cached) = self.loaded_modules.get(&module_path) {
-            // Если есть псевдоним, создаем новый LoadedModule с обновленным namespace
-            if let Some(alias_name) = alias {
-                return Ok(LoadedModule {
-                    namespace: alias_name,
-                    ast: cached.ast.clone(),
-                    exports: cached.exports.clone(),
-                    file_path: cached.file_path.clone(),
-                    scope_id: cached.scope_id,
-                });
-            }
-            return Ok(cached.clone());
+            // Пространство имён определяется этим импортом, а не первым:
+            // без псевдонима — по имени файла
+            return Ok(LoadedModule {
+                namespace: Self::extract_namespace(import_path, alias),
+                ..cached.clone()
+            });
         }
 
         // 4. Начинаем загрузку (проверка циклических зависимостей)
         self.loader.begin_loading(&module_path)?;
 
-        // 5. Читаем содержимое файла
-        let source = self.loader.read_module_file(&module_path)?;
-
-        // 6. Парсим модуль
-        let ast = self
-            .parser
-            .parse(source)
+        // 5-6. Читаем и парсим модуль; встроенные модули разбираются один раз
+        let parsed = match self
+            .loader
+            .embedded_module(&module_path)
+            .and_then(|module| stdlib::parse_embedded(&module))
+        {
+            Some(parsed) => parsed,
+            None => {
+                let source = self.loader.read_module_file(&module_path)?;
+                self.parser.parse(source)
+            }
+        };
+        let mut ast = parsed
             .map_err(|e| format!("Ошибка парсинга модуля {}: {:?}", module_path.display(), e))?;
+        ast.file_path = Some(module_path.clone());
 
         // 7. Извлекаем список экспортов
         let exports = Self::collect_exports(&ast);
@@ -119,22 +153,25 @@ impl ModuleResolver {
         let namespace = Self::extract_namespace(import_path, alias.clone());
 
         // 9. Создаем scope для модуля в SymbolTable
-        let scope_id = symbol_table.push_scope();
+        let scope_id = symbol_table.new_child_scope();
 
         // 10. Регистрируем экспортированные символы в SymbolTable
         Self::register_exports_in_symbol_table(&ast, scope_id, symbol_table)?;
 
-        // 11. Создаем LoadedModule
-        let loaded_module = LoadedModule {
+        // 11. Добавляем символы реэкспортируемых модулей
+        let mut loaded_module = LoadedModule {
             namespace,
             ast,
             exports,
             file_path: module_path.clone(),
             scope_id,
+            dependencies: Vec::new(),
         };
+        let reexported = self.load_reexports(&mut loaded_module, symbol_table);
 
         // 12. Завершаем загрузку
         self.loader.end_loading(&module_path);
+        loaded_module.dependencies = reexported?;
 
         // 13. Кэшируем
         self.loaded_modules
@@ -143,6 +180,104 @@ impl ModuleResolver {
         Ok(loaded_module)
     }
 
+    /// Загрузить модули из `экспорт использовать` и добавить их экспорты,
+    /// прошедшие фильтр, к экспортам модуля. Реэкспортированный символ
+    /// помнит, в каком модуле он определён, поэтому `фасад.х` компилируется
+    /// в обращение к глобальной переменной исходного модуля.
+    /// Возвращает реэкспортируемые модули вместе с их зависимостями
+    fn load_reexports(
+        &mut self,
+        module: &mut LoadedModule,
+        symbol_table: &mut SymbolTable,
+    ) -> Result<Vec<LoadedModule>, String> {
+        let reexports: Vec<_> = module
+            .ast
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Import(import) if import.is_exported => Some(import.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let mut dependencies = Vec::new();
+        for import in reexports {
+            let inner = self
+                .load_module(
+                    &import.path,
+                    import.alias.clone(),
+                    Some(&module.file_path),
+                    symbol_table,
+                )
+                .and_then(|inner| Self::apply_filter(inner, &import.filter, symbol_table))
+                .map_err(|e| format!("Реэкспорт '{}': {}", import.path, e))?;
+
+            for name in &inner.exports {
+                let Some(symbol) = symbol_table.lookup_in_scope(inner.scope_id, name) else {
+                    continue;
+                };
+                let mut symbol = symbol.clone();
+                symbol.imported_from = symbol
+                    .imported_from
+                    .or_else(|| Some(inner.namespace.clone()));
+                symbol_table
+                    .define_in_scope(module.scope_id, symbol)
+                    .map_err(|e| format!("Ошибка реэкспорта '{}': {}", name, e))?;
+                module.exports.push(name.clone());
+            }
+
+            let mut inner = inner;
+            dependencies.append(&mut inner.dependencies);
+            dependencies.push(inner);
+        }
+        Ok(dependencies)
+    }
+
+    /// Применить списки `показать`/`скрыть` к загруженному модулю.
+    ///
+    /// Модуль из кэша разделяет область видимости с другими импортами, поэтому
+    /// отфильтрованные экспорты копируются в собственную область этого импорта.
+    /// Скрытые символы остаются в ней с пометкой `is_hidden`, чтобы разрешение
+    /// имён сообщало о них точнее, чем о несуществующих
+    pub fn apply_filter(
+        module: LoadedModule,
+        filter: &ImportFilter,
+        symbol_table: &mut SymbolTable,
+    ) -> Result<LoadedModule, String> {
+        if *filter == ImportFilter::All {
+            return Ok(module);
+        }
+        if let Some(unknown) = filter
+            .names()
+            .iter()
+            .find(|name| !module.exports.contains(name))
+        {
+            return Err(format!("Модуль не экспортирует '{}'", unknown));
+        }
+
+        let scope_id = symbol_table.new_child_scope();
+        for name in &module.exports {
+            let Some(symbol) = symbol_table.lookup_in_scope(module.scope_id, name) else {
+                continue;
+            };
+            let mut symbol = symbol.clone();
+            symbol.is_hidden = !filter.allows(name);
+            symbol_table.define_in_scope(scope_id, symbol)?;
+        }
+
+        let exports = module
+            .exports
+            .iter()
+            .filter(|name| filter.allows(name))
+            .cloned()
+            .collect();
+        Ok(LoadedModule {
+            exports,
+            scope_id,
+            ..module
+        })
+    }
+
     /// Загрузить нативный модуль
     fn load_native_module(
         &mut self,
@@ -154,20 +289,24 @@ impl ModuleResolver {
             .native_registry
             .get_module(import_path)
             .ok_or_else(|| format!("Нативный модуль '{}' не найден", import_path))?;
+        if !self.native_registry.is_allowed(import_path) {
+            return Err(format!(
+                "Модуль '{}' недоступен: песочница запрещает его импорт",
+                import_path
+            ));
+        }
 
         // Определяем имя пространства имен
         let namespace = Self::extract_namespace(import_path, alias.clone());
 
         // Создаем пустой AST (нативные модули не имеют AST)
-        let ast = Program {
-            statements: Vec::new(),
-        };
+        let ast = Program::new(Vec::new());
 
-        // Копируем список экспортов
-        let exports = native_module.exports.clone();
+        // Копируем список экспортов, которые разрешает песочница
+        let exports = self.native_registry.allowed_exports(native_module);
 
         // Создаем scope для модуля в SymbolTable
-        let scope_id = symbol_table.push_scope();
+        let scope_id = symbol_table.new_child_scope();
 
         // Регистрируем экспортированные символы как функции
         for export_name in &exports {
@@ -194,6 +333,7 @@ impl ModuleResolver {
             exports,
             file_path: PathBuf::from(format!("<native:{}>", import_path)),
             scope_id,
+            dependencies: Vec::new(),
         };
 
         Ok(loaded_module)
@@ -205,14 +345,14 @@ impl ModuleResolver {
     /// - `extract_namespace("математика", None)` → `"математика"`
     /// - `extract_namespace("путь/к/математика", None)` → `"математика"`
     /// - `extract_namespace("математика", Some("мат"))` → `"мат"`
-    fn extract_namespace(import_path: &str, alias: Option<String>) -> String {
+    pub(crate) fn extract_namespace(import_path: &str, alias: Option<String>) -> String {
         // Если есть псевдоним, используем его
         if let Some(alias_name) = alias {
             return alias_name;
         }
 
         // Иначе берем последний элемент пути и убираем расширение .pns
-        let last_part = import_path.split('/').last().unwrap_or(import_path);
+        let last_part = import_path.split('/').next_back().unwrap_or(import_path);
 
         // Убираем расширение .pns, если есть
         if let Some(name_without_ext) = last_part.strip_suffix(".pns") {
@@ -252,6 +392,7 @@ impl ModuleResolver {
         exports
     }
 
+    #[allow(dead_code)]
     /// Проверить, загружен ли модуль
     pub fn is_loaded(&self, module_path: &Path) -> bool {
         self.loaded_modules.contains_key(module_path)
@@ -269,12 +410,18 @@ impl ModuleResolver {
         for statement in &ast.statements {
             match statement {
                 Statement::VarDecl(var_decl) if var_decl.is_exported => {
-                    let symbol = Symbol::new(
-                        var_decl.name.clone(),
-                        SymbolKind::Variable,
-                        true,
-                        var_decl.span,
-                    );
+                    let kind = if var_decl.is_const {
+                        SymbolKind::Constant
+                    } else {
+                        SymbolKind::Variable
+                    };
+                    let symbol = Symbol::new(var_decl.name.clone(), kind, true, var_decl.span)
+                        .with_type(
+                            var_decl
+                                .type_annotation
+                                .as_deref()
+                                .map(Type::from_annotation),
+                        );
                     symbol_table
                         .define_in_scope(scope_id, symbol)
                         .map_err(|e| {
@@ -287,7 +434,10 @@ impl ModuleResolver {
                         SymbolKind::Function,
                         true,
                         func_decl.span,
-                    );
+                    )
+                    .with_type(Some(Type::Function(Some(Rc::new(
+                        signature_of(&func_decl.params, &func_decl.return_type),
+                    )))));
                     symbol_table
                         .define_in_scope(scope_id, symbol)
                         .map_err(|e| {
@@ -388,6 +538,7 @@ mod tests {
                     type_annotation: None,
                     initializer: None,
                     is_exported: true,
+                    is_const: false,
                     span: Span::default(),
                 }),
                 Statement::VarDecl(VarDecl {
@@ -395,17 +546,20 @@ mod tests {
                     type_annotation: None,
                     initializer: None,
                     is_exported: false,
+                    is_const: false,
                     span: Span::default(),
                 }),
                 Statement::FuncDecl(FuncDecl {
                     name: "exported_func".to_string(),
                     params: vec![],
+                    return_type: None,
                     body: vec![],
                     annotations: vec![],
                     is_exported: true,
                     span: Span::default(),
                 }),
             ],
+            ..Default::default()
         };
 
         let exports = ModuleResolver::collect_exports(&ast);
@@ -441,6 +595,10 @@ mod tests {
         assert_eq!(loaded.exports.len(), 1);
         assert_eq!(loaded.exports[0], "ПИ");
 
+        // AST запоминает файл и исходный текст для диагностики
+        assert_eq!(loaded.ast.file_path.as_ref(), Some(&loaded.file_path));
+        assert_eq!(&*loaded.ast.source, "экспорт пер ПИ = 3.14;");
+
         // Проверяем, что символ зарегистрирован в SymbolTable
         let scope = symbol_table.get_scope(loaded.scope_id);
         let exports = scope.exported_symbols();
@@ -452,6 +610,51 @@ mod tests {
         fs::remove_dir(temp_dir).ok();
     }
 
+    #[test]
+    fn test_load_module_with_reexport() {
+        let temp_dir = env::temp_dir().join("ponos_test_reexport");
+        fs::create_dir_all(&temp_dir).ok();
+        fs::write(
+            temp_dir.join("внутренний.pns"),
+            "экспорт пер А = 1;\nэкспорт пер Б = 2;",
+        )
+        .unwrap();
+        let facade_path = temp_dir.join("фасад.pns");
+        fs::write(
+            &facade_path,
+            "экспорт использовать \"внутренний\" скрыть Б;\nэкспорт пер В = 3;",
+        )
+        .unwrap();
+
+        let mut resolver = ModuleResolver::new();
+        let mut symbol_table = SymbolTable::new();
+        let loaded = resolver
+            .load_module(facade_path.to_str().unwrap(), None, None, &mut symbol_table)
+            .unwrap();
+
+        assert_eq!(loaded.exports, vec!["В".to_string(), "А".to_string()]);
+        assert_eq!(loaded.dependencies.len(), 1);
+        assert_eq!(loaded.dependencies[0].namespace, "внутренний");
+        // Реэкспорт ссылается на модуль, где символ определён
+        let symbol = symbol_table.lookup_in_scope(loaded.scope_id, "А").unwrap();
+        assert_eq!(symbol.imported_from.as_deref(), Some("внутренний"));
+        assert!(symbol_table.lookup_in_scope(loaded.scope_id, "Б").is_none());
+
+        // Модуль из кэша получает пространство имён своего импорта
+        let cached = resolver
+            .load_module(
+                facade_path.to_str().unwrap(),
+                Some("ф".to_string()),
+                None,
+                &mut symbol_table,
+            )
+            .unwrap();
+        assert_eq!(cached.namespace, "ф");
+        assert_eq!(cached.dependencies.len(), 1);
+
+        fs::remove_dir_all(temp_dir).ok();
+    }
+
     #[test]
     fn test_load_module_with_alias() {
         let temp_dir = env::temp_dir().join("ponos_test_alias");
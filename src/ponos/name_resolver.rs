@@ -1,17 +1,56 @@
-use crate::ponos::ast::{Expression, Program, Statement};
+use crate::ponos::ast::{
+    Annotation, AnnotationArgument, Expression, Parameter, Program, Statement,
+};
 use crate::ponos::symbol_table::{SymbolKind, SymbolTable};
+use std::collections::HashSet;
 
 /// Разрешитель имен - преобразует FieldAccess в ModuleAccess где необходимо
 ///
 /// Проходит по AST и преобразует выражения вида `модуль.символ` из `FieldAccess`
 /// в `ModuleAccess`, если `модуль` является зарегистрированным пространством имен (Symbol::Module)
 /// и символ экспортирован из этого модуля.
-pub struct NameResolver;
+///
+/// Переменная, параметр или функция с именем пространства имен скрывают модуль
+/// с места объявления до конца своей области видимости: там `имя.поле` остаётся
+/// доступом к полю значения.
+#[derive(Default)]
+pub struct NameResolver {
+    scopes: Vec<Scope>,
+}
+
+/// Области видимости повторяют правила генератора: `пер`, вложенные функции и
+/// переменная `перехват` видны до конца функции, а переменные `для каждого` —
+/// только в теле цикла
+#[derive(Default)]
+struct Scope {
+    names: HashSet<String>,
+    is_function: bool, // Область функции или верхнего уровня, а не блока
+}
 
 impl NameResolver {
     /// Создать новый разрешитель имен
     pub fn new() -> Self {
-        NameResolver
+        NameResolver::default()
+    }
+
+    /// Войти в область функции с её параметрами
+    fn push_function_scope<'a>(&mut self, params: impl IntoIterator<Item = &'a String>) {
+        self.scopes.push(Scope {
+            names: params.into_iter().cloned().collect(),
+            is_function: true,
+        });
+    }
+
+    /// Объявить имя в ближайшей области функции
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.iter_mut().rev().find(|scope| scope.is_function) {
+            scope.names.insert(name.to_string());
+        }
+    }
+
+    /// Скрыто ли имя локальным объявлением
+    fn is_shadowed(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.names.contains(name))
     }
 
     /// Разрешить имена в AST программы
@@ -24,10 +63,61 @@ impl NameResolver {
     /// `Ok(())` при успехе, `Err(String)` при ошибке
     pub fn resolve(&mut self, ast: &mut Program, symbol_table: &SymbolTable) -> Result<(), String> {
         // Обрабатываем все statements
-        for statement in &mut ast.statements {
-            self.resolve_statement(statement, symbol_table)?;
+        self.scopes.clear();
+        self.push_function_scope([]);
+        let result = self.resolve_block(&mut ast.statements, symbol_table);
+        self.scopes.clear();
+        result
+    }
+
+    /// Разрешить имена в блоке statements
+    fn resolve_block(
+        &mut self,
+        block: &mut [Statement],
+        symbol_table: &SymbolTable,
+    ) -> Result<(), String> {
+        for stmt in block {
+            self.resolve_statement(stmt, symbol_table)?;
+        }
+        Ok(())
+    }
+
+    /// Разрешить имена в теле функции в её собственной области видимости
+    fn resolve_function_body(
+        &mut self,
+        params: &[Parameter],
+        body: &mut [Statement],
+        symbol_table: &SymbolTable,
+    ) -> Result<(), String> {
+        self.push_function_scope(params.iter().map(|p| &p.name));
+        let result = self.resolve_block(body, symbol_table);
+        self.scopes.pop();
+        result
+    }
+
+    /// Разрешить имена в значениях параметров по умолчанию
+    fn resolve_params(
+        &mut self,
+        params: &mut [Parameter],
+        symbol_table: &SymbolTable,
+    ) -> Result<(), String> {
+        for default in params.iter_mut().filter_map(|p| p.default.as_mut()) {
+            self.resolve_expression(default, symbol_table)?;
         }
+        Ok(())
+    }
 
+    /// Разрешить имена в аргументах аннотаций
+    fn resolve_annotations(
+        &mut self,
+        annotations: &mut [Annotation],
+        symbol_table: &SymbolTable,
+    ) -> Result<(), String> {
+        for arg in annotations.iter_mut().flat_map(|a| a.args.iter_mut()) {
+            let (AnnotationArgument::Positional(value) | AnnotationArgument::Named { value, .. }) =
+                arg;
+            self.resolve_expression(value, symbol_table)?;
+        }
         Ok(())
     }
 
@@ -37,66 +127,133 @@ impl NameResolver {
         stmt: &mut Statement,
         symbol_table: &SymbolTable,
     ) -> Result<(), String> {
-        use crate::ponos::ast::AssignmentTarget;
+        use crate::ponos::ast::{AssignmentTarget, ClassMember};
 
         match stmt {
             Statement::VarDecl(var_decl) => {
                 if let Some(init) = &mut var_decl.initializer {
                     self.resolve_expression(init, symbol_table)?;
                 }
+                // Инициализатор вычисляется до объявления и видит модуль
+                self.declare(&var_decl.name);
             }
             Statement::FuncDecl(func_decl) => {
-                for stmt in &mut func_decl.body {
-                    self.resolve_statement(stmt, symbol_table)?;
+                self.resolve_annotations(&mut func_decl.annotations, symbol_table)?;
+                self.resolve_params(&mut func_decl.params, symbol_table)?;
+                self.declare(&func_decl.name);
+                self.resolve_function_body(&func_decl.params, &mut func_decl.body, symbol_table)?;
+            }
+            Statement::ClassDecl(class_decl) => {
+                self.resolve_annotations(&mut class_decl.annotations, symbol_table)?;
+                self.declare(&class_decl.name);
+                for member in &mut class_decl.members {
+                    match member {
+                        ClassMember::Field { .. } => {}
+                        ClassMember::StaticField { initializer, .. } => {
+                            if let Some(init) = initializer {
+                                self.resolve_expression(init, symbol_table)?;
+                            }
+                        }
+                        ClassMember::Method(method) | ClassMember::StaticMethod(method) => {
+                            self.resolve_annotations(&mut method.annotations, symbol_table)?;
+                            self.resolve_params(&mut method.params, symbol_table)?;
+                            self.resolve_function_body(
+                                &method.params,
+                                &mut method.body,
+                                symbol_table,
+                            )?;
+                        }
+                        ClassMember::Constructor(constructor) => {
+                            self.resolve_params(&mut constructor.params, symbol_table)?;
+                            self.resolve_function_body(
+                                &constructor.params,
+                                &mut constructor.body,
+                                symbol_table,
+                            )?;
+                        }
+                    }
+                }
+            }
+            Statement::InterfaceDecl(interface_decl) => {
+                for method in &mut interface_decl.methods {
+                    self.resolve_params(&mut method.params, symbol_table)?;
                 }
             }
+            Statement::AnnotationDecl(annotation_decl) => {
+                self.resolve_function_body(&[], &mut annotation_decl.body, symbol_table)?;
+            }
             Statement::Assignment(assign) => {
                 // Обрабатываем значение
                 self.resolve_expression(&mut assign.value, symbol_table)?;
 
-                // Обрабатываем target (может быть FieldAccess)
-                if let AssignmentTarget::FieldAccess(obj, _) = &mut assign.target {
-                    self.resolve_expression(obj, symbol_table)?;
+                // Обрабатываем target (объект поля или индексируемое выражение)
+                match &mut assign.target {
+                    AssignmentTarget::Identifier(_) => {}
+                    AssignmentTarget::FieldAccess(obj, _) => {
+                        self.resolve_expression(obj, symbol_table)?;
+                    }
+                    AssignmentTarget::Index(obj, index) => {
+                        self.resolve_expression(obj, symbol_table)?;
+                        self.resolve_expression(index, symbol_table)?;
+                    }
                 }
             }
             Statement::If(if_stmt) => {
                 self.resolve_expression(&mut if_stmt.condition, symbol_table)?;
-                for stmt in &mut if_stmt.then_branch {
-                    self.resolve_statement(stmt, symbol_table)?;
-                }
+                self.resolve_block(&mut if_stmt.then_branch, symbol_table)?;
                 if let Some(else_branch) = &mut if_stmt.else_branch {
-                    for stmt in else_branch {
-                        self.resolve_statement(stmt, symbol_table)?;
-                    }
+                    self.resolve_block(else_branch, symbol_table)?;
                 }
             }
             Statement::While(while_stmt) => {
                 self.resolve_expression(&mut while_stmt.condition, symbol_table)?;
-                for stmt in &mut while_stmt.body {
-                    self.resolve_statement(stmt, symbol_table)?;
-                }
+                self.resolve_block(&mut while_stmt.body, symbol_table)?;
             }
             Statement::ForEach(foreach_stmt) => {
                 self.resolve_expression(&mut foreach_stmt.iterable, symbol_table)?;
-                for stmt in &mut foreach_stmt.body {
-                    self.resolve_statement(stmt, symbol_table)?;
-                }
+                let names = std::iter::once(&foreach_stmt.element_name)
+                    .chain(&foreach_stmt.index_name)
+                    .cloned()
+                    .collect();
+                self.scopes.push(Scope {
+                    names,
+                    is_function: false,
+                });
+                let result = self.resolve_block(&mut foreach_stmt.body, symbol_table);
+                self.scopes.pop();
+                result?;
             }
             Statement::Return(ret_stmt) => {
                 if let Some(value) = &mut ret_stmt.value {
                     self.resolve_expression(value, symbol_table)?;
                 }
             }
+            Statement::Try(try_stmt) => {
+                self.resolve_block(&mut try_stmt.try_body, symbol_table)?;
+                for catch in &mut try_stmt.catches {
+                    if let Some(class) = &mut catch.class {
+                        self.resolve_expression(class, symbol_table)?;
+                    }
+                    if let Some(var) = &catch.var {
+                        self.declare(var);
+                    }
+                    self.resolve_block(&mut catch.body, symbol_table)?;
+                }
+                if let Some(finally_body) = &mut try_stmt.finally_body {
+                    self.resolve_block(finally_body, symbol_table)?;
+                }
+            }
+            Statement::Throw(throw_stmt) => {
+                self.resolve_expression(&mut throw_stmt.expression, symbol_table)?;
+            }
             Statement::Expression(expr) => {
                 self.resolve_expression(expr, symbol_table)?;
             }
             Statement::ModuleBlock(module_block) => {
-                for stmt in &mut module_block.statements {
-                    self.resolve_statement(stmt, symbol_table)?;
-                }
+                self.resolve_function_body(&[], &mut module_block.statements, symbol_table)?;
             }
-            // Остальные statements не содержат выражений
-            _ => {}
+            // Импорт не содержит выражений
+            Statement::Import(_) => {}
         }
         Ok(())
     }
@@ -132,7 +289,10 @@ impl NameResolver {
                 self.resolve_expression(&mut field_access.object, symbol_table)?;
 
                 // Проверяем, является ли объект идентификатором из пространства имен
-                if let Expression::Identifier(name, _span) = &field_access.object {
+                // (и не скрыт локальным объявлением)
+                if let Expression::Identifier(name, _span) = &field_access.object
+                    && !self.is_shadowed(name)
+                {
                     // Ищем символ в SymbolTable
                     if let Some(module_symbol) = symbol_table.lookup(name) {
                         // Проверяем, это модуль?
@@ -145,11 +305,18 @@ impl NameResolver {
                             // Проверяем, что символ экспортирован из модуля
                             let symbol_name = &field_access.field;
                             match symbol_table.lookup_in_scope(module_scope_id, symbol_name) {
+                                Some(symbol) if symbol.is_hidden => {
+                                    return Err(format!(
+                                        "Символ '{}' скрыт при импорте модуля '{}'",
+                                        symbol_name, name
+                                    ));
+                                }
                                 Some(symbol) if symbol.is_exported => {
                                     // Символ существует и экспортирован - преобразуем в ModuleAccess
                                     let module_access = ModuleAccessExpr {
                                         namespace: name.clone(),
                                         symbol: field_access.field.clone(),
+                                        origin: symbol.imported_from.clone(),
                                         span: field_access.span,
                                     };
                                     *expr = Expression::ModuleAccess(Box::new(module_access));
@@ -176,12 +343,43 @@ impl NameResolver {
                 }
             }
             Expression::Lambda(lambda) => {
-                for stmt in &mut lambda.body {
-                    self.resolve_statement(stmt, symbol_table)?;
+                self.resolve_params(&mut lambda.params, symbol_table)?;
+                self.resolve_function_body(&lambda.params, &mut lambda.body, symbol_table)?;
+            }
+            Expression::Index(index) => {
+                self.resolve_expression(&mut index.object, symbol_table)?;
+                self.resolve_expression(&mut index.index, symbol_table)?;
+            }
+            Expression::Range(range) => {
+                for bound in [&mut range.start, &mut range.end].into_iter().flatten() {
+                    self.resolve_expression(bound, symbol_table)?;
+                }
+            }
+            Expression::ArrayLiteral(array) => {
+                for element in &mut array.elements {
+                    self.resolve_expression(element, symbol_table)?;
+                }
+            }
+            Expression::DictLiteral(dict) => {
+                for (key, value) in &mut dict.pairs {
+                    self.resolve_expression(key, symbol_table)?;
+                    self.resolve_expression(value, symbol_table)?;
                 }
             }
-            // Остальные выражения не содержат вложенных выражений или уже разрешены
-            _ => {}
+            Expression::Spread(inner, _) => {
+                self.resolve_expression(inner, symbol_table)?;
+            }
+            // Литералы, идентификаторы и уже разрешённые обращения к модулям
+            // не содержат вложенных выражений
+            Expression::Integer(..)
+            | Expression::Number(..)
+            | Expression::String(..)
+            | Expression::Boolean(..)
+            | Expression::Nil(_)
+            | Expression::Identifier(..)
+            | Expression::ModuleAccess(_)
+            | Expression::This(_)
+            | Expression::Super(..) => {}
         }
         Ok(())
     }
@@ -191,11 +389,11 @@ impl NameResolver {
 mod tests {
     use super::*;
     use crate::ponos::ast::{
-        BinaryExpr, CallExpr, Expression, FieldAccessExpr, ModuleAccessExpr, Program, Statement,
-        VarDecl,
+        BinaryExpr, CallExpr, CatchClause, Expression, FieldAccessExpr, FuncDecl, Program,
+        ReturnStatement, Statement, ThrowStatement, TryStatement, VarDecl,
     };
     use crate::ponos::span::Span;
-    use crate::ponos::symbol_table::{ScopeId, Symbol, SymbolKind, SymbolTable};
+    use crate::ponos::symbol_table::{Symbol, SymbolKind, SymbolTable};
 
     /// Вспомогательная функция для создания SymbolTable с модулем и экспортом
     /// Регистрирует namespace как Symbol::Module в главном scope
@@ -244,8 +442,10 @@ mod tests {
                     span: Span::default(),
                 }))),
                 is_exported: false,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         let symbol_table = create_module_with_export("модуль", "символ");
@@ -278,8 +478,10 @@ mod tests {
                     span: Span::default(),
                 }))),
                 is_exported: false,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         // Создаём модуль, но НЕ регистрируем "объект" как пространство имен
@@ -323,8 +525,10 @@ mod tests {
                     span: Span::default(),
                 }))),
                 is_exported: false,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         let symbol_table = create_module_with_export("модуль", "символ");
@@ -362,6 +566,7 @@ mod tests {
                     span: Span::default(),
                 },
             )))],
+            ..Default::default()
         };
 
         let symbol_table = create_module_with_export("модуль", "функция");
@@ -381,6 +586,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolve_in_try_and_throw() {
+        // Создаем AST: попытка исключение модуль.символ; перехват е модуль.символ; конец
+        let access = || {
+            Expression::FieldAccess(Box::new(FieldAccessExpr {
+                object: Expression::Identifier("модуль".to_string(), Span::default()),
+                field: "символ".to_string(),
+                span: Span::default(),
+            }))
+        };
+        let mut ast = Program {
+            statements: vec![Statement::Try(Box::new(TryStatement {
+                try_body: vec![Statement::Throw(Box::new(ThrowStatement {
+                    expression: access(),
+                    span: Span::default(),
+                }))],
+                catches: vec![CatchClause {
+                    var: Some("е".to_string()),
+                    class: None,
+                    body: vec![Statement::Expression(access())],
+                    span: Span::default(),
+                }],
+                finally_body: None,
+                span: Span::default(),
+            }))],
+            ..Default::default()
+        };
+
+        let symbol_table = create_module_with_export("модуль", "символ");
+        let mut resolver = NameResolver::new();
+        resolver.resolve(&mut ast, &symbol_table).unwrap();
+
+        let Statement::Try(try_stmt) = &ast.statements[0] else {
+            panic!("Expected Try");
+        };
+        assert!(matches!(
+            &try_stmt.try_body[0],
+            Statement::Throw(throw) if matches!(throw.expression, Expression::ModuleAccess(_))
+        ));
+        assert!(matches!(
+            &try_stmt.catches[0].body[0],
+            Statement::Expression(Expression::ModuleAccess(_))
+        ));
+    }
+
+    #[test]
+    fn test_parameter_shadows_namespace() {
+        // Создаем AST: функ ф(модуль) возврат модуль.символ; конец
+        let mut ast = Program {
+            statements: vec![Statement::FuncDecl(FuncDecl {
+                name: "ф".to_string(),
+                params: vec![Parameter {
+                    name: "модуль".to_string(),
+                    type_annotation: None,
+                    default: None,
+                    is_rest: false,
+                    span: Span::default(),
+                }],
+                return_type: None,
+                body: vec![Statement::Return(ReturnStatement {
+                    value: Some(Expression::FieldAccess(Box::new(FieldAccessExpr {
+                        object: Expression::Identifier("модуль".to_string(), Span::default()),
+                        field: "символ".to_string(),
+                        span: Span::default(),
+                    }))),
+                    span: Span::default(),
+                })],
+                annotations: vec![],
+                is_exported: false,
+                span: Span::default(),
+            })],
+            ..Default::default()
+        };
+
+        let symbol_table = create_module_with_export("модуль", "символ");
+        let mut resolver = NameResolver::new();
+        resolver.resolve(&mut ast, &symbol_table).unwrap();
+
+        let Statement::FuncDecl(func_decl) = &ast.statements[0] else {
+            panic!("Expected FuncDecl");
+        };
+        assert!(matches!(
+            &func_decl.body[0],
+            Statement::Return(ret) if matches!(ret.value, Some(Expression::FieldAccess(_)))
+        ));
+    }
+
     // Тест test_is_namespace удалён, так как метод is_namespace больше не существует
 
     #[test]
@@ -405,8 +697,10 @@ mod tests {
                     span: Span::default(),
                 }))),
                 is_exported: false,
+                is_const: false,
                 span: Span::default(),
             })],
+            ..Default::default()
         };
 
         // Создаём два модуля с экспортированными символами
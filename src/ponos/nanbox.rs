@@ -0,0 +1,406 @@
+//! Экспериментальное компактное представление значений (NaN-boxing).
+//!
+//! Включается фичей `nan-boxing`. Это отдельный прототип: VM и её опкоды
+//! работают с `Value`, а модуль повторяет Add, Less, Greater и доступ к полям
+//! поверх своего представления, чтобы измерить выигрыш от 8-байтового
+//! значения (см. `benches/value_repr.rs`).
+//!
+//! Схема кодирования `u64`:
+//! - обычное число `f64` хранится как есть (все NaN приводятся к каноническому);
+//! - `ничто`, `истина`, `ложь` — тихий NaN с маленьким тегом в младших битах;
+//! - целое из 48 бит — тихий NaN с тегом `TAG_INT` и числом в младших 48
+//!   битах; целые больше по модулю хранятся в куче;
+//! - ссылочные значения — тихий NaN с установленным знаковым битом и индексом
+//!   объекта в куче `Heap` в младших 32 битах.
+//!
+//! Куча в эксперименте не освобождает объекты: сборщик мусора — отдельная задача.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ponos::value::{self, Instance, Value};
+
+const QNAN: u64 = 0x7ffc_0000_0000_0000;
+const SIGN_BIT: u64 = 0x8000_0000_0000_0000;
+const HANDLE_MASK: u64 = SIGN_BIT | QNAN;
+
+const TAG_NIL: u64 = 1;
+const TAG_FALSE: u64 = 2;
+const TAG_TRUE: u64 = 3;
+const TAG_INT: u64 = 1 << 48;
+const INT_BITS: u32 = 48;
+const INT_MASK: u64 = (1 << INT_BITS) - 1;
+
+/// Значение, упакованное в 8 байт
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NanBox(u64);
+
+impl NanBox {
+    pub const NIL: NanBox = NanBox(QNAN | TAG_NIL);
+    pub const FALSE: NanBox = NanBox(QNAN | TAG_FALSE);
+    pub const TRUE: NanBox = NanBox(QNAN | TAG_TRUE);
+
+    pub fn number(n: f64) -> Self {
+        if n.is_nan() {
+            // Канонический NaN не пересекается с тегированными значениями
+            NanBox(f64::NAN.to_bits())
+        } else {
+            NanBox(n.to_bits())
+        }
+    }
+
+    pub fn boolean(b: bool) -> Self {
+        if b { Self::TRUE } else { Self::FALSE }
+    }
+
+    /// Целое внутри дескриптора; None, если оно не помещается в 48 бит
+    pub fn int(n: i64) -> Option<Self> {
+        let fits = (n << (64 - INT_BITS)) >> (64 - INT_BITS) == n;
+        fits.then_some(NanBox(QNAN | TAG_INT | (n as u64 & INT_MASK)))
+    }
+
+    fn handle(index: u32) -> Self {
+        NanBox(HANDLE_MASK | index as u64)
+    }
+
+    pub fn is_number(self) -> bool {
+        self.0 & QNAN != QNAN
+    }
+
+    pub fn as_number(self) -> Option<f64> {
+        if self.is_number() {
+            Some(f64::from_bits(self.0))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_int(self) -> Option<i64> {
+        if self.0 & (HANDLE_MASK | TAG_INT) == QNAN | TAG_INT {
+            // Сдвиг влево и арифметический вправо восстанавливает знак
+            Some(((self.0 << (64 - INT_BITS)) as i64) >> (64 - INT_BITS))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bool(self) -> Option<bool> {
+        match self {
+            Self::TRUE => Some(true),
+            Self::FALSE => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn is_nil(self) -> bool {
+        self == Self::NIL
+    }
+
+    fn as_handle(self) -> Option<usize> {
+        if self.0 & HANDLE_MASK == HANDLE_MASK {
+            Some((self.0 & 0xffff_ffff) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Debug for NanBox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(n) = self.as_number() {
+            write!(f, "NanBox({})", n)
+        } else if let Some(n) = self.as_int() {
+            write!(f, "NanBox(целое {})", n)
+        } else if let Some(b) = self.as_bool() {
+            write!(f, "NanBox({})", b)
+        } else if self.is_nil() {
+            write!(f, "NanBox(ничто)")
+        } else {
+            write!(f, "NanBox(#{})", self.as_handle().unwrap_or_default())
+        }
+    }
+}
+
+/// Хранилище ссылочных значений, на которые указывают `NanBox`-дескрипторы
+#[derive(Debug, Default)]
+pub struct Heap {
+    objects: Vec<Value>,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Heap {
+            objects: Vec::new(),
+        }
+    }
+
+    /// Количество объектов в куче
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Упаковать значение: числа, целые из 48 бит, булевы и ничто хранятся
+    /// внутри дескриптора, остальное помещается в кучу
+    pub fn encode(&mut self, value: Value) -> NanBox {
+        match value {
+            Value::Number(n) => NanBox::number(n),
+            Value::Int(n) => NanBox::int(n).unwrap_or_else(|| self.allocate(value)),
+            Value::Boolean(b) => NanBox::boolean(b),
+            Value::Nil => NanBox::NIL,
+            other => self.allocate(other),
+        }
+    }
+
+    fn allocate(&mut self, value: Value) -> NanBox {
+        let index = self.objects.len();
+        self.objects.push(value);
+        NanBox::handle(index as u32)
+    }
+
+    /// Распаковать значение обратно в `Value`
+    pub fn decode(&self, boxed: NanBox) -> Value {
+        if let Some(n) = boxed.as_number() {
+            return Value::Number(n);
+        }
+        if let Some(n) = boxed.as_int() {
+            return Value::Int(n);
+        }
+        if let Some(b) = boxed.as_bool() {
+            return Value::Boolean(b);
+        }
+        match boxed.as_handle() {
+            Some(index) => self.objects[index].clone(),
+            None => Value::Nil,
+        }
+    }
+
+    fn get(&self, boxed: NanBox) -> Option<&Value> {
+        boxed.as_handle().map(|index| &self.objects[index])
+    }
+
+    fn instance(&self, boxed: NanBox) -> Result<&Rc<RefCell<Instance>>, String> {
+        match self.get(boxed) {
+            Some(Value::Instance(instance)) => Ok(instance),
+            _ => Err("Ожидался экземпляр класса".to_string()),
+        }
+    }
+
+    /// Аналог OpCode::Add: быстрый путь для чисел и целых, конкатенация строк
+    /// через кучу
+    pub fn add(&mut self, a: NanBox, b: NanBox) -> Result<NanBox, String> {
+        if let (Some(x), Some(y)) = (a.as_number(), b.as_number()) {
+            return Ok(NanBox::number(x + y));
+        }
+        if let (Some(x), Some(y)) = (a.as_int(), b.as_int())
+            && let Some(sum) = NanBox::int(x + y)
+        {
+            // Сумма двух 48-битных целых не переполняет i64
+            return Ok(sum);
+        }
+
+        let concatenated = match (self.decode(a), self.decode(b)) {
+            (Value::String(s1), Value::String(s2)) => format!("{}{}", s1, s2),
+            (Value::Int(n), Value::String(s)) => format!("{}{}", n, s),
+            (Value::String(s), Value::Int(n)) => format!("{}{}", s, n),
+            (Value::Number(n), Value::String(s)) => format!("{}{}", n, s),
+            (Value::String(s), Value::Number(n)) => format!("{}{}", s, n),
+            (a, b) => match value::arithmetic(&a, &b, i64::checked_add, |x, y| x + y) {
+                Some(sum) => return Ok(self.encode(sum)),
+                None => return Err("Оператор + поддерживает только числа и строки".to_string()),
+            },
+        };
+        Ok(self.encode(Value::String(concatenated.into())))
+    }
+
+    /// Аналог OpCode::Greater
+    pub fn greater(&self, a: NanBox, b: NanBox) -> Result<NanBox, String> {
+        if let (Some(x), Some(y)) = (a.as_number(), b.as_number()) {
+            return Ok(NanBox::boolean(x > y));
+        }
+        if let (Some(x), Some(y)) = (a.as_int(), b.as_int()) {
+            return Ok(NanBox::boolean(x > y));
+        }
+        let ordering = value::compare(&self.decode(a), &self.decode(b))?;
+        Ok(NanBox::boolean(ordering == Some(Ordering::Greater)))
+    }
+
+    /// Аналог OpCode::Less
+    pub fn less(&self, a: NanBox, b: NanBox) -> Result<NanBox, String> {
+        if let (Some(x), Some(y)) = (a.as_number(), b.as_number()) {
+            return Ok(NanBox::boolean(x < y));
+        }
+        if let (Some(x), Some(y)) = (a.as_int(), b.as_int()) {
+            return Ok(NanBox::boolean(x < y));
+        }
+        let ordering = value::compare(&self.decode(a), &self.decode(b))?;
+        Ok(NanBox::boolean(ordering == Some(Ordering::Less)))
+    }
+
+    /// Аналог OpCode::GetProperty для полей экземпляра
+    pub fn get_property(&mut self, receiver: NanBox, name: &str) -> Result<NanBox, String> {
+        let field = self
+            .instance(receiver)?
+            .borrow()
+            .fields
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Свойство '{}' не найдено", name))?;
+        Ok(self.encode(field))
+    }
+
+    /// Аналог OpCode::SetProperty для полей экземпляра
+    pub fn set_property(
+        &mut self,
+        receiver: NanBox,
+        name: &str,
+        value: NanBox,
+    ) -> Result<(), String> {
+        let value = self.decode(value);
+        self.instance(receiver)?
+            .borrow_mut()
+            .fields
+            .insert(name.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Применить числовую операцию (Sub, Mul, Div, Mod) к упакованным операндам.
+/// Целые в 48 битах идут через `int_op`, как в `value::arithmetic`; при
+/// переполнении и для смешанных операндов результат вещественный
+pub fn number_op(
+    a: NanBox,
+    b: NanBox,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> Result<NanBox, String> {
+    if let (Some(x), Some(y)) = (a.as_int(), b.as_int())
+        && let Some(result) = int_op(x, y).and_then(NanBox::int)
+    {
+        return Ok(result);
+    }
+    let as_float = |boxed: NanBox| boxed.as_number().or(boxed.as_int().map(|n| n as f64));
+    match (as_float(a), as_float(b)) {
+        (Some(x), Some(y)) => Ok(NanBox::number(float_op(x, y))),
+        (None, _) => Err("Левый операнд не число".to_string()),
+        (_, None) => Err("Правый операнд не число".to_string()),
+    }
+}
+
+/// Создать экземпляр без класса-владельца методов (для тестов и бенчмарков)
+pub fn new_instance(class_name: &str, fields: HashMap<String, Value>) -> Value {
+    let class = value::Class {
+        name: class_name.to_string(),
+        methods: HashMap::new(),
+        fields: fields.keys().cloned().collect(),
+        parent: None,
+        statics: RefCell::default(),
+    };
+    Value::Instance(Rc::new(RefCell::new(Instance {
+        class: Rc::new(class),
+        fields,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nanbox_is_eight_bytes() {
+        assert_eq!(std::mem::size_of::<NanBox>(), 8);
+    }
+
+    #[test]
+    fn immediates_round_trip() {
+        let heap = Heap::new();
+        for n in [0.0, -1.5, f64::INFINITY, f64::MIN_POSITIVE, 1e300] {
+            assert_eq!(NanBox::number(n).as_number(), Some(n));
+        }
+        assert!(NanBox::number(f64::NAN).as_number().unwrap().is_nan());
+        assert_eq!(heap.decode(NanBox::TRUE), Value::Boolean(true));
+        assert_eq!(heap.decode(NanBox::FALSE), Value::Boolean(false));
+        assert_eq!(heap.decode(NanBox::NIL), Value::Nil);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn strings_live_on_heap() {
+        let mut heap = Heap::new();
+        let hello = heap.encode(Value::from("при"));
+        let world = heap.encode(Value::from("вет"));
+        assert!(!hello.is_number());
+
+        let joined = heap.add(hello, world).unwrap();
+        assert_eq!(heap.decode(joined), Value::from("привет"));
+        assert_eq!(heap.len(), 3);
+    }
+
+    #[test]
+    fn arithmetic_and_comparison() {
+        let mut heap = Heap::new();
+        let a = NanBox::number(7.0);
+        let b = NanBox::number(2.0);
+
+        assert_eq!(heap.add(a, b).unwrap().as_number(), Some(9.0));
+        assert_eq!(
+            number_op(a, b, i64::checked_rem, |x, y| x % y)
+                .unwrap()
+                .as_number(),
+            Some(1.0)
+        );
+        assert_eq!(heap.greater(a, b), Ok(NanBox::TRUE));
+        assert_eq!(heap.less(a, b), Ok(NanBox::FALSE));
+        assert!(number_op(NanBox::TRUE, b, i64::checked_sub, |x, y| x - y).is_err());
+    }
+
+    #[test]
+    fn integers_stay_inline() {
+        let mut heap = Heap::new();
+        for n in [0, -1, 42, (1 << 47) - 1, -(1 << 47)] {
+            let boxed = heap.encode(Value::Int(n));
+            assert_eq!(boxed.as_int(), Some(n));
+            assert_eq!(boxed.as_number(), None);
+            assert_eq!(heap.decode(boxed), Value::Int(n));
+        }
+        assert!(heap.is_empty());
+
+        let seven = NanBox::int(7).unwrap();
+        let two = NanBox::int(2).unwrap();
+        assert_eq!(heap.add(seven, two).unwrap().as_int(), Some(9));
+        assert_eq!(heap.less(two, seven), Ok(NanBox::TRUE));
+        let product = number_op(seven, two, i64::checked_mul, |x, y| x * y).unwrap();
+        assert_eq!(product.as_int(), Some(14));
+        let mixed = number_op(seven, NanBox::number(0.5), i64::checked_sub, |x, y| x - y);
+        assert_eq!(mixed.unwrap().as_number(), Some(6.5));
+
+        // Целое вне 48 бит и переполнение при сложении уходят в кучу
+        let big = heap.encode(Value::Int(i64::MAX));
+        assert_eq!(heap.len(), 1);
+        assert_eq!(heap.decode(big), Value::Int(i64::MAX));
+        let max = NanBox::int((1 << 47) - 1).unwrap();
+        let sum = heap.add(max, max).unwrap();
+        assert_eq!(heap.decode(sum), Value::Int((1 << 48) - 2));
+    }
+
+    #[test]
+    fn properties_go_through_handles() {
+        let mut heap = Heap::new();
+        let mut fields = HashMap::new();
+        fields.insert("x".to_string(), Value::Number(1.0));
+        let point = heap.encode(new_instance("Точка", fields));
+
+        heap.set_property(point, "x", NanBox::number(5.0)).unwrap();
+        assert_eq!(
+            heap.get_property(point, "x").unwrap().as_number(),
+            Some(5.0)
+        );
+        assert!(heap.get_property(point, "y").is_err());
+        assert!(heap.get_property(NanBox::NIL, "x").is_err());
+    }
+}
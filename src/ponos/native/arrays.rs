@@ -0,0 +1,219 @@
+use crate::ponos::typechecker::Type;
+use crate::ponos::value::{self, Value};
+use crate::ponos::vm::NativeContext;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// массивы.сортировать(массив, компаратор?) -> Array
+///
+/// Возвращает новый отсортированный массив, исходный не меняется. Компаратор —
+/// функция Ponos от двух элементов: отрицательное число, если первый должен
+/// идти раньше, положительное — если позже, 0 — если они равны. Без
+/// компаратора сравниваются числа и строки. Сортировка устойчивая
+pub fn array_sort(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let (array, comparator) = match args {
+        [Value::Array(array)] => (array, None),
+        [Value::Array(array), comparator] => (array, Some(comparator)),
+        [_] | [_, _] => return Err("сортировать ожидает массив первым аргументом".to_string()),
+        _ => return Err("сортировать требует 1 или 2 аргумента: массив и компаратор".to_string()),
+    };
+    sort_items(ctx, array.borrow().clone(), comparator)
+}
+
+/// массивы.сортировать_по(массив, компаратор) -> Array
+///
+/// То же, что сортировать с компаратором, но компаратор обязателен.
+/// Противоречивый компаратор (например, всегда -1) не ошибка: результат —
+/// какая-то перестановка элементов
+pub fn array_sort_by(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [array, comparator] = args else {
+        return Err("сортировать_по требует 2 аргумента: массив и компаратор".to_string());
+    };
+    let items = array_items("сортировать_по", array)?;
+    expect_function("сортировать_по", "вторым", comparator)?;
+    sort_items(ctx, items, Some(comparator))
+}
+
+// Устойчивая сортировка копии элементов; первая ошибка компаратора или
+// сравнения прерывает сортировку
+fn sort_items(
+    ctx: &mut NativeContext,
+    items: Vec<Value>,
+    comparator: Option<&Value>,
+) -> Result<Value, String> {
+    if comparator.is_none() {
+        check_default_orderable(&items)?;
+    }
+    let sorted = merge_sort(items, |a, b| match comparator {
+        Some(comparator) => ctx
+            .call(comparator, &[a.clone(), b.clone()])
+            .and_then(|result| comparator_ordering(&result)),
+        None => default_ordering(a, b),
+    })?;
+    Ok(Value::Array(Rc::new(RefCell::new(sorted))))
+}
+
+// Восходящая сортировка слиянием. В отличие от slice::sort_by она не требует
+// от компаратора полного порядка: с противоречивым компаратором порядок
+// результата не определён, но это по-прежнему перестановка элементов
+fn merge_sort(
+    mut items: Vec<Value>,
+    mut compare: impl FnMut(&Value, &Value) -> Result<Ordering, String>,
+) -> Result<Vec<Value>, String> {
+    let len = items.len();
+    let mut merged = Vec::with_capacity(len);
+    let mut width = 1;
+    while width < len {
+        for start in (0..len).step_by(2 * width) {
+            let middle = (start + width).min(len);
+            let end = (start + 2 * width).min(len);
+            let (mut left, mut right) = (start, middle);
+            while left < middle && right < end {
+                // Правый элемент идёт раньше, только если он строго меньше:
+                // так равные элементы сохраняют исходный порядок
+                if compare(&items[left], &items[right])? == Ordering::Greater {
+                    merged.push(items[right].clone());
+                    right += 1;
+                } else {
+                    merged.push(items[left].clone());
+                    left += 1;
+                }
+            }
+            merged.extend_from_slice(&items[left..middle]);
+            merged.extend_from_slice(&items[right..end]);
+        }
+        std::mem::swap(&mut items, &mut merged);
+        merged.clear();
+        width *= 2;
+    }
+    Ok(items)
+}
+
+/// массивы.карта(массив, функ) -> Array
+///
+/// Новый массив из результатов функции Ponos для каждого элемента
+pub fn array_map(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [array, callee] = args else {
+        return Err("карта требует 2 аргумента: массив и функция".to_string());
+    };
+    let items = array_items("карта", array)?;
+    expect_function("карта", "вторым", callee)?;
+    let mut mapped = Vec::with_capacity(items.len());
+    for item in items {
+        mapped.push(ctx.call(callee, &[item])?);
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(mapped))))
+}
+
+/// массивы.фильтр(массив, предикат) -> Array
+///
+/// Новый массив из элементов, для которых предикат вернул истину
+pub fn array_filter(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [array, predicate] = args else {
+        return Err("фильтр требует 2 аргумента: массив и предикат".to_string());
+    };
+    let items = array_items("фильтр", array)?;
+    expect_function("фильтр", "вторым", predicate)?;
+    let mut kept = Vec::new();
+    for item in items {
+        match ctx.call(predicate, std::slice::from_ref(&item))? {
+            Value::Boolean(true) => kept.push(item),
+            Value::Boolean(false) => {}
+            other => {
+                return Err(format!(
+                    "фильтр: предикат должен вернуть логический, получено: {}",
+                    Type::of_value(&other)
+                ));
+            }
+        }
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(kept))))
+}
+
+/// массивы.свернуть(массив, начальное, функ) -> значение
+///
+/// Свёртка слева: функ(функ(начальное, м[0]), м[1])...; для пустого массива —
+/// начальное значение
+pub fn array_fold(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [array, initial, callee] = args else {
+        return Err(
+            "свернуть требует 3 аргумента: массив, начальное значение и функция".to_string(),
+        );
+    };
+    let items = array_items("свернуть", array)?;
+    expect_function("свернуть", "третьим", callee)?;
+    let mut accumulator = initial.clone();
+    for item in items {
+        accumulator = ctx.call(callee, &[accumulator, item])?;
+    }
+    Ok(accumulator)
+}
+
+// Копия элементов: функция может менять исходный массив во время обхода
+fn array_items(name: &str, value: &Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(array) => Ok(array.borrow().clone()),
+        other => Err(format!(
+            "{}: первым аргументом должен быть массив, получено: {}",
+            name,
+            Type::of_value(other)
+        )),
+    }
+}
+
+fn expect_function(name: &str, position: &str, value: &Value) -> Result<(), String> {
+    if matches!(Type::of_value(value), Type::Function(_)) {
+        return Ok(());
+    }
+    Err(format!(
+        "{}: {} аргументом должна быть функция, получено: {}",
+        name,
+        position,
+        Type::of_value(value)
+    ))
+}
+
+fn comparator_ordering(result: &Value) -> Result<Ordering, String> {
+    match result.as_number() {
+        Some(n) if n < 0.0 => Ok(Ordering::Less),
+        Some(n) if n > 0.0 => Ok(Ordering::Greater),
+        Some(_) => Ok(Ordering::Equal),
+        None => Err(format!(
+            "Компаратор должен вернуть число, получено: {}",
+            Type::of_value(result)
+        )),
+    }
+}
+
+// Без компаратора все элементы должны быть числами (целые и вещественные
+// сравниваются между собой) или все строками. Проверка идёт до сортировки и
+// по порядку элементов, поэтому ошибка не зависит от алгоритма сортировки
+fn check_default_orderable(items: &[Value]) -> Result<(), String> {
+    let kind = |value: &Value| match value {
+        Value::String(_) => Some("строки"),
+        _ if value.as_number().is_some() => Some("числа"),
+        _ => None,
+    };
+    let Some(first) = items.first() else {
+        return Ok(());
+    };
+    let expected = kind(first);
+    for (index, item) in items.iter().enumerate() {
+        if kind(item).is_none() || kind(item) != expected {
+            return Err(format!(
+                "Без компаратора можно сортировать только числа или только строки: \
+                 элемент {} — {}, а элемент 0 — {}; передайте компаратор",
+                index,
+                Type::of_value(item),
+                Type::of_value(first)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn default_ordering(a: &Value, b: &Value) -> Result<Ordering, String> {
+    // NaN при сортировке считается равным любому числу
+    Ok(value::compare(a, b)?.unwrap_or(Ordering::Equal))
+}
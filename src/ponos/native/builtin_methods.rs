@@ -1,4 +1,4 @@
-use crate::ponos::value::Value;
+use crate::ponos::value::{self, Value};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -12,6 +12,7 @@ pub enum TypeDiscriminant {
     Array,
     String,
     Dict,
+    StringBuilder,
 }
 
 /// Реестр встроенных методов для базовых типов
@@ -19,6 +20,12 @@ pub struct BuiltinMethodRegistry {
     methods: HashMap<(TypeDiscriminant, String), BuiltinMethod>,
 }
 
+impl Default for BuiltinMethodRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BuiltinMethodRegistry {
     pub fn new() -> Self {
         let mut registry = BuiltinMethodRegistry {
@@ -38,15 +45,32 @@ impl BuiltinMethodRegistry {
         registry.register(TypeDiscriminant::Dict, "значения", dict_values);
         registry.register(TypeDiscriminant::Dict, "очистить", dict_clear);
 
+        // Регистрация методов для построителя строк
+        registry.register(TypeDiscriminant::StringBuilder, "добавить", builder_append);
+        registry.register(
+            TypeDiscriminant::StringBuilder,
+            "в_строку",
+            builder_to_string,
+        );
+        registry.register(
+            TypeDiscriminant::StringBuilder,
+            "собрать",
+            builder_to_string,
+        );
+        registry.register(TypeDiscriminant::StringBuilder, "очистить", builder_clear);
+
         registry
     }
 
     fn register(&mut self, type_disc: TypeDiscriminant, method_name: &str, method: BuiltinMethod) {
-        self.methods.insert((type_disc, method_name.to_string()), method);
+        self.methods
+            .insert((type_disc, method_name.to_string()), method);
     }
 
     pub fn get(&self, type_disc: TypeDiscriminant, method_name: &str) -> Option<BuiltinMethod> {
-        self.methods.get(&(type_disc, method_name.to_string())).copied()
+        self.methods
+            .get(&(type_disc, method_name.to_string()))
+            .copied()
     }
 }
 
@@ -57,7 +81,10 @@ impl BuiltinMethodRegistry {
 /// Метод добавить(элемент) - добавляет элемент в конец массива
 fn array_push(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if args.len() != 1 {
-        return Err(format!("Метод 'добавить' принимает 1 аргумент, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'добавить' принимает 1 аргумент, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
@@ -72,7 +99,10 @@ fn array_push(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод очистить() - очищает массив
 fn array_clear(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        return Err(format!("Метод 'очистить' не принимает аргументы, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'очистить' не принимает аргументы, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
@@ -91,11 +121,14 @@ fn array_clear(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод длина() - возвращает длину строки
 fn string_length(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        return Err(format!("Метод 'длина' не принимает аргументы, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'длина' не принимает аргументы, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
-        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
         _ => Err("Метод 'длина' можно вызывать только на строке".to_string()),
     }
 }
@@ -103,7 +136,10 @@ fn string_length(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод разделить(разделитель) - разбивает строку по разделителю
 fn string_split(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if args.len() != 1 {
-        return Err(format!("Метод 'разделить' принимает 1 аргумент, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'разделить' принимает 1 аргумент, передано: {}",
+            args.len()
+        ));
     }
 
     let separator = match &args[0] {
@@ -115,7 +151,7 @@ fn string_split(receiver: &Value, args: &[Value]) -> Result<Value, String> {
         Value::String(s) => {
             let parts: Vec<Value> = s
                 .split(separator)
-                .map(|part| Value::String(part.to_string()))
+                .map(|part| Value::String(part.to_string().into()))
                 .collect();
             Ok(Value::Array(Rc::new(RefCell::new(parts))))
         }
@@ -130,7 +166,10 @@ fn string_split(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод ключи() - возвращает массив ключей словаря
 fn dict_keys(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        return Err(format!("Метод 'ключи' не принимает аргументы, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'ключи' не принимает аргументы, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
@@ -138,11 +177,7 @@ fn dict_keys(receiver: &Value, args: &[Value]) -> Result<Value, String> {
             let dict_borrow = dict.borrow();
             let keys: Vec<Value> = dict_borrow
                 .keys()
-                .map(|k| match k {
-                    crate::ponos::value::ValueKey::String(s) => Value::String(s.clone()),
-                    crate::ponos::value::ValueKey::Number(n) => Value::Number(n.into_inner()),
-                    crate::ponos::value::ValueKey::Boolean(b) => Value::Boolean(*b),
-                })
+                .map(crate::ponos::value::ValueKey::to_value)
                 .collect();
             Ok(Value::Array(Rc::new(RefCell::new(keys))))
         }
@@ -153,7 +188,10 @@ fn dict_keys(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод значения() - возвращает массив значений словаря
 fn dict_values(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        return Err(format!("Метод 'значения' не принимает аргументы, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'значения' не принимает аргументы, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
@@ -169,7 +207,10 @@ fn dict_values(receiver: &Value, args: &[Value]) -> Result<Value, String> {
 /// Метод очистить() - очищает словарь
 fn dict_clear(receiver: &Value, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        return Err(format!("Метод 'очистить' не принимает аргументы, передано: {}", args.len()));
+        return Err(format!(
+            "Метод 'очистить' не принимает аргументы, передано: {}",
+            args.len()
+        ));
     }
 
     match receiver {
@@ -180,3 +221,64 @@ fn dict_clear(receiver: &Value, args: &[Value]) -> Result<Value, String> {
         _ => Err("Метод 'очистить' можно вызывать только на словаре".to_string()),
     }
 }
+
+// ============================================================================
+// Методы для построителя строк
+// ============================================================================
+
+/// Метод добавить(значение) - дописывает значение в буфер и возвращает
+/// построитель, чтобы вызовы можно было объединять в цепочку
+fn builder_append(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    let Value::StringBuilder(buf) = receiver else {
+        return Err("Метод 'добавить' можно вызывать только на построителе строк".to_string());
+    };
+
+    match args {
+        [Value::String(s)] => buf.borrow_mut().push_str(s),
+        [Value::Int(n)] => value::write_int(&mut buf.borrow_mut(), *n),
+        [Value::Number(n)] => value::write_number(&mut buf.borrow_mut(), *n),
+        [Value::Boolean(b)] => buf.borrow_mut().push_str(if *b { "true" } else { "false" }),
+        [_] => return Err("добавить принимает строку, число или булево значение".to_string()),
+        _ => {
+            return Err(format!(
+                "Метод 'добавить' принимает 1 аргумент, передано: {}",
+                args.len()
+            ));
+        }
+    }
+    Ok(receiver.clone())
+}
+
+/// Метод в_строку() - возвращает накопленный текст; построитель можно
+/// продолжать использовать и после этого
+fn builder_to_string(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err(format!(
+            "Метод 'в_строку' не принимает аргументов, передано: {}",
+            args.len()
+        ));
+    }
+
+    match receiver {
+        Value::StringBuilder(buf) => Ok(Value::String(buf.borrow().clone().into())),
+        _ => Err("Метод 'в_строку' можно вызывать только на построителе строк".to_string()),
+    }
+}
+
+/// Метод очистить() - удаляет накопленный текст, сохраняя выделенную память
+fn builder_clear(receiver: &Value, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err(format!(
+            "Метод 'очистить' не принимает аргументов, передано: {}",
+            args.len()
+        ));
+    }
+
+    match receiver {
+        Value::StringBuilder(buf) => {
+            buf.borrow_mut().clear();
+            Ok(Value::Nil)
+        }
+        _ => Err("Метод 'очистить' можно вызывать только на построителе строк".to_string()),
+    }
+}
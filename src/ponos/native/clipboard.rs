@@ -0,0 +1,143 @@
+use crate::ponos::value::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Буфер обмена и браузер через системные утилиты (pbcopy, wl-copy, xclip, ...),
+// чтобы не тянуть зависимости от оконных библиотек. Модуль собирается
+// только с фичей `desktop`
+
+/// Команды для чтения буфера обмена в порядке предпочтения
+fn read_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        vec![("pbpaste", vec![])]
+    } else if cfg!(windows) {
+        vec![(
+            "powershell",
+            vec!["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+        )]
+    } else {
+        vec![
+            ("wl-paste", vec!["--no-newline"]),
+            ("xclip", vec!["-selection", "clipboard", "-o"]),
+            ("xsel", vec!["--clipboard", "--output"]),
+        ]
+    }
+}
+
+/// Команды для записи в буфер обмена (текст передается через stdin)
+fn write_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", vec![])]
+    } else if cfg!(windows) {
+        vec![("clip", vec![])]
+    } else {
+        vec![
+            ("wl-copy", vec![]),
+            ("xclip", vec!["-selection", "clipboard"]),
+            ("xsel", vec!["--clipboard", "--input"]),
+        ]
+    }
+}
+
+/// Команда, открывающая адрес в браузере по умолчанию
+fn browser_command(url: &str) -> (&'static str, Vec<String>) {
+    if cfg!(target_os = "macos") {
+        ("open", vec![url.to_string()])
+    } else if cfg!(windows) {
+        // rundll32 не проходит через cmd, поэтому '&' в адресе безопасен
+        (
+            "rundll32",
+            vec!["url.dll,FileProtocolHandler".to_string(), url.to_string()],
+        )
+    } else {
+        ("xdg-open", vec![url.to_string()])
+    }
+}
+
+const NO_TOOL: &str = "Не найдена утилита для работы с буфером обмена";
+
+/// буфер_обмена.прочитать() -> строка
+pub fn clipboard_read(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("прочитать не принимает аргументов".to_string());
+    }
+
+    for (program, program_args) in read_commands() {
+        let Ok(output) = Command::new(program)
+            .args(&program_args)
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(Value::String(
+                String::from_utf8_lossy(&output.stdout).to_string().into(),
+            ));
+        }
+    }
+
+    Err(NO_TOOL.to_string())
+}
+
+/// буфер_обмена.записать(текст) -> ничто
+pub fn clipboard_write(args: &[Value]) -> Result<Value, String> {
+    let text = match args {
+        [Value::String(s)] => s,
+        [_] => return Err("Текст для буфера обмена должен быть строкой".to_string()),
+        _ => return Err("записать требует 1 аргумент: текст".to_string()),
+    };
+
+    for (program, program_args) in write_commands() {
+        let Ok(mut child) = Command::new(program)
+            .args(&program_args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Ошибка записи в буфер обмена: {}", e))?;
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("Ошибка записи в буфер обмена: {}", e))?;
+        if status.success() {
+            return Ok(Value::Nil);
+        }
+    }
+
+    Err(NO_TOOL.to_string())
+}
+
+/// буфер_обмена.открыть_в_браузере(урл) -> ничто
+pub fn open_in_browser(args: &[Value]) -> Result<Value, String> {
+    let url = match args {
+        [Value::String(s)] => s,
+        [_] => return Err("Адрес должен быть строкой".to_string()),
+        _ => return Err("открыть_в_браузере требует 1 аргумент: адрес".to_string()),
+    };
+
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!(
+            "Поддерживаются только адреса http:// и https://, получено '{}'",
+            url
+        ));
+    }
+
+    let (program, program_args) = browser_command(url);
+    Command::new(program)
+        .args(&program_args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Не удалось открыть браузер: {}", e))?;
+
+    Ok(Value::Nil)
+}
@@ -0,0 +1,80 @@
+use crate::ponos::value::{Dict, Value, ValueKey};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type DictRef = Rc<RefCell<Dict>>;
+
+fn expect_dict<'a>(name: &str, value: &'a Value) -> Result<&'a DictRef, String> {
+    match value {
+        Value::Dict(dict) => Ok(dict),
+        _ => Err(format!("{}: аргумент должен быть словарём", name)),
+    }
+}
+
+fn expect_args(name: &str, args: &[Value], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} требует {} аргумент(а), передано: {}",
+            name,
+            count,
+            args.len()
+        ))
+    }
+}
+
+/// словари.ключи(словарь) -> Array
+pub fn dict_keys(args: &[Value]) -> Result<Value, String> {
+    expect_args("ключи", args, 1)?;
+    let dict = expect_dict("ключи", &args[0])?;
+    let keys = dict.borrow().keys().map(ValueKey::to_value).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(keys))))
+}
+
+/// словари.значения(словарь) -> Array
+pub fn dict_values(args: &[Value]) -> Result<Value, String> {
+    expect_args("значения", args, 1)?;
+    let dict = expect_dict("значения", &args[0])?;
+    let values = dict.borrow().values().cloned().collect();
+    Ok(Value::Array(Rc::new(RefCell::new(values))))
+}
+
+/// словари.содержит_ключ(словарь, ключ) -> Boolean
+pub fn dict_contains_key(args: &[Value]) -> Result<Value, String> {
+    expect_args("содержит_ключ", args, 2)?;
+    let dict = expect_dict("содержит_ключ", &args[0])?;
+    let key = ValueKey::from_value(&args[1])?;
+    Ok(Value::Boolean(dict.borrow().contains_key(&key)))
+}
+
+/// словари.удалить(словарь, ключ) -> удалённое значение или ничто
+pub fn dict_remove(args: &[Value]) -> Result<Value, String> {
+    expect_args("удалить", args, 2)?;
+    let dict = expect_dict("удалить", &args[0])?;
+    let key = ValueKey::from_value(&args[1])?;
+    Ok(dict.borrow_mut().remove(&key).unwrap_or(Value::Nil))
+}
+
+/// словари.объединить(а, б) -> новый словарь; при совпадении ключей побеждает б
+pub fn dict_merge(args: &[Value]) -> Result<Value, String> {
+    expect_args("объединить", args, 2)?;
+    let first = expect_dict("объединить", &args[0])?;
+    let second = expect_dict("объединить", &args[1])?;
+
+    let mut merged = first.borrow().clone();
+    merged.extend(
+        second
+            .borrow()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    Ok(Value::Dict(Rc::new(RefCell::new(merged))))
+}
+
+/// словари.размер(словарь) -> Int
+pub fn dict_size(args: &[Value]) -> Result<Value, String> {
+    expect_args("размер", args, 1)?;
+    let dict = expect_dict("размер", &args[0])?;
+    Ok(Value::Int(dict.borrow().len() as i64))
+}
@@ -0,0 +1,228 @@
+use crate::ponos::value::Value;
+
+// Двоичные кодировки строк. Кодирующие функции принимают строку (берутся её
+// байты UTF-8) или массив байт, как у фс.читать_байты; раскодирующие
+// возвращают строку и бросают исключение, если результат — не UTF-8
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn bytes_arg(name: &str, args: &[Value]) -> Result<Vec<u8>, String> {
+    match args {
+        [Value::String(s)] => Ok(s.as_bytes().to_vec()),
+        [Value::Array(items)] => items
+            .borrow()
+            .iter()
+            .map(|item| match item {
+                Value::Int(n) if (0..=255).contains(n) => Ok(*n as u8),
+                Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Ok(*n as u8),
+                _ => Err(format!(
+                    "{}: массив должен состоять из байт (целых чисел 0..255)",
+                    name
+                )),
+            })
+            .collect(),
+        [_] => Err(format!(
+            "{}: аргумент должен быть строкой или массивом байт",
+            name
+        )),
+        _ => Err(format!("{} ожидает 1 аргумент", name)),
+    }
+}
+
+fn text_arg<'a>(name: &str, args: &'a [Value]) -> Result<&'a str, String> {
+    match args {
+        [Value::String(s)] => Ok(s),
+        [_] => Err(format!("{}: аргумент должен быть строкой", name)),
+        _ => Err(format!("{} ожидает 1 аргумент", name)),
+    }
+}
+
+fn utf8_result(name: &str, bytes: Vec<u8>) -> Result<Value, String> {
+    String::from_utf8(bytes)
+        .map(Value::from)
+        .map_err(|_| format!("{}: результат не является текстом UTF-8", name))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let error = || format!("Некорректная строка base64: '{}'", text);
+    // Переводы строк встречаются в длинных base64-блоках, их пропускаем
+    let symbols: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !symbols.len().is_multiple_of(4) {
+        return Err(error());
+    }
+
+    let mut out = Vec::with_capacity(symbols.len() / 4 * 3);
+    for (index, chunk) in symbols.chunks(4).enumerate() {
+        let is_last = index == symbols.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return Err(error());
+        }
+        let mut triple = 0u32;
+        for &symbol in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|&c| c == symbol)
+                .ok_or_else(error)?;
+            triple = triple << 6 | value as u32;
+        }
+        triple <<= 6 * padding as u32;
+        out.extend_from_slice(&triple.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(out)
+}
+
+/// Процентное кодирование: всё, кроме A-Z a-z 0-9 - _ . ~, записывается как %XX
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Раскодировать %XX; '+' означает пробел, как в параметрах адресной строки
+pub(crate) fn percent_decode(text: &str) -> Result<Vec<u8>, String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Некорректная %-последовательность в '{}'", text))?;
+                out.push(byte);
+                i += 2;
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// кодировки.base64_закодировать(строка | байты) -> строка
+pub fn encode_base64(args: &[Value]) -> Result<Value, String> {
+    let bytes = bytes_arg("base64_закодировать", args)?;
+    Ok(Value::String(base64_encode(&bytes).into()))
+}
+
+/// кодировки.base64_раскодировать(строка) -> строка
+pub fn decode_base64(args: &[Value]) -> Result<Value, String> {
+    let bytes = base64_decode(text_arg("base64_раскодировать", args)?)?;
+    utf8_result("base64_раскодировать", bytes)
+}
+
+/// кодировки.hex_закодировать(строка | байты) -> строка (строчные цифры)
+pub fn encode_hex(args: &[Value]) -> Result<Value, String> {
+    let bytes = bytes_arg("hex_закодировать", args)?;
+    Ok(Value::String(
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+            .into(),
+    ))
+}
+
+/// кодировки.hex_раскодировать(строка) -> строка
+pub fn decode_hex(args: &[Value]) -> Result<Value, String> {
+    let text = text_arg("hex_раскодировать", args)?;
+    let error = || format!("Некорректная шестнадцатеричная строка: '{}'", text);
+    if !text.is_ascii() || !text.len().is_multiple_of(2) {
+        return Err(error());
+    }
+    let bytes = (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| error()))
+        .collect::<Result<Vec<u8>, String>>()?;
+    utf8_result("hex_раскодировать", bytes)
+}
+
+/// кодировки.url_закодировать(строка) -> строка
+pub fn encode_url(args: &[Value]) -> Result<Value, String> {
+    let text = text_arg("url_закодировать", args)?;
+    Ok(Value::String(percent_encode(text.as_bytes()).into()))
+}
+
+/// кодировки.url_раскодировать(строка) -> строка
+pub fn decode_url(args: &[Value]) -> Result<Value, String> {
+    let bytes = percent_decode(text_arg("url_раскодировать", args)?)?;
+    utf8_result("url_раскодировать", bytes)
+}
+
+/// кодировки.длина_в_байтах(строка) -> число байт в UTF-8
+pub fn byte_length(args: &[Value]) -> Result<Value, String> {
+    let text = text_arg("длина_в_байтах", args)?;
+    Ok(Value::Number(text.len() as f64))
+}
+
+/// кодировки.длина_в_символах(строка) -> число символов Unicode (как длина())
+pub fn char_length(args: &[Value]) -> Result<Value, String> {
+    let text = text_arg("длина_в_символах", args)?;
+    Ok(Value::Number(text.chars().count() as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(base64_encode(plain.as_bytes()), encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+        }
+        for bad in ["Zg=", "Z===", "Zg==Zg==", "Zm9*"] {
+            assert!(base64_decode(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn percent_encoding_round_trips() {
+        let text = "мир & a+b/c";
+        let encoded = percent_encode(text.as_bytes());
+        assert_eq!(encoded, "%D0%BC%D0%B8%D1%80%20%26%20a%2Bb%2Fc");
+        assert_eq!(percent_decode(&encoded).unwrap(), text.as_bytes());
+        assert_eq!(percent_decode("a+b").unwrap(), b"a b");
+        assert!(percent_decode("100%").is_err());
+    }
+}
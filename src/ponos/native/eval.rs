@@ -0,0 +1,111 @@
+use crate::ponos::ast::{AstNode, Statement};
+use crate::ponos::generator::Generator;
+use crate::ponos::name_resolver::NameResolver;
+use crate::ponos::parser::PonosParser;
+use crate::ponos::semantic::SemanticChecker;
+use crate::ponos::span::Span;
+use crate::ponos::symbol_table::{Symbol, SymbolKind, SymbolTable};
+use crate::ponos::typechecker::{Type, TypeChecker};
+use crate::ponos::value::Value;
+use crate::ponos::vm::NativeContext;
+use std::path::PathBuf;
+
+// выполнить_код(строка): разбор, разрешение имён, проверка и выполнение кода
+// в текущей VM. Код видит глобальные переменные программы и модули, которые
+// она импортировала (через префикс модуля), а его глобальные переменные
+// остаются в программе после выполнения
+
+/// Имя исходного текста в сообщениях об ошибках и стеке вызовов
+const SOURCE_NAME: &str = "<код>";
+
+/// выполнить_код(код) -> значение последнего выражения или ничто
+///
+/// Ошибка разбора или проверки кода бросается как Ошибка с позицией в коде;
+/// исключение, брошенное самим кодом, выходит из выполнить_код без изменений
+pub fn run_code(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let source = match args {
+        [Value::String(source)] => source,
+        [other] => {
+            return Err(format!(
+                "выполнить_код: аргумент должен быть строкой, получено: {}",
+                Type::of_value(other)
+            ));
+        }
+        _ => return Err("выполнить_код() требует ровно 1 аргумент: код".to_string()),
+    };
+
+    let mut ast = PonosParser::new()
+        .parse(source.to_string())
+        .map_err(|e| located(&e.to_string(), e.span, source))?;
+    ast.file_path = Some(PathBuf::from(SOURCE_NAME));
+    if let Some(import) = ast
+        .statements
+        .iter()
+        .find(|statement| matches!(statement, Statement::Import(_)))
+    {
+        return Err(located(
+            "импорт в выполняемом коде не поддерживается, импортируйте модуль в программе",
+            import.span(),
+            source,
+        ));
+    }
+
+    let symbol_table = loaded_modules(ctx);
+    NameResolver::new()
+        .resolve(&mut ast, &symbol_table)
+        .map_err(|e| format!("выполнить_код: {}", e))?;
+    let is_global = |name: &str| ctx.is_global_defined(name);
+    let semantic = SemanticChecker::new(&symbol_table, &is_global)
+        .check(&ast)
+        .into_iter()
+        .map(|error| (error.message, error.span));
+    let types = TypeChecker::new(&symbol_table)
+        .check(&ast)
+        .into_iter()
+        .map(|error| (error.message, error.span));
+    if let Some((message, span)) = semantic.chain(types).next() {
+        return Err(located(&message, span, source));
+    }
+
+    let has_value = matches!(ast.statements.last(), Some(Statement::Expression(_)));
+    let code = Generator::new()
+        .generate(AstNode::Program(ast))
+        .map_err(|e| located(&e.message, e.span, source))?;
+    let value = ctx.execute(code)?;
+    Ok(if has_value { value } else { Value::Nil })
+}
+
+/// Таблица символов с модулями, загруженными в VM, чтобы `модуль.имя`
+/// разрешалось в обращение к экспорту модуля
+fn loaded_modules(ctx: &NativeContext) -> SymbolTable {
+    let mut symbol_table = SymbolTable::new();
+    for (namespace, exports) in ctx.loaded_modules() {
+        let scope_id = symbol_table.new_child_scope();
+        for (name, global) in exports {
+            let mut symbol = Symbol::new(name.clone(), SymbolKind::Variable, true, Span::default());
+            let origin = global.strip_suffix(&format!("::{}", name));
+            if origin != Some(namespace.as_str()) {
+                symbol.imported_from = origin.map(str::to_string);
+            }
+            symbol_table
+                .define_in_scope(scope_id, symbol)
+                .expect("имена экспортов модуля уникальны");
+        }
+        symbol_table
+            .define(Symbol::new_module(namespace, scope_id, Span::default()))
+            .expect("пространства имён модулей уникальны");
+    }
+    symbol_table
+}
+
+// Сообщение об ошибке с позицией в выполняемом коде
+fn located(message: &str, span: Span, source: &str) -> String {
+    let (start, _) = span.to_location(source);
+    format!(
+        "выполнить_код: {} в {}:{}:{}",
+        message,
+        SOURCE_NAME,
+        start.line + 1,
+        start.column + 1
+    )
+}
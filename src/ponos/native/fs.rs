@@ -1,45 +1,174 @@
-use crate::ponos::value::{Class, Instance, Value};
+use crate::ponos::value::{Class, Instance, Value, ValueKey};
+use crate::ponos::vm::NativeContext;
 use std::{
     cell::RefCell,
     collections::HashMap,
-    fs::{self, DirEntry},
+    fs::{self},
     rc::Rc,
     vec,
 };
 
-pub fn fs_read(args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("читать ожидает 1 аргумент (путь)".to_string());
+/// Кодировки, поддерживаемые при чтении текстовых файлов
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    Cp1251,
+}
+
+impl Encoding {
+    fn from_name(name: &str) -> Result<Encoding, String> {
+        match name.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "cp1251" | "windows-1251" => Ok(Encoding::Cp1251),
+            _ => Err(format!(
+                "Неизвестная кодировка '{}': поддерживаются utf-8 и cp1251",
+                name
+            )),
+        }
+    }
+}
+
+/// Параметры чтения: { "кодировка": "utf-8" | "cp1251", "с_заменой": булево }
+struct ReadOptions {
+    encoding: Encoding,
+    /// Заменять недопустимые последовательности на U+FFFD вместо ошибки
+    lossy: bool,
+}
+
+impl ReadOptions {
+    fn from_value(value: Option<&Value>) -> Result<ReadOptions, String> {
+        let mut options = ReadOptions {
+            encoding: Encoding::Utf8,
+            lossy: false,
+        };
+        let dict = match value {
+            None => return Ok(options),
+            Some(Value::Dict(dict)) => dict.borrow(),
+            Some(_) => return Err("Параметры чтения должны быть словарём".to_string()),
+        };
+
+        for (key, value) in dict.iter() {
+            match (key, value) {
+                (ValueKey::String(k), Value::String(name)) if **k == "кодировка" => {
+                    options.encoding = Encoding::from_name(name)?;
+                }
+                (ValueKey::String(k), Value::Boolean(lossy)) if **k == "с_заменой" => {
+                    options.lossy = *lossy;
+                }
+                (ValueKey::String(k), _) if **k == "кодировка" || **k == "с_заменой" =>
+                {
+                    return Err(format!("Неверный тип параметра '{}'", k));
+                }
+                _ => return Err(format!("Неизвестный параметр чтения: {:?}", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Символы cp1251 для байтов 0x80..=0xBF; байт 0x98 не определён
+const CP1251_HIGH: &str = "ЂЃ‚ѓ„…†‡€‰Љ‹ЊЌЋЏђ‘’“”•–—\u{fffd}™љ›њќћџ\
+                           \u{a0}ЎўЈ¤Ґ¦§Ё©Є«¬\u{ad}®Ї°±Ііґµ¶·ё№є»јЅѕї";
+
+fn decode_cp1251_byte(byte: u8) -> Option<char> {
+    match byte {
+        0x00..=0x7F => Some(byte as char),
+        0x80..=0xBF => CP1251_HIGH
+            .chars()
+            .nth((byte - 0x80) as usize)
+            .filter(|&c| c != char::REPLACEMENT_CHARACTER),
+        // 0xC0..=0xFF — А..я подряд
+        _ => char::from_u32(0x0410 + (byte - 0xC0) as u32),
+    }
+}
+
+fn decode(bytes: Vec<u8>, options: &ReadOptions) -> Result<String, String> {
+    match options.encoding {
+        Encoding::Utf8 if options.lossy => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        Encoding::Utf8 => String::from_utf8(bytes).map_err(|e| {
+            format!(
+                "Файл не в кодировке UTF-8: недопустимый байт на позиции {}",
+                e.utf8_error().valid_up_to()
+            )
+        }),
+        Encoding::Cp1251 => bytes
+            .iter()
+            .enumerate()
+            .map(|(position, &byte)| match decode_cp1251_byte(byte) {
+                Some(c) => Ok(c),
+                None if options.lossy => Ok(char::REPLACEMENT_CHARACTER),
+                None => Err(format!(
+                    "Байт 0x{:02X} на позиции {} не определён в cp1251",
+                    byte, position
+                )),
+            })
+            .collect(),
+    }
+}
+
+/// фс.читать(путь, параметры = {}) -> строка
+///
+/// По умолчанию файл читается как UTF-8, ошибка декодирования бросается
+/// исключением. Параметры: "кодировка" ("utf-8", "cp1251"), "с_заменой".
+/// В сопрограмме файл читается в фоне, не останавливая другие задачи
+pub fn fs_read(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("читать ожидает 1-2 аргумента (путь, параметры)".to_string());
     }
 
     let path = match &args[0] {
-        Value::String(s) => s,
+        Value::String(s) => s.to_string(),
         _ => return Err("Путь должен быть строкой".to_string()),
     };
+    let options = ReadOptions::from_value(args.get(1))?;
 
-    let content = fs::read_to_string(path).map_err(|e| format!("Ошибка чтения: {}", e))?;
+    ctx.run_blocking(move || {
+        let text = fs::read(path)
+            .map_err(|e| format!("Ошибка чтения: {}", e))
+            .and_then(|bytes| decode(bytes, &options));
+        Box::new(move || text.map(Value::from))
+    })
+}
 
-    Ok(Value::String(content))
+/// фс.читать_байты(путь) -> массив чисел 0..255
+pub fn fs_read_bytes(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("читать_байты ожидает 1 аргумент (путь)".to_string());
+    }
+
+    let path = match &args[0] {
+        Value::String(s) => s.to_string(),
+        _ => return Err("Путь должен быть строкой".to_string()),
+    };
+
+    ctx.run_blocking(move || {
+        let bytes = fs::read(path).map_err(|e| format!("Ошибка чтения: {}", e));
+        Box::new(move || {
+            let values = bytes?.into_iter().map(|b| Value::Int(b as i64)).collect();
+            Ok(Value::Array(Rc::new(RefCell::new(values))))
+        })
+    })
 }
 
-pub fn fs_write(args: &[Value]) -> Result<Value, String> {
+pub fn fs_write(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
     if args.len() != 2 {
         return Err("писать ожидает 2 аргумента".to_string());
     }
 
     let path = match &args[0] {
-        Value::String(s) => s,
+        Value::String(s) => s.to_string(),
         _ => return Err("Путь должен быть строкой".to_string()),
     };
 
     let content = match &args[1] {
-        Value::String(s) => s,
+        Value::String(s) => s.to_string(),
         _ => return Err("Содержимое должно быть строкой".to_string()),
     };
 
-    fs::write(path, content).map_err(|e| format!("Ошибка записи: {}", e))?;
-
-    Ok(Value::Nil)
+    ctx.run_blocking(move || {
+        let written = fs::write(path, content).map_err(|e| format!("Ошибка записи: {}", e));
+        Box::new(move || written.map(|_| Value::Nil))
+    })
 }
 
 pub fn fs_exists(args: &[Value]) -> Result<Value, String> {
@@ -48,7 +177,7 @@ pub fn fs_exists(args: &[Value]) -> Result<Value, String> {
     }
 
     let path = match &args[0] {
-        Value::String(s) => s,
+        Value::String(s) => s.as_str(),
         _ => return Err("Путь должен быть строкой".to_string()),
     };
 
@@ -61,7 +190,7 @@ pub fn fs_delete(args: &[Value]) -> Result<Value, String> {
     }
 
     let path = match &args[0] {
-        Value::String(s) => s,
+        Value::String(s) => s.as_str(),
         _ => return Err("Путь должен быть строкой".to_string()),
     };
 
@@ -75,7 +204,7 @@ pub fn fs_read_dir(args: &[Value]) -> Result<Value, String> {
         return Err("Неверное количество параметров!".to_string());
     }
     let path = match &args[0] {
-        Value::String(s) => s.clone(),
+        Value::String(s) => s.to_string(),
         _ => return Err("Параметр должен быть строкой!".to_string()),
     };
     let mut res: Vec<Value> = Vec::new();
@@ -88,7 +217,12 @@ pub fn fs_read_dir(args: &[Value]) -> Result<Value, String> {
             fields: HashMap::from([
                 (
                     "имя".to_string(),
-                    Value::String(info.file_name().into_string().expect("Cannot transform")),
+                    Value::String(
+                        info.file_name()
+                            .into_string()
+                            .expect("Cannot transform")
+                            .into(),
+                    ),
                 ),
                 (
                     "это_директория".to_string(),
@@ -96,8 +230,15 @@ pub fn fs_read_dir(args: &[Value]) -> Result<Value, String> {
                 ),
                 (
                     "абсолютный_путь".to_string(),
-                    Value::String(info.path().canonicalize().unwrap().display().to_string())
-                )
+                    Value::String(
+                        info.path()
+                            .canonicalize()
+                            .unwrap()
+                            .display()
+                            .to_string()
+                            .into(),
+                    ),
+                ),
             ]),
         };
         res.push(Value::Instance(Rc::new(RefCell::new(instance))));
@@ -106,7 +247,7 @@ pub fn fs_read_dir(args: &[Value]) -> Result<Value, String> {
 }
 
 fn create_file_info_class() -> Class {
-    let class = Class {
+    Class {
         name: "ИнформацияОФайле".to_string(),
         methods: HashMap::new(),
         fields: vec![
@@ -115,15 +256,14 @@ fn create_file_info_class() -> Class {
             "имя".to_string(),
         ],
         parent: None,
-    };
-
-    class
+        statics: RefCell::default(),
+    }
 }
 
 // Заготовка под ООП-style API
 
 fn create_file_class() -> Class {
-    let class = Class {
+    Class {
         name: "Файл".to_string(),
         methods: HashMap::new(),
         fields: vec![
@@ -132,9 +272,8 @@ fn create_file_class() -> Class {
             "имя".to_string(),
         ],
         parent: None,
-    };
-
-    class
+        statics: RefCell::default(),
+    }
 }
 
 pub fn file_constructor(args: &[Value]) -> Result<Value, String> {
@@ -142,7 +281,7 @@ pub fn file_constructor(args: &[Value]) -> Result<Value, String> {
         return Err("Неверное количество параметров!".to_string());
     }
     let path = match &args[0] {
-        Value::String(s) => s.clone(),
+        Value::String(s) => s.to_string(),
         _ => return Err("Параметр должен быть строкой!".to_string()),
     };
 
@@ -153,25 +292,43 @@ pub fn file_constructor(args: &[Value]) -> Result<Value, String> {
 
     let instance = Instance {
         class: Rc::new(create_file_class()),
-        fields: HashMap::from([("путь".to_string(), Value::String(path))]),
+        fields: HashMap::from([("путь".to_string(), Value::String(path.into()))]),
     };
     Ok(Value::Instance(Rc::new(RefCell::new(instance))))
 }
 
-pub fn file_read_method(instance: &Rc<RefCell<Instance>>, args: &[Value]) -> Result<Value, String> {
+pub fn file_read_method(
+    _instance: &Rc<RefCell<Instance>>,
+    _args: &[Value],
+) -> Result<Value, String> {
     todo!()
 }
 
 pub fn file_write_method(
-    instance: &Rc<RefCell<Instance>>,
-    args: &[Value],
+    _instance: &Rc<RefCell<Instance>>,
+    _args: &[Value],
 ) -> Result<Value, String> {
     todo!()
 }
 
 pub fn file_delete_method(
-    instance: &Rc<RefCell<Instance>>,
-    args: &[Value],
+    _instance: &Rc<RefCell<Instance>>,
+    _args: &[Value],
 ) -> Result<Value, String> {
     todo!()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cp1251_table_covers_high_half() {
+        assert_eq!(CP1251_HIGH.chars().count(), 64);
+        assert_eq!(decode_cp1251_byte(0xA8), Some('Ё'));
+        assert_eq!(decode_cp1251_byte(0xB9), Some('№'));
+        assert_eq!(decode_cp1251_byte(0xC0), Some('А'));
+        assert_eq!(decode_cp1251_byte(0xFF), Some('я'));
+        assert_eq!(decode_cp1251_byte(0x98), None);
+    }
+}
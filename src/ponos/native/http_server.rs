@@ -0,0 +1,397 @@
+use crate::ponos::native::encodings;
+use crate::ponos::native::json::value_to_json;
+use crate::ponos::native::net::parse_headers;
+use crate::ponos::value::{Value, ValueKey};
+use crate::ponos::vm::NativeContext;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::time::Duration;
+
+// Минимальный HTTP/1.1-сервер для стд/сеть. Запросы обрабатываются по одному
+// в потоке интерпретатора: обработчик — обычная функция Ponos. После ответа
+// соединение закрывается (keep-alive не поддерживается)
+
+/// Сколько ждать данных от клиента, прежде чем бросить соединение
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Предел размера заголовков и тела запроса
+const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+
+struct ServerOptions {
+    address: String,
+    max_requests: Option<usize>,
+}
+
+struct Request {
+    method: String,
+    target: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+struct Response {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// сеть.слушать(порт, обработчик, опции = {}) -> ничто
+///
+/// Обработчик получает словарь запроса {"метод", "путь", "строка_запроса",
+/// "параметры", "заголовки", "тело"} и возвращает словарь ответа {"статус",
+/// "заголовки", "тело"} (или {"json": значение}) либо просто строку тела.
+/// Опции: "адрес" (по умолчанию "127.0.0.1") и "макс_запросов" — после
+/// стольких запросов сервер останавливается и слушать() возвращается
+pub fn http_listen(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err("слушать требует 2 или 3 аргумента: порт, обработчик, [опции]".to_string());
+    }
+    let port = match args[0].as_number() {
+        Some(n) if n.fract() == 0.0 && (0.0..=65535.0).contains(&n) => n as u16,
+        _ => return Err("слушать: порт должен быть целым числом от 0 до 65535".to_string()),
+    };
+    let handler = &args[1];
+    let options = match args.get(2) {
+        Some(value) => parse_server_options(value)?,
+        None => ServerOptions {
+            address: "127.0.0.1".to_string(),
+            max_requests: None,
+        },
+    };
+
+    let listener = TcpListener::bind((options.address.as_str(), port)).map_err(|e| {
+        format!(
+            "Не удалось занять адрес {}:{}: {}",
+            options.address, port, e
+        )
+    })?;
+
+    let mut served = 0;
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        // Клиент, приславший мусор или оборвавший соединение, не должен
+        // останавливать сервер: отвечаем 400 и ждём следующего
+        let request = match read_request(&mut stream) {
+            Ok(request) => request,
+            Err(e) => {
+                let _ = write_response(&mut stream, &plain_response(400, e));
+                continue;
+            }
+        };
+        served += 1;
+
+        let result = ctx
+            .call(handler, &[request_to_value(request)])
+            .and_then(|value| response_from_value(&value));
+        match result {
+            Ok(response) => {
+                let _ = write_response(&mut stream, &response);
+            }
+            Err(e) => {
+                let _ = write_response(
+                    &mut stream,
+                    &plain_response(500, "Внутренняя ошибка сервера".to_string()),
+                );
+                return Err(format!("Ошибка в обработчике HTTP-запроса: {}", e));
+            }
+        }
+        if options.max_requests.is_some_and(|max| served >= max) {
+            break;
+        }
+    }
+    Ok(Value::Nil)
+}
+
+fn parse_server_options(value: &Value) -> Result<ServerOptions, String> {
+    let Value::Dict(dict) = value else {
+        return Err("Опции сервера должны быть словарем".to_string());
+    };
+    let mut options = ServerOptions {
+        address: "127.0.0.1".to_string(),
+        max_requests: None,
+    };
+    for (key, val) in dict.borrow().iter() {
+        let ValueKey::String(key) = key else {
+            return Err("Ключи опций должны быть строками".to_string());
+        };
+        match (key.as_str(), val) {
+            ("адрес", Value::String(address)) => options.address = address.to_string(),
+            ("адрес", _) => return Err("Опция 'адрес' должна быть строкой".to_string()),
+            ("макс_запросов", Value::Int(n)) if *n >= 1 => {
+                options.max_requests = Some(*n as usize)
+            }
+            ("макс_запросов", Value::Number(n)) if *n >= 1.0 && n.fract() == 0.0 => {
+                options.max_requests = Some(*n as usize)
+            }
+            ("макс_запросов", _) => {
+                return Err("Опция 'макс_запросов' должна быть натуральным числом".to_string());
+            }
+            (unknown, _) => return Err(format!("Неизвестная опция '{}'", unknown)),
+        }
+    }
+    Ok(options)
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    parse_request(BufReader::new(stream))
+}
+
+/// Прочитать строку, не выходя за оставшийся предел `remaining`: строка без
+/// перевода строки не должна занимать память без ограничения
+fn read_limited_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    remaining: &mut usize,
+) -> std::io::Result<usize> {
+    let read = reader.by_ref().take(*remaining as u64).read_line(line)?;
+    *remaining -= read;
+    Ok(read)
+}
+
+fn parse_request(mut reader: impl BufRead) -> Result<Request, String> {
+    // Строка запроса и заголовки вместе не больше MAX_REQUEST_BYTES
+    let mut remaining = MAX_REQUEST_BYTES;
+    let mut line = String::new();
+    read_limited_line(&mut reader, &mut line, &mut remaining)
+        .map_err(|e| format!("Не удалось прочитать запрос: {}", e))?;
+    if !line.ends_with('\n') {
+        return Err("Строка запроса оборвана или слишком велика".to_string());
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err("Некорректная строка запроса".to_string());
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        read_limited_line(&mut reader, &mut line, &mut remaining)
+            .map_err(|e| format!("Не удалось прочитать заголовки: {}", e))?;
+        if !line.ends_with('\n') {
+            return Err("Заголовки запроса оборваны или слишком велики".to_string());
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Некорректный заголовок: {}", line))?;
+        // Имена заголовков регистронезависимы, поэтому приводим их к нижнему регистру
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    let length = match headers.get("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| "Некорректный Content-Length".to_string())?,
+        None => 0,
+    };
+    if length > MAX_REQUEST_BYTES {
+        return Err("Тело запроса слишком велико".to_string());
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("Не удалось прочитать тело запроса: {}", e))?;
+
+    Ok(Request {
+        method,
+        target,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn string_dict(map: HashMap<String, String>) -> Value {
+    let map = map
+        .into_iter()
+        .map(|(k, v)| (ValueKey::String(k.into()), Value::String(v.into())))
+        .collect();
+    Value::Dict(Rc::new(RefCell::new(map)))
+}
+
+fn request_to_value(request: Request) -> Value {
+    let (path, query) = match request.target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.target, String::new()),
+    };
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect();
+
+    let fields = [
+        ("метод", Value::String(request.method.into())),
+        ("путь", Value::String(percent_decode(&path).into())),
+        ("строка_запроса", Value::String(query.into())),
+        ("параметры", string_dict(params)),
+        ("заголовки", string_dict(request.headers)),
+        ("тело", Value::String(request.body.into())),
+    ];
+    let map = fields
+        .into_iter()
+        .map(|(k, v)| (ValueKey::String(k.to_string().into()), v))
+        .collect();
+    Value::Dict(Rc::new(RefCell::new(map)))
+}
+
+/// Раскодировать компонент адресной строки; строку с некорректными
+/// %-последовательностями оставляем как есть, а не отвечаем ошибкой
+fn percent_decode(text: &str) -> String {
+    match encodings::percent_decode(text) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => text.to_string(),
+    }
+}
+
+fn plain_response(status: u16, body: String) -> Response {
+    Response {
+        status,
+        headers: HashMap::new(),
+        body,
+    }
+}
+
+fn response_from_value(value: &Value) -> Result<Response, String> {
+    let dict = match value {
+        Value::String(body) => return Ok(plain_response(200, body.to_string())),
+        Value::Dict(dict) => dict.borrow(),
+        _ => {
+            return Err("Обработчик должен вернуть словарь ответа или строку".to_string());
+        }
+    };
+
+    let mut response = plain_response(200, String::new());
+    let mut json = None;
+    for (key, val) in dict.iter() {
+        let ValueKey::String(key) = key else {
+            return Err("Ключи ответа должны быть строками".to_string());
+        };
+        match (key.as_str(), val) {
+            ("статус", Value::Int(n)) if (100..=999).contains(n) => {
+                response.status = *n as u16
+            }
+            ("статус", Value::Number(n)) if (100.0..=999.0).contains(n) && n.fract() == 0.0 => {
+                response.status = *n as u16
+            }
+            ("статус", _) => {
+                return Err("'статус' должен быть кодом от 100 до 999".to_string());
+            }
+            ("заголовки", headers) => response.headers = parse_headers(headers)?,
+            ("тело", Value::String(body)) => response.body = body.to_string(),
+            ("тело", _) => return Err("'тело' ответа должно быть строкой".to_string()),
+            ("json", val) => json = Some(value_to_json(val)?),
+            (unknown, _) => return Err(format!("Неизвестное поле ответа '{}'", unknown)),
+        }
+    }
+
+    if let Some(json) = json {
+        if !response.body.is_empty() {
+            return Err("Нельзя указывать одновременно 'тело' и 'json'".to_string());
+        }
+        response.body = serde_json::to_string(&json)
+            .map_err(|e| format!("Ошибка сериализации JSON ответа: {}", e))?;
+        if !response
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("content-type"))
+        {
+            response
+                .headers
+                .insert("Content-Type".to_string(), "application/json".to_string());
+        }
+    }
+    Ok(response)
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason_phrase(response.status)
+    );
+    let has_content_type = response
+        .headers
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case("content-type"));
+    if !has_content_type {
+        head.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endless_lines_stop_at_the_request_limit() {
+        let endless = std::io::BufReader::new(std::io::repeat(b'a'));
+        assert_eq!(
+            parse_request(endless).err().as_deref(),
+            Some("Строка запроса оборвана или слишком велика")
+        );
+
+        let head = std::io::Cursor::new("GET / HTTP/1.1\r\nX-Long: ");
+        let endless = std::io::BufReader::new(head.chain(std::io::repeat(b'a')));
+        assert_eq!(
+            parse_request(endless).err().as_deref(),
+            Some("Заголовки запроса оборваны или слишком велики")
+        );
+
+        let request = parse_request("GET /путь HTTP/1.1\r\nHost: x\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(request.target, "/путь");
+        assert_eq!(request.headers["host"], "x");
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("a+b%20c"), "a b c");
+        assert_eq!(percent_decode("%D0%BC%D0%B8%D1%80"), "мир");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -1,52 +1,92 @@
 use crate::ponos::value::Value;
+use crate::ponos::vm::NativeContext;
+use std::io::{self, Read, Write};
 
-fn format_value(v: &Value) -> String {
-    match v {
-        Value::String(s) => s.clone(),
-        Value::Number(n) => n.to_string(),
-        Value::Boolean(b) => b.to_string(),
-        Value::Nil => "ничто".to_string(),
-        Value::Array(arr) => {
-            let items: Vec<String> = arr.borrow().iter().map(format_value).collect();
-            format!("[{}]", items.join(", "))
-        }
-        Value::Dict(dict) => {
-            let items: Vec<String> = dict.borrow().iter().map(|(k, v)| {
-                let key_str = match k {
-                    crate::ponos::value::ValueKey::String(s) => format!("\"{}\"", s),
-                    crate::ponos::value::ValueKey::Number(n) => n.to_string(),
-                    crate::ponos::value::ValueKey::Boolean(b) => b.to_string(),
-                };
-                format!("{}: {}", key_str, format_value(v))
-            }).collect();
-            format!("{{{}}}", items.join(", "))
-        }
-        _ => "<объект>".to_string(),
+// Значения через пробел с переводом строки, как их печатает ввод_вывод.вывести
+fn line(args: &[Value]) -> String {
+    let mut line = String::new();
+    for arg in args {
+        line.push_str(&arg.to_string());
+        line.push(' ');
     }
+    line.push('\n');
+    line
 }
 
-pub fn io_print(args: &[Value]) -> Result<Value, String> {
-    for arg in args {
-        print!("{} ", format_value(arg));
-    }
-    println!();
+pub fn io_print(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    ctx.write_output(&line(args));
+    Ok(Value::Nil)
+}
+
+/// ввод_вывод.вывести_ошибку(...значения) — то же, что вывести, но в stderr
+pub fn io_print_error(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    // Уже выведенное в stdout должно оказаться раньше сообщения об ошибке
+    ctx.flush_output();
+    eprint!("{}", line(args));
     Ok(Value::Nil)
 }
 
-pub fn io_input(args: &[Value]) -> Result<Value, String> {
-    use std::io::{self, Write};
+/// ввод_вывод.ввести(приглашение = "") -> строка | ничто
+///
+/// Строка из stdin без перевода строки; `ничто`, если ввод закончился
+pub fn io_input(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty()
+        && let Value::String(prompt) = &args[0]
+    {
+        ctx.write_output(prompt);
+    }
+    ctx.flush_output();
+
+    let mut input = String::new();
+    let read = io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| format!("Ошибка ввода: {}", e))?;
+    if read == 0 {
+        return Ok(Value::Nil);
+    }
+
+    Ok(Value::String(input.trim().to_string().into()))
+}
 
+/// ввод_вывод.ввести_все() -> строка
+///
+/// Весь stdin до конца ввода — для данных, переданных через конвейер
+pub fn io_read_all(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
     if !args.is_empty() {
-        if let Value::String(prompt) = &args[0] {
-            print!("{}", prompt);
-            io::stdout().flush().unwrap();
-        }
+        return Err("ввести_все не принимает аргументов".to_string());
     }
+    ctx.flush_output();
 
     let mut input = String::new();
     io::stdin()
-        .read_line(&mut input)
+        .read_to_string(&mut input)
         .map_err(|e| format!("Ошибка ввода: {}", e))?;
+    Ok(Value::String(input.into()))
+}
 
-    Ok(Value::String(input.trim().to_string()))
+/// ввод_вывод.сбросить() — записать накопленный вывод в stdout
+pub fn io_flush(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("сбросить не принимает аргументов".to_string());
+    }
+    ctx.flush_output();
+    io::stderr()
+        .flush()
+        .map_err(|e| format!("Ошибка вывода: {}", e))?;
+    Ok(Value::Nil)
+}
+
+/// ввод_вывод.без_буфера(включить = истина)
+///
+/// Если stdout не терминал, вывод по умолчанию копится и записывается
+/// блоками. Без буфера каждый вызов вывести сразу попадает в stdout —
+/// например, чтобы ход работы было видно через `| tee`
+pub fn io_unbuffered(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let enabled = match args {
+        [] => true,
+        [Value::Boolean(enabled)] => *enabled,
+        _ => return Err("без_буфера принимает один логический аргумент".to_string()),
+    };
+    ctx.set_output_buffering(!enabled);
+    Ok(Value::Nil)
 }
@@ -1,18 +1,23 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 use serde_json::Value as JsonValue;
 
-use crate::ponos::value::{Value, ValueKey};
+use crate::ponos::exceptions::{COLUMN_FIELD, JSON_ERROR, LINE_FIELD};
+use crate::ponos::value::{Dict, Value, ValueKey};
+use crate::ponos::vm::NativeContext;
 
 pub(crate) fn json_to_value(value: &JsonValue) -> Result<Value, String> {
     match value {
         JsonValue::Null => Ok(Value::Nil),
         JsonValue::Bool(b) => Ok(Value::Boolean(*b)),
-        JsonValue::Number(n) => n
-            .as_f64()
-            .map(Value::Number)
-            .ok_or_else(|| "Число вне диапазона f64".to_string()),
-        JsonValue::String(s) => Ok(Value::String(s.clone())),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Ok(Value::Int(i)),
+            None => n
+                .as_f64()
+                .map(Value::Number)
+                .ok_or_else(|| "Число вне диапазона f64".to_string()),
+        },
+        JsonValue::String(s) => Ok(Value::String(s.clone().into())),
         JsonValue::Array(arr) => {
             let mut items = Vec::with_capacity(arr.len());
             for v in arr.iter() {
@@ -21,73 +26,264 @@ pub(crate) fn json_to_value(value: &JsonValue) -> Result<Value, String> {
             Ok(Value::Array(Rc::new(RefCell::new(items))))
         }
         JsonValue::Object(obj) => {
-            let mut map = HashMap::new();
+            let mut map = Dict::new();
             for (k, v) in obj.iter() {
-                map.insert(ValueKey::String(k.clone()), json_to_value(v)?);
+                map.insert(ValueKey::String(k.clone().into()), json_to_value(v)?);
             }
             Ok(Value::Dict(Rc::new(RefCell::new(map))))
         }
     }
 }
 
+/// Сообщение ОшибкаJSON для значения, которое содержит само себя
+const CYCLE_MESSAGE: &str = "Невозможно сериализовать в JSON: массив или словарь содержит сам себя";
+
+/// Почему значение не преобразовалось в JSON
+enum ToJsonError {
+    /// Массив или словарь содержит сам себя
+    Cycle,
+    Invalid(String),
+}
+
+impl From<ToJsonError> for String {
+    fn from(error: ToJsonError) -> String {
+        match error {
+            ToJsonError::Cycle => CYCLE_MESSAGE.to_string(),
+            ToJsonError::Invalid(message) => message,
+        }
+    }
+}
+
 pub(crate) fn value_to_json(value: &Value) -> Result<JsonValue, String> {
-    match value {
-        Value::Nil => Ok(JsonValue::Null),
-        Value::Boolean(b) => Ok(JsonValue::Bool(*b)),
-        Value::Number(n) => serde_json::Number::from_f64(*n)
-            .map(JsonValue::Number)
-            .ok_or_else(|| "Число не может быть представлено в JSON".to_string()),
-        Value::String(s) => Ok(JsonValue::String(s.clone())),
+    to_json(value, &mut Vec::new()).map_err(String::from)
+}
+
+// `visiting` — массивы и словари на пути от корня до значения: встретив один
+// из них ещё раз, обход зациклился бы. Общие части без циклов допустимы
+fn to_json(value: &Value, visiting: &mut Vec<*const ()>) -> Result<JsonValue, ToJsonError> {
+    let container = match value {
+        Value::Array(arr) => Rc::as_ptr(arr) as *const (),
+        Value::Dict(dict) => Rc::as_ptr(dict) as *const (),
+        _ => return scalar_to_json(value).map_err(ToJsonError::Invalid),
+    };
+    if visiting.contains(&container) {
+        return Err(ToJsonError::Cycle);
+    }
+    visiting.push(container);
+    let json = match value {
         Value::Array(arr) => {
             let borrowed = arr.borrow();
             let mut items = Vec::with_capacity(borrowed.len());
             for v in borrowed.iter() {
-                items.push(value_to_json(v)?);
+                items.push(to_json(v, visiting)?);
             }
-            Ok(JsonValue::Array(items))
+            JsonValue::Array(items)
         }
         Value::Dict(dict) => {
             let borrowed = dict.borrow();
             let mut obj = serde_json::Map::new();
             for (k, v) in borrowed.iter() {
                 let key = match k {
-                    ValueKey::String(s) => s.clone(),
+                    ValueKey::String(s) => s.to_string(),
+                    ValueKey::Int(n) => n.to_string(),
                     ValueKey::Number(n) => n.to_string(),
                     ValueKey::Boolean(b) => b.to_string(),
                 };
-                obj.insert(key, value_to_json(v)?);
+                obj.insert(key, to_json(v, visiting)?);
             }
-            Ok(JsonValue::Object(obj))
+            JsonValue::Object(obj)
+        }
+        _ => unreachable!("выше обработаны только массивы и словари"),
+    };
+    visiting.pop();
+    Ok(json)
+}
+
+fn scalar_to_json(value: &Value) -> Result<JsonValue, String> {
+    match value {
+        Value::Nil => Ok(JsonValue::Null),
+        Value::Boolean(b) => Ok(JsonValue::Bool(*b)),
+        Value::Int(n) => Ok(JsonValue::Number((*n).into())),
+        // Целые числа пишутся без дробной части, чтобы 42 не превращалось в 42.0
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => {
+            Ok(JsonValue::Number((*n as i64).into()))
         }
+        Value::Number(n) => serde_json::Number::from_f64(*n)
+            .map(JsonValue::Number)
+            .ok_or_else(|| format!("Число {} не может быть представлено в JSON", n)),
+        Value::String(s) => Ok(JsonValue::String(s.to_string())),
+        Value::StringBuilder(buf) => Ok(JsonValue::String(buf.borrow().clone())),
         _ => Err("Невозможно сериализовать этот тип в JSON".to_string()),
     }
 }
 
+/// Исключение ОшибкаJSON для некорректного JSON: поля `строка` и `столбец`
+/// позволяют обработчику показать место ошибки
+fn parse_error_value(
+    ctx: &NativeContext,
+    error: &serde_json::Error,
+    source_name: Option<&str>,
+) -> Value {
+    let message = match source_name {
+        Some(path) => format!("Ошибка разбора JSON в файле '{}': {}", path, error),
+        None => format!("Ошибка разбора JSON: {}", error),
+    };
+    let exception = ctx.error(JSON_ERROR, message);
+    if let Value::Instance(instance) = &exception {
+        let fields = &mut instance.borrow_mut().fields;
+        fields.insert(LINE_FIELD.to_string(), Value::Int(error.line() as i64));
+        fields.insert(COLUMN_FIELD.to_string(), Value::Int(error.column() as i64));
+    }
+    exception
+}
+
+fn parse_source(
+    ctx: &mut NativeContext,
+    source: &str,
+    source_name: Option<&str>,
+) -> Result<Value, String> {
+    match serde_json::from_str::<JsonValue>(source) {
+        Ok(parsed) => json_to_value(&parsed),
+        Err(error) => {
+            let exception = parse_error_value(ctx, &error, source_name);
+            ctx.throw(exception);
+            Ok(Value::Nil)
+        }
+    }
+}
+
+fn is_pretty(name: &str, options: Option<&Value>) -> Result<bool, String> {
+    let dict = match options {
+        None => return Ok(false),
+        Some(Value::Dict(dict)) => dict.borrow(),
+        Some(_) => return Err(format!("{}: опции должны быть словарем", name)),
+    };
+    let mut pretty = false;
+    for (key, value) in dict.iter() {
+        match (key, value) {
+            (ValueKey::String(key), Value::Boolean(flag)) if **key == "красиво" => {
+                pretty = *flag
+            }
+            (ValueKey::String(key), _) if **key == "красиво" => {
+                return Err(format!("{}: опция 'красиво' должна быть булевой", name));
+            }
+            (ValueKey::String(other), _) => {
+                return Err(format!("{}: неизвестная опция '{}'", name, other));
+            }
+            _ => return Err(format!("{}: ключи опций должны быть строками", name)),
+        }
+    }
+    Ok(pretty)
+}
+
+fn serialize(name: &str, value: &Value, options: Option<&Value>) -> Result<String, ToJsonError> {
+    let json_value = to_json(value, &mut Vec::new())?;
+    let serialized = if is_pretty(name, options).map_err(ToJsonError::Invalid)? {
+        serde_json::to_string_pretty(&json_value)
+    } else {
+        serde_json::to_string(&json_value)
+    };
+    serialized.map_err(|e| ToJsonError::Invalid(format!("Ошибка сериализации JSON: {}", e)))
+}
+
+// Цикл в значении бросается как ОшибкаJSON (None), остальные ошибки
+// сериализации — обычные ошибки нативной функции
+fn serialize_or_throw(
+    ctx: &mut NativeContext,
+    name: &str,
+    value: &Value,
+    options: Option<&Value>,
+) -> Result<Option<String>, String> {
+    match serialize(name, value, options) {
+        Ok(serialized) => Ok(Some(serialized)),
+        Err(ToJsonError::Cycle) => {
+            let exception = ctx.error(JSON_ERROR, CYCLE_MESSAGE.to_string());
+            ctx.throw(exception);
+            Ok(None)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
 /// json.десериализовать(строка) -> Value
-pub fn json_parse(args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
+///
+/// Некорректный JSON бросает ОшибкаJSON
+pub fn json_parse(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [Value::String(source)] = args else {
         return Err("десериализовать требует 1 аргумент: строку с JSON".to_string());
+    };
+    parse_source(ctx, source, None)
+}
+
+/// json.сериализовать(значение, опции = {}) -> строка
+///
+/// Опция "красиво": истина — многострочный вывод с отступами. Массив или
+/// словарь, содержащий сам себя, бросает ОшибкаJSON
+pub fn json_stringify(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("сериализовать требует 1 или 2 аргумента: значение, [опции]".to_string());
     }
+    Ok(
+        match serialize_or_throw(ctx, "сериализовать", &args[0], args.get(1))? {
+            Some(serialized) => Value::String(serialized.into()),
+            None => Value::Nil,
+        },
+    )
+}
 
-    let source = match &args[0] {
-        Value::String(s) => s,
-        _ => return Err("Аргумент должен быть строкой".to_string()),
+/// json.сериализовать_в_файл(значение, путь, опции = {}) -> ничто
+pub fn json_write_file(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "сериализовать_в_файл требует 2 или 3 аргумента: значение, путь, [опции]".to_string(),
+        );
+    }
+    let Value::String(path) = &args[1] else {
+        return Err("сериализовать_в_файл: путь должен быть строкой".to_string());
     };
+    let Some(serialized) = serialize_or_throw(ctx, "сериализовать_в_файл", &args[0], args.get(2))?
+    else {
+        return Ok(Value::Nil);
+    };
+    std::fs::write(path.as_str(), serialized)
+        .map_err(|e| format!("Не удалось записать файл '{}': {}", path, e))?;
+    Ok(Value::Nil)
+}
 
-    let parsed: JsonValue =
-        serde_json::from_str(source).map_err(|e| format!("Ошибка разбора JSON: {}", e))?;
-
-    json_to_value(&parsed)
+/// json.десериализовать_из_файла(путь) -> Value
+pub fn json_read_file(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [Value::String(path)] = args else {
+        return Err("десериализовать_из_файла требует 1 аргумент: путь".to_string());
+    };
+    let source = std::fs::read_to_string(path.as_str())
+        .map_err(|e| format!("Не удалось прочитать файл '{}': {}", path, e))?;
+    parse_source(ctx, &source, Some(path))
 }
 
-/// json.сериализовать(значение) -> строка
-pub fn json_stringify(args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("сериализовать требует 1 аргумент: значение".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integral_numbers_round_trip_without_fraction() {
+        let value =
+            json_to_value(&serde_json::from_str("[42, -7, 1.5, null, true]").unwrap()).unwrap();
+        assert_eq!(
+            serialize("сериализовать", &value, None).ok().unwrap(),
+            "[42,-7,1.5,null,true]"
+        );
+        assert!(value_to_json(&Value::Number(f64::NAN)).is_err());
     }
 
-    let json_value = value_to_json(&args[0])?;
-    let serialized = serde_json::to_string(&json_value)
-        .map_err(|e| format!("Ошибка сериализации JSON: {}", e))?;
-    Ok(Value::String(serialized))
+    #[test]
+    fn self_referencing_containers_are_rejected() {
+        let array = Rc::new(RefCell::new(vec![Value::Int(1)]));
+        array.borrow_mut().push(Value::Array(array.clone()));
+        assert_eq!(
+            value_to_json(&Value::Array(array.clone())).unwrap_err(),
+            CYCLE_MESSAGE
+        );
+        // Разорвать цикл, чтобы Rc освободился
+        array.borrow_mut().pop();
+    }
 }
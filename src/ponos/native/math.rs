@@ -0,0 +1,107 @@
+use crate::ponos::native::random;
+use crate::ponos::value::Value;
+use crate::ponos::vm::VM;
+
+// Нативная часть модуля стд/математика. Функции регистрируются как встроенные
+// глобальные имена с префиксом `_мат_`, а stdlib/математика.pns экспортирует
+// обёртки над ними — так модуль остаётся обычным исходником Ponos, который
+// можно заменить через --stdlib
+
+/// Префикс глобальных имён нативных математических функций
+pub const NATIVE_PREFIX: &str = "_мат_";
+
+type MathFn = fn(&[Value]) -> Result<Value, String>;
+
+const FUNCTIONS: &[(&str, MathFn)] = &[
+    ("корень", math_sqrt),
+    ("степень", math_pow),
+    ("синус", math_sin),
+    ("косинус", math_cos),
+    ("округлить", math_round),
+    ("пол", math_floor),
+    ("потолок", math_ceil),
+    ("случайное", math_random),
+];
+
+/// Зарегистрировать нативные функции в VM
+pub fn register(vm: &mut VM) {
+    for &(name, function) in FUNCTIONS {
+        vm.register_and_define(&format!("{}{}", NATIVE_PREFIX, name), function);
+    }
+}
+
+fn number_arg(name: &str, args: &[Value]) -> Result<f64, String> {
+    match args {
+        [Value::Int(n)] => Ok(*n as f64),
+        [Value::Number(n)] => Ok(*n),
+        [_] => Err(format!("{}: аргумент должен быть числом", name)),
+        _ => Err(format!(
+            "{} ожидает 1 аргумент, передано: {}",
+            name,
+            args.len()
+        )),
+    }
+}
+
+/// мат.корень(x) -> число
+pub fn math_sqrt(args: &[Value]) -> Result<Value, String> {
+    let x = number_arg("корень", args)?;
+    if x < 0.0 {
+        return Err(format!("корень: отрицательный аргумент {}", x));
+    }
+    Ok(Value::Number(x.sqrt()))
+}
+
+/// мат.степень(основание, показатель) -> число
+pub fn math_pow(args: &[Value]) -> Result<Value, String> {
+    match args {
+        // Целое в неотрицательной целой степени остаётся целым, пока не переполнится
+        [Value::Int(base), Value::Int(exponent)] if *exponent >= 0 => Ok(u32::try_from(*exponent)
+            .ok()
+            .and_then(|exponent| base.checked_pow(exponent))
+            .map_or(
+                Value::Number((*base as f64).powf(*exponent as f64)),
+                Value::Int,
+            )),
+        [base, exponent] => match (base.as_number(), exponent.as_number()) {
+            (Some(base), Some(exponent)) => Ok(Value::Number(base.powf(exponent))),
+            _ => Err("степень: аргументы должны быть числами".to_string()),
+        },
+        _ => Err("степень ожидает 2 аргумента: основание и показатель".to_string()),
+    }
+}
+
+/// мат.синус(x) -> число (x в радианах)
+pub fn math_sin(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(number_arg("синус", args)?.sin()))
+}
+
+/// мат.косинус(x) -> число (x в радианах)
+pub fn math_cos(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(number_arg("косинус", args)?.cos()))
+}
+
+/// мат.округлить(x) -> число (половины округляются от нуля)
+pub fn math_round(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(number_arg("округлить", args)?.round()))
+}
+
+/// мат.пол(x) -> число
+pub fn math_floor(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(number_arg("пол", args)?.floor()))
+}
+
+/// мат.потолок(x) -> число
+pub fn math_ceil(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(number_arg("потолок", args)?.ceil()))
+}
+
+/// мат.случайное() -> число из [0, 1)
+///
+/// Использует общий генератор стд/случайное, поэтому зависит от установить_зерно
+pub fn math_random(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("случайное не принимает аргументов".to_string());
+    }
+    Ok(Value::Number(random::next_f64()))
+}
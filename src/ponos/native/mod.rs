@@ -1,17 +1,66 @@
+pub mod arrays;
 pub mod builtin_methods;
+#[cfg(feature = "desktop")]
+pub mod clipboard;
+pub mod dicts;
+pub mod encodings;
+pub mod eval;
 pub mod fs;
+pub mod http_server;
 pub mod io;
 pub mod json;
+pub mod math;
 pub mod net;
+pub mod os;
+pub mod path;
+pub mod process;
+pub mod random;
+pub mod reflection;
+pub mod regex;
+pub mod sockets;
 pub mod strings;
 pub mod system;
+pub mod testing;
+pub mod threads;
+pub mod time;
 
+use crate::ponos::exceptions::IO_ERROR;
 use crate::ponos::vm::VM;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Модули, ошибки функций которых бросаются как ОшибкаИО
+const IO_MODULES: [&str; 5] = [
+    "стд/фс",
+    "стд/путь",
+    "стд/сеть",
+    "стд/сокеты",
+    "стд/процесс",
+];
+
+/// Модули и отдельные функции модулей без доступа к файлам, сети, процессам
+/// и системе: их можно разрешать недоверенным скриптам
+pub const SAFE_MODULES: [&str; 13] = [
+    "стд/ввод_вывод",
+    "стд/время",
+    "стд/случайное",
+    "стд/регэксп",
+    "стд/массивы",
+    "стд/строки",
+    "стд/словари",
+    // Без сериализовать_в_файл и десериализовать_из_файла
+    "стд/json.сериализовать",
+    "стд/json.десериализовать",
+    "стд/кодировки",
+    "стд/тест",
+    "стд/потоки",
+    "стд/рефлексия",
+];
 
 /// Реестр нативных модулей
 pub struct NativeModuleRegistry {
     modules: HashMap<String, NativeModule>,
+    /// Модули, которые разрешено импортировать; `None` — все
+    allowed: Option<HashSet<String>>,
 }
 
 /// Нативный модуль с экспортированными функциями
@@ -20,23 +69,38 @@ pub struct NativeModule {
     pub exports: Vec<String>,
 }
 
+impl Default for NativeModuleRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NativeModuleRegistry {
     /// Создать новый реестр с зарегистрированными нативными модулями
     pub fn new() -> Self {
         let mut registry = NativeModuleRegistry {
             modules: HashMap::new(),
+            allowed: None,
         };
 
         // Регистрируем встроенные модули
         registry.register_module(NativeModule {
             name: "стд/ввод_вывод".to_string(),
-            exports: vec!["вывести".to_string(), "ввести".to_string()],
+            exports: vec![
+                "вывести".to_string(),
+                "вывести_ошибку".to_string(),
+                "ввести".to_string(),
+                "ввести_все".to_string(),
+                "сбросить".to_string(),
+                "без_буфера".to_string(),
+            ],
         });
 
         registry.register_module(NativeModule {
             name: "стд/фс".to_string(),
             exports: vec![
                 "читать".to_string(),
+                "читать_байты".to_string(),
                 "писать".to_string(),
                 "существует".to_string(),
                 "удалить".to_string(),
@@ -44,6 +108,20 @@ impl NativeModuleRegistry {
             ],
         });
 
+        registry.register_module(NativeModule {
+            name: "стд/путь".to_string(),
+            exports: vec![
+                "объединить".to_string(),
+                "родитель".to_string(),
+                "имя_файла".to_string(),
+                "расширение".to_string(),
+                "абсолютный".to_string(),
+                "существует_каталог".to_string(),
+                "создать_каталог".to_string(),
+                "список_файлов".to_string(),
+            ],
+        });
+
         registry.register_module(NativeModule {
             name: "стд/система".to_string(),
             exports: vec![
@@ -51,6 +129,61 @@ impl NativeModuleRegistry {
                 "получить_переменную_среды".to_string(),
                 "установить_переменную_среды".to_string(),
                 "аргументы".to_string(),
+                "завершить".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/ос".to_string(),
+            exports: vec![
+                "пользователь".to_string(),
+                "домашний_каталог".to_string(),
+                "имя_хоста".to_string(),
+                "число_процессоров".to_string(),
+                "платформа".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/время".to_string(),
+            exports: vec![
+                "сейчас".to_string(),
+                "форматировать".to_string(),
+                "разобрать".to_string(),
+                "спать".to_string(),
+                "таймер".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/случайное".to_string(),
+            exports: vec![
+                "случайное".to_string(),
+                "случайное_целое".to_string(),
+                "перемешать".to_string(),
+                "выбрать".to_string(),
+                "установить_зерно".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/регэксп".to_string(),
+            exports: vec![
+                "совпадает".to_string(),
+                "найти_все".to_string(),
+                "заменить".to_string(),
+                "группы".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/массивы".to_string(),
+            exports: vec![
+                "сортировать".to_string(),
+                "сортировать_по".to_string(),
+                "карта".to_string(),
+                "фильтр".to_string(),
+                "свернуть".to_string(),
             ],
         });
 
@@ -63,12 +196,40 @@ impl NativeModuleRegistry {
                 "верхний_регистр".to_string(),
                 "нижний_регистр".to_string(),
                 "начинается_с".to_string(),
+                "построитель".to_string(),
+            ],
+        });
+
+        #[cfg(feature = "desktop")]
+        registry.register_module(NativeModule {
+            name: "стд/буфер_обмена".to_string(),
+            exports: vec![
+                "прочитать".to_string(),
+                "записать".to_string(),
+                "открыть_в_браузере".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/словари".to_string(),
+            exports: vec![
+                "ключи".to_string(),
+                "значения".to_string(),
+                "содержит_ключ".to_string(),
+                "удалить".to_string(),
+                "объединить".to_string(),
+                "размер".to_string(),
             ],
         });
 
         registry.register_module(NativeModule {
             name: "стд/json".to_string(),
-            exports: vec!["десериализовать".to_string(), "сериализовать".to_string()],
+            exports: vec![
+                "десериализовать".to_string(),
+                "сериализовать".to_string(),
+                "сериализовать_в_файл".to_string(),
+                "десериализовать_из_файла".to_string(),
+            ],
         });
 
         registry.register_module(NativeModule {
@@ -77,6 +238,67 @@ impl NativeModuleRegistry {
                 "запрос".to_string(),
                 "получить".to_string(),
                 "запрос_json".to_string(),
+                "слушать".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/сокеты".to_string(),
+            exports: vec![
+                "подключиться".to_string(),
+                "отправить".to_string(),
+                "получить".to_string(),
+                "закрыть".to_string(),
+                "открыть_udp".to_string(),
+                "отправить_udp".to_string(),
+                "получить_udp".to_string(),
+                "порт".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/кодировки".to_string(),
+            exports: vec![
+                "base64_закодировать".to_string(),
+                "base64_раскодировать".to_string(),
+                "hex_закодировать".to_string(),
+                "hex_раскодировать".to_string(),
+                "url_закодировать".to_string(),
+                "url_раскодировать".to_string(),
+                "длина_в_байтах".to_string(),
+                "длина_в_символах".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/процесс".to_string(),
+            exports: vec!["запустить".to_string()],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/тест".to_string(),
+            exports: vec!["утверждать".to_string()],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/потоки".to_string(),
+            exports: vec![
+                "поток".to_string(),
+                "присоединить".to_string(),
+                "канал".to_string(),
+                "отправить".to_string(),
+                "получить".to_string(),
+                "закрыть".to_string(),
+            ],
+        });
+
+        registry.register_module(NativeModule {
+            name: "стд/рефлексия".to_string(),
+            exports: vec![
+                "экспорты".to_string(),
+                "имя".to_string(),
+                "арность".to_string(),
+                "получить_глобал".to_string(),
             ],
         });
 
@@ -98,6 +320,46 @@ impl NativeModuleRegistry {
         self.modules.get(path)
     }
 
+    /// Разрешить импорт только перечисленных нативных модулей (песочница):
+    /// `"стд/строки"` разрешает модуль целиком, `"стд/json.сериализовать"` —
+    /// одну его функцию. Повторный вызов заменяет список
+    pub fn allow_only<I, S>(&mut self, modules: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed = Some(modules.into_iter().map(Into::into).collect());
+    }
+
+    /// Разрешён ли импорт нативного модуля: целиком или хотя бы одной функции
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.allowed.as_ref().is_none_or(|allowed| {
+            allowed.contains(path)
+                || allowed.iter().any(|entry| {
+                    entry
+                        .strip_prefix(path)
+                        .is_some_and(|rest| rest.starts_with('.'))
+                })
+        })
+    }
+
+    /// Разрешена ли функция нативного модуля
+    pub fn is_export_allowed(&self, path: &str, export: &str) -> bool {
+        self.allowed.as_ref().is_none_or(|allowed| {
+            allowed.contains(path) || allowed.contains(&format!("{}.{}", path, export))
+        })
+    }
+
+    /// Экспорты модуля, которые разрешено импортировать
+    pub fn allowed_exports(&self, module: &NativeModule) -> Vec<String> {
+        module
+            .exports
+            .iter()
+            .filter(|export| self.is_export_allowed(&module.name, export))
+            .cloned()
+            .collect()
+    }
+
     /// Зарегистрировать функции нативного модуля в VM
     pub fn register_module_in_vm(
         &self,
@@ -108,32 +370,48 @@ impl NativeModuleRegistry {
         let module = self
             .get_module(module_path)
             .ok_or_else(|| format!("Нативный модуль '{}' не найден", module_path))?;
+        let exports = self.allowed_exports(module);
 
         // Регистрируем функции с манглированными именами
         match module_path {
             "стд/ввод_вывод" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "вывести" => {
-                            vm.register_and_define(&mangled_name, io::io_print);
+                            vm.register_and_define_with_context(&mangled_name, io::io_print);
+                        }
+                        "вывести_ошибку" => {
+                            vm.register_and_define_with_context(&mangled_name, io::io_print_error);
                         }
                         "ввести" => {
-                            vm.register_and_define(&mangled_name, io::io_input);
+                            vm.register_and_define_with_context(&mangled_name, io::io_input);
+                        }
+                        "ввести_все" => {
+                            vm.register_and_define_with_context(&mangled_name, io::io_read_all);
+                        }
+                        "сбросить" => {
+                            vm.register_and_define_with_context(&mangled_name, io::io_flush);
+                        }
+                        "без_буфера" => {
+                            vm.register_and_define_with_context(&mangled_name, io::io_unbuffered);
                         }
                         _ => {}
                     }
                 }
             }
             "стд/фс" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "читать" => {
-                            vm.register_and_define(&mangled_name, fs::fs_read);
+                            vm.register_and_define_with_context(&mangled_name, fs::fs_read);
+                        }
+                        "читать_байты" => {
+                            vm.register_and_define_with_context(&mangled_name, fs::fs_read_bytes);
                         }
                         "писать" => {
-                            vm.register_and_define(&mangled_name, fs::fs_write);
+                            vm.register_and_define_with_context(&mangled_name, fs::fs_write);
                         }
                         "существует" => {
                             vm.register_and_define(&mangled_name, fs::fs_exists);
@@ -143,14 +421,13 @@ impl NativeModuleRegistry {
                         }
                         "прочитать_директорию" => {
                             vm.register_and_define(&mangled_name, fs::fs_read_dir);
-                        } 
+                        }
                         _ => {}
                     }
                 }
-                
             }
             "стд/система" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "выполнить" => {
@@ -165,12 +442,165 @@ impl NativeModuleRegistry {
                         "аргументы" => {
                             vm.register_and_define(&mangled_name, system::get_args);
                         }
+                        "завершить" => {
+                            vm.register_and_define_with_context(&mangled_name, system::sys_exit);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/путь" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "объединить" => {
+                            vm.register_and_define(&mangled_name, path::path_join);
+                        }
+                        "родитель" => {
+                            vm.register_and_define(&mangled_name, path::path_parent);
+                        }
+                        "имя_файла" => {
+                            vm.register_and_define(&mangled_name, path::path_file_name);
+                        }
+                        "расширение" => {
+                            vm.register_and_define(&mangled_name, path::path_extension);
+                        }
+                        "абсолютный" => {
+                            vm.register_and_define(&mangled_name, path::path_absolute);
+                        }
+                        "существует_каталог" => {
+                            vm.register_and_define(&mangled_name, path::path_is_dir);
+                        }
+                        "создать_каталог" => {
+                            vm.register_and_define(&mangled_name, path::path_create_dir);
+                        }
+                        "список_файлов" => {
+                            vm.register_and_define(&mangled_name, path::path_list_files);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/ос" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "пользователь" => {
+                            vm.register_and_define(&mangled_name, os::os_user);
+                        }
+                        "домашний_каталог" => {
+                            vm.register_and_define(&mangled_name, os::os_home_dir);
+                        }
+                        "имя_хоста" => {
+                            vm.register_and_define(&mangled_name, os::os_hostname);
+                        }
+                        "число_процессоров" => {
+                            vm.register_and_define(&mangled_name, os::os_cpu_count);
+                        }
+                        "платформа" => {
+                            vm.register_and_define(&mangled_name, os::os_platform);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/время" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "сейчас" => {
+                            vm.register_and_define(&mangled_name, time::time_now);
+                        }
+                        "форматировать" => {
+                            vm.register_and_define(&mangled_name, time::time_format);
+                        }
+                        "разобрать" => {
+                            vm.register_and_define(&mangled_name, time::time_parse);
+                        }
+                        "спать" => {
+                            vm.register_and_define_with_context(&mangled_name, time::time_sleep);
+                        }
+                        "таймер" => {
+                            vm.register_and_define(&mangled_name, time::time_timer);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/случайное" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "случайное" => {
+                            vm.register_and_define(&mangled_name, random::random_float);
+                        }
+                        "случайное_целое" => {
+                            vm.register_and_define(&mangled_name, random::random_int);
+                        }
+                        "перемешать" => {
+                            vm.register_and_define(&mangled_name, random::random_shuffle);
+                        }
+                        "выбрать" => {
+                            vm.register_and_define(&mangled_name, random::random_choice);
+                        }
+                        "установить_зерно" => {
+                            vm.register_and_define(&mangled_name, random::random_seed);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/регэксп" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "совпадает" => {
+                            vm.register_and_define(&mangled_name, regex::regex_is_match);
+                        }
+                        "найти_все" => {
+                            vm.register_and_define(&mangled_name, regex::regex_find_all);
+                        }
+                        "заменить" => {
+                            vm.register_and_define(&mangled_name, regex::regex_replace);
+                        }
+                        "группы" => {
+                            vm.register_and_define(&mangled_name, regex::regex_groups);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/массивы" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "сортировать" => {
+                            vm.register_and_define_with_context(&mangled_name, arrays::array_sort);
+                        }
+                        "сортировать_по" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                arrays::array_sort_by,
+                            );
+                        }
+                        "карта" => {
+                            vm.register_and_define_with_context(&mangled_name, arrays::array_map);
+                        }
+                        "фильтр" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                arrays::array_filter,
+                            );
+                        }
+                        "свернуть" => {
+                            vm.register_and_define_with_context(&mangled_name, arrays::array_fold);
+                        }
                         _ => {}
                     }
                 }
             }
             "стд/строки" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "разделить" => {
@@ -191,36 +621,254 @@ impl NativeModuleRegistry {
                         "начинается_с" => {
                             vm.register_and_define(&mangled_name, strings::str_start_with);
                         }
+                        "построитель" => {
+                            vm.register_and_define(&mangled_name, strings::str_builder);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            #[cfg(feature = "desktop")]
+            "стд/буфер_обмена" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "прочитать" => {
+                            vm.register_and_define(&mangled_name, clipboard::clipboard_read);
+                        }
+                        "записать" => {
+                            vm.register_and_define(&mangled_name, clipboard::clipboard_write);
+                        }
+                        "открыть_в_браузере" => {
+                            vm.register_and_define(&mangled_name, clipboard::open_in_browser);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/словари" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "ключи" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_keys);
+                        }
+                        "значения" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_values);
+                        }
+                        "содержит_ключ" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_contains_key);
+                        }
+                        "удалить" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_remove);
+                        }
+                        "объединить" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_merge);
+                        }
+                        "размер" => {
+                            vm.register_and_define(&mangled_name, dicts::dict_size);
+                        }
                         _ => {}
                     }
                 }
             }
             "стд/json" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "десериализовать" => {
-                            vm.register_and_define(&mangled_name, json::json_parse);
+                            vm.register_and_define_with_context(&mangled_name, json::json_parse);
                         }
                         "сериализовать" => {
-                            vm.register_and_define(&mangled_name, json::json_stringify);
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                json::json_stringify,
+                            );
+                        }
+                        "сериализовать_в_файл" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                json::json_write_file,
+                            );
+                        }
+                        "десериализовать_из_файла" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                json::json_read_file,
+                            );
                         }
                         _ => {}
                     }
                 }
             }
             "стд/сеть" => {
-                for export in &module.exports {
+                for export in &exports {
                     let mangled_name = format!("{}::{}", namespace, export);
                     match export.as_str() {
                         "запрос" => {
-                            vm.register_and_define(&mangled_name, net::http_request);
+                            vm.register_and_define_with_context(&mangled_name, net::http_request);
                         }
                         "получить" => {
-                            vm.register_and_define(&mangled_name, net::http_get);
+                            vm.register_and_define_with_context(&mangled_name, net::http_get);
                         }
                         "запрос_json" => {
-                            vm.register_and_define(&mangled_name, net::http_request_json);
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                net::http_request_json,
+                            );
+                        }
+                        "слушать" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                http_server::http_listen,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/сокеты" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "подключиться" => {
+                            vm.register_and_define(&mangled_name, sockets::tcp_connect);
+                        }
+                        "отправить" => {
+                            vm.register_and_define(&mangled_name, sockets::tcp_send);
+                        }
+                        "получить" => {
+                            vm.register_and_define(&mangled_name, sockets::tcp_receive);
+                        }
+                        "закрыть" => {
+                            vm.register_and_define(&mangled_name, sockets::socket_close);
+                        }
+                        "открыть_udp" => {
+                            vm.register_and_define(&mangled_name, sockets::udp_bind);
+                        }
+                        "отправить_udp" => {
+                            vm.register_and_define(&mangled_name, sockets::udp_send);
+                        }
+                        "получить_udp" => {
+                            vm.register_and_define(&mangled_name, sockets::udp_receive);
+                        }
+                        "порт" => {
+                            vm.register_and_define(&mangled_name, sockets::socket_port);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/кодировки" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "base64_закодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::encode_base64);
+                        }
+                        "base64_раскодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::decode_base64);
+                        }
+                        "hex_закодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::encode_hex);
+                        }
+                        "hex_раскодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::decode_hex);
+                        }
+                        "url_закодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::encode_url);
+                        }
+                        "url_раскодировать" => {
+                            vm.register_and_define(&mangled_name, encodings::decode_url);
+                        }
+                        "длина_в_байтах" => {
+                            vm.register_and_define(&mangled_name, encodings::byte_length);
+                        }
+                        "длина_в_символах" => {
+                            vm.register_and_define(&mangled_name, encodings::char_length);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/процесс" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    if export == "запустить" {
+                        vm.register_and_define_with_context(&mangled_name, process::process_run);
+                    }
+                }
+            }
+            "стд/тест" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    if export == "утверждать" {
+                        vm.register_and_define(&mangled_name, testing::assert);
+                    }
+                }
+            }
+            "стд/потоки" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "поток" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                threads::thread_spawn,
+                            );
+                        }
+                        "присоединить" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                threads::thread_join,
+                            );
+                        }
+                        "канал" => {
+                            vm.register_and_define(&mangled_name, threads::channel_new);
+                        }
+                        "отправить" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                threads::channel_send,
+                            );
+                        }
+                        "получить" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                threads::channel_receive,
+                            );
+                        }
+                        "закрыть" => {
+                            vm.register_and_define(&mangled_name, threads::channel_close);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "стд/рефлексия" => {
+                for export in &exports {
+                    let mangled_name = format!("{}::{}", namespace, export);
+                    match export.as_str() {
+                        "экспорты" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                reflection::module_exports,
+                            );
+                        }
+                        "имя" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                reflection::function_name,
+                            );
+                        }
+                        "арность" => {
+                            vm.register_and_define(&mangled_name, reflection::function_arity);
+                        }
+                        "получить_глобал" => {
+                            vm.register_and_define_with_context(
+                                &mangled_name,
+                                reflection::lookup_global,
+                            );
                         }
                         _ => {}
                     }
@@ -231,6 +879,13 @@ impl NativeModuleRegistry {
             }
         }
 
+        if IO_MODULES.contains(&module_path) {
+            for export in &exports {
+                vm.set_native_error_class(&format!("{}::{}", namespace, export), IO_ERROR);
+            }
+        }
+        vm.record_native_module(module_path, namespace, exports);
+
         Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use crate::ponos::native::json::{json_to_value, value_to_json};
-use crate::ponos::value::{Value, ValueKey};
+use crate::ponos::value::{Dict, Value, ValueKey};
+use crate::ponos::vm::NativeContext;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -14,7 +15,15 @@ struct RequestOptions {
     expect_json: bool,
 }
 
-pub fn http_request(args: &[Value]) -> Result<Value, String> {
+/// сеть.запрос(метод, url, опции = {}) -> словарь ответа
+///
+/// Опции: "заголовки" (словарь), "тело" (строка) или "json" (значение),
+/// "таймаут_мс", "ожидать_json". Ответ: {"статус", "заголовки", "тело",
+/// "json"}; "json" заполняется, если ответ пришёл как application/json или
+/// задано "ожидать_json". Коды 4xx/5xx возвращаются как обычный ответ, а
+/// ошибки соединения и таймауты бросают исключение. В сопрограмме запрос
+/// выполняется в фоне: пока ответ не пришёл, работают другие задачи
+pub fn http_request(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
     if args.len() < 2 || args.len() > 3 {
         return Err("запрос требует 2 или 3 аргумента: метод, url, [опции]".to_string());
     }
@@ -41,11 +50,12 @@ pub fn http_request(args: &[Value]) -> Result<Value, String> {
         options = parse_options(&args[2], false)?;
     }
 
-    perform_request(&method, &url, options)
+    perform_request(ctx, method, url.to_string(), options)
 }
 
-pub fn http_get(args: &[Value]) -> Result<Value, String> {
-    if args.len() < 1 || args.len() > 2 {
+/// сеть.получить(url, опции = {}) -> словарь ответа (GET-запрос, см. запрос)
+pub fn http_get(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
         return Err("получить требует 1 или 2 аргумента: url, [опции]".to_string());
     }
 
@@ -66,10 +76,13 @@ pub fn http_get(args: &[Value]) -> Result<Value, String> {
         options = parse_options(&args[1], false)?;
     }
 
-    perform_request("GET", &url, options)
+    perform_request(ctx, "GET".to_string(), url.to_string(), options)
 }
 
-pub fn http_request_json(args: &[Value]) -> Result<Value, String> {
+/// сеть.запрос_json(метод, url, данные, опции = {}) -> словарь ответа
+///
+/// Отправляет данные как JSON и разбирает JSON-ответ в поле "json"
+pub fn http_request_json(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
     if args.len() < 3 || args.len() > 4 {
         return Err(
             "запрос_json требует 3 или 4 аргумента: метод, url, данные, [опции]".to_string(),
@@ -109,7 +122,7 @@ pub fn http_request_json(args: &[Value]) -> Result<Value, String> {
         options = parsed;
     }
 
-    perform_request(&method, &url, options)
+    perform_request(ctx, method, url.to_string(), options)
 }
 
 fn parse_options(value: &Value, expect_json_default: bool) -> Result<RequestOptions, String> {
@@ -141,7 +154,7 @@ fn parse_options(value: &Value, expect_json_default: bool) -> Result<RequestOpti
                     Value::String(s) => s.clone(),
                     _ => return Err("Опция 'тело' должна быть строкой".to_string()),
                 };
-                options.body = Some(body);
+                options.body = Some(body.to_string());
             }
             "json" => {
                 options.json_body = Some(
@@ -150,8 +163,8 @@ fn parse_options(value: &Value, expect_json_default: bool) -> Result<RequestOpti
                 );
             }
             "таймаут_мс" => {
-                let millis = match val {
-                    Value::Number(n) if *n >= 0.0 => *n as u64,
+                let millis = match val.as_number() {
+                    Some(n) if n >= 0.0 => n as u64,
                     _ => {
                         return Err(
                             "Опция 'таймаут_мс' должна быть неотрицательным числом".to_string()
@@ -180,7 +193,7 @@ fn parse_options(value: &Value, expect_json_default: bool) -> Result<RequestOpti
     Ok(options)
 }
 
-fn parse_headers(value: &Value) -> Result<HashMap<String, String>, String> {
+pub(crate) fn parse_headers(value: &Value) -> Result<HashMap<String, String>, String> {
     let dict = match value {
         Value::Dict(d) => d,
         _ => return Err("Заголовки должны быть словарем".to_string()),
@@ -189,12 +202,13 @@ fn parse_headers(value: &Value) -> Result<HashMap<String, String>, String> {
     let mut headers = HashMap::new();
     for (key, val) in dict.borrow().iter() {
         let name = match key {
-            ValueKey::String(s) => s.clone(),
+            ValueKey::String(s) => s.to_string(),
             _ => return Err("Имена заголовков должны быть строками".to_string()),
         };
 
         let value_str = match val {
-            Value::String(s) => s.clone(),
+            Value::String(s) => s.to_string(),
+            Value::Int(n) => n.to_string(),
             Value::Number(n) => n.to_string(),
             Value::Boolean(b) => b.to_string(),
             _ => {
@@ -208,7 +222,27 @@ fn parse_headers(value: &Value) -> Result<HashMap<String, String>, String> {
     Ok(headers)
 }
 
-fn perform_request(method: &str, url: &str, options: RequestOptions) -> Result<Value, String> {
+// Ответ, полученный в фоновом потоке; значение Ponos из него собирается в потоке VM
+struct Response {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+    json: Option<serde_json::Value>,
+}
+
+fn perform_request(
+    ctx: &mut NativeContext,
+    method: String,
+    url: String,
+    options: RequestOptions,
+) -> Result<Value, String> {
+    ctx.run_blocking(move || {
+        let response = fetch(&method, &url, options);
+        Box::new(move || response_to_value(response?))
+    })
+}
+
+fn fetch(method: &str, url: &str, options: RequestOptions) -> Result<Response, String> {
     let mut builder = AgentBuilder::new();
     if let Some(timeout) = options.timeout {
         builder = builder.timeout(timeout);
@@ -245,12 +279,12 @@ fn perform_request(method: &str, url: &str, options: RequestOptions) -> Result<V
         .map_err(|e| format!("Ошибка чтения тела ответа: {}", e))?;
 
     let should_parse_json = options.expect_json || has_json_content_type(&header_map);
-    let parsed_json = if should_parse_json {
+    let json = if should_parse_json {
         if body.trim().is_empty() {
-            Some(Value::Nil)
+            Some(serde_json::Value::Null)
         } else {
             match serde_json::from_str::<serde_json::Value>(&body) {
-                Ok(json) => Some(json_to_value(&json)?),
+                Ok(json) => Some(json),
                 Err(err) => {
                     if options.expect_json {
                         return Err(format!("Ошибка разбора JSON ответа: {}", err));
@@ -263,20 +297,31 @@ fn perform_request(method: &str, url: &str, options: RequestOptions) -> Result<V
         None
     };
 
-    let mut result = HashMap::new();
-    result.insert(
-        ValueKey::String("статус".to_string()),
-        Value::Number(status as f64),
-    );
+    Ok(Response {
+        status,
+        headers: header_map,
+        body,
+        json,
+    })
+}
+
+fn response_to_value(response: Response) -> Result<Value, String> {
+    let parsed_json = match &response.json {
+        Some(json) => json_to_value(json)?,
+        None => Value::Nil,
+    };
+
+    let mut result = Dict::new();
     result.insert(
-        ValueKey::String("заголовки".to_string()),
-        headers_to_value(header_map),
+        ValueKey::from("статус"),
+        Value::Number(response.status as f64),
     );
-    result.insert(ValueKey::String("тело".to_string()), Value::String(body));
     result.insert(
-        ValueKey::String("json".to_string()),
-        parsed_json.unwrap_or(Value::Nil),
+        ValueKey::from("заголовки"),
+        headers_to_value(response.headers),
     );
+    result.insert(ValueKey::from("тело"), Value::String(response.body.into()));
+    result.insert(ValueKey::from("json"), parsed_json);
 
     Ok(Value::Dict(Rc::new(RefCell::new(result))))
 }
@@ -297,10 +342,13 @@ fn has_json_content_type(headers: &HashMap<String, String>) -> bool {
     })
 }
 
+// Заголовки — в порядке имён, чтобы обход словаря не зависел от хеширования
 fn headers_to_value(headers: HashMap<String, String>) -> Value {
-    let mut map = HashMap::new();
-    for (k, v) in headers.into_iter() {
-        map.insert(ValueKey::String(k), Value::String(v));
-    }
+    let mut headers: Vec<_> = headers.into_iter().collect();
+    headers.sort();
+    let map = headers
+        .into_iter()
+        .map(|(k, v)| (ValueKey::String(k.into()), Value::String(v.into())))
+        .collect();
     Value::Dict(Rc::new(RefCell::new(map)))
 }
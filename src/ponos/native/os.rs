@@ -0,0 +1,63 @@
+use crate::ponos::value::Value;
+use std::env;
+use std::fs;
+
+// Сведения о машине и пользователе. Управление процессом — в модуле system
+
+fn expect_no_args(name: &str, args: &[Value]) -> Result<(), String> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} не принимает аргументов", name))
+    }
+}
+
+/// Первая непустая переменная окружения из списка
+fn first_env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// ос.пользователь() -> строка | ничто
+pub fn os_user(args: &[Value]) -> Result<Value, String> {
+    expect_no_args("пользователь", args)?;
+    Ok(first_env(&["USER", "LOGNAME", "USERNAME"]).map_or(Value::Nil, Value::from))
+}
+
+/// ос.домашний_каталог() -> строка | ничто
+pub fn os_home_dir(args: &[Value]) -> Result<Value, String> {
+    expect_no_args("домашний_каталог", args)?;
+    Ok(first_env(&["HOME", "USERPROFILE"]).map_or(Value::Nil, Value::from))
+}
+
+/// ос.имя_хоста() -> строка | ничто
+pub fn os_hostname(args: &[Value]) -> Result<Value, String> {
+    expect_no_args("имя_хоста", args)?;
+
+    let from_file = ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|content| content.trim().to_string())
+        .find(|name| !name.is_empty());
+
+    Ok(from_file
+        .or_else(|| first_env(&["HOSTNAME", "COMPUTERNAME"]))
+        .map_or(Value::Nil, Value::from))
+}
+
+/// ос.число_процессоров() -> число
+pub fn os_cpu_count(args: &[Value]) -> Result<Value, String> {
+    expect_no_args("число_процессоров", args)?;
+    let count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    Ok(Value::Int(count as i64))
+}
+
+/// ос.платформа() -> строка ("linux", "macos", "windows", ...)
+pub fn os_platform(args: &[Value]) -> Result<Value, String> {
+    expect_no_args("платформа", args)?;
+    Ok(Value::String(env::consts::OS.to_string().into()))
+}
@@ -0,0 +1,133 @@
+use crate::ponos::value::Value;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Работа с путями. Функции, которые разбирают путь (родитель, имя_файла,
+// расширение), к файловой системе не обращаются; отсутствующая часть пути
+// возвращается как ничто
+
+fn path_arg<'a>(name: &str, args: &'a [Value]) -> Result<&'a str, String> {
+    match args {
+        [Value::String(path)] => Ok(path),
+        [_] => Err(format!("{}: путь должен быть строкой", name)),
+        _ => Err(format!(
+            "{} ожидает 1 аргумент, передано: {}",
+            name,
+            args.len()
+        )),
+    }
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::String(path.display().to_string().into())
+}
+
+fn optional_string(part: Option<&std::ffi::OsStr>) -> Value {
+    part.map_or(Value::Nil, |part| {
+        Value::String(part.to_string_lossy().into_owned().into())
+    })
+}
+
+/// путь.объединить(часть, ...) -> строка
+///
+/// Абсолютная часть отбрасывает всё, что было перед ней: объединить("а", "/б") == "/б"
+pub fn path_join(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() {
+        return Err("объединить ожидает хотя бы 1 аргумент".to_string());
+    }
+    let mut result = PathBuf::new();
+    for arg in args {
+        match arg {
+            Value::String(part) => result.push(part.as_str()),
+            _ => return Err("объединить: части пути должны быть строками".to_string()),
+        }
+    }
+    Ok(path_value(&result))
+}
+
+/// путь.родитель(путь) -> строка | ничто
+pub fn path_parent(args: &[Value]) -> Result<Value, String> {
+    let path = Path::new(path_arg("родитель", args)?);
+    Ok(path.parent().map_or(Value::Nil, path_value))
+}
+
+/// путь.имя_файла(путь) -> строка | ничто
+pub fn path_file_name(args: &[Value]) -> Result<Value, String> {
+    let path = Path::new(path_arg("имя_файла", args)?);
+    Ok(optional_string(path.file_name()))
+}
+
+/// путь.расширение(путь) -> строка | ничто (без точки: "txt")
+pub fn path_extension(args: &[Value]) -> Result<Value, String> {
+    let path = Path::new(path_arg("расширение", args)?);
+    Ok(optional_string(path.extension()))
+}
+
+/// путь.абсолютный(путь) -> строка
+///
+/// Дополняет относительный путь текущим каталогом; файл может не существовать
+pub fn path_absolute(args: &[Value]) -> Result<Value, String> {
+    let path = path_arg("абсолютный", args)?;
+    let absolute = std::path::absolute(path)
+        .map_err(|e| format!("абсолютный: не удалось получить путь '{}': {}", path, e))?;
+    Ok(path_value(&absolute))
+}
+
+/// путь.существует_каталог(путь) -> булево
+pub fn path_is_dir(args: &[Value]) -> Result<Value, String> {
+    let path = path_arg("существует_каталог", args)?;
+    Ok(Value::Boolean(Path::new(path).is_dir()))
+}
+
+/// путь.создать_каталог(путь) -> ничто
+///
+/// Создаёт и промежуточные каталоги; существующий каталог не ошибка
+pub fn path_create_dir(args: &[Value]) -> Result<Value, String> {
+    let path = path_arg("создать_каталог", args)?;
+    fs::create_dir_all(path)
+        .map_err(|e| format!("Не удалось создать каталог '{}': {}", path, e))?;
+    Ok(Value::Nil)
+}
+
+/// путь.список_файлов(каталог) -> массив строк
+///
+/// Имена всех элементов каталога (файлов и подкаталогов) в алфавитном порядке
+pub fn path_list_files(args: &[Value]) -> Result<Value, String> {
+    let path = path_arg("список_файлов", args)?;
+    let entries = fs::read_dir(path)
+        .map_err(|e| format!("Не удалось прочитать каталог '{}': {}", path, e))?;
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Не удалось прочитать каталог '{}': {}", path, e))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+
+    let names = names.into_iter().map(Value::from).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(names))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(function: fn(&[Value]) -> Result<Value, String>, path: &str) -> Value {
+        function(&[Value::String(path.to_string().into())]).unwrap()
+    }
+
+    #[test]
+    fn splits_paths_without_touching_disk() {
+        let joined = path_join(&[Value::from("каталог"), Value::from("отчёт.tar.gz")]).unwrap();
+        assert_eq!(joined, Value::from("каталог/отчёт.tar.gz"));
+
+        let path = "каталог/отчёт.tar.gz";
+        assert_eq!(call(path_parent, path), Value::from("каталог"));
+        assert_eq!(call(path_file_name, path), Value::from("отчёт.tar.gz"));
+        assert_eq!(call(path_extension, path), Value::from("gz"));
+        assert_eq!(call(path_extension, "README"), Value::Nil);
+        assert_eq!(call(path_parent, "/"), Value::Nil);
+    }
+}
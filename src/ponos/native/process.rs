@@ -0,0 +1,126 @@
+use crate::ponos::native::system::{
+    ExecuteOptions, process_result, read_in_background, scalar_to_string, spawn_process,
+    wait_with_timeout,
+};
+use crate::ponos::value::{Value, ValueKey};
+use crate::ponos::vm::NativeContext;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+// стд/процесс: запуск программы с отдельным списком аргументов, без оболочки.
+// Параметры те же, что у система.выполнить, плюс потоковый режим для долгих
+// процессов: строки вывода передаются обработчику по мере появления
+
+/// Параметр потокового режима: функция(строка, поток)
+const LINE_HANDLER_OPTION: &str = "при_выводе";
+
+/// Строка вывода с именем потока ("вывод" или "ошибки")
+type Line = (&'static str, String);
+
+/// Читать поток построчно и пересылать строки в канал
+fn forward_lines(
+    stream: Option<impl Read + Send + 'static>,
+    name: &'static str,
+    sender: Sender<Line>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let Some(stream) = stream else {
+            return;
+        };
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+            if line.ends_with(b"\n") {
+                line.pop();
+            }
+            let text = String::from_utf8_lossy(&line).into_owned();
+            if sender.send((name, text)).is_err() {
+                return;
+            }
+            line.clear();
+        }
+    })
+}
+
+fn deliver_lines(
+    ctx: &mut NativeContext,
+    handler: &Value,
+    lines: &Receiver<Line>,
+) -> Result<(), String> {
+    while let Ok((stream, line)) = lines.try_recv() {
+        ctx.call(
+            handler,
+            &[
+                Value::String(line.into()),
+                Value::String(stream.to_string().into()),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// процесс.запустить(команда, аргументы = [], параметры = {}) -> словарь
+///
+/// Параметры: "каталог", "окружение", "таймаут", "ввод", как у
+/// система.выполнить, и "при_выводе" — функция(строка, поток), которой по
+/// мере появления передаются строки stdout ("вывод") и stderr ("ошибки").
+/// В потоковом режиме строки не накапливаются, и "вывод"/"ошибки" в
+/// результате пусты. Результат: { "вывод", "ошибки", "код", "превышено_время" }
+pub fn process_run(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(
+            "запустить требует 1-3 аргумента: команда, [аргументы], [параметры]".to_string(),
+        );
+    }
+    let Value::String(command) = &args[0] else {
+        return Err("запустить: команда должна быть строкой".to_string());
+    };
+    let cmd_args = match args.get(1) {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items
+            .borrow()
+            .iter()
+            .map(scalar_to_string)
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err("запустить: аргументы должны быть массивом".to_string()),
+    };
+
+    let (mut options, handler) = match args.get(2) {
+        None => (ExecuteOptions::default(), None),
+        Some(Value::Dict(dict)) => {
+            // Обработчик — параметр только этого модуля, остальное разбирает система
+            let mut dict = dict.borrow().clone();
+            let handler = dict.remove(&ValueKey::from(LINE_HANDLER_OPTION));
+            (ExecuteOptions::from_dict(&dict)?, handler)
+        }
+        Some(_) => return Err("запустить: параметры должны быть словарем".to_string()),
+    };
+
+    let mut child = spawn_process(command, &cmd_args, &mut options)?;
+
+    let Some(handler) = handler else {
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+        let (status, timed_out) = wait_with_timeout(&mut child, options.timeout, || Ok(()))?;
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        return Ok(process_result(&stdout, &stderr, status, timed_out));
+    };
+
+    let (sender, lines) = mpsc::channel();
+    let readers = [
+        forward_lines(child.stdout.take(), "вывод", sender.clone()),
+        forward_lines(child.stderr.take(), "ошибки", sender),
+    ];
+    let (status, timed_out) = wait_with_timeout(&mut child, options.timeout, || {
+        deliver_lines(ctx, &handler, &lines)
+    })?;
+    // Процесс завершился, но в каналах могли остаться последние строки
+    for reader in readers {
+        let _ = reader.join();
+    }
+    deliver_lines(ctx, &handler, &lines)?;
+
+    Ok(process_result(&[], &[], status, timed_out))
+}
@@ -0,0 +1,183 @@
+use crate::ponos::value::Value;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Генератор xorshift64* — общий для стд/случайное и мат.случайное(), поэтому
+// установить_зерно делает воспроизводимыми оба. Генератор быстрый, но не
+// криптографический
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(scramble(time_seed()));
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Перемешать биты зерна (splitmix64): близкие зёрна дают несвязанные
+/// последовательности, а нулевое состояние, из которого xorshift не выходит,
+/// не получается
+fn scramble(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) | 1
+}
+
+fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// Равномерное число из [0, 1): старшие 53 бита укладываются в мантиссу f64
+pub fn next_f64() -> f64 {
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Равномерное целое из [0, n)
+fn next_below(n: u64) -> u64 {
+    // Отбрасываем «хвост», чтобы остаток от деления не смещал распределение
+    let zone = u64::MAX - u64::MAX % n;
+    loop {
+        let x = next_u64();
+        if x < zone {
+            return x % n;
+        }
+    }
+}
+
+fn integer_arg(name: &str, value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Int(n) => Ok(*n),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => Ok(*n as i64),
+        Value::Number(n) => Err(format!("{}: ожидалось целое число, получено {}", name, n)),
+        _ => Err(format!("{}: аргумент должен быть числом", name)),
+    }
+}
+
+/// случайное.случайное() -> число из [0, 1)
+pub fn random_float(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("случайное не принимает аргументов".to_string());
+    }
+    Ok(Value::Number(next_f64()))
+}
+
+/// случайное.случайное_целое(от, до) -> число из [от, до] (обе границы включены)
+pub fn random_int(args: &[Value]) -> Result<Value, String> {
+    let [from, to] = args else {
+        return Err("случайное_целое требует 2 аргумента: от и до".to_string());
+    };
+    let from = integer_arg("случайное_целое", from)?;
+    let to = integer_arg("случайное_целое", to)?;
+    if from > to {
+        return Err(format!(
+            "случайное_целое: нижняя граница {} больше верхней {}",
+            from, to
+        ));
+    }
+    // Разность считается в u64: на всём диапазоне i64 она не помещается в i64
+    let offset = match (to.wrapping_sub(from) as u64).checked_add(1) {
+        Some(span) => next_below(span),
+        None => next_u64(),
+    };
+    Ok(Value::Int(from.wrapping_add(offset as i64)))
+}
+
+/// случайное.перемешать(массив) -> Array
+///
+/// Возвращает новый перемешанный массив (алгоритм Фишера — Йетса), исходный
+/// не меняется
+pub fn random_shuffle(args: &[Value]) -> Result<Value, String> {
+    let [Value::Array(array)] = args else {
+        return Err("перемешать требует 1 аргумент: массив".to_string());
+    };
+    let mut items = array.borrow().clone();
+    for i in (1..items.len()).rev() {
+        let j = next_below(i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(items))))
+}
+
+/// случайное.выбрать(массив) -> случайный элемент
+pub fn random_choice(args: &[Value]) -> Result<Value, String> {
+    let [Value::Array(array)] = args else {
+        return Err("выбрать требует 1 аргумент: массив".to_string());
+    };
+    let items = array.borrow();
+    if items.is_empty() {
+        return Err("выбрать: массив пуст".to_string());
+    }
+    Ok(items[next_below(items.len() as u64) as usize].clone())
+}
+
+/// случайное.установить_зерно(n) -> ничто
+///
+/// После установки зерна последовательность случайных чисел повторяется
+/// от запуска к запуску
+pub fn random_seed(args: &[Value]) -> Result<Value, String> {
+    let [seed] = args else {
+        return Err("установить_зерно требует 1 аргумент: число".to_string());
+    };
+    let seed = integer_arg("установить_зерно", seed)?;
+    STATE.with(|state| state.set(scramble(seed as u64)));
+    Ok(Value::Nil)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_makes_sequence_reproducible() {
+        random_seed(&[Value::Number(42.0)]).unwrap();
+        let first: Vec<u64> = (0..5).map(|_| next_u64()).collect();
+        random_seed(&[Value::Number(42.0)]).unwrap();
+        let second: Vec<u64> = (0..5).map(|_| next_u64()).collect();
+        assert_eq!(first, second);
+
+        random_seed(&[Value::Number(43.0)]).unwrap();
+        assert_ne!(next_u64(), first[0]);
+    }
+
+    #[test]
+    fn integers_cover_inclusive_range() {
+        random_seed(&[Value::Number(7.0)]).unwrap();
+        let mut seen = [false; 3];
+        for _ in 0..200 {
+            match random_int(&[Value::Int(-1), Value::Number(1.0)]).unwrap() {
+                Value::Int(n) => seen[(n + 1) as usize] = true,
+                other => panic!("Ожидалось число, получено {:?}", other),
+            }
+        }
+        assert_eq!(seen, [true; 3]);
+        assert!(random_int(&[Value::Number(2.0), Value::Number(1.0)]).is_err());
+    }
+
+    #[test]
+    fn integers_accept_extreme_bounds() {
+        let full = [Value::Int(i64::MIN), Value::Int(i64::MAX)];
+        assert!(matches!(random_int(&full), Ok(Value::Int(_))));
+        for bounds in [(i64::MIN, i64::MIN + 1), (i64::MAX - 1, i64::MAX)] {
+            match random_int(&[Value::Int(bounds.0), Value::Int(bounds.1)]).unwrap() {
+                Value::Int(n) => assert!((bounds.0..=bounds.1).contains(&n)),
+                other => panic!("Ожидалось число, получено {:?}", other),
+            }
+        }
+        assert_eq!(
+            random_int(&[Value::Int(i64::MAX), Value::Int(i64::MAX)]),
+            Ok(Value::Int(i64::MAX))
+        );
+    }
+}
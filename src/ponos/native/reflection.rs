@@ -0,0 +1,156 @@
+use crate::ponos::typechecker::Type;
+use crate::ponos::value::{Dict, Value, ValueKey};
+use crate::ponos::vm::{NativeContext, VM};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Встроенные функции для интроспекции значений: тип() и атрибуты().
+// Проверка `является(значение, Класс)` обходит цепочку наследования и
+// реализована в VM рядом с инструкцией InstanceOf. Модуль стд/рефлексия
+// описывает модули и функции: экспорты, имя, арность и поиск глобальной
+// переменной по строке
+
+/// Зарегистрировать встроенные функции интроспекции
+pub fn register(vm: &mut VM) {
+    vm.register_and_define("тип", type_of);
+    vm.register_and_define("атрибуты", attributes);
+}
+
+/// тип(значение) -> строка
+///
+/// Имя типа, как в аннотациях: "число", "строка", "массив", ...; для
+/// экземпляра — имя его класса
+pub fn type_of(args: &[Value]) -> Result<Value, String> {
+    let [value] = args else {
+        return Err("тип() требует ровно 1 аргумент".to_string());
+    };
+    let name = match value {
+        Value::StringBuilder(_) => "построитель_строк".to_string(),
+        Value::Class(_) => "класс".to_string(),
+        Value::Range(..) => "диапазон".to_string(),
+        Value::Resource(resource) => resource.kind.to_string(),
+        Value::Task(_) => "задача".to_string(),
+        other => Type::of_value(other).to_string(),
+    };
+    Ok(Value::String(name.into()))
+}
+
+/// атрибуты(экземпляр) -> словарь полей
+///
+/// Поля идут в порядке объявления, от базового класса к производному;
+/// поля, не объявленные в классе, — после них по алфавиту. Словарь — копия:
+/// его изменение не меняет экземпляр
+pub fn attributes(args: &[Value]) -> Result<Value, String> {
+    let instance = match args {
+        [Value::Instance(instance)] => instance.borrow(),
+        [other] => {
+            return Err(format!(
+                "атрибуты: ожидается экземпляр класса, получено: {}",
+                Type::of_value(other)
+            ));
+        }
+        _ => return Err("атрибуты() требует ровно 1 аргумент".to_string()),
+    };
+
+    let mut chain = Vec::new();
+    let mut class = Some(instance.class.clone());
+    while let Some(current) = class {
+        class = current.parent.clone();
+        chain.push(current);
+    }
+    let mut names: Vec<&String> = chain
+        .iter()
+        .rev()
+        .flat_map(|class| &class.fields)
+        .filter(|name| instance.fields.contains_key(*name))
+        .collect();
+    let mut undeclared: Vec<&String> = instance
+        .fields
+        .keys()
+        .filter(|name| !names.contains(name))
+        .collect();
+    undeclared.sort();
+    names.extend(undeclared);
+
+    let fields: Dict = names
+        .into_iter()
+        .map(|name| {
+            (
+                ValueKey::String(name.clone().into()),
+                instance.fields[name].clone(),
+            )
+        })
+        .collect();
+    Ok(Value::Dict(Rc::new(RefCell::new(fields))))
+}
+
+/// рефлексия.экспорты(модуль) -> массив имён
+///
+/// Экспорты загруженного модуля в порядке объявления; модуль задаётся
+/// пространством имён, под которым он импортирован
+pub fn module_exports(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let namespace = match args {
+        [Value::String(namespace)] => namespace,
+        [_] => return Err("экспорты: аргумент должен быть строкой".to_string()),
+        _ => return Err("экспорты() требует ровно 1 аргумент: модуль".to_string()),
+    };
+    let exports = ctx
+        .module_exports(namespace)
+        .ok_or_else(|| format!("экспорты: модуль '{}' не загружен", namespace))?;
+    let names = exports.into_iter().map(Value::from).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(names))))
+}
+
+/// рефлексия.имя(функция) -> строка
+///
+/// Имя, под которым функция объявлена; у нативной функции — имя её
+/// глобальной переменной, у анонимной функции и встроенного метода — `ничто`
+pub fn function_name(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let name = match function_arg("имя", args)? {
+        Value::Function(function) => Some(function.name.clone()),
+        Value::Closure(closure) => Some(closure.function.name.clone()),
+        Value::BoundMethod(bound) => Some(bound.method.name.clone()),
+        Value::NativeFunction(id) => ctx.native_name(id),
+        _ => None,
+    };
+    Ok(name
+        .filter(|name| name != "<lambda>")
+        .map_or(Value::Nil, Value::from))
+}
+
+/// рефлексия.арность(функция) -> число
+///
+/// Число параметров без `...остальные`, включая необязательные; у метода
+/// `это` не считается. Для нативных функций — `ничто`
+pub fn function_arity(args: &[Value]) -> Result<Value, String> {
+    let function = match function_arg("арность", args)? {
+        Value::Function(function) => function,
+        Value::Closure(closure) => &closure.function,
+        Value::BoundMethod(bound) => &bound.method,
+        _ => return Ok(Value::Nil),
+    };
+    Ok(Value::Int(function.positional_arity() as i64))
+}
+
+/// рефлексия.получить_глобал(имя) -> значение или ничто
+///
+/// Глобальная переменная по имени: `"функ"` или `"модуль::функ"`
+pub fn lookup_global(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    match args {
+        [Value::String(name)] => Ok(ctx.global(name).unwrap_or(Value::Nil)),
+        [_] => Err("получить_глобал: аргумент должен быть строкой".to_string()),
+        _ => Err("получить_глобал() требует ровно 1 аргумент: имя".to_string()),
+    }
+}
+
+fn function_arg<'a>(name: &str, args: &'a [Value]) -> Result<&'a Value, String> {
+    match args {
+        [value] if matches!(Type::of_value(value), Type::Function(_)) => Ok(value),
+        [other] => Err(format!(
+            "{}: ожидается функция, получено: {}",
+            name,
+            Type::of_value(other)
+        )),
+        _ => Err(format!("{}() требует ровно 1 аргумент: функция", name)),
+    }
+}
@@ -0,0 +1,868 @@
+use crate::ponos::value::Value;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+// Регулярные выражения для стд/регэксп. Шаблон компилируется в программу,
+// которая выполняется перебором с возвратами поверх символов Unicode. Развилки
+// лежат на явном стеке, так что длина строки не ограничена стеком потока, а
+// пройденные состояния запоминаются, так что перебор не уходит в экспоненту.
+// Поддерживаются: литералы, `.`, классы `[а-я]` и `[^...]`, `\d \w \s` и их
+// отрицания, `\b \B`, якоря `^ $`, группы `(...)` и `(?:...)`, альтернатива
+// `|`, квантификаторы `* + ? {n} {n,} {n,m}` с ленивыми вариантами. Обратных
+// ссылок и просмотра вперёд нет
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ClassItem::Char(x) => c == *x,
+            ClassItem::Range(from, to) => (*from..=*to).contains(&c),
+            ClassItem::Digit(positive) => c.is_ascii_digit() == *positive,
+            ClassItem::Word(positive) => is_word_char(c) == *positive,
+            ClassItem::Space(positive) => c.is_whitespace() == *positive,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    Group {
+        inner: Box<Node>,
+        index: Option<usize>,
+    },
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        inner: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Скомпилированный шаблон
+#[derive(Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    group_count: usize,
+}
+
+/// Границы группы в символах: None, если группа не участвовала в совпадении
+type Captures = Vec<Option<(usize, usize)>>;
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = PatternParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            group_count: 0,
+        };
+        let root = parser.parse_alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("лишняя закрывающая скобка ')'"));
+        }
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.compile(&root)?;
+        compiler.emit(Inst::Match)?;
+        Ok(Regex {
+            program: compiler.program,
+            group_count: parser.group_count,
+        })
+    }
+
+    /// Найти первое совпадение, начиная с позиции `from`. Нулевая группа —
+    /// всё совпадение
+    fn find_at(&self, text: &[char], from: usize) -> Option<Captures> {
+        self.search(
+            text,
+            from,
+            &mut Visited::new(self.program.len(), text.len()),
+        )
+    }
+
+    // Неудачный перебор с одного начала оставляет в `visited` только
+    // состояния, из которых совпадения нет, поэтому они общие для всех начал
+    fn search(&self, text: &[char], from: usize, visited: &mut Visited) -> Option<Captures> {
+        let mut matcher = Matcher {
+            regex: self,
+            text,
+            visited,
+        };
+        (from..=text.len()).find_map(|start| matcher.run(start))
+    }
+
+    /// Все непересекающиеся совпадения слева направо
+    fn find_all(&self, text: &[char]) -> Vec<Captures> {
+        let mut result = Vec::new();
+        let mut visited = Visited::new(self.program.len(), text.len());
+        let mut from = 0;
+        while from <= text.len() {
+            let Some(captures) = self.search(text, from, &mut visited) else {
+                break;
+            };
+            // Состояния на пути к найденному совпадению не были неудачными
+            visited.clear();
+            let (start, end) = captures[0].expect("нулевая группа всегда задана");
+            // Пустое совпадение не должно зациклить поиск
+            from = if end == start { end + 1 } else { end };
+            result.push(captures);
+        }
+        result
+    }
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+    group_count: usize,
+}
+
+impl PatternParser {
+    fn error(&self, message: &str) -> String {
+        format!("Неверный шаблон (позиция {}): {}", self.pos, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alternation(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                (0, None)
+            }
+            Some('+') => {
+                self.pos += 1;
+                (1, None)
+            }
+            Some('?') => {
+                self.pos += 1;
+                (0, Some(1))
+            }
+            Some('{') => match self.parse_braces()? {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(
+            atom,
+            Node::Start | Node::End | Node::WordBoundary(_) | Node::Repeat { .. }
+        ) {
+            return Err(self.error("квантификатор не к чему применить"));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            inner: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// `{n}`, `{n,}`, `{n,m}`; если после `{` не число, скобка — обычный символ
+    fn parse_braces(&mut self) -> Result<Option<(usize, Option<usize>)>, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let min = self.parse_number();
+        let Some(min) = min else {
+            self.pos = start;
+            return Ok(None);
+        };
+        let max = if self.eat(',') {
+            self.parse_number()
+        } else {
+            Some(min)
+        };
+        if !self.eat('}') {
+            self.pos = start;
+            return Ok(None);
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("в {n,m} n больше m"));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("неожиданный конец"))?;
+        self.pos += 1;
+        match c {
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(self.error("поддерживаются только группы (?:...)"));
+                    }
+                    None
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count)
+                };
+                let inner = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("не закрыта скобка '('"));
+                }
+                Ok(Node::Group {
+                    inner: Box::new(inner),
+                    index,
+                })
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '*' | '+' | '?' => Err(self.error("квантификатор не к чему применить")),
+            '\\' => self.parse_escape(),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("шаблон оканчивается на '\\'"))?;
+        self.pos += 1;
+        let class = |item| Node::Class {
+            items: vec![item],
+            negated: false,
+        };
+        Ok(match c {
+            'd' => class(ClassItem::Digit(true)),
+            'D' => class(ClassItem::Digit(false)),
+            'w' => class(ClassItem::Word(true)),
+            'W' => class(ClassItem::Word(false)),
+            's' => class(ClassItem::Space(true)),
+            'S' => class(ClassItem::Space(false)),
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            other => Node::Char(escaped_char(other)),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("не закрыта скобка '['"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let item = if c == '\\' {
+                let escaped = self
+                    .peek()
+                    .ok_or_else(|| self.error("шаблон оканчивается на '\\'"))?;
+                self.pos += 1;
+                match escaped {
+                    'd' => ClassItem::Digit(true),
+                    'D' => ClassItem::Digit(false),
+                    'w' => ClassItem::Word(true),
+                    'W' => ClassItem::Word(false),
+                    's' => ClassItem::Space(true),
+                    'S' => ClassItem::Space(false),
+                    other => ClassItem::Char(escaped_char(other)),
+                }
+            } else {
+                ClassItem::Char(c)
+            };
+
+            // Диапазон а-я; '-' в конце класса — обычный символ
+            if let ClassItem::Char(from) = item
+                && self.peek() == Some('-')
+                && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']')
+            {
+                self.pos += 1;
+                let mut to = self.chars[self.pos];
+                self.pos += 1;
+                if to == '\\' {
+                    to = escaped_char(
+                        self.peek()
+                            .ok_or_else(|| self.error("шаблон оканчивается на '\\'"))?,
+                    );
+                    self.pos += 1;
+                }
+                if to < from {
+                    return Err(self.error("неверный диапазон в классе символов"));
+                }
+                items.push(ClassItem::Range(from, to));
+            } else {
+                items.push(item);
+            }
+        }
+        Ok(Node::Class { items, negated })
+    }
+}
+
+fn escaped_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        other => other,
+    }
+}
+
+/// Инструкция программы, в которую компилируется шаблон
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Записать позицию в ячейку границ групп
+    Save(usize),
+    /// Продолжить с первого адреса, а при неудаче — со второго
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Предел размера программы: повторения `{n,m}` раскрываются в копии
+const MAX_PROGRAM_LEN: usize = 100_000;
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_PROGRAM_LEN {
+            return Err("Неверный шаблон: слишком много повторений".to_string());
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Char(c) => self.emit(Inst::Char(*c)).map(drop),
+            Node::Any => self.emit(Inst::Any).map(drop),
+            Node::Class { items, negated } => self
+                .emit(Inst::Class {
+                    items: items.clone(),
+                    negated: *negated,
+                })
+                .map(drop),
+            Node::Start => self.emit(Inst::Start).map(drop),
+            Node::End => self.emit(Inst::End).map(drop),
+            Node::WordBoundary(expected) => self.emit(Inst::WordBoundary(*expected)).map(drop),
+            Node::Group { inner, index: None } => self.compile(inner),
+            Node::Group {
+                inner,
+                index: Some(index),
+            } => {
+                self.emit(Inst::Save(2 * index))?;
+                self.compile(inner)?;
+                self.emit(Inst::Save(2 * index + 1)).map(drop)
+            }
+            Node::Concat(nodes) => nodes.iter().try_for_each(|node| self.compile(node)),
+            Node::Alternation(branches) => {
+                // Split на каждую ветку, кроме последней; ветки прыгают в конец
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.compile(branch)?;
+                        jumps.push(self.emit(Inst::Jump(0))?);
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    } else {
+                        self.compile(branch)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+                Ok(())
+            }
+            Node::Repeat {
+                inner,
+                min,
+                max,
+                greedy,
+            } => self.compile_repeat(inner, *min, *max, *greedy),
+        }
+    }
+
+    fn compile_repeat(
+        &mut self,
+        inner: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    ) -> Result<(), String> {
+        for _ in 0..min {
+            self.compile(inner)?;
+        }
+        // Необязательные итерации. Пустая итерация `*` возвращает перебор в
+        // уже пройденное состояние и потому отбрасывается, не зацикливая его
+        let optional = |this: &mut Self| -> Result<usize, String> {
+            let split = this.emit(Inst::Split(0, 0))?;
+            this.compile(inner)?;
+            Ok(split)
+        };
+        let (splits, end) = match max {
+            None => {
+                let split = optional(self)?;
+                self.emit(Inst::Jump(split))?;
+                (vec![split], self.program.len())
+            }
+            Some(max) => {
+                let splits = (min..max)
+                    .map(|_| optional(self))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (splits, self.program.len())
+            }
+        };
+        for split in splits {
+            self.program[split] = if greedy {
+                Inst::Split(split + 1, end)
+            } else {
+                Inst::Split(end, split + 1)
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Что восстановить при возврате к последней развилке
+enum Backtrack {
+    Resume { pc: usize, pos: usize },
+    Slot { slot: usize, value: Option<usize> },
+}
+
+/// Предел битовой карты пройденных состояний (4 МБ); для программ и строк
+/// больше неё состояния хранятся в множестве
+const MAX_VISITED_BITS: usize = 1 << 25;
+
+/// Пройденные состояния (инструкция, позиция). Без обратных ссылок исход
+/// перебора из состояния не зависит от пути к нему: если состояние уже
+/// встречалось, то либо оттуда совпадения нет, либо это пустая итерация
+/// повторения. Поэтому перебор делает не больше шагов, чем длина программы,
+/// умноженная на длину строки
+enum Visited {
+    Bits {
+        width: usize,
+        words: Vec<u64>,
+        // Номера ненулевых слов, чтобы очистка не проходила всю карту
+        touched: Vec<usize>,
+    },
+    Set {
+        width: usize,
+        states: HashSet<usize>,
+    },
+}
+
+impl Visited {
+    fn new(program_len: usize, text_len: usize) -> Visited {
+        let width = text_len + 1;
+        match program_len.checked_mul(width) {
+            Some(bits) if bits <= MAX_VISITED_BITS => Visited::Bits {
+                width,
+                words: vec![0; bits.div_ceil(64)],
+                touched: Vec::new(),
+            },
+            _ => Visited::Set {
+                width,
+                states: HashSet::new(),
+            },
+        }
+    }
+
+    /// Отметить состояние; ложь, если оно уже было пройдено
+    fn insert(&mut self, pc: usize, pos: usize) -> bool {
+        match self {
+            Visited::Bits {
+                width,
+                words,
+                touched,
+            } => {
+                let bit = pc * *width + pos;
+                let word = &mut words[bit / 64];
+                if *word & (1 << (bit % 64)) != 0 {
+                    return false;
+                }
+                if *word == 0 {
+                    touched.push(bit / 64);
+                }
+                *word |= 1 << (bit % 64);
+                true
+            }
+            Visited::Set { width, states } => states.insert(pc * *width + pos),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Visited::Bits { words, touched, .. } => {
+                for word in touched.drain(..) {
+                    words[word] = 0;
+                }
+            }
+            Visited::Set { states, .. } => states.clear(),
+        }
+    }
+}
+
+struct Matcher<'a> {
+    regex: &'a Regex,
+    text: &'a [char],
+    visited: &'a mut Visited,
+}
+
+impl Matcher<'_> {
+    fn is_word_at(&self, pos: Option<usize>) -> bool {
+        pos.and_then(|p| self.text.get(p))
+            .is_some_and(|&c| is_word_char(c))
+    }
+
+    /// Выполнить программу с позиции `start`. Развилки хранятся на явном
+    /// стеке, поэтому глубина перебора не ограничена стеком потока
+    fn run(&mut self, start: usize) -> Option<Captures> {
+        let program = &self.regex.program;
+        let mut slots = vec![None; 2 * (self.regex.group_count + 1)];
+        let mut backtrack = Vec::new();
+        let (mut pc, mut pos) = (0, start);
+        loop {
+            let current = self.text.get(pos).copied();
+            // Позиция после инструкции или None, если она не совпала
+            let next = match &program[pc] {
+                // Из пройденного состояния совпадения нет
+                _ if !self.visited.insert(pc, pos) => None,
+                Inst::Char(c) => (current == Some(*c)).then_some(pos + 1),
+                Inst::Any => current.is_some_and(|c| c != '\n').then_some(pos + 1),
+                Inst::Class { items, negated } => current
+                    .is_some_and(|c| items.iter().any(|item| item.matches(c)) != *negated)
+                    .then_some(pos + 1),
+                Inst::Start => (pos == 0).then_some(pos),
+                Inst::End => (pos == self.text.len()).then_some(pos),
+                Inst::WordBoundary(expected) => {
+                    let before = self.is_word_at(pos.checked_sub(1));
+                    let after = self.is_word_at(Some(pos));
+                    ((before != after) == *expected).then_some(pos)
+                }
+                Inst::Save(slot) => {
+                    backtrack.push(Backtrack::Slot {
+                        slot: *slot,
+                        value: slots[*slot],
+                    });
+                    slots[*slot] = Some(pos);
+                    Some(pos)
+                }
+                Inst::Split(first, second) => {
+                    backtrack.push(Backtrack::Resume { pc: *second, pos });
+                    pc = *first;
+                    continue;
+                }
+                Inst::Jump(target) => {
+                    pc = *target;
+                    continue;
+                }
+                Inst::Match => {
+                    slots[0] = Some(start);
+                    slots[1] = Some(pos);
+                    return Some(
+                        slots
+                            .chunks(2)
+                            .map(|bounds| bounds[0].zip(bounds[1]))
+                            .collect(),
+                    );
+                }
+            };
+            if let Some(next) = next {
+                pc += 1;
+                pos = next;
+                continue;
+            }
+            // Неудача: откатиться к последней развилке
+            loop {
+                match backtrack.pop()? {
+                    Backtrack::Resume { pc: next, pos: at } => {
+                        pc = next;
+                        pos = at;
+                        break;
+                    }
+                    Backtrack::Slot { slot, value } => slots[slot] = value,
+                }
+            }
+        }
+    }
+}
+
+fn substring(text: &[char], (start, end): (usize, usize)) -> String {
+    text[start..end].iter().collect()
+}
+
+fn pattern_and_text<'a>(
+    name: &str,
+    args: &'a [Value],
+    arity: usize,
+) -> Result<(Regex, &'a str), String> {
+    if args.len() != arity {
+        return Err(format!(
+            "{} ожидает {} аргумента, передано: {}",
+            name,
+            arity,
+            args.len()
+        ));
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(pattern), Value::String(text)) => Ok((Regex::new(pattern)?, text)),
+        _ => Err(format!("{}: шаблон и строка должны быть строками", name)),
+    }
+}
+
+/// регэксп.совпадает(шаблон, строка) -> булево
+///
+/// Истина, если шаблон встречается в строке; для проверки всей строки
+/// используйте якоря: "^...$"
+pub fn regex_is_match(args: &[Value]) -> Result<Value, String> {
+    let (regex, text) = pattern_and_text("совпадает", args, 2)?;
+    let text: Vec<char> = text.chars().collect();
+    Ok(Value::Boolean(regex.find_at(&text, 0).is_some()))
+}
+
+/// регэксп.найти_все(шаблон, строка) -> массив строк
+pub fn regex_find_all(args: &[Value]) -> Result<Value, String> {
+    let (regex, text) = pattern_and_text("найти_все", args, 2)?;
+    let text: Vec<char> = text.chars().collect();
+    let found = regex
+        .find_all(&text)
+        .into_iter()
+        .filter_map(|caps| caps[0])
+        .map(|span| Value::String(substring(&text, span).into()))
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(found))))
+}
+
+/// регэксп.группы(шаблон, строка) -> массив | ничто
+///
+/// Группы первого совпадения: [всё совпадение, группа 1, ...]. Группа, не
+/// участвовавшая в совпадении, равна ничто. Без совпадения — ничто
+pub fn regex_groups(args: &[Value]) -> Result<Value, String> {
+    let (regex, text) = pattern_and_text("группы", args, 2)?;
+    let text: Vec<char> = text.chars().collect();
+    Ok(match regex.find_at(&text, 0) {
+        Some(caps) => {
+            let groups = caps
+                .into_iter()
+                .map(|span| {
+                    span.map_or(Value::Nil, |span| {
+                        Value::String(substring(&text, span).into())
+                    })
+                })
+                .collect();
+            Value::Array(Rc::new(RefCell::new(groups)))
+        }
+        None => Value::Nil,
+    })
+}
+
+/// регэксп.заменить(шаблон, строка, замена) -> строка
+///
+/// Заменяет все совпадения. В замене `$0` — всё совпадение, `$1`... — группы,
+/// `$$` — знак доллара
+pub fn regex_replace(args: &[Value]) -> Result<Value, String> {
+    let (regex, text) = pattern_and_text("заменить", args, 3)?;
+    let replacement = match &args[2] {
+        Value::String(s) => s,
+        _ => return Err("заменить: замена должна быть строкой".to_string()),
+    };
+    let text: Vec<char> = text.chars().collect();
+
+    let mut result = String::new();
+    let mut last = 0;
+    for caps in regex.find_all(&text) {
+        let (start, end) = caps[0].expect("нулевая группа всегда задана");
+        result.extend(&text[last..start]);
+        expand_replacement(replacement, &caps, &text, &mut result);
+        last = end;
+    }
+    result.extend(&text[last..]);
+    Ok(Value::String(result.into()))
+}
+
+fn expand_replacement(replacement: &str, caps: &Captures, text: &[char], out: &mut String) {
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'$') {
+            chars.next();
+            out.push('$');
+            continue;
+        }
+        let mut digits = String::new();
+        while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+            digits.push(*d);
+            chars.next();
+        }
+        match digits.parse::<usize>() {
+            Ok(index) => {
+                if let Some(Some(span)) = caps.get(index) {
+                    out.push_str(&substring(text, *span));
+                }
+            }
+            Err(_) => out.push('$'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<String> {
+        let text: Vec<char> = text.chars().collect();
+        Regex::new(pattern)
+            .unwrap()
+            .find_at(&text, 0)
+            .and_then(|caps| caps[0])
+            .map(|span| substring(&text, span))
+    }
+
+    #[test]
+    fn matches_basic_constructs() {
+        assert_eq!(find("б+", "аббв").as_deref(), Some("бб"));
+        assert_eq!(find("б+?", "аббв").as_deref(), Some("б"));
+        assert_eq!(find("^\\d{2,3}$", "1234"), None);
+        assert_eq!(find("^\\d{2,3}$", "123").as_deref(), Some("123"));
+        assert_eq!(find("[а-яё]+", "Привет").as_deref(), Some("ривет"));
+        assert_eq!(find("[^а-я ]+", "мир 42").as_deref(), Some("42"));
+        assert_eq!(find("кот|пёс", "мой пёс").as_deref(), Some("пёс"));
+        assert_eq!(find("\\bмир\\b", "мирный мир").as_deref(), Some("мир"));
+        assert_eq!(find("(а|б)*в", "ааббв").as_deref(), Some("ааббв"));
+        assert_eq!(find("(?:)*x", "x").as_deref(), Some("x"));
+        assert_eq!(find("a{,2}", "a{,2}").as_deref(), Some("a{,2}"));
+    }
+
+    #[test]
+    fn matches_long_input_without_recursion() {
+        let long = "a".repeat(200_000);
+        assert_eq!(find("^a*$", &long).map(|m| m.len()), Some(long.len()));
+        assert_eq!(
+            find("^(?:a|b)+?$", &long).map(|m| m.len()),
+            Some(long.len())
+        );
+        assert_eq!(find("^a*b", &long), None);
+
+        let text: Vec<char> = "ааб".chars().collect();
+        let caps = Regex::new("(а)*(б)?(в)?")
+            .unwrap()
+            .find_at(&text, 0)
+            .unwrap();
+        assert_eq!(caps, vec![Some((0, 3)), Some((1, 2)), Some((2, 3)), None]);
+    }
+
+    #[test]
+    fn nested_repeats_take_polynomial_time() {
+        let started = std::time::Instant::now();
+        let text = "a".repeat(5_000);
+        assert_eq!(find("(a*)*b", &text), None);
+        assert_eq!(find("(a|aa)+$", &text).map(|m| m.len()), Some(text.len()));
+        assert_eq!(
+            find("^(a?){500}a{500}$", &"a".repeat(500)).map(|m| m.len()),
+            Some(500)
+        );
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // Пустая итерация повторения не зацикливает перебор
+        assert_eq!(find("(a*)*b", "aab").as_deref(), Some("aab"));
+        let regex = Regex::new("(a|)+").unwrap();
+        let text: Vec<char> = "aa-a".chars().collect();
+        let spans: Vec<_> = regex.find_all(&text).iter().map(|caps| caps[0]).collect();
+        assert_eq!(
+            spans,
+            vec![Some((0, 2)), Some((2, 2)), Some((3, 4)), Some((4, 4))]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in ["(а", "а)", "*а", "[а-", "а{3,1}", "\\", "(а{1000}){1000}"] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::ponos::value::{Dict, Resource, ResourceHandle, Value, ValueKey};
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::rc::Rc;
+use std::time::Duration;
+
+// Сокеты стд/сокеты. Сокет — значение-ресурс: дескриптор закрывается
+// функцией закрыть() или сам, когда на сокет больше нет ссылок. Данные
+// передаются строками UTF-8; некорректные байты при получении заменяются на �
+
+const TCP_KIND: &str = "TCP-сокет";
+const UDP_KIND: &str = "UDP-сокет";
+
+/// Сколько байт читает получить(), если предел не указан
+const DEFAULT_RECEIVE_LIMIT: usize = 64 * 1024;
+
+fn string_arg<'a>(name: &str, what: &str, value: &'a Value) -> Result<&'a str, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(format!("{}: {} должен быть строкой", name, what)),
+    }
+}
+
+fn port_arg(name: &str, value: &Value) -> Result<u16, String> {
+    match value.as_number() {
+        Some(n) if n.fract() == 0.0 && (0.0..=65535.0).contains(&n) => Ok(n as u16),
+        _ => Err(format!(
+            "{}: порт должен быть целым числом от 0 до 65535",
+            name
+        )),
+    }
+}
+
+fn limit_arg(name: &str, value: Option<&Value>) -> Result<usize, String> {
+    match value.map(Value::as_number) {
+        None => Ok(DEFAULT_RECEIVE_LIMIT),
+        Some(Some(n)) if n >= 1.0 && n.fract() == 0.0 => Ok(n as usize),
+        Some(_) => Err(format!(
+            "{}: предел должен быть натуральным числом байт",
+            name
+        )),
+    }
+}
+
+/// Выполнить действие над открытым сокетом нужного вида
+fn with_socket<T>(
+    name: &str,
+    value: &Value,
+    action: impl FnOnce(&mut ResourceHandle) -> Result<T, String>,
+) -> Result<T, String> {
+    let Value::Resource(resource) = value else {
+        return Err(format!("{}: первый аргумент должен быть сокетом", name));
+    };
+    let mut handle = resource.handle.borrow_mut();
+    match handle.as_mut() {
+        Some(handle) => action(handle),
+        None => Err(format!("{}: {} уже закрыт", name, resource.kind)),
+    }
+}
+
+fn socket_value(kind: &'static str, handle: ResourceHandle) -> Value {
+    Value::Resource(Rc::new(Resource::new(kind, handle)))
+}
+
+/// сокеты.подключиться(адрес, порт, таймаут_мс = без ограничения) -> TCP-сокет
+pub fn tcp_connect(args: &[Value]) -> Result<Value, String> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(
+            "подключиться требует 2 или 3 аргумента: адрес, порт, [таймаут_мс]".to_string(),
+        );
+    }
+    let host = string_arg("подключиться", "адрес", &args[0])?;
+    let port = port_arg("подключиться", &args[1])?;
+    let timeout = match args.get(2).map(Value::as_number) {
+        None => None,
+        Some(Some(ms)) if ms > 0.0 => Some(Duration::from_millis(ms as u64)),
+        Some(_) => return Err("подключиться: таймаут должен быть положительным числом".to_string()),
+    };
+
+    let stream = match timeout {
+        None => TcpStream::connect((host, port)),
+        Some(timeout) => {
+            let address = std::net::ToSocketAddrs::to_socket_addrs(&(host, port))
+                .map_err(|e| format!("Не удалось разрешить адрес {}: {}", host, e))?
+                .next()
+                .ok_or_else(|| format!("Адрес {} не найден", host))?;
+            TcpStream::connect_timeout(&address, timeout).and_then(|stream| {
+                stream.set_read_timeout(Some(timeout))?;
+                Ok(stream)
+            })
+        }
+    }
+    .map_err(|e| format!("Не удалось подключиться к {}:{}: {}", host, port, e))?;
+
+    Ok(socket_value(TCP_KIND, ResourceHandle::TcpStream(stream)))
+}
+
+/// сокеты.отправить(сокет, данные) -> число отправленных байт
+pub fn tcp_send(args: &[Value]) -> Result<Value, String> {
+    let [socket, data] = args else {
+        return Err("отправить требует 2 аргумента: сокет и данные".to_string());
+    };
+    let data = string_arg("отправить", "данные", data)?;
+    with_socket("отправить", socket, |handle| match handle {
+        ResourceHandle::TcpStream(stream) => {
+            stream
+                .write_all(data.as_bytes())
+                .map_err(|e| format!("Ошибка отправки: {}", e))?;
+            Ok(Value::Int(data.len() as i64))
+        }
+        _ => Err("отправить: ожидался TCP-сокет, для UDP используйте отправить_udp".to_string()),
+    })
+}
+
+/// сокеты.получить(сокет, предел = 65536) -> строка
+///
+/// Ждёт и возвращает то, что пришло, — не больше `предел` байт. Пустая
+/// строка означает, что собеседник закрыл соединение
+pub fn tcp_receive(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("получить требует 1 или 2 аргумента: сокет, [предел]".to_string());
+    }
+    let limit = limit_arg("получить", args.get(1))?;
+    with_socket("получить", &args[0], |handle| match handle {
+        ResourceHandle::TcpStream(stream) => {
+            let mut buffer = vec![0; limit];
+            let read = stream
+                .read(&mut buffer)
+                .map_err(|e| format!("Ошибка получения: {}", e))?;
+            Ok(Value::String(
+                String::from_utf8_lossy(&buffer[..read]).into_owned().into(),
+            ))
+        }
+        _ => Err("получить: ожидался TCP-сокет, для UDP используйте получить_udp".to_string()),
+    })
+}
+
+/// сокеты.закрыть(сокет) -> ничто
+///
+/// Закрывает сокет любого вида; повторное закрытие не ошибка
+pub fn socket_close(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [Value::Resource(resource)] => {
+            resource.close();
+            Ok(Value::Nil)
+        }
+        [_] => Err("закрыть: аргумент должен быть сокетом".to_string()),
+        _ => Err("закрыть требует 1 аргумент: сокет".to_string()),
+    }
+}
+
+/// сокеты.открыть_udp(адрес = "0.0.0.0", порт = 0) -> UDP-сокет
+///
+/// Порт 0 — любой свободный; узнать его можно через порт(сокет)
+pub fn udp_bind(args: &[Value]) -> Result<Value, String> {
+    if args.len() > 2 {
+        return Err("открыть_udp принимает до 2 аргументов: адрес и порт".to_string());
+    }
+    let host = match args.first() {
+        Some(value) => string_arg("открыть_udp", "адрес", value)?,
+        None => "0.0.0.0",
+    };
+    let port = match args.get(1) {
+        Some(value) => port_arg("открыть_udp", value)?,
+        None => 0,
+    };
+    let socket = UdpSocket::bind((host, port))
+        .map_err(|e| format!("Не удалось открыть UDP-сокет {}:{}: {}", host, port, e))?;
+    Ok(socket_value(UDP_KIND, ResourceHandle::UdpSocket(socket)))
+}
+
+/// сокеты.отправить_udp(сокет, адрес, порт, данные) -> число отправленных байт
+pub fn udp_send(args: &[Value]) -> Result<Value, String> {
+    let [socket, host, port, data] = args else {
+        return Err("отправить_udp требует 4 аргумента: сокет, адрес, порт, данные".to_string());
+    };
+    let host = string_arg("отправить_udp", "адрес", host)?;
+    let port = port_arg("отправить_udp", port)?;
+    let data = string_arg("отправить_udp", "данные", data)?;
+    with_socket("отправить_udp", socket, |handle| match handle {
+        ResourceHandle::UdpSocket(socket) => {
+            let sent = socket
+                .send_to(data.as_bytes(), (host, port))
+                .map_err(|e| format!("Ошибка отправки: {}", e))?;
+            Ok(Value::Int(sent as i64))
+        }
+        _ => Err("отправить_udp: ожидался UDP-сокет".to_string()),
+    })
+}
+
+/// сокеты.получить_udp(сокет, предел = 65536) -> {"данные", "адрес", "порт"}
+pub fn udp_receive(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("получить_udp требует 1 или 2 аргумента: сокет, [предел]".to_string());
+    }
+    let limit = limit_arg("получить_udp", args.get(1))?;
+    with_socket("получить_udp", &args[0], |handle| match handle {
+        ResourceHandle::UdpSocket(socket) => {
+            let mut buffer = vec![0; limit];
+            let (read, sender) = socket
+                .recv_from(&mut buffer)
+                .map_err(|e| format!("Ошибка получения: {}", e))?;
+            let fields = [
+                (
+                    "данные",
+                    Value::String(String::from_utf8_lossy(&buffer[..read]).into_owned().into()),
+                ),
+                ("адрес", Value::String(sender.ip().to_string().into())),
+                ("порт", Value::Int(sender.port() as i64)),
+            ];
+            let map: Dict = fields
+                .into_iter()
+                .map(|(k, v)| (ValueKey::String(k.to_string().into()), v))
+                .collect();
+            Ok(Value::Dict(Rc::new(RefCell::new(map))))
+        }
+        _ => Err("получить_udp: ожидался UDP-сокет".to_string()),
+    })
+}
+
+/// сокеты.порт(сокет) -> число (локальный порт)
+pub fn socket_port(args: &[Value]) -> Result<Value, String> {
+    let [socket] = args else {
+        return Err("порт требует 1 аргумент: сокет".to_string());
+    };
+    with_socket("порт", socket, |handle| {
+        let address = match handle {
+            ResourceHandle::TcpStream(stream) => stream.local_addr(),
+            ResourceHandle::UdpSocket(socket) => socket.local_addr(),
+            _ => return Err("порт: аргумент должен быть сокетом".to_string()),
+        }
+        .map_err(|e| format!("порт: {}", e))?;
+        Ok(Value::Int(address.port() as i64))
+    })
+}
@@ -20,7 +20,7 @@ pub fn str_split(args: &[Value]) -> Result<Value, String> {
 
     let parts: Vec<Value> = string
         .split(separator.as_str())
-        .map(|s| Value::String(s.to_string()))
+        .map(|s| Value::String(s.to_string().into()))
         .collect();
 
     Ok(Value::Array(Rc::new(RefCell::new(parts))))
@@ -33,7 +33,7 @@ pub fn str_trim(args: &[Value]) -> Result<Value, String> {
     }
 
     match &args[0] {
-        Value::String(s) => Ok(Value::String(s.trim().to_string())),
+        Value::String(s) => Ok(Value::String(s.trim().to_string().into())),
         _ => Err("Аргумент должен быть строкой".to_string()),
     }
 }
@@ -59,7 +59,7 @@ pub fn str_replace(args: &[Value]) -> Result<Value, String> {
         _ => return Err("Третий аргумент должен быть строкой".to_string()),
     };
 
-    Ok(Value::String(string.replace(from, to)))
+    Ok(Value::String(string.replace(from.as_str(), to).into()))
 }
 
 /// строки.верхний_регистр(строка) -> String
@@ -69,7 +69,7 @@ pub fn str_to_upper(args: &[Value]) -> Result<Value, String> {
     }
 
     match &args[0] {
-        Value::String(s) => Ok(Value::String(s.to_uppercase())),
+        Value::String(s) => Ok(Value::String(s.to_uppercase().into())),
         _ => Err("Аргумент должен быть строкой".to_string()),
     }
 }
@@ -81,7 +81,7 @@ pub fn str_to_lower(args: &[Value]) -> Result<Value, String> {
     }
 
     match &args[0] {
-        Value::String(s) => Ok(Value::String(s.to_lowercase())),
+        Value::String(s) => Ok(Value::String(s.to_lowercase().into())),
         _ => Err("Аргумент должен быть строкой".to_string()),
     }
 }
@@ -92,7 +92,287 @@ pub fn str_start_with(args: &[Value]) -> Result<Value, String> {
     }
 
     match (&args[0], &args[1]) {
-        (Value::String(s1), Value::String(s2)) => Ok(Value::Boolean(s1.starts_with(s2))),
+        (Value::String(s1), Value::String(s2)) => Ok(Value::Boolean(s1.starts_with(s2.as_str()))),
         (_, _) => Err("Аргумент должен быть строкой".to_string()),
     }
 }
+
+/// строки.построитель() -> построитель
+///
+/// То же, что встроенная `построитель_строк()`
+pub fn str_builder(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("построитель не принимает аргументов".to_string());
+    }
+    Ok(Value::StringBuilder(Rc::new(RefCell::new(String::new()))))
+}
+
+/// формат(шаблон, ...значения) -> строка
+///
+/// `{}` подставляет следующее значение, `{N}` — значение с номером N (с нуля).
+/// После двоеточия указывается спецификация `[[заполнитель]выравнивание][0][ширина][.точность]`:
+/// выравнивание `<`, `>` или `^`, `0` дополняет число нулями после знака,
+/// точность задаёт число знаков после запятой у чисел и обрезает строки.
+/// Ширина больше 1024 и точность больше 100 — ошибка.
+/// `{{` и `}}` выводят фигурные скобки
+pub fn str_format(args: &[Value]) -> Result<Value, String> {
+    match args.split_first() {
+        Some((Value::String(template), values)) => format_template(template, values)
+            .map(Value::from)
+            .map_err(|err| format!("формат: {}", err)),
+        Some(_) => Err("формат: шаблон должен быть строкой".to_string()),
+        None => Err("формат требует шаблон".to_string()),
+    }
+}
+
+/// Подставить значения в шаблон `формат`. Ошибки возвращаются без имени
+/// вызванной функции
+pub fn format_template(template: &str, values: &[Value]) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next = 0; // Номер значения для следующего `{}`
+    let mut used = 0; // Сколько значений затребовал шаблон
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("незакрытая '{' в шаблоне".to_string()),
+                    }
+                }
+                let (position, spec) = field.split_once(':').unwrap_or((&field, ""));
+                let index = if position.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    position
+                        .parse::<usize>()
+                        .map_err(|_| format!("неверный номер значения '{{{}}}'", field))?
+                };
+                let value = values.get(index).ok_or_else(|| {
+                    format!(
+                        "шаблону нужно значение номер {}, передано значений: {}",
+                        index,
+                        values.len()
+                    )
+                })?;
+                used = used.max(index + 1);
+                let spec = FormatSpec::parse(spec)
+                    .ok_or_else(|| format!("неверная спецификация '{{{}}}'", field))?;
+                spec.check_limits(&field)?;
+                spec.write(&mut out, value);
+            }
+            '}' => return Err("одиночная '}' в шаблоне; используйте '}}'".to_string()),
+            c => out.push(c),
+        }
+    }
+    if used < values.len() {
+        return Err(format!(
+            "передано значений: {}, шаблон использует {}",
+            values.len(),
+            used
+        ));
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Предел ширины подстановки: иначе `{:999999999999}` попытался бы занять
+/// терабайт памяти
+const MAX_WIDTH: usize = 1024;
+
+/// Предел точности: у f64 всё равно не больше 17 значащих цифр
+const MAX_PRECISION: usize = 100;
+
+/// Спецификация подстановки: всё после `:` в `{:...}`
+#[derive(Debug, Default)]
+struct FormatSpec {
+    fill: Option<char>,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Option<FormatSpec> {
+        let mut result = FormatSpec::default();
+        let chars: Vec<char> = spec.chars().collect();
+        let align = |c: char| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        let mut i = 0;
+        if let Some(a) = chars.get(1).copied().and_then(align) {
+            result.fill = Some(chars[0]);
+            result.align = Some(a);
+            i = 2;
+        } else if let Some(a) = chars.first().copied().and_then(align) {
+            result.align = Some(a);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            result.zero = true;
+            i += 1;
+        }
+        let digits = |i: &mut usize| {
+            let start = *i;
+            while chars.get(*i).is_some_and(char::is_ascii_digit) {
+                *i += 1;
+            }
+            chars[start..*i].iter().collect::<String>()
+        };
+        let width = digits(&mut i);
+        if !width.is_empty() {
+            result.width = width.parse().ok()?;
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            result.precision = Some(digits(&mut i).parse().ok()?);
+        }
+        (i == chars.len()).then_some(result)
+    }
+
+    fn check_limits(&self, field: &str) -> Result<(), String> {
+        if self.width > MAX_WIDTH {
+            return Err(format!(
+                "ширина в '{{{}}}' больше допустимой ({})",
+                field, MAX_WIDTH
+            ));
+        }
+        if self
+            .precision
+            .is_some_and(|precision| precision > MAX_PRECISION)
+        {
+            return Err(format!(
+                "точность в '{{{}}}' больше допустимой ({})",
+                field, MAX_PRECISION
+            ));
+        }
+        Ok(())
+    }
+
+    fn write(&self, out: &mut String, value: &Value) {
+        let number = value.as_number();
+        let text = match (number, self.precision) {
+            (Some(n), Some(precision)) => format!("{:.*}", precision, n),
+            (None, Some(precision)) => value.to_string().chars().take(precision).collect(),
+            (_, None) => value.to_string(),
+        };
+        let len = text.chars().count();
+        if len >= self.width {
+            out.push_str(&text);
+            return;
+        }
+        let padding = self.width - len;
+
+        // Нули ставятся между знаком и цифрами
+        if self.zero && number.is_some() && self.align.is_none() {
+            let digits = match text.strip_prefix('-') {
+                Some(digits) => {
+                    out.push('-');
+                    digits
+                }
+                None => &text,
+            };
+            out.extend(std::iter::repeat_n('0', padding));
+            out.push_str(digits);
+            return;
+        }
+
+        let fill = self.fill.unwrap_or(' ');
+        let align = self.align.unwrap_or(if number.is_some() {
+            Align::Right
+        } else {
+            Align::Left
+        });
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        out.extend(std::iter::repeat_n(fill, before));
+        out.push_str(&text);
+        out.extend(std::iter::repeat_n(fill, after));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(template: &str, values: &[Value]) -> Result<String, String> {
+        format_template(template, values)
+    }
+
+    #[test]
+    fn substitutes_values_in_order_and_by_index() {
+        let values = [Value::Int(1), Value::from("два")];
+        assert_eq!(format("x = {}, y = {}", &values).unwrap(), "x = 1, y = два");
+        assert_eq!(format("{1} {0} {1}", &values).unwrap(), "два 1 два");
+        assert_eq!(format("{{}} {}", &[Value::Nil]).unwrap(), "{} ничто");
+    }
+
+    #[test]
+    fn applies_width_precision_and_padding() {
+        let number = [Value::Number(1.23456)];
+        assert_eq!(format("{:.2}", &number).unwrap(), "1.23");
+        assert_eq!(format("[{:8.3}]", &number).unwrap(), "[   1.235]");
+        assert_eq!(format("[{:<6}]", &[Value::Int(42)]).unwrap(), "[42    ]");
+        assert_eq!(format("[{:*^7}]", &[Value::Int(42)]).unwrap(), "[**42***]");
+        assert_eq!(format("{:05}", &[Value::Int(-42)]).unwrap(), "-0042");
+        assert_eq!(format("{:.1}", &[Value::Int(2)]).unwrap(), "2.0");
+
+        let word = [Value::from("привет")];
+        assert_eq!(format("[{:8}]", &word).unwrap(), "[привет  ]");
+        assert_eq!(format("[{:>8}]", &word).unwrap(), "[  привет]");
+        assert_eq!(format("{:.3}", &word).unwrap(), "при");
+    }
+
+    #[test]
+    fn reports_template_errors() {
+        assert!(format("{}", &[]).unwrap_err().contains("номер 0"));
+        assert!(format("{", &[]).unwrap_err().contains("незакрытая"));
+        assert!(format("}", &[]).unwrap_err().contains("одиночная"));
+        assert!(
+            format("{:x}", &[Value::Int(1)])
+                .unwrap_err()
+                .contains("спецификация")
+        );
+        assert!(
+            format("{:999999999999}", &[Value::Int(1)])
+                .unwrap_err()
+                .contains("ширина в '{:999999999999}' больше допустимой (1024)")
+        );
+        assert!(
+            format("{:.500}", &[Value::Int(1)])
+                .unwrap_err()
+                .contains("точность в '{:.500}' больше допустимой (100)")
+        );
+        assert_eq!(format("{:1024}", &[Value::Int(1)]).unwrap().len(), 1024);
+        assert!(
+            format("{}", &[Value::Int(1), Value::Int(2)])
+                .unwrap_err()
+                .contains("использует 1")
+        );
+    }
+}
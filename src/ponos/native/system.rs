@@ -1,13 +1,190 @@
-use crate::ponos::value::Value;
+use crate::ponos::value::{self, Dict, Value, ValueKey};
+use crate::ponos::vm::NativeContext;
 use std::cell::RefCell;
 use std::env;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 thread_local! {
-    static CLI_ARGS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static CLI_ARGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
 }
 
+/// Параметры запуска процесса: { "каталог", "окружение", "таймаут", "ввод" }
+#[derive(Default)]
+pub(crate) struct ExecuteOptions {
+    directory: Option<String>,
+    env: Vec<(String, String)>,
+    pub(crate) timeout: Option<Duration>,
+    stdin: Option<String>,
+}
+
+impl ExecuteOptions {
+    pub(crate) fn from_dict(dict: &Dict) -> Result<ExecuteOptions, String> {
+        let mut options = ExecuteOptions::default();
+        for (key, value) in dict {
+            let key = match key {
+                ValueKey::String(key) => key.as_str(),
+                other => return Err(format!("Неизвестный параметр запуска: {:?}", other)),
+            };
+            match (key, value) {
+                ("каталог", Value::String(dir)) => options.directory = Some(dir.to_string()),
+                ("окружение", Value::Dict(vars)) => {
+                    for (name, value) in vars.borrow().iter() {
+                        let name = match name {
+                            ValueKey::String(name) => name.clone(),
+                            _ => {
+                                return Err(
+                                    "Имена переменных среды должны быть строками".to_string()
+                                );
+                            }
+                        };
+                        options
+                            .env
+                            .push((name.to_string(), scalar_to_string(value)?));
+                    }
+                }
+                ("таймаут", Value::Int(ms)) if *ms >= 0 => {
+                    options.timeout = Some(Duration::from_millis(*ms as u64));
+                }
+                ("таймаут", Value::Number(ms)) if *ms >= 0.0 && ms.is_finite() => {
+                    options.timeout = Some(Duration::from_secs_f64(ms / 1000.0));
+                }
+                ("ввод", Value::String(text)) => options.stdin = Some(text.to_string()),
+                ("каталог" | "окружение" | "таймаут" | "ввод", _) => {
+                    return Err(format!("Неверное значение параметра '{}'", key));
+                }
+                _ => return Err(format!("Неизвестный параметр запуска: '{}'", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+pub(crate) fn scalar_to_string(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.to_string()),
+        Value::Int(n) => Ok(n.to_string()),
+        Value::Number(n) => Ok(value::number_to_string(*n)),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => Err("Значение должно быть строкой, числом или булевым".to_string()),
+    }
+}
+
+/// Прочитать поток процесса целиком в отдельном потоке, чтобы процесс
+/// не блокировался на заполненном канале
+pub(crate) fn read_in_background(
+    stream: Option<impl Read + Send + 'static>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Запустить процесс с перехваченными stdout и stderr. Ввод из параметров
+/// пишется в отдельном потоке: процесс может читать его и одновременно писать вывод
+pub(crate) fn spawn_process(
+    command: &str,
+    args: &[String],
+    options: &mut ExecuteOptions,
+) -> Result<Child, String> {
+    let mut cmd = Command::new(command);
+    cmd.args(args)
+        .envs(options.env.iter().map(|(k, v)| (k, v)))
+        .stdin(if options.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = &options.directory {
+        cmd.current_dir(dir);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Ошибка выполнения: {}", e))?;
+
+    if let (Some(text), Some(mut stdin)) = (options.stdin.take(), child.stdin.take()) {
+        thread::spawn(move || {
+            let _ = stdin.write_all(text.as_bytes());
+        });
+    }
+    Ok(child)
+}
+
+/// Дождаться завершения процесса; по таймауту он принудительно завершается.
+/// `on_tick` вызывается на каждом шаге ожидания; если он вернул ошибку,
+/// процесс завершается, а ошибка возвращается. Результат: (статус, превышено_время)
+pub(crate) fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    mut on_tick: impl FnMut() -> Result<(), String>,
+) -> Result<(ExitStatus, bool), String> {
+    let started = Instant::now();
+    loop {
+        if let Err(e) = on_tick() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| format!("Ошибка ожидания процесса: {}", e))?
+        {
+            return Ok((status, false));
+        }
+        if timeout.is_some_and(|limit| started.elapsed() >= limit) {
+            let _ = child.kill();
+            let status = child
+                .wait()
+                .map_err(|e| format!("Ошибка ожидания процесса: {}", e))?;
+            return Ok((status, true));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Словарь результата: { "вывод", "ошибки", "код", "превышено_время" }.
+/// Код равен ничто, если процесс завершён сигналом или по таймауту
+pub(crate) fn process_result(
+    stdout: &[u8],
+    stderr: &[u8],
+    status: ExitStatus,
+    timed_out: bool,
+) -> Value {
+    let mut result = Dict::new();
+    result.insert(
+        ValueKey::from("вывод"),
+        Value::String(String::from_utf8_lossy(stdout).into_owned().into()),
+    );
+    result.insert(
+        ValueKey::from("ошибки"),
+        Value::String(String::from_utf8_lossy(stderr).into_owned().into()),
+    );
+    result.insert(
+        ValueKey::from("код"),
+        match status.code() {
+            Some(code) if !timed_out => Value::Number(code as f64),
+            _ => Value::Nil,
+        },
+    );
+    result.insert(ValueKey::from("превышено_время"), Value::Boolean(timed_out));
+    Value::Dict(Rc::new(RefCell::new(result)))
+}
+
+/// система.выполнить(команда, аргументы..., параметры?) -> словарь
+///
+/// Последний аргумент-словарь задаёт параметры запуска. Результат:
+/// { "вывод": строка, "ошибки": строка, "код": число | ничто, "превышено_время": булево }.
+/// Код равен ничто, если процесс завершён сигналом или по таймауту
 pub fn sys_execute(args: &[Value]) -> Result<Value, String> {
     if args.is_empty() {
         return Err("выполнить ожидает команду".to_string());
@@ -18,23 +195,24 @@ pub fn sys_execute(args: &[Value]) -> Result<Value, String> {
         _ => return Err("Команда должна быть строкой".to_string()),
     };
 
-    let cmd_args: Vec<String> = args[1..]
+    let (cmd_args, mut options) = match &args[1..] {
+        [rest @ .., Value::Dict(dict)] => (rest, ExecuteOptions::from_dict(&dict.borrow())?),
+        rest => (rest, ExecuteOptions::default()),
+    };
+    let cmd_args = cmd_args
         .iter()
-        .map(|v| match v {
-            Value::String(s) => s.clone(),
-            Value::Number(n) => n.to_string(),
-            Value::Boolean(b) => b.to_string(),
-            _ => String::new(),
-        })
-        .collect();
+        .map(scalar_to_string)
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let output = Command::new(command)
-        .args(&cmd_args)
-        .output()
-        .map_err(|e| format!("Ошибка выполнения: {}", e))?;
+    let mut child = spawn_process(command, &cmd_args, &mut options)?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    Ok(Value::String(stdout))
+    let (status, timed_out) = wait_with_timeout(&mut child, options.timeout, || Ok(()))?;
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(process_result(&stdout, &stderr, status, timed_out))
 }
 
 pub fn env_get(args: &[Value]) -> Result<Value, String> {
@@ -43,12 +221,12 @@ pub fn env_get(args: &[Value]) -> Result<Value, String> {
     }
 
     let var_name = match &args[0] {
-        Value::String(s) => s,
+        Value::String(s) => s.as_str(),
         _ => return Err("Имя переменной должно быть строкой".to_string()),
     };
 
     match env::var(var_name) {
-        Ok(value) => Ok(Value::String(value)),
+        Ok(value) => Ok(Value::String(value.into())),
         Err(_) => Ok(Value::Nil), // Переменная не найдена
     }
 }
@@ -65,14 +243,15 @@ pub fn env_set(args: &[Value]) -> Result<Value, String> {
     };
 
     let var_value = match &args[1] {
-        Value::String(s) => s.clone(),
+        Value::String(s) => s.to_string(),
+        Value::Int(n) => n.to_string(),
         Value::Number(n) => n.to_string(),
         Value::Boolean(b) => b.to_string(),
         _ => return Err("Значение должно быть строкой, числом или булевым".to_string()),
     };
 
     unsafe {
-        env::set_var(var_name, var_value);
+        env::set_var(var_name.as_str(), var_value);
     }
     Ok(Value::Nil)
 }
@@ -84,12 +263,25 @@ pub fn set_cli_args(args: Vec<String>) {
     });
 }
 
-/// система.аргументы() -> Array
+/// система.завершить(код = 0): завершить программу с кодом возврата процесса
+pub fn sys_exit(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let code = match args {
+        [] => 0,
+        [Value::Int(n)] if (0..=255).contains(n) => *n as i32,
+        [Value::Number(n)] if n.fract() == 0.0 && (0.0..=255.0).contains(n) => *n as i32,
+        [_] => return Err("завершить: код должен быть целым числом от 0 до 255".to_string()),
+        _ => return Err("завершить ожидает не больше 1 аргумента".to_string()),
+    };
+    ctx.exit(code);
+    Ok(Value::Nil)
+}
+
+/// система.аргументы() -> Array: [интерпретатор, скрипт, аргументы скрипта...]
 pub fn get_args(_args: &[Value]) -> Result<Value, String> {
     let args = CLI_ARGS.with(|a| {
         a.borrow()
             .iter()
-            .map(|s| Value::String(s.clone()))
+            .map(|s| Value::String(s.clone().into()))
             .collect::<Vec<Value>>()
     });
 
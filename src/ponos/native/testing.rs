@@ -0,0 +1,22 @@
+use crate::ponos::value::Value;
+
+// стд/тест: проверки для скриптов и тестов. Невыполненная проверка бросает
+// исключение; если его никто не перехватит, программа завершится с кодом 1
+
+/// тест.утверждать(условие, сообщение = "") -> ничто
+pub fn assert(args: &[Value]) -> Result<Value, String> {
+    let (condition, message) = match args {
+        [condition] => (condition, None),
+        [condition, Value::String(message)] => (condition, Some(message)),
+        [_, _] => return Err("утверждать: сообщение должно быть строкой".to_string()),
+        _ => return Err("утверждать требует 1 или 2 аргумента: условие, [сообщение]".to_string()),
+    };
+    match (condition, message) {
+        (Value::Boolean(true), _) => Ok(Value::Nil),
+        (Value::Boolean(false), None) => Err("Утверждение не выполнено".to_string()),
+        (Value::Boolean(false), Some(message)) => {
+            Err(format!("Утверждение не выполнено: {}", message))
+        }
+        _ => Err("утверждать: условие должно быть логическим значением".to_string()),
+    }
+}
@@ -0,0 +1,224 @@
+use crate::ponos::portable::Snapshot;
+use crate::ponos::typechecker::Type;
+use crate::ponos::value::{Resource, ResourceHandle, Value};
+use crate::ponos::vm::{Limits, NativeContext, VM};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Потоки стд/потоки. Каждый поток ОС выполняет функцию в собственной VM:
+// глобальные переменные, стек и сопрограммы у потоков свои. Общаются потоки
+// только через каналы, а значения при передаче (аргументы потока, его
+// результат, сообщения канала) копируются целиком
+
+const THREAD_KIND: &str = "поток";
+const CHANNEL_KIND: &str = "канал";
+// Стек как у главного потока: на нём держатся вложенные циклы выполнения VM
+const THREAD_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Итог потока, который забирает присоединить()
+#[derive(Debug)]
+pub enum ThreadOutcome {
+    Returned(Snapshot),
+    Threw(Snapshot),
+    /// Поток не смог начать или завершить работу: текст ошибки
+    Failed(String),
+}
+
+/// Канал сообщений между потоками. Получатели ждут, пока в канале не
+/// появится сообщение или он не будет закрыт
+#[derive(Debug, Default)]
+pub struct Channel {
+    queue: Mutex<Queue>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    messages: VecDeque<Snapshot>,
+    closed: bool,
+}
+
+impl Channel {
+    fn send(&self, message: Snapshot) -> Result<(), String> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.closed {
+            return Err("отправить: канал закрыт".to_string());
+        }
+        queue.messages.push_back(message);
+        self.available.notify_one();
+        Ok(())
+    }
+
+    // None — истёк таймаут или канал закрыт и пуст
+    fn receive(&self, timeout: Option<Duration>) -> Option<Snapshot> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(message) = queue.messages.pop_front() {
+                return Some(message);
+            }
+            if queue.closed {
+                return None;
+            }
+            queue = match deadline {
+                None => self.available.wait(queue).unwrap(),
+                Some(deadline) => {
+                    let left = deadline.checked_duration_since(Instant::now())?;
+                    self.available.wait_timeout(queue, left).unwrap().0
+                }
+            };
+        }
+    }
+
+    fn close(&self) {
+        self.queue.lock().unwrap().closed = true;
+        self.available.notify_all();
+    }
+}
+
+fn channel_arg(name: &str, value: &Value) -> Result<Arc<Channel>, String> {
+    if let Value::Resource(resource) = value
+        && let Some(ResourceHandle::Channel(channel)) = &*resource.handle.borrow()
+    {
+        return Ok(channel.clone());
+    }
+    Err(format!("{}: первый аргумент должен быть каналом", name))
+}
+
+/// потоки.поток(функ, ...аргументы) -> поток
+///
+/// Функция и аргументы копируются в новую VM вместе с глобальными
+/// переменными, к которым функция обращается
+pub fn thread_spawn(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let Some(callee) = args.first() else {
+        return Err("поток() требует функцию".to_string());
+    };
+    if !matches!(Type::of_value(callee), Type::Function(_)) {
+        return Err(format!(
+            "поток: первым аргументом должна быть функция, получено: {}",
+            Type::of_value(callee)
+        ));
+    }
+    let snapshot = ctx.export(args).map_err(|e| format!("поток: {}", e))?;
+    let limits = ctx.limits();
+    // Вывод, напечатанный до запуска потока, идёт раньше вывода потока
+    ctx.flush_output();
+    let handle = thread::Builder::new()
+        .stack_size(THREAD_STACK_SIZE)
+        .spawn(move || run_thread(snapshot, limits))
+        .map_err(|e| format!("поток: не удалось запустить поток: {}", e))?;
+    Ok(Value::Resource(Rc::new(Resource::new(
+        THREAD_KIND,
+        ResourceHandle::Thread(handle),
+    ))))
+}
+
+fn run_thread(snapshot: Snapshot, limits: Limits) -> ThreadOutcome {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut vm = VM::new();
+        vm.set_limits(limits);
+        let mut values = snapshot.import(&mut vm)?;
+        let callee = values.remove(0);
+        let outcome = match vm.call_in_task(callee, &values) {
+            Ok(value) => ThreadOutcome::Returned(Snapshot::export(&mut vm, &[value])?),
+            Err(exception) => ThreadOutcome::Threw(Snapshot::export(&mut vm, &[exception])?),
+        };
+        Ok::<_, String>(outcome)
+    }));
+    match outcome {
+        Ok(Ok(outcome)) => outcome,
+        Ok(Err(message)) => ThreadOutcome::Failed(message),
+        Err(payload) => ThreadOutcome::Failed(crate::ponos::panic_message(payload)),
+    }
+}
+
+/// потоки.присоединить(поток) -> результат функции потока
+///
+/// Ждёт завершения потока. Исключение, не перехваченное в потоке, бросается
+/// здесь. Присоединить поток можно только один раз
+pub fn thread_join(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let resource = match args {
+        [Value::Resource(resource)] if resource.kind == THREAD_KIND => resource,
+        [_] => return Err("присоединить: аргумент должен быть потоком".to_string()),
+        _ => return Err("присоединить требует 1 аргумент: поток".to_string()),
+    };
+    let Some(ResourceHandle::Thread(handle)) = resource.handle.borrow_mut().take() else {
+        return Err("присоединить: поток уже присоединён".to_string());
+    };
+    let outcome = handle
+        .join()
+        .unwrap_or_else(|payload| ThreadOutcome::Failed(crate::ponos::panic_message(payload)));
+    match outcome {
+        ThreadOutcome::Returned(snapshot) => Ok(ctx.import(snapshot)?.remove(0)),
+        ThreadOutcome::Threw(snapshot) => {
+            let exception = ctx.import(snapshot)?.remove(0);
+            ctx.throw(exception);
+            Ok(Value::Nil)
+        }
+        ThreadOutcome::Failed(message) => Err(format!("присоединить: {}", message)),
+    }
+}
+
+/// потоки.канал() -> канал
+pub fn channel_new(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("канал() не принимает аргументов".to_string());
+    }
+    Ok(Value::Resource(Rc::new(Resource::new(
+        CHANNEL_KIND,
+        ResourceHandle::Channel(Arc::default()),
+    ))))
+}
+
+/// потоки.отправить(канал, значение)
+///
+/// Получатель получит копию значения; в закрытый канал отправлять нельзя
+pub fn channel_send(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let [channel, value] = args else {
+        return Err("отправить требует 2 аргумента: канал, значение".to_string());
+    };
+    let channel = channel_arg("отправить", channel)?;
+    let message = ctx
+        .export(std::slice::from_ref(value))
+        .map_err(|e| format!("отправить: {}", e))?;
+    channel.send(message)?;
+    Ok(Value::Nil)
+}
+
+/// потоки.получить(канал, таймаут_мс = без ограничения) -> значение или ничто
+///
+/// Ждёт сообщения; `ничто` — если истёк таймаут или канал закрыт и пуст
+pub fn channel_receive(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("получить требует 1 или 2 аргумента: канал, [таймаут_мс]".to_string());
+    }
+    let channel = channel_arg("получить", &args[0])?;
+    let timeout = match args.get(1).map(Value::as_number) {
+        None => None,
+        Some(Some(ms)) if ms >= 0.0 && ms.is_finite() => Some(Duration::from_secs_f64(ms / 1000.0)),
+        Some(_) => {
+            return Err("получить: таймаут должен быть неотрицательным числом".to_string());
+        }
+    };
+    match channel.receive(timeout) {
+        Some(message) => Ok(ctx.import(message)?.remove(0)),
+        None => Ok(Value::Nil),
+    }
+}
+
+/// потоки.закрыть(канал)
+///
+/// Получатели забирают оставшиеся сообщения, после чего получают `ничто`
+pub fn channel_close(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [channel] => {
+            channel_arg("закрыть", channel)?.close();
+            Ok(Value::Nil)
+        }
+        _ => Err("закрыть требует 1 аргумент: канал".to_string()),
+    }
+}
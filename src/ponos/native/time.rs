@@ -0,0 +1,268 @@
+use crate::ponos::value::Value;
+use crate::ponos::vm::NativeContext;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Время в модуле — число миллисекунд от начала эпохи Unix (UTC).
+// Часовых поясов модуль не знает: форматирование и разбор ведутся в UTC
+
+/// Формат по умолчанию для форматировать() и разобрать()
+const DEFAULT_FORMAT: &str = "ДД.ММ.ГГГГ";
+
+const MS_PER_SECOND: i64 = 1000;
+const MS_PER_DAY: i64 = 86_400 * MS_PER_SECOND;
+
+/// Поля формата. Регистр различает месяц (ММ) и минуты (мм)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("ГГГГ", Field::Year),
+    ("ММ", Field::Month),
+    ("ДД", Field::Day),
+    ("чч", Field::Hour),
+    ("мм", Field::Minute),
+    ("сс", Field::Second),
+];
+
+/// Элемент разобранного формата: поле или литеральный символ
+enum Token {
+    Field(Field),
+    Literal(char),
+}
+
+fn parse_format(format: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = format;
+    'outer: while let Some(c) = rest.chars().next() {
+        for (pattern, field) in FIELDS {
+            if let Some(tail) = rest.strip_prefix(pattern) {
+                tokens.push(Token::Field(*field));
+                rest = tail;
+                continue 'outer;
+            }
+        }
+        tokens.push(Token::Literal(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    tokens
+}
+
+/// Количество дней от 1970-01-01 до заданной даты (григорианский календарь)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Обратное преобразование: (год, месяц, день) по номеру дня от 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn format_timestamp(ms: i64, format: &str) -> String {
+    let days = ms.div_euclid(MS_PER_DAY);
+    let seconds_of_day = ms.rem_euclid(MS_PER_DAY) / MS_PER_SECOND;
+    let (year, month, day) = civil_from_days(days);
+
+    let mut result = String::new();
+    for token in parse_format(format) {
+        match token {
+            Token::Literal(c) => result.push(c),
+            Token::Field(Field::Year) => result.push_str(&format!("{:04}", year)),
+            Token::Field(Field::Month) => result.push_str(&format!("{:02}", month)),
+            Token::Field(Field::Day) => result.push_str(&format!("{:02}", day)),
+            Token::Field(Field::Hour) => result.push_str(&format!("{:02}", seconds_of_day / 3600)),
+            Token::Field(Field::Minute) => {
+                result.push_str(&format!("{:02}", seconds_of_day / 60 % 60))
+            }
+            Token::Field(Field::Second) => result.push_str(&format!("{:02}", seconds_of_day % 60)),
+        }
+    }
+    result
+}
+
+fn parse_timestamp(text: &str, format: &str) -> Result<i64, String> {
+    let error = || format!("Строка '{}' не соответствует формату '{}'", text, format);
+
+    let (mut year, mut month, mut day) = (1970, 1, 1);
+    let (mut hour, mut minute, mut second) = (0, 0, 0);
+    let mut rest = text;
+
+    for token in parse_format(format) {
+        match token {
+            Token::Literal(c) => rest = rest.strip_prefix(c).ok_or_else(error)?,
+            Token::Field(field) => {
+                let width = if field == Field::Year { 4 } else { 2 };
+                let digits: String = rest.chars().take(width).collect();
+                if digits.len() != width || !digits.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(error());
+                }
+                let number: i64 = digits.parse().map_err(|_| error())?;
+                rest = &rest[width..];
+                match field {
+                    Field::Year => year = number,
+                    Field::Month => month = number,
+                    Field::Day => day = number,
+                    Field::Hour => hour = number,
+                    Field::Minute => minute = number,
+                    Field::Second => second = number,
+                }
+            }
+        }
+    }
+
+    if !rest.is_empty() {
+        return Err(error());
+    }
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(format!("Несуществующая дата: '{}'", text));
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("Несуществующее время: '{}'", text));
+    }
+
+    let seconds = hour * 3600 + minute * 60 + second;
+    Ok(days_from_civil(year, month, day) * MS_PER_DAY + seconds * MS_PER_SECOND)
+}
+
+fn format_arg<'a>(name: &str, args: &'a [Value], index: usize) -> Result<&'a str, String> {
+    match args.get(index) {
+        None => Ok(DEFAULT_FORMAT),
+        Some(Value::String(format)) => Ok(format),
+        Some(_) => Err(format!("{}: формат должен быть строкой", name)),
+    }
+}
+
+/// время.сейчас() -> число (миллисекунды от начала эпохи Unix)
+pub fn time_now(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("сейчас не принимает аргументов".to_string());
+    }
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Системное время раньше начала эпохи: {}", e))?;
+    Ok(Value::Int(elapsed.as_millis() as i64))
+}
+
+/// время.форматировать(время, формат = "ДД.ММ.ГГГГ") -> строка
+///
+/// Поля формата: ГГГГ, ММ, ДД, чч, мм, сс; остальные символы выводятся как есть
+pub fn time_format(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("форматировать требует 1-2 аргумента: время и формат".to_string());
+    }
+    let ms = match &args[0] {
+        Value::Int(n) => *n,
+        Value::Number(n) if n.is_finite() => *n as i64,
+        _ => return Err("форматировать: время должно быть числом".to_string()),
+    };
+    let format = format_arg("форматировать", args, 1)?;
+    Ok(Value::String(format_timestamp(ms, format).into()))
+}
+
+/// время.разобрать(строка, формат = "ДД.ММ.ГГГГ") -> число
+pub fn time_parse(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("разобрать требует 1-2 аргумента: строку и формат".to_string());
+    }
+    let text = match &args[0] {
+        Value::String(s) => s,
+        _ => return Err("разобрать: первый аргумент должен быть строкой".to_string()),
+    };
+    let format = format_arg("разобрать", args, 1)?;
+    Ok(Value::Int(parse_timestamp(text, format)?))
+}
+
+/// время.спать(мс) -> ничто
+///
+/// В сопрограмме спит только она сама: остальные задачи продолжают работать
+pub fn time_sleep(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    match args {
+        [Value::Int(ms)] if *ms >= 0 => {
+            ctx.sleep(Duration::from_millis(*ms as u64));
+            Ok(Value::Nil)
+        }
+        [Value::Number(ms)] if *ms >= 0.0 && ms.is_finite() => {
+            ctx.sleep(Duration::from_secs_f64(ms / 1000.0));
+            Ok(Value::Nil)
+        }
+        [_] => Err("спать: длительность должна быть неотрицательным числом".to_string()),
+        _ => Err("спать требует 1 аргумент: длительность в миллисекундах".to_string()),
+    }
+}
+
+/// время.таймер() -> число
+///
+/// Монотонные миллисекунды (с долями) от первого вызова в процессе. Подходит
+/// для замеров: разность двух значений не зависит от перевода системных часов
+pub fn time_timer(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("таймер не принимает аргументов".to_string());
+    }
+    static START: OnceLock<Instant> = OnceLock::new();
+    let start = START.get_or_init(Instant::now);
+    Ok(Value::Number(start.elapsed().as_secs_f64() * 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_conversion_round_trips() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        for days in [-800_000, -1, 0, 59, 11_016, 19_782, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn format_and_parse_are_inverse() {
+        let format = "ГГГГ-ММ-ДД чч:мм:сс";
+        let ms = parse_timestamp("2024-02-29 13:05:09", format).unwrap();
+        assert_eq!(ms, 1_709_211_909_000);
+        assert_eq!(format_timestamp(ms, format), "2024-02-29 13:05:09");
+        assert_eq!(format_timestamp(ms, DEFAULT_FORMAT), "29.02.2024");
+        assert!(parse_timestamp("29.02.2023", DEFAULT_FORMAT).is_err());
+        assert!(parse_timestamp("1.1.2024", DEFAULT_FORMAT).is_err());
+    }
+}
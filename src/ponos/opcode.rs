@@ -1,6 +1,14 @@
+use crate::ponos::value::Value;
+
+/// Операнды инструкций (адреса, индексы констант и слотов, число аргументов)
+/// хранятся в 32 битах: инструкция занимает 12 байт вместо 24
+pub fn operand(value: usize) -> u32 {
+    u32::try_from(value).expect("Операнд инструкции не помещается в 32 бита")
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum OpCode {
-    Constant(usize),
+    Constant(u32),
     Pop, // Удалить значение с вершины стека
     Dup, // Дублировать значение на вершине стека
 
@@ -23,45 +31,145 @@ pub enum OpCode {
     Less,
 
     // локальные переменные
-    DefineLocal(usize),
-    GetLocal(usize),
-    SetLocal(usize),
+    DefineLocal(u32),
+    GetLocal(u32),
+    SetLocal(u32),
+    AppendLocal(u32), // x = x + значение: строка дописывается в слот без копирования
 
     // замыкания
-    Closure(usize, usize),
-    GetUpvalue(usize),
-    SetUpvalue(usize),
-    CloseUpvalues(usize),
+    Closure(u32, u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    CloseUpvalues(u32),
 
     // поток выполнения
-    Jump(usize),                 // Безусловный переход на абсолютный адрес
-    JumpIfTrue(usize),           // Переход если вершина стека true
-    JumpIfFalse(usize),          // Переход если вершина стека false
-    PushExceptionHandler(usize), // Зарегистрировать обработчик исключений с адресом catch-блока
-    PopExceptionHandler,         // Снять обработчик после успешного try
-    Throw,                       // Выброс исключения (значение на стеке)
-    Call(usize),
+    Jump(u32),                 // Безусловный переход на абсолютный адрес
+    JumpIfTrue(u32),           // Переход если вершина стека true
+    JumpIfFalse(u32),          // Переход если вершина стека false
+    PushExceptionHandler(u32), // Зарегистрировать обработчик исключений с адресом catch-блока
+    PopExceptionHandler,       // Снять обработчик после успешного try
+    Throw,                     // Выброс исключения (значение на стеке)
+    Call(u32),
+    CallSpread(u32), // Вызов с аргументами из N массивов на стеке (ф(...массив))
     Return_,
 
     // ООП
-    Class,
+    Class(u32),        // Создать класс (имя в константах)
     Inherit, // Установить родительский класс (pop superclass, pop subclass, push subclass)
-    DefineMethod(usize), // Добавить метод в класс (имя в константах)
-    GetProperty, // Получить свойство экземпляра
-    SetProperty, // Установить свойство экземпляра
-    GetSuper, // Получить метод родительского класса
+    DefineMethod(u32), // Добавить метод в класс (имя в константах)
+    DefineStatic(u32), // Добавить статический член в класс (pop значение, имя в константах)
+    GetProperty(u32), // Получить свойство экземпляра (имя в константах)
+    SetProperty(u32), // Установить свойство экземпляра (имя в константах)
+    GetSuper(u32), // Получить метод родительского класса (имя в константах)
+    InstanceOf, // Экземпляр ли значение класса или его наследника (pop класс, pop значение, push bool)
 
     // Индексирование и коллекции
-    GetIndex,     // Получить элемент по индексу (2 значения на стеке: объект, индекс)
-    SetIndex,     // Установить элемент по индексу (3 значения на стеке: объект, индекс, значение)
-    Array(usize), // Создать массив из N элементов на стеке
-    Dict(usize),  // Создать словарь из N пар (ключ, значение) на стеке
+    GetIndex,   // Получить элемент по индексу (2 значения на стеке: объект, индекс)
+    SetIndex,   // Установить элемент по индексу (3 значения на стеке: объект, индекс, значение)
+    Array(u32), // Создать массив из N элементов на стеке
+    Dict(u32),  // Создать словарь из N пар (ключ, значение) на стеке
+    Iterable,   // Коллекция для 'для каждого': словарь заменяется массивом его ключей
 
     // переменные
-    DefineGlobal(usize),
-    SetGlobal(usize),
-    GetGlobal(usize),
+    DefineGlobal(u32),
+    SetGlobal(u32),
+    GetGlobal(u32),
+    AppendGlobal(u32), // То же, что AppendLocal, для глобальной переменной
 
     Halt, // Данный опкод не никак не обрабатывается и нужен только чтобы jump'у в конце выражения
           // было куда переходить
 }
+
+impl OpCode {
+    /// Индекс в пуле констант, который инструкция несёт как операнд
+    pub fn constant_operand(&self) -> Option<usize> {
+        match *self {
+            OpCode::Constant(idx)
+            | OpCode::Closure(idx, _)
+            | OpCode::Class(idx)
+            | OpCode::DefineMethod(idx)
+            | OpCode::DefineStatic(idx)
+            | OpCode::GetProperty(idx)
+            | OpCode::SetProperty(idx)
+            | OpCode::GetSuper(idx) => Some(idx as usize),
+            _ => None,
+        }
+    }
+}
+
+/// Таблица строк исходного кода для байткода. Хранит только границы
+/// участков: запись (инструкция, строка) действует до следующей записи
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineTable {
+    entries: Vec<(usize, usize)>,
+}
+
+impl LineTable {
+    /// Инструкции, начиная с `offset`, относятся к строке `line`
+    pub fn mark(&mut self, offset: usize, line: usize) {
+        match self.entries.last_mut() {
+            Some(&mut (_, last_line)) if last_line == line => {}
+            // Участок без инструкций заменяется новым
+            Some(last) if last.0 == offset => last.1 = line,
+            _ => self.entries.push((offset, line)),
+        }
+    }
+
+    /// Строка исходного кода инструкции; None — у байткода нет сведений о строках
+    pub fn line_at(&self, offset: usize) -> Option<usize> {
+        let idx = self.entries.partition_point(|&(start, _)| start <= offset);
+        idx.checked_sub(1).map(|idx| self.entries[idx].1)
+    }
+
+    /// Участки таблицы: (первая инструкция, строка)
+    pub fn entries(&self) -> &[(usize, usize)] {
+        &self.entries
+    }
+}
+
+/// Листинг байткода: пронумерованные инструкции с номерами строк исходного
+/// кода (`|` — та же строка, что у предыдущей инструкции) и таблица констант.
+/// Рядом с инструкцией, операнд которой — константа, выводится её значение
+pub fn listing(opcodes: &[OpCode], constants: &[Value], lines: &LineTable) -> String {
+    let mut text = String::from("Байткод:\n");
+    let mut previous_line = None;
+    for (index, opcode) in opcodes.iter().enumerate() {
+        let line = lines.line_at(index);
+        let line_column = match line {
+            Some(line) if previous_line != Some(line) => line.to_string(),
+            Some(_) => "|".to_string(),
+            None => String::new(),
+        };
+        previous_line = line;
+        let instruction = format!("{:?}", opcode);
+        match opcode.constant_operand().and_then(|idx| constants.get(idx)) {
+            Some(Value::String(s)) => text.push_str(&format!(
+                "{:>6}  {:>4}  {:<24}; {:?}\n",
+                index, line_column, instruction, s
+            )),
+            Some(constant) => text.push_str(&format!(
+                "{:>6}  {:>4}  {:<24}; {}\n",
+                index, line_column, instruction, constant
+            )),
+            None => text.push_str(&format!(
+                "{:>6}  {:>4}  {}\n",
+                index, line_column, instruction
+            )),
+        }
+    }
+    text.push_str("Константы:\n");
+    for (index, constant) in constants.iter().enumerate() {
+        text.push_str(&format!("{:>6}  {:?}\n", index, constant));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opcode_is_twelve_bytes() {
+        assert_eq!(std::mem::size_of::<OpCode>(), 12);
+    }
+}
@@ -2,7 +2,7 @@ use crate::ponos::parser::error::{ParseErrorKind, PonosParseError};
 use crate::ponos::span::Span;
 use std::cell::Cell;
 use winnow::prelude::*;
-use winnow::stream::{AsChar, Stream};
+use winnow::stream::Stream;
 use winnow::token::take_while;
 
 /// Тип входных данных для парсера
@@ -11,32 +11,47 @@ pub type Input<'a> = &'a str;
 /// Тип результата парсера
 pub type PResult<'a, O> = Result<O, winnow::error::ErrMode<PonosParseError>>;
 
+// Позиции считаются от начала разбираемого исходника: парсер видит только
+// остаток входа, а остаток всегда заканчивается там же, где исходник, поэтому
+// смещение = длина исходника - длина остатка. Исходник запоминается по адресу
+// его конца, чтобы разбор другой строки не унаследовал чужую длину
+#[derive(Clone, Copy)]
+struct SourceAnchor {
+    end: usize, // Адрес байта за концом исходника
+    len: usize,
+}
+
 thread_local! {
-    static SOURCE_LENGTH: Cell<usize> = Cell::new(0);
+    static SOURCE: Cell<Option<SourceAnchor>> = const { Cell::new(None) };
 }
 
-/// Устанавливает длину исходника для расчёта абсолютных позиций
-pub fn set_source_length(len: usize) {
-    SOURCE_LENGTH.with(|cell| cell.set(len));
+fn anchor_of(source: &str) -> SourceAnchor {
+    SourceAnchor {
+        end: source.as_ptr() as usize + source.len(),
+        len: source.len(),
+    }
 }
 
-pub fn ensure_source_length(len: usize) {
-    SOURCE_LENGTH.with(|cell| {
-        if cell.get() == 0 {
-            cell.set(len);
-        }
+/// Запоминает исходник, от начала которого считаются позиции
+pub fn set_source(source: &str) {
+    SOURCE.with(|cell| cell.set(Some(anchor_of(source))));
+}
+
+/// Запоминает `input` как исходник, если он не является остатком уже
+/// запомненного: так разбор фрагмента (в тестах, в REPL) считает позиции от
+/// начала фрагмента, а вложенные вызовы — от начала всего исходника
+pub fn ensure_source(input: &str) {
+    let anchor = anchor_of(input);
+    SOURCE.with(|cell| match cell.get() {
+        Some(current) if current.end == anchor.end && current.len >= anchor.len => {}
+        _ => cell.set(Some(anchor)),
     });
 }
 
 fn source_length_or(default: usize) -> usize {
-    SOURCE_LENGTH.with(|cell| {
-        let current = cell.get();
-        if current == 0 {
-            cell.set(default);
-            default
-        } else {
-            current
-        }
+    SOURCE.with(|cell| match cell.get() {
+        Some(anchor) if anchor.len >= default => anchor.len,
+        _ => default,
     })
 }
 
@@ -47,7 +62,10 @@ pub fn offset_from_remaining(remaining: usize) -> usize {
 
 pub fn span_from_remaining(start_remaining: usize, end_remaining: usize) -> Span {
     let len = source_length_or(start_remaining);
-    Span::new(len.saturating_sub(start_remaining), len.saturating_sub(end_remaining))
+    Span::new(
+        len.saturating_sub(start_remaining),
+        len.saturating_sub(end_remaining),
+    )
 }
 
 pub fn span_with_width(start_remaining: usize, width: usize) -> Span {
@@ -61,6 +79,42 @@ pub fn ws<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     Ok(())
 }
 
+/// Зарезервированные слова: их нельзя использовать как имена. Контекстные
+/// слова (`как`, `из`, `каждого`, `показать`, `конструктор`, ...) значимы
+/// только на своих местах и остаются обычными идентификаторами, как и
+/// операторы `и`/`или`: выражение не может начинаться с оператора, поэтому
+/// имя `и` в нём не двусмысленно
+pub const RESERVED_WORDS: &[&str] = &[
+    "пер",
+    "конст",
+    "функ",
+    "конец",
+    "класс",
+    "интерфейс",
+    "аннотация",
+    "если",
+    "иначе",
+    "пока",
+    "для",
+    "возврат",
+    "попытка",
+    "перехват",
+    "наконец",
+    "исключение",
+    "экспорт",
+    "использовать",
+    "это",
+    "родитель",
+    "истина",
+    "ложь",
+    "ничто",
+];
+
+/// Является ли слово зарезервированным
+pub fn is_reserved(word: &str) -> bool {
+    RESERVED_WORDS.contains(&word)
+}
+
 /// Парсит ключевое слово, за которым должна следовать граница слова
 pub fn keyword<'a>(kw: &'static str) -> impl Parser<Input<'a>, (), PonosParseError> {
     move |input: &mut Input<'a>| {
@@ -82,17 +136,17 @@ pub fn keyword<'a>(kw: &'static str) -> impl Parser<Input<'a>, (), PonosParseErr
         *input = &input[kw.len()..];
 
         // Проверяем границу слова (следующий символ не должен быть буквой/цифрой)
-        if let Some(next_char) = input.chars().next() {
-            if next_char.is_alphanumeric() || next_char == '_' {
-                input.reset(&start);
-                return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
-                    ParseErrorKind::UnexpectedToken {
-                        expected: vec![kw.to_string()],
-                        found: format!("{}{}", kw, next_char),
-                    },
-                    span_with_width(start_remaining, kw.len() + next_char.len_utf8()),
-                )));
-            }
+        if let Some(next_char) = input.chars().next()
+            && (next_char.is_alphanumeric() || next_char == '_')
+        {
+            input.reset(&start);
+            return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
+                ParseErrorKind::UnexpectedToken {
+                    expected: vec![kw.to_string()],
+                    found: format!("{}{}", kw, next_char),
+                },
+                span_with_width(start_remaining, kw.len() + next_char.len_utf8()),
+            )));
         }
 
         Ok(())
@@ -144,11 +198,11 @@ pub fn char_<'a>(ch: char) -> impl Parser<Input<'a>, char, PonosParseError> {
     move |input: &mut Input<'a>| {
         let start_remaining = input.len();
 
-        if let Some(first) = input.chars().next() {
-            if first == ch {
-                *input = &input[first.len_utf8()..];
-                return Ok(first);
-            }
+        if let Some(first) = input.chars().next()
+            && first == ch
+        {
+            *input = &input[first.len_utf8()..];
+            return Ok(first);
         }
 
         Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
@@ -165,6 +219,7 @@ pub fn char_<'a>(ch: char) -> impl Parser<Input<'a>, char, PonosParseError> {
     }
 }
 
+#[allow(dead_code)]
 /// Оборачивает парсер для отслеживания позиций (span)
 pub fn spanned<'a, O, P>(mut parser: P) -> impl Parser<Input<'a>, (O, Span), PonosParseError>
 where
@@ -220,7 +275,7 @@ mod tests {
 
     #[test]
     fn test_span_from_remaining_uses_absolute_offsets() {
-        set_source_length(12);
+        set_source("пер x = 1");
         // Начало входа: осталось 12 байт, потребляем 5
         let span = span_from_remaining(12, 7);
         assert_eq!(span.start, 0);
@@ -1,5 +1,7 @@
+use crate::ponos::parser::combinator::span_with_width;
 use crate::ponos::span::Span;
 use std::fmt;
+#[allow(deprecated)]
 use winnow::error::{ErrorKind, FromExternalError, ParserError};
 use winnow::stream::Stream;
 
@@ -19,6 +21,8 @@ pub enum ParseErrorKind {
     InvalidString(String),
     /// Неверный идентификатор
     InvalidIdentifier(String),
+    /// Зарезервированное слово на месте имени
+    ReservedWord(String),
     /// Произвольная ошибка
     Custom(String),
 }
@@ -47,12 +51,21 @@ impl PonosParseError {
 
     /// Форматирует ошибку с подсветкой исходного кода
     pub fn format(&self, source: &str, filename: &str) -> String {
+        self.render(source, filename, "Ошибка", "\x1b[31m")
+    }
+
+    /// Форматирует то же сообщение как предупреждение: код при этом выполняется
+    pub fn format_warning(&self, source: &str, filename: &str) -> String {
+        self.render(source, filename, "Предупреждение", "\x1b[33m")
+    }
+
+    // Заголовок `label` и подчёркивание цветом `accent`
+    fn render(&self, source: &str, filename: &str, label: &str, accent: &str) -> String {
         let (start_loc, end_loc) = self.span.to_location(source);
 
         let mut output = String::new();
 
         // Цвета ANSI
-        let red = "\x1b[31m";
         let yellow = "\x1b[33m";
         let blue = "\x1b[34m";
         let bold = "\x1b[1m";
@@ -61,9 +74,10 @@ impl PonosParseError {
 
         // Заголовок ошибки с цветом
         output.push_str(&format!(
-            "{}{}Ошибка:{} {} в {}{}:{}:{}{}\n",
+            "{}{}{}:{} {} в {}{}:{}:{}{}\n",
             bold,
-            red,
+            accent,
+            label,
             reset,
             self.kind.message(),
             blue,
@@ -81,19 +95,13 @@ impl PonosParseError {
         // Исходный код с подсветкой и контекстом
         let lines: Vec<&str> = source.lines().collect();
         if start_loc.line < lines.len() {
-            output.push_str("\n");
+            output.push('\n');
 
             // Показываем 2 строки до ошибки для контекста
             let context_start = start_loc.line.saturating_sub(2);
             for i in context_start..start_loc.line {
                 if i < lines.len() {
-                    output.push_str(&format!(
-                        "{}{:4} |{} {}\n",
-                        gray,
-                        i + 1,
-                        reset,
-                        lines[i]
-                    ));
+                    output.push_str(&format!("{}{:4} |{} {}\n", gray, i + 1, reset, lines[i]));
                 }
             }
 
@@ -120,7 +128,7 @@ impl PonosParseError {
                 blue,
                 reset,
                 " ".repeat(start_loc.column),
-                red,
+                accent,
                 "^".repeat(underline_len),
                 reset
             ));
@@ -142,10 +150,10 @@ impl PonosParseError {
                 ""
             };
 
-            if !found_text.is_empty() && found_text.trim().len() > 0 {
-                output.push_str(&format!(" {}{}{}", red, found_text, reset));
+            if !found_text.is_empty() && !found_text.trim().is_empty() {
+                output.push_str(&format!(" {}{}{}", accent, found_text, reset));
             }
-            output.push_str("\n");
+            output.push('\n');
 
             // Показываем 1 строку после ошибки для контекста
             if start_loc.line + 1 < lines.len() {
@@ -161,7 +169,10 @@ impl PonosParseError {
 
         // Подсказка с цветом
         if let Some(hint) = self.kind.hint() {
-            output.push_str(&format!("\n{}{}💡 Подсказка:{} {}\n", bold, yellow, reset, hint));
+            output.push_str(&format!(
+                "\n{}{}💡 Подсказка:{} {}\n",
+                bold, yellow, reset, hint
+            ));
         }
 
         output
@@ -192,6 +203,9 @@ impl ParseErrorKind {
             ParseErrorKind::InvalidIdentifier(s) => {
                 format!("Неверный идентификатор: '{}'", s)
             }
+            ParseErrorKind::ReservedWord(s) => {
+                format!("Ключевое слово '{}' нельзя использовать как имя", s)
+            }
             ParseErrorKind::Custom(msg) => msg.clone(),
         }
     }
@@ -201,7 +215,7 @@ impl ParseErrorKind {
             ParseErrorKind::UnexpectedToken { expected, found } => {
                 // Подсказки для точки с запятой
                 if expected.contains(&";".to_string()) {
-                    return Some("Возможно, вы забыли поставить точку с запятой? Операторы 'пер', 'возврат', 'исключение' и выражения должны заканчиваться на ';'".to_string());
+                    return Some("Операторы разделяются переводом строки или ';'. Чтобы записать несколько операторов в одной строке, разделите их ';'".to_string());
                 }
 
                 // Подсказки для 'конец'
@@ -246,14 +260,17 @@ impl ParseErrorKind {
                 Some("Файл закончился раньше времени. Проверьте, все ли блоки закрыты словом 'конец', все ли строки закрыты кавычками, и все ли скобки закрыты.".to_string())
             }
             ParseErrorKind::InvalidNumber(num) => {
-                Some(format!("Проверьте формат числа '{}'. Числа должны быть в формате: 42 или 3.14", num))
+                Some(format!("Проверьте формат числа '{}'. Числа записываются как 42, 3.14, 1.5e-3, 1_000_000, 0x1F или 0b1010", num))
             }
             ParseErrorKind::InvalidString(_) => {
-                Some("Строки должны быть заключены в двойные кавычки (\"). Используйте \\ для экранирования: \\n, \\t, \\\"".to_string())
+                Some("Строки должны быть заключены в двойные кавычки (\"). Используйте \\ для экранирования: \\n, \\t, \\\", \\x41, \\u{1F600}".to_string())
             }
             ParseErrorKind::InvalidIdentifier(id) => {
                 Some(format!("Идентификатор '{}' недопустим. Идентификаторы должны начинаться с буквы или _, и содержать только буквы, цифры и _", id))
             }
+            ParseErrorKind::ReservedWord(word) => {
+                Some(format!("Выберите другое имя, например '{}_'. Слова, которые только начинаются с ключевого, как 'переменная' или 'конечный', допустимы", word))
+            }
             ParseErrorKind::Custom(_) => None,
         }
     }
@@ -268,11 +285,12 @@ impl fmt::Display for PonosParseError {
 impl std::error::Error for PonosParseError {}
 
 // Реализация ParserError для интеграции с winnow
+#[allow(deprecated)]
 impl<I: Stream> ParserError<I> for PonosParseError {
-    fn from_error_kind(_input: &I, _kind: ErrorKind) -> Self {
+    fn from_error_kind(input: &I, _kind: ErrorKind) -> Self {
         PonosParseError::new(
             ParseErrorKind::Custom("Ошибка парсинга".to_string()),
-            Span::default(),
+            span_with_width(input.eof_offset(), 0),
         )
     }
 
@@ -286,13 +304,14 @@ impl<I: Stream> ParserError<I> for PonosParseError {
     }
 }
 
+#[allow(deprecated)]
 impl<I: Stream, E: std::error::Error + Send + Sync + 'static> FromExternalError<I, E>
     for PonosParseError
 {
-    fn from_external_error(_input: &I, _kind: ErrorKind, _e: E) -> Self {
+    fn from_external_error(input: &I, _kind: ErrorKind, _e: E) -> Self {
         PonosParseError::new(
             ParseErrorKind::Custom("Внешняя ошибка".to_string()),
-            Span::default(),
+            span_with_width(input.eof_offset(), 0),
         )
     }
 }
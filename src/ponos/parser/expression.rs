@@ -1,20 +1,23 @@
 use crate::ponos::ast::*;
 use crate::ponos::parser::combinator::{
-    ensure_source_length, Input, PResult, char_, offset_from_remaining, span_from_remaining,
+    Input, PResult, char_, ensure_source, offset_from_remaining, span_from_remaining,
 };
+use crate::ponos::parser::error::{ParseErrorKind, PonosParseError};
 use crate::ponos::parser::lexer::{
-    keyword_and, keyword_end, keyword_func, keyword_or, keyword_super, keyword_this, parse_bool,
-    parse_identifier, parse_nil, parse_number, parse_string, skip_ws_and_comments,
+    NumberLiteral, keyword_and, keyword_end, keyword_func, keyword_or, keyword_super, keyword_this,
+    parse_bool, parse_field_name, parse_identifier, parse_nil, parse_number, parse_string,
+    parse_type_name, skip_ws_and_comments,
 };
 use crate::ponos::span::Span;
-use winnow::error::ErrMode;
 use winnow::combinator::{alt, delimited, separated};
+use winnow::error::ErrMode;
 use winnow::prelude::*;
 use winnow::stream::Stream;
+use winnow::token::literal;
 
 /// Главная функция парсинга выражений
 pub fn parse_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
-    ensure_source_length(input.len());
+    ensure_source(input);
     parse_binary_expression(input, 0)
 }
 
@@ -38,7 +41,7 @@ fn parse_binary_expression<'a>(
             break;
         }
 
-        let (operator, op_span) = op_result.unwrap();
+        let (operator, _op_span) = op_result.unwrap();
         let precedence = operator.precedence();
 
         if precedence < min_precedence {
@@ -79,6 +82,7 @@ fn parse_binary_operator<'a>(input: &mut Input<'a>) -> PResult<'a, (BinaryOperat
         // Логические операторы (ключевые слова)
         keyword_and.map(|_| BinaryOperator::And),
         keyword_or.map(|_| BinaryOperator::Or),
+        "??".map(|_| BinaryOperator::Coalesce),
         // Сравнения (двухсимвольные сначала!)
         "==".map(|_| BinaryOperator::Equal),
         "!=".map(|_| BinaryOperator::NotEqual),
@@ -120,8 +124,8 @@ fn parse_unary_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression>
     if let Ok(operator) = op_result {
         skip_ws_and_comments(input)?;
         let operand = parse_unary_expression(input)?;
-        let end = input.len();
-        let span = span_from_remaining(start, end);
+        // Конец — по операнду: после него разбор уже пропустил пробелы
+        let span = Span::new(offset_from_remaining(start), operand.span().end);
 
         Ok(Expression::Unary(Box::new(UnaryExpr {
             operator,
@@ -135,18 +139,36 @@ fn parse_unary_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression>
     }
 }
 
+/// Парсит аргумент вызова: выражение или `...массив`
+fn parse_argument<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
+    let start = input.len();
+    if literal::<_, _, PonosParseError>("...")
+        .parse_next(input)
+        .is_ok()
+    {
+        let value = parse_expression(input)?;
+        let span = span_from_remaining(start, input.len());
+        return Ok(Expression::Spread(Box::new(value), span));
+    }
+    parse_expression(input)
+}
+
 /// Парсит постфиксные выражения (вызовы функций, доступ к полям)
 fn parse_postfix_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     let mut expr = parse_primary_expression(input)?;
 
     loop {
+        let before_ws = *input;
         skip_ws_and_comments(input)?;
+        // '(' и '[' с новой строки начинают следующий оператор, а не вызов
+        // или индексирование; цепочка через '.' переносится свободно
+        let on_new_line = before_ws[..before_ws.len() - input.len()].contains('\n');
 
         // Пытаемся спарсить постфиксный оператор
         let saved = input.checkpoint();
 
         // Вызов функции: expr(args)
-        if char_('(').parse_next(input).is_ok() {
+        if !on_new_line && char_('(').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
 
             // Парсим аргументы
@@ -160,7 +182,7 @@ fn parse_postfix_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression
 
                 let args = separated(
                     0..,
-                    parse_expression,
+                    parse_argument,
                     (skip_ws_and_comments, char_(','), skip_ws_and_comments),
                 )
                 .parse_next(input)?;
@@ -186,7 +208,7 @@ fn parse_postfix_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression
         // Доступ к полю: expr.field
         if char_('.').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
-            let field = parse_identifier(input)?.to_string();
+            let field = parse_field_name(input)?.to_string();
             let span = Span::new(expr.span().start, offset_from_remaining(input.len()));
 
             expr = Expression::FieldAccess(Box::new(FieldAccessExpr {
@@ -201,7 +223,7 @@ fn parse_postfix_expression<'a>(input: &mut Input<'a>) -> PResult<'a, Expression
 
         // Индексирование: expr[index] или срез: expr[start:end]
         let bracket_start_remaining = input.len();
-        if char_('[').parse_next(input).is_ok() {
+        if !on_new_line && char_('[').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
 
             // Проверяем, не срез ли это (начинается с :)
@@ -302,7 +324,10 @@ fn parse_number_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     let num = parse_number(input)?;
     let end = input.len();
     let span = span_from_remaining(start, end);
-    Ok(Expression::Number(num, span))
+    Ok(match num {
+        NumberLiteral::Integer(n) => Expression::Integer(n, span),
+        NumberLiteral::Float(n) => Expression::Number(n, span),
+    })
 }
 
 fn parse_string_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
@@ -343,7 +368,7 @@ fn parse_super_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     skip_ws_and_comments(input)?;
     char_('.').parse_next(input)?;
     skip_ws_and_comments(input)?;
-    let method = parse_identifier(input)?.to_string();
+    let method = parse_field_name(input)?.to_string();
     let end = input.len();
     let span = span_from_remaining(start, end);
     Ok(Expression::Super(method, span))
@@ -381,12 +406,7 @@ fn parse_lambda_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     let params = if char_(')').parse_next(input).is_ok() {
         Vec::new()
     } else {
-        let params: Vec<Parameter> = separated(
-            0..,
-            parse_parameter,
-            (skip_ws_and_comments, char_(','), skip_ws_and_comments),
-        )
-        .parse_next(input)?;
+        let params = parse_parameters(input)?;
 
         skip_ws_and_comments(input)?;
         char_(')').parse_next(input)?;
@@ -397,9 +417,9 @@ fn parse_lambda_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     skip_ws_and_comments(input)?;
 
     // Опциональный тип возврата: ": тип"
-    let _return_type = if char_(':').parse_next(input).is_ok() {
+    let return_type = if char_(':').parse_next(input).is_ok() {
         skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
+        Some(parse_type_name(input)?.to_string())
     } else {
         None
     };
@@ -430,13 +450,54 @@ fn parse_lambda_expr<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
 
     Ok(Expression::Lambda(Box::new(LambdaExpr {
         params,
+        return_type,
         body,
         span,
     })))
 }
 
-fn parse_parameter<'a>(input: &mut Input<'a>) -> PResult<'a, Parameter> {
+/// Парсит список параметров через запятую. Параметры со значением по
+/// умолчанию должны идти после обязательных, `...остальные` — последним
+pub fn parse_parameters<'a>(input: &mut Input<'a>) -> PResult<'a, Vec<Parameter>> {
+    let params: Vec<Parameter> = separated(
+        0..,
+        parse_parameter,
+        (skip_ws_and_comments, char_(','), skip_ws_and_comments),
+    )
+    .parse_next(input)?;
+
+    let mut optional = false;
+    for (index, param) in params.iter().enumerate() {
+        if param.is_rest && (index + 1 != params.len() || param.default.is_some()) {
+            return Err(ErrMode::Cut(PonosParseError::new(
+                ParseErrorKind::Custom(format!(
+                    "Параметр '...{}' должен быть последним и без значения по умолчанию",
+                    param.name
+                )),
+                param.span,
+            )));
+        }
+        if param.default.is_some() {
+            optional = true;
+        } else if optional && !param.is_rest {
+            return Err(ErrMode::Cut(PonosParseError::new(
+                ParseErrorKind::Custom(format!(
+                    "Обязательный параметр '{}' не может идти после параметров со значением по умолчанию",
+                    param.name
+                )),
+                param.span,
+            )));
+        }
+    }
+    Ok(params)
+}
+
+/// Парсит параметр функции или лямбды: [...]имя [: тип] [= значение по умолчанию]
+pub fn parse_parameter<'a>(input: &mut Input<'a>) -> PResult<'a, Parameter> {
     let start = input.len();
+    let is_rest = literal::<_, _, PonosParseError>("...")
+        .parse_next(input)
+        .is_ok();
     let name = parse_identifier(input)?.to_string();
 
     skip_ws_and_comments(input)?;
@@ -444,7 +505,16 @@ fn parse_parameter<'a>(input: &mut Input<'a>) -> PResult<'a, Parameter> {
     // Опциональная аннотация типа
     let type_annotation = if char_(':').parse_next(input).is_ok() {
         skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
+        let ty = parse_type_name(input)?.to_string();
+        skip_ws_and_comments(input)?;
+        Some(ty)
+    } else {
+        None
+    };
+
+    // Значение по умолчанию вычисляется при вызове, если аргумент не передан
+    let default = if char_('=').parse_next(input).is_ok() {
+        Some(parse_expression(input)?)
     } else {
         None
     };
@@ -455,11 +525,14 @@ fn parse_parameter<'a>(input: &mut Input<'a>) -> PResult<'a, Parameter> {
     Ok(Parameter {
         name,
         type_annotation,
+        default,
+        is_rest,
         span,
     })
 }
 
-/// Парсит литерал массива: [элемент1, элемент2, ...]
+/// Парсит литерал массива: [элемент1, элемент2, ...]; после последнего
+/// элемента допускается запятая
 fn parse_array_literal<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     let start = input.len();
     char_('[').parse_next(input)?;
@@ -490,6 +563,10 @@ fn parse_array_literal<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
         let checkpoint = input.checkpoint();
         if char_(',').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
+            // Завершающая запятая перед ]
+            if input.starts_with(']') {
+                break;
+            }
             continue;
         }
         input.reset(&checkpoint);
@@ -510,7 +587,8 @@ fn parse_array_literal<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     })))
 }
 
-/// Парсит литерал словаря: {ключ1: значение1, ключ2: значение2, ...}
+/// Парсит литерал словаря: {ключ1: значение1, ключ2: значение2, ...}; после
+/// последней пары допускается запятая
 fn parse_dict_literal<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
     let start = input.len();
     char_('{').parse_next(input)?;
@@ -542,6 +620,10 @@ fn parse_dict_literal<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
         let checkpoint = input.checkpoint();
         if char_(',').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
+            // Завершающая запятая перед }
+            if input.starts_with('}') {
+                break;
+            }
             continue;
         }
         input.reset(&checkpoint);
@@ -599,7 +681,14 @@ mod tests {
         let mut input = "42";
         let expr = parse_expression(&mut input).unwrap();
         match expr {
-            Expression::Number(n, _) => assert_eq!(n, 42.0),
+            Expression::Integer(n, _) => assert_eq!(n, 42),
+            _ => panic!("Expected integer"),
+        }
+
+        let mut input = "4.5";
+        let expr = parse_expression(&mut input).unwrap();
+        match expr {
+            Expression::Number(n, _) => assert_eq!(n, 4.5),
             _ => panic!("Expected number"),
         }
     }
@@ -632,9 +721,9 @@ mod tests {
             Expression::Binary(b) => {
                 assert_eq!(b.operator, BinaryOperator::Add);
                 match (&b.left, &b.right) {
-                    (Expression::Number(l, _), Expression::Number(r, _)) => {
-                        assert_eq!(*l, 2.0);
-                        assert_eq!(*r, 3.0);
+                    (Expression::Integer(l, _), Expression::Integer(r, _)) => {
+                        assert_eq!(*l, 2);
+                        assert_eq!(*r, 3);
                     }
                     _ => panic!("Expected numbers"),
                 }
@@ -653,8 +742,8 @@ mod tests {
             Expression::Binary(b) => {
                 assert_eq!(b.operator, BinaryOperator::Add);
                 match (&b.left, &b.right) {
-                    (Expression::Number(l, _), Expression::Binary(r)) => {
-                        assert_eq!(*l, 2.0);
+                    (Expression::Integer(l, _), Expression::Binary(r)) => {
+                        assert_eq!(*l, 2);
                         assert_eq!(r.operator, BinaryOperator::Multiply);
                     }
                     _ => panic!("Wrong structure"),
@@ -672,7 +761,7 @@ mod tests {
             Expression::Unary(u) => {
                 assert_eq!(u.operator, UnaryOperator::Negate);
                 match &u.operand {
-                    Expression::Number(n, _) => assert_eq!(*n, 5.0),
+                    Expression::Integer(n, _) => assert_eq!(*n, 5),
                     _ => panic!("Expected number"),
                 }
             }
@@ -793,6 +882,7 @@ mod tests {
                 assert_eq!(l.params[0].type_annotation, Some("число".to_string()));
                 assert_eq!(l.params[1].name, "b");
                 assert_eq!(l.params[1].type_annotation, Some("число".to_string()));
+                assert_eq!(l.return_type, Some("число".to_string()));
                 assert_eq!(l.body.len(), 1);
             }
             _ => panic!("Expected lambda"),
@@ -848,6 +938,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_nil_coalescing() {
+        // ?? связывает слабее или и левоассоциативен
+        let mut input = "а или б ?? в ?? г";
+        let expr = parse_expression(&mut input).unwrap();
+        let Expression::Binary(outer) = expr else {
+            panic!("Expected binary ??");
+        };
+        assert_eq!(outer.operator, BinaryOperator::Coalesce);
+        assert!(matches!(&outer.right, Expression::Identifier(name, _) if name == "г"));
+        let Expression::Binary(inner) = &outer.left else {
+            panic!("Expected nested ??");
+        };
+        assert_eq!(inner.operator, BinaryOperator::Coalesce);
+        assert!(matches!(&inner.left, Expression::Binary(or) if or.operator == BinaryOperator::Or));
+    }
+
     #[test]
     fn test_parse_logical_not() {
         let mut input = "!истина";
@@ -968,6 +1075,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_nested_literals_with_trailing_commas() {
+        let mut input = "[\n    1,\n    [2, 3,],\n    {\"а\": [4], \"б\": {},},\n]";
+        let expr = parse_expression(&mut input).unwrap();
+        assert!(input.is_empty(), "остаток: {:?}", input);
+        let Expression::ArrayLiteral(array) = expr else {
+            panic!("Expected array literal, got {:?}", expr);
+        };
+        assert_eq!(array.elements.len(), 3);
+        assert!(
+            matches!(&array.elements[1], Expression::ArrayLiteral(inner) if inner.elements.len() == 2)
+        );
+        match &array.elements[2] {
+            Expression::DictLiteral(dict) => {
+                assert_eq!(dict.pairs.len(), 2);
+                assert!(
+                    matches!(&dict.pairs[1].1, Expression::DictLiteral(d) if d.pairs.is_empty())
+                );
+            }
+            other => panic!("Expected dict literal, got {:?}", other),
+        }
+
+        // Одна запятая без элементов — не литерал
+        assert!(parse_expression(&mut "[,]").is_err());
+        assert!(parse_expression(&mut "{\"а\": 1,,}").is_err());
+    }
+
     #[test]
     fn dict_literal_error_span_points_to_missing_comma() {
         let source = r#"{ "a": 1, "b": {"c": 2} "d": 3 }"#;
@@ -1,43 +1,137 @@
-use crate::ponos::parser::combinator::{Input, PResult, identifier as ident_parser, keyword};
+use crate::ponos::parser::combinator::{
+    Input, PResult, identifier as ident_parser, is_reserved, keyword, span_with_width,
+};
 use crate::ponos::parser::error::{ParseErrorKind, PonosParseError};
-use crate::ponos::span::Span;
 use winnow::combinator::alt;
 use winnow::prelude::*;
 use winnow::token::take_while;
 
-/// Парсит число (целое или с плавающей точкой)
-pub fn parse_number<'a>(input: &mut Input<'a>) -> PResult<'a, f64> {
-    let start_len = input.len();
+/// Числовой литерал: целый или с дробной частью
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberLiteral {
+    Integer(i64),
+    Float(f64),
+}
 
-    // Парсим целую часть
-    let int_part = take_while(1.., |c: char| c.is_ascii_digit()).parse_next(input)?;
+/// Парсит число: целое (`42`, `1_000_000`, `0x1F`, `0b1010`) или с плавающей
+/// точкой (`3.14`, `1.5e-3`, `2E10`). Десятичное целое, не помещающееся
+/// в i64, читается как число с плавающей точкой. Знак `_` разделяет цифры
+pub fn parse_number<'a>(input: &mut Input<'a>) -> PResult<'a, NumberLiteral> {
+    let start = *input;
+    if !input.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                expected: vec!["число".to_string()],
+                found: input.chars().take(1).collect(),
+            },
+            span_with_width(start.len(), 0),
+        )));
+    }
 
-    // Опционально парсим дробную часть
-    let frac_part: Option<&str> = if input.starts_with('.') {
-        *input = &input[1..]; // consume '.'
-        take_while::<_, _, PonosParseError>(1.., |c: char| c.is_ascii_digit())
-            .parse_next(input)
-            .ok()
-    } else {
-        None
+    let radix = match input.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        _ => 10,
     };
+    if radix != 10 {
+        *input = &input[2..];
+        let word = take_while(0.., |c: char| c.is_alphanumeric() || c == '_').parse_next(input)?;
+        let digits = number_digits(word, radix, start, input)?;
+        return i64::from_str_radix(&digits, radix)
+            .map(NumberLiteral::Integer)
+            .map_err(|_| {
+                number_error(
+                    ParseErrorKind::Custom(format!(
+                        "Число {} не помещается в 64-битное целое",
+                        &start[..start.len() - input.len()]
+                    )),
+                    start,
+                    input,
+                )
+            });
+    }
 
-    let end_len = input.len();
-    let _span = Span::new(start_len - end_len, start_len);
+    let mut text = decimal_digits(input, start)?;
+    let mut is_float = false;
 
-    // Собираем строку и парсим число
-    let num_str = if let Some(frac) = frac_part {
-        format!("{}.{}", int_part, frac)
-    } else {
-        int_part.to_string()
-    };
+    // Дробная часть
+    if input.starts_with('.') {
+        *input = &input[1..]; // consume '.'
+        if input.starts_with(|c: char| c.is_ascii_digit()) {
+            text.push('.');
+            text.push_str(&decimal_digits(input, start)?);
+            is_float = true;
+        }
+    }
 
-    num_str.parse::<f64>().map_err(|_| {
-        winnow::error::ErrMode::Backtrack(PonosParseError::new(
-            ParseErrorKind::InvalidNumber(num_str),
-            Span::new(0, 10),
-        ))
-    })
+    // Показатель степени: e5, e+5, e-5
+    if let Some(rest) = input.strip_prefix(['e', 'E']) {
+        let unsigned = rest.strip_prefix(['+', '-']).unwrap_or(rest);
+        text.push('e');
+        text.push_str(&rest[..rest.len() - unsigned.len()]);
+        *input = unsigned;
+        if !input.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(invalid_number(start, input));
+        }
+        text.push_str(&decimal_digits(input, start)?);
+        is_float = true;
+    }
+
+    // Сразу за числом не может идти буква: `12абв`, `1е5` (кириллическая е)
+    if input.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        take_while(0.., |c: char| c.is_alphanumeric() || c == '_').parse_next(input)?;
+        return Err(invalid_number(start, input));
+    }
+
+    if !is_float && let Ok(n) = text.parse::<i64>() {
+        return Ok(NumberLiteral::Integer(n));
+    }
+    text.parse::<f64>()
+        .map(NumberLiteral::Float)
+        .map_err(|_| invalid_number(start, input))
+}
+
+// Десятичные цифры с разделителями `_`; возвращает цифры без разделителей
+fn decimal_digits<'a>(input: &mut Input<'a>, start: Input<'a>) -> PResult<'a, String> {
+    let word = take_while(0.., |c: char| c.is_ascii_digit() || c == '_').parse_next(input)?;
+    number_digits(word, 10, start, input)
+}
+
+// Проверить цифры числа в системе `radix` и убрать разделители `_`,
+// которые должны стоять между цифрами
+fn number_digits<'a>(
+    word: &str,
+    radix: u32,
+    start: Input<'a>,
+    input: &Input<'a>,
+) -> PResult<'a, String> {
+    let separated = word
+        .split('_')
+        .all(|group| !group.is_empty() && group.chars().all(|c| c.is_digit(radix)));
+    if !separated {
+        return Err(invalid_number(start, input));
+    }
+    Ok(word.replace('_', ""))
+}
+
+// Ошибка формата числа: позиция — всё прочитанное от начала литерала
+fn invalid_number<'a>(
+    start: Input<'a>,
+    input: &Input<'a>,
+) -> winnow::error::ErrMode<PonosParseError> {
+    let text = start[..start.len() - input.len()].to_string();
+    number_error(ParseErrorKind::InvalidNumber(text), start, input)
+}
+
+fn number_error<'a>(
+    kind: ParseErrorKind,
+    start: Input<'a>,
+    input: &Input<'a>,
+) -> winnow::error::ErrMode<PonosParseError> {
+    winnow::error::ErrMode::Cut(PonosParseError::new(
+        kind,
+        span_with_width(start.len(), start.len() - input.len()),
+    ))
 }
 
 /// Парсит строковый литерал с escape-последовательностями
@@ -55,51 +149,23 @@ pub fn parse_string<'a>(input: &mut Input<'a>) -> PResult<'a, String> {
                     .map(|c| c.to_string())
                     .unwrap_or_else(|| "EOF".to_string()),
             },
-            Span::new(0, 1),
+            span_with_width(start_len, 1),
         )));
     }
     *input = &input[1..]; // consume '"'
 
     let mut result = String::new();
-    let mut escaped = false;
 
     while let Some(c) = input.chars().next() {
-        if escaped {
-            // Обрабатываем escape-последовательность
-            match c {
-                'n' => result.push('\n'),
-                't' => result.push('\t'),
-                'r' => result.push('\r'),
-                '\\' => result.push('\\'),
-                '"' => result.push('"'),
-                '\'' => result.push('\''),
-                '0' => result.push('\0'),
-                _ => {
-                    // Неизвестная escape-последовательность
-                    return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
-                        ParseErrorKind::InvalidString(format!(
-                            "Неизвестная escape-последовательность: \\{}",
-                            c
-                        )),
-                        Span::new(0, 2),
-                    )));
-                }
-            }
-            escaped = false;
-            *input = &input[c.len_utf8()..];
-        } else if c == '\\' {
-            escaped = true;
-            *input = &input[1..];
+        if c == '\\' {
+            result.push(parse_escape(input)?);
         } else if c == '"' {
             // Закрывающая кавычка
             *input = &input[1..];
             return Ok(result);
         } else if c == '\n' {
             // Строка не может содержать незакрытый перевод строки
-            return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
-                ParseErrorKind::InvalidString("Незакрытая строка".to_string()),
-                Span::new(0, start_len - input.len()),
-            )));
+            return Err(unclosed_string(start_len, input));
         } else {
             result.push(c);
             *input = &input[c.len_utf8()..];
@@ -107,10 +173,107 @@ pub fn parse_string<'a>(input: &mut Input<'a>) -> PResult<'a, String> {
     }
 
     // Если мы дошли до конца без закрывающей кавычки
-    Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
+    Err(unclosed_string(start_len, input))
+}
+
+// Незакрытая строка: от открывающей кавычки до конца строки исходника
+fn unclosed_string(start_len: usize, input: &Input) -> winnow::error::ErrMode<PonosParseError> {
+    winnow::error::ErrMode::Cut(PonosParseError::new(
         ParseErrorKind::InvalidString("Незакрытая строка".to_string()),
-        Span::new(0, start_len),
-    )))
+        span_with_width(start_len, start_len - input.len()),
+    ))
+}
+
+/// Парсит escape-последовательность, начиная с `\`: `\n`, `\t`, `\r`, `\0`,
+/// `\\`, `\"`, `\'`, `\xHH` (код до 7F) и `\u{H...}` (код символа Unicode).
+/// Ошибка в последовательности прерывает разбор с позицией этой последовательности
+fn parse_escape<'a>(input: &mut Input<'a>) -> PResult<'a, char> {
+    let start_remaining = input.len();
+    let error = |message: String, input: &Input<'a>| {
+        winnow::error::ErrMode::Cut(PonosParseError::new(
+            ParseErrorKind::InvalidString(message),
+            span_with_width(start_remaining, start_remaining - input.len()),
+        ))
+    };
+
+    *input = &input[1..]; // consume '\'
+    let Some(c) = input.chars().next() else {
+        return Err(error("Незакрытая строка".to_string(), input));
+    };
+    *input = &input[c.len_utf8()..];
+
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '\\' => Ok('\\'),
+        '"' => Ok('"'),
+        '\'' => Ok('\''),
+        '0' => Ok('\0'),
+        'x' => {
+            let digits: String = input.chars().take(2).collect();
+            if digits.len() != 2 || !digits.chars().all(|d| d.is_ascii_hexdigit()) {
+                return Err(error(
+                    "После \\x ожидаются две шестнадцатеричные цифры: \\x41".to_string(),
+                    input,
+                ));
+            }
+            *input = &input[2..];
+            match u8::from_str_radix(&digits, 16) {
+                Ok(code) if code.is_ascii() => Ok(char::from(code)),
+                _ => Err(error(
+                    format!(
+                        "\\x{} вне диапазона \\x00-\\x7F, используйте \\u{{{}}}",
+                        digits, digits
+                    ),
+                    input,
+                )),
+            }
+        }
+        'u' => {
+            let Some(rest) = input.strip_prefix('{') else {
+                return Err(error(
+                    "После \\u ожидается код символа в фигурных скобках: \\u{1F600}".to_string(),
+                    input,
+                ));
+            };
+            // Ищем '}' только до конца строкового литерала
+            let close = rest.find(['}', '"', '\n']);
+            let Some(close) = close.filter(|&close| rest[close..].starts_with('}')) else {
+                return Err(error(
+                    "Незакрытая последовательность \\u{".to_string(),
+                    input,
+                ));
+            };
+            let digits = &rest[..close];
+            *input = &rest[close + 1..];
+            if digits.is_empty()
+                || digits.len() > 6
+                || !digits.chars().all(|d| d.is_ascii_hexdigit())
+            {
+                return Err(error(
+                    format!(
+                        "В \\u{{{}}} ожидается от 1 до 6 шестнадцатеричных цифр",
+                        digits
+                    ),
+                    input,
+                ));
+            }
+            u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| {
+                    error(
+                        format!("\\u{{{}}} не является символом Unicode", digits),
+                        input,
+                    )
+                })
+        }
+        _ => Err(error(
+            format!("Неизвестная escape-последовательность: \\{}", c),
+            input,
+        )),
+    }
 }
 
 /// Парсит булево значение
@@ -127,11 +290,33 @@ pub fn parse_nil<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("ничто").parse_next(input)
 }
 
-/// Парсит идентификатор (обертка для удобства)
+/// Парсит идентификатор, который не является зарезервированным словом.
+/// Идентификатор читается целиком, поэтому `переменная` — одно имя, а не
+/// `пер` и `еменная`
 pub fn parse_identifier<'a>(input: &mut Input<'a>) -> PResult<'a, &'a str> {
+    let start = *input;
+    let name = ident_parser(input)?;
+    if is_reserved(name) {
+        *input = start;
+        return Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
+            ParseErrorKind::ReservedWord(name.to_string()),
+            span_with_width(start.len(), name.len()),
+        )));
+    }
+    Ok(name)
+}
+
+/// Парсит имя поля или метода после точки: здесь допустимы и
+/// зарезервированные слова (`путь.родитель(п)`)
+pub fn parse_field_name<'a>(input: &mut Input<'a>) -> PResult<'a, &'a str> {
     ident_parser(input)
 }
 
+/// Парсит имя типа в аннотации: идентификатор или `ничто`
+pub fn parse_type_name<'a>(input: &mut Input<'a>) -> PResult<'a, &'a str> {
+    alt((keyword("ничто").map(|_| "ничто"), parse_identifier)).parse_next(input)
+}
+
 /// Парсит однострочный комментарий
 pub fn line_comment<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     if !input.starts_with("//") {
@@ -140,7 +325,7 @@ pub fn line_comment<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
                 expected: vec!["//".to_string()],
                 found: input.chars().take(2).collect(),
             },
-            Span::new(0, 2),
+            span_with_width(input.len(), 2),
         )));
     }
     *input = &input[2..];
@@ -164,9 +349,10 @@ pub fn block_comment<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
                 expected: vec!["/*".to_string()],
                 found: input.chars().take(2).collect(),
             },
-            Span::new(0, 2),
+            span_with_width(input.len(), 2),
         )));
     }
+    let start_len = input.len();
     *input = &input[2..];
 
     // Читаем до */
@@ -179,10 +365,10 @@ pub fn block_comment<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
         *input = &input[c.len_utf8()..];
     }
 
-    // Незакрытый комментарий
-    Err(winnow::error::ErrMode::Backtrack(PonosParseError::new(
+    // Незакрытый комментарий: ошибка указывает на его начало
+    Err(winnow::error::ErrMode::Cut(PonosParseError::new(
         ParseErrorKind::Custom("Незакрытый многострочный комментарий".to_string()),
-        Span::new(0, 2),
+        span_with_width(start_len, 2),
     )))
 }
 
@@ -194,8 +380,12 @@ pub fn skip_ws_and_comments<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
         // Пропускаем пробелы
         take_while::<_, _, PonosParseError>(0.., |c: char| c.is_whitespace()).parse_next(input)?;
 
-        // Пытаемся пропустить комментарий
-        let _ = alt((line_comment, block_comment)).parse_next(input);
+        // Пытаемся пропустить комментарий; незакрытый комментарий — ошибка
+        if let Err(winnow::error::ErrMode::Cut(e)) =
+            alt((line_comment, block_comment)).parse_next(input)
+        {
+            return Err(winnow::error::ErrMode::Cut(e));
+        }
 
         // Если ничего не изменилось, выходим
         if input.len() == before {
@@ -211,6 +401,10 @@ pub fn keyword_var<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("пер").parse_next(input)
 }
 
+pub fn keyword_const<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
+    keyword("конст").parse_next(input)
+}
+
 pub fn keyword_func<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("функ").parse_next(input)
 }
@@ -255,6 +449,10 @@ pub fn keyword_catch<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("перехват").parse_next(input)
 }
 
+pub fn keyword_finally<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
+    keyword("наконец").parse_next(input)
+}
+
 pub fn keyword_throw<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("исключение").parse_next(input)
 }
@@ -283,10 +481,12 @@ pub fn keyword_use<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("использовать").parse_next(input)
 }
 
+#[allow(dead_code)]
 pub fn keyword_show<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("показать").parse_next(input)
 }
 
+#[allow(dead_code)]
 pub fn keyword_hide<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("скрыть").parse_next(input)
 }
@@ -299,6 +499,10 @@ pub fn keyword_constructor<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("конструктор").parse_next(input)
 }
 
+pub fn keyword_static<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
+    keyword("статический").parse_next(input)
+}
+
 pub fn keyword_and<'a>(input: &mut Input<'a>) -> PResult<'a, ()> {
     keyword("и").parse_next(input)
 }
@@ -324,27 +528,98 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_parse_number() {
         let mut input = "42";
-        assert_eq!(parse_number(&mut input).unwrap(), 42.0);
+        assert_eq!(
+            parse_number(&mut input).unwrap(),
+            NumberLiteral::Integer(42)
+        );
 
         let mut input = "3.14";
-        assert_eq!(parse_number(&mut input).unwrap(), 3.14);
+        assert_eq!(
+            parse_number(&mut input).unwrap(),
+            NumberLiteral::Float(3.14)
+        );
 
         let mut input = "0";
-        assert_eq!(parse_number(&mut input).unwrap(), 0.0);
+        assert_eq!(parse_number(&mut input).unwrap(), NumberLiteral::Integer(0));
 
         let mut input = "123.456";
-        assert_eq!(parse_number(&mut input).unwrap(), 123.456);
+        assert_eq!(
+            parse_number(&mut input).unwrap(),
+            NumberLiteral::Float(123.456)
+        );
+
+        let mut input = "2.0";
+        assert_eq!(parse_number(&mut input).unwrap(), NumberLiteral::Float(2.0));
+
+        let mut input = "99999999999999999999";
+        assert_eq!(
+            parse_number(&mut input).unwrap(),
+            NumberLiteral::Float(1e20)
+        );
+    }
+
+    #[test]
+    fn test_parse_number_extended_forms() {
+        for (source, expected) in [
+            ("1_000_000", NumberLiteral::Integer(1_000_000)),
+            ("0x1F", NumberLiteral::Integer(31)),
+            ("0Xff", NumberLiteral::Integer(255)),
+            ("0b1010", NumberLiteral::Integer(10)),
+            ("0b1111_0000", NumberLiteral::Integer(240)),
+            ("1.5e-3", NumberLiteral::Float(1.5e-3)),
+            ("2E10", NumberLiteral::Float(2e10)),
+            ("1e+2", NumberLiteral::Float(100.0)),
+            ("3_000.000_5", NumberLiteral::Float(3000.0005)),
+        ] {
+            let mut input = source;
+            assert_eq!(parse_number(&mut input).unwrap(), expected, "{}", source);
+            assert_eq!(input, "", "{}", source);
+        }
+
+        // Число заканчивается перед оператором
+        let mut input = "0x10-1";
+        assert_eq!(
+            parse_number(&mut input).unwrap(),
+            NumberLiteral::Integer(16)
+        );
+        assert_eq!(input, "-1");
+    }
+
+    #[test]
+    fn test_parse_number_errors() {
+        for bad in [
+            "1_",
+            "1__0",
+            "0x",
+            "0x_1",
+            "0b102",
+            "1e",
+            "1e+",
+            "12абв",
+            "0x8000000000000000",
+        ] {
+            let mut input = bad;
+            assert!(
+                matches!(
+                    parse_number(&mut input),
+                    Err(winnow::error::ErrMode::Cut(_))
+                ),
+                "{}",
+                bad
+            );
+        }
     }
 
     #[test]
     fn test_parse_bool() {
         let mut input = "истина";
-        assert_eq!(parse_bool(&mut input).unwrap(), true);
+        assert!(parse_bool(&mut input).unwrap());
 
         let mut input = "ложь";
-        assert_eq!(parse_bool(&mut input).unwrap(), false);
+        assert!(!parse_bool(&mut input).unwrap());
     }
 
     #[test]
@@ -377,6 +652,45 @@ mod tests {
         assert!(parse_string(&mut input).is_err());
     }
 
+    #[test]
+    fn test_parse_string_unicode_and_hex_escapes() {
+        let mut input = r#""\u{1F600} \u{41}\u{43b} \x41\x7F""#;
+        assert_eq!(parse_string(&mut input).unwrap(), "😀 Aл A\u{7f}");
+
+        let mut input = r#""кавычка \" и // не комментарий""#;
+        assert_eq!(
+            parse_string(&mut input).unwrap(),
+            "кавычка \" и // не комментарий"
+        );
+
+        for bad in [
+            r#""\q""#,
+            r#""\x80""#,
+            r#""\x4""#,
+            r#""\u41""#,
+            r#""\u{}""#,
+            r#""\u{1234567}""#,
+            r#""\u{D800}""#,
+            r#""\u{110000}""#,
+            r#""\u{41""#,
+        ] {
+            let mut input = bad;
+            match parse_string(&mut input) {
+                Err(winnow::error::ErrMode::Cut(e)) => {
+                    assert!(
+                        matches!(e.kind, ParseErrorKind::InvalidString(_)),
+                        "{}",
+                        bad
+                    )
+                }
+                other => panic!(
+                    "{}: ожидалась ошибка escape-последовательности, {:?}",
+                    bad, other
+                ),
+            }
+        }
+    }
+
     #[test]
     fn test_parse_identifier() {
         let mut input = "переменная";
@@ -390,6 +704,52 @@ mod tests {
 
         let mut input = "переменная123";
         assert_eq!(parse_identifier(&mut input).unwrap(), "переменная123");
+
+        // Имя, которое начинается с ключевого слова, читается целиком
+        let mut input = "пока_не_конец = 1";
+        assert_eq!(parse_identifier(&mut input).unwrap(), "пока_не_конец");
+        assert_eq!(input, " = 1");
+
+        // Операторы и контекстные слова остаются допустимыми именами
+        let mut input = "и";
+        assert_eq!(parse_identifier(&mut input).unwrap(), "и");
+        let mut input = "конструктор";
+        assert_eq!(parse_identifier(&mut input).unwrap(), "конструктор");
+    }
+
+    #[test]
+    fn test_parse_identifier_rejects_reserved_words() {
+        for word in ["пер", "конец", "если", "истина", "ничто", "это"] {
+            let mut input = word;
+            assert!(parse_identifier(&mut input).is_err(), "{}", word);
+            assert_eq!(input, word, "вход не должен потребляться");
+        }
+
+        // После точки зарезервированные слова допустимы как имена полей
+        let mut input = "родитель";
+        assert_eq!(parse_field_name(&mut input).unwrap(), "родитель");
+        let mut input = "ничто";
+        assert_eq!(parse_type_name(&mut input).unwrap(), "ничто");
+    }
+
+    #[test]
+    fn test_keywords_need_word_boundary() {
+        let mut input = "переменная";
+        assert!(keyword_var(&mut input).is_err());
+        assert_eq!(input, "переменная");
+
+        let mut input = "конечный";
+        assert!(keyword_end(&mut input).is_err());
+
+        let mut input = "истинный";
+        assert!(parse_bool(&mut input).is_err());
+
+        let mut input = "ничтожный";
+        assert!(parse_nil(&mut input).is_err());
+
+        let mut input = "иначе(";
+        assert!(keyword_else(&mut input).is_ok());
+        assert_eq!(input, "(");
     }
 
     #[test]
@@ -6,10 +6,10 @@ pub mod statement;
 pub mod types;
 
 use crate::ponos::ast::Program;
-use combinator::{Input, PResult, set_source_length, ws};
+use combinator::{Input, PResult, set_source, ws};
 pub use error::{ParseErrorKind, PonosParseError};
+use std::rc::Rc;
 use winnow::error::ErrMode;
-use winnow::Parser;
 
 /// Главный парсер Ponos
 pub struct PonosParser {
@@ -24,10 +24,10 @@ impl PonosParser {
     /// Парсит исходный код в AST
     pub fn parse(&mut self, source: String) -> Result<Program, PonosParseError> {
         let mut input = source.as_str();
-        set_source_length(source.len());
+        set_source(&source);
 
         match parse_program(&mut input) {
-            Ok(program) => {
+            Ok(mut program) => {
                 // Проверяем, что весь ввод обработан
                 ws(&mut input).ok();
                 if !input.is_empty() {
@@ -39,6 +39,7 @@ impl PonosParser {
                         crate::ponos::span::Span::new(source.len() - input.len(), source.len()),
                     ));
                 }
+                program.source = Rc::from(source.as_str());
                 Ok(program)
             }
             Err(e) => match e {
@@ -76,7 +77,7 @@ fn parse_program<'a>(input: &mut Input<'a>) -> PResult<'a, Program> {
         statements.push(stmt);
     }
 
-    Ok(Program { statements })
+    Ok(Program::new(statements))
 }
 
 #[cfg(test)]
@@ -99,4 +100,42 @@ mod tests {
         let result = parser.parse("   \n\t  ".to_string());
         assert!(result.is_ok());
     }
+
+    // Строка и столбец начала ошибки разбора, с единицы
+    fn error_location(source: &str) -> (usize, usize) {
+        let error = PonosParser::new().parse(source.to_string()).unwrap_err();
+        let (start, _) = error.span.to_location(source);
+        (start.line + 1, start.column + 1)
+    }
+
+    #[test]
+    fn test_error_locations_point_into_source() {
+        assert_eq!(error_location("пер а = 1;\nпер б = \"строка"), (2, 9));
+        assert_eq!(error_location("пер а = 1;\n  /* комментарий"), (2, 3));
+        assert_eq!(error_location("пер а = 1;\nпер б = 0x;"), (2, 9));
+    }
+
+    #[test]
+    fn test_fragment_after_longer_source() {
+        // Позиции фрагмента не зависят от ранее разобранного длинного текста
+        PonosParser::new()
+            .parse("пер длинное_имя = 1;\n".repeat(50))
+            .unwrap();
+        assert_eq!(error_location("пер x = \"a"), (1, 9));
+    }
+
+    #[test]
+    fn test_statement_spans_slice_source() {
+        use crate::ponos::ast::{IfStatement, Statement};
+        let source =
+            "пер а = -1 ;\nесли а > 0\n  печать(а);\nиначе если а < 0\n  печать(-а);\nконец\n";
+        let program = PonosParser::new().parse(source.to_string()).unwrap();
+        let text = |span: crate::ponos::span::Span| &source[span.start..span.end];
+        assert_eq!(text(program.statements[0].span()), "пер а = -1 ;");
+        let Statement::If(IfStatement { else_branch, .. }) = &program.statements[1] else {
+            panic!("ожидался оператор если");
+        };
+        let else_if = &else_branch.as_ref().unwrap()[0];
+        assert_eq!(text(else_if.span()), "если а < 0\n  печать(-а)");
+    }
 }
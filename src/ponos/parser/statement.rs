@@ -1,13 +1,13 @@
 use crate::ponos::ast::*;
 use crate::ponos::parser::combinator::{
-    ensure_source_length, Input, PResult, char_, span_from_remaining,
+    Input, PResult, char_, ensure_source, offset_from_remaining, span_from_remaining,
 };
-use crate::ponos::parser::expression::parse_expression;
+use crate::ponos::parser::expression::{parse_expression, parse_parameters};
 use crate::ponos::parser::lexer::{
-    keyword_annotation, keyword_catch, keyword_class, keyword_each, keyword_else, keyword_end,
-    keyword_export, keyword_for, keyword_from, keyword_func, keyword_if, keyword_interface,
-    keyword_return, keyword_throw, keyword_try, keyword_use, keyword_var, keyword_while,
-    parse_identifier, skip_ws_and_comments,
+    keyword_annotation, keyword_catch, keyword_class, keyword_const, keyword_each, keyword_else,
+    keyword_end, keyword_export, keyword_finally, keyword_for, keyword_from, keyword_func,
+    keyword_if, keyword_interface, keyword_return, keyword_throw, keyword_try, keyword_use,
+    keyword_var, keyword_while, parse_identifier, parse_type_name, skip_ws_and_comments,
 };
 use crate::ponos::span::Span;
 use winnow::combinator::separated;
@@ -16,7 +16,7 @@ use winnow::stream::Stream;
 
 /// Парсит оператор (выбирает подходящий парсер)
 pub fn parse_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
-    ensure_source_length(input.len());
+    ensure_source(input);
     skip_ws_and_comments(input)?;
 
     let checkpoint = input.checkpoint();
@@ -28,12 +28,22 @@ pub fn parse_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
     }
     input.reset(&checkpoint);
 
+    // Аннотации перед объявлением функции или класса
+    if char_('@').parse_next(input).is_ok() {
+        input.reset(&checkpoint);
+        return parse_annotated_declaration(input);
+    }
+
     // export перед конструкцией
     let export_checkpoint = input.checkpoint();
     if keyword_export(input).is_ok() {
         skip_ws_and_comments(input)?;
 
-        if keyword_var(input).is_ok() {
+        if keyword_use(input).is_ok() {
+            input.reset(&checkpoint);
+            return parse_import_statement(input);
+        }
+        if keyword_var(input).is_ok() || keyword_const(input).is_ok() {
             input.reset(&checkpoint);
             return parse_var_statement(input);
         }
@@ -60,7 +70,7 @@ pub fn parse_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
     }
 
     // Прочие конструкции
-    if keyword_var(input).is_ok() {
+    if keyword_var(input).is_ok() || keyword_const(input).is_ok() {
         input.reset(&checkpoint);
         return parse_var_statement(input);
     }
@@ -109,8 +119,91 @@ pub fn parse_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
     parse_assignment_or_expression_statement(input)
 }
 
+/// Парсит аннотации и объявление, к которому они относятся: @Имя(аргументы)...
+/// [экспорт] функ ... или класс ...
+fn parse_annotated_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let mut annotations = Vec::new();
+    loop {
+        skip_ws_and_comments(input)?;
+        if char_('@').parse_next(input).is_err() {
+            break;
+        }
+        annotations.push(parse_annotation(input)?);
+    }
+
+    let start = input.len();
+    match parse_statement(input)? {
+        Statement::FuncDecl(mut decl) => {
+            decl.annotations = annotations;
+            Ok(Statement::FuncDecl(decl))
+        }
+        Statement::ClassDecl(mut decl) => {
+            decl.annotations = annotations;
+            Ok(Statement::ClassDecl(decl))
+        }
+        _ => {
+            use crate::ponos::parser::error::{ParseErrorKind, PonosParseError};
+            Err(winnow::error::ErrMode::Cut(PonosParseError::new(
+                ParseErrorKind::Custom(
+                    "Аннотации можно ставить только перед функциями и классами".to_string(),
+                ),
+                span_from_remaining(start, start),
+            )))
+        }
+    }
+}
+
+/// Парсит аннотацию после '@': Имя или Имя(аргумент, имя = значение)
+fn parse_annotation<'a>(input: &mut Input<'a>) -> PResult<'a, Annotation> {
+    let start = input.len() + 1;
+    let name = parse_identifier(input)?.to_string();
+
+    let mut args = Vec::new();
+    let saved = input.checkpoint();
+    skip_ws_and_comments(input)?;
+    if char_('(').parse_next(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        if char_(')').parse_next(input).is_err() {
+            args = separated(
+                1..,
+                parse_annotation_argument,
+                (skip_ws_and_comments, char_(','), skip_ws_and_comments),
+            )
+            .parse_next(input)?;
+            skip_ws_and_comments(input)?;
+            char_(')').parse_next(input)?;
+        }
+    } else {
+        input.reset(&saved);
+    }
+
+    Ok(Annotation {
+        name,
+        args,
+        span: span_from_remaining(start, input.len()),
+    })
+}
+
+fn parse_annotation_argument<'a>(input: &mut Input<'a>) -> PResult<'a, AnnotationArgument> {
+    // Именованный аргумент: имя = значение (но не сравнение имя == значение)
+    let saved = input.checkpoint();
+    if let Ok(name) = parse_identifier(input) {
+        let name = name.to_string();
+        skip_ws_and_comments(input)?;
+        if !input.starts_with("==") && char_('=').parse_next(input).is_ok() {
+            skip_ws_and_comments(input)?;
+            let value = parse_expression(input)?;
+            return Ok(AnnotationArgument::Named { name, value });
+        }
+    }
+    input.reset(&saved);
+    Ok(AnnotationArgument::Positional(parse_expression(input)?))
+}
+
 /// Парсит объявление переменной: [экспорт] пер identifier [: type] = expression ;
+/// или константы: [экспорт] конст identifier [: type] = expression ;
 pub fn parse_var_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let statement = *input;
     let start = input.len();
 
     // Опциональное ключевое слово экспорт
@@ -121,16 +214,24 @@ pub fn parse_var_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement>
         false
     };
 
-    keyword_var(input)?;
+    let is_const = if keyword_const(input).is_ok() {
+        true
+    } else {
+        keyword_var(input)?;
+        false
+    };
     skip_ws_and_comments(input)?;
 
     let name = parse_identifier(input)?.to_string();
+    let mut last_end = offset_from_remaining(input.len());
     skip_ws_and_comments(input)?;
 
     // Опциональная аннотация типа
     let type_annotation = if char_(':').parse_next(input).is_ok() {
         skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
+        let type_name = parse_type_name(input)?.to_string();
+        last_end = offset_from_remaining(input.len());
+        Some(type_name)
     } else {
         None
     };
@@ -145,8 +246,17 @@ pub fn parse_var_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement>
         None
     };
 
-    skip_ws_and_comments(input)?;
-    char_(';').parse_next(input)?;
+    if is_const && initializer.is_none() {
+        return Err(cut_error(
+            &format!("Константе '{}' нужно начальное значение", name),
+            start,
+        ));
+    }
+
+    if let Some(initializer) = &initializer {
+        last_end = initializer.span().end;
+    }
+    statement_end(input, statement, last_end)?;
 
     let end = input.len();
     let span = span_from_remaining(start, end);
@@ -156,6 +266,7 @@ pub fn parse_var_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement>
         type_annotation,
         initializer,
         is_exported,
+        is_const,
         span,
     }))
 }
@@ -185,12 +296,7 @@ pub fn parse_function_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, Stat
     let params = if char_(')').parse_next(input).is_ok() {
         Vec::new()
     } else {
-        let params: Vec<Parameter> = separated(
-            0..,
-            parse_parameter,
-            (skip_ws_and_comments, char_(','), skip_ws_and_comments),
-        )
-        .parse_next(input)?;
+        let params = parse_parameters(input)?;
 
         skip_ws_and_comments(input)?;
         char_(')').parse_next(input)?;
@@ -201,9 +307,9 @@ pub fn parse_function_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, Stat
     skip_ws_and_comments(input)?;
 
     // Опциональный тип возврата: ": тип"
-    let _return_type = if char_(':').parse_next(input).is_ok() {
+    let return_type = if char_(':').parse_next(input).is_ok() {
         skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
+        Some(parse_type_name(input)?.to_string())
     } else {
         None
     };
@@ -234,8 +340,9 @@ pub fn parse_function_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, Stat
     Ok(Statement::FuncDecl(FuncDecl {
         name,
         params,
+        return_type,
         body,
-        annotations: Vec::new(), // TODO: добавить парсинг аннотаций
+        annotations: Vec::new(), // Заполняет parse_annotated_declaration
         is_exported,
         span,
     }))
@@ -254,6 +361,7 @@ fn parse_else_branch<'a>(input: &mut Input<'a>) -> PResult<'a, Option<Vec<Statem
 
     // Проверяем, не "иначе если" ли это
     let checkpoint2 = input.checkpoint();
+    let else_if_start = input.len();
     if keyword_if(input).is_ok() {
         // Это "иначе если" - парсим условие и тело
         skip_ws_and_comments(input)?;
@@ -285,12 +393,22 @@ fn parse_else_branch<'a>(input: &mut Input<'a>) -> PResult<'a, Option<Vec<Statem
         // Рекурсивно парсим следующую else-ветку (может быть еще один else-if или финальный else)
         let nested_else = parse_else_branch(input)?;
 
+        // Завершающий "конец" принадлежит внешнему если, поэтому ветка
+        // заканчивается своим последним оператором
+        let end = nested_else
+            .iter()
+            .flatten()
+            .chain(&then_branch)
+            .map(|stmt| stmt.span().end)
+            .max()
+            .unwrap_or(condition.span().end);
+
         // Создаем if-statement для представления else-if
         let else_if = Statement::If(IfStatement {
             condition,
             then_branch,
             else_branch: nested_else,
-            span: Span::default(),
+            span: Span::new(offset_from_remaining(else_if_start), end),
         });
 
         Ok(Some(vec![else_if]))
@@ -479,14 +597,14 @@ pub fn parse_foreach_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Stateme
 
 /// Парсит оператор throw: исключение <expression> ;
 pub fn parse_throw_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let statement = *input;
     let start = input.len();
 
     keyword_throw(input)?;
     skip_ws_and_comments(input)?;
 
     let expression = parse_expression(input)?;
-    skip_ws_and_comments(input)?;
-    char_(';').parse_next(input)?;
+    statement_end(input, statement, expression.span().end)?;
 
     let end = input.len();
     let span = span_from_remaining(start, end);
@@ -497,45 +615,69 @@ pub fn parse_throw_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement
     })))
 }
 
-/// Парсит try-catch блок: попытка ... перехват [ид] ... конец
+/// Парсит try блок: попытка ... [перехват [ид] ...] [наконец ...] конец.
+/// Нужна хотя бы одна из веток `перехват` и `наконец`
 pub fn parse_try_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
     let start = input.len();
 
     keyword_try(input)?;
     skip_ws_and_comments(input)?;
 
-    let mut try_body = Vec::new();
-    loop {
-        skip_ws_and_comments(input)?;
+    let try_body = parse_try_block(input)?;
 
-        let checkpoint = input.checkpoint();
-        if keyword_catch(input).is_ok() {
-            input.reset(&checkpoint);
+    let mut catches: Vec<CatchClause> = Vec::new();
+    loop {
+        let clause_start = input.len();
+        if keyword_catch(input).is_err() {
             break;
         }
-        input.reset(&checkpoint);
-
-        match parse_statement(input) {
-            Ok(stmt) => try_body.push(stmt),
-            Err(err) => {
-                input.reset(&checkpoint);
-                if keyword_catch(input).is_ok() {
-                    input.reset(&checkpoint);
-                    break;
-                }
-                return Err(err);
-            }
+        if catches.last().is_some_and(|clause| clause.class.is_none()) {
+            return Err(cut_error(
+                "Ветка 'перехват' без класса должна быть последней",
+                clause_start,
+            ));
         }
+        catches.push(parse_catch_clause(input, clause_start)?);
+    }
+
+    let finally_body = if keyword_finally(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        Some(parse_try_block(input)?)
+    } else {
+        None
+    };
+
+    if catches.is_empty() && finally_body.is_none() {
+        return Err(cut_error(
+            "После 'попытка' ожидается 'перехват' или 'наконец'",
+            input.len(),
+        ));
     }
 
-    keyword_catch(input)?;
+    keyword_end(input)?;
+
+    let end = input.len();
+    let span = span_from_remaining(start, end);
+
+    Ok(Statement::Try(Box::new(TryStatement {
+        try_body,
+        catches,
+        finally_body,
+        span,
+    })))
+}
+
+/// Ветка после `перехват`: необязательные имя переменной и `: Класс`, затем блок
+fn parse_catch_clause<'a>(input: &mut Input<'a>, start: usize) -> PResult<'a, CatchClause> {
     skip_ws_and_comments(input)?;
 
-    let catch_var = {
+    let var = {
         let checkpoint = input.checkpoint();
         match parse_identifier(input) {
             Ok(name) => {
-                // Если сразу после идентификатора идёт вызов/скобка, считаем, что переменной нет
+                // Если сразу после идентификатора идёт вызов/скобка, считаем,
+                // что переменной нет. Ключевые слова следующей ветки или тела
+                // parse_identifier не принимает
                 let mut after = *input;
                 skip_ws_and_comments(&mut after).ok();
                 if after.starts_with('(') {
@@ -553,54 +695,129 @@ pub fn parse_try_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement>
         }
     };
 
-    let mut catch_body = Vec::new();
-    loop {
+    let class = if var.is_some() && char_(':').parse_next(input).is_ok() {
         skip_ws_and_comments(input)?;
+        Some(parse_class_path(input)?)
+    } else {
+        None
+    };
+
+    // Ветка заканчивается последним оператором тела, а не пробелами перед
+    // следующей веткой
+    let header_end = offset_from_remaining(input.len());
+    let body = parse_try_block(input)?;
+    let end = body.last().map_or(header_end, |stmt| stmt.span().end);
+    Ok(CatchClause {
+        var,
+        class,
+        body,
+        span: Span::new(offset_from_remaining(start), end),
+    })
+}
 
+/// Класс в ветке `перехват`: имя или путь через точку (`модуль.Класс`)
+fn parse_class_path<'a>(input: &mut Input<'a>) -> PResult<'a, Expression> {
+    let start = input.len();
+    let name = parse_identifier(input)
+        .map_err(|_| cut_error("После ':' ожидается имя класса исключения", start))?;
+    let mut class =
+        Expression::Identifier(name.to_string(), span_from_remaining(start, input.len()));
+    loop {
         let checkpoint = input.checkpoint();
-        if keyword_end(input).is_ok() {
-            input.reset(&checkpoint);
+        if char_('.').parse_next(input).is_err() {
             break;
         }
-        input.reset(&checkpoint);
-
-        match parse_statement(input) {
-            Ok(stmt) => catch_body.push(stmt),
-            Err(err) => {
-                input.reset(&checkpoint);
-                if keyword_end(input).is_ok() {
-                    input.reset(&checkpoint);
-                    break;
-                }
-                return Err(err);
-            }
-        }
+        let Ok(field) = parse_identifier(input) else {
+            input.reset(&checkpoint);
+            break;
+        };
+        class = Expression::FieldAccess(Box::new(FieldAccessExpr {
+            object: class,
+            field: field.to_string(),
+            span: span_from_remaining(start, input.len()),
+        }));
     }
+    skip_ws_and_comments(input)?;
+    Ok(class)
+}
 
+fn cut_error(
+    message: &str,
+    position: usize,
+) -> winnow::error::ErrMode<crate::ponos::parser::error::PonosParseError> {
+    use crate::ponos::parser::error::{ParseErrorKind, PonosParseError};
+    winnow::error::ErrMode::Cut(PonosParseError::new(
+        ParseErrorKind::Custom(message.to_string()),
+        span_from_remaining(position, position),
+    ))
+}
+
+/// Конец простого оператора: `;`, перевод строки после его последнего токена,
+/// конец блока или конец файла. `statement` — ввод с начала оператора,
+/// `last_end` — смещение конца последнего токена оператора
+fn statement_end<'a>(
+    input: &mut Input<'a>,
+    statement: Input<'a>,
+    last_end: usize,
+) -> PResult<'a, ()> {
     skip_ws_and_comments(input)?;
-    keyword_end(input)?;
+    if char_(';').parse_next(input).is_ok() {
+        return Ok(());
+    }
 
-    let end = input.len();
-    let span = span_from_remaining(start, end);
+    // Пропущенное после последнего токена: пробелы и комментарии
+    let consumed = &statement[..statement.len() - input.len()];
+    let gap_start = last_end.saturating_sub(offset_from_remaining(statement.len()));
+    let after_newline = consumed
+        .get(gap_start..)
+        .is_some_and(|gap| gap.contains('\n'));
+    if after_newline || input.is_empty() || at_block_end(input) {
+        return Ok(());
+    }
+    char_(';').parse_next(input).map(|_| ())
+}
 
-    Ok(Statement::Try(Box::new(TryStatement {
-        try_body,
-        catch_var,
-        catch_body,
-        span,
-    })))
+// Следующее слово закрывает блок или начинает его следующую ветку
+fn at_block_end(input: &mut Input<'_>) -> bool {
+    let checkpoint = input.checkpoint();
+    let found = keyword_end(input).is_ok()
+        || keyword_else(input).is_ok()
+        || keyword_catch(input).is_ok()
+        || keyword_finally(input).is_ok();
+    input.reset(&checkpoint);
+    found
+}
+
+/// Операторы ветки попытки до `перехват`, `наконец` или `конец`
+fn parse_try_block<'a>(input: &mut Input<'a>) -> PResult<'a, Vec<Statement>> {
+    let mut body = Vec::new();
+    loop {
+        skip_ws_and_comments(input)?;
+
+        let checkpoint = input.checkpoint();
+        let at_clause_end = keyword_catch(input).is_ok()
+            || keyword_finally(input).is_ok()
+            || keyword_end(input).is_ok();
+        input.reset(&checkpoint);
+        if at_clause_end {
+            return Ok(body);
+        }
+
+        body.push(parse_statement(input)?);
+    }
 }
 
 /// Парсит return оператор: возврат [expr] ;
 pub fn parse_return_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let statement = *input;
     let start = input.len();
 
     keyword_return(input)?;
-    skip_ws_and_comments(input)?;
+    let keyword_end = offset_from_remaining(input.len());
 
-    // Пустой возврат: сразу точка с запятой
+    // Пустой возврат: сразу конец оператора
     let checkpoint = input.checkpoint();
-    if char_(';').parse_next(input).is_ok() {
+    if statement_end(input, statement, keyword_end).is_ok() {
         let end = input.len();
         let span = span_from_remaining(start, end);
         return Ok(Statement::Return(ReturnStatement { value: None, span }));
@@ -608,9 +825,10 @@ pub fn parse_return_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statemen
 
     // Возврат с выражением
     input.reset(&checkpoint);
-    let value = Some(parse_expression(input)?);
     skip_ws_and_comments(input)?;
-    char_(';').parse_next(input)?;
+    let value = parse_expression(input)?;
+    statement_end(input, statement, value.span().end)?;
+    let value = Some(value);
 
     let end = input.len();
     let span = span_from_remaining(start, end);
@@ -620,6 +838,7 @@ pub fn parse_return_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statemen
 
 /// Парсит присваивание: (identifier | expr.field) = expression ;
 pub fn parse_assignment_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let statement = *input;
     let start = input.len();
 
     // Парсим левую часть - может быть identifier или field access
@@ -630,9 +849,7 @@ pub fn parse_assignment_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Stat
     skip_ws_and_comments(input)?;
 
     let value = parse_expression(input)?;
-    skip_ws_and_comments(input)?;
-
-    char_(';').parse_next(input)?;
+    statement_end(input, statement, value.span().end)?;
 
     let end = input.len();
     let span = span_from_remaining(start, end);
@@ -689,65 +906,72 @@ pub fn parse_assignment_or_expression_statement<'a>(
 
 /// Парсит выражение как оператор
 pub fn parse_expression_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
+    let statement = *input;
     let expr = parse_expression(input)?;
-    skip_ws_and_comments(input)?;
-    char_(';').parse_next(input)?;
+    statement_end(input, statement, expr.span().end)?;
     Ok(Statement::Expression(expr))
 }
 
-/// Парсит параметр функции
-fn parse_parameter<'a>(input: &mut Input<'a>) -> PResult<'a, Parameter> {
-    let start = input.len();
-    let name = parse_identifier(input)?.to_string();
-
-    skip_ws_and_comments(input)?;
-
-    // Опциональная аннотация типа
-    let type_annotation = if char_(':').parse_next(input).is_ok() {
-        skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
-    } else {
-        None
-    };
-
-    let end = input.len();
-    let span = span_from_remaining(start, end);
-
-    Ok(Parameter {
-        name,
-        type_annotation,
-        span,
-    })
-}
-
 // Расширенные конструкции грамматики
 
-/// Парсит импорт: использовать "path" [как псевдоним] ;
+/// Парсит импорт: [экспорт] использовать "path" [как псевдоним] [показать а, б | скрыть в] ;
 pub fn parse_import_statement<'a>(input: &mut Input<'a>) -> PResult<'a, Statement> {
-    use crate::ponos::parser::lexer::{keyword_as, keyword_use, parse_string};
+    use crate::ponos::parser::lexer::{
+        keyword_as, keyword_hide, keyword_show, keyword_use, parse_string,
+    };
+    let statement = *input;
     let start = input.len();
 
+    let is_exported = if keyword_export(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        true
+    } else {
+        false
+    };
     keyword_use(input)?;
     skip_ws_and_comments(input)?;
 
     let path = parse_string(input)?;
+    let mut last_end = offset_from_remaining(input.len());
     skip_ws_and_comments(input)?;
 
     // Опциональный модификатор "как псевдоним"
     let alias = if keyword_as(input).is_ok() {
         skip_ws_and_comments(input)?;
-        Some(parse_identifier(input)?.to_string())
+        let alias = parse_identifier(input)?.to_string();
+        last_end = offset_from_remaining(input.len());
+        Some(alias)
     } else {
         None
     };
-
     skip_ws_and_comments(input)?;
-    char_(';').parse_next(input)?;
+
+    // Опциональный список видимых или скрытых символов
+    let filter = if keyword_show(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        ImportFilter::Show(parse_identifier_list(input)?)
+    } else if keyword_hide(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        ImportFilter::Hide(parse_identifier_list(input)?)
+    } else {
+        ImportFilter::All
+    };
+    if !matches!(filter, ImportFilter::All) {
+        last_end = offset_from_remaining(input.len());
+    }
+
+    statement_end(input, statement, last_end)?;
 
     let end = input.len();
     let span = span_from_remaining(start, end);
 
-    Ok(Statement::Import(ImportStatement { path, alias, span }))
+    Ok(Statement::Import(ImportStatement {
+        path,
+        alias,
+        filter,
+        is_exported,
+        span,
+    }))
 }
 
 /// Вспомогательная функция для парсинга списка идентификаторов через запятую
@@ -766,7 +990,7 @@ pub fn parse_class_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, Stateme
     use crate::ponos::parser::lexer::{keyword_class, keyword_extends, keyword_implements};
     let start = input.len();
 
-    // TODO: parse annotations
+    // Аннотации заполняет parse_annotated_declaration
     let annotations = Vec::new();
 
     // Опциональное ключевое слово экспорт
@@ -836,6 +1060,13 @@ fn parse_class_members<'a>(input: &mut Input<'a>) -> PResult<'a, Vec<ClassMember
             break;
         }
 
+        // Статические члены
+        if crate::ponos::parser::lexer::keyword_static(input).is_ok() {
+            skip_ws_and_comments(input)?;
+            members.push(parse_static_member(input)?);
+            continue;
+        }
+
         // Пытаемся спарсить конструктор
         let saved = input.checkpoint();
         if let Ok(constructor) = parse_constructor_declaration(input) {
@@ -852,13 +1083,16 @@ fn parse_class_members<'a>(input: &mut Input<'a>) -> PResult<'a, Vec<ClassMember
         }
         input.reset(&saved);
 
-        // Иначе это поле
+        // Иначе это поле; `пер` перед именем поля допустимо и ничего не меняет
+        if keyword_var(input).is_ok() {
+            skip_ws_and_comments(input)?;
+        }
         let field_name = parse_identifier(input)?.to_string();
         skip_ws_and_comments(input)?;
 
         let type_annotation = if char_(':').parse_next(input).is_ok() {
             skip_ws_and_comments(input)?;
-            Some(parse_identifier(input)?.to_string())
+            Some(parse_type_name(input)?.to_string())
         } else {
             None
         };
@@ -880,6 +1114,45 @@ fn parse_class_members<'a>(input: &mut Input<'a>) -> PResult<'a, Vec<ClassMember
     Ok(members)
 }
 
+/// Парсит член после `статический`: метод или поле с необязательным значением
+fn parse_static_member<'a>(input: &mut Input<'a>) -> PResult<'a, ClassMember> {
+    let saved = input.checkpoint();
+    if keyword_func(input).is_ok() {
+        input.reset(&saved);
+        return match parse_function_declaration(input)? {
+            Statement::FuncDecl(func) => Ok(ClassMember::StaticMethod(func)),
+            _ => unreachable!("parse_function_declaration возвращает FuncDecl"),
+        };
+    }
+
+    let name = parse_identifier(input)?.to_string();
+    skip_ws_and_comments(input)?;
+
+    let type_annotation = if char_(':').parse_next(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        Some(parse_type_name(input)?.to_string())
+    } else {
+        None
+    };
+    skip_ws_and_comments(input)?;
+
+    let initializer = if char_('=').parse_next(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        let value = parse_expression(input)?;
+        skip_ws_and_comments(input)?;
+        Some(value)
+    } else {
+        None
+    };
+    char_(';').parse_next(input)?;
+
+    Ok(ClassMember::StaticField {
+        name,
+        type_annotation,
+        initializer,
+    })
+}
+
 /// Парсит конструктор: конструктор (params) statements конец
 pub fn parse_constructor_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, ConstructorDecl> {
     use crate::ponos::parser::lexer::keyword_constructor;
@@ -895,12 +1168,7 @@ pub fn parse_constructor_declaration<'a>(input: &mut Input<'a>) -> PResult<'a, C
     let params = if char_(')').parse_next(input).is_ok() {
         Vec::new()
     } else {
-        let params: Vec<Parameter> = separated(
-            0..,
-            parse_parameter,
-            (skip_ws_and_comments, char_(','), skip_ws_and_comments),
-        )
-        .parse_next(input)?;
+        let params = parse_parameters(input)?;
 
         skip_ws_and_comments(input)?;
         char_(')').parse_next(input)?;
@@ -1011,12 +1279,7 @@ fn parse_method_signature<'a>(input: &mut Input<'a>) -> PResult<'a, MethodSignat
     let params = if char_(')').parse_next(input).is_ok() {
         Vec::new()
     } else {
-        let params: Vec<Parameter> = separated(
-            0..,
-            parse_parameter,
-            (skip_ws_and_comments, char_(','), skip_ws_and_comments),
-        )
-        .parse_next(input)?;
+        let params = parse_parameters(input)?;
 
         skip_ws_and_comments(input)?;
         char_(')').parse_next(input)?;
@@ -1024,13 +1287,28 @@ fn parse_method_signature<'a>(input: &mut Input<'a>) -> PResult<'a, MethodSignat
         params
     };
 
+    skip_ws_and_comments(input)?;
+
+    // Опциональный тип возврата: ": тип"
+    let return_type = if char_(':').parse_next(input).is_ok() {
+        skip_ws_and_comments(input)?;
+        Some(parse_type_name(input)?.to_string())
+    } else {
+        None
+    };
+
     skip_ws_and_comments(input)?;
     char_(';').parse_next(input)?;
 
     let end = input.len();
     let span = span_from_remaining(start, end);
 
-    Ok(MethodSignature { name, params, span })
+    Ok(MethodSignature {
+        name,
+        params,
+        return_type,
+        span,
+    })
 }
 
 /// Парсит объявление аннотации: [экспорт] аннотация identifier statements конец
@@ -1116,6 +1394,7 @@ mod tests {
             Statement::FuncDecl(func) => {
                 assert_eq!(func.name, "foo");
                 assert_eq!(func.params.len(), 2);
+                assert_eq!(func.return_type, None);
                 assert_eq!(func.body.len(), 1);
             }
             _ => panic!("Expected FuncDecl"),
@@ -1143,6 +1422,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_function_with_return_type() {
+        let mut input = "функ длина_имени(имя: строка): число возврат длина(имя); конец";
+        match parse_statement(&mut input).unwrap() {
+            Statement::FuncDecl(func) => {
+                assert_eq!(func.return_type, Some("число".to_string()));
+                assert_eq!(func.body.len(), 1);
+            }
+            _ => panic!("Expected FuncDecl"),
+        }
+    }
+
     #[test]
     fn test_parse_function_with_mixed_params() {
         let mut input = "функ смешанная(без_типа, с_типом: число) конец";
@@ -1164,6 +1455,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_function_with_default_params() {
+        let mut input = "функ ф(а, б: число = 10, в = а + 1) конец";
+        match parse_statement(&mut input).unwrap() {
+            Statement::FuncDecl(func) => {
+                assert!(func.params[0].default.is_none());
+                assert!(matches!(
+                    func.params[1].default,
+                    Some(Expression::Integer(10, _))
+                ));
+                assert_eq!(func.params[1].type_annotation, Some("число".to_string()));
+                assert!(matches!(
+                    func.params[2].default,
+                    Some(Expression::Binary(_))
+                ));
+            }
+            _ => panic!("Expected FuncDecl"),
+        }
+
+        let mut input = "функ ф(а = 1, б) конец";
+        let err = format!("{:?}", parse_statement(&mut input).unwrap_err());
+        assert!(err.contains("Обязательный параметр 'б'"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_rest_params_and_spread_arguments() {
+        let mut input = "функ ф(а, ...остальные) возврат ф(...остальные, а); конец";
+        match parse_statement(&mut input).unwrap() {
+            Statement::FuncDecl(func) => {
+                assert!(!func.params[0].is_rest);
+                assert!(func.params[1].is_rest);
+                assert_eq!(func.params[1].name, "остальные");
+                match &func.body[0] {
+                    Statement::Return(ReturnStatement {
+                        value: Some(Expression::Call(call)),
+                        ..
+                    }) => {
+                        assert!(matches!(call.arguments[0], Expression::Spread(..)));
+                        assert!(matches!(call.arguments[1], Expression::Identifier(..)));
+                    }
+                    other => panic!("Expected return of call, got {:?}", other),
+                }
+            }
+            _ => panic!("Expected FuncDecl"),
+        }
+
+        let mut input = "функ ф(...а, б) конец";
+        let err = format!("{:?}", parse_statement(&mut input).unwrap_err());
+        assert!(err.contains("должен быть последним"), "{}", err);
+    }
+
     #[test]
     fn test_parse_if() {
         let mut input = "если x > 0 возврат x; конец";
@@ -1225,6 +1567,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_statement_spanning_lines() {
+        // Переводы строк внутри скобок и перед '.' оператор не завершают
+        let mut input = "пер итог = построить(\n    [1,\n     2],\n    {\"а\": 1}\n)\n    .обработать()\n    .результат;\nдальше();";
+        let stmt = parse_statement(&mut input).unwrap();
+        match stmt {
+            Statement::VarDecl(decl) => match decl.initializer {
+                Some(Expression::FieldAccess(access)) => assert_eq!(access.field, "результат"),
+                other => panic!("Expected field access, got {:?}", other),
+            },
+            other => panic!("Expected VarDecl, got {:?}", other),
+        }
+        assert_eq!(input.trim_start(), "дальше();");
+    }
+
+    #[test]
+    fn test_newline_ends_statement() {
+        let mut input = "пер а = 1\nа = а + 1 // комментарий\nвывести(а)";
+        assert!(matches!(
+            parse_statement(&mut input),
+            Ok(Statement::VarDecl(_))
+        ));
+        assert!(matches!(
+            parse_statement(&mut input),
+            Ok(Statement::Assignment(_))
+        ));
+        assert!(matches!(
+            parse_statement(&mut input),
+            Ok(Statement::Expression(_))
+        ));
+        assert_eq!(input, "");
+
+        // Оператор перед концом блока можно не завершать
+        let mut input = "если а возврат конец";
+        assert!(matches!(parse_statement(&mut input), Ok(Statement::If(_))));
+
+        // Два оператора в одной строке без ';' — ошибка
+        let mut input = "пер а = 1 вывести(а)";
+        assert!(parse_statement(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_newline_before_bracket_starts_statement() {
+        let mut input = "пер а = б\n(в)";
+        let Ok(Statement::VarDecl(decl)) = parse_statement(&mut input) else {
+            panic!("Expected VarDecl");
+        };
+        assert!(matches!(decl.initializer, Some(Expression::Identifier(..))));
+        assert_eq!(input.trim_start(), "(в)");
+
+        // Бинарный оператор в начале строки продолжает выражение
+        let mut input = "пер а = б\n    + в";
+        let Ok(Statement::VarDecl(decl)) = parse_statement(&mut input) else {
+            panic!("Expected VarDecl");
+        };
+        assert!(matches!(decl.initializer, Some(Expression::Binary(..))));
+    }
+
     #[test]
     fn test_parse_assignment() {
         let mut input = "x = 42;";
@@ -1236,7 +1636,7 @@ mod tests {
                     _ => panic!("Expected identifier target"),
                 }
                 match assign.value {
-                    Expression::Number(n, _) => assert_eq!(n, 42.0),
+                    Expression::Integer(n, _) => assert_eq!(n, 42),
                     _ => panic!("Expected number"),
                 }
             }
@@ -1363,6 +1763,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_class_with_static_members() {
+        let mut input =
+            "класс Счётчик статический всего: число = 0; статический функ сбросить() конец конец";
+        let stmt = parse_statement(&mut input).unwrap();
+        match stmt {
+            Statement::ClassDecl(class) => {
+                assert_eq!(class.members.len(), 2);
+                match &class.members[0] {
+                    ClassMember::StaticField {
+                        name,
+                        type_annotation,
+                        initializer,
+                    } => {
+                        assert_eq!(name, "всего");
+                        assert_eq!(type_annotation.as_deref(), Some("число"));
+                        assert!(matches!(initializer, Some(Expression::Integer(0, _))));
+                    }
+                    other => panic!("Expected StaticField, got {:?}", other),
+                }
+                assert!(
+                    matches!(&class.members[1], ClassMember::StaticMethod(method) if method.name == "сбросить")
+                );
+            }
+            _ => panic!("Expected ClassDecl"),
+        }
+    }
+
     #[test]
     fn test_parse_interface() {
         let mut input = "интерфейс Печатаемый функ печать(); конец";
@@ -1387,11 +1815,49 @@ mod tests {
                 assert_eq!(interface.methods.len(), 1);
                 assert_eq!(interface.methods[0].name, "равно");
                 assert_eq!(interface.methods[0].params.len(), 1);
+                assert_eq!(interface.methods[0].return_type, None);
             }
             _ => panic!("Expected InterfaceDecl"),
         }
     }
 
+    #[test]
+    fn test_parse_interface_with_return_type() {
+        let mut input = "интерфейс Фигура функ площадь(): число; конец";
+        match parse_statement(&mut input).unwrap() {
+            Statement::InterfaceDecl(interface) => {
+                assert_eq!(interface.methods[0].return_type, Some("число".to_string()));
+            }
+            _ => panic!("Expected InterfaceDecl"),
+        }
+    }
+
+    #[test]
+    fn test_parse_annotated_function() {
+        let mut input = "@Тест\n@Таймаут(100, повторы = 2)\nэкспорт функ тест_сложения() конец";
+        let stmt = parse_statement(&mut input).unwrap();
+        match stmt {
+            Statement::FuncDecl(func) => {
+                assert_eq!(func.name, "тест_сложения");
+                assert!(func.is_exported);
+                let names: Vec<_> = func.annotations.iter().map(|a| a.name.as_str()).collect();
+                assert_eq!(names, ["Тест", "Таймаут"]);
+                assert!(func.annotations[0].args.is_empty());
+                assert!(matches!(
+                    func.annotations[1].args.as_slice(),
+                    [
+                        AnnotationArgument::Positional(Expression::Integer(..)),
+                        AnnotationArgument::Named { name, .. },
+                    ] if name == "повторы"
+                ));
+            }
+            _ => panic!("Expected FuncDecl"),
+        }
+
+        let mut input = "@Тест пер x = 1;";
+        assert!(parse_statement(&mut input).is_err());
+    }
+
     #[test]
     fn test_parse_annotation() {
         let mut input = "аннотация Тест конец";
@@ -1431,6 +1897,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_import_show_and_hide() {
+        let mut input = r#"использовать "модуль" как М показать а, б;"#;
+        match parse_statement(&mut input).unwrap() {
+            Statement::Import(import) => {
+                assert_eq!(import.alias, Some("М".to_string()));
+                assert_eq!(
+                    import.filter,
+                    ImportFilter::Show(vec!["а".to_string(), "б".to_string()])
+                );
+            }
+            _ => panic!("Expected Import"),
+        }
+
+        let mut input = r#"использовать "модуль" скрыть в;"#;
+        match parse_statement(&mut input).unwrap() {
+            Statement::Import(import) => {
+                assert!(import.alias.is_none());
+                assert_eq!(import.filter, ImportFilter::Hide(vec!["в".to_string()]));
+                assert!(!import.is_exported);
+            }
+            _ => panic!("Expected Import"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reexport() {
+        let mut input = r#"экспорт использовать "модуль" показать а;"#;
+        match parse_statement(&mut input).unwrap() {
+            Statement::Import(import) => {
+                assert!(import.is_exported);
+                assert_eq!(import.path, "модуль");
+                assert_eq!(import.filter, ImportFilter::Show(vec!["а".to_string()]));
+            }
+            _ => panic!("Expected Import"),
+        }
+    }
+
     #[test]
     fn test_parse_field_access_assignment() {
         let mut input = "obj.поле = 42;";
@@ -1455,7 +1959,7 @@ mod tests {
         match stmt {
             Statement::VarDecl(decl) => {
                 assert_eq!(decl.name, "x");
-                assert_eq!(decl.is_exported, true);
+                assert!(decl.is_exported);
             }
             _ => panic!("Expected VarDecl"),
         }
@@ -1468,10 +1972,36 @@ mod tests {
         match stmt {
             Statement::VarDecl(decl) => {
                 assert_eq!(decl.name, "x");
-                assert_eq!(decl.is_exported, false);
+                assert!(!decl.is_exported);
+            }
+            _ => panic!("Expected VarDecl"),
+        }
+    }
+
+    #[test]
+    fn test_parse_const_declarations() {
+        let mut input = "экспорт конст ПИ: число = 3.14;";
+        match parse_statement(&mut input).unwrap() {
+            Statement::VarDecl(decl) => {
+                assert_eq!(decl.name, "ПИ");
+                assert!(decl.is_const);
+                assert!(decl.is_exported);
+                assert_eq!(decl.type_annotation.as_deref(), Some("число"));
             }
             _ => panic!("Expected VarDecl"),
         }
+
+        let mut input = "пер x = 1;";
+        match parse_statement(&mut input).unwrap() {
+            Statement::VarDecl(decl) => assert!(!decl.is_const),
+            _ => panic!("Expected VarDecl"),
+        }
+
+        let mut input = "конст x;";
+        assert!(matches!(
+            parse_statement(&mut input),
+            Err(winnow::error::ErrMode::Cut(_))
+        ));
     }
 
     #[test]
@@ -1481,7 +2011,7 @@ mod tests {
         match stmt {
             Statement::FuncDecl(func) => {
                 assert_eq!(func.name, "foo");
-                assert_eq!(func.is_exported, true);
+                assert!(func.is_exported);
             }
             _ => panic!("Expected FuncDecl"),
         }
@@ -1494,7 +2024,7 @@ mod tests {
         match stmt {
             Statement::FuncDecl(func) => {
                 assert_eq!(func.name, "foo");
-                assert_eq!(func.is_exported, false);
+                assert!(!func.is_exported);
             }
             _ => panic!("Expected FuncDecl"),
         }
@@ -1507,7 +2037,7 @@ mod tests {
         match stmt {
             Statement::ClassDecl(class) => {
                 assert_eq!(class.name, "Точка");
-                assert_eq!(class.is_exported, true);
+                assert!(class.is_exported);
             }
             _ => panic!("Expected ClassDecl"),
         }
@@ -1520,7 +2050,7 @@ mod tests {
         match stmt {
             Statement::ClassDecl(class) => {
                 assert_eq!(class.name, "Точка");
-                assert_eq!(class.is_exported, false);
+                assert!(!class.is_exported);
             }
             _ => panic!("Expected ClassDecl"),
         }
@@ -1533,7 +2063,7 @@ mod tests {
         match stmt {
             Statement::InterfaceDecl(interface) => {
                 assert_eq!(interface.name, "Печатаемый");
-                assert_eq!(interface.is_exported, true);
+                assert!(interface.is_exported);
             }
             _ => panic!("Expected InterfaceDecl"),
         }
@@ -1546,7 +2076,7 @@ mod tests {
         match stmt {
             Statement::InterfaceDecl(interface) => {
                 assert_eq!(interface.name, "Печатаемый");
-                assert_eq!(interface.is_exported, false);
+                assert!(!interface.is_exported);
             }
             _ => panic!("Expected InterfaceDecl"),
         }
@@ -1559,7 +2089,7 @@ mod tests {
         match stmt {
             Statement::AnnotationDecl(annotation) => {
                 assert_eq!(annotation.name, "Тест");
-                assert_eq!(annotation.is_exported, true);
+                assert!(annotation.is_exported);
             }
             _ => panic!("Expected AnnotationDecl"),
         }
@@ -1572,7 +2102,7 @@ mod tests {
         match stmt {
             Statement::AnnotationDecl(annotation) => {
                 assert_eq!(annotation.name, "Тест");
-                assert_eq!(annotation.is_exported, false);
+                assert!(!annotation.is_exported);
             }
             _ => panic!("Expected AnnotationDecl"),
         }
@@ -1609,8 +2139,10 @@ mod tests {
         match stmt {
             Statement::Try(try_stmt) => {
                 assert_eq!(try_stmt.try_body.len(), 1);
-                assert_eq!(try_stmt.catch_body.len(), 1);
-                assert_eq!(try_stmt.catch_var.as_deref(), Some("err"));
+                assert_eq!(try_stmt.catches.len(), 1);
+                assert_eq!(try_stmt.catches[0].body.len(), 1);
+                assert_eq!(try_stmt.catches[0].var.as_deref(), Some("err"));
+                assert!(try_stmt.finally_body.is_none());
             }
             other => panic!("Expected Try statement, got {:?}", other),
         }
@@ -1628,8 +2160,121 @@ mod tests {
         let stmt = parse_statement(&mut input).expect("try-catch without var parsed");
         match stmt {
             Statement::Try(try_stmt) => {
-                assert!(try_stmt.catch_var.is_none());
-                assert_eq!(try_stmt.catch_body.len(), 1);
+                assert!(try_stmt.catches[0].var.is_none());
+                assert_eq!(try_stmt.catches[0].body.len(), 1);
+            }
+            other => panic!("Expected Try statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_try_finally() {
+        let mut input: Input = r#"
+попытка
+    открыть();
+перехват е
+    вывести(е);
+наконец
+    закрыть();
+    вывести("готово");
+конец
+"#;
+        let stmt = parse_statement(&mut input).expect("try-catch-finally parsed");
+        match stmt {
+            Statement::Try(try_stmt) => {
+                assert_eq!(try_stmt.catches[0].var.as_deref(), Some("е"));
+                assert_eq!(try_stmt.finally_body.map(|body| body.len()), Some(2));
+            }
+            other => panic!("Expected Try statement, got {:?}", other),
+        }
+
+        let mut input: Input = "попытка открыть(); наконец закрыть(); конец";
+        match parse_statement(&mut input).expect("try-finally parsed") {
+            Statement::Try(try_stmt) => {
+                assert!(try_stmt.catches.is_empty());
+                assert_eq!(try_stmt.finally_body.map(|body| body.len()), Some(1));
+            }
+            other => panic!("Expected Try statement, got {:?}", other),
+        }
+
+        let mut input: Input = "попытка открыть(); конец";
+        assert!(parse_statement(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_parse_typed_catch_clauses() {
+        let mut input: Input = r#"
+попытка
+    открыть();
+перехват е: ОшибкаИО
+    вывести(е.сообщение);
+перехват е: ошибки.ОшибкаРазбора
+    вывести(е);
+перехват
+    вывести("другое");
+конец
+"#;
+        let stmt = parse_statement(&mut input).expect("typed catch clauses parsed");
+        match stmt {
+            Statement::Try(try_stmt) => {
+                let catches = &try_stmt.catches;
+                assert_eq!(catches.len(), 3);
+                assert!(matches!(
+                    &catches[0].class,
+                    Some(Expression::Identifier(name, _)) if name == "ОшибкаИО"
+                ));
+                match &catches[1].class {
+                    Some(Expression::FieldAccess(access)) => {
+                        assert_eq!(access.field, "ОшибкаРазбора")
+                    }
+                    other => panic!("Expected qualified class, got {:?}", other),
+                }
+                assert!(catches[2].var.is_none() && catches[2].class.is_none());
+            }
+            other => panic!("Expected Try statement, got {:?}", other),
+        }
+
+        let mut input: Input =
+            "попытка открыть(); перехват е закрыть(); перехват е: ОшибкаИО закрыть(); конец";
+        assert!(parse_statement(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_identifiers_starting_with_keywords() {
+        for (source, expected) in [
+            ("пер переменная = 1;", "переменная"),
+            ("пер конечный = 1;", "конечный"),
+            ("пер иначе_ли = 1;", "иначе_ли"),
+            ("пер истинный = 1;", "истинный"),
+            ("пер функция = 1;", "функция"),
+            ("пер пока2 = 1;", "пока2"),
+        ] {
+            let mut input: Input = source;
+            match parse_statement(&mut input) {
+                Ok(Statement::VarDecl(decl)) => assert_eq!(decl.name, expected),
+                other => panic!("{}: expected VarDecl, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_words_are_not_names() {
+        for source in [
+            "пер конец = 1;",
+            "функ если() конец",
+            "функ ф(пока) конец",
+            "класс это конец",
+        ] {
+            let mut input: Input = source;
+            assert!(parse_statement(&mut input).is_err(), "{}", source);
+        }
+
+        // Без переменной перехвата ключевое слово начинает тело ветки
+        let mut input: Input = "попытка открыть(); перехват исключение \"снова\"; конец";
+        match parse_statement(&mut input) {
+            Ok(Statement::Try(try_stmt)) => {
+                assert!(try_stmt.catches[0].var.is_none());
+                assert!(matches!(try_stmt.catches[0].body[0], Statement::Throw(_)));
             }
             other => panic!("Expected Try statement, got {:?}", other),
         }
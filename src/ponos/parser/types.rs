@@ -1,5 +1,6 @@
 use crate::ponos::parser::combinator::{Input, PResult};
 
+#[allow(dead_code)]
 /// Тип аннотации (временная заглушка, будет расширена)
 #[derive(Debug, Clone, PartialEq)]
 pub enum TypeAnnotation {
@@ -7,14 +8,16 @@ pub enum TypeAnnotation {
     // TODO: добавить другие варианты типов
 }
 
+#[allow(dead_code)]
 /// Парсит аннотацию типа
-pub fn parse_type_annotation<'a>(input: &mut Input<'a>) -> PResult<'a, TypeAnnotation> {
+pub fn parse_type_annotation<'a>(_input: &mut Input<'a>) -> PResult<'a, TypeAnnotation> {
     // TODO: : type_expression
     todo!("parse_type_annotation not yet implemented")
 }
 
+#[allow(dead_code)]
 /// Парсит выражение типа
-pub fn parse_type_expression<'a>(input: &mut Input<'a>) -> PResult<'a, TypeAnnotation> {
+pub fn parse_type_expression<'a>(_input: &mut Input<'a>) -> PResult<'a, TypeAnnotation> {
     // TODO: Будет реализовано в Этапе 5
     todo!("parse_type_expression not yet implemented")
 }
@@ -0,0 +1,515 @@
+use crate::ponos::native::threads::Channel;
+use crate::ponos::opcode::{LineTable, OpCode, operand};
+use crate::ponos::value::{
+    BoundMethod, Class, Closure, Function, Instance, Resource, ResourceHandle, Upvalue,
+    UpvalueDescriptor, Value, ValueKey,
+};
+use crate::ponos::vm::VM;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Переносимые значения: глубокая копия значений Ponos, которую можно
+// передать в другой поток ОС. У каждого потока своя VM, поэтому значения
+// копируются целиком, а не разделяются: изменение массива в одном потоке не
+// видно в другом. Функции переносятся вместе с глобальными переменными, к
+// которым обращаются, — их значения копируются на момент переноса
+
+/// Переносимое значение
+#[derive(Debug)]
+pub enum Portable {
+    Int(i64),
+    Number(f64),
+    String(String),
+    Boolean(bool),
+    Nil,
+    Range(Option<f64>, Option<f64>),
+    Array(Vec<Portable>),
+    /// Ключи — скалярные значения: ValueKey держит строки в Rc и не
+    /// передаётся между потоками
+    Dict(Vec<(Portable, Portable)>),
+    StringBuilder(String),
+    Function(PortableFunction),
+    /// Замыкание со значениями захваченных переменных
+    Closure(PortableFunction, Vec<Portable>),
+    /// Нативная функция по имени глобальной переменной, под которым она определена
+    Native(String),
+    /// Встроенный класс ошибки по имени
+    ErrorClass(String),
+    /// Класс по номеру в `Snapshot.classes`
+    Class(usize),
+    /// Экземпляр: класс (`Class` или `ErrorClass`) и поля
+    Instance(Box<Portable>, Fields),
+    BoundMethod(Box<Portable>, Fields, PortableFunction),
+    Channel(Arc<Channel>),
+}
+
+/// Поля экземпляра или статические члены класса по именам
+type Fields = Vec<(String, Portable)>;
+
+/// Функция без привязки к слотам глобальных переменных VM: инструкции
+/// обращаются к глобальным по именам из констант, как до `link_globals`
+#[derive(Debug)]
+pub struct PortableFunction {
+    arity: usize,
+    entry_points: Vec<usize>,
+    variadic: bool,
+    opcodes: Vec<OpCode>,
+    constants: Vec<Portable>,
+    name: String,
+    upvalue_count: usize,
+    upvalue_descriptors: Vec<UpvalueDescriptor>,
+    source_name: Option<String>,
+    lines: LineTable,
+}
+
+#[derive(Debug)]
+struct PortableClass {
+    name: String,
+    methods: Vec<(String, PortableFunction)>,
+    fields: Vec<String>,
+    parent: Option<Portable>,
+    statics: Fields,
+}
+
+/// Переносимые значения вместе со всем, что нужно для их восстановления в
+/// другой VM
+#[derive(Debug)]
+pub struct Snapshot {
+    values: Vec<Portable>,
+    classes: Vec<PortableClass>,
+    globals: Fields,
+    native_modules: Vec<(String, String, Vec<String>)>,
+}
+
+impl Snapshot {
+    /// Скопировать значения из `vm`. Err, если среди них есть то, что нельзя
+    /// перенести в другой поток (ресурс ОС, задача, связанный нативный метод)
+    pub fn export(vm: &mut VM, values: &[Value]) -> Result<Snapshot, String> {
+        let mut exporter = Exporter {
+            vm,
+            classes: Vec::new(),
+            class_ids: HashMap::new(),
+            globals: Vec::new(),
+            exported_globals: HashSet::new(),
+            visiting: Vec::new(),
+        };
+        let values = values
+            .iter()
+            .map(|value| exporter.value(value))
+            .collect::<Result<_, _>>()?;
+        let classes = exporter
+            .classes
+            .into_iter()
+            .map(|class| class.expect("Класс не выгружен до конца"))
+            .collect();
+        Ok(Snapshot {
+            values,
+            classes,
+            globals: exporter.globals,
+            native_modules: vm.native_modules().to_vec(),
+        })
+    }
+
+    /// Восстановить значения в `vm`. Нативные модули, которых в VM ещё нет,
+    /// регистрируются; глобальные переменные определяются, только если VM их
+    /// ещё не знает, чтобы не затереть её собственные
+    pub fn import(self, vm: &mut VM) -> Result<Vec<Value>, String> {
+        for (path, namespace, exports) in &self.native_modules {
+            vm.register_native_module(path, namespace, exports)?;
+        }
+
+        let mut importer = Importer {
+            vm,
+            classes: Vec::with_capacity(self.classes.len()),
+        };
+        // Номер родителя всегда меньше номера наследника
+        let mut statics = Vec::new();
+        for class in self.classes {
+            let parent = match class.parent {
+                Some(parent) => match importer.value(parent)? {
+                    Value::Class(parent) => Some(parent),
+                    _ => unreachable!("Родитель класса — не класс"),
+                },
+                None => None,
+            };
+            let methods = class
+                .methods
+                .into_iter()
+                .map(|(name, method)| Ok((name, importer.function(method)?)))
+                .collect::<Result<_, String>>()?;
+            importer.classes.push(Rc::new(Class {
+                name: class.name,
+                methods,
+                fields: class.fields,
+                parent,
+                statics: RefCell::default(),
+            }));
+            statics.push(class.statics);
+        }
+        // Статические поля могут ссылаться на любые классы снимка
+        for (idx, fields) in statics.into_iter().enumerate() {
+            for (name, value) in fields {
+                let value = importer.value(value)?;
+                importer.classes[idx]
+                    .statics
+                    .borrow_mut()
+                    .insert(name, value);
+            }
+        }
+
+        for (name, value) in self.globals {
+            if !importer.vm.is_global_defined(&name) {
+                let value = importer.value(value)?;
+                importer.vm.set_global(&name, value);
+            }
+        }
+        self.values
+            .into_iter()
+            .map(|value| importer.value(value))
+            .collect()
+    }
+}
+
+struct Exporter<'a> {
+    vm: &'a mut VM,
+    // Выгружаемые классы; None — класс ещё выгружается (рекурсия через его методы)
+    classes: Vec<Option<PortableClass>>,
+    class_ids: HashMap<*const Class, usize>,
+    globals: Fields,
+    exported_globals: HashSet<String>,
+    // Массивы и словари на пути от корня: повтор означает цикл
+    visiting: Vec<*const ()>,
+}
+
+impl Exporter<'_> {
+    fn value(&mut self, value: &Value) -> Result<Portable, String> {
+        Ok(match value {
+            Value::Int(n) => Portable::Int(*n),
+            Value::Number(n) => Portable::Number(*n),
+            Value::String(s) => Portable::String(s.to_string()),
+            Value::Boolean(b) => Portable::Boolean(*b),
+            Value::Nil => Portable::Nil,
+            Value::Range(start, end) => Portable::Range(*start, *end),
+            Value::StringBuilder(buf) => Portable::StringBuilder(buf.borrow().clone()),
+            Value::Array(arr) => {
+                self.enter(Rc::as_ptr(arr) as *const ())?;
+                let items = arr
+                    .borrow()
+                    .iter()
+                    .map(|item| self.value(item))
+                    .collect::<Result<_, _>>()?;
+                self.visiting.pop();
+                Portable::Array(items)
+            }
+            Value::Dict(dict) => {
+                self.enter(Rc::as_ptr(dict) as *const ())?;
+                let entries = dict
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| Ok((self.value(&key.to_value())?, self.value(value)?)))
+                    .collect::<Result<_, String>>()?;
+                self.visiting.pop();
+                Portable::Dict(entries)
+            }
+            Value::Function(function) => Portable::Function(self.function(function)?),
+            Value::Closure(closure) => {
+                let upvalues = closure
+                    .upvalues
+                    .iter()
+                    .map(|upvalue| {
+                        let value = match &*upvalue.borrow() {
+                            Upvalue::Open(idx) => self.vm.stack[*idx].clone(),
+                            Upvalue::Closed(value) => value.clone(),
+                        };
+                        self.value(&value)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Portable::Closure(self.function(&closure.function)?, upvalues)
+            }
+            Value::NativeFunction(id) => match self.vm.native_name(id) {
+                Some(name) => Portable::Native(name),
+                None => return Err("Нативную функцию без имени нельзя передать в поток".into()),
+            },
+            Value::Class(class) => self.class(class)?,
+            Value::Instance(instance) => {
+                let (class, fields) = self.instance(instance)?;
+                Portable::Instance(class, fields)
+            }
+            Value::BoundMethod(bound) => {
+                let (class, fields) = self.instance(&bound.receiver)?;
+                Portable::BoundMethod(class, fields, self.function(&bound.method)?)
+            }
+            Value::Resource(resource) => match &*resource.handle.borrow() {
+                Some(ResourceHandle::Channel(channel)) => Portable::Channel(channel.clone()),
+                _ => {
+                    return Err(format!(
+                        "Значение <{}> нельзя передать в поток",
+                        resource.kind
+                    ));
+                }
+            },
+            Value::BoundBuiltinMethod(_) | Value::BoundNativeMethod(_) => {
+                return Err("Метод встроенного значения нельзя передать в поток".into());
+            }
+            Value::Task(_) => return Err("Сопрограмму нельзя передать в поток".into()),
+        })
+    }
+
+    fn enter(&mut self, ptr: *const ()) -> Result<(), String> {
+        if self.visiting.contains(&ptr) {
+            return Err("Значение с циклическими ссылками нельзя передать в поток".into());
+        }
+        self.visiting.push(ptr);
+        Ok(())
+    }
+
+    fn function(&mut self, function: &Function) -> Result<PortableFunction, String> {
+        let mut constants: Vec<Portable> = function
+            .constants
+            .iter()
+            .map(|constant| self.value(constant))
+            .collect::<Result<_, _>>()?;
+
+        // Слоты глобальных переменных снова становятся именами в константах
+        let mut name_constants: HashMap<u32, u32> = HashMap::new();
+        let mut opcodes = function.opcodes.clone();
+        for opcode in &mut opcodes {
+            let slot = match opcode {
+                OpCode::DefineGlobal(slot)
+                | OpCode::SetGlobal(slot)
+                | OpCode::GetGlobal(slot)
+                | OpCode::AppendGlobal(slot) => slot,
+                _ => continue,
+            };
+            let name = self.vm.global_name(*slot as usize).to_string();
+            *slot = *name_constants.entry(*slot).or_insert_with(|| {
+                constants.push(Portable::String(name.clone()));
+                operand(constants.len() - 1)
+            });
+            self.global(name)?;
+        }
+
+        Ok(PortableFunction {
+            arity: function.arity,
+            entry_points: function.entry_points.clone(),
+            variadic: function.variadic,
+            opcodes,
+            constants,
+            name: function.name.clone(),
+            upvalue_count: function.upvalue_count,
+            upvalue_descriptors: function.upvalue_descriptors.clone(),
+            source_name: function.source_name.as_deref().map(str::to_string),
+            lines: function.lines.clone(),
+        })
+    }
+
+    // Скопировать значение глобальной переменной, к которой обращается
+    // переносимая функция. Нативные функции и встроенные классы в другой VM
+    // уже есть под теми же именами
+    fn global(&mut self, name: String) -> Result<(), String> {
+        if !self.exported_globals.insert(name.clone()) {
+            return Ok(());
+        }
+        let value = match self.vm.get_global(&name) {
+            None | Some(Value::NativeFunction(_)) => return Ok(()),
+            Some(Value::Class(class)) if self.vm.error_class_name(&class).is_some() => {
+                return Ok(());
+            }
+            Some(value) => value,
+        };
+        let value = self.value(&value)?;
+        self.globals.push((name, value));
+        Ok(())
+    }
+
+    fn class(&mut self, class: &Rc<Class>) -> Result<Portable, String> {
+        if let Some(name) = self.vm.error_class_name(class) {
+            return Ok(Portable::ErrorClass(name.to_string()));
+        }
+        if let Some(&id) = self.class_ids.get(&Rc::as_ptr(class)) {
+            return Ok(Portable::Class(id));
+        }
+
+        // Родитель выгружается первым, поэтому его номер меньше
+        let parent = match &class.parent {
+            Some(parent) => Some(self.class(parent)?),
+            None => None,
+        };
+        let id = self.classes.len();
+        self.classes.push(None);
+        self.class_ids.insert(Rc::as_ptr(class), id);
+
+        let methods = class
+            .methods
+            .iter()
+            .map(|(name, method)| Ok((name.clone(), self.function(method)?)))
+            .collect::<Result<_, String>>()?;
+        let statics = class
+            .statics
+            .borrow()
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), self.value(value)?)))
+            .collect::<Result<_, String>>()?;
+        self.classes[id] = Some(PortableClass {
+            name: class.name.clone(),
+            methods,
+            fields: class.fields.clone(),
+            parent,
+            statics,
+        });
+        Ok(Portable::Class(id))
+    }
+
+    fn instance(
+        &mut self,
+        instance: &Rc<RefCell<Instance>>,
+    ) -> Result<(Box<Portable>, Fields), String> {
+        self.enter(Rc::as_ptr(instance) as *const ())?;
+        let instance = instance.borrow();
+        let class = Box::new(self.class(&instance.class)?);
+        let fields = instance
+            .fields
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), self.value(value)?)))
+            .collect::<Result<_, String>>()?;
+        self.visiting.pop();
+        Ok((class, fields))
+    }
+}
+
+struct Importer<'a> {
+    vm: &'a mut VM,
+    classes: Vec<Rc<Class>>,
+}
+
+impl Importer<'_> {
+    fn value(&mut self, value: Portable) -> Result<Value, String> {
+        Ok(match value {
+            Portable::Int(n) => Value::Int(n),
+            Portable::Number(n) => Value::Number(n),
+            Portable::String(s) => Value::String(s.into()),
+            Portable::Boolean(b) => Value::Boolean(b),
+            Portable::Nil => Value::Nil,
+            Portable::Range(start, end) => Value::Range(start, end),
+            Portable::StringBuilder(s) => Value::StringBuilder(Rc::new(RefCell::new(s))),
+            Portable::Array(items) => {
+                let items = items
+                    .into_iter()
+                    .map(|item| self.value(item))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(Rc::new(RefCell::new(items)))
+            }
+            Portable::Dict(entries) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        Ok((ValueKey::from_value(&self.value(key)?)?, self.value(value)?))
+                    })
+                    .collect::<Result<_, String>>()?;
+                Value::Dict(Rc::new(RefCell::new(entries)))
+            }
+            Portable::Function(function) => Value::Function(self.function(function)?),
+            Portable::Closure(function, upvalues) => {
+                let upvalues = upvalues
+                    .into_iter()
+                    .map(|value| Ok(Rc::new(RefCell::new(Upvalue::Closed(self.value(value)?)))))
+                    .collect::<Result<_, String>>()?;
+                Value::Closure(Rc::new(Closure {
+                    function: self.function(function)?,
+                    upvalues,
+                }))
+            }
+            Portable::Native(name) => match self.vm.get_global(&name) {
+                Some(native @ Value::NativeFunction(_)) => native,
+                _ => return Err(format!("Нативная функция '{}' недоступна в потоке", name)),
+            },
+            Portable::ErrorClass(name) => match self.vm.error_class(&name) {
+                Some(class) => Value::Class(class),
+                None => return Err(format!("Неизвестный класс ошибки '{}'", name)),
+            },
+            Portable::Class(id) => Value::Class(self.classes[id].clone()),
+            Portable::Instance(class, fields) => Value::Instance(self.instance(*class, fields)?),
+            Portable::BoundMethod(class, fields, method) => {
+                Value::BoundMethod(Rc::new(BoundMethod {
+                    receiver: self.instance(*class, fields)?,
+                    method: self.function(method)?,
+                }))
+            }
+            Portable::Channel(channel) => Value::Resource(Rc::new(Resource::new(
+                "канал",
+                ResourceHandle::Channel(channel),
+            ))),
+        })
+    }
+
+    fn function(&mut self, function: PortableFunction) -> Result<Rc<Function>, String> {
+        // Вложенные функции из констант связываются при их восстановлении
+        let constants: Vec<Value> = function
+            .constants
+            .into_iter()
+            .map(|constant| self.value(constant))
+            .collect::<Result<_, _>>()?;
+        Ok(Rc::new(Function {
+            arity: function.arity,
+            entry_points: function.entry_points,
+            variadic: function.variadic,
+            opcodes: self.vm.link_opcodes(&function.opcodes, &constants),
+            constants,
+            name: function.name,
+            upvalue_count: function.upvalue_count,
+            upvalue_descriptors: function.upvalue_descriptors,
+            source_name: function.source_name.map(Rc::from),
+            lines: function.lines,
+        }))
+    }
+
+    fn instance(
+        &mut self,
+        class: Portable,
+        fields: Fields,
+    ) -> Result<Rc<RefCell<Instance>>, String> {
+        let Value::Class(class) = self.value(class)? else {
+            unreachable!("Класс экземпляра — не класс");
+        };
+        let fields = fields
+            .into_iter()
+            .map(|(name, value)| Ok((name, self.value(value)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Rc::new(RefCell::new(Instance { class, fields })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ponos::value::Dict;
+
+    #[test]
+    fn test_values_are_copied_between_vms() {
+        let inner = Value::Dict(Rc::new(RefCell::new(Dict::from([(
+            ValueKey::from("ключ"),
+            Value::Number(1.5),
+        )]))));
+        let array = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1), inner])));
+
+        let snapshot = Snapshot::export(&mut VM::new(), std::slice::from_ref(&array)).unwrap();
+        let copy = snapshot.import(&mut VM::new()).unwrap().remove(0);
+        assert!(crate::ponos::value::is_equal(&copy, &array));
+        let (Value::Array(original), Value::Array(copy)) = (&array, &copy) else {
+            unreachable!();
+        };
+        assert!(!Rc::ptr_eq(original, copy));
+    }
+
+    #[test]
+    fn test_cyclic_values_are_rejected() {
+        let array = Rc::new(RefCell::new(vec![Value::Int(1)]));
+        array.borrow_mut().push(Value::Array(array.clone()));
+
+        let result = Snapshot::export(&mut VM::new(), &[Value::Array(array.clone())]);
+        assert!(result.unwrap_err().contains("циклическими"));
+        array.borrow_mut().clear();
+    }
+}
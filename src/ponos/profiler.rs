@@ -0,0 +1,256 @@
+use crate::ponos::value::Function;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Профилировщик считает выполненные инструкции и время по функциям Ponos.
+// Вход в функцию отмечается при создании кадра, выход — по глубине стека
+// вызовов: так учитываются и возвраты, и раскрутка стека исключением.
+// Собственное время функции — время, пока выполнялись её инструкции (вместе
+// с вызванными из них нативными функциями), полное — от входа до выхода
+
+/// Статистика одной функции
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    /// Файл и строка объявления, если известны
+    pub location: Option<String>,
+    pub calls: usize,
+    pub instructions: usize,
+    pub self_time: Duration,
+    pub total_time: Duration,
+}
+
+/// Итоги профилирования, отсортированные по собственному времени
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Profile {
+    pub functions: Vec<FunctionProfile>,
+}
+
+/// Сколько самых горячих функций выводит отчёт
+const REPORT_LIMIT: usize = 20;
+
+impl Profile {
+    /// Таблица самых горячих функций для вывода пользователю
+    pub fn report(&self) -> String {
+        let mut text = String::from("Профиль выполнения:\n");
+        text.push_str(&format!(
+            "{:>10} {:>12} {:>12} {:>12}  функция\n",
+            "вызовов", "инструкций", "собств., мс", "всего, мс"
+        ));
+        for function in self.functions.iter().take(REPORT_LIMIT) {
+            let name = match &function.location {
+                Some(location) => format!("{} ({})", function.name, location),
+                None => function.name.clone(),
+            };
+            text.push_str(&format!(
+                "{:>10} {:>12} {:>12.3} {:>12.3}  {}\n",
+                function.calls,
+                function.instructions,
+                millis(function.self_time),
+                millis(function.total_time),
+                name
+            ));
+        }
+        if self.functions.len() > REPORT_LIMIT {
+            text.push_str(&format!(
+                "... и ещё функций: {}\n",
+                self.functions.len() - REPORT_LIMIT
+            ));
+        }
+        text
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[derive(Debug)]
+struct Entry {
+    // Функция держится, чтобы её адрес не достался другой функции
+    function: Rc<Function>,
+    calls: usize,
+    instructions: usize,
+    self_time: Duration,
+    total_time: Duration,
+    open: usize, // Сколько активных кадров функции (рекурсия)
+}
+
+#[derive(Debug)]
+struct ActiveCall {
+    entry: usize,
+    depth: usize,
+    started: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    entries: Vec<Entry>,
+    index: HashMap<*const Function, usize>,
+    active: Vec<ActiveCall>,
+    // Функция и глубина кадра, чьи инструкции выполняются сейчас
+    current: Option<(*const Function, usize, usize)>,
+    switched_at: Option<Instant>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry(&mut self, function: &Rc<Function>) -> usize {
+        let key = Rc::as_ptr(function);
+        if let Some(&idx) = self.index.get(&key) {
+            return idx;
+        }
+        self.entries.push(Entry {
+            function: function.clone(),
+            calls: 0,
+            instructions: 0,
+            self_time: Duration::ZERO,
+            total_time: Duration::ZERO,
+            open: 0,
+        });
+        self.index.insert(key, self.entries.len() - 1);
+        self.entries.len() - 1
+    }
+
+    /// Отметить вход в функцию; `depth` — глубина стека вызовов с новым кадром
+    pub fn enter(&mut self, function: &Rc<Function>, depth: usize) {
+        self.leave(depth - 1);
+        let entry = self.entry(function);
+        self.entries[entry].calls += 1;
+        self.entries[entry].open += 1;
+        self.active.push(ActiveCall {
+            entry,
+            depth,
+            started: Instant::now(),
+        });
+    }
+
+    /// Закрыть вызовы, кадры которых глубже `depth`
+    pub fn leave(&mut self, depth: usize) {
+        while self.active.last().is_some_and(|call| call.depth > depth) {
+            let call = self.active.pop().unwrap();
+            let entry = &mut self.entries[call.entry];
+            entry.open -= 1;
+            // У рекурсивной функции время считается по самому внешнему вызову
+            if entry.open == 0 {
+                entry.total_time += call.started.elapsed();
+            }
+        }
+    }
+
+    /// Отметить выполнение инструкции функции из кадра глубины `depth`.
+    /// Время замеряется только при смене активного кадра
+    pub fn instruction(&mut self, function: &Rc<Function>, depth: usize) {
+        let key = Rc::as_ptr(function);
+        let entry = match self.current {
+            Some((current, current_depth, entry)) if current == key && current_depth == depth => {
+                entry
+            }
+            _ => {
+                self.leave(depth);
+                self.flush(Instant::now());
+                let entry = self.entry(function);
+                self.current = Some((key, depth, entry));
+                entry
+            }
+        };
+        self.entries[entry].instructions += 1;
+    }
+
+    // Добавить время с последней смены кадра текущей функции
+    fn flush(&mut self, now: Instant) {
+        if let (Some((_, _, entry)), Some(since)) = (self.current, self.switched_at) {
+            self.entries[entry].self_time += now - since;
+        }
+        self.switched_at = Some(now);
+    }
+
+    /// Завершить замеры и собрать отчёт. Функции с одинаковым именем и
+    /// местом объявления (например, повторно скомпилированные) объединяются
+    pub fn finish(mut self) -> Profile {
+        self.flush(Instant::now());
+        self.leave(0);
+
+        let mut functions: Vec<FunctionProfile> = Vec::new();
+        let mut merged: HashMap<(String, Option<String>), usize> = HashMap::new();
+        for entry in self.entries {
+            let name = entry.function.name.clone();
+            let location = location(&entry.function);
+            let idx = *merged
+                .entry((name.clone(), location.clone()))
+                .or_insert_with(|| {
+                    functions.push(FunctionProfile {
+                        name,
+                        location,
+                        calls: 0,
+                        instructions: 0,
+                        self_time: Duration::ZERO,
+                        total_time: Duration::ZERO,
+                    });
+                    functions.len() - 1
+                });
+            let function = &mut functions[idx];
+            function.calls += entry.calls;
+            function.instructions += entry.instructions;
+            function.self_time += entry.self_time;
+            function.total_time += entry.total_time;
+        }
+        functions.sort_by(|a, b| {
+            b.self_time
+                .cmp(&a.self_time)
+                .then(b.instructions.cmp(&a.instructions))
+        });
+        Profile { functions }
+    }
+}
+
+fn location(function: &Function) -> Option<String> {
+    let line = function.lines.entries().first().map(|&(_, line)| line);
+    match (&function.source_name, line) {
+        (Some(file), Some(line)) => Some(format!("{}:{}", file, line)),
+        (Some(file), None) => Some(file.to_string()),
+        (None, Some(line)) => Some(format!("строка {}", line)),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ponos::Ponos;
+
+    #[test]
+    fn test_profile_counts_calls_and_instructions() {
+        let mut ponos = Ponos::new();
+        ponos.enable_profiler();
+        ponos
+            .eval(
+                "функ фиб(н) если н < 2 возврат н; конец возврат фиб(н - 1) + фиб(н - 2); конец\n\
+                 функ пустая() конец\n\
+                 фиб(10); пустая();",
+            )
+            .unwrap();
+        let profile = ponos.take_profile().unwrap();
+
+        let fib = profile.functions.iter().find(|f| f.name == "фиб").unwrap();
+        assert_eq!(fib.calls, 177);
+        assert!(fib.instructions > fib.calls, "{:?}", fib);
+        assert!(fib.total_time >= fib.self_time, "{:?}", fib);
+        let empty = profile
+            .functions
+            .iter()
+            .find(|f| f.name == "пустая")
+            .unwrap();
+        assert_eq!(empty.calls, 1);
+        assert!(
+            profile.report().contains("фиб (строка 1)"),
+            "{}",
+            profile.report()
+        );
+
+        assert_eq!(ponos.take_profile(), None);
+    }
+}
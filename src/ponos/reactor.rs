@@ -0,0 +1,182 @@
+use crate::ponos::value::Value;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Instant;
+
+// Реактор планировщика сопрограмм: фоновые операции ввода-вывода и таймеры.
+// Блокирующая операция выполняется в отдельном потоке ОС, а её результат
+// возвращается в поток VM по каналу. Значения Ponos не переносятся между
+// потоками, поэтому поток возвращает замыкание, которое собирает значение
+// уже в потоке VM
+
+/// Результат фоновой операции: строит значение Ponos в потоке VM
+pub type Completion = Box<dyn FnOnce() -> Result<Value, String> + Send>;
+
+/// Событие, которое разбудит ждущую сопрограмму
+pub enum Event {
+    /// Завершилась фоновая операция с указанным номером
+    Io(usize, Completion),
+    /// Сработал таймер с указанным номером
+    Timer(usize),
+}
+
+pub struct Reactor {
+    sender: Sender<(usize, Completion)>,
+    receiver: Receiver<(usize, Completion)>,
+    next_id: usize,
+    // Сколько фоновых операций ещё не вернули результат
+    in_flight: usize,
+    timers: Vec<(Instant, usize)>,
+}
+
+impl Default for Reactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reactor {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Reactor {
+            sender,
+            receiver,
+            next_id: 0,
+            in_flight: 0,
+            timers: Vec::new(),
+        }
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Запустить `job` в отдельном потоке; вернуть номер операции. Паника
+    /// в `job` завершает операцию ошибкой: иначе реактор ждал бы её вечно
+    pub fn start<F>(&mut self, job: F) -> usize
+    where
+        F: FnOnce() -> Completion + Send + 'static,
+    {
+        let id = self.next_id();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let completion = panic::catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+                let message = crate::ponos::panic_message(payload);
+                Box::new(move || Err(format!("Фоновая операция завершилась паникой: {}", message)))
+            });
+            // Получатель живёт, пока жив реактор; VM мог уже завершиться
+            let _ = sender.send((id, completion));
+        });
+        self.in_flight += 1;
+        id
+    }
+
+    /// Завести таймер на момент `deadline`; вернуть его номер
+    pub fn add_timer(&mut self, deadline: Instant) -> usize {
+        let id = self.next_id();
+        self.timers.push((deadline, id));
+        id
+    }
+
+    /// Нет ни незавершённых операций, ни таймеров
+    pub fn is_idle(&self) -> bool {
+        self.in_flight == 0 && self.timers.is_empty()
+    }
+
+    /// Собрать наступившие события. С `wait` реактор, если событий пока нет,
+    /// ждёт ближайшего из них
+    pub fn poll(&mut self, wait: bool) -> Vec<Event> {
+        let mut events: Vec<Event> = self
+            .receiver
+            .try_iter()
+            .map(|(id, completion)| Event::Io(id, completion))
+            .collect();
+        self.in_flight -= events.len();
+
+        if wait && events.is_empty() && !self.is_idle() {
+            let deadline = self.timers.iter().map(|&(deadline, _)| deadline).min();
+            let received = match deadline {
+                Some(deadline) => self
+                    .receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self
+                    .receiver
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            if let Ok((id, completion)) = received {
+                self.in_flight -= 1;
+                events.push(Event::Io(id, completion));
+            }
+        }
+
+        // Сработавшие таймеры — в порядке их сроков
+        let now = Instant::now();
+        let mut expired: Vec<(Instant, usize)> = Vec::new();
+        self.timers.retain(|&timer| {
+            if timer.0 <= now {
+                expired.push(timer);
+                false
+            } else {
+                true
+            }
+        });
+        expired.sort();
+        events.extend(expired.into_iter().map(|(_, id)| Event::Timer(id)));
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_poll_returns_completions_and_expired_timers() {
+        let mut reactor = Reactor::new();
+        assert!(reactor.is_idle());
+        assert!(reactor.poll(true).is_empty());
+
+        let later = reactor.add_timer(Instant::now() + Duration::from_millis(30));
+        let sooner = reactor.add_timer(Instant::now());
+        let job = reactor.start(|| Box::new(|| Ok(Value::Int(7))) as Completion);
+
+        let mut timers = Vec::new();
+        let mut completed = None;
+        while !reactor.is_idle() {
+            for event in reactor.poll(true) {
+                match event {
+                    Event::Io(id, completion) => completed = Some((id, completion())),
+                    Event::Timer(id) => timers.push(id),
+                }
+            }
+        }
+        assert_eq!(completed, Some((job, Ok(Value::Int(7)))));
+        assert_eq!(timers, vec![sooner, later]);
+    }
+
+    #[test]
+    fn test_panicking_job_completes_with_error() {
+        let mut reactor = Reactor::new();
+        let job = reactor.start(|| -> Completion { panic!("сбой операции") });
+
+        let mut completed = None;
+        while !reactor.is_idle() {
+            for event in reactor.poll(true) {
+                if let Event::Io(id, completion) = event {
+                    completed = Some((id, completion()));
+                }
+            }
+        }
+        assert_eq!(
+            completed,
+            Some((
+                job,
+                Err("Фоновая операция завершилась паникой: сбой операции".to_string())
+            ))
+        );
+    }
+}
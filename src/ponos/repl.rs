@@ -0,0 +1,213 @@
+use crate::ponos::Ponos;
+use crate::ponos::ast::Statement;
+use crate::ponos::opcode;
+use crate::ponos::typechecker::Type;
+use crate::ponos::value::Value;
+use std::fs;
+use std::path::PathBuf;
+
+// Служебные команды REPL: строки, начинающиеся с ':', не выполняются как код,
+// а управляют сессией
+
+pub const HELP: &str = "\
+Команды:
+  :help           эта справка
+  :vars           глобальные переменные и загруженные модули
+  :type выражение тип выражения (выражение не выполняется)
+  :disasm код     байткод функции по имени или фрагмента кода
+  :load файл      выполнить файл в текущей сессии
+  :reload модуль  перечитать изменённый на диске модуль
+  :reset          начать сессию заново
+";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Vars,
+    Type(String),
+    Disasm(String),
+    Load(PathBuf),
+    Reload(String),
+    Reset,
+}
+
+impl Command {
+    /// Разобрать служебную команду. `None` — строка не является командой
+    /// и выполняется как код
+    pub fn parse(line: &str) -> Option<Result<Command, String>> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let required = |command: fn(String) -> Command| {
+            if argument.is_empty() {
+                Err(format!("Команде ':{}' нужен аргумент", name))
+            } else {
+                Ok(command(argument.to_string()))
+            }
+        };
+        Some(match name {
+            "help" => Ok(Command::Help),
+            "vars" => Ok(Command::Vars),
+            "type" => required(Command::Type),
+            "disasm" => required(Command::Disasm),
+            "load" => required(|path| Command::Load(PathBuf::from(path))),
+            "reload" => required(Command::Reload),
+            "reset" => Ok(Command::Reset),
+            _ => Err(format!(
+                "Неизвестная команда ':{}'; список команд — :help",
+                name
+            )),
+        })
+    }
+}
+
+/// Выполнить строку кода. Если строка заканчивается выражением, возвращается
+/// его значение, чтобы REPL его напечатал; у операторов и выражений со
+/// значением `ничто` (например, вызова `вывести`) результата нет
+pub fn eval_line(ponos: &mut Ponos, line: &str) -> Result<Option<Value>, String> {
+    // Строку, которую не удалось разобрать, выполняем как есть ради сообщения об ошибке
+    let echo = ponos
+        .parse_only(line.to_string())
+        .is_ok_and(|program| matches!(program.statements.last(), Some(Statement::Expression(_))));
+
+    let value = ponos.eval(line)?;
+    Ok(if echo && value != Value::Nil {
+        Some(value)
+    } else {
+        None
+    })
+}
+
+/// Выполнить команду и вернуть текст для вывода. `fresh` создаёт новый
+/// интерпретатор для `:reset` — с теми же настройками, что и исходный
+pub fn execute(
+    ponos: &mut Ponos,
+    command: Command,
+    fresh: impl FnOnce() -> Ponos,
+) -> Result<String, String> {
+    match command {
+        Command::Help => Ok(HELP.to_string()),
+        Command::Vars => Ok(vars(ponos)),
+        Command::Type(source) => ponos.type_of(&source).map(|ty| ty.to_string()),
+        Command::Disasm(source) => disasm(ponos, &source),
+        Command::Load(path) => {
+            let source = fs::read_to_string(&path)
+                .map_err(|e| format!("Не удалось открыть {}: {}", path.display(), e))?;
+            ponos.eval_with_file(&source, Some(path.clone()))?;
+            Ok(format!("Файл {} загружен", path.display()))
+        }
+        Command::Reload(namespace) => {
+            ponos.reload_module(&namespace)?;
+            Ok(format!("Модуль '{}' перезагружен", namespace))
+        }
+        Command::Reset => {
+            *ponos = fresh();
+            Ok("Сессия начата заново".to_string())
+        }
+    }
+}
+
+fn vars(ponos: &mut Ponos) -> String {
+    let mut text = String::new();
+    for (name, value) in ponos.globals() {
+        text.push_str(&format!(
+            "{}: {} = {}\n",
+            name,
+            Type::of_value(&value),
+            value
+        ));
+    }
+    for (namespace, path) in ponos.modules() {
+        text.push_str(&format!("модуль {} ({})\n", namespace, path.display()));
+    }
+    if text.is_empty() {
+        text.push_str("Переменных нет\n");
+    }
+    text
+}
+
+/// Байткод функции, если аргумент — имя глобальной функции, иначе байткод
+/// фрагмента кода. Фрагмент компилируется, но не выполняется
+fn disasm(ponos: &mut Ponos, source: &str) -> Result<String, String> {
+    let function = match ponos.get_global(source) {
+        Some(Value::Function(function)) => Some(function),
+        Some(Value::Closure(closure)) => Some(closure.function.clone()),
+        _ => None,
+    };
+    if let Some(function) = function {
+        return Ok(format!(
+            "Функция {} (аргументов: {})\n{}",
+            function.name,
+            function.arity_description(),
+            opcode::listing(&function.opcodes, &function.constants, &function.lines)
+        ));
+    }
+    let ctx = ponos.compile(source, None)?;
+    Ok(opcode::listing(&ctx.opcodes, &ctx.constants, &ctx.lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(ponos: &mut Ponos, line: &str) -> Result<String, String> {
+        let command = Command::parse(line).expect("строка должна быть командой")?;
+        execute(ponos, command, Ponos::new)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse("пер x = 1;"), None);
+        assert_eq!(Command::parse(" :vars "), Some(Ok(Command::Vars)));
+        assert_eq!(
+            Command::parse(":type x + 1"),
+            Some(Ok(Command::Type("x + 1".to_string())))
+        );
+        assert_eq!(
+            Command::parse(":load main.pns"),
+            Some(Ok(Command::Load(PathBuf::from("main.pns"))))
+        );
+        assert!(matches!(Command::parse(":type"), Some(Err(_))));
+        assert!(matches!(Command::parse(":выход"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_eval_line_echoes_expressions() {
+        let mut ponos = Ponos::new();
+        assert_eq!(eval_line(&mut ponos, "2 + 2").unwrap(), Some(Value::Int(4)));
+        assert_eq!(eval_line(&mut ponos, "пер x = 5;").unwrap(), None);
+        assert_eq!(
+            eval_line(&mut ponos, "x * 2;").unwrap(),
+            Some(Value::Int(10))
+        );
+        assert_eq!(eval_line(&mut ponos, "вывести(x)").unwrap(), None);
+        assert!(eval_line(&mut ponos, "пер = ;").is_err());
+    }
+
+    #[test]
+    fn test_vars_type_and_disasm() {
+        let mut ponos = Ponos::new();
+        ponos
+            .eval("пер счёт = 2; функ удвоить(x) возврат x * 2; конец")
+            .unwrap();
+
+        let vars = run(&mut ponos, ":vars").unwrap();
+        assert!(vars.contains("счёт: число = 2"), "{}", vars);
+        assert!(vars.contains("удвоить: функция"), "{}", vars);
+        assert!(!vars.contains("вывести"), "{}", vars);
+
+        assert_eq!(run(&mut ponos, ":type счёт + 1").unwrap(), "число");
+        assert_eq!(run(&mut ponos, ":type \"а\" + \"б\"").unwrap(), "строка");
+
+        let listing = run(&mut ponos, ":disasm удвоить").unwrap();
+        assert!(listing.contains("Функция удвоить"), "{}", listing);
+        assert!(listing.contains("Байткод:"), "{}", listing);
+        let listing = run(&mut ponos, ":disasm 1 + 2;").unwrap();
+        assert!(listing.contains("Константы:"), "{}", listing);
+
+        run(&mut ponos, ":reset").unwrap();
+        assert_eq!(run(&mut ponos, ":vars").unwrap(), "Переменных нет\n");
+    }
+}
@@ -0,0 +1,563 @@
+use crate::ponos::ast::{
+    AssignmentTarget, ClassDecl, ClassMember, Expression, Parameter, Program, Statement,
+};
+use crate::ponos::parser::{ParseErrorKind, PonosParseError};
+use crate::ponos::span::Span;
+use crate::ponos::symbol_table::{SymbolKind, SymbolTable};
+use std::collections::HashSet;
+
+// Семантическая проверка перед генерацией кода: обращения к необъявленным
+// переменным, повторные объявления, присваивания константам и конструкции
+// вне своего контекста (`возврат` вне функции, `это` вне метода). Без неё такие ошибки проявлялись
+// только при выполнении или паникой генератора.
+//
+// Областей видимости две: глобальная (код верхнего уровня или модуля) и
+// функция. Блоки `если`/`пока` области не создают, а имена, объявленные
+// в любом месте области, видны во всей ней: функцию можно вызвать до её
+// объявления, а вложенная функция видит переменные, объявленные ниже неё
+
+/// Семантическая ошибка с позицией в исходном коде
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl SemanticError {
+    /// Форматирует ошибку с подсветкой исходного кода, как ошибки парсинга
+    pub fn format(&self, source: &str, filename: &str) -> String {
+        PonosParseError::new(ParseErrorKind::Custom(self.message.clone()), self.span)
+            .format(source, filename)
+    }
+}
+
+/// Семантическая проверка программы
+pub struct SemanticChecker<'a> {
+    symbol_table: &'a SymbolTable,
+    /// Определена ли глобальная переменная в VM: встроенные функции,
+    /// функции хоста и глобальные переменные предыдущих запусков
+    is_global: &'a dyn Fn(&str) -> bool,
+    /// Объявленные имена: глобальная область и области функций
+    scopes: Vec<HashSet<String>>,
+    /// Имена, объявленные в тех же областях через `конст`
+    constants: Vec<HashSet<String>>,
+    /// Пространство имён модуля, код которого проверяется сейчас
+    namespace: Option<String>,
+    /// Глубина вложенности функций
+    function_depth: usize,
+    /// Проверяется ли сейчас тело метода или конструктора
+    in_method: bool,
+    errors: Vec<SemanticError>,
+}
+
+impl<'a> SemanticChecker<'a> {
+    pub fn new(symbol_table: &'a SymbolTable, is_global: &'a dyn Fn(&str) -> bool) -> Self {
+        SemanticChecker {
+            symbol_table,
+            is_global,
+            scopes: Vec::new(),
+            constants: Vec::new(),
+            namespace: None,
+            function_depth: 0,
+            in_method: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Проверить программу и вернуть все найденные ошибки
+    pub fn check(mut self, program: &Program) -> Vec<SemanticError> {
+        self.check_global_scope(&program.statements);
+        self.errors
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(SemanticError { message, span });
+    }
+
+    /// Код верхнего уровня программы или модуля: глобальная переменная,
+    /// функция или класс объявляются в нём не больше одного раза
+    fn check_global_scope(&mut self, statements: &[Statement]) {
+        let mut declared = HashSet::new();
+        for stmt in statements {
+            let declaration = match stmt {
+                Statement::VarDecl(decl) => Some((&decl.name, decl.span)),
+                Statement::FuncDecl(func) => Some((&func.name, func.span)),
+                Statement::ClassDecl(class) => Some((&class.name, class.span)),
+                _ => None,
+            };
+            let Some((name, span)) = declaration else {
+                continue;
+            };
+            if !declared.insert(name.clone()) {
+                self.error(
+                    span,
+                    format!("Имя '{}' уже объявлено в этой области видимости", name),
+                );
+            } else if let Some(namespace) = self.imported_from(name) {
+                self.error(
+                    span,
+                    format!("Имя '{}' уже импортировано из модуля '{}'", name, namespace),
+                );
+            }
+        }
+
+        let (mut scope, mut constants) = (HashSet::new(), HashSet::new());
+        collect_declarations(statements, &mut scope, &mut constants);
+        self.scopes.push(scope);
+        self.constants.push(constants);
+        self.check_block(statements);
+        self.scopes.pop();
+        self.constants.pop();
+    }
+
+    /// Модуль, из которого имя импортировано без префикса в программу
+    fn imported_from(&self, name: &str) -> Option<String> {
+        if self.namespace.is_some() {
+            return None;
+        }
+        self.symbol_table.lookup(name)?.imported_from.clone()
+    }
+
+    /// Тело функции, лямбды, метода или конструктора в собственной области
+    fn check_function_body(&mut self, params: &[Parameter], body: &[Statement]) {
+        let mut scope = HashSet::new();
+        for param in params {
+            if !scope.insert(param.name.clone()) {
+                self.error(
+                    param.span,
+                    format!("Параметр '{}' указан дважды", param.name),
+                );
+            }
+        }
+        let mut constants = HashSet::new();
+        collect_declarations(body, &mut scope, &mut constants);
+
+        self.scopes.push(scope);
+        self.constants.push(constants);
+        self.function_depth += 1;
+        for default in params.iter().filter_map(|param| param.default.as_ref()) {
+            self.check_expression(default);
+        }
+        self.check_block(body);
+        self.function_depth -= 1;
+        self.scopes.pop();
+        self.constants.pop();
+    }
+
+    fn check_class(&mut self, class: &ClassDecl) {
+        if let Some(parent) = &class.extends {
+            self.check_name(parent, class.span);
+        }
+
+        let mut members = HashSet::new();
+        let outer = std::mem::replace(&mut self.in_method, true);
+        for member in &class.members {
+            let name = match member {
+                ClassMember::Field { name, .. } | ClassMember::StaticField { name, .. } => {
+                    name.as_str()
+                }
+                ClassMember::Method(method) | ClassMember::StaticMethod(method) => {
+                    method.name.as_str()
+                }
+                ClassMember::Constructor(_) => "конструктор",
+            };
+            if !members.insert(name) {
+                self.error(
+                    class.span,
+                    format!("Член '{}' класса '{}' объявлен дважды", name, class.name),
+                );
+            }
+            match member {
+                ClassMember::Method(method) => {
+                    self.check_function_body(&method.params, &method.body)
+                }
+                ClassMember::Constructor(ctor) => {
+                    self.check_function_body(&ctor.params, &ctor.body)
+                }
+                // У статических членов нет `это`
+                ClassMember::StaticMethod(method) => {
+                    self.in_method = false;
+                    self.check_function_body(&method.params, &method.body);
+                    self.in_method = true;
+                }
+                ClassMember::StaticField {
+                    initializer: Some(value),
+                    ..
+                } => {
+                    self.in_method = false;
+                    self.check_expression(value);
+                    self.in_method = true;
+                }
+                ClassMember::Field { .. } | ClassMember::StaticField { .. } => {}
+            }
+        }
+        self.in_method = outer;
+    }
+
+    /// Объявлено ли имя: в программе, как пространство имён модуля или в VM
+    fn is_declared(&self, name: &str) -> bool {
+        if self.scopes.iter().any(|scope| scope.contains(name)) {
+            return true;
+        }
+        if self.symbol_table.lookup(name).is_some() || (self.is_global)(name) {
+            return true;
+        }
+        // Нативные функции модуля доступны его коду по имени с префиксом
+        self.namespace
+            .as_ref()
+            .is_some_and(|namespace| (self.is_global)(&format!("{}::{}", namespace, name)))
+    }
+
+    /// Константа ли имя: решает ближайшая область, где имя объявлено, а для
+    /// импортированных без префикса имён — объявление в модуле
+    fn is_constant(&self, name: &str) -> bool {
+        match self.scopes.iter().rposition(|scope| scope.contains(name)) {
+            Some(index) => self.constants[index].contains(name),
+            None => self
+                .symbol_table
+                .lookup(name)
+                .is_some_and(|symbol| symbol.kind == SymbolKind::Constant),
+        }
+    }
+
+    /// Константа ли `модуль.имя`
+    fn is_module_constant(&self, namespace: &str, name: &str) -> bool {
+        if self.scopes.iter().any(|scope| scope.contains(namespace)) {
+            return false;
+        }
+        self.symbol_table
+            .lookup(namespace)
+            .and_then(|module| module.module_scope_id)
+            .and_then(|scope| self.symbol_table.lookup_in_scope(scope, name))
+            .is_some_and(|symbol| symbol.kind == SymbolKind::Constant)
+    }
+
+    fn check_name(&mut self, name: &str, span: Span) {
+        if !self.is_declared(name) {
+            self.error(span, format!("Переменная '{}' не объявлена", name));
+        }
+    }
+
+    fn check_block(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.check_statement(stmt);
+        }
+    }
+
+    fn check_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl(decl) => {
+                if let Some(init) = &decl.initializer {
+                    self.check_expression(init);
+                }
+            }
+            Statement::FuncDecl(func) => {
+                // `это` вложенной функции — не экземпляр класса
+                let outer = std::mem::replace(&mut self.in_method, false);
+                self.check_function_body(&func.params, &func.body);
+                self.in_method = outer;
+            }
+            Statement::ClassDecl(class) => self.check_class(class),
+            Statement::ModuleBlock(block) => {
+                let outer = self.namespace.replace(block.namespace.clone());
+                self.check_global_scope(&block.statements);
+                self.namespace = outer;
+            }
+            Statement::If(if_stmt) => {
+                self.check_expression(&if_stmt.condition);
+                self.check_block(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.check_block(else_branch);
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.check_expression(&while_stmt.condition);
+                self.check_block(&while_stmt.body);
+            }
+            Statement::ForEach(foreach) => {
+                if self.function_depth == 0 {
+                    self.error(
+                        foreach.span,
+                        "'для каждого' может использоваться только внутри функции".to_string(),
+                    );
+                }
+                self.check_expression(&foreach.iterable);
+                self.check_block(&foreach.body);
+            }
+            Statement::Return(ret) => {
+                if self.function_depth == 0 {
+                    self.error(ret.span, "'возврат' вне функции".to_string());
+                }
+                if let Some(value) = &ret.value {
+                    self.check_expression(value);
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.check_block(&try_stmt.try_body);
+                for clause in &try_stmt.catches {
+                    if let Some(class) = &clause.class {
+                        self.check_expression(class);
+                    }
+                    self.check_block(&clause.body);
+                }
+                self.check_block(try_stmt.finally_body.as_deref().unwrap_or_default());
+            }
+            Statement::Throw(throw) => self.check_expression(&throw.expression),
+            Statement::Assignment(assign) => {
+                self.check_expression(&assign.value);
+                match &assign.target {
+                    AssignmentTarget::Identifier(name) => {
+                        self.check_name(name, assign.span);
+                        if self.is_constant(name) {
+                            self.error(
+                                assign.span,
+                                format!("Нельзя присвоить значение константе '{}'", name),
+                            );
+                        }
+                    }
+                    AssignmentTarget::FieldAccess(object, field) => {
+                        self.check_expression(object);
+                        if let Expression::Identifier(namespace, _) = object.as_ref()
+                            && self.is_module_constant(namespace, field)
+                        {
+                            self.error(
+                                assign.span,
+                                format!(
+                                    "Нельзя присвоить значение константе '{}.{}'",
+                                    namespace, field
+                                ),
+                            );
+                        }
+                    }
+                    AssignmentTarget::Index(object, index) => {
+                        self.check_expression(object);
+                        self.check_expression(index);
+                    }
+                }
+            }
+            Statement::Expression(expr) => self.check_expression(expr),
+            // Тела объявлений аннотаций не компилируются
+            Statement::AnnotationDecl(_) | Statement::InterfaceDecl(_) | Statement::Import(_) => {}
+        }
+    }
+
+    fn check_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Integer(..)
+            | Expression::Number(..)
+            | Expression::String(..)
+            | Expression::Boolean(..)
+            | Expression::Nil(_)
+            | Expression::ModuleAccess(_) => {}
+            Expression::Identifier(name, span) => self.check_name(name, *span),
+            Expression::Binary(binary) => {
+                self.check_expression(&binary.left);
+                self.check_expression(&binary.right);
+            }
+            Expression::Unary(unary) => self.check_expression(&unary.operand),
+            Expression::Call(call) => {
+                self.check_expression(&call.callee);
+                for arg in &call.arguments {
+                    self.check_expression(arg);
+                }
+            }
+            Expression::FieldAccess(access) => self.check_expression(&access.object),
+            Expression::Lambda(lambda) => self.check_function_body(&lambda.params, &lambda.body),
+            Expression::Index(index) => {
+                self.check_expression(&index.object);
+                self.check_expression(&index.index);
+            }
+            Expression::Range(range) => {
+                for bound in [&range.start, &range.end].into_iter().flatten() {
+                    self.check_expression(bound);
+                }
+            }
+            Expression::ArrayLiteral(array) => {
+                for element in &array.elements {
+                    self.check_expression(element);
+                }
+            }
+            Expression::DictLiteral(dict) => {
+                for (key, value) in &dict.pairs {
+                    self.check_expression(key);
+                    self.check_expression(value);
+                }
+            }
+            Expression::This(span) => {
+                if !self.in_method {
+                    self.error(*span, "'это' вне метода класса".to_string());
+                }
+            }
+            Expression::Super(_, span) => {
+                if !self.in_method {
+                    self.error(*span, "'супер' вне метода класса".to_string());
+                }
+            }
+            Expression::Spread(value, _) => self.check_expression(value),
+        }
+    }
+}
+
+/// Собрать имена, объявленные в области: во вложенных блоках, но не во
+/// вложенных функциях. Имена констант дополнительно попадают в `constants`
+fn collect_declarations(
+    statements: &[Statement],
+    names: &mut HashSet<String>,
+    constants: &mut HashSet<String>,
+) {
+    for stmt in statements {
+        match stmt {
+            Statement::VarDecl(decl) => {
+                names.insert(decl.name.clone());
+                if decl.is_const {
+                    constants.insert(decl.name.clone());
+                }
+            }
+            Statement::FuncDecl(func) => {
+                names.insert(func.name.clone());
+            }
+            Statement::ClassDecl(class) => {
+                names.insert(class.name.clone());
+            }
+            Statement::Import(import) => {
+                if let Some(alias) = &import.alias {
+                    names.insert(alias.clone());
+                }
+            }
+            Statement::If(if_stmt) => {
+                collect_declarations(&if_stmt.then_branch, names, constants);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    collect_declarations(else_branch, names, constants);
+                }
+            }
+            Statement::While(while_stmt) => {
+                collect_declarations(&while_stmt.body, names, constants)
+            }
+            Statement::ForEach(foreach) => {
+                names.insert(foreach.element_name.clone());
+                names.extend(foreach.index_name.clone());
+                collect_declarations(&foreach.body, names, constants);
+            }
+            Statement::Try(try_stmt) => {
+                collect_declarations(&try_stmt.try_body, names, constants);
+                for clause in &try_stmt.catches {
+                    names.extend(clause.var.clone());
+                    collect_declarations(&clause.body, names, constants);
+                }
+                collect_declarations(
+                    try_stmt.finally_body.as_deref().unwrap_or_default(),
+                    names,
+                    constants,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ponos::parser::PonosParser;
+
+    fn errors(source: &str) -> Vec<String> {
+        let program = PonosParser::new().parse(source.to_string()).unwrap();
+        let symbol_table = SymbolTable::new();
+        let is_global = |name: &str| name == "вывести";
+        SemanticChecker::new(&symbol_table, &is_global)
+            .check(&program)
+            .into_iter()
+            .map(|error| error.message)
+            .collect()
+    }
+
+    #[test]
+    fn declared_names_are_accepted() {
+        let source = r#"
+            функ главная()
+                вывести(помощник(1));
+                пер x = 1;
+                если x > 0
+                    пер y = x;
+                конец
+                вывести(y);
+            конец
+            функ помощник(a)
+                функ вложенная()
+                    возврат a + счёт;
+                конец
+                возврат вложенная();
+            конец
+            пер счёт = 0;
+            класс Точка
+                x: число
+                конструктор(x)
+                    это.x = x;
+                конец
+            конец
+        "#;
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn undeclared_names_are_reported() {
+        let source = r#"
+            функ f()
+                вывести(скрытое);
+            конец
+            нет_такой = 1;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                "Переменная 'скрытое' не объявлена",
+                "Переменная 'нет_такой' не объявлена"
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_and_misplaced_constructs_are_reported() {
+        let source = r#"
+            пер y = 1;
+            пер y = 2;
+            функ f(a, a)
+                возврат это;
+            конец
+            возврат 1;
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                "Имя 'y' уже объявлено в этой области видимости",
+                "Параметр 'a' указан дважды",
+                "'это' вне метода класса",
+                "'возврат' вне функции"
+            ]
+        );
+    }
+
+    #[test]
+    fn assignments_to_constants_are_reported() {
+        let source = r#"
+            конст ЛИМИТ = 10;
+            пер счёт = 0;
+            функ f(ЛИМИТ)
+                ЛИМИТ = 5;
+                конст ШАГ = 2;
+                ШАГ = 3;
+                счёт = счёт + ШАГ;
+            конец
+            функ g()
+                ЛИМИТ = 20;
+            конец
+        "#;
+        assert_eq!(
+            errors(source),
+            vec![
+                "Нельзя присвоить значение константе 'ШАГ'",
+                "Нельзя присвоить значение константе 'ЛИМИТ'"
+            ]
+        );
+    }
+}
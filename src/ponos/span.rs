@@ -12,7 +12,7 @@ impl Location {
 }
 
 /// Представляет диапазон символов в исходном коде
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -32,7 +32,7 @@ impl Span {
     }
 
     /// Преобразует байтовые позиции в номера строк и столбцов
-    pub fn to_location(&self, source: &str) -> (Location, Location) {
+    pub fn to_location(self, source: &str) -> (Location, Location) {
         let start_loc = byte_offset_to_location(source, self.start);
         let end_loc = byte_offset_to_location(source, self.end);
         (start_loc, end_loc)
@@ -44,9 +44,26 @@ impl Span {
     }
 }
 
-impl Default for Span {
-    fn default() -> Self {
-        Span { start: 0, end: 0 }
+/// Номера строк по байтовым смещениям: начала строк вычисляются один раз,
+/// поиск строки — двоичный
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        LineIndex { line_starts }
+    }
+
+    /// Номер строки (с 1), в которой находится смещение
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= offset)
+            .max(1)
     }
 }
 
@@ -102,6 +119,16 @@ mod tests {
         assert_eq!(loc.column, 3);
     }
 
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("пер а = 1;\nпер б = 2;\n\nвывести(б);");
+        assert_eq!(index.line(0), 1);
+        assert_eq!(index.line(5), 1);
+        assert_eq!(index.line(17), 2);
+        assert_eq!(index.line(36), 4);
+        assert_eq!(LineIndex::default().line(10), 1);
+    }
+
     #[test]
     fn test_span_text() {
         let source = "hello world";
@@ -0,0 +1,283 @@
+//! Исполняемая спецификация семантики (`ponos spec`).
+//!
+//! Каталог `spec/` содержит небольшие программы с ожидаемым результатом.
+//! Тесты прогоняют каждый пример, а `ponos spec` собирает из них справочник
+//! в Markdown, так что документация не расходится с реализацией. Формат файла:
+//!
+//! ```text
+//! // Заголовок раздела
+//! // Описание (строки комментариев)
+//! // === Название примера
+//! ...код...
+//! // --- вывод
+//! // ожидаемый вывод, по строке комментария на строку вывода
+//! // === Пример с ошибкой
+//! ...код...
+//! // --- ошибка
+//! // фрагмент текста ошибки
+//! ```
+
+use crate::ponos::Ponos;
+
+const EXAMPLE_MARKER: &str = "// === ";
+const OUTPUT_MARKER: &str = "// --- вывод";
+const ERROR_MARKER: &str = "// --- ошибка";
+
+static SECTION_SOURCES: &[(&str, &str)] = &[
+    (
+        "01_приоритет.pns",
+        include_str!("../../spec/01_приоритет.pns"),
+    ),
+    (
+        "02_истинность.pns",
+        include_str!("../../spec/02_истинность.pns"),
+    ),
+    (
+        "03_точка_с_запятой.pns",
+        include_str!("../../spec/03_точка_с_запятой.pns"),
+    ),
+    (
+        "04_области_видимости.pns",
+        include_str!("../../spec/04_области_видимости.pns"),
+    ),
+    (
+        "05_ссылки_и_копии.pns",
+        include_str!("../../spec/05_ссылки_и_копии.pns"),
+    ),
+];
+
+/// Ожидаемый результат примера
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    /// Программа завершается успешно и печатает ровно этот текст
+    Output(String),
+    /// Программа завершается ошибкой, текст которой содержит этот фрагмент
+    Error(String),
+}
+
+/// Пример: программа и ожидаемый результат
+#[derive(Debug, Clone)]
+pub struct Example {
+    pub title: String,
+    pub code: String,
+    pub expected: Expectation,
+}
+
+/// Раздел спецификации (один файл каталога `spec/`)
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub file: String,
+    pub title: String,
+    pub description: String,
+    pub examples: Vec<Example>,
+}
+
+fn comment_text(line: &str) -> &str {
+    let line = line.strip_prefix("//").unwrap_or(line);
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+impl Example {
+    fn parse(file: &str, title: &str, body: &str) -> Result<Example, String> {
+        let (code, marker, expected) = [OUTPUT_MARKER, ERROR_MARKER]
+            .iter()
+            .filter_map(|marker| {
+                body.split_once(marker)
+                    .map(|(code, rest)| (code, *marker, rest))
+            })
+            .min_by_key(|(code, _, _)| code.len())
+            .ok_or_else(|| {
+                format!(
+                    "{}: у примера '{}' нет раздела '{}' или '{}'",
+                    file, title, OUTPUT_MARKER, ERROR_MARKER
+                )
+            })?;
+
+        let expected = expected
+            .lines()
+            .map(str::trim_end)
+            .skip_while(|line| line.is_empty())
+            .map(comment_text)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string();
+
+        Ok(Example {
+            title: title.to_string(),
+            code: code.trim().to_string(),
+            expected: if marker == OUTPUT_MARKER {
+                Expectation::Output(expected)
+            } else {
+                Expectation::Error(expected)
+            },
+        })
+    }
+
+    /// Выполнить пример в чистом интерпретаторе: Ok(вывод) или Err(текст ошибки)
+    pub fn run(&self) -> Result<String, String> {
+        let mut ponos = Ponos::new();
+        ponos.capture_output();
+        // Ожидаемая ошибка — часть примера, а не отчёт в stderr
+        ponos.set_report_errors(false);
+        let result = ponos.eval(&self.code);
+        let output = ponos.take_output().unwrap_or_default();
+        result.map(|_| output)
+    }
+
+    /// Проверить, что пример ведёт себя так, как записано в спецификации
+    pub fn check(&self) -> Result<(), String> {
+        match (&self.expected, self.run()) {
+            (Expectation::Output(expected), Ok(output)) if output.trim_end() == expected => Ok(()),
+            (Expectation::Output(expected), Ok(output)) => Err(format!(
+                "ожидался вывод:\n{}\nполучен:\n{}",
+                expected,
+                output.trim_end()
+            )),
+            (Expectation::Output(_), Err(error)) => Err(format!("неожиданная ошибка: {}", error)),
+            (Expectation::Error(expected), Err(error)) if error.contains(expected.as_str()) => {
+                Ok(())
+            }
+            (Expectation::Error(expected), Err(error)) => Err(format!(
+                "ожидалась ошибка, содержащая '{}', получена: {}",
+                expected, error
+            )),
+            (Expectation::Error(expected), Ok(_)) => Err(format!(
+                "ожидалась ошибка, содержащая '{}', но программа завершилась успешно",
+                expected
+            )),
+        }
+    }
+}
+
+impl Section {
+    /// Разобрать файл раздела
+    pub fn parse(file: &str, source: &str) -> Result<Section, String> {
+        let mut parts = source.split(EXAMPLE_MARKER);
+        let header = parts.next().unwrap_or_default();
+
+        let mut lines = header.lines().map(comment_text);
+        let title = lines
+            .next()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .ok_or_else(|| format!("{}: нет заголовка", file))?
+            .to_string();
+        let description = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+        let examples = parts
+            .map(|part| {
+                let (title, body) = part.split_once('\n').unwrap_or((part, ""));
+                Example::parse(file, title.trim(), body)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if examples.is_empty() {
+            return Err(format!("{}: в разделе нет примеров", file));
+        }
+
+        Ok(Section {
+            file: file.to_string(),
+            title,
+            description,
+            examples,
+        })
+    }
+}
+
+/// Все разделы спецификации по порядку
+pub fn sections() -> Vec<Section> {
+    SECTION_SOURCES
+        .iter()
+        .map(|(file, source)| Section::parse(file, source).expect("Некорректный файл спецификации"))
+        .collect()
+}
+
+/// Проверить все примеры; возвращает описания расхождений
+pub fn check_all(sections: &[Section]) -> Vec<String> {
+    sections
+        .iter()
+        .flat_map(|section| {
+            section.examples.iter().filter_map(move |example| {
+                example
+                    .check()
+                    .err()
+                    .map(|e| format!("{}: {}: {}", section.file, example.title, e))
+            })
+        })
+        .collect()
+}
+
+/// Собрать справочник семантики в Markdown
+pub fn render_markdown(sections: &[Section]) -> String {
+    let mut out = String::new();
+    out.push_str("# Семантика Ponos\n\n");
+    out.push_str(
+        "<!-- Файл создан командой `ponos spec docs/семантика.md` из примеров в каталоге spec/. \
+         Не редактируйте его вручную. -->\n\n",
+    );
+    out.push_str(
+        "Каждый пример ниже — исполняемый тест: его вывод или ошибка проверяются при сборке.\n",
+    );
+
+    for (number, section) in sections.iter().enumerate() {
+        out.push_str(&format!("\n## {}. {}\n\n", number + 1, section.title));
+        if !section.description.is_empty() {
+            out.push_str(&section.description);
+            out.push('\n');
+        }
+
+        for example in &section.examples {
+            out.push_str(&format!("\n### {}\n\n", example.title));
+            out.push_str(&format!("```ponos\n{}\n```\n\n", example.code));
+            let (label, text) = match &example.expected {
+                Expectation::Output(text) => ("Вывод", text),
+                Expectation::Error(text) => ("Ошибка", text),
+            };
+            out.push_str(&format!("{}:\n\n```text\n{}\n```\n", label, text));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_examples_hold() {
+        let failures = check_all(&sections());
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+
+    #[test]
+    fn generated_document_is_up_to_date() {
+        let document = include_str!("../../docs/семантика.md");
+        assert!(
+            document == render_markdown(&sections()),
+            "docs/семантика.md устарел: выполните `cargo run -- spec docs/семантика.md`"
+        );
+    }
+
+    #[test]
+    fn parse_reads_examples() {
+        let section = Section::parse(
+            "x.pns",
+            "// Раздел\n// Описание\n// === Пример\nвывести(1);\n// --- вывод\n// 1\n\
+             // === Ошибка\nпер = ;\n// --- ошибка\n// Ожидалось\n",
+        )
+        .unwrap();
+        assert_eq!(section.title, "Раздел");
+        assert_eq!(section.description, "Описание");
+        assert_eq!(section.examples.len(), 2);
+        assert_eq!(section.examples[0].code, "вывести(1);");
+        assert_eq!(
+            section.examples[0].expected,
+            Expectation::Output("1".to_string())
+        );
+        assert_eq!(
+            section.examples[1].expected,
+            Expectation::Error("Ожидалось".to_string())
+        );
+        assert!(Section::parse("x.pns", "// Раздел\nвывести(1);").is_err());
+    }
+}
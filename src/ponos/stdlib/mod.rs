@@ -1,4 +1,7 @@
+use crate::ponos::ast::Program;
+use crate::ponos::parser::{PonosParseError, PonosParser};
 use once_cell::sync::Lazy;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 /// Встроенные модули стандартной библиотеки
@@ -10,6 +13,12 @@ pub static EMBEDDED_STDLIB: Lazy<HashMap<&'static str, &'static str>> = Lazy::ne
         "стд/математика",
         include_str!("../../../stdlib/математика.pns"),
     );
+    map.insert(
+        "стд/коллекции",
+        include_str!("../../../stdlib/коллекции.pns"),
+    );
+    map.insert("стд/текст", include_str!("../../../stdlib/текст.pns"));
+    map.insert("стд/утилиты", include_str!("../../../stdlib/утилиты.pns"));
 
     map
 });
@@ -24,6 +33,28 @@ pub fn get_embedded_source(path: &str) -> Option<&'static str> {
     EMBEDDED_STDLIB.get(path).copied()
 }
 
+thread_local! {
+    // Разобранные встроенные модули. AST держит исходник в Rc, поэтому кэш
+    // свой у каждого потока; интерпретаторы одного потока разбирают модуль
+    // один раз
+    static PARSED_STDLIB: RefCell<HashMap<&'static str, Program>> = RefCell::new(HashMap::new());
+}
+
+/// AST встроенного модуля: разбирается при первом обращении, дальше
+/// возвращается копия из кэша. `None` — такого встроенного модуля нет
+pub fn parse_embedded(path: &str) -> Option<Result<Program, PonosParseError>> {
+    let (&key, &source) = EMBEDDED_STDLIB.get_key_value(path)?;
+    if let Some(program) = PARSED_STDLIB.with(|cache| cache.borrow().get(key).cloned()) {
+        return Some(Ok(program));
+    }
+    let program = match PonosParser::new().parse(source.to_string()) {
+        Ok(program) => program,
+        Err(e) => return Some(Err(e)),
+    };
+    PARSED_STDLIB.with(|cache| cache.borrow_mut().insert(key, program.clone()));
+    Some(Ok(program))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -31,6 +62,9 @@ mod tests {
     #[test]
     fn test_embedded_stdlib_exists() {
         assert!(is_embedded_stdlib("стд/математика"));
+        assert!(is_embedded_stdlib("стд/коллекции"));
+        assert!(is_embedded_stdlib("стд/текст"));
+        assert!(is_embedded_stdlib("стд/утилиты"));
         assert!(!is_embedded_stdlib("не_существует"));
     }
 
@@ -38,6 +72,14 @@ mod tests {
     fn test_get_embedded_source() {
         let math_source = get_embedded_source("стд/математика");
         assert!(math_source.is_some());
-        assert!(math_source.unwrap().contains("экспорт пер ПИ"));
+        assert!(math_source.unwrap().contains("экспорт конст ПИ"));
+    }
+
+    #[test]
+    fn test_parse_embedded_is_cached() {
+        let first = parse_embedded("стд/математика").unwrap().unwrap();
+        let second = parse_embedded("стд/математика").unwrap().unwrap();
+        assert!(std::rc::Rc::ptr_eq(&first.source, &second.source));
+        assert!(parse_embedded("стд/нет_такого").is_none());
     }
 }
@@ -1,10 +1,12 @@
 use crate::ponos::span::Span;
+use crate::ponos::typechecker::Type;
 use std::collections::HashMap;
 
 /// Тип символа
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SymbolKind {
     Variable,
+    Constant, // Переменная, объявленная через `конст`
     Function,
     Class,
     Interface,
@@ -26,11 +28,18 @@ pub struct Symbol {
     pub kind: SymbolKind,
     /// Экспортируется ли символ
     pub is_exported: bool,
+    #[allow(dead_code)]
     /// Позиция в исходном коде
     pub span: Span,
     /// Область видимости модуля (только для SymbolKind::Module)
     pub module_scope_id: Option<ScopeId>,
-    // TODO: В будущем добавить type_info для типчекера
+    /// Статический тип символа, если он известен из аннотаций
+    pub type_info: Option<Type>,
+    /// Экспорт исключён из импорта списком `показать`/`скрыть`
+    pub is_hidden: bool,
+    /// Пространство имён модуля, где определён символ: для имён из списка
+    /// `показать`, доступных без префикса, и для реэкспортов
+    pub imported_from: Option<String>,
 }
 
 impl Symbol {
@@ -42,9 +51,18 @@ impl Symbol {
             is_exported,
             span,
             module_scope_id: None,
+            type_info: None,
+            is_hidden: false,
+            imported_from: None,
         }
     }
 
+    /// Указать статический тип символа
+    pub fn with_type(mut self, type_info: Option<Type>) -> Self {
+        self.type_info = type_info;
+        self
+    }
+
     /// Создать символ модуля со ссылкой на его scope
     pub fn new_module(name: String, module_scope_id: ScopeId, span: Span) -> Self {
         Symbol {
@@ -53,6 +71,9 @@ impl Symbol {
             is_exported: false, // Модули не экспортируются, экспортируются их символы
             span,
             module_scope_id: Some(module_scope_id),
+            type_info: None,
+            is_hidden: false,
+            imported_from: None,
         }
     }
 }
@@ -60,6 +81,7 @@ impl Symbol {
 /// Область видимости
 #[derive(Debug, Clone)]
 pub struct Scope {
+    #[allow(dead_code)]
     /// Идентификатор этой области
     pub id: ScopeId,
     /// Родительская область видимости
@@ -90,16 +112,23 @@ impl Scope {
         Ok(())
     }
 
+    /// Удалить символ из этой области
+    pub fn remove(&mut self, name: &str) -> Option<Symbol> {
+        self.symbols.remove(name)
+    }
+
     /// Найти символ в этой области (без поиска в родителях)
     pub fn lookup_local(&self, name: &str) -> Option<&Symbol> {
         self.symbols.get(name)
     }
 
+    #[allow(dead_code)]
     /// Получить все символы
     pub fn symbols(&self) -> &HashMap<String, Symbol> {
         &self.symbols
     }
 
+    #[allow(dead_code)]
     /// Получить экспортированные символы
     pub fn exported_symbols(&self) -> Vec<&Symbol> {
         self.symbols.values().filter(|s| s.is_exported).collect()
@@ -143,6 +172,30 @@ impl SymbolTable {
         scope_id
     }
 
+    /// Создать дочернюю область текущей, не входя в неё. Так заводятся
+    /// области модулей: их символы доступны только через пространство имён
+    pub fn new_child_scope(&mut self) -> ScopeId {
+        let scope_id = self.push_scope();
+        self.pop_scope();
+        scope_id
+    }
+
+    /// Войти в новую область, дочернюю глобальной: в ней разрешаются имена
+    /// кода модуля и видны его собственные импорты. Возвращает область, в
+    /// которую нужно вернуться
+    pub fn enter_module_scope(&mut self) -> ScopeId {
+        let previous = self.current_scope;
+        self.current_scope = ScopeId(0);
+        self.push_scope();
+        previous
+    }
+
+    /// Вернуться из области модуля в область `previous`
+    pub fn leave_module_scope(&mut self, previous: ScopeId) {
+        self.current_scope = previous;
+    }
+
+    #[allow(dead_code)]
     /// Закрыть текущую область видимости
     pub fn pop_scope(&mut self) {
         let current = self.get_scope(self.current_scope);
@@ -166,6 +219,11 @@ impl SymbolTable {
         self.get_scope_mut(self.current_scope).define(symbol)
     }
 
+    /// Удалить символ из текущей области видимости
+    pub fn remove(&mut self, name: &str) -> Option<Symbol> {
+        self.get_scope_mut(self.current_scope).remove(name)
+    }
+
     /// Определить символ в конкретной области видимости
     pub fn define_in_scope(&mut self, scope_id: ScopeId, symbol: Symbol) -> Result<(), String> {
         self.get_scope_mut(scope_id).define(symbol)
@@ -195,6 +253,7 @@ impl SymbolTable {
         self.get_scope(scope_id).lookup_local(name)
     }
 
+    #[allow(dead_code)]
     /// Получить текущий scope ID
     pub fn current_scope(&self) -> ScopeId {
         self.current_scope
@@ -221,7 +280,7 @@ mod tests {
         );
         assert_eq!(symbol.name, "test");
         assert_eq!(symbol.kind, SymbolKind::Function);
-        assert_eq!(symbol.is_exported, true);
+        assert!(symbol.is_exported);
     }
 
     #[test]
@@ -0,0 +1,150 @@
+use crate::ponos::Ponos;
+use crate::ponos::ast::{FuncDecl, Program, Statement};
+use std::path::Path;
+
+// Встроенные тесты: функции верхнего уровня с аннотацией @Тест или с именем
+// `тест_...`. Каждый тест выполняется в отдельном интерпретаторе — код файла
+// запускается заново, поэтому тесты не видят глобальных изменений друг друга
+
+/// Аннотация, которой помечаются тестовые функции
+pub const TEST_ANNOTATION: &str = "Тест";
+
+/// Префикс имени, по которому функция считается тестом без аннотации
+pub const TEST_PREFIX: &str = "тест_";
+
+/// Результат одного теста: вывод теста и текст ошибки, если тест провален
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Result<(), String>,
+    pub output: String,
+}
+
+/// Найти тестовые функции программы в порядке объявления
+pub fn discover(program: &Program) -> Vec<&FuncDecl> {
+    program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::FuncDecl(decl)
+                if decl.name.starts_with(TEST_PREFIX)
+                    || decl.annotations.iter().any(|a| a.name == TEST_ANNOTATION) =>
+            {
+                Some(decl)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Выполнить тесты файла. `fresh` создаёт интерпретатор для каждого теста.
+/// Err — файл не удалось разобрать; ошибки загрузки кода файла считаются
+/// провалом каждого его теста
+pub fn run_file(
+    source: &str,
+    file: &Path,
+    fresh: impl Fn() -> Ponos,
+) -> Result<Vec<TestResult>, String> {
+    let name = file.display().to_string();
+    let program = fresh()
+        .parse_only(source.to_string())
+        .map_err(|e| e.format(source, &name))?;
+
+    let results = discover(&program)
+        .into_iter()
+        .map(|test| {
+            let mut ponos = fresh();
+            ponos.capture_output();
+            // Провал с его стеком вызовов сообщает сам запускатель тестов
+            ponos.set_report_errors(false);
+            let outcome = if test.params.is_empty() {
+                run_test(&mut ponos, source, file, &test.name)
+            } else {
+                Err("Тестовая функция не должна принимать параметры".to_string())
+            };
+            TestResult {
+                name: test.name.clone(),
+                outcome,
+                output: ponos.take_output().unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+fn run_test(ponos: &mut Ponos, source: &str, file: &Path, name: &str) -> Result<(), String> {
+    ponos.eval_with_file(source, Some(file.to_path_buf()))?;
+    if ponos.exit_code().is_none() {
+        ponos.call(name, &[])?;
+    }
+    match ponos.exit_code() {
+        Some(code) => Err(format!("Тест завершил программу с кодом {}", code)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_discover_annotated_and_prefixed_functions() {
+        let source =
+            "@Тест\nфунк сложение() конец\nфунк тест_вычитания() конец\nфунк помощник() конец\n";
+        let program = Ponos::new().parse_only(source.to_string()).unwrap();
+        let names: Vec<_> = discover(&program).iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["сложение", "тест_вычитания"]);
+    }
+
+    #[test]
+    fn test_each_test_runs_in_fresh_interpreter() {
+        let source = "\
+использовать \"стд/тест\" как т;
+пер счётчик = 0;
+функ тест_первый()
+    счётчик = счётчик + 1;
+    т.утверждать(счётчик == 1, \"счётчик общий\");
+конец
+функ тест_второй()
+    счётчик = счётчик + 1;
+    т.утверждать(счётчик == 1, \"счётчик общий\");
+конец
+функ тест_провал()
+    вывести(\"перед провалом\");
+    т.утверждать(ложь, \"ожидаемый провал\");
+конец
+";
+        let results = run_file(source, &PathBuf::from("тесты.pns"), Ponos::new).unwrap();
+        let outcomes: Vec<_> = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.outcome.is_ok()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("тест_первый", true),
+                ("тест_второй", true),
+                ("тест_провал", false)
+            ]
+        );
+        let failure = &results[2];
+        assert!(
+            failure
+                .outcome
+                .as_ref()
+                .unwrap_err()
+                .contains("ожидаемый провал"),
+            "{:?}",
+            failure.outcome
+        );
+        assert_eq!(failure.output, "перед провалом\n");
+        let failure = failure.outcome.as_ref().unwrap_err();
+        assert!(
+            failure.contains("Стек вызовов:\n  #0 тест_провал (тесты.pns, строка 13)"),
+            "{}",
+            failure
+        );
+        assert!(!failure.contains("<вызов>"), "{}", failure);
+    }
+}
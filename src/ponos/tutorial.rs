@@ -0,0 +1,126 @@
+//! Встроенный учебник (`ponos учёба`).
+//!
+//! Уроки лежат в каталоге `tutorial/` и встраиваются в бинарник так же, как
+//! стандартная библиотека. Файл урока — обычный исходник Ponos из трёх частей:
+//!
+//! ```text
+//! // Заголовок
+//! // Описание и задание (строки комментариев)
+//! // === решение
+//! ...эталонное решение...
+//! // === проверка
+//! ...код, бросающий исключение, если решение неверно...
+//! ```
+//!
+//! Решение пользователя проверяется запуском его кода вместе с проверочным
+//! блоком в чистом интерпретаторе.
+
+use crate::ponos::Ponos;
+
+const SOLUTION_MARKER: &str = "// === решение";
+const CHECK_MARKER: &str = "// === проверка";
+
+static LESSON_SOURCES: &[&str] = &[
+    include_str!("../../tutorial/01_переменные.pns"),
+    include_str!("../../tutorial/02_строки.pns"),
+    include_str!("../../tutorial/03_функции.pns"),
+    include_str!("../../tutorial/04_условия.pns"),
+    include_str!("../../tutorial/05_циклы.pns"),
+    include_str!("../../tutorial/06_массивы.pns"),
+    include_str!("../../tutorial/07_классы.pns"),
+    include_str!("../../tutorial/08_построитель_строк.pns"),
+];
+
+/// Урок учебника
+#[derive(Debug, Clone)]
+pub struct Lesson {
+    pub number: usize,
+    pub title: String,
+    pub description: String,
+    pub solution: String,
+    pub check: String,
+}
+
+impl Lesson {
+    /// Разобрать файл урока
+    pub fn parse(number: usize, source: &str) -> Result<Lesson, String> {
+        let (header, rest) = source
+            .split_once(SOLUTION_MARKER)
+            .ok_or_else(|| format!("Урок {}: нет раздела '{}'", number, SOLUTION_MARKER))?;
+        let (solution, check) = rest
+            .split_once(CHECK_MARKER)
+            .ok_or_else(|| format!("Урок {}: нет раздела '{}'", number, CHECK_MARKER))?;
+
+        let mut lines = header.lines().map(|line| {
+            let line = line.strip_prefix("//").unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line)
+        });
+        let title = lines
+            .next()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .ok_or_else(|| format!("Урок {}: нет заголовка", number))?
+            .to_string();
+        let description = lines.collect::<Vec<_>>().join("\n").trim_end().to_string();
+
+        Ok(Lesson {
+            number,
+            title,
+            description,
+            solution: solution.trim().to_string(),
+            check: check.trim().to_string(),
+        })
+    }
+
+    /// Проверить решение: выполнить его вместе с проверочным блоком урока
+    pub fn check(&self, solution: &str) -> Result<(), String> {
+        let mut ponos = Ponos::new();
+        ponos.eval(&format!("{}\n{}\n", solution, self.check))?;
+        Ok(())
+    }
+}
+
+/// Все уроки учебника по порядку
+pub fn lessons() -> Vec<Lesson> {
+    LESSON_SOURCES
+        .iter()
+        .enumerate()
+        .map(|(i, source)| Lesson::parse(i + 1, source).expect("Некорректный файл урока"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lessons_are_numbered_and_described() {
+        let lessons = lessons();
+        assert_eq!(lessons.len(), LESSON_SOURCES.len());
+        assert_eq!(lessons[0].number, 1);
+        assert_eq!(lessons[0].title, "Переменные");
+        assert!(lessons[0].description.contains("Задание"));
+    }
+
+    #[test]
+    fn reference_solutions_pass_their_checks() {
+        for lesson in lessons() {
+            if let Err(e) = lesson.check(&lesson.solution) {
+                panic!("Урок {} ({}): {}", lesson.number, lesson.title, e);
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_solution_is_rejected() {
+        let lesson = &lessons()[0];
+        assert!(lesson.check("пер ответ = 41;").is_err());
+        assert!(lesson.check("пер ответ = ;").is_err());
+    }
+
+    #[test]
+    fn parse_requires_sections() {
+        assert!(Lesson::parse(1, "// Заголовок\nпер x = 1;").is_err());
+        assert!(Lesson::parse(1, "// Заголовок\n// === решение\nпер x = 1;").is_err());
+    }
+}
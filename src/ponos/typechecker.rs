@@ -0,0 +1,968 @@
+use crate::ponos::ast::{
+    AssignmentTarget, BinaryOperator, ClassDecl, ClassMember, Expression, FuncDecl, Parameter,
+    Program, Statement, UnaryOperator,
+};
+use crate::ponos::parser::{ParseErrorKind, PonosParseError};
+use crate::ponos::span::Span;
+use crate::ponos::symbol_table::{SymbolKind, SymbolTable};
+use crate::ponos::value::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+// Проверка типов по необязательным аннотациям (`пер x: число`, `функ f(a: строка)`).
+// Язык остаётся динамическим: выражение, тип которого нельзя вывести из
+// аннотаций и литералов, получает тип `любой` и совместимо с чем угодно.
+// Ошибка сообщается только тогда, когда оба типа известны и не совпадают
+
+/// Статический тип выражения
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Number,
+    String,
+    Boolean,
+    Nil,
+    Array,
+    Dict,
+    /// Функция; сигнатура известна для объявленных функций
+    Function(Option<Rc<Signature>>),
+    /// Экземпляр класса с указанным именем
+    Instance(String),
+    /// Тип неизвестен (`любой`)
+    Unknown,
+}
+
+/// Сигнатура функции: типы параметров и тип результата
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    pub return_type: Type,
+    /// Последний параметр собирает остальные аргументы; его тип — тип
+    /// каждого из них
+    pub rest: bool,
+}
+
+impl Type {
+    /// Тип по имени из аннотации; незнакомое имя считается именем класса
+    pub fn from_annotation(name: &str) -> Type {
+        match name {
+            "число" => Type::Number,
+            "строка" => Type::String,
+            "логический" => Type::Boolean,
+            "ничто" => Type::Nil,
+            "массив" => Type::Array,
+            "словарь" => Type::Dict,
+            "функция" => Type::Function(None),
+            "любой" => Type::Unknown,
+            class => Type::Instance(class.to_string()),
+        }
+    }
+
+    /// Тип значения во время выполнения
+    pub fn of_value(value: &Value) -> Type {
+        match value {
+            Value::Int(_) | Value::Number(_) => Type::Number,
+            Value::String(_) | Value::StringBuilder(_) => Type::String,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Nil => Type::Nil,
+            Value::Array(_) => Type::Array,
+            Value::Dict(_) => Type::Dict,
+            Value::Function(_)
+            | Value::NativeFunction(_)
+            | Value::Closure(_)
+            | Value::BoundMethod(_)
+            | Value::BoundBuiltinMethod(_)
+            | Value::BoundNativeMethod(_) => Type::Function(None),
+            Value::Instance(instance) => Type::Instance(instance.borrow().class.name.clone()),
+            _ => Type::Unknown,
+        }
+    }
+
+    fn from_optional_annotation(annotation: &Option<String>) -> Type {
+        annotation
+            .as_deref()
+            .map_or(Type::Unknown, Type::from_annotation)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params: Vec<String> = self.params.iter().map(Type::to_string).collect();
+        if let (true, Some(last)) = (self.rest, params.last_mut()) {
+            last.insert_str(0, "...");
+        }
+        write!(f, "({}): {}", params.join(", "), self.return_type)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Number => write!(f, "число"),
+            Type::String => write!(f, "строка"),
+            Type::Boolean => write!(f, "логический"),
+            Type::Nil => write!(f, "ничто"),
+            Type::Array => write!(f, "массив"),
+            Type::Dict => write!(f, "словарь"),
+            Type::Function(_) => write!(f, "функция"),
+            Type::Instance(class) => write!(f, "{}", class),
+            Type::Unknown => write!(f, "любой"),
+        }
+    }
+}
+
+/// Сигнатура объявленной функции по аннотациям параметров и результата
+pub(crate) fn signature_of(params: &[Parameter], return_type: &Option<String>) -> Signature {
+    Signature {
+        params: params
+            .iter()
+            .map(|param| Type::from_optional_annotation(&param.type_annotation))
+            .collect(),
+        return_type: Type::from_optional_annotation(return_type),
+        rest: params.last().is_some_and(|param| param.is_rest),
+    }
+}
+
+/// Ошибка типов с позицией в исходном коде
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl TypeError {
+    /// Форматирует ошибку с подсветкой исходного кода, как ошибки парсинга
+    pub fn format(&self, source: &str, filename: &str) -> String {
+        PonosParseError::new(ParseErrorKind::Custom(self.message.clone()), self.span)
+            .format(source, filename)
+    }
+}
+
+/// Сведения о классе, нужные для проверки обращений к его экземплярам
+#[derive(Default)]
+struct ClassInfo {
+    extends: Option<String>,
+    implements: Vec<String>,
+    fields: HashMap<String, Type>,
+    methods: HashMap<String, Rc<Signature>>,
+    constructor: Option<Rc<Signature>>,
+}
+
+/// Проверка типов программы
+pub struct TypeChecker<'a> {
+    symbol_table: &'a SymbolTable,
+    functions: HashMap<String, Rc<Signature>>,
+    classes: HashMap<String, ClassInfo>,
+    /// Методы интерфейсов: интерфейс -> (метод -> сигнатура)
+    interfaces: HashMap<String, HashMap<String, Rc<Signature>>>,
+    /// Объявленные типы переменных по областям видимости
+    scopes: Vec<HashMap<String, Type>>,
+    /// Класс, методы которого проверяются сейчас (тип `это`)
+    current_class: Option<String>,
+    /// Типы результата функций, тела которых проверяются сейчас
+    return_types: Vec<Type>,
+    errors: Vec<TypeError>,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(symbol_table: &'a SymbolTable) -> Self {
+        TypeChecker {
+            symbol_table,
+            functions: HashMap::new(),
+            classes: HashMap::new(),
+            interfaces: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            current_class: None,
+            return_types: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Вывести статический тип выражения, зная типы уже определённых
+    /// переменных; ошибки типов при этом не собираются
+    pub fn type_of(mut self, expr: &Expression, known: &[(String, Type)]) -> Type {
+        for (name, ty) in known {
+            self.declare(name, ty.clone());
+        }
+        self.infer(expr)
+    }
+
+    /// Проверить программу и вернуть все найденные ошибки
+    pub fn check(mut self, program: &Program) -> Vec<TypeError> {
+        self.collect_declarations(&program.statements);
+        self.check_block(&program.statements);
+        self.errors
+    }
+
+    /// Функции и классы можно использовать до объявления, поэтому их
+    /// сигнатуры собираются заранее
+    fn collect_declarations(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            match stmt {
+                Statement::FuncDecl(func) => {
+                    let signature = signature_of(&func.params, &func.return_type);
+                    self.functions.insert(func.name.clone(), Rc::new(signature));
+                }
+                Statement::InterfaceDecl(interface) => {
+                    let methods = interface
+                        .methods
+                        .iter()
+                        .map(|method| {
+                            let signature = signature_of(&method.params, &method.return_type);
+                            (method.name.clone(), Rc::new(signature))
+                        })
+                        .collect();
+                    self.interfaces.insert(interface.name.clone(), methods);
+                }
+                Statement::ClassDecl(class) => {
+                    self.classes.insert(class.name.clone(), class_info(class));
+                }
+                Statement::ModuleBlock(block) => self.collect_declarations(&block.statements),
+                _ => {}
+            }
+        }
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(TypeError { message, span });
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn with_scope(&mut self, action: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        action(self);
+        self.scopes.pop();
+    }
+
+    /// Является ли класс `class` классом `ancestor`, его наследником или
+    /// реализацией интерфейса `ancestor`. Если иерархия неизвестна (класс
+    /// из другого модуля), считаем, что да
+    fn is_subclass(&self, class: &str, ancestor: &str) -> bool {
+        let mut current = Some(class);
+        while let Some(name) = current {
+            if name == ancestor {
+                return true;
+            }
+            if let Some(info) = self.classes.get(name)
+                && info.implements.iter().any(|i| i == ancestor)
+            {
+                return true;
+            }
+            match self.classes.get(name) {
+                Some(info) => current = info.extends.as_deref(),
+                None => return true,
+            }
+        }
+        false
+    }
+
+    /// Можно ли использовать значение типа `actual` там, где ожидается `expected`
+    fn accepts(&self, expected: &Type, actual: &Type) -> bool {
+        match (expected, actual) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Function(_), Type::Function(_)) => true,
+            (Type::Instance(expected), Type::Instance(actual)) => {
+                self.is_subclass(actual, expected)
+            }
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    fn check_block(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.check_statement(stmt);
+        }
+    }
+
+    fn check_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDecl(decl) => {
+                let declared = Type::from_optional_annotation(&decl.type_annotation);
+                if let Some(init) = &decl.initializer {
+                    let actual = self.infer(init);
+                    if !self.accepts(&declared, &actual) {
+                        self.error(
+                            init.span(),
+                            format!(
+                                "Переменная '{}' объявлена как {}, но инициализируется значением типа {}",
+                                decl.name, declared, actual
+                            ),
+                        );
+                    }
+                }
+                self.declare(&decl.name, declared);
+            }
+            Statement::FuncDecl(func) => self.check_function(func),
+            Statement::ClassDecl(class) => self.check_class(class),
+            Statement::AnnotationDecl(decl) => self.with_scope(|tc| tc.check_block(&decl.body)),
+            Statement::ModuleBlock(block) => self.check_block(&block.statements),
+            Statement::If(if_stmt) => {
+                self.infer(&if_stmt.condition);
+                self.with_scope(|tc| tc.check_block(&if_stmt.then_branch));
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.with_scope(|tc| tc.check_block(else_branch));
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.infer(&while_stmt.condition);
+                self.with_scope(|tc| tc.check_block(&while_stmt.body));
+            }
+            Statement::ForEach(foreach) => {
+                self.infer(&foreach.iterable);
+                self.with_scope(|tc| {
+                    tc.declare(&foreach.element_name, Type::Unknown);
+                    if let Some(index) = &foreach.index_name {
+                        tc.declare(index, Type::Unknown);
+                    }
+                    tc.check_block(&foreach.body);
+                });
+            }
+            Statement::Return(ret) => {
+                let actual = match &ret.value {
+                    Some(value) => self.infer(value),
+                    None => Type::Nil,
+                };
+                let expected = self.return_types.last().cloned().unwrap_or(Type::Unknown);
+                if !self.accepts(&expected, &actual) {
+                    let span = ret.value.as_ref().map_or(ret.span, Expression::span);
+                    self.error(
+                        span,
+                        format!(
+                            "Функция должна возвращать {}, а возвращает значение типа {}",
+                            expected, actual
+                        ),
+                    );
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.with_scope(|tc| tc.check_block(&try_stmt.try_body));
+                for clause in &try_stmt.catches {
+                    self.with_scope(|tc| {
+                        if let Some(var) = &clause.var {
+                            tc.declare(var, Type::Unknown);
+                        }
+                        tc.check_block(&clause.body);
+                    });
+                }
+                if let Some(finally_body) = &try_stmt.finally_body {
+                    self.with_scope(|tc| tc.check_block(finally_body));
+                }
+            }
+            Statement::Throw(throw) => {
+                self.infer(&throw.expression);
+            }
+            Statement::Assignment(assign) => self.check_assignment(&assign.target, &assign.value),
+            Statement::Expression(expr) => {
+                self.infer(expr);
+            }
+            Statement::InterfaceDecl(_) | Statement::Import(_) => {}
+        }
+    }
+
+    fn check_function(&mut self, func: &FuncDecl) {
+        self.check_body(&func.params, &func.return_type, &func.body);
+
+        // Функция без возврата возвращает ничто
+        let declared = Type::from_optional_annotation(&func.return_type);
+        if !self.accepts(&declared, &Type::Nil) && !contains_return(&func.body) {
+            self.error(
+                func.span,
+                format!(
+                    "Функция '{}' должна возвращать {}, но не содержит ни одного оператора возврат",
+                    func.name, declared
+                ),
+            );
+        }
+    }
+
+    /// Проверить тело функции, лямбды или конструктора в собственной области
+    fn check_body(
+        &mut self,
+        params: &[Parameter],
+        return_type: &Option<String>,
+        body: &[Statement],
+    ) {
+        self.return_types
+            .push(Type::from_optional_annotation(return_type));
+        self.with_scope(|tc| {
+            tc.declare_params(params);
+            tc.check_block(body);
+        });
+        self.return_types.pop();
+    }
+
+    fn declare_params(&mut self, params: &[Parameter]) {
+        for param in params {
+            let expected = Type::from_optional_annotation(&param.type_annotation);
+            if param.is_rest {
+                self.declare(&param.name, Type::Array);
+                continue;
+            }
+            if let Some(default) = &param.default {
+                let actual = self.infer(default);
+                if !self.accepts(&expected, &actual) {
+                    self.error(
+                        default.span(),
+                        format!(
+                            "Значение по умолчанию параметра '{}' должно иметь тип {}, указано значение типа {}",
+                            param.name, expected, actual
+                        ),
+                    );
+                }
+            }
+            self.declare(&param.name, expected);
+        }
+    }
+
+    fn check_class(&mut self, class: &ClassDecl) {
+        let outer = self.current_class.replace(class.name.clone());
+        for member in &class.members {
+            match member {
+                ClassMember::Method(method) | ClassMember::StaticMethod(method) => {
+                    self.check_function(method)
+                }
+                ClassMember::Constructor(ctor) => self.check_body(&ctor.params, &None, &ctor.body),
+                ClassMember::StaticField {
+                    name,
+                    type_annotation,
+                    initializer: Some(value),
+                } => {
+                    let expected = Type::from_optional_annotation(type_annotation);
+                    let actual = self.infer(value);
+                    if !self.accepts(&expected, &actual) {
+                        self.error(
+                            value.span(),
+                            format!(
+                                "Статическое поле '{}' объявлено как {}, но инициализируется значением типа {}",
+                                name, expected, actual
+                            ),
+                        );
+                    }
+                }
+                ClassMember::Field { .. } | ClassMember::StaticField { .. } => {}
+            }
+        }
+        self.current_class = outer;
+        self.check_interface_conformance(class);
+    }
+
+    /// Методы класса должны быть совместимы с аннотациями методов его
+    /// интерфейсов: те же типы параметров и результата, если они указаны
+    fn check_interface_conformance(&mut self, class: &ClassDecl) {
+        let instance = Type::Instance(class.name.clone());
+        for interface_name in &class.implements {
+            let Some(interface) = self.interfaces.get(interface_name).cloned() else {
+                continue;
+            };
+            for (method_name, expected) in interface {
+                let Some(actual) = self.method_signature(&instance, &method_name) else {
+                    continue;
+                };
+                let span = class
+                    .members
+                    .iter()
+                    .find_map(|member| match member {
+                        ClassMember::Method(method) if method.name == method_name => {
+                            Some(method.span)
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or(class.span);
+
+                let params_match = expected
+                    .params
+                    .iter()
+                    .zip(&actual.params)
+                    .all(|(expected, actual)| self.accepts(expected, actual));
+                if !params_match || !self.accepts(&expected.return_type, &actual.return_type) {
+                    self.error(
+                        span,
+                        format!(
+                            "Метод '{}' класса '{}' не совпадает с сигнатурой интерфейса '{}': ожидается {}, объявлено {}",
+                            method_name, class.name, interface_name, expected, actual
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_assignment(&mut self, target: &AssignmentTarget, value: &Expression) {
+        let actual = self.infer(value);
+        match target {
+            AssignmentTarget::Identifier(name) => {
+                let declared = self.lookup(name).cloned().unwrap_or(Type::Unknown);
+                if !self.accepts(&declared, &actual) {
+                    self.error(
+                        value.span(),
+                        format!(
+                            "Переменная '{}' объявлена как {}, ей нельзя присвоить значение типа {}",
+                            name, declared, actual
+                        ),
+                    );
+                }
+            }
+            AssignmentTarget::FieldAccess(object, field) => {
+                let object_type = self.infer(object);
+                if let Some((class, declared)) = self.field_type(&object_type, field)
+                    && !self.accepts(&declared, &actual)
+                {
+                    self.error(
+                        value.span(),
+                        format!(
+                            "Поле '{}' класса '{}' имеет тип {}, ему нельзя присвоить значение типа {}",
+                            field, class, declared, actual
+                        ),
+                    );
+                }
+            }
+            AssignmentTarget::Index(object, index) => {
+                self.infer(object);
+                self.infer(index);
+            }
+        }
+    }
+
+    /// Найти объявленный тип поля у класса экземпляра или его предков
+    fn field_type(&self, object: &Type, field: &str) -> Option<(String, Type)> {
+        let Type::Instance(class) = object else {
+            return None;
+        };
+        let mut current = Some(class.as_str());
+        while let Some(name) = current {
+            let info = self.classes.get(name)?;
+            if let Some(ty) = info.fields.get(field) {
+                return Some((name.to_string(), ty.clone()));
+            }
+            current = info.extends.as_deref();
+        }
+        None
+    }
+
+    fn method_signature(&self, object: &Type, method: &str) -> Option<Rc<Signature>> {
+        let Type::Instance(class) = object else {
+            return None;
+        };
+        let mut current = Some(class.as_str());
+        while let Some(name) = current {
+            let info = self.classes.get(name)?;
+            if let Some(signature) = info.methods.get(method) {
+                return Some(signature.clone());
+            }
+            current = info.extends.as_deref();
+        }
+        None
+    }
+
+    /// Конструктор класса; класс без конструктора наследует конструктор предка
+    fn constructor_signature(&self, class: &str) -> Option<(String, Rc<Signature>)> {
+        let mut current = Some(class);
+        while let Some(name) = current {
+            let info = self.classes.get(name)?;
+            if let Some(signature) = &info.constructor {
+                return Some((name.to_string(), signature.clone()));
+            }
+            current = info.extends.as_deref();
+        }
+        None
+    }
+
+    /// Сигнатура функции из другого модуля, если она известна таблице символов
+    fn module_signature(&self, namespace: &str, symbol: &str) -> Option<Rc<Signature>> {
+        let module = self.symbol_table.lookup(namespace)?;
+        if module.kind != SymbolKind::Module {
+            return None;
+        }
+        let symbol = self
+            .symbol_table
+            .lookup_in_scope(module.module_scope_id?, symbol)?;
+        match &symbol.type_info {
+            Some(Type::Function(signature)) => signature.clone(),
+            _ => None,
+        }
+    }
+
+    /// Сигнатура функции, импортированной без префикса списком `показать`
+    fn imported_signature(&self, name: &str) -> Option<Rc<Signature>> {
+        let symbol = self.symbol_table.lookup(name)?;
+        symbol.imported_from.as_ref()?;
+        match &symbol.type_info {
+            Some(Type::Function(signature)) => signature.clone(),
+            _ => None,
+        }
+    }
+
+    fn module_symbol_type(&self, namespace: &str, symbol: &str) -> Type {
+        self.symbol_table
+            .lookup(namespace)
+            .and_then(|module| module.module_scope_id)
+            .and_then(|scope| self.symbol_table.lookup_in_scope(scope, symbol))
+            .and_then(|symbol| symbol.type_info.clone())
+            .unwrap_or(Type::Unknown)
+    }
+
+    fn check_arguments(
+        &mut self,
+        callee: &str,
+        signature: &Signature,
+        arguments: &[Expression],
+        types: &[Type],
+    ) {
+        // Лишние аргументы проверяются по типу параметра `...остальные`;
+        // после развёрнутого массива позиции аргументов неизвестны
+        let rest = signature.params.last().filter(|_| signature.rest);
+        let expected = (0..).map_while(|index| signature.params.get(index).or(rest));
+        for (index, ((expected, actual), arg)) in expected
+            .zip(types)
+            .zip(arguments)
+            .take_while(|(_, arg)| !matches!(arg, Expression::Spread(..)))
+            .enumerate()
+        {
+            if !self.accepts(expected, actual) {
+                self.error(
+                    arg.span(),
+                    format!(
+                        "Аргумент {} {} должен иметь тип {}, передано значение типа {}",
+                        index + 1,
+                        callee,
+                        expected,
+                        actual
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: &Expression, arguments: &[Expression]) -> Type {
+        let types: Vec<Type> = arguments.iter().map(|arg| self.infer(arg)).collect();
+
+        match callee {
+            // Локальная переменная может перекрывать глобальную функцию
+            Expression::Identifier(name, _) if self.lookup(name).is_none() => {
+                if let Some(signature) = self.functions.get(name).cloned() {
+                    let callee = format!("функции '{}'", name);
+                    self.check_arguments(&callee, &signature, arguments, &types);
+                    return signature.return_type.clone();
+                }
+                if self.classes.contains_key(name) {
+                    if let Some((class, signature)) = self.constructor_signature(name) {
+                        let callee = format!("конструктора '{}'", class);
+                        self.check_arguments(&callee, &signature, arguments, &types);
+                    }
+                    return Type::Instance(name.clone());
+                }
+                if let Some(signature) = self.imported_signature(name) {
+                    let callee = format!("функции '{}'", name);
+                    self.check_arguments(&callee, &signature, arguments, &types);
+                    return signature.return_type.clone();
+                }
+                Type::Unknown
+            }
+            Expression::FieldAccess(access) => {
+                let object = self.infer(&access.object);
+                if let Some(signature) = self.method_signature(&object, &access.field) {
+                    let callee = format!("метода '{}'", access.field);
+                    self.check_arguments(&callee, &signature, arguments, &types);
+                    return signature.return_type.clone();
+                }
+                Type::Unknown
+            }
+            Expression::ModuleAccess(access) => {
+                match self.module_signature(&access.namespace, &access.symbol) {
+                    Some(signature) => {
+                        let callee = format!("функции '{}.{}'", access.namespace, access.symbol);
+                        self.check_arguments(&callee, &signature, arguments, &types);
+                        signature.return_type.clone()
+                    }
+                    None => Type::Unknown,
+                }
+            }
+            other => {
+                self.infer(other);
+                Type::Unknown
+            }
+        }
+    }
+
+    /// Вывести тип выражения, попутно проверив вложенные выражения
+    fn infer(&mut self, expr: &Expression) -> Type {
+        match expr {
+            Expression::Integer(..) | Expression::Number(..) => Type::Number,
+            Expression::String(..) => Type::String,
+            Expression::Boolean(..) => Type::Boolean,
+            Expression::Nil(_) => Type::Nil,
+            Expression::Identifier(name, _) => match self.lookup(name) {
+                Some(ty) => ty.clone(),
+                None => match self.functions.get(name) {
+                    Some(signature) => Type::Function(Some(signature.clone())),
+                    None => self
+                        .symbol_table
+                        .lookup(name)
+                        .filter(|symbol| symbol.imported_from.is_some())
+                        .and_then(|symbol| symbol.type_info.clone())
+                        .unwrap_or(Type::Unknown),
+                },
+            },
+            Expression::Binary(binary) => {
+                let left = self.infer(&binary.left);
+                let right = self.infer(&binary.right);
+                Self::binary_type(binary.operator, &left, &right)
+            }
+            Expression::Unary(unary) => {
+                self.infer(&unary.operand);
+                match unary.operator {
+                    UnaryOperator::Negate => Type::Number,
+                    UnaryOperator::Not => Type::Boolean,
+                }
+            }
+            Expression::Call(call) => self.check_call(&call.callee, &call.arguments),
+            Expression::FieldAccess(access) => {
+                let object = self.infer(&access.object);
+                match self.field_type(&object, &access.field) {
+                    Some((_, ty)) => ty,
+                    None => match self.method_signature(&object, &access.field) {
+                        Some(signature) => Type::Function(Some(signature)),
+                        None => Type::Unknown,
+                    },
+                }
+            }
+            Expression::ModuleAccess(access) => {
+                self.module_symbol_type(&access.namespace, &access.symbol)
+            }
+            Expression::Lambda(lambda) => {
+                self.check_body(&lambda.params, &lambda.return_type, &lambda.body);
+                let signature = signature_of(&lambda.params, &lambda.return_type);
+                Type::Function(Some(Rc::new(signature)))
+            }
+            Expression::Index(index) => {
+                let object = self.infer(&index.object);
+                self.infer(&index.index);
+                match object {
+                    Type::String => Type::String,
+                    _ => Type::Unknown,
+                }
+            }
+            Expression::Range(range) => {
+                for bound in [&range.start, &range.end].into_iter().flatten() {
+                    self.infer(bound);
+                }
+                Type::Unknown
+            }
+            Expression::ArrayLiteral(array) => {
+                for element in &array.elements {
+                    self.infer(element);
+                }
+                Type::Array
+            }
+            Expression::DictLiteral(dict) => {
+                for (key, value) in &dict.pairs {
+                    self.infer(key);
+                    self.infer(value);
+                }
+                Type::Dict
+            }
+            Expression::This(_) => self
+                .current_class
+                .clone()
+                .map_or(Type::Unknown, Type::Instance),
+            Expression::Super(..) => Type::Unknown,
+            Expression::Spread(value, _) => {
+                self.infer(value);
+                Type::Unknown
+            }
+        }
+    }
+
+    /// Тип результата бинарного оператора. Неподходящие операнды здесь не
+    /// проверяются: это ошибка выполнения, а не несоответствие аннотации
+    fn binary_type(operator: BinaryOperator, left: &Type, right: &Type) -> Type {
+        use BinaryOperator::*;
+
+        match operator {
+            Equal | NotEqual | Less | LessEqual | Greater | GreaterEqual => Type::Boolean,
+            // и/или возвращают один из операндов
+            And | Or if *left == Type::Boolean && *right == Type::Boolean => Type::Boolean,
+            And | Or => Type::Unknown,
+            // ?? возвращает левый операнд, если он не ничто
+            Coalesce if *left == Type::Nil => right.clone(),
+            Coalesce if left == right => left.clone(),
+            Coalesce => Type::Unknown,
+            // + склеивает строку с числом или строкой
+            Add if *left == Type::String || *right == Type::String => Type::String,
+            _ if *left == Type::Number && *right == Type::Number => Type::Number,
+            _ => Type::Unknown,
+        }
+    }
+}
+
+/// Есть ли в теле оператор возврат (не считая вложенных функций)
+fn contains_return(body: &[Statement]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        Statement::Return(_) => true,
+        Statement::If(if_stmt) => {
+            contains_return(&if_stmt.then_branch)
+                || if_stmt.else_branch.as_deref().is_some_and(contains_return)
+        }
+        Statement::While(while_stmt) => contains_return(&while_stmt.body),
+        Statement::ForEach(foreach) => contains_return(&foreach.body),
+        Statement::Try(try_stmt) => {
+            contains_return(&try_stmt.try_body)
+                || try_stmt
+                    .catches
+                    .iter()
+                    .any(|clause| contains_return(&clause.body))
+                || try_stmt
+                    .finally_body
+                    .as_deref()
+                    .is_some_and(contains_return)
+        }
+        _ => false,
+    })
+}
+
+fn class_info(class: &ClassDecl) -> ClassInfo {
+    let mut info = ClassInfo {
+        extends: class.extends.clone(),
+        implements: class.implements.clone(),
+        ..Default::default()
+    };
+    for member in &class.members {
+        match member {
+            ClassMember::Field {
+                name,
+                type_annotation,
+            } => {
+                info.fields.insert(
+                    name.clone(),
+                    Type::from_optional_annotation(type_annotation),
+                );
+            }
+            ClassMember::Method(method) => {
+                let signature = signature_of(&method.params, &method.return_type);
+                info.methods.insert(method.name.clone(), Rc::new(signature));
+            }
+            ClassMember::Constructor(ctor) => {
+                info.constructor = Some(Rc::new(signature_of(&ctor.params, &None)));
+            }
+            // Статические члены принадлежат классу, а не экземплярам
+            ClassMember::StaticField { .. } | ClassMember::StaticMethod(_) => {}
+        }
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ponos::parser::PonosParser;
+
+    fn errors(source: &str) -> Vec<String> {
+        let program = PonosParser::new().parse(source.to_string()).unwrap();
+        let symbol_table = SymbolTable::new();
+        TypeChecker::new(&symbol_table)
+            .check(&program)
+            .into_iter()
+            .map(|e| e.message)
+            .collect()
+    }
+
+    #[test]
+    fn unannotated_code_is_accepted() {
+        let source = "пер x = 1; x = \"строка\"; функ f(a) возврат a + 1; конец f(\"s\");";
+        assert!(errors(source).is_empty());
+    }
+
+    #[test]
+    fn annotated_mismatches_are_reported() {
+        let source = "пер x: число = \"один\";\n\
+                      пер y: строка = \"а\";\n\
+                      y = 2 * 3;\n\
+                      функ f(a: число, b) возврат a; конец\n\
+                      f(1, \"b\");\n\
+                      f(\"a\", 1);\n\
+                      f(1 + 2, \"b\" + 1);\n\
+                      пер z: строка = \"итог: \" + 1;";
+        assert_eq!(
+            errors(source),
+            vec![
+                "Переменная 'x' объявлена как число, но инициализируется значением типа строка",
+                "Переменная 'y' объявлена как строка, ей нельзя присвоить значение типа число",
+                "Аргумент 1 функции 'f' должен иметь тип число, передано значение типа строка",
+            ]
+        );
+    }
+
+    #[test]
+    fn nil_coalescing_keeps_operand_type() {
+        let source = "пер а: строка = ничто ?? \"по умолчанию\";\n\
+                      пер б: число = 1 ?? 2;\n\
+                      пер в: число = ничто ?? \"текст\";";
+        assert_eq!(
+            errors(source),
+            vec!["Переменная 'в' объявлена как число, но инициализируется значением типа строка"]
+        );
+    }
+
+    #[test]
+    fn return_types_are_checked() {
+        let source = "функ имя(): строка возврат 1; конец\n\
+                      функ пусто(): число конец\n\
+                      функ ничего(): ничто возврат; конец\n\
+                      пер длина_имени: число = имя();\n\
+                      пер ф = (функ(x): логический возврат \"да\"; конец);";
+        assert_eq!(
+            errors(source),
+            vec![
+                "Функция должна возвращать строка, а возвращает значение типа число",
+                "Функция 'пусто' должна возвращать число, но не содержит ни одного оператора возврат",
+                "Переменная 'длина_имени' объявлена как число, но инициализируется значением типа строка",
+                "Функция должна возвращать логический, а возвращает значение типа строка",
+            ]
+        );
+    }
+
+    #[test]
+    fn interface_signatures_are_enforced() {
+        let source = "интерфейс Фигура функ площадь(): число; конец\n\
+                      класс Квадрат реализует Фигура\n\
+                      функ площадь(): строка возврат \"много\"; конец\n\
+                      конец\n\
+                      функ показать(ф: Фигура) конец\n\
+                      показать(Квадрат());";
+        assert_eq!(
+            errors(source),
+            vec![
+                "Метод 'площадь' класса 'Квадрат' не совпадает с сигнатурой интерфейса 'Фигура': ожидается (): число, объявлено (): строка",
+            ]
+        );
+    }
+
+    #[test]
+    fn classes_fields_and_subclasses() {
+        let source = "класс Животное\n\
+                      имя: строка\n\
+                      конструктор(имя: строка) это.имя = имя; конец\n\
+                      конец\n\
+                      класс Кот наследует Животное конец\n\
+                      пер ж: Животное = Кот(\"Барсик\");\n\
+                      ж.имя = 5;\n\
+                      пер к: Кот = Животное(1);";
+        assert_eq!(
+            errors(source),
+            vec![
+                "Поле 'имя' класса 'Животное' имеет тип строка, ему нельзя присвоить значение типа число",
+                "Аргумент 1 конструктора 'Животное' должен иметь тип строка, передано значение типа число",
+                "Переменная 'к' объявлена как Кот, но инициализируется значением типа Животное",
+            ]
+        );
+    }
+}
@@ -1,33 +1,189 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Write, rc::Rc, sync::OnceLock};
 
-use crate::ponos::opcode::OpCode;
+use crate::ponos::native::threads::{Channel, ThreadOutcome};
+use crate::ponos::opcode::{LineTable, OpCode};
+use crate::ponos::typechecker::Type;
 use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
 
 /// Ключ для словаря - может быть любым типом Value
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ValueKey {
+    Int(i64),
     Number(OrderedFloat<f64>),
-    String(String),
+    String(Rc<String>),
     Boolean(bool),
 }
 
+impl From<&str> for ValueKey {
+    fn from(s: &str) -> Self {
+        ValueKey::String(Rc::new(s.to_string()))
+    }
+}
+
+impl From<String> for ValueKey {
+    fn from(s: String) -> Self {
+        ValueKey::String(Rc::new(s))
+    }
+}
+
 impl ValueKey {
     pub fn from_value(v: &Value) -> Result<ValueKey, String> {
         match v {
-            Value::Number(n) => Ok(ValueKey::Number(OrderedFloat(*n))),
+            Value::Int(n) => Ok(ValueKey::Int(*n)),
+            // Целые значения f64 приводятся к целому ключу, чтобы 1 и 1.0
+            // были одним ключом, как и при сравнении
+            Value::Number(n) => Ok(match float_to_int(*n) {
+                Some(i) => ValueKey::Int(i),
+                None => ValueKey::Number(OrderedFloat(*n)),
+            }),
             Value::String(s) => Ok(ValueKey::String(s.clone())),
             Value::Boolean(b) => Ok(ValueKey::Boolean(*b)),
-            _ => Err(
-                "Ключом словаря может быть только число, строка или булево значение".to_string(),
-            ),
+            other => Err(format!(
+                "Ключом словаря может быть только число, строка или булево значение, получено: {}",
+                Type::of_value(other)
+            )),
+        }
+    }
+
+    /// Обратное преобразование ключа в значение
+    pub fn to_value(&self) -> Value {
+        match self {
+            ValueKey::Int(n) => Value::Int(*n),
+            ValueKey::Number(n) => Value::Number(n.into_inner()),
+            ValueKey::String(s) => Value::String(s.clone()),
+            ValueKey::Boolean(b) => Value::Boolean(*b),
         }
     }
 }
 
+/// Словарь Ponos. Ключи хранятся в порядке добавления: в нём словарь
+/// обходят `для каждого`, ключи(), значения() и вывод. Запись по
+/// существующему ключу оставляет его на месте, удалённый ключ при новой
+/// записи встаёт в конец
+#[derive(Clone, Debug, Default)]
+pub struct Dict {
+    // Позиция ключа в entries
+    index: HashMap<ValueKey, usize>,
+    entries: Vec<(ValueKey, Value)>,
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &ValueKey) -> Option<&Value> {
+        self.index.get(key).map(|&pos| &self.entries[pos].1)
+    }
+
+    pub fn get_mut(&mut self, key: &ValueKey) -> Option<&mut Value> {
+        self.index.get(key).map(|&pos| &mut self.entries[pos].1)
+    }
+
+    pub fn contains_key(&self, key: &ValueKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Записать значение; вернуть прежнее, если ключ уже был
+    pub fn insert(&mut self, key: ValueKey, value: Value) -> Option<Value> {
+        if let Some(&pos) = self.index.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[pos].1, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Удалить ключ, сохранив порядок остальных
+    pub fn remove(&mut self, key: &ValueKey) -> Option<Value> {
+        let pos = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(pos);
+        for (key, _) in &self.entries[pos..] {
+            *self.index.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.entries.clear();
+    }
+
+    /// Пары в порядке добавления ключей
+    pub fn iter(&self) -> impl Iterator<Item = (&ValueKey, &Value)> {
+        self.into_iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &ValueKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl Extend<(ValueKey, Value)> for Dict {
+    fn extend<I: IntoIterator<Item = (ValueKey, Value)>>(&mut self, pairs: I) {
+        for (key, value) in pairs {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl FromIterator<(ValueKey, Value)> for Dict {
+    fn from_iter<I: IntoIterator<Item = (ValueKey, Value)>>(pairs: I) -> Self {
+        let mut dict = Dict::new();
+        dict.extend(pairs);
+        dict
+    }
+}
+
+impl<const N: usize> From<[(ValueKey, Value); N]> for Dict {
+    fn from(pairs: [(ValueKey, Value); N]) -> Self {
+        pairs.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Dict {
+    type Item = (&'a ValueKey, &'a Value);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (ValueKey, Value)>,
+        fn(&'a (ValueKey, Value)) -> (&'a ValueKey, &'a Value),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl IntoIterator for Dict {
+    type Item = (ValueKey, Value);
+    type IntoIter = std::vec::IntoIter<(ValueKey, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Value {
+    Int(i64),
     Number(f64),
-    String(String),
+    // Строки неизменяемы для программы и разделяются между копиями значения:
+    // константа, глобальная переменная и элемент массива копируются без
+    // выделения памяти, а дописывание на месте копирует строку, только если
+    // на неё ссылается кто-то ещё
+    String(Rc<String>),
     Boolean(bool),
     Nil,
     Function(Rc<Function>),
@@ -38,22 +194,83 @@ pub enum Value {
     BoundMethod(Rc<BoundMethod>),
     BoundBuiltinMethod(Rc<BoundBuiltinMethod>),
     BoundNativeMethod(Rc<BoundNativeMethod>), // Нативный метод, привязанный к Instance
-    Range(Option<f64>, Option<f64>), // (start, end) для срезов
-    Array(Rc<RefCell<Vec<Value>>>),  // Массив (изменяемый)
-    Dict(Rc<RefCell<HashMap<ValueKey, Value>>>), // Словарь (изменяемый)
+    Range(Option<f64>, Option<f64>),          // (start, end) для срезов
+    Array(Rc<RefCell<Vec<Value>>>),           // Массив (изменяемый)
+    Dict(Rc<RefCell<Dict>>),                  // Словарь (изменяемый)
+    StringBuilder(Rc<RefCell<String>>),       // Буфер построителя строк (изменяемый)
+    Resource(Rc<Resource>),                   // Ресурс ОС: сокет и т.п.
+    Task(Rc<Task>),                           // Сопрограмма
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl Value {
+    /// Число как f64: целые приводятся, для остальных значений `None`
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct NativeFnId(pub usize);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Function {
     pub arity: usize,
+    /// Адреса начала выполнения для вызовов с необязательными параметрами:
+    /// `entry_points[k]` — для вызова с `arity - (len - 1) + k` аргументами.
+    /// Пропущенные аргументы вычисляются кодом со своего адреса до начала
+    /// тела. Пусто, если у функции нет значений по умолчанию
+    pub entry_points: Vec<usize>,
+    /// Последний параметр (`...остальные`) получает массив лишних аргументов
+    pub variadic: bool,
     pub opcodes: Vec<OpCode>,
     pub constants: Vec<Value>,
     pub name: String,
     pub upvalue_count: usize,
     pub upvalue_descriptors: Vec<UpvalueDescriptor>,
+    pub source_name: Option<Rc<str>>, // Файл, в котором определена функция
+    pub lines: LineTable,             // Строки исходного кода для инструкций
+}
+
+impl Function {
+    /// Число параметров, которым аргументы передаются по позиции
+    pub fn positional_arity(&self) -> usize {
+        self.arity - usize::from(self.variadic)
+    }
+
+    /// Наименьшее число аргументов, с которым можно вызвать функцию
+    pub fn min_arity(&self) -> usize {
+        (self.positional_arity() + 1).saturating_sub(self.entry_points.len().max(1))
+    }
+
+    /// Адрес первой инструкции для вызова с `arg_count` аргументами или
+    /// `None`, если столько аргументов функция не принимает
+    pub fn entry_point(&self, arg_count: usize) -> Option<usize> {
+        let positional = self.positional_arity();
+        if arg_count < self.min_arity() || (arg_count > positional && !self.variadic) {
+            return None;
+        }
+        Some(
+            self.entry_points
+                .get(arg_count.min(positional) - self.min_arity())
+                .copied()
+                .unwrap_or(0),
+        )
+    }
+
+    /// Ожидаемое число аргументов для сообщений об ошибках
+    pub fn arity_description(&self) -> String {
+        if self.variadic {
+            format!("не менее {}", self.min_arity())
+        } else if self.min_arity() == self.arity {
+            self.arity.to_string()
+        } else {
+            format!("от {} до {}", self.min_arity(), self.arity)
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -64,7 +281,7 @@ pub struct UpvalueDescriptor {
 
 #[derive(Clone, Debug)]
 pub struct Closure {
-    pub function: Function,
+    pub function: Rc<Function>,
     pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
 }
 
@@ -76,12 +293,15 @@ pub enum Upvalue {
 
 // Структуры для ООП
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Class {
     pub name: String,
     pub methods: HashMap<String, Rc<Function>>,
     pub fields: Vec<String>,
     pub parent: Option<Rc<Class>>, // Для фазы 2 (наследование)
+    /// Статические поля и методы; изменяемы, так как класс разделяется
+    /// между всеми ссылками на него
+    pub statics: RefCell<HashMap<String, Value>>,
 }
 
 impl Class {
@@ -95,6 +315,83 @@ impl Class {
             None
         }
     }
+
+    /// Класс в цепочке наследования, где объявлен статический член `name`
+    pub fn find_static(self: &Rc<Self>, name: &str) -> Option<Rc<Class>> {
+        let mut class = Some(self.clone());
+        while let Some(current) = class {
+            if current.statics.borrow().contains_key(name) {
+                return Some(current);
+            }
+            class = current.parent.clone();
+        }
+        None
+    }
+
+    /// Совпадает ли класс с `other` или унаследован от него
+    pub fn is_subclass_of(self: &Rc<Self>, other: &Rc<Class>) -> bool {
+        let mut class = Some(self.clone());
+        while let Some(current) = class {
+            if Rc::ptr_eq(&current, other) {
+                return true;
+            }
+            class = current.parent.clone();
+        }
+        false
+    }
+}
+
+/// Дескриптор ОС, которым владеет значение Ponos
+#[derive(Debug)]
+pub enum ResourceHandle {
+    TcpStream(std::net::TcpStream),
+    UdpSocket(std::net::UdpSocket),
+    /// Поток ОС, ещё не присоединённый через присоединить()
+    Thread(std::thread::JoinHandle<ThreadOutcome>),
+    /// Канал между потоками; у каждого потока свой ресурс на общий канал
+    Channel(std::sync::Arc<Channel>),
+}
+
+/// Ресурс ОС. Освобождается явно через закрыть() или автоматически, когда
+/// VM отпускает последнюю ссылку на значение
+#[derive(Debug)]
+pub struct Resource {
+    /// Имя вида ресурса для сообщений и вывода: "TCP-сокет", "канал"
+    pub kind: &'static str,
+    pub handle: RefCell<Option<ResourceHandle>>,
+}
+
+impl Resource {
+    pub fn new(kind: &'static str, handle: ResourceHandle) -> Resource {
+        Resource {
+            kind,
+            handle: RefCell::new(Some(handle)),
+        }
+    }
+
+    /// Освободить дескриптор; повторное закрытие ничего не делает
+    pub fn close(&self) {
+        self.handle.borrow_mut().take();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.handle.borrow().is_none()
+    }
+}
+
+/// Сопрограмма, созданная `сопрограмма(функ)`. Стек и кадры незавершённой
+/// задачи хранит планировщик VM; здесь — только итог выполнения
+#[derive(Debug)]
+pub struct Task {
+    pub id: usize,
+    /// Результат завершённой задачи: значение или брошенное исключение
+    pub result: RefCell<Option<Result<Value, Value>>>,
+}
+
+impl Task {
+    pub fn is_done(&self) -> bool {
+        self.result.borrow().is_some()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -147,20 +444,121 @@ impl std::fmt::Debug for BoundNativeMethod {
     }
 }
 
+/// Глубокая копия для `копировать()`: массивы, словари, экземпляры и
+/// построители строк копируются вместе с содержимым. Значение, на которое
+/// ссылаются дважды, копируется один раз, поэтому общие части и циклы
+/// сохраняются в копии. Функции, классы, ресурсы и задачи не копируются
+pub fn deep_copy(value: &Value) -> Value {
+    DeepCopy::default().copy(value)
+}
+
+#[derive(Default)]
+struct DeepCopy {
+    // Уже скопированные значения по адресу оригинала
+    copies: HashMap<*const (), Value>,
+}
+
+impl DeepCopy {
+    fn copy(&mut self, value: &Value) -> Value {
+        let key = match value {
+            Value::Array(arr) => Rc::as_ptr(arr) as *const (),
+            Value::Dict(dict) => Rc::as_ptr(dict) as *const (),
+            Value::Instance(instance) => Rc::as_ptr(instance) as *const (),
+            Value::StringBuilder(buf) => Rc::as_ptr(buf) as *const (),
+            Value::BoundMethod(bound) => {
+                return Value::BoundMethod(Rc::new(BoundMethod {
+                    receiver: self.copy_instance(&bound.receiver),
+                    method: bound.method.clone(),
+                }));
+            }
+            other => return other.clone(),
+        };
+        if let Some(copy) = self.copies.get(&key) {
+            return copy.clone();
+        }
+
+        // Копия запоминается до обхода содержимого: ссылка на оригинал
+        // изнутри него станет ссылкой на копию
+        match value {
+            Value::Array(arr) => {
+                let copy = Rc::new(RefCell::new(Vec::new()));
+                self.copies.insert(key, Value::Array(copy.clone()));
+                let items = arr.borrow().iter().map(|item| self.copy(item)).collect();
+                *copy.borrow_mut() = items;
+                Value::Array(copy)
+            }
+            Value::Dict(dict) => {
+                let copy = Rc::new(RefCell::new(Dict::new()));
+                self.copies.insert(key, Value::Dict(copy.clone()));
+                let entries = dict
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.copy(value)))
+                    .collect();
+                *copy.borrow_mut() = entries;
+                Value::Dict(copy)
+            }
+            Value::Instance(instance) => Value::Instance(self.copy_instance(instance)),
+            Value::StringBuilder(buf) => {
+                let copy = Value::StringBuilder(Rc::new(RefCell::new(buf.borrow().clone())));
+                self.copies.insert(key, copy.clone());
+                copy
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn copy_instance(&mut self, instance: &Rc<RefCell<Instance>>) -> Rc<RefCell<Instance>> {
+        let key = Rc::as_ptr(instance) as *const ();
+        if let Some(Value::Instance(copy)) = self.copies.get(&key) {
+            return copy.clone();
+        }
+        let copy = Rc::new(RefCell::new(Instance {
+            class: instance.borrow().class.clone(),
+            fields: HashMap::new(),
+        }));
+        self.copies.insert(key, Value::Instance(copy.clone()));
+        let fields = instance
+            .borrow()
+            .fields
+            .iter()
+            .map(|(name, value)| (name.clone(), self.copy(value)))
+            .collect();
+        copy.borrow_mut().fields = fields;
+        copy
+    }
+}
+
 pub fn is_equal(a: &Value, b: &Value) -> bool {
-    match (a, b) {
-        (Value::Number(x), Value::Number(y)) => x == y,
-        (Value::String(x), Value::String(y)) => x == y,
-        (Value::Boolean(x), Value::Boolean(y)) => x == y,
-        (Value::Nil, Value::Nil) => true,
-        (Value::Range(s1, e1), Value::Range(s2, e2)) => s1 == s2 && e1 == e2,
-        // Instance сравнивается по ссылке (идентичность объектов)
-        (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+    values_equal(a, b, &mut Vec::new())
+}
+
+// `visiting` — пары массивов или словарей на пути от корня: если пара
+// встретилась снова, структуры зациклены, и дальше они сравниваются по ссылке
+fn values_equal(a: &Value, b: &Value, visiting: &mut Vec<(*const (), *const ())>) -> bool {
+    let pair = match (a, b) {
+        (Value::Array(x), Value::Array(y)) => {
+            (Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ())
+        }
+        (Value::Dict(x), Value::Dict(y)) => {
+            (Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ())
+        }
+        _ => return scalars_equal(a, b),
+    };
+    if visiting.contains(&pair) {
+        return pair.0 == pair.1;
+    }
+    visiting.push(pair);
+    let equal = match (a, b) {
         // Массивы сравниваются поэлементно
         (Value::Array(a1), Value::Array(a2)) => {
             let arr1 = a1.borrow();
             let arr2 = a2.borrow();
-            arr1.len() == arr2.len() && arr1.iter().zip(arr2.iter()).all(|(x, y)| is_equal(x, y))
+            arr1.len() == arr2.len()
+                && arr1
+                    .iter()
+                    .zip(arr2.iter())
+                    .all(|(x, y)| values_equal(x, y, visiting))
         }
         // Словари сравниваются поэлементно
         (Value::Dict(d1), Value::Dict(d2)) => {
@@ -169,21 +567,60 @@ pub fn is_equal(a: &Value, b: &Value) -> bool {
             dict1.len() == dict2.len()
                 && dict1
                     .iter()
-                    .all(|(k, v)| dict2.get(k).map_or(false, |v2| is_equal(v, v2)))
+                    .all(|(k, v)| dict2.get(k).is_some_and(|v2| values_equal(v, v2, visiting)))
+        }
+        _ => unreachable!("выше обработаны только массивы и словари"),
+    };
+    visiting.pop();
+    equal
+}
+
+fn scalars_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => x == y,
+        (Value::Number(x), Value::Number(y)) => x == y,
+        (Value::Int(i), Value::Number(f)) | (Value::Number(f), Value::Int(i)) => {
+            float_to_int(*f) == Some(*i)
+        }
+        (Value::String(x), Value::String(y)) => x == y,
+        (Value::Boolean(x), Value::Boolean(y)) => x == y,
+        (Value::Nil, Value::Nil) => true,
+        (Value::Range(s1, e1), Value::Range(s2, e2)) => s1 == s2 && e1 == e2,
+        // Instance сравнивается по ссылке (идентичность объектов)
+        (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+        // Построители, как и экземпляры, сравниваются по ссылке
+        (Value::StringBuilder(a), Value::StringBuilder(b)) => Rc::ptr_eq(a, b),
+        (Value::Resource(a), Value::Resource(b)) => Rc::ptr_eq(a, b),
+        (Value::Task(a), Value::Task(b)) => Rc::ptr_eq(a, b),
+        // Функции и классы равны только самим себе
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        (Value::Closure(a), Value::Closure(b)) => Rc::ptr_eq(a, b),
+        (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+        (Value::NativeFunction(a), Value::NativeFunction(b)) => a == b,
+        // Метод, взятый у объекта дважды, — тот же метод того же объекта
+        (Value::BoundMethod(a), Value::BoundMethod(b)) => {
+            Rc::ptr_eq(&a.receiver, &b.receiver) && Rc::ptr_eq(&a.method, &b.method)
         }
         _ => false,
     }
 }
 
-// Реализация PartialEq для Value
+// Структурное равенство для Rust-кода (тесты, HashMap, HashSet). Оператор
+// `==` языка — это is_equal: там 1 == 1.0, NaN не равен себе, а массивы
+// сравниваются поэлементно. Здесь числа сравниваются как OrderedFloat (NaN
+// равен NaN), поэтому отношение рефлексивно и Value реализует Eq
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => OrderedFloat(*a) == OrderedFloat(*b),
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
             (Value::Nil, Value::Nil) => true,
-            (Value::Range(s1, e1), Value::Range(s2, e2)) => s1 == s2 && e1 == e2,
+            (Value::Range(s1, e1), Value::Range(s2, e2)) => {
+                s1.map(OrderedFloat) == s2.map(OrderedFloat)
+                    && e1.map(OrderedFloat) == e2.map(OrderedFloat)
+            }
             (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
             (Value::Closure(a), Value::Closure(b)) => Rc::ptr_eq(a, b),
             (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
@@ -194,16 +631,663 @@ impl PartialEq for Value {
             (Value::NativeFunction(a), Value::NativeFunction(b)) => a == b,
             (Value::Array(a), Value::Array(b)) => Rc::ptr_eq(a, b),
             (Value::Dict(a), Value::Dict(b)) => Rc::ptr_eq(a, b),
+            (Value::StringBuilder(a), Value::StringBuilder(b)) => Rc::ptr_eq(a, b),
+            (Value::Resource(a), Value::Resource(b)) => Rc::ptr_eq(a, b),
+            (Value::Task(a), Value::Task(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
 }
 
-pub fn is_greater(a: &Value, b: &Value) -> bool {
+impl Value {
+    /// Адрес разделяемого содержимого: значения ссылочных типов равны в
+    /// PartialEq, только если это один и тот же объект
+    fn identity(&self) -> Option<*const ()> {
+        Some(match self {
+            Value::Function(function) => Rc::as_ptr(function) as *const (),
+            Value::Closure(closure) => Rc::as_ptr(closure) as *const (),
+            Value::Class(class) => Rc::as_ptr(class) as *const (),
+            Value::Instance(instance) => Rc::as_ptr(instance) as *const (),
+            Value::BoundMethod(bound) => Rc::as_ptr(bound) as *const (),
+            Value::BoundBuiltinMethod(bound) => Rc::as_ptr(bound) as *const (),
+            Value::BoundNativeMethod(bound) => Rc::as_ptr(bound) as *const (),
+            Value::Array(array) => Rc::as_ptr(array) as *const (),
+            Value::Dict(dict) => Rc::as_ptr(dict) as *const (),
+            Value::StringBuilder(buf) => Rc::as_ptr(buf) as *const (),
+            Value::Resource(resource) => Rc::as_ptr(resource) as *const (),
+            Value::Task(task) => Rc::as_ptr(task) as *const (),
+            _ => return None,
+        })
+    }
+}
+
+impl Eq for Value {}
+
+/// Хэш согласован с PartialEq: скаляры хэшируются по значению, ссылочные
+/// типы — по адресу объекта
+impl std::hash::Hash for Value {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Int(n) => n.hash(state),
+            Value::Number(n) => OrderedFloat(*n).hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Nil => {}
+            Value::NativeFunction(id) => id.hash(state),
+            Value::Range(start, end) => {
+                start.map(OrderedFloat).hash(state);
+                end.map(OrderedFloat).hash(state);
+            }
+            other => other.identity().hash(state),
+        }
+    }
+}
+
+/// Текстовое представление значения, как его печатает вывести()
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Number(n) => write!(f, "{}", number_to_string(*n)),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Nil => write!(f, "ничто"),
+            Value::Array(_) | Value::Dict(_) => write_container(f, self, &mut Vec::new()),
+            Value::StringBuilder(buf) => write!(f, "{}", buf.borrow()),
+            Value::Resource(resource) => write!(f, "<{}>", resource.kind),
+            Value::Task(task) => write!(f, "<задача {}>", task.id),
+            Value::Function(function) => write_function(f, &function.name),
+            Value::Closure(closure) => write_function(f, &closure.function.name),
+            Value::NativeFunction(_) => write!(f, "<нативная функция>"),
+            Value::Class(class) => write!(f, "<класс {}>", class.name),
+            Value::Instance(instance) => write!(f, "<объект {}>", instance.borrow().class.name),
+            Value::BoundMethod(bound) => write!(
+                f,
+                "<метод {}.{}>",
+                bound.receiver.borrow().class.name,
+                bound.method.name
+            ),
+            Value::BoundBuiltinMethod(bound) => {
+                write!(f, "<встроенный метод {}>", Type::of_value(&bound.receiver))
+            }
+            Value::BoundNativeMethod(bound) => {
+                write!(f, "<метод {}>", bound.receiver.borrow().class.name)
+            }
+            Value::Range(start, end) => {
+                let bound = |b: &Option<f64>| b.map(number_to_string).unwrap_or_default();
+                write!(f, "{}:{}", bound(start), bound(end))
+            }
+        }
+    }
+}
+
+// `visiting` — массивы и словари на пути от корня, как в стд/json: контейнер,
+// встреченный внутри самого себя, печатается как [...] или {...}
+fn write_container(
+    f: &mut std::fmt::Formatter<'_>,
+    value: &Value,
+    visiting: &mut Vec<*const ()>,
+) -> std::fmt::Result {
+    let (container, placeholder) = match value {
+        Value::Array(arr) => (Rc::as_ptr(arr) as *const (), "[...]"),
+        Value::Dict(dict) => (Rc::as_ptr(dict) as *const (), "{...}"),
+        other => return write!(f, "{}", other),
+    };
+    if visiting.contains(&container) {
+        return write!(f, "{}", placeholder);
+    }
+    visiting.push(container);
+    match value {
+        Value::Array(arr) => {
+            write!(f, "[")?;
+            for (i, item) in arr.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_container(f, item, visiting)?;
+            }
+            write!(f, "]")?;
+        }
+        Value::Dict(dict) => {
+            write!(f, "{{")?;
+            for (i, (k, v)) in dict.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                match k {
+                    ValueKey::String(s) => write!(f, "\"{}\": ", s)?,
+                    ValueKey::Int(n) => write!(f, "{}: ", n)?,
+                    ValueKey::Number(n) => write!(f, "{}: ", n)?,
+                    ValueKey::Boolean(b) => write!(f, "{}: ", b)?,
+                }
+                write_container(f, v, visiting)?;
+            }
+            write!(f, "}}")?;
+        }
+        _ => unreachable!("выше обработаны только массивы и словари"),
+    }
+    visiting.pop();
+    Ok(())
+}
+
+fn write_function(f: &mut std::fmt::Formatter<'_>, name: &str) -> std::fmt::Result {
+    match name {
+        "<lambda>" => write!(f, "<функция>"),
+        name => write!(f, "<функция {}>", name),
+    }
+}
+
+// Преобразования между типами Rust и значениями Ponos для встраивания интерпретатора
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(Rc::new(s.to_string()))
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(Rc::new(s))
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        let items = items.into_iter().map(Into::into).collect();
+        Value::Array(Rc::new(RefCell::new(items)))
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Nil, Into::into)
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(n) => Ok(n as f64),
+            Value::Number(n) => Ok(n),
+            other => Err(format!("Ожидалось число, получено {:?}", other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(n) => Ok(n),
+            Value::Number(n) => {
+                float_to_int(n).ok_or_else(|| format!("Ожидалось целое число, получено {}", n))
+            }
+            other => Err(format!("Ожидалось целое число, получено {:?}", other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            other => Err(format!("Ожидалось булево значение, получено {:?}", other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(Rc::try_unwrap(s).unwrap_or_else(|s| (*s).clone())),
+            other => Err(format!("Ожидалась строка, получено {:?}", other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<Value> {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(items) => Ok(items.borrow().clone()),
+            other => Err(format!("Ожидался массив, получено {:?}", other)),
+        }
+    }
+}
+
+/// Сколько целых чисел, начиная с 0, хранится в кэше строковых представлений
+const CACHED_INTEGERS: usize = 1024;
+
+/// Строки для небольших неотрицательных целых. Таблица заполняется один раз,
+/// дальше чтение не требует блокировок
+static INTEGER_STRINGS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Дописать число в строку в том же виде, что и `format!("{}", n)`.
+/// Целые значения форматируются без разбора f64, небольшие берутся из кэша
+pub fn write_number(out: &mut String, n: f64) {
+    let is_integer = n.fract() == 0.0 && !(n == 0.0 && n.is_sign_negative());
+
+    if is_integer && (0.0..CACHED_INTEGERS as f64).contains(&n) {
+        let table =
+            INTEGER_STRINGS.get_or_init(|| (0..CACHED_INTEGERS).map(|i| i.to_string()).collect());
+        out.push_str(&table[n as usize]);
+    } else if is_integer && n.abs() < 1e15 {
+        let _ = write!(out, "{}", n as i64);
+    } else {
+        let _ = write!(out, "{}", n);
+    }
+}
+
+/// Дописать целое число в строку; небольшие берутся из того же кэша
+pub fn write_int(out: &mut String, n: i64) {
+    if (0..CACHED_INTEGERS as i64).contains(&n) {
+        let table =
+            INTEGER_STRINGS.get_or_init(|| (0..CACHED_INTEGERS).map(|i| i.to_string()).collect());
+        out.push_str(&table[n as usize]);
+    } else {
+        let _ = write!(out, "{}", n);
+    }
+}
+
+/// `цель = цель + справа` без копирования строки: если цель — строка, а
+/// справа строка или число, оно дописывается в её буфер. `false` — сложение
+/// нужно выполнить обычным путём
+pub fn append_in_place(target: &mut Value, right: &Value) -> bool {
+    let Value::String(out) = target else {
+        return false;
+    };
+    if !matches!(right, Value::String(_) | Value::Int(_) | Value::Number(_)) {
+        return false;
+    }
+    let out = Rc::make_mut(out);
+    match right {
+        Value::String(s) => out.push_str(s),
+        Value::Int(n) => write_int(out, *n),
+        Value::Number(n) => write_number(out, *n),
+        _ => return false,
+    }
+    true
+}
+
+/// Строковое представление числа (см. `write_number`)
+pub fn number_to_string(n: f64) -> String {
+    let mut out = String::new();
+    write_number(&mut out, n);
+    out
+}
+
+/// Целое значение f64, если оно точно представимо в i64
+pub fn float_to_int(n: f64) -> Option<i64> {
+    // i64::MAX as f64 == 2^63 уже не помещается в i64, поэтому граница открытая
+    if n.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&n) {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
+
+/// Арифметика над числами с продвижением типов: два целых дают целое, если
+/// `int_op` не переполнился, иначе (и для смешанных операндов) результат
+/// вычисляется `float_op` над f64. `None` — один из операндов не число
+pub fn arithmetic(
+    a: &Value,
+    b: &Value,
+    int_op: fn(i64, i64) -> Option<i64>,
+    float_op: fn(f64, f64) -> f64,
+) -> Option<Value> {
     match (a, b) {
-        (Value::Number(x), Value::Number(y)) => x > y,
-        (Value::String(x), Value::String(y)) => x > y,
-        (Value::Boolean(x), Value::Boolean(y)) => x > y,
-        _ => false,
+        (Value::Int(x), Value::Int(y)) => Some(match int_op(*x, *y) {
+            Some(n) => Value::Int(n),
+            None => Value::Number(float_op(*x as f64, *y as f64)),
+        }),
+        _ => Some(Value::Number(float_op(a.as_number()?, b.as_number()?))),
+    }
+}
+
+/// Порядок значений для операторов `<`, `>`, `<=`, `>=` и сортировки.
+/// Числа (целые и вещественные) сравниваются по значению, строки —
+/// лексикографически по кодам символов, `ложь` меньше `истины`. Значения
+/// разных типов и неупорядочиваемые значения (массивы, словари, объекты) —
+/// ошибка. `Ok(None)` — сравнение с NaN, при котором ложны все операторы
+pub fn compare(a: &Value, b: &Value) -> Result<Option<Ordering>, String> {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => Ok(Some(x.cmp(y))),
+        (Value::String(x), Value::String(y)) => Ok(Some(x.cmp(y))),
+        (Value::Boolean(x), Value::Boolean(y)) => Ok(Some(x.cmp(y))),
+        _ => match (a.as_number(), b.as_number()) {
+            (Some(x), Some(y)) => Ok(x.partial_cmp(&y)),
+            _ => Err(format!(
+                "Нельзя сравнить значения типов {} и {}",
+                Type::of_value(a),
+                Type::of_value(b)
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_number_matches_display() {
+        let samples = [
+            0.0,
+            -0.0,
+            1.0,
+            42.0,
+            1023.0,
+            1024.0,
+            -7.0,
+            1.5,
+            -0.25,
+            1e15,
+            1e21,
+            123456789012.0,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for n in samples {
+            assert_eq!(number_to_string(n), format!("{}", n), "для {:?}", n);
+        }
+    }
+
+    #[test]
+    fn dict_keeps_insertion_order() {
+        let key = |s: &str| ValueKey::String(s.to_string().into());
+        let mut dict = Dict::from([(key("в"), Value::Int(1)), (key("а"), Value::Int(2))]);
+        dict.insert(ValueKey::Int(7), Value::Int(3));
+        assert_eq!(dict.insert(key("в"), Value::Int(4)), Some(Value::Int(1)));
+        assert_eq!(dict.remove(&key("а")), Some(Value::Int(2)));
+        dict.insert(key("а"), Value::Int(5));
+
+        let keys: Vec<_> = dict.keys().cloned().collect();
+        assert_eq!(keys, vec![key("в"), ValueKey::Int(7), key("а")]);
+        assert_eq!(dict.get(&ValueKey::Int(7)), Some(&Value::Int(3)));
+        assert_eq!(dict.get(&key("а")), Some(&Value::Int(5)));
+    }
+
+    #[test]
+    fn deep_copy_preserves_shared_parts_and_cycles() {
+        let shared = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1)])));
+        let outer = Rc::new(RefCell::new(vec![shared.clone(), shared]));
+        outer.borrow_mut().push(Value::Array(outer.clone()));
+
+        let Value::Array(copy) = deep_copy(&Value::Array(outer.clone())) else {
+            unreachable!();
+        };
+        assert!(!Rc::ptr_eq(&copy, &outer));
+        let items = copy.borrow().clone();
+        let [
+            Value::Array(first),
+            Value::Array(second),
+            Value::Array(cycle),
+        ] = &items[..]
+        else {
+            unreachable!();
+        };
+        assert!(Rc::ptr_eq(first, second));
+        assert!(Rc::ptr_eq(cycle, &copy));
+        first.borrow_mut().push(Value::Int(2));
+        let original = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1)])));
+        assert!(is_equal(&outer.borrow()[0], &original));
+
+        // Разорвать циклы, чтобы не оставлять утечек в тестах
+        outer.borrow_mut().clear();
+        copy.borrow_mut().clear();
+    }
+
+    #[test]
+    fn integers_compare_with_floats_by_value() {
+        assert!(is_equal(&Value::Int(2), &Value::Number(2.0)));
+        assert!(!is_equal(&Value::Int(2), &Value::Number(2.5)));
+        // 2^53 + 1 не представимо в f64 и не равно ближайшему f64
+        assert!(!is_equal(
+            &Value::Int(9_007_199_254_740_993),
+            &Value::Number(9_007_199_254_740_992.0)
+        ));
+        assert_eq!(
+            compare(&Value::Number(2.5), &Value::Int(2)),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            compare(&Value::Int(3), &Value::Number(2.5)),
+            Ok(Some(Ordering::Greater))
+        );
+
+        assert_eq!(
+            ValueKey::from_value(&Value::Number(1.0)),
+            ValueKey::from_value(&Value::Int(1))
+        );
+        assert_eq!(
+            ValueKey::from_value(&Value::Number(1.5))
+                .unwrap()
+                .to_value(),
+            Value::Number(1.5)
+        );
+    }
+
+    #[test]
+    fn arithmetic_promotes_to_float() {
+        let add = |a: Value, b: Value| arithmetic(&a, &b, i64::checked_add, |x, y| x + y);
+        assert_eq!(add(Value::Int(2), Value::Int(3)), Some(Value::Int(5)));
+        assert_eq!(
+            add(Value::Int(2), Value::Number(0.5)),
+            Some(Value::Number(2.5))
+        );
+        assert_eq!(
+            add(Value::Int(i64::MAX), Value::Int(1)),
+            Some(Value::Number(i64::MAX as f64 + 1.0))
+        );
+        assert_eq!(add(Value::Int(1), Value::Nil), None);
+    }
+
+    #[test]
+    fn strings_are_ordered_and_mixed_types_are_errors() {
+        let string = |s: &str| Value::String(s.to_string().into());
+        assert_eq!(
+            compare(&string("а"), &string("б")),
+            Ok(Some(Ordering::Less))
+        );
+        assert_eq!(
+            compare(&string("яблоко"), &string("ябл")),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            compare(&string("кот"), &string("кот")),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(
+            compare(&Value::Boolean(false), &Value::Boolean(true)),
+            Ok(Some(Ordering::Less))
+        );
+        assert_eq!(compare(&Value::Number(f64::NAN), &Value::Int(1)), Ok(None));
+        assert_eq!(
+            compare(&string("1"), &Value::Int(1)),
+            Err("Нельзя сравнить значения типов строка и число".to_string())
+        );
+        assert!(compare(&Value::Nil, &Value::Nil).is_err());
+    }
+
+    #[test]
+    fn append_in_place_copies_shared_strings() {
+        let original = Value::from("аб");
+        let mut copy = original.clone();
+        assert!(append_in_place(&mut copy, &Value::Int(1)));
+        assert_eq!(copy, Value::from("аб1"));
+        assert_eq!(original, Value::from("аб"));
+
+        // Единственная ссылка: строка дописывается в тот же буфер
+        let Value::String(buffer) = &copy else {
+            unreachable!()
+        };
+        let before = Rc::as_ptr(buffer);
+        assert!(append_in_place(&mut copy, &Value::from("в")));
+        assert!(matches!(&copy, Value::String(s) if Rc::as_ptr(s) == before && **s == "аб1в"));
+
+        assert!(!append_in_place(&mut copy, &Value::Nil));
+        assert!(!append_in_place(&mut Value::Int(1), &Value::from("в")));
+    }
+
+    #[test]
+    fn hash_agrees_with_equality() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        let array = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1)])));
+        let same_items = Value::Array(Rc::new(RefCell::new(vec![Value::Int(1)])));
+
+        assert_eq!(hash(&Value::from("кот")), hash(&Value::from("кот")));
+        assert_eq!(hash(&Value::Number(0.0)), hash(&Value::Number(-0.0)));
+        assert_eq!(hash(&array), hash(&array.clone()));
+        assert_ne!(array, same_items);
+        assert_ne!(hash(&array), hash(&same_items));
+
+        // Изменяемое содержимое массивов и объектов не влияет на хэш: он
+        // берётся по адресу
+        #[allow(clippy::mutable_key_type)]
+        let set: std::collections::HashSet<Value> =
+            [Value::Int(1), Value::Int(1), Value::from("1"), Value::Nil]
+                .into_iter()
+                .collect();
+        assert_eq!(set.len(), 3);
+        assert_eq!(Value::Number(f64::NAN), Value::Number(f64::NAN));
+    }
+
+    #[test]
+    fn every_value_has_display() {
+        let function = Rc::new(Function {
+            name: "сложить".to_string(),
+            ..Function::default()
+        });
+        let lambda = Rc::new(Function {
+            name: "<lambda>".to_string(),
+            ..Function::default()
+        });
+        let class = Rc::new(Class {
+            name: "Точка".to_string(),
+            ..Class::default()
+        });
+        let instance = Rc::new(RefCell::new(Instance {
+            class: class.clone(),
+            fields: HashMap::new(),
+        }));
+
+        assert_eq!(
+            Value::Function(function.clone()).to_string(),
+            "<функция сложить>"
+        );
+        assert_eq!(Value::Function(lambda).to_string(), "<функция>");
+        assert_eq!(Value::Class(class).to_string(), "<класс Точка>");
+        assert_eq!(
+            Value::Instance(instance.clone()).to_string(),
+            "<объект Точка>"
+        );
+        let bound = BoundMethod {
+            receiver: instance,
+            method: function,
+        };
+        assert_eq!(
+            Value::BoundMethod(Rc::new(bound)).to_string(),
+            "<метод Точка.сложить>"
+        );
+        assert_eq!(
+            Value::NativeFunction(NativeFnId(0)).to_string(),
+            "<нативная функция>"
+        );
+        assert_eq!(Value::Range(Some(1.0), None).to_string(), "1:");
+        assert_eq!(Value::Range(None, Some(2.5)).to_string(), ":2.5");
+    }
+
+    #[test]
+    fn cyclic_values_print_and_compare() {
+        let cyclic = || {
+            let array = Rc::new(RefCell::new(vec![Value::Int(1)]));
+            array.borrow_mut().push(Value::Array(array.clone()));
+            Value::Array(array)
+        };
+        let array = cyclic();
+        let dict = Rc::new(RefCell::new(Dict::new()));
+        let key = ValueKey::String("я".to_string().into());
+        dict.borrow_mut()
+            .insert(key.clone(), Value::Dict(dict.clone()));
+        let dict = Value::Dict(dict);
+
+        assert_eq!(array.to_string(), "[1, [...]]");
+        assert_eq!(dict.to_string(), "{\"я\": {...}}");
+        assert!(is_equal(&array, &array));
+        assert!(is_equal(&dict, &dict));
+        assert!(!is_equal(&array, &cyclic()));
+
+        // Общая часть без цикла печатается полностью
+        let shared = Value::from(vec![1]);
+        let pair = Value::from(vec![shared.clone(), shared]);
+        assert_eq!(pair.to_string(), "[[1], [1]]");
+    }
+
+    #[test]
+    fn functions_and_classes_equal_themselves() {
+        let function = Value::Function(Rc::new(Function::default()));
+        let other = Value::Function(Rc::new(Function::default()));
+        let class = Rc::new(Class::default());
+        let instance = Rc::new(RefCell::new(Instance {
+            class: class.clone(),
+            fields: HashMap::new(),
+        }));
+        let method = Rc::new(Function::default());
+        let bound = || {
+            Value::BoundMethod(Rc::new(BoundMethod {
+                receiver: instance.clone(),
+                method: method.clone(),
+            }))
+        };
+
+        assert!(is_equal(&function, &function));
+        assert!(!is_equal(&function, &other));
+        assert!(is_equal(&Value::Class(class.clone()), &Value::Class(class)));
+        assert!(is_equal(
+            &Value::NativeFunction(NativeFnId(3)),
+            &Value::NativeFunction(NativeFnId(3))
+        ));
+        assert!(is_equal(&bound(), &bound()));
     }
 }
@@ -0,0 +1,419 @@
+use crate::ponos::opcode::OpCode;
+use crate::ponos::value::{Function, Value};
+
+// Проверка байткода перед выполнением. VM доверяет операндам инструкций:
+// адрес перехода или индекс константы за пределами байткода, как и снятие
+// значения с пустого стека, приводят к панике посреди выполнения. Проверка
+// находит такие ошибки в байткоде, собранном вручную или повреждённом, до
+// запуска. Операнды глобальных инструкций проверяются в виде, который даёт
+// генератор: индекс имени в пуле констант, а не номер слота
+
+/// Проверить байткод верхнего уровня и все функции из его пула констант
+pub fn verify(opcodes: &[OpCode], constants: &[Value]) -> Result<(), String> {
+    Verifier {
+        name: "<главная>",
+        opcodes,
+        constants,
+        upvalue_count: 0,
+    }
+    .verify(&[0], 0)
+}
+
+/// Проверить функцию и вложенные в неё функции
+pub fn verify_function(function: &Function) -> Result<(), String> {
+    if function.upvalue_count != function.upvalue_descriptors.len() {
+        return Err(format!(
+            "функция {}: {} захваченных переменных, а описаний захвата {}",
+            function.name,
+            function.upvalue_count,
+            function.upvalue_descriptors.len()
+        ));
+    }
+    // Без значений по умолчанию выполнение начинается с адреса 0. При вызове
+    // на стеке уже лежат все параметры: недостающие аргументы равны ничто
+    let mut entry_points = function.entry_points.clone();
+    if entry_points.is_empty() {
+        entry_points.push(0);
+    }
+    Verifier {
+        name: &function.name,
+        opcodes: &function.opcodes,
+        constants: &function.constants,
+        upvalue_count: function.upvalue_count,
+    }
+    .verify(&entry_points, function.arity)
+}
+
+struct Verifier<'a> {
+    name: &'a str,
+    opcodes: &'a [OpCode],
+    constants: &'a [Value],
+    upvalue_count: usize,
+}
+
+impl Verifier<'_> {
+    fn verify(&self, entry_points: &[usize], initial_depth: usize) -> Result<(), String> {
+        for (ip, opcode) in self.opcodes.iter().enumerate() {
+            self.check_operands(ip, opcode)?;
+        }
+        for &entry in entry_points {
+            if entry > self.opcodes.len() {
+                return Err(format!(
+                    "функция {}: точка входа {} за пределами байткода из {} инструкций",
+                    self.name,
+                    entry,
+                    self.opcodes.len()
+                ));
+            }
+        }
+        self.check_stack(entry_points, initial_depth)?;
+
+        for constant in self.constants {
+            match constant {
+                Value::Function(function) => verify_function(function)?,
+                Value::Closure(closure) => verify_function(&closure.function)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn error(&self, ip: usize, message: String) -> String {
+        format!(
+            "функция {}, инструкция {} ({:?}): {}",
+            self.name, ip, self.opcodes[ip], message
+        )
+    }
+
+    fn check_operands(&self, ip: usize, opcode: &OpCode) -> Result<(), String> {
+        match *opcode {
+            OpCode::Jump(addr)
+            | OpCode::JumpIfTrue(addr)
+            | OpCode::JumpIfFalse(addr)
+            | OpCode::PushExceptionHandler(addr)
+                if addr as usize > self.opcodes.len() =>
+            {
+                return Err(self.error(
+                    ip,
+                    format!(
+                        "адрес перехода за пределами байткода из {} инструкций",
+                        self.opcodes.len()
+                    ),
+                ));
+            }
+            OpCode::GetUpvalue(idx) | OpCode::SetUpvalue(idx)
+                if idx as usize >= self.upvalue_count =>
+            {
+                return Err(self.error(
+                    ip,
+                    format!("у функции {} захваченных переменных", self.upvalue_count),
+                ));
+            }
+            OpCode::Closure(idx, upvalue_count) => {
+                let Some(Value::Function(function)) = self.constants.get(idx as usize) else {
+                    return Err(self.error(ip, "в пуле констант должна быть функция".to_string()));
+                };
+                if upvalue_count as usize != function.upvalue_count {
+                    return Err(self.error(
+                        ip,
+                        format!(
+                            "функция {} захватывает {} переменных",
+                            function.name, function.upvalue_count
+                        ),
+                    ));
+                }
+                // Переменные, которые замыкание берёт из захваченных его
+                // создателем, должны у создателя быть
+                if let Some(descriptor) = function.upvalue_descriptors.iter().find(|descriptor| {
+                    !descriptor.is_local && descriptor.index >= self.upvalue_count
+                }) {
+                    return Err(self.error(
+                        ip,
+                        format!(
+                            "замыкание захватывает переменную {}, а у функции {} захваченных переменных",
+                            descriptor.index, self.upvalue_count
+                        ),
+                    ));
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let Some(idx) = self.constant_index(opcode) else {
+            return Ok(());
+        };
+        match self.constants.get(idx) {
+            None => Err(self.error(
+                ip,
+                format!(
+                    "индекс константы за пределами пула из {} констант",
+                    self.constants.len()
+                ),
+            )),
+            Some(Value::String(_)) => Ok(()),
+            Some(_) if matches!(opcode, OpCode::Constant(_)) => Ok(()),
+            Some(other) => Err(self.error(
+                ip,
+                format!(
+                    "имя в пуле констант должно быть строкой, получено {:?}",
+                    other
+                ),
+            )),
+        }
+    }
+
+    // Индекс константы в операнде; у глобальных инструкций до связывания это
+    // индекс имени переменной
+    fn constant_index(&self, opcode: &OpCode) -> Option<usize> {
+        match *opcode {
+            OpCode::DefineGlobal(idx)
+            | OpCode::SetGlobal(idx)
+            | OpCode::GetGlobal(idx)
+            | OpCode::AppendGlobal(idx) => Some(idx as usize),
+            _ => opcode.constant_operand(),
+        }
+    }
+
+    /// Пройти по всем путям выполнения и убедиться, что ни одна инструкция
+    /// не снимает со стека больше значений, чем на нём есть. Для каждой
+    /// инструкции запоминается наименьшая глубина стека кадра, с которой до
+    /// неё можно дойти
+    fn check_stack(&self, entry_points: &[usize], initial_depth: usize) -> Result<(), String> {
+        let mut depths: Vec<Option<usize>> = vec![None; self.opcodes.len() + 1];
+        let mut pending: Vec<(usize, usize)> = entry_points
+            .iter()
+            .map(|&entry| (entry, initial_depth))
+            .collect();
+
+        while let Some((ip, depth)) = pending.pop() {
+            match depths[ip] {
+                Some(known) if known <= depth => continue,
+                _ => depths[ip] = Some(depth),
+            }
+            // Конец байткода — неявный возврат из функции
+            let Some(&opcode) = self.opcodes.get(ip) else {
+                continue;
+            };
+
+            let (pops, pushes) = stack_effect(opcode);
+            if pops > depth {
+                return Err(self.error(
+                    ip,
+                    format!(
+                        "снимает со стека {} значений, а на нём не больше {}",
+                        pops, depth
+                    ),
+                ));
+            }
+            let mut next = depth - pops + pushes;
+            match opcode {
+                // Значение уходит в слот локальной переменной, стек кадра
+                // дорастает до этого слота
+                OpCode::DefineLocal(slot) | OpCode::SetLocal(slot) => {
+                    next = next.max((slot + 1) as usize)
+                }
+                OpCode::AppendLocal(slot) if slot as usize >= next => {
+                    return Err(self.error(
+                        ip,
+                        format!("слот {} ещё не занят: на стеке {} значений", slot, next),
+                    ));
+                }
+                _ => {}
+            }
+
+            match opcode {
+                OpCode::Jump(addr) => pending.push((addr as usize, next)),
+                OpCode::JumpIfTrue(addr) | OpCode::JumpIfFalse(addr) => {
+                    pending.push((addr as usize, next));
+                    pending.push((ip + 1, next));
+                }
+                // Обработчик получает стек, каким он был при входе в try,
+                // и значение исключения на вершине
+                OpCode::PushExceptionHandler(addr) => {
+                    pending.push((addr as usize, next + 1));
+                    pending.push((ip + 1, next));
+                }
+                OpCode::Throw | OpCode::Return_ => {}
+                _ => pending.push((ip + 1, next)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Сколько значений инструкция снимает со стека и сколько кладёт на него
+pub(crate) fn stack_effect(opcode: OpCode) -> (usize, usize) {
+    match opcode {
+        OpCode::Constant(_)
+        | OpCode::True_
+        | OpCode::False_
+        | OpCode::GetLocal(_)
+        | OpCode::Closure(..)
+        | OpCode::GetUpvalue(_)
+        | OpCode::Class(_)
+        | OpCode::GetGlobal(_) => (0, 1),
+        OpCode::Pop
+        | OpCode::JumpIfTrue(_)
+        | OpCode::JumpIfFalse(_)
+        | OpCode::Throw
+        | OpCode::DefineLocal(_)
+        | OpCode::SetLocal(_)
+        | OpCode::AppendLocal(_)
+        | OpCode::SetUpvalue(_)
+        | OpCode::DefineGlobal(_)
+        | OpCode::SetGlobal(_)
+        | OpCode::AppendGlobal(_) => (1, 0),
+        OpCode::Dup => (1, 2),
+        OpCode::Negate
+        | OpCode::Not
+        | OpCode::GetProperty(_)
+        | OpCode::GetSuper(_)
+        | OpCode::Iterable => (1, 1),
+        OpCode::Add
+        | OpCode::Sub
+        | OpCode::Mul
+        | OpCode::Div
+        | OpCode::Mod
+        | OpCode::Eql
+        | OpCode::Greater
+        | OpCode::Less
+        | OpCode::Inherit
+        | OpCode::DefineMethod(_)
+        | OpCode::DefineStatic(_)
+        | OpCode::SetProperty(_)
+        | OpCode::InstanceOf
+        | OpCode::GetIndex => (2, 1),
+        OpCode::SetIndex => (3, 1),
+        // Вызываемое значение и аргументы заменяются результатом
+        OpCode::Call(count) | OpCode::CallSpread(count) => ((count + 1) as usize, 1),
+        OpCode::Array(count) => (count as usize, 1),
+        OpCode::Dict(count) => ((2 * count) as usize, 1),
+        // Возврат без значения на стеке возвращает ничто
+        OpCode::Return_ => (0, 0),
+        OpCode::CloseUpvalues(_)
+        | OpCode::Jump(_)
+        | OpCode::PushExceptionHandler(_)
+        | OpCode::PopExceptionHandler
+        | OpCode::Halt => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> crate::ponos::generator::GenContext {
+        let program = crate::ponos::parser::PonosParser::new()
+            .parse(source.to_string())
+            .unwrap();
+        crate::ponos::Generator::new()
+            .generate(crate::ponos::ast::AstNode::Program(program))
+            .unwrap()
+    }
+
+    #[test]
+    fn accepts_generated_code() {
+        let ctx = compile(
+            "функ f(а, б = 2, ...в)\n    пер с = а + б;\n    с = с + 1;\n    попытка\n        исключение \"х\";\n    перехват е\n        с = 0;\n    конец\n    возврат функ() возврат с; конец;\nконец\n\
+             класс А\n    х\n    функ получить()\n        возврат это.х;\n    конец\nконец\n\
+             функ г()\n    для каждого э из [1, 2]\n        вывести({\"к\": э}[\"к\"]);\n    конец\nконец",
+        );
+        assert_eq!(verify(&ctx.opcodes, &ctx.constants), Ok(()));
+    }
+
+    #[test]
+    fn rejects_out_of_range_operands() {
+        let constants = vec![Value::Int(1)];
+        let error = verify(&[OpCode::Jump(5), OpCode::Halt], &constants).unwrap_err();
+        assert_eq!(
+            error,
+            "функция <главная>, инструкция 0 (Jump(5)): адрес перехода за пределами байткода из 2 инструкций"
+        );
+
+        let error = verify(&[OpCode::Constant(1)], &constants).unwrap_err();
+        assert!(
+            error.contains("индекс константы за пределами пула"),
+            "{}",
+            error
+        );
+
+        // Имя свойства — строка, а не число
+        let error = verify(&[OpCode::Constant(0), OpCode::GetProperty(0)], &constants).unwrap_err();
+        assert!(
+            error.contains("имя в пуле констант должно быть строкой"),
+            "{}",
+            error
+        );
+
+        let error = verify(&[OpCode::GetUpvalue(0)], &constants).unwrap_err();
+        assert!(
+            error.contains("у функции 0 захваченных переменных"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_stack_underflow_on_any_path() {
+        let constants = vec![Value::Int(1)];
+        assert_eq!(
+            verify(&[OpCode::Constant(0), OpCode::Pop], &constants),
+            Ok(())
+        );
+
+        // Второе слагаемое кладётся на стек только на одном из путей
+        let opcodes = [
+            OpCode::Constant(0),
+            OpCode::True_,
+            OpCode::JumpIfFalse(4),
+            OpCode::Constant(0),
+            OpCode::Add,
+        ];
+        let error = verify(&opcodes, &constants).unwrap_err();
+        assert_eq!(
+            error,
+            "функция <главная>, инструкция 4 (Add): снимает со стека 2 значений, а на нём не больше 1"
+        );
+
+        // Обработчик исключения получает значение исключения на стеке
+        let opcodes = [
+            OpCode::PushExceptionHandler(3),
+            OpCode::PopExceptionHandler,
+            OpCode::Jump(4),
+            OpCode::Pop,
+            OpCode::Halt,
+        ];
+        assert_eq!(verify(&opcodes, &constants), Ok(()));
+    }
+
+    #[test]
+    fn checks_nested_functions_and_entry_points() {
+        let ctx = compile("функ f(а = 1)\n    возврат а;\nконец");
+        let Some(Value::Function(function)) = ctx
+            .constants
+            .iter()
+            .find(|constant| matches!(constant, Value::Function(_)))
+        else {
+            panic!("нет функции в пуле констант");
+        };
+        assert_eq!(verify_function(function), Ok(()));
+
+        let mut broken = (**function).clone();
+        broken.entry_points = vec![0, broken.opcodes.len() + 1];
+        let error = verify_function(&broken).unwrap_err();
+        assert!(error.contains("точка входа"), "{}", error);
+
+        broken = (**function).clone();
+        broken.opcodes.insert(0, OpCode::Add);
+        broken.entry_points.clear();
+        broken.arity = 1;
+        let constants = vec![Value::Function(std::rc::Rc::new(broken))];
+        let error = verify(&[OpCode::Constant(0)], &constants).unwrap_err();
+        assert!(
+            error.starts_with("функция f, инструкция 0 (Add)"),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,31 +1,222 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+    },
+};
 
 use crate::ponos::{
-    native::builtin_methods::{BuiltinMethodRegistry, TypeDiscriminant},
-    opcode::OpCode,
+    exceptions::{self, ErrorClasses},
+    generator::GenContext,
+    native::{
+        self,
+        builtin_methods::{BuiltinMethodRegistry, TypeDiscriminant},
+    },
+    opcode::{LineTable, OpCode, operand},
+    portable::Snapshot,
+    profiler::{Profile, Profiler},
+    reactor::{Completion, Event, Reactor},
+    typechecker::Type,
     value::{
         self, BoundBuiltinMethod, BoundMethod, BoundNativeMethod, Class, Closure, Function,
-        Instance, NativeFnId, NativeMethodImpl, Upvalue, Value, ValueKey,
+        Instance, NativeFnId, NativeMethodImpl, Task, Upvalue, Value, ValueKey,
     },
+    verifier,
 };
 
 #[derive(Debug)]
 struct CallFrame {
-    opcodes: Vec<OpCode>,
-    constants: Vec<Value>,
+    function: Rc<Function>, // Байткод и константы разделяются между вызовами без копирования
     ip: usize,
     base: usize, // Базовый индекс в стеке
     upvalues: Vec<Rc<RefCell<Upvalue>>>,
     exception_handlers: Vec<ExceptionHandler>,
 }
 
+// Глубина стека, которую строгий режим ждёт после инструкции `ip` кадра
+// с началом `base` на глубине вызовов `frames`
+struct ExpectedStack {
+    frames: usize,
+    base: usize,
+    len: usize,
+    ip: usize,
+    opcode: OpCode,
+}
+
 #[derive(Debug, Clone)]
 struct ExceptionHandler {
     handler_addr: usize,
     stack_size: usize,
 }
 
-type NativeFn = fn(&[Value]) -> Result<Value, String>;
+// Приостановленная сопрограмма: её часть стека значений и кадры вызовов.
+// Индексы в стеке (base кадров, обработчики, открытые upvalue) хранятся
+// относительно начала этой части: при возобновлении она кладётся поверх
+// текущего стека, где бы он ни кончался
+struct SuspendedTask {
+    task: Rc<Task>,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    // Upvalue, открытые на локальные переменные задачи, со своими слотами.
+    // Пока задача стоит, они закрыты, чтобы чужой код не читал чужой стек
+    upvalues: Vec<(Rc<RefCell<Upvalue>>, usize)>,
+    // Чего ждёт задача из waiting_tasks
+    waiting_on: Option<Wait>,
+    // Чем заменить результат вызова, на котором задача остановилась:
+    // значением или исключением, которое нужно бросить
+    resume: Option<Result<Value, Value>>,
+}
+
+// Почему сопрограмма вернула управление планировщику
+enum Suspend {
+    Yield,
+    Wait(Wait),
+}
+
+// Событие, которого ждёт приостановленная задача
+enum Wait {
+    Task(Rc<Task>),
+    // Фоновая операция реактора и класс ошибки, которым бросается её Err
+    Io(usize, &'static str),
+    Timer(usize),
+}
+
+/// Нативная функция. Хранится как замыкание, поэтому может нести состояние
+/// хоста (настройки, счётчики, ссылки на объекты приложения)
+pub type NativeFn = Rc<dyn Fn(&mut NativeContext, &[Value]) -> Result<Value, String>>;
+
+/// Доступ нативной функции к VM во время вызова: позволяет вызывать
+/// переданные в неё функции Ponos (компараторы, обработчики и т.п.)
+pub struct NativeContext<'a> {
+    vm: &'a mut VM,
+}
+
+impl NativeContext<'_> {
+    /// Вызвать функцию Ponos. Если она бросила исключение, которое не было
+    /// перехвачено внутри вызова, возвращается Err, а само исключение будет
+    /// повторно брошено в вызывающем коде после возврата из нативной функции
+    pub fn call(&mut self, callee: &Value, args: &[Value]) -> Result<Value, String> {
+        self.vm.call_value(callee.clone(), args)
+    }
+
+    /// Напечатать текст программы: в stdout или в буфер, если вывод перехватывается
+    pub fn write_output(&mut self, text: &str) {
+        self.vm.write_output(text);
+    }
+
+    /// Записать накопленный вывод в stdout: перед чтением stdin, выводом
+    /// в stderr и по запросу программы
+    pub fn flush_output(&mut self) {
+        self.vm.flush_output();
+    }
+
+    /// Включить или выключить блочную буферизацию stdout
+    pub fn set_output_buffering(&mut self, buffered: bool) {
+        self.vm.set_output_buffering(buffered);
+    }
+
+    /// Выполнить блокирующую операцию `job` (сеть, файлы). В сопрограмме она
+    /// идёт в фоновом потоке, а задача приостанавливается до её результата;
+    /// тогда сразу возвращается `ничто`, которое планировщик заменит итогом
+    /// операции. Вне сопрограммы операция выполняется сразу
+    pub fn run_blocking<F>(&mut self, job: F) -> Result<Value, String>
+    where
+        F: FnOnce() -> Completion + Send + 'static,
+    {
+        self.vm.run_blocking(job)
+    }
+
+    /// Подождать `duration`, не останавливая другие сопрограммы
+    pub fn sleep(&mut self, duration: Duration) {
+        self.vm.sleep(duration);
+    }
+
+    /// Бросить исключение произвольным значением (а не строкой, как при
+    /// возврате Err). Оно будет брошено после возврата из нативной функции,
+    /// её собственный результат при этом отбрасывается
+    pub fn throw(&mut self, exception: Value) {
+        self.vm.pending_exception = Some(exception);
+    }
+
+    /// Экземпляр встроенного класса ошибки (`exceptions::IO_ERROR` и т.п.)
+    /// для броска через `throw`
+    pub fn error(&self, class: &str, message: String) -> Value {
+        self.vm.errors.instantiate(class, message)
+    }
+
+    /// Скопировать значения для передачи в другой поток
+    pub(crate) fn export(&mut self, values: &[Value]) -> Result<Snapshot, String> {
+        Snapshot::export(self.vm, values)
+    }
+
+    /// Пределы ресурсов VM: их наследуют потоки, запущенные программой
+    pub(crate) fn limits(&self) -> Limits {
+        self.vm.limits
+    }
+
+    /// Восстановить значения, переданные из другого потока
+    pub(crate) fn import(&mut self, snapshot: Snapshot) -> Result<Vec<Value>, String> {
+        snapshot.import(self.vm)
+    }
+
+    /// Значение глобальной переменной по имени: `имя` или `модуль::имя`.
+    /// Экспорт модуля находится и тогда, когда он реэкспортирован из другого
+    pub fn global(&mut self, name: &str) -> Option<Value> {
+        let exported = name.rsplit_once("::").and_then(|(namespace, export)| {
+            self.vm
+                .module_exports(namespace)?
+                .iter()
+                .find(|(name, _)| name == export)
+                .map(|(_, global)| global.clone())
+        });
+        self.vm.get_global(exported.as_deref().unwrap_or(name))
+    }
+
+    /// Имена экспортов модуля или `None`, если модуль не загружен
+    pub fn module_exports(&self, namespace: &str) -> Option<Vec<String>> {
+        let exports = self.vm.module_exports(namespace)?;
+        Some(exports.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    /// Глобальное имя нативной функции
+    pub fn native_name(&self, id: &NativeFnId) -> Option<String> {
+        self.vm.native_name(id)
+    }
+
+    /// Определена ли глобальная переменная (в том числе отложенная)
+    pub fn is_global_defined(&self, name: &str) -> bool {
+        self.vm.is_global_defined(name)
+    }
+
+    /// Загруженные модули: пространство имён и его экспорты с глобальными
+    /// переменными, в которых они определены
+    pub(crate) fn loaded_modules(&self) -> Vec<(String, Vec<(String, String)>)> {
+        self.vm
+            .module_exports
+            .iter()
+            .map(|(namespace, exports)| (namespace.clone(), exports.clone()))
+            .collect()
+    }
+
+    /// Выполнить скомпилированный код верхнего уровня в этой VM. Исключение
+    /// кода, как и у `call`, возвращается как Err и бросается повторно
+    pub(crate) fn execute(&mut self, code: GenContext) -> Result<Value, String> {
+        self.vm
+            .execute_nested(&code.opcodes, &code.constants, code.lines, code.source_name)
+    }
+
+    /// Завершить программу с кодом `code`: выполнение останавливается после
+    /// возврата из нативной функции, обработчики исключений не срабатывают
+    pub fn exit(&mut self, code: i32) {
+        self.vm.exit_code = Some(code);
+    }
+}
 
 /// Реестр нативных методов для классов
 /// Хранит методы в виде HashMap<(имя_класса, имя_метода), реализация_метода>
@@ -33,6 +224,12 @@ pub struct NativeMethodRegistry {
     methods: HashMap<(String, String), NativeMethodImpl>,
 }
 
+impl Default for NativeMethodRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NativeMethodRegistry {
     pub fn new() -> Self {
         NativeMethodRegistry {
@@ -54,77 +251,441 @@ impl NativeMethodRegistry {
     }
 }
 
+/// Глобальные переменные, адресуемые по номеру слота.
+/// Имя разрешается в слот один раз при загрузке кода (см. `VM::link_globals`),
+/// после чего доступ к глобальной переменной — это индексация вектора.
+#[derive(Default)]
+struct Globals {
+    slots: HashMap<String, usize>,
+    names: Vec<String>,
+    values: Vec<Option<Value>>, // None — слот зарезервирован, но переменная еще не определена
+    lazy: HashMap<usize, LazyGlobal>, // Значения, вычисляемые при первом обращении
+    builtins: usize, // Слоты встроенных функций идут первыми; программа может их перекрыть
+}
+
+/// Отложенное значение глобальной переменной (например, еще не скомпилированная функция модуля)
+pub type LazyGlobal = Box<dyn FnOnce() -> Result<Value, String>>;
+
+impl Globals {
+    /// Получить слот для имени, создав его при необходимости
+    fn slot(&mut self, name: &str) -> usize {
+        if let Some(&slot) = self.slots.get(name) {
+            return slot;
+        }
+
+        let slot = self.values.len();
+        self.slots.insert(name.to_string(), slot);
+        self.names.push(name.to_string());
+        self.values.push(None);
+        slot
+    }
+
+    fn define(&mut self, name: &str, value: Value) {
+        let slot = self.slot(name);
+        self.values[slot] = Some(value);
+    }
+
+    fn is_defined(&self, slot: usize) -> bool {
+        self.values[slot].is_some() || self.lazy.contains_key(&slot)
+    }
+
+    #[cfg(test)]
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.slots
+            .get(name)
+            .and_then(|&slot| self.values[slot].as_ref())
+    }
+}
+
+/// Пределы ресурсов VM; `None` — без ограничения
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Сколько инструкций может выполнить один запуск кода верхнего уровня.
+    /// Превышение прерывает выполнение: перехватить его нельзя
+    pub max_instructions: Option<u64>,
+    /// Наибольшая глубина вызовов; превышение бросает ОшибкаЛимита
+    pub max_call_depth: Option<usize>,
+    /// Наибольший размер стека значений; превышение бросает ОшибкаЛимита
+    pub max_stack: Option<usize>,
+}
+
+impl Limits {
+    fn is_unlimited(&self) -> bool {
+        *self == Limits::default()
+    }
+}
+
 pub struct VM {
     pub stack: Vec<Value>,
-    globals: HashMap<String, Value>, // Плоское пространство глобальных переменных
+    globals: Globals, // Плоское пространство глобальных переменных
     frames: Vec<CallFrame>,
     native_functions: Vec<NativeFn>,
     open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
     builtin_method_registry: BuiltinMethodRegistry,
     native_method_registry: NativeMethodRegistry,
+    // Кадры ниже этой глубины принадлежат внешнему циклу выполнения
+    // (вызов из нативной функции обратно в Ponos)
+    frame_boundary: usize,
+    // Сколько вложенных циклов выполнения сейчас открыто поверх основного
+    nested_runs: usize,
+    // Исключение, вылетевшее из обратного вызова и ожидающее повторного броска
+    pending_exception: Option<Value>,
+    /// Код завершения, запрошенный программой (система.завершить)
+    exit_code: Option<i32>,
+    // Буфер для вывести(): если задан, вывод программы собирается в него, а не в stdout
+    captured_output: Option<String>,
+    // Вывод в stdout, ещё не записанный при блочной буферизации
+    stdout_buffer: String,
+    // Копить вывод в stdout_buffer: включено, если stdout не терминал (конвейер,
+    // файл), и выключается `ввод_вывод.без_буфера()`
+    buffer_output: bool,
+    // Профилировщик функций: если задан, каждая инструкция отмечается в нём
+    profiler: Option<Box<Profiler>>,
+    // Печатать в stderr каждую выполняемую инструкцию (--trace)
+    trace: bool,
+    // Печатать в stderr отчёт о необработанном исключении или прерывании;
+    // без отчёта стек вызовов попадает в текст паники
+    report_errors: bool,
+    // Проверять стековый эффект каждой инструкции (--strict-stack)
+    strict_stack: bool,
+    // Строгий режим: сколько значений должна оставить на стеке предыдущая
+    // инструкция, если выполнение осталось в её кадре
+    expected_stack: Option<ExpectedStack>,
+    // Включён профилировщик, трассировка, строгий режим стека или пределы
+    // ресурсов: единственная проверка на инструкцию
+    instrumented: bool,
+    limits: Limits,
+    // Флаг прерывания выполнения, поднимаемый извне VM
+    interrupt: Option<Arc<AtomicBool>>,
+    // Инструкции, выполненные с начала запуска кода верхнего уровня
+    instructions: u64,
+    // Встроенные классы ошибок: ими бросаются ошибки выполнения
+    errors: ErrorClasses,
+    // Класс ошибки, которым бросается Err нативной функции, если не Ошибка
+    native_error_classes: HashMap<usize, &'static str>,
+    // Сопрограммы, готовые продолжить выполнение, в порядке очереди
+    ready_tasks: VecDeque<SuspendedTask>,
+    // Сопрограммы, ждущие завершения другой задачи
+    waiting_tasks: Vec<SuspendedTask>,
+    // Выполняемая сопрограмма и граница её кадров в стеке вызовов
+    current_task: Option<(Rc<Task>, usize)>,
+    // Выполняемая сопрограмма просит приостановить её после возврата из
+    // нативной функции (передать, ждать)
+    suspend: Option<Suspend>,
+    task_count: usize,
+    // Фоновые операции и таймеры, которых ждут задачи
+    reactor: Reactor,
+    // Зарегистрированные нативные модули: путь, пространство имён и
+    // определённые функции (песочница может разрешить не все экспорты)
+    native_modules: Vec<(String, String, Vec<String>)>,
+    // Экспорты загруженных модулей: пространство имён -> имя экспорта и
+    // глобальная переменная, в которой он определён
+    module_exports: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'a> VM {
+impl Drop for VM {
+    fn drop(&mut self) {
+        self.flush_output();
+    }
+}
+
+impl VM {
     pub fn new() -> Self {
         let mut vm = VM {
             stack: Vec::new(),
-            globals: HashMap::new(),
+            globals: Globals::default(),
             frames: Vec::new(),
             native_functions: Vec::new(),
             open_upvalues: Vec::new(),
             builtin_method_registry: BuiltinMethodRegistry::new(),
             native_method_registry: NativeMethodRegistry::new(),
+            frame_boundary: 0,
+            nested_runs: 0,
+            pending_exception: None,
+            exit_code: None,
+            captured_output: None,
+            stdout_buffer: String::new(),
+            buffer_output: !std::io::stdout().is_terminal(),
+            profiler: None,
+            trace: false,
+            report_errors: true,
+            strict_stack: false,
+            expected_stack: None,
+            instrumented: false,
+            limits: Limits::default(),
+            interrupt: None,
+            instructions: 0,
+            errors: ErrorClasses::new(),
+            native_error_classes: HashMap::new(),
+            ready_tasks: VecDeque::new(),
+            waiting_tasks: Vec::new(),
+            current_task: None,
+            suspend: None,
+            task_count: 0,
+            reactor: Reactor::new(),
+            native_modules: Vec::new(),
+            module_exports: HashMap::new(),
         };
 
         // Регистрируем встроенные функции
         vm.register_builtin_functions();
+        vm.globals.builtins = vm.globals.values.len();
 
         vm
     }
 
     fn register_builtin_functions(&mut self) {
         self.register_and_define("длина", builtin_len);
-        self.register_and_define("вывести", builtin_print)
+        self.register_and_define_with_context("вывести", builtin_print);
+        self.register_and_define_with_context("вывести_ф", builtin_print_formatted);
+        self.register_and_define("формат", native::strings::str_format);
+        self.register_and_define("построитель_строк", builtin_string_builder);
+        self.register_and_define("является_ничем", builtin_is_nil);
+        self.register_and_define("копировать", builtin_copy);
+        self.register_and_define("является", builtin_is_instance);
+        self.register_and_define_with_context("сопрограмма", builtin_spawn);
+        self.register_and_define_with_context("передать", builtin_yield);
+        self.register_and_define_with_context("ждать", builtin_await);
+        self.register_and_define_with_context("таймер_через", builtin_timer);
+        native::math::register(self);
+        native::reflection::register(self);
+        self.register_and_define_with_context("выполнить_код", native::eval::run_code);
+        let classes: Vec<_> = self
+            .errors
+            .iter()
+            .map(|(name, class)| (name, Value::Class(class.clone())))
+            .collect();
+        for (name, class) in classes {
+            self.globals.define(name, class);
+        }
     }
 
-    pub fn execute(&mut self, opcodes: Vec<OpCode>, constants: &mut Vec<Value>) {
-        self.frames.push(CallFrame {
+    /// Выполнить код верхнего уровня. Байткод сначала проверяется:
+    /// некорректный (собранный вручную или повреждённый) возвращается в Err,
+    /// не начав выполняться
+    pub fn execute(&mut self, opcodes: Vec<OpCode>, constants: &[Value]) -> Result<(), String> {
+        self.execute_with_lines(opcodes, constants, LineTable::default(), None)
+    }
+
+    /// Выполнить код верхнего уровня с таблицей строк и именем файла,
+    /// чтобы стек вызовов указывал на строки исходного кода
+    pub fn execute_with_lines(
+        &mut self,
+        opcodes: Vec<OpCode>,
+        constants: &[Value],
+        lines: LineTable,
+        source_name: Option<Rc<str>>,
+    ) -> Result<(), String> {
+        if self.exit_code.is_some() {
+            // Программа уже завершена: код модулей и следующих фрагментов не выполняется
+            return Ok(());
+        }
+        verifier::verify(&opcodes, constants)
+            .map_err(|message| format!("Некорректный байткод: {}", message))?;
+        let (opcodes, constants) = self.link_globals(&opcodes, constants);
+
+        // Код верхнего уровня оборачивается в функцию без параметров
+        let main = Function {
+            arity: 0,
+            entry_points: Vec::new(),
+            variadic: false,
+            opcodes,
+            constants,
+            name: "<главная>".to_string(),
+            upvalue_count: 0,
+            upvalue_descriptors: Vec::new(),
+            source_name,
+            lines,
+        };
+
+        self.run(main, 0);
+        if self.exit_code.is_none() {
+            self.run_scheduler();
+        }
+        self.flush_output();
+        Ok(())
+    }
+
+    /// Вызвать значение Ponos (функцию, замыкание, нативную функцию) из Rust-кода
+    /// и вернуть результат. Вызов может происходить и во время выполнения другого
+    /// кода: цикл завершается, как только вернётся кадр, созданный для вызова.
+    /// Err возвращается, если исключение вышло за пределы вложенного вызова
+    pub fn call_value(&mut self, callee: Value, args: &[Value]) -> Result<Value, String> {
+        self.run_nested(call_entry(callee, args))
+    }
+
+    /// Выполнить код верхнего уровня во время выполнения другого кода
+    /// (`выполнить_код`): глобальные переменные общие с программой.
+    /// Возвращает значение, оставленное кодом на стеке, или `ничто`
+    pub(crate) fn execute_nested(
+        &mut self,
+        opcodes: &[OpCode],
+        constants: &[Value],
+        lines: LineTable,
+        source_name: Option<Rc<str>>,
+    ) -> Result<Value, String> {
+        verifier::verify(opcodes, constants)
+            .map_err(|message| format!("Некорректный байткод: {}", message))?;
+        let (opcodes, constants) = self.link_globals(opcodes, constants);
+        let main = Function {
+            arity: 0,
+            entry_points: Vec::new(),
+            variadic: false,
             opcodes,
-            constants: constants.clone(),
+            constants,
+            name: "<код>".to_string(),
+            upvalue_count: 0,
+            upvalue_descriptors: Vec::new(),
+            source_name,
+            lines,
+        };
+        self.run_nested(main)
+    }
+
+    // Выполнить функцию во вложенном цикле выполнения. Каждый такой цикл
+    // занимает место на стеке потока Rust, поэтому их глубина ограничена
+    // отдельно от глубины вызовов Ponos
+    fn run_nested(&mut self, function: Function) -> Result<Value, String> {
+        if self.nested_runs >= MAX_NESTED_RUNS {
+            let message = format!(
+                "Превышена глубина вложенных вызовов из нативных функций: {}",
+                MAX_NESTED_RUNS
+            );
+            let exception = self
+                .errors
+                .instantiate(exceptions::LIMIT_ERROR, message.clone());
+            self.pending_exception = Some(exception);
+            return Err(message);
+        }
+        let base = self.stack.len();
+        self.nested_runs += 1;
+        self.run(function, base);
+        self.nested_runs -= 1;
+        self.nested_result(base)
+    }
+
+    // Итог вложенного цикла выполнения, начатого со стека глубины `base`
+    fn nested_result(&mut self, base: usize) -> Result<Value, String> {
+        if self.exit_code.is_some() {
+            self.stack.truncate(base);
+            return Err("Программа завершена".to_string());
+        }
+        if let Some(exception) = &self.pending_exception {
+            self.stack.truncate(base);
+            return Err(format!(
+                "Исключение в вызванной функции: {}",
+                self.errors.describe(exception)
+            ));
+        }
+
+        let result = if self.stack.len() > base {
+            self.stack.pop().unwrap()
+        } else {
+            Value::Nil
+        };
+        self.stack.truncate(base);
+        Ok(result)
+    }
+
+    /// Выполнять код функции верхнего уровня, пока не вернётся её кадр
+    fn run(&mut self, entry: Function, base: usize) {
+        let boundary = self.frames.len();
+        if boundary == 0 {
+            self.instructions = 0;
+            // Прерывание, запрошенное, пока код не выполнялся, к нему не относится
+            if let Some(flag) = &self.interrupt {
+                flag.store(false, AtomicOrdering::Relaxed);
+            }
+        }
+        let entry = Rc::new(entry);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(&entry, boundary + 1);
+        }
+        self.frames.push(CallFrame {
+            function: entry,
             ip: 0,
-            base: 0,
+            base,
             upvalues: Vec::new(),
             exception_handlers: Vec::new(),
         });
+        self.run_frames(boundary);
+    }
 
-        while !self.frames.is_empty() {
-            let frame_idx = self.frames.len() - 1;
+    /// Выполнять кадры выше `boundary`, пока не вернётся нижний из них или
+    /// выполняемая в них сопрограмма не приостановится
+    fn run_frames(&mut self, boundary: usize) {
+        let outer_boundary = self.frame_boundary;
+        self.frame_boundary = boundary;
+        self.expected_stack = None;
+        let entry_depth = boundary + 1;
+
+        // Состояние активного кадра держим в локальных переменных и
+        // синхронизируем с self.frames только при смене кадра
+        let (mut frame_idx, mut function, mut ip, mut base) = self.active_frame();
+
+        // Бросить встроенную ошибку из текущей инструкции и продолжить с
+        // обработчика (или выйти из цикла, если исключение его покинуло)
+        macro_rules! raise {
+            ($class:expr, $($message:tt)+) => {{
+                self.frames[frame_idx].ip = ip;
+                let exception = self.errors.instantiate($class, format!($($message)+));
+                self.handle_exception(exception);
+                if self.frames.len() < entry_depth {
+                    break;
+                }
+                (frame_idx, function, ip, base) = self.active_frame();
+                continue;
+            }};
+        }
 
-            if self.frames[frame_idx].ip >= self.frames[frame_idx].opcodes.len() {
+        loop {
+            if ip >= function.opcodes.len() {
                 // Автоматический return
-                if self.frames.len() == 1 {
+                debug_assert!(
+                    self.stack.len() >= base,
+                    "Функция {} сняла со стека значения вызывающего кода (стек {}, начало кадра {})",
+                    function.name,
+                    self.stack.len(),
+                    base
+                );
+                self.expected_stack = None;
+                if self.frames.len() == entry_depth {
                     // Для main frame очистить локальные переменные, оставив только результат
-                    let base = self.frames[0].base;
                     if self.stack.len() > base {
                         let result = self.stack.pop().unwrap();
                         self.stack.truncate(base);
                         self.stack.push(result);
                     }
+                    self.frames.pop();
+                    self.profile_leave();
                     break;
                 }
-                self.stack.push(Value::Nil);
-                let base = self.frames[frame_idx].base;
                 self.frames.pop();
+                self.profile_leave();
                 self.stack.truncate(base);
+                self.stack.push(Value::Nil);
+                (frame_idx, function, ip, base) = self.active_frame();
                 continue;
             }
 
-            let cur_opcode = self.frames[frame_idx].opcodes[self.frames[frame_idx].ip];
+            let cur_opcode = function.opcodes[ip];
+            ip += 1;
+            if self.instrumented {
+                self.instrument(&function, frame_idx + 1, ip - 1, base, cur_opcode);
+                if let Some(message) = self.exceeded_limit(frame_idx, ip) {
+                    raise!(exceptions::LIMIT_ERROR, "{}", message);
+                }
+            }
 
             match cur_opcode {
                 OpCode::Constant(idx) => {
-                    self.stack
-                        .push(self.frames[frame_idx].constants[idx].clone());
+                    self.stack.push(function.constants[idx as usize].clone());
                 }
                 OpCode::Pop => {
                     self.stack.pop().expect("Стек пуст при Pop");
@@ -134,51 +695,97 @@ impl<'a> VM {
                     self.stack.push(value);
                 }
                 OpCode::Negate => {
-                    let a = match self.stack.pop().unwrap() {
-                        Value::Number(n) => n,
-                        _ => panic!("Operand not a number"),
+                    let negated = match self.stack.pop().unwrap() {
+                        Value::Int(n) => n
+                            .checked_neg()
+                            .map_or(Value::Number(-(n as f64)), Value::Int),
+                        Value::Number(n) => Value::Number(-n),
+                        other => raise!(
+                            exceptions::TYPE_ERROR,
+                            "Унарный минус применим только к числу, получено: {}",
+                            Type::of_value(&other)
+                        ),
                     };
 
-                    self.stack.push(Value::Number(-a));
+                    self.stack.push(negated);
+                }
+                OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div | OpCode::Mod => {
+                    let result = match cur_opcode {
+                        OpCode::Add => self.binary_add_op(),
+                        OpCode::Sub => self.binary_number_op("-", i64::checked_sub, |a, b| a - b),
+                        OpCode::Mul => self.binary_number_op("*", i64::checked_mul, |a, b| a * b),
+                        // Деление всегда вещественное: 7 / 2 == 3.5
+                        OpCode::Div => self.binary_number_op("/", |_, _| None, |a, b| a / b),
+                        _ => {
+                            if let [.., Value::Int(_), Value::Int(0)] = self.stack.as_slice() {
+                                raise!(exceptions::ERROR, "Остаток от деления на ноль");
+                            }
+                            // Делитель не ноль, так что wrapping_rem отличается от
+                            // checked_rem только для MIN % -1, где остаток — 0
+                            self.binary_number_op("%", |a, b| Some(a.wrapping_rem(b)), |a, b| a % b)
+                        }
+                    };
+                    if let Err(message) = result {
+                        raise!(exceptions::TYPE_ERROR, "{}", message);
+                    }
                 }
-                OpCode::Add => self.binary_add_op(),
-                OpCode::Sub => self.binary_number_op(|a, b| a - b),
-                OpCode::Mul => self.binary_number_op(|a, b| a * b),
-                OpCode::Div => self.binary_number_op(|a, b| a / b),
-                OpCode::Mod => self.binary_number_op(|a, b| a % b),
                 OpCode::True_ => self.stack.push(Value::Boolean(true)),
                 OpCode::False_ => self.stack.push(Value::Boolean(false)),
                 OpCode::Eql => self.binary_logical_op(|a, b| value::is_equal(&a, &b)),
                 OpCode::Not => {
                     let val = match self.stack.pop().unwrap() {
                         Value::Boolean(b) => b,
-                        _ => panic!("Значение не булевого типа"),
+                        other => raise!(
+                            exceptions::TYPE_ERROR,
+                            "Значение не булевого типа: {}",
+                            Type::of_value(&other)
+                        ),
                     };
 
                     self.stack.push(Value::Boolean(!val));
                 }
-                OpCode::Greater => self.binary_logical_op(|a, b| value::is_greater(&a, &b)),
-                OpCode::Less => self.binary_logical_op(|a, b| {
-                    !value::is_greater(&a, &b) && !value::is_equal(&a, &b)
-                }),
+                OpCode::Greater | OpCode::Less => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    let expected = match cur_opcode {
+                        OpCode::Greater => Ordering::Greater,
+                        _ => Ordering::Less,
+                    };
+                    match value::compare(&a, &b) {
+                        Ok(ordering) => self.stack.push(Value::Boolean(ordering == Some(expected))),
+                        Err(message) => raise!(exceptions::TYPE_ERROR, "{}", message),
+                    }
+                }
                 OpCode::GetLocal(slot) => {
-                    let index = self.frames[frame_idx].base + slot;
+                    let index = base + slot as usize;
                     let value = self.stack.get(index).cloned().unwrap_or(Value::Nil);
                     self.stack.push(value);
                 }
                 OpCode::SetLocal(slot) => {
                     let value = self.stack.pop().expect("Стек пуст");
-                    let index = self.frames[frame_idx].base + slot;
+                    let index = base + slot as usize;
 
                     if index >= self.stack.len() {
                         self.stack.resize(index + 1, Value::Nil);
                     }
                     self.stack[index] = value;
                 }
+                OpCode::AppendLocal(slot) => {
+                    let right = self.stack.pop().expect("Стек пуст");
+                    let index = base + slot as usize;
+                    if !value::append_in_place(&mut self.stack[index], &right) {
+                        self.stack.push(self.stack[index].clone());
+                        self.stack.push(right);
+                        if let Err(message) = self.binary_add_op() {
+                            raise!(exceptions::TYPE_ERROR, "{}", message);
+                        }
+                        self.stack[index] = self.stack.pop().unwrap();
+                    }
+                }
                 OpCode::DefineLocal(slot) => {
                     // Pop значение с evaluation stack и сохранить в слот локальной переменной
                     let value = self.stack.pop().expect("Стек пуст");
-                    let index = self.frames[frame_idx].base + slot;
+                    let index = base + slot as usize;
 
                     // Убеждаемся, что в стеке достаточно места для всех локальных переменных
                     // Локальные переменные идут сразу после base
@@ -189,7 +796,7 @@ impl<'a> VM {
                 }
                 OpCode::Closure(fn_const_idx, _upvalue_count) => {
                     // Получаем функцию из константного пула
-                    let function = match &self.frames[frame_idx].constants[fn_const_idx] {
+                    let function = match &function.constants[fn_const_idx as usize] {
                         Value::Function(f) => f.clone(),
                         other => panic!(
                             "Ожидалась функция в константном пуле по индексу {}, но найдено: {:?}",
@@ -202,7 +809,7 @@ impl<'a> VM {
                     for descriptor in &function.upvalue_descriptors {
                         if descriptor.is_local {
                             // Захватываем локальную переменную из текущего фрейма
-                            let stack_index = self.frames[frame_idx].base + descriptor.index;
+                            let stack_index = base + descriptor.index;
 
                             // Проверяем, не создан ли уже upvalue для этого слота
                             let upvalue = self.capture_upvalue(stack_index);
@@ -224,16 +831,13 @@ impl<'a> VM {
                     }
 
                     // Создаем замыкание
-                    let closure = Closure {
-                        function: (*function).clone(),
-                        upvalues,
-                    };
+                    let closure = Closure { function, upvalues };
 
                     self.stack.push(Value::Closure(Rc::new(closure)));
                 }
                 OpCode::GetUpvalue(index) => {
                     let frame = &self.frames[frame_idx];
-                    let upvalue = &frame.upvalues[index];
+                    let upvalue = &frame.upvalues[index as usize];
 
                     let value = match &*upvalue.borrow() {
                         Upvalue::Open(stack_idx) => self.stack[*stack_idx].clone(),
@@ -245,7 +849,7 @@ impl<'a> VM {
                 OpCode::SetUpvalue(index) => {
                     let value = self.stack.pop().expect("Стек пуст");
                     let frame = &self.frames[frame_idx];
-                    let upvalue = &frame.upvalues[index];
+                    let upvalue = &frame.upvalues[index as usize];
 
                     let mut upval = upvalue.borrow_mut();
                     match &mut *upval {
@@ -259,13 +863,15 @@ impl<'a> VM {
                 }
                 OpCode::CloseUpvalues(local_count) => {
                     let frame = &self.frames[frame_idx];
-                    let threshold = frame.base + local_count;
+                    let threshold = frame.base + local_count as usize;
                     self.close_upvalues_from(threshold);
                 }
                 OpCode::Jump(addr) => {
-                    // Безусловный переход
-                    self.frames[frame_idx].ip = addr;
-                    continue;
+                    // Безусловный переход; переход назад — очередной проход цикла
+                    if (addr as usize) < ip && self.take_interrupt() {
+                        raise!(exceptions::INTERRUPT, "Выполнение прервано пользователем");
+                    }
+                    ip = addr as usize;
                 }
                 OpCode::JumpIfTrue(addr) => {
                     let condition = self
@@ -273,8 +879,7 @@ impl<'a> VM {
                         .pop()
                         .expect("Стек пуст при проверке условия JumpIfTrue");
                     if let Value::Boolean(true) = condition {
-                        self.frames[frame_idx].ip = addr;
-                        continue;
+                        ip = addr as usize;
                     }
                 }
                 OpCode::JumpIfFalse(addr) => {
@@ -283,13 +888,12 @@ impl<'a> VM {
                         .pop()
                         .expect("Стек пуст при проверке условия JumpIfFalse");
                     if let Value::Boolean(false) = condition {
-                        self.frames[frame_idx].ip = addr;
-                        continue;
+                        ip = addr as usize;
                     }
                 }
                 OpCode::PushExceptionHandler(handler_addr) => {
                     let handler = ExceptionHandler {
-                        handler_addr,
+                        handler_addr: handler_addr as usize,
                         stack_size: self.stack.len(),
                     };
                     self.frames[frame_idx].exception_handlers.push(handler);
@@ -302,10 +906,32 @@ impl<'a> VM {
                 }
                 OpCode::Throw => {
                     let exception_value = self.stack.pop().expect("Стек пуст при Throw");
+                    self.frames[frame_idx].ip = ip;
                     self.handle_exception(exception_value);
-                    continue;
+                    if self.frames.len() < entry_depth {
+                        // Исключение покинуло вложенный вызов
+                        break;
+                    }
+                    (frame_idx, function, ip, base) = self.active_frame();
                 }
-                OpCode::Call(arg_count) => {
+                OpCode::Call(_) | OpCode::CallSpread(_) => {
+                    if self.take_interrupt() {
+                        raise!(exceptions::INTERRUPT, "Выполнение прервано пользователем");
+                    }
+                    // Вызов может сменить активный кадр: сохраняем адрес возврата
+                    self.frames[frame_idx].ip = ip;
+
+                    let arg_count = match cur_opcode {
+                        OpCode::CallSpread(pieces) => {
+                            match self.spread_arguments(pieces as usize) {
+                                Ok(arg_count) => arg_count,
+                                Err(message) => raise!(exceptions::TYPE_ERROR, "{}", message),
+                            }
+                        }
+                        OpCode::Call(arg_count) => arg_count as usize,
+                        _ => unreachable!(),
+                    };
+
                     let callee_idx = self.stack.len() - arg_count - 1;
                     let callee = self.stack[callee_idx].clone();
 
@@ -318,7 +944,7 @@ impl<'a> VM {
                                     self.stack.truncate(callee_idx);
                                     self.stack.push(result);
                                 }
-                                Err(err) => panic!("Ошибка встроенного метода: {}", err),
+                                Err(err) => raise!(exceptions::ERROR, "{}", err),
                             }
                         }
                         Value::BoundNativeMethod(bound) => {
@@ -329,7 +955,7 @@ impl<'a> VM {
                                     self.stack.truncate(callee_idx);
                                     self.stack.push(result);
                                 }
-                                Err(err) => panic!("Ошибка нативного метода: {}", err),
+                                Err(err) => raise!(exceptions::ERROR, "{}", err),
                             }
                         }
                         Value::Class(class) => {
@@ -343,9 +969,11 @@ impl<'a> VM {
                             // Заменяем класс на экземпляр на стеке
                             self.stack[callee_idx] = Value::Instance(instance_rc.clone());
 
-                            // Вызываем конструктор если есть
-                            if let Some(ctor) = class.methods.get("конструктор") {
-                                self.call_function(ctor.clone(), arg_count, true).unwrap();
+                            // Вызываем конструктор (свой или унаследованный), если есть
+                            if let Some(ctor) = class.find_method("конструктор") {
+                                if let Err(message) = self.call_function(ctor, arg_count, true) {
+                                    raise!(exceptions::TYPE_ERROR, "{}", message);
+                                }
                             } else {
                                 // Нет конструктора - просто удаляем аргументы
                                 self.stack.drain(callee_idx + 1..);
@@ -354,44 +982,69 @@ impl<'a> VM {
                         Value::BoundMethod(bound) => {
                             // Заменяем BoundMethod на экземпляр (это будет slot 0)
                             self.stack[callee_idx] = Value::Instance(bound.receiver.clone());
-                            self.call_function(bound.method.clone(), arg_count, true)
-                                .unwrap();
+                            if let Err(message) =
+                                self.call_function(bound.method.clone(), arg_count, true)
+                            {
+                                raise!(exceptions::TYPE_ERROR, "{}", message);
+                            }
                         }
                         Value::Function(func) => {
-                            self.call_function(func, arg_count, false).unwrap()
+                            if let Err(message) = self.call_function(func, arg_count, false) {
+                                raise!(exceptions::TYPE_ERROR, "{}", message);
+                            }
+                        }
+                        Value::Closure(closure) => {
+                            if let Err(message) = self.call_closure(closure, arg_count) {
+                                raise!(exceptions::TYPE_ERROR, "{}", message);
+                            }
+                        }
+                        Value::NativeFunction(id) => self.call_native(id, arg_count),
+                        other => {
+                            self.stack.truncate(callee_idx);
+                            raise!(
+                                exceptions::TYPE_ERROR,
+                                "Значение типа {} нельзя вызвать",
+                                Type::of_value(&other)
+                            )
                         }
-                        Value::Closure(closure) => self.call_closure(closure, arg_count).unwrap(),
-                        Value::NativeFunction(id) => self.call_native(id, arg_count).unwrap(),
-                        _ => panic!("Попытка вызвать не-функцию"),
                     }
+
+                    if self.frames.len() < entry_depth {
+                        // Исключение из обратного вызова покинуло вложенный цикл
+                        break;
+                    }
+                    if self.suspend.is_some() {
+                        // Сопрограмма отдала управление: кадры сохранит планировщик
+                        break;
+                    }
+                    (frame_idx, function, ip, base) = self.active_frame();
                 }
                 OpCode::Return_ => {
+                    // Функция, снявшая со стека больше, чем положила, испортила
+                    // бы стек вызывающего кода
+                    debug_assert!(
+                        self.stack.len() > base,
+                        "Return_ в функции {}: возвращаемого значения нет в стеке кадра (стек {}, начало кадра {})",
+                        function.name,
+                        self.stack.len(),
+                        base
+                    );
                     let return_value = self.stack.pop().unwrap_or(Value::Nil);
-                    let base = self.frames[frame_idx].base;
 
                     self.close_upvalues_from(base);
                     self.frames.pop().expect("Пустой стек вызовов");
+                    self.profile_leave();
                     self.stack.truncate(base);
                     self.stack.push(return_value);
-                    continue;
+                    (frame_idx, function, ip, base) = self.active_frame();
                 }
-                OpCode::Class => {
-                    // Следующий опкод: Constant с именем класса
-                    self.frames[frame_idx].ip += 1;
-                    let name_opcode = self.frames[frame_idx].opcodes[self.frames[frame_idx].ip];
-                    let name = match name_opcode {
-                        OpCode::Constant(idx) => match &self.frames[frame_idx].constants[idx] {
-                            Value::String(s) => s.clone(),
-                            _ => panic!("Имя класса должно быть строкой"),
-                        },
-                        _ => panic!("Ожидался Constant после Class"),
-                    };
-
+                OpCode::Class(name_idx) => {
                     let class = Class {
-                        name,
+                        name: self.expect_string(&function.constants, name_idx),
                         methods: HashMap::new(),
                         fields: Vec::new(),
                         parent: None,
+                        statics: RefCell::default(),
                     };
 
                     self.stack.push(Value::Class(Rc::new(class)));
@@ -415,8 +1068,7 @@ impl<'a> VM {
                     }
                 }
                 OpCode::DefineMethod(name_idx) => {
-                    let method_name =
-                        self.expect_string(&self.frames[frame_idx].constants, name_idx);
+                    let method_name = self.expect_string(&function.constants, name_idx);
                     let method = self.stack.pop().unwrap();
 
                     let class_value = self.stack.last_mut().unwrap();
@@ -426,7 +1078,7 @@ impl<'a> VM {
 
                             let func = match method {
                                 Value::Function(f) => f,
-                                Value::Closure(c) => Rc::new(c.function.clone()),
+                                Value::Closure(c) => c.function.clone(),
                                 _ => panic!("Метод должен быть функцией или замыканием"),
                             };
 
@@ -435,17 +1087,18 @@ impl<'a> VM {
                         _ => panic!("DefineMethod: не класс на вершине стека"),
                     }
                 }
-                OpCode::GetProperty => {
-                    // Следующий опкод: Constant с индексом имени свойства
-                    self.frames[frame_idx].ip += 1;
-                    let name_opcode = self.frames[frame_idx].opcodes[self.frames[frame_idx].ip];
-                    let property_name = match name_opcode {
-                        OpCode::Constant(idx) => match &self.frames[frame_idx].constants[idx] {
-                            Value::String(s) => s.clone(),
-                            _ => panic!("Имя свойства должно быть строкой"),
-                        },
-                        _ => panic!("Ожидался Constant после GetProperty"),
-                    };
+                OpCode::DefineStatic(name_idx) => {
+                    let name = self.expect_string(&function.constants, name_idx);
+                    let value = self.stack.pop().unwrap();
+                    match self.stack.last() {
+                        Some(Value::Class(class)) => {
+                            class.statics.borrow_mut().insert(name, value);
+                        }
+                        _ => panic!("DefineStatic: не класс на вершине стека"),
+                    }
+                }
+                OpCode::GetProperty(name_idx) => {
+                    let property_name = Self::constant_name(&function.constants, name_idx);
 
                     let receiver = self.stack.pop().unwrap();
 
@@ -454,76 +1107,98 @@ impl<'a> VM {
                         Value::Array(_) => Some(TypeDiscriminant::Array),
                         Value::String(_) => Some(TypeDiscriminant::String),
                         Value::Dict(_) => Some(TypeDiscriminant::Dict),
+                        Value::StringBuilder(_) => Some(TypeDiscriminant::StringBuilder),
                         _ => None,
                     };
 
                     // 2. Искать в реестре встроенных методов
                     let mut handled = false;
-                    if let Some(disc) = type_disc {
-                        if let Some(builtin_method) = self.builtin_method_registry.get(disc, &property_name) {
-                            let bound = Value::BoundBuiltinMethod(Rc::new(BoundBuiltinMethod {
-                                receiver: Box::new(receiver.clone()),
-                                method: builtin_method,
+                    if let Some(disc) = type_disc
+                        && let Some(builtin_method) =
+                            self.builtin_method_registry.get(disc, &property_name)
+                    {
+                        let bound = Value::BoundBuiltinMethod(Rc::new(BoundBuiltinMethod {
+                            receiver: Box::new(receiver.clone()),
+                            method: builtin_method,
+                        }));
+                        self.stack.push(bound);
+                        handled = true;
+                    }
+
+                    // 3. Проверить нативные методы для Instance
+                    if !handled && let Value::Instance(ref instance_rc) = receiver {
+                        let class_name = &instance_rc.borrow().class.name;
+                        if let Some(native_method) =
+                            self.native_method_registry.get(class_name, &property_name)
+                        {
+                            let bound = Value::BoundNativeMethod(Rc::new(BoundNativeMethod {
+                                receiver: instance_rc.clone(),
+                                method: native_method,
                             }));
                             self.stack.push(bound);
                             handled = true;
                         }
                     }
 
-                    // 3. Проверить нативные методы для Instance
+                    // 4. Fallback на существующую обработку Instance
                     if !handled {
-                        if let Value::Instance(ref instance_rc) = receiver {
-                            let class_name = &instance_rc.borrow().class.name;
-                            if let Some(native_method) = self.native_method_registry.get(class_name, &property_name) {
-                                let bound = Value::BoundNativeMethod(Rc::new(BoundNativeMethod {
-                                    receiver: instance_rc.clone(),
-                                    method: native_method,
-                                }));
-                                self.stack.push(bound);
-                                handled = true;
+                        match receiver {
+                            Value::Instance(instance_rc) => {
+                                // Сначала ищем в полях
+                                let field_value = instance_rc
+                                    .borrow()
+                                    .fields
+                                    .get(property_name.as_str())
+                                    .cloned();
+
+                                if let Some(value) = field_value {
+                                    self.stack.push(value);
+                                }
+                                // Потом в методах (включая родительские классы) → BoundMethod
+                                else {
+                                    let method =
+                                        instance_rc.borrow().class.find_method(&property_name);
+
+                                    if let Some(method) = method {
+                                        let bound = BoundMethod {
+                                            receiver: instance_rc.clone(),
+                                            method,
+                                        };
+                                        self.stack.push(Value::BoundMethod(Rc::new(bound)));
+                                    } else {
+                                        let class = instance_rc.borrow().class.name.clone();
+                                        raise!(
+                                            exceptions::TYPE_ERROR,
+                                            "У объекта класса {} нет свойства '{}'",
+                                            class,
+                                            property_name
+                                        );
+                                    }
+                                }
                             }
-                        }
-                    }
-
-                    // 4. Fallback на существующую обработку Instance
-                    if !handled {match receiver {
-                        Value::Instance(instance_rc) => {
-                            // Сначала ищем в полях
-                            let field_value =
-                                instance_rc.borrow().fields.get(&property_name).cloned();
-
-                            if let Some(value) = field_value {
+                            Value::Class(class) => {
+                                let Some(owner) = class.find_static(&property_name) else {
+                                    raise!(
+                                        exceptions::TYPE_ERROR,
+                                        "У класса {} нет статического члена '{}'",
+                                        class.name,
+                                        property_name
+                                    );
+                                };
+                                let value = owner.statics.borrow()[property_name.as_str()].clone();
                                 self.stack.push(value);
                             }
-                            // Потом в методах (включая родительские классы) → BoundMethod
-                            else {
-                                let method = instance_rc.borrow().class.find_method(&property_name);
-
-                                if let Some(method) = method {
-                                    let bound = BoundMethod {
-                                        receiver: instance_rc.clone(),
-                                        method,
-                                    };
-                                    self.stack.push(Value::BoundMethod(Rc::new(bound)));
-                                } else {
-                                    panic!("Свойство '{}' не найдено", property_name);
-                                }
-                            }
+                            other => raise!(
+                                exceptions::TYPE_ERROR,
+                                "У значения типа {} нет свойства '{}'",
+                                Type::of_value(&other),
+                                property_name
+                            ),
                         }
-                        _ => panic!("GetProperty: не экземпляр и нет встроенного метода, получен {:?}", receiver),
-                    }}
+                    }
                 }
-                OpCode::SetProperty => {
-                    // Следующий опкод: Constant с индексом имени свойства
-                    self.frames[frame_idx].ip += 1;
-                    let name_opcode = self.frames[frame_idx].opcodes[self.frames[frame_idx].ip];
-                    let property_name = match name_opcode {
-                        OpCode::Constant(idx) => match &self.frames[frame_idx].constants[idx] {
-                            Value::String(s) => s.clone(),
-                            _ => panic!("Имя свойства должно быть строкой"),
-                        },
-                        _ => panic!("Ожидался Constant после SetProperty"),
-                    };
+                OpCode::SetProperty(name_idx) => {
+                    let property_name = Self::constant_name(&function.constants, name_idx);
 
                     let instance_value = self.stack.pop().unwrap();
                     let value = self.stack.pop().unwrap();
@@ -533,23 +1208,51 @@ impl<'a> VM {
                             instance_rc
                                 .borrow_mut()
                                 .fields
-                                .insert(property_name, value.clone());
+                                .insert(property_name.to_string(), value.clone());
+                            self.stack.push(value);
+                        }
+                        // Статическое поле меняется в классе, где оно объявлено
+                        Value::Class(class) => {
+                            let Some(owner) = class.find_static(&property_name) else {
+                                raise!(
+                                    exceptions::TYPE_ERROR,
+                                    "У класса {} нет статического члена '{}'",
+                                    class.name,
+                                    property_name
+                                );
+                            };
+                            owner
+                                .statics
+                                .borrow_mut()
+                                .insert(property_name.to_string(), value.clone());
                             self.stack.push(value);
                         }
-                        _ => panic!("SetProperty: не экземпляр"),
+                        other => raise!(
+                            exceptions::TYPE_ERROR,
+                            "Свойство '{}' можно задать только объекту, получено: {}",
+                            property_name,
+                            Type::of_value(&other)
+                        ),
                     }
                 }
-                OpCode::GetSuper => {
-                    // Следующий опкод: Constant с именем метода
-                    self.frames[frame_idx].ip += 1;
-                    let name_opcode = self.frames[frame_idx].opcodes[self.frames[frame_idx].ip];
-                    let method_name = match name_opcode {
-                        OpCode::Constant(idx) => match &self.frames[frame_idx].constants[idx] {
-                            Value::String(s) => s.clone(),
-                            _ => panic!("Имя метода должно быть строкой"),
-                        },
-                        _ => panic!("Ожидался Constant после GetSuper"),
+                OpCode::InstanceOf => {
+                    let class = self.stack.pop().expect("Стек пуст при InstanceOf");
+                    let value = self.stack.pop().expect("Стек пуст при InstanceOf");
+                    let Value::Class(class) = class else {
+                        raise!(
+                            exceptions::TYPE_ERROR,
+                            "Ожидался класс исключения, получено: {}",
+                            Type::of_value(&class)
+                        );
+                    };
+                    let matches = match &value {
+                        Value::Instance(instance) => instance.borrow().class.is_subclass_of(&class),
+                        _ => false,
                     };
+                    self.stack.push(Value::Boolean(matches));
+                }
+                OpCode::GetSuper(name_idx) => {
+                    let method_name = Self::constant_name(&function.constants, name_idx);
 
                     // Получаем экземпляр со стека
                     let instance_value = self.stack.pop().unwrap();
@@ -565,10 +1268,13 @@ impl<'a> VM {
                                 .clone();
 
                             // Ищем метод в родительском классе
-                            let method = parent_class.find_method(&method_name).expect(&format!(
-                                "Метод '{}' не найден в родительском классе",
-                                method_name
-                            ));
+                            let method =
+                                parent_class.find_method(&method_name).unwrap_or_else(|| {
+                                    panic!(
+                                        "Метод '{}' не найден в родительском классе",
+                                        method_name
+                                    )
+                                });
 
                             // Создаём BoundMethod
                             let bound = BoundMethod {
@@ -587,17 +1293,21 @@ impl<'a> VM {
 
                     match (&object, &index) {
                         // Индексирование строки
-                        (Value::String(s), Value::Number(n)) => {
-                            let idx = *n as usize;
+                        (Value::String(s), Value::Int(_) | Value::Number(_)) => {
+                            let idx = match index_position(&index) {
+                                Ok(idx) => idx,
+                                Err(message) => raise!(exceptions::INDEX_ERROR, "{}", message),
+                            };
                             if idx >= s.chars().count() {
-                                panic!(
+                                raise!(
+                                    exceptions::INDEX_ERROR,
                                     "Индекс {} вне диапазона для строки длиной {}",
                                     idx,
                                     s.chars().count()
                                 );
                             }
                             let ch = s.chars().nth(idx).unwrap();
-                            self.stack.push(Value::String(ch.to_string()));
+                            self.stack.push(Value::String(ch.to_string().into()));
                         }
                         // Срез строки
                         (Value::String(s), Value::Range(start, end)) => {
@@ -607,9 +1317,12 @@ impl<'a> VM {
 
                             if start_idx > char_count || end_idx > char_count || start_idx > end_idx
                             {
-                                panic!(
+                                raise!(
+                                    exceptions::INDEX_ERROR,
                                     "Некорректные границы среза: [{}:{}] для строки длиной {}",
-                                    start_idx, end_idx, char_count
+                                    start_idx,
+                                    end_idx,
+                                    char_count
                                 );
                             }
 
@@ -618,14 +1331,18 @@ impl<'a> VM {
                                 .skip(start_idx)
                                 .take(end_idx - start_idx)
                                 .collect();
-                            self.stack.push(Value::String(slice));
+                            self.stack.push(Value::String(slice.into()));
                         }
                         // Индексирование массива
-                        (Value::Array(arr), Value::Number(n)) => {
-                            let idx = *n as usize;
+                        (Value::Array(arr), Value::Int(_) | Value::Number(_)) => {
+                            let idx = match index_position(&index) {
+                                Ok(idx) => idx,
+                                Err(message) => raise!(exceptions::INDEX_ERROR, "{}", message),
+                            };
                             let array = arr.borrow();
                             if idx >= array.len() {
-                                panic!(
+                                raise!(
+                                    exceptions::INDEX_ERROR,
                                     "Индекс {} вне диапазона для массива длиной {}",
                                     idx,
                                     array.len()
@@ -643,7 +1360,8 @@ impl<'a> VM {
                                 || end_idx > array.len()
                                 || start_idx > end_idx
                             {
-                                panic!(
+                                raise!(
+                                    exceptions::INDEX_ERROR,
                                     "Некорректные границы среза: [{}:{}] для массива длиной {}",
                                     start_idx,
                                     end_idx,
@@ -656,15 +1374,22 @@ impl<'a> VM {
                         }
                         // Индексирование словаря
                         (Value::Dict(dict), key_value) => {
-                            let key =
-                                ValueKey::from_value(key_value).expect("Некорректный ключ словаря");
+                            let key = match ValueKey::from_value(key_value) {
+                                Ok(key) => key,
+                                Err(message) => raise!(exceptions::TYPE_ERROR, "{}", message),
+                            };
                             let dictionary = dict.borrow();
                             match dictionary.get(&key) {
                                 Some(value) => self.stack.push(value.clone()),
                                 None => self.stack.push(Value::Nil), // Ключ не найден
                             }
                         }
-                        _ => panic!("Индексирование не поддерживается для данных типов"),
+                        _ => raise!(
+                            exceptions::TYPE_ERROR,
+                            "Индексирование не поддерживается: {}[{}]",
+                            Type::of_value(&object),
+                            Type::of_value(&index)
+                        ),
                     }
                 }
                 OpCode::SetIndex => {
@@ -673,21 +1398,37 @@ impl<'a> VM {
                     let object = self.stack.pop().unwrap();
 
                     match (object, &index) {
-                        (Value::Array(arr), Value::Number(n)) => {
-                            let idx = *n as usize;
+                        (Value::Array(arr), Value::Int(_) | Value::Number(_)) => {
+                            let idx = match index_position(&index) {
+                                Ok(idx) => idx,
+                                Err(message) => raise!(exceptions::INDEX_ERROR, "{}", message),
+                            };
                             let mut array = arr.borrow_mut();
                             if idx >= array.len() {
-                                panic!("Индекс {} вне диапазона", idx);
+                                raise!(
+                                    exceptions::INDEX_ERROR,
+                                    "Индекс {} вне диапазона для массива длиной {}",
+                                    idx,
+                                    array.len()
+                                );
                             }
                             array[idx] = value.clone();
                             self.stack.push(value); // Присваивание возвращает значение
                         }
                         (Value::Dict(dict), key_value) => {
-                            let key = ValueKey::from_value(key_value).expect("Некорректный ключ");
+                            let key = match ValueKey::from_value(key_value) {
+                                Ok(key) => key,
+                                Err(message) => raise!(exceptions::TYPE_ERROR, "{}", message),
+                            };
                             dict.borrow_mut().insert(key, value.clone());
                             self.stack.push(value);
                         }
-                        _ => panic!("Установка индекса не поддерживается"),
+                        (object, _) => raise!(
+                            exceptions::TYPE_ERROR,
+                            "Присваивание по индексу не поддерживается: {}[{}]",
+                            Type::of_value(&object),
+                            Type::of_value(&index)
+                        ),
                     }
                 }
                 OpCode::Array(count) => {
@@ -699,66 +1440,131 @@ impl<'a> VM {
                     self.stack
                         .push(Value::Array(Rc::new(RefCell::new(elements))));
                 }
+                OpCode::Iterable => {
+                    // Ключи снимаются заранее: изменение словаря в теле цикла
+                    // не влияет на обход
+                    if let Some(Value::Dict(dict)) = self.stack.last() {
+                        let keys = dict.borrow().keys().map(ValueKey::to_value).collect();
+                        *self.stack.last_mut().unwrap() = Value::Array(Rc::new(RefCell::new(keys)));
+                    }
+                }
                 OpCode::Dict(count) => {
-                    let mut map = HashMap::new();
-                    // Собираем пары со стека
-                    for _ in 0..count {
-                        let value = self.stack.pop().unwrap();
-                        let key_value = self.stack.pop().unwrap();
-                        let key =
-                            ValueKey::from_value(&key_value).expect("Некорректный ключ словаря");
-                        map.insert(key, value);
+                    // Пары лежат на стеке в порядке записи в литерале
+                    let pairs = self.stack.split_off(self.stack.len() - 2 * count as usize);
+                    let map: Result<value::Dict, String> = pairs
+                        .chunks(2)
+                        .map(|pair| Ok((ValueKey::from_value(&pair[0])?, pair[1].clone())))
+                        .collect();
+                    match map {
+                        Ok(map) => self.stack.push(Value::Dict(Rc::new(RefCell::new(map)))),
+                        Err(message) => raise!(exceptions::TYPE_ERROR, "{}", message),
                     }
-                    self.stack.push(Value::Dict(Rc::new(RefCell::new(map))));
                 }
-                OpCode::DefineGlobal(name_idx) => {
-                    let name = self.expect_string(&self.frames[frame_idx].constants, name_idx);
+                OpCode::DefineGlobal(slot) => {
+                    let slot = slot as usize;
                     let value = self
                         .stack
                         .pop()
                         .expect("Стек пуст при определении глобальной переменной");
 
-                    if self.globals.contains_key(&name) {
-                        panic!("Глобальная переменная {name} уже определена");
+                    if self.globals.is_defined(slot) && slot >= self.globals.builtins {
+                        panic!(
+                            "Глобальная переменная {} уже определена",
+                            self.globals.names[slot]
+                        );
                     }
 
-                    self.globals.insert(name, value);
+                    self.globals.values[slot] = Some(value);
                 }
-                OpCode::SetGlobal(name_idx) => {
-                    let name = self.expect_string(&self.frames[frame_idx].constants, name_idx);
+                OpCode::SetGlobal(slot) => {
+                    let slot = slot as usize;
                     let value = self
                         .stack
                         .pop()
                         .expect("Стек пуст при присваивании глобальной переменной");
 
-                    let slot = self
-                        .globals
-                        .get_mut(&name)
-                        .unwrap_or_else(|| panic!("Глобальная переменная {name} не найдена"));
+                    // Присваивание заменяет и еще не материализованное значение
+                    if self.globals.lazy.remove(&slot).is_some() {
+                        self.globals.values[slot] = Some(Value::Nil);
+                    }
 
-                    *slot = value;
+                    match &mut self.globals.values[slot] {
+                        Some(existing) => *existing = value,
+                        None => panic!(
+                            "Глобальная переменная {} не найдена",
+                            self.globals.names[slot]
+                        ),
+                    }
                 }
-                OpCode::GetGlobal(name_idx) => {
-                    let name = self.expect_string(&self.frames[frame_idx].constants, name_idx);
-                    let value = self
-                        .globals
-                        .get(&name)
-                        .cloned()
-                        .unwrap_or_else(|| panic!("Глобальная переменная {name} не найдена"));
+                OpCode::GetGlobal(slot) => {
+                    let slot = slot as usize;
+                    if self.globals.values[slot].is_none()
+                        && let Err(message) = self.materialize_global(slot)
+                    {
+                        raise!(exceptions::ERROR, "{}", message);
+                    }
+
+                    let value = match &self.globals.values[slot] {
+                        Some(value) => value.clone(),
+                        None => raise!(exceptions::ERROR, "{}", self.undefined_global(slot)),
+                    };
 
                     self.stack.push(value);
                 }
+                OpCode::AppendGlobal(slot) => {
+                    let slot = slot as usize;
+                    if self.globals.values[slot].is_none()
+                        && let Err(message) = self.materialize_global(slot)
+                    {
+                        raise!(exceptions::ERROR, "{}", message);
+                    }
+                    let right = self.stack.pop().expect("Стек пуст");
+                    let left = match &mut self.globals.values[slot] {
+                        Some(existing) => {
+                            if value::append_in_place(existing, &right) {
+                                continue;
+                            }
+                            existing.clone()
+                        }
+                        None => raise!(exceptions::ERROR, "{}", self.undefined_global(slot)),
+                    };
+                    self.stack.push(left);
+                    self.stack.push(right);
+                    if let Err(message) = self.binary_add_op() {
+                        raise!(exceptions::TYPE_ERROR, "{}", message);
+                    }
+                    self.globals.values[slot] = self.stack.pop();
+                }
                 OpCode::Halt => {}
             };
-
-            if frame_idx < self.frames.len() {
-                self.frames[frame_idx].ip += 1;
-            }
         }
+
+        self.frame_boundary = outer_boundary;
+    }
+
+    /// Загрузить состояние верхнего кадра в кеш цикла выполнения
+    fn active_frame(&self) -> (usize, Rc<Function>, usize, usize) {
+        let frame_idx = self.frames.len() - 1;
+        let frame = &self.frames[frame_idx];
+        (frame_idx, frame.function.clone(), frame.ip, frame.base)
     }
 
     fn handle_exception(&mut self, exception_value: Value) {
-        while let Some(frame_idx) = self.frames.len().checked_sub(1) {
+        self.record_stack(&exception_value);
+        // Обработчик восстанавливает стек, каким он был при входе в try
+        self.expected_stack = None;
+
+        // Без обработчика сообщаем об ошибке до раскрутки, пока стек вызовов цел
+        let has_handler = self.frames[self.frame_boundary..]
+            .iter()
+            .any(|frame| !frame.exception_handlers.is_empty());
+        if !has_handler && self.frame_boundary == 0 && self.current_task.is_none() {
+            self.handle_unhandled_exception(exception_value);
+            return;
+        }
+
+        while self.frames.len() > self.frame_boundary {
+            let frame_idx = self.frames.len() - 1;
             if let Some(handler) = {
                 let frame = self.frames.get_mut(frame_idx).expect("frame missing");
                 frame.exception_handlers.pop()
@@ -787,97 +1593,356 @@ impl<'a> VM {
             self.stack.truncate(base);
         }
 
+        if self.frame_boundary > 0 || self.current_task.is_some() {
+            // Обработчик может быть во внешнем цикле: исключение будет брошено
+            // повторно после возврата из нативной функции (или станет итогом
+            // сопрограммы)
+            self.pending_exception = Some(exception_value);
+            return;
+        }
+
         // Обработчик не найден
         self.handle_unhandled_exception(exception_value);
     }
 
-    fn handle_unhandled_exception(&self, exception_value: Value) {
-        eprintln!("Необработанное исключение:");
-        match exception_value {
-            Value::String(s) => eprintln!("  {}", s),
-            other => eprintln!("  {:?}", other),
+    // Сохранить стек вызовов в брошенный экземпляр ошибки. Стек уже
+    // брошенной ошибки (повторный бросок из перехвата) не меняется
+    fn record_stack(&self, exception: &Value) {
+        let Some(instance) = self.errors.error_instance(exception) else {
+            return;
+        };
+        let mut instance = instance.borrow_mut();
+        let recorded = match instance.fields.get(exceptions::STACK_FIELD) {
+            Some(Value::Array(stack)) => !stack.borrow().is_empty(),
+            _ => false,
+        };
+        if !recorded {
+            let stack = self.stack_trace().into_iter().map(Value::from).collect();
+            instance.fields.insert(
+                exceptions::STACK_FIELD.to_string(),
+                Value::Array(Rc::new(RefCell::new(stack))),
+            );
         }
+    }
 
-        eprintln!("\nСтек вызовов:");
-        for (i, frame) in self.frames.iter().rev().enumerate() {
-            eprintln!("  #{} на инструкции {}", i, frame.ip);
-        }
+    // Кадры от текущего к внешнему в виде `функция (файл, строка N)`, без
+    // служебных кадров, с которых начинаются вызовы из Rust и задачи
+    fn stack_trace(&self) -> Vec<String> {
+        self.frames
+            .iter()
+            .rev()
+            .filter(|frame| frame.function.name != CALL_ENTRY_NAME)
+            .map(|frame| {
+                let function = &frame.function;
+                // ip кадра указывает на инструкцию после Throw или Call
+                let position = match function.lines.line_at(frame.ip.saturating_sub(1)) {
+                    Some(line) => format!("строка {}", line),
+                    None => format!("инструкция {}", frame.ip),
+                };
+                match &function.source_name {
+                    Some(file) => format!("{} ({}, {})", function.name, file, position),
+                    None => format!("{} ({})", function.name, position),
+                }
+            })
+            .collect()
+    }
 
-        panic!("Программа завершена из-за необработанного исключения");
+    // Прервать выполнение, минуя обработчики исключений. Как и необработанное
+    // исключение, прерывание сообщается паникой, которую перехватывает Ponos
+    fn abort(&mut self, message: String) -> ! {
+        let trace = self.report_error("Выполнение прервано:", &message);
+        panic!("Программа прервана: {}{}", message, trace);
     }
 
-    fn binary_logical_op<F>(&mut self, f: F)
-    where
-        F: FnOnce(Value, Value) -> bool,
-    {
-        let b = self.stack.pop().unwrap();
-        let a = self.stack.pop().unwrap();
+    fn handle_unhandled_exception(&mut self, exception_value: Value) {
+        let message = self.errors.describe(&exception_value);
+        let trace = self.report_error("Необработанное исключение:", &message);
+        panic!(
+            "Программа завершена из-за необработанного исключения: {}{}",
+            message, trace
+        );
+    }
 
-        self.stack.push(Value::Boolean(f(a, b)));
+    // Напечатать отчёт об ошибке со стеком вызовов. Если отчёты выключены,
+    // вернуть стек вызовов для текста паники, иначе пустую строку
+    fn report_error(&mut self, title: &str, message: &str) -> String {
+        let mut trace = String::from("\nСтек вызовов:");
+        for (i, frame) in self.stack_trace().iter().enumerate() {
+            trace.push_str(&format!("\n  #{} {}", i, frame));
+        }
+        // Накопленный вывод программы должен оказаться перед сообщением об ошибке
+        self.flush_output();
+        if !self.report_errors {
+            return trace;
+        }
+        eprintln!("{}", title);
+        eprintln!("  {}", message);
+        eprintln!("{}", trace);
+        String::new()
     }
 
-    fn binary_number_op<F>(&mut self, f: F)
+    fn binary_logical_op<F>(&mut self, f: F)
     where
-        F: FnOnce(f64, f64) -> f64,
+        F: FnOnce(Value, Value) -> bool,
     {
-        let b = match self.stack.pop().unwrap() {
-            Value::Number(n) => n,
-            _ => panic!("Right operand is not a number"),
-        };
+        let b = self.stack.pop().unwrap();
+        let a = self.stack.pop().unwrap();
 
-        let a = match self.stack.pop().unwrap() {
-            Value::Number(n) => n,
-            _ => panic!("Left operand is not a number"),
-        };
+        self.stack.push(Value::Boolean(f(a, b)));
+    }
 
-        self.stack.push(Value::Number(f(a, b)));
+    fn binary_number_op(
+        &mut self,
+        operator: &str,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<(), String> {
+        let b = self.stack.pop().unwrap();
+        let a = self.stack.pop().unwrap();
+        match value::arithmetic(&a, &b, int_op, float_op) {
+            Some(result) => {
+                self.stack.push(result);
+                Ok(())
+            }
+            None => Err(format!(
+                "Оператор {} применим только к числам, получено: {} {} {}",
+                operator,
+                Type::of_value(&a),
+                operator,
+                Type::of_value(&b)
+            )),
+        }
     }
 
-    fn binary_add_op(&mut self) {
+    fn binary_add_op(&mut self) -> Result<(), String> {
         let right = self.stack.pop().unwrap();
         let left = self.stack.pop().unwrap();
 
-        match (&left, &right) {
-            // Конкатенация строк
-            (Value::String(s1), Value::String(s2)) => {
-                self.stack.push(Value::String(format!("{}{}", s1, s2)));
-            }
-            // Арифметика чисел
-            (Value::Number(n1), Value::Number(n2)) => {
-                self.stack.push(Value::Number(n1 + n2));
-            }
+        // Арифметика чисел
+        if let Some(sum) = value::arithmetic(&left, &right, i64::checked_add, |a, b| a + b) {
+            self.stack.push(sum);
+            return Ok(());
+        }
+
+        // Строка + строка или число: буфер левого операнда переиспользуется,
+        // если на него больше никто не ссылается
+        let mut left = left;
+        if value::append_in_place(&mut left, &right) {
+            self.stack.push(left);
+            return Ok(());
+        }
+
+        match (left, right) {
             // Преобразование число + строка
+            (Value::Int(n), Value::String(s)) => {
+                let mut result = String::with_capacity(s.len() + 8);
+                value::write_int(&mut result, n);
+                result.push_str(&s);
+                self.stack.push(Value::String(result.into()));
+            }
             (Value::Number(n), Value::String(s)) => {
-                self.stack.push(Value::String(format!("{}{}", n, s)));
+                let mut result = String::with_capacity(s.len() + 8);
+                value::write_number(&mut result, n);
+                result.push_str(&s);
+                self.stack.push(Value::String(result.into()));
             }
-            (Value::String(s), Value::Number(n)) => {
-                self.stack.push(Value::String(format!("{}{}", s, n)));
+
+            (left, right) => {
+                return Err(format!(
+                    "Оператор + поддерживает только числа и строки, получено: {} + {}",
+                    Type::of_value(&left),
+                    Type::of_value(&right)
+                ));
             }
-            _ => panic!("Оператор + поддерживает только числа и строки"),
         }
+        Ok(())
     }
 
-    fn expect_string(&self, constants: &[Value], idx: usize) -> String {
-        match &constants[idx] {
-            Value::String(s) => s.clone(),
+    /// Переписать операнды DefineGlobal/SetGlobal/GetGlobal с индекса имени
+    /// в пуле констант на номер глобального слота. Вложенные функции из пула
+    /// констант обрабатываются рекурсивно, так что проход выполняется один раз
+    /// на загружаемый фрагмент кода.
+    fn link_globals(
+        &mut self,
+        opcodes: &[OpCode],
+        constants: &[Value],
+    ) -> (Vec<OpCode>, Vec<Value>) {
+        let linked_opcodes = self.link_opcodes(opcodes, constants);
+        let linked_constants = constants
+            .iter()
+            .map(|constant| self.link_value(constant.clone()))
+            .collect();
+
+        (linked_opcodes, linked_constants)
+    }
+
+    /// Переписать операнды глобальных инструкций, не трогая вложенные функции
+    pub(crate) fn link_opcodes(&mut self, opcodes: &[OpCode], constants: &[Value]) -> Vec<OpCode> {
+        opcodes
+            .iter()
+            .map(|opcode| match *opcode {
+                OpCode::DefineGlobal(name_idx) => {
+                    OpCode::DefineGlobal(self.global_slot(constants, name_idx))
+                }
+                OpCode::SetGlobal(name_idx) => {
+                    OpCode::SetGlobal(self.global_slot(constants, name_idx))
+                }
+                OpCode::GetGlobal(name_idx) => {
+                    OpCode::GetGlobal(self.global_slot(constants, name_idx))
+                }
+                OpCode::AppendGlobal(name_idx) => {
+                    OpCode::AppendGlobal(self.global_slot(constants, name_idx))
+                }
+                other => other,
+            })
+            .collect()
+    }
+
+    /// Связать глобальные имена внутри функции или замыкания
+    fn link_value(&mut self, value: Value) -> Value {
+        match value {
+            Value::Function(func) => Value::Function(self.link_function(&func)),
+            Value::Closure(closure) => Value::Closure(Rc::new(Closure {
+                function: self.link_function(&closure.function),
+                upvalues: closure.upvalues.clone(),
+            })),
+            other => other,
+        }
+    }
+
+    fn link_function(&mut self, func: &Function) -> Rc<Function> {
+        let (opcodes, constants) = self.link_globals(&func.opcodes, &func.constants);
+        Rc::new(Function {
+            opcodes,
+            constants,
+            ..func.clone()
+        })
+    }
+
+    /// Вычислить отложенное значение глобального слота, если оно зарегистрировано
+    /// Сообщение об обращении к глобальной переменной без значения
+    fn undefined_global(&self, slot: usize) -> String {
+        match self.globals.names[slot].split_once("::") {
+            // Модуль импортирован, но его код ещё не выполнен: так бывает при
+            // циклическом импорте
+            Some((module, name)) => format!(
+                "Символ '{}' модуля '{}' ещё не определён: код модуля выполняется позже (циклический импорт?)",
+                name, module
+            ),
+            None => panic!(
+                "Глобальная переменная {} не найдена",
+                self.globals.names[slot]
+            ),
+        }
+    }
+
+    // Вычислить отложенное значение слота. Ошибка вычисления бросается в
+    // месте обращения и повторяется при следующих обращениях
+    fn materialize_global(&mut self, slot: usize) -> Result<(), String> {
+        if let Some(init) = self.globals.lazy.remove(&slot) {
+            let value = match init() {
+                Ok(value) => self.link_value(value),
+                Err(message) => {
+                    let repeated = message.clone();
+                    self.globals
+                        .lazy
+                        .insert(slot, Box::new(move || Err(repeated)));
+                    return Err(message);
+                }
+            };
+            self.globals.values[slot] = Some(value);
+            return Ok(());
+        }
+
+        // Код модуля обращается к глобальным именам с префиксом пространства
+        // имён; если модуль такое имя не определил, берётся встроенная функция
+        // (длина, вывести, нативные функции модулей стандартной библиотеки)
+        let builtin = match self.globals.names[slot].rsplit_once("::") {
+            Some((_, name)) => self.globals.slots.get(name).copied(),
+            None => None,
+        };
+        if let Some(builtin) = builtin {
+            self.materialize_global(builtin)?;
+            self.globals.values[slot] = self.globals.values[builtin].clone();
+        }
+        Ok(())
+    }
+
+    fn global_slot(&mut self, constants: &[Value], name_idx: u32) -> u32 {
+        let name = self.expect_string(constants, name_idx);
+        operand(self.globals.slot(&name))
+    }
+
+    fn expect_string(&self, constants: &[Value], idx: u32) -> String {
+        match &constants[idx as usize] {
+            Value::String(s) => s.to_string(),
             _ => panic!("Ожидалась строка в пуле констант по индексу {idx}"),
         }
     }
 
+    /// Имя свойства или метода из пула констант: буфер строки разделяется
+    /// с константой, поэтому обращение к свойству не выделяет память
+    fn constant_name(constants: &[Value], idx: u32) -> Rc<String> {
+        match &constants[idx as usize] {
+            Value::String(s) => s.clone(),
+            _ => panic!("Ожидалось имя в пуле констант по индексу {idx}"),
+        }
+    }
+
+    /// Разложить `arg_count` аргументов с вершины стека по слотам параметров
+    /// функции и вернуть адрес начала выполнения. Пропущенные необязательные
+    /// аргументы вычислит пролог функции, лишние уходят в массив `...остальные`
+    fn bind_arguments(&mut self, func: &Function, arg_count: usize) -> Result<usize, String> {
+        let Some(ip) = func.entry_point(arg_count) else {
+            return Err(format!(
+                "Ожидается {} аргументов, передано {}",
+                func.arity_description(),
+                arg_count
+            ));
+        };
+        let base = self.stack.len() - arg_count;
+        let positional = func.positional_arity();
+        let rest: Vec<Value> = if arg_count > positional {
+            self.stack.drain(base + positional..).collect()
+        } else {
+            Vec::new()
+        };
+        self.stack.resize(base + positional, Value::Nil);
+        if func.variadic {
+            self.stack.push(Value::Array(Rc::new(RefCell::new(rest))));
+        }
+        Ok(ip)
+    }
+
+    /// Заменить массивы аргументов `CallSpread` на вершине стека их
+    /// элементами и вернуть общее число аргументов
+    fn spread_arguments(&mut self, pieces: usize) -> Result<usize, String> {
+        let start = self.stack.len() - pieces;
+        let mut arguments = Vec::new();
+        for piece in self.stack.drain(start..) {
+            match piece {
+                Value::Array(array) => arguments.extend(array.borrow().iter().cloned()),
+                other => {
+                    return Err(format!(
+                        "Развернуть в аргументы можно только массив, получено: {}",
+                        Type::of_value(&other)
+                    ));
+                }
+            }
+        }
+        let count = arguments.len();
+        self.stack.extend(arguments);
+        Ok(count)
+    }
+
     fn call_function(
         &mut self,
         func: Rc<Function>,
         arg_count: usize,
         is_method: bool,
     ) -> Result<(), String> {
-        if arg_count != func.arity {
-            return Err(format!(
-                "Ожидается {} аргументов, передано {}",
-                func.arity, arg_count
-            ));
-        }
-
-        let base = self.stack.len() - arg_count;
+        let ip = self.bind_arguments(&func, arg_count)?;
+        let base = self.stack.len() - func.arity;
 
         // Для методов и конструкторов не удаляем callee - он станет 'это' (слот 0)
         let final_base = if !is_method {
@@ -887,10 +1952,12 @@ impl<'a> VM {
             base - 1
         };
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(&func, self.frames.len() + 1);
+        }
         self.frames.push(CallFrame {
-            opcodes: func.opcodes.clone(),
-            constants: func.constants.clone(),
-            ip: 0,
+            function: func,
+            ip,
             base: final_base,
             upvalues: Vec::new(),
             exception_handlers: Vec::new(),
@@ -900,22 +1967,18 @@ impl<'a> VM {
     }
 
     fn call_closure(&mut self, closure: Rc<Closure>, arg_count: usize) -> Result<(), String> {
-        if arg_count != closure.function.arity {
-            return Err(format!(
-                "Ожидается {} аргументов, передано {}",
-                closure.function.arity, arg_count
-            ));
-        }
-
-        let base = self.stack.len() - arg_count;
+        let ip = self.bind_arguments(&closure.function, arg_count)?;
+        let base = self.stack.len() - closure.function.arity;
         self.stack.remove(base - 1); // Удалить callee
         // После удаления callee, base сдвигается на 1
         let final_base = base - 1;
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(&closure.function, self.frames.len() + 1);
+        }
         self.frames.push(CallFrame {
-            opcodes: closure.function.opcodes.clone(),
-            constants: closure.function.constants.clone(),
-            ip: 0,
+            function: closure.function.clone(),
+            ip,
             base: final_base,
             upvalues: closure.upvalues.clone(),
             exception_handlers: Vec::new(),
@@ -924,26 +1987,62 @@ impl<'a> VM {
         Ok(())
     }
 
-    fn call_native(&mut self, id: NativeFnId, arg_count: usize) -> Result<(), String> {
+    fn call_native(&mut self, id: NativeFnId, arg_count: usize) {
         let args_start = self.stack.len() - arg_count;
         let args: Vec<Value> = self.stack.drain(args_start..).collect();
 
         self.stack.pop(); // Удалить callee
 
-        let native_fn = self.native_functions[id.0];
-        let result = native_fn(&args)?;
+        // Клонируем Rc, чтобы не держать заимствование VM во время вызова
+        let native_fn = self.native_functions[id.0].clone();
+        let result = native_fn(&mut NativeContext { vm: self }, &args);
+        if let Some(Suspend::Wait(Wait::Io(_, class))) = &mut self.suspend
+            && let Some(&native_class) = self.native_error_classes.get(&id.0)
+        {
+            // Ошибка фоновой операции бросается тем же классом, что и ошибка
+            // нативной функции
+            *class = native_class;
+        }
 
-        self.stack.push(result);
-        Ok(())
+        if self.exit_code.is_some() {
+            // Сворачиваем кадры текущего цикла выполнения; внешние циклы
+            // свернутся так же, когда вложенный вызов вернёт управление
+            while self.frames.len() > self.frame_boundary {
+                let base = self.frames.pop().expect("Пустой стек вызовов").base;
+                self.close_upvalues_from(base);
+                self.stack.truncate(base);
+            }
+            return;
+        }
+        if let Some(exception) = self.pending_exception.take() {
+            // Исключение из обратного вызова продолжает раскрутку с места вызова
+            self.handle_exception(exception);
+            return;
+        }
+
+        match result {
+            Ok(value) => self.stack.push(value),
+            // Ошибка нативной функции бросается как исключение с текстом ошибки
+            // Ошибка нативной функции бросается экземпляром класса ошибки
+            Err(message) => {
+                let class = self
+                    .native_error_classes
+                    .get(&id.0)
+                    .copied()
+                    .unwrap_or(exceptions::ERROR);
+                let exception = self.errors.instantiate(class, message);
+                self.handle_exception(exception);
+            }
+        }
     }
 
     fn capture_upvalue(&mut self, stack_index: usize) -> Rc<RefCell<Upvalue>> {
         // Ищем существующий открытый upvalue для этого индекса стека
         for upvalue in &self.open_upvalues {
-            if let Upvalue::Open(idx) = *upvalue.borrow() {
-                if idx == stack_index {
-                    return upvalue.clone();
-                }
+            if let Upvalue::Open(idx) = *upvalue.borrow()
+                && idx == stack_index
+            {
+                return upvalue.clone();
             }
         }
 
@@ -956,23 +2055,787 @@ impl<'a> VM {
     fn close_upvalues_from(&mut self, stack_index: usize) {
         for upvalue in &self.open_upvalues {
             let mut upval = upvalue.borrow_mut();
-            if let Upvalue::Open(idx) = *upval {
-                if idx >= stack_index {
-                    *upval = Upvalue::Closed(self.stack[idx].clone());
+            if let Upvalue::Open(idx) = *upval
+                && idx >= stack_index
+            {
+                *upval = Upvalue::Closed(self.stack[idx].clone());
+            }
+        }
+    }
+
+    /// Создать сопрограмму, которая вызовет `callee(args)`. Задача начнёт
+    /// выполняться, когда управление получит планировщик: сразу в очереди
+    /// или по событию `waiting_on`
+    fn spawn_task(&mut self, callee: Value, args: &[Value], waiting_on: Option<Wait>) -> Rc<Task> {
+        self.task_count += 1;
+        let task = Rc::new(Task {
+            id: self.task_count,
+            result: RefCell::new(None),
+        });
+        let suspended = SuspendedTask {
+            task: task.clone(),
+            stack: Vec::new(),
+            frames: vec![CallFrame {
+                function: Rc::new(call_entry(callee, args)),
+                ip: 0,
+                base: 0,
+                upvalues: Vec::new(),
+                exception_handlers: Vec::new(),
+            }],
+            upvalues: Vec::new(),
+            waiting_on: None,
+            resume: None,
+        };
+        match waiting_on {
+            Some(waiting_on) => self.waiting_tasks.push(SuspendedTask {
+                waiting_on: Some(waiting_on),
+                ..suspended
+            }),
+            None => self.ready_tasks.push_back(suspended),
+        }
+        task
+    }
+
+    /// Продолжить сопрограмму до её завершения или следующей приостановки.
+    /// Кадры задачи кладутся поверх текущих и выполняются вложенным циклом
+    fn resume_task(&mut self, suspended: SuspendedTask) {
+        let SuspendedTask {
+            task,
+            stack,
+            frames,
+            upvalues,
+            resume,
+            ..
+        } = suspended;
+        let offset = self.stack.len();
+        let boundary = self.frames.len();
+        self.stack.extend(stack);
+        self.frames
+            .extend(frames.into_iter().map(|frame| rebase(frame, offset, true)));
+        for (upvalue, slot) in upvalues {
+            // Пока задача стояла, переменную могли изменить через замыкание
+            if let Upvalue::Closed(value) = upvalue.replace(Upvalue::Open(offset + slot)) {
+                self.stack[offset + slot] = value;
+            }
+            self.open_upvalues.push(upvalue);
+        }
+
+        let outer_task = self.current_task.replace((task.clone(), boundary));
+        match resume {
+            Some(Ok(value)) => {
+                *self
+                    .stack
+                    .last_mut()
+                    .expect("Стек пуст при возобновлении задачи") = value;
+            }
+            Some(Err(exception)) => {
+                // Исключение бросается из вызова, на котором задача остановилась
+                self.stack.pop();
+                let outer_boundary = std::mem::replace(&mut self.frame_boundary, boundary);
+                self.handle_exception(exception);
+                self.frame_boundary = outer_boundary;
+            }
+            None => {}
+        }
+        if self.frames.len() > boundary {
+            self.run_frames(boundary);
+        }
+        self.current_task = outer_task;
+
+        if self.exit_code.is_some() {
+            // Программа завершается: кадры задачи уже свёрнуты
+            self.suspend = None;
+            self.stack.truncate(offset);
+            return;
+        }
+
+        let Some(reason) = self.suspend.take() else {
+            let result = match self.pending_exception.take() {
+                Some(exception) => Err(exception),
+                None if self.stack.len() > offset => Ok(self.stack.pop().unwrap()),
+                None => Ok(Value::Nil),
+            };
+            self.stack.truncate(offset);
+            self.finish_task(&task, result, boundary == 0);
+            return;
+        };
+
+        let frames = self
+            .frames
+            .split_off(boundary)
+            .into_iter()
+            .map(|frame| rebase(frame, offset, false))
+            .collect();
+        let mut upvalues = Vec::new();
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let idx = match *upvalue.borrow() {
+                Upvalue::Open(idx) => idx,
+                Upvalue::Closed(_) => return false,
+            };
+            if idx < offset {
+                return true;
+            }
+            upvalue.replace(Upvalue::Closed(stack[idx].clone()));
+            upvalues.push((upvalue.clone(), idx - offset));
+            false
+        });
+        let suspended = SuspendedTask {
+            task,
+            stack: self.stack.split_off(offset),
+            frames,
+            upvalues,
+            waiting_on: None,
+            resume: None,
+        };
+        match reason {
+            Suspend::Yield => self.ready_tasks.push_back(suspended),
+            Suspend::Wait(waiting_on) => self.waiting_tasks.push(SuspendedTask {
+                waiting_on: Some(waiting_on),
+                ..suspended
+            }),
+        }
+    }
+
+    // Сохранить итог задачи и разбудить задачи, ждавшие её. `detached` —
+    // задача выполнялась после завершения основной программы: если её
+    // исключение некому перехватить, оно завершает программу
+    fn finish_task(&mut self, task: &Rc<Task>, result: Result<Value, Value>, detached: bool) {
+        *task.result.borrow_mut() = Some(result.clone());
+        let woken = self.wake(
+            |waiting_on| matches!(waiting_on, Wait::Task(awaited) if Rc::ptr_eq(awaited, task)),
+            Some(result.clone()),
+        );
+        if let (false, true, Err(exception)) = (woken, detached, result) {
+            self.handle_unhandled_exception(exception);
+        }
+    }
+
+    // Перевести в очередь задачи, ждущие события `matches`, с результатом
+    // `resume`; вернуть, была ли такая задача
+    fn wake(
+        &mut self,
+        matches: impl Fn(&Wait) -> bool,
+        resume: Option<Result<Value, Value>>,
+    ) -> bool {
+        let (woken, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.waiting_tasks)
+            .into_iter()
+            .partition(|waiter| waiter.waiting_on.as_ref().is_some_and(&matches));
+        self.waiting_tasks = waiting;
+        let found = !woken.is_empty();
+        for waiter in woken {
+            self.ready_tasks.push_back(SuspendedTask {
+                waiting_on: None,
+                resume: resume.clone(),
+                ..waiter
+            });
+        }
+        found
+    }
+
+    /// Разбудить задачи по событиям реактора. С `wait` ждать первого события,
+    /// если их пока нет; false — ждать нечего
+    fn poll_reactor(&mut self, wait: bool) -> bool {
+        if self.reactor.is_idle() {
+            return false;
+        }
+        for event in self.reactor.poll(wait) {
+            match event {
+                Event::Io(id, completion) => {
+                    let Some(idx) = self.waiting_tasks.iter().position(
+                        |waiter| matches!(waiter.waiting_on, Some(Wait::Io(op, _)) if op == id),
+                    ) else {
+                        continue;
+                    };
+                    let waiter = self.waiting_tasks.swap_remove(idx);
+                    let Some(Wait::Io(_, class)) = waiter.waiting_on else {
+                        unreachable!()
+                    };
+                    let resume =
+                        completion().map_err(|message| self.errors.instantiate(class, message));
+                    self.ready_tasks.push_back(SuspendedTask {
+                        waiting_on: None,
+                        resume: Some(resume),
+                        ..waiter
+                    });
                 }
+                Event::Timer(id) => {
+                    // Задача таймера ещё не начиналась, а у спящей результат
+                    // вызова — уже лежащий на стеке `ничто`
+                    self.wake(
+                        |waiting_on| matches!(waiting_on, Wait::Timer(timer) if *timer == id),
+                        None,
+                    );
+                }
+            }
+        }
+        true
+    }
+
+    /// Дать поработать каждой задаче, стоящей в очереди, до её следующей
+    /// приостановки
+    fn run_ready_tasks(&mut self) {
+        self.poll_reactor(false);
+        for _ in 0..self.ready_tasks.len() {
+            let Some(next) = self.ready_tasks.pop_front() else {
+                break;
+            };
+            self.resume_task(next);
+            if self.exit_code.is_some() {
+                break;
+            }
+        }
+    }
+
+    /// Выполнять задачи, пока `task` не завершится
+    fn run_until_done(&mut self, task: &Rc<Task>) -> Result<(), String> {
+        while !task.is_done() && self.exit_code.is_none() {
+            match self.ready_tasks.pop_front() {
+                Some(next) => self.resume_task(next),
+                None if self.poll_reactor(true) => {}
+                None => {
+                    return Err(format!(
+                        "Задача {} не может завершиться: все задачи ждут друг друга",
+                        task.id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Выполнять задачи, пока есть готовые или ожидающие таймера и ввода-вывода.
+    /// Вызывается по завершении основной программы
+    fn run_scheduler(&mut self) {
+        while self.exit_code.is_none() {
+            match self.ready_tasks.pop_front() {
+                Some(next) => self.resume_task(next),
+                None if self.poll_reactor(true) => {}
+                None => break,
             }
         }
     }
 
+    /// Выполняемая сопрограмма, если нативная функция вызвана прямо из её
+    /// кода, а не из обратного вызова другой нативной функции: только такую
+    /// задачу можно приостановить, сохранив её кадры
+    fn suspendable_task(&self) -> Option<Rc<Task>> {
+        match &self.current_task {
+            Some((task, boundary)) if *boundary == self.frame_boundary => Some(task.clone()),
+            _ => None,
+        }
+    }
+
+    /// Ждёт ли задача `from` (прямо или через цепочку других) задачу `target`
+    fn awaits(&self, from: &Rc<Task>, target: &Rc<Task>) -> bool {
+        let mut current = from.clone();
+        for _ in 0..=self.waiting_tasks.len() {
+            if Rc::ptr_eq(&current, target) {
+                return true;
+            }
+            let next = self
+                .waiting_tasks
+                .iter()
+                .find_map(|waiter| match &waiter.waiting_on {
+                    Some(Wait::Task(awaited)) if Rc::ptr_eq(&waiter.task, &current) => {
+                        Some(awaited.clone())
+                    }
+                    _ => None,
+                });
+            match next {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// Выполнить блокирующую операцию: в сопрограмме — в фоновом потоке,
+    /// приостановив задачу до результата, иначе — сразу
+    fn run_blocking<F>(&mut self, job: F) -> Result<Value, String>
+    where
+        F: FnOnce() -> Completion + Send + 'static,
+    {
+        if self.suspendable_task().is_none() {
+            return job()();
+        }
+        let id = self.reactor.start(job);
+        self.suspend = Some(Suspend::Wait(Wait::Io(id, exceptions::ERROR)));
+        Ok(Value::Nil)
+    }
+
+    /// Приостановить на `duration`: задачу — до срабатывания таймера, а вне
+    /// задачи — весь поток
+    fn sleep(&mut self, duration: Duration) {
+        if self.suspendable_task().is_some() {
+            let timer = self.reactor.add_timer(Instant::now() + duration);
+            self.suspend = Some(Suspend::Wait(Wait::Timer(timer)));
+        } else {
+            std::thread::sleep(duration);
+        }
+    }
+
     fn register_native(&mut self, func: NativeFn) -> NativeFnId {
         let id = NativeFnId(self.native_functions.len());
         self.native_functions.push(func);
         id
     }
-    pub fn register_and_define(&mut self, name: &str, func: NativeFn) {
-        let id = self.register_native(func);
+    /// Зарегистрировать глобальную переменную, значение которой будет вычислено
+    /// при первом обращении к ней
+    pub fn define_lazy_global(&mut self, name: &str, init: LazyGlobal) {
+        let slot = self.globals.slot(name);
+        if self.globals.is_defined(slot) {
+            panic!("Глобальная переменная {name} уже определена");
+        }
+        self.globals.lazy.insert(slot, init);
+    }
+
+    /// Получить значение глобальной переменной по полному имени
+    pub fn get_global(&mut self, name: &str) -> Option<Value> {
+        let slot = *self.globals.slots.get(name)?;
+        if self.globals.values[slot].is_none() {
+            self.materialize_global(slot).ok()?;
+        }
+        self.globals.values[slot].clone()
+    }
+
+    /// Определена ли глобальная переменная (в том числе отложенная)
+    pub fn is_global_defined(&self, name: &str) -> bool {
+        self.globals
+            .slots
+            .get(name)
+            .is_some_and(|&slot| self.globals.is_defined(slot))
+    }
+
+    /// Определить или переопределить глобальную переменную
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let slot = self.globals.slot(name);
+        self.globals.lazy.remove(&slot);
+        self.globals.values[slot] = Some(value);
+    }
+
+    /// Сбросить состояние выполнения после ошибки: стек значений и вызовов
+    /// очищаются, глобальные переменные сохраняются
+    /// Начать собирать вывод программы в буфер вместо stdout
+    pub fn capture_output(&mut self) {
+        self.captured_output = Some(String::new());
+    }
+
+    /// Забрать собранный вывод и вернуться к печати в stdout
+    pub fn take_output(&mut self) -> Option<String> {
+        self.captured_output.take()
+    }
+
+    /// Включить профилирование функций для следующих запусков кода
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(Box::new(Profiler::new()));
+        self.update_instrumented();
+    }
+
+    /// Завершить профилирование и забрать собранную статистику
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        let profile = self.profiler.take().map(|profiler| profiler.finish());
+        self.update_instrumented();
+        profile
+    }
+
+    /// Печатать в stderr каждую инструкцию: глубину и имя кадра, адрес,
+    /// строку исходного кода, опкод и верхушку стека кадра
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.trace = enabled;
+        self.update_instrumented();
+    }
+
+    /// Ограничить число инструкций, глубину вызовов и размер стека для
+    /// следующих запусков кода
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.instructions = 0;
+        self.update_instrumented();
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Проверять в цикле выполнения флаг прерывания: когда его поднимут
+    /// (обработчик Ctrl-C, другой поток), выполняемый код получит
+    /// ПрерываниеПользователя, а флаг опустится
+    pub fn set_interrupt_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    /// Печатать ли в stderr отчёт о необработанном исключении или
+    /// прерывании. Без отчёта сообщение и стек вызовов возвращаются только в
+    /// тексте ошибки: так тесты и примеры не дублируют свои провалы в stderr
+    pub fn set_report_errors(&mut self, enabled: bool) {
+        self.report_errors = enabled;
+    }
+
+    /// Проверять перед каждой инструкцией, что она снимает значения только
+    /// из стека своего кадра, а предыдущая оставила на стеке столько
+    /// значений, сколько положено по её стековому эффекту. Нарушение —
+    /// ошибка генератора или вручную собранного байткода: VM паникует
+    pub fn set_strict_stack(&mut self, enabled: bool) {
+        self.strict_stack = enabled;
+        self.expected_stack = None;
+        self.update_instrumented();
+    }
+
+    fn update_instrumented(&mut self) {
+        self.instrumented = self.trace
+            || self.strict_stack
+            || self.profiler.is_some()
+            || !self.limits.is_unlimited();
+    }
+
+    // Запрошено ли прерывание; запрос при этом снимается. Проверяется на
+    // переходах назад и вызовах: без них код не может выполняться долго
+    fn take_interrupt(&self) -> bool {
+        self.interrupt.as_ref().is_some_and(|flag| {
+            flag.load(AtomicOrdering::Relaxed) && flag.swap(false, AtomicOrdering::Relaxed)
+        })
+    }
+
+    // Посчитать инструкцию и проверить пределы ресурсов. Исчерпанный запас
+    // инструкций прерывает выполнение целиком: обработчик, перехвативший
+    // ошибку, снова упёрся бы в предел. Превышение глубины вызовов и стека
+    // возвращается текстом ошибки, которую бросит цикл выполнения
+    fn exceeded_limit(&mut self, frame_idx: usize, ip: usize) -> Option<String> {
+        self.instructions += 1;
+        if let Some(max) = self.limits.max_instructions
+            && self.instructions > max
+        {
+            self.frames[frame_idx].ip = ip;
+            self.abort(format!("Превышен лимит инструкций: {}", max));
+        }
+        if let Some(max) = self.limits.max_call_depth
+            && self.frames.len() > max
+        {
+            return Some(format!("Превышена глубина вызовов: {}", max));
+        }
+        if let Some(max) = self.limits.max_stack
+            && self.stack.len() > max
+        {
+            return Some(format!("Превышен размер стека: {}", max));
+        }
+        None
+    }
+
+    // Отметить инструкцию в профилировщике и трассе
+    fn instrument(
+        &mut self,
+        function: &Rc<Function>,
+        depth: usize,
+        ip: usize,
+        base: usize,
+        opcode: OpCode,
+    ) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.instruction(function, depth);
+        }
+        if self.trace {
+            let line = match function.lines.line_at(ip) {
+                Some(line) => format!("стр. {}", line),
+                None => String::new(),
+            };
+            eprintln!(
+                "{:>3} {:<16} {:>5} {:>9}  {:<24} стек: {}",
+                depth,
+                function.name,
+                ip,
+                line,
+                format!("{:?}", opcode),
+                stack_snapshot(&self.stack[base.min(self.stack.len())..])
+            );
+        }
+        if self.strict_stack {
+            self.check_stack_effect(function, ip, base, opcode);
+        }
+    }
+
+    // Строгий режим стека. Вызовы, возвраты и исключения меняют стек по
+    // правилам кадров, поэтому после них глубина не сверяется
+    fn check_stack_effect(&mut self, function: &Function, ip: usize, base: usize, opcode: OpCode) {
+        let frames = self.frames.len();
+        if let Some(expected) = self.expected_stack.take()
+            && expected.frames == frames
+            && expected.base == base
+            && expected.len != self.stack.len()
+        {
+            panic!(
+                "Строгая проверка стека: функция {}, инструкция {} ({:?}) оставила стек глубиной {} вместо {}",
+                function.name,
+                expected.ip,
+                expected.opcode,
+                self.stack.len(),
+                expected.len
+            );
+        }
+
+        let (pops, pushes) = verifier::stack_effect(opcode);
+        // Возвращаемое значение тоже должно лежать в кадре функции
+        let pops = if opcode == OpCode::Return_ { 1 } else { pops };
+        let depth = self.stack.len().saturating_sub(base);
+        if pops > depth {
+            panic!(
+                "Строгая проверка стека: функция {}, инструкция {} ({:?}) снимает со стека больше значений ({}), чем лежит в кадре ({})",
+                function.name, ip, opcode, pops, depth
+            );
+        }
+
+        let len = self.stack.len() - pops + pushes;
+        let len = match opcode {
+            OpCode::Call(_) | OpCode::CallSpread(_) | OpCode::Return_ | OpCode::Throw => return,
+            // Стек кадра дорастает до слота локальной переменной
+            OpCode::DefineLocal(slot) | OpCode::SetLocal(slot) => len.max(base + slot as usize + 1),
+            _ => len,
+        };
+        self.expected_stack = Some(ExpectedStack {
+            frames,
+            base,
+            len,
+            ip,
+            opcode,
+        });
+    }
+
+    // Закрыть в профилировщике вызовы, кадры которых уже сняты со стека
+    fn profile_leave(&mut self) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.leave(self.frames.len());
+        }
+    }
+
+    fn write_output(&mut self, text: &str) {
+        if let Some(buffer) = &mut self.captured_output {
+            buffer.push_str(text);
+        } else if self.buffer_output {
+            self.stdout_buffer.push_str(text);
+            if self.stdout_buffer.len() >= STDOUT_BUFFER_SIZE {
+                self.flush_output();
+            }
+        } else {
+            self.write_stdout(text);
+        }
+    }
+
+    /// Записать накопленный вывод программы в stdout
+    pub fn flush_output(&mut self) {
+        if !self.stdout_buffer.is_empty() {
+            let text = std::mem::take(&mut self.stdout_buffer);
+            self.write_stdout(&text);
+        }
+    }
+
+    /// Копить вывод в буфере (`истина`) или сразу записывать в stdout
+    pub fn set_output_buffering(&mut self, buffered: bool) {
+        if !buffered {
+            self.flush_output();
+        }
+        self.buffer_output = buffered;
+    }
+
+    // Читатель закрыл конвейер (`ponos скрипт.pns | head`): программа
+    // завершается, как процесс, получивший SIGPIPE
+    fn write_stdout(&mut self, text: &str) {
+        let mut stdout = std::io::stdout().lock();
+        let written = stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush());
+        if let Err(err) = written
+            && err.kind() == std::io::ErrorKind::BrokenPipe
+        {
+            self.buffer_output = true;
+            self.exit_code.get_or_insert(BROKEN_PIPE_EXIT_CODE);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.frames.clear();
+        self.open_upvalues.clear();
+        self.frame_boundary = 0;
+        self.nested_runs = 0;
+        self.pending_exception = None;
+        // Кадры незавершённых сопрограмм не пережили сброса
+        self.current_task = None;
+        self.suspend = None;
+        self.ready_tasks.clear();
+        self.waiting_tasks.clear();
+        self.reactor = Reactor::new();
+    }
+
+    /// Определена ли глобальная переменная (в том числе отложенно)
+    pub fn has_global(&self, name: &str) -> bool {
         self.globals
-            .insert(name.to_string(), Value::NativeFunction(id));
+            .slots
+            .get(name)
+            .is_some_and(|&slot| self.globals.is_defined(slot))
+    }
+
+    /// Код завершения, если программа вызвала система.завершить
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Имена определённых глобальных переменных (в том числе отложенных)
+    pub fn defined_globals(&self) -> Vec<String> {
+        self.globals
+            .slots
+            .iter()
+            .filter(|&(_, &slot)| self.globals.is_defined(slot))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Имя глобальной переменной по номеру слота
+    pub(crate) fn global_name(&self, slot: usize) -> &str {
+        &self.globals.names[slot]
+    }
+
+    /// Имя, под которым определена нативная функция; встроенные функции
+    /// находятся раньше копий в пространствах имён модулей
+    pub(crate) fn native_name(&self, id: &NativeFnId) -> Option<String> {
+        self.globals
+            .values
+            .iter()
+            .position(|value| matches!(value, Some(Value::NativeFunction(other)) if other == id))
+            .map(|slot| self.globals.names[slot].clone())
+    }
+
+    /// Имя встроенного класса ошибки, если `class` — один из них
+    pub(crate) fn error_class_name(&self, class: &Rc<Class>) -> Option<&'static str> {
+        self.errors
+            .iter()
+            .find(|(_, error)| Rc::ptr_eq(error, class))
+            .map(|(name, _)| name)
+    }
+
+    /// Встроенный класс ошибки по имени
+    pub(crate) fn error_class(&self, name: &str) -> Option<Rc<Class>> {
+        self.errors
+            .iter()
+            .find(|(error, _)| *error == name)
+            .map(|(_, class)| class.clone())
+    }
+
+    /// Нативные модули, функции которых определены в VM
+    pub(crate) fn native_modules(&self) -> &[(String, String, Vec<String>)] {
+        &self.native_modules
+    }
+
+    /// Отметить, что функции `exports` нативного модуля определены в
+    /// пространстве имён
+    pub(crate) fn record_native_module(
+        &mut self,
+        path: &str,
+        namespace: &str,
+        exports: Vec<String>,
+    ) {
+        let module = (path.to_string(), namespace.to_string(), exports);
+        if !self.native_modules.contains(&module) {
+            self.native_modules.push(module);
+        }
+    }
+
+    /// Определить функции `exports` нативного модуля, если модуля ещё нет в
+    /// VM. Остальные экспорты модуля не определяются, так что функции,
+    /// запрещённые песочнице, не появятся и в VM потока
+    pub(crate) fn register_native_module(
+        &mut self,
+        path: &str,
+        namespace: &str,
+        exports: &[String],
+    ) -> Result<(), String> {
+        if self
+            .native_modules
+            .iter()
+            .any(|(p, n, _)| p == path && n == namespace)
+        {
+            return Ok(());
+        }
+        let mut registry = native::NativeModuleRegistry::new();
+        registry.allow_only(exports.iter().map(|export| format!("{}.{}", path, export)));
+        registry.register_module_in_vm(path, namespace, self)
+    }
+
+    /// Запомнить экспорты модуля, загруженного в пространство имён
+    /// `namespace`: пары из имени экспорта и полного имени глобальной
+    /// переменной (у реэкспорта — в исходном модуле)
+    pub fn record_module_exports(&mut self, namespace: &str, exports: Vec<(String, String)>) {
+        self.module_exports.insert(namespace.to_string(), exports);
+    }
+
+    /// Экспорты модуля в порядке объявления или `None`, если модуль не загружен
+    pub fn module_exports(&self, namespace: &str) -> Option<&[(String, String)]> {
+        self.module_exports.get(namespace).map(Vec::as_slice)
+    }
+
+    /// Вызвать функцию сопрограммой и выполнять планировщик, пока она не
+    /// завершится; остальные задачи выполняются после неё. Исключение
+    /// функции возвращается как Err, а не завершает программу
+    pub(crate) fn call_in_task(&mut self, callee: Value, args: &[Value]) -> Result<Value, Value> {
+        // Кадр хоста: задача выполняется как вызванная из него, а не как
+        // отсоединённая, поэтому её исключение остаётся в результате
+        self.frames.push(CallFrame {
+            function: Rc::new(call_entry(Value::Nil, &[])),
+            ip: 0,
+            base: self.stack.len(),
+            upvalues: Vec::new(),
+            exception_handlers: Vec::new(),
+        });
+        let task = self.spawn_task(callee, args, None);
+        let outcome = self.run_until_done(&task);
+        self.frames.pop();
+        if let Err(message) = outcome {
+            return Err(self.errors.instantiate(exceptions::ERROR, message));
+        }
+        self.run_scheduler();
+        let result = task.result.borrow_mut().take();
+        result.unwrap_or(Ok(Value::Nil))
+    }
+
+    /// Сбросить глобальные переменные модуля (`пространство::имя`), чтобы код
+    /// модуля можно было выполнить заново. Слоты сохраняются, так что уже
+    /// скомпилированный код увидит новые значения
+    pub fn clear_namespace(&mut self, namespace: &str) {
+        let prefix = format!("{}::", namespace);
+        for (name, &slot) in &self.globals.slots {
+            if name.starts_with(&prefix) {
+                self.globals.values[slot] = None;
+                self.globals.lazy.remove(&slot);
+            }
+        }
+    }
+
+    /// Зарегистрировать нативную функцию (обычную функцию или замыкание)
+    /// и определить её как глобальную переменную
+    pub fn register_and_define<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        self.register_and_define_with_context(name, move |_, args| func(args));
+    }
+
+    /// Зарегистрировать нативную функцию, которой нужен доступ к VM
+    /// (например, чтобы вызвать переданную ей функцию Ponos)
+    pub fn register_and_define_with_context<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&mut NativeContext, &[Value]) -> Result<Value, String> + 'static,
+    {
+        let id = self.register_native(Rc::new(func));
+        self.globals.define(name, Value::NativeFunction(id));
+    }
+
+    /// Бросать ошибки нативной функции, определённой под глобальным именем
+    /// `name`, экземплярами класса `class` (например, `exceptions::IO_ERROR`)
+    pub fn set_native_error_class(&mut self, name: &str, class: &'static str) {
+        if let Some(&slot) = self.globals.slots.get(name)
+            && let Some(Value::NativeFunction(id)) = &self.globals.values[slot]
+        {
+            self.native_error_classes.insert(id.0, class);
+        }
     }
 
     /// Зарегистрировать нативный метод для класса
@@ -996,47 +2859,289 @@ fn builtin_len(args: &[Value]) -> Result<Value, String> {
     }
 
     match &args[0] {
-        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
-        Value::Array(arr) => Ok(Value::Number(arr.borrow().len() as f64)),
-        Value::Dict(dict) => Ok(Value::Number(dict.borrow().len() as f64)),
+        Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+        Value::Array(arr) => Ok(Value::Int(arr.borrow().len() as i64)),
+        Value::Dict(dict) => Ok(Value::Int(dict.borrow().len() as i64)),
+        Value::StringBuilder(buf) => Ok(Value::Int(buf.borrow().chars().count() as i64)),
         _ => Err("длина() поддерживает только строки, массивы и словари".to_string()),
     }
 }
 
-fn builtin_print(args: &[Value]) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err("вывести() требует ровно 1 аргумент".to_string());
+/// является_ничем(значение) -> логический
+fn builtin_is_nil(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [value] => Ok(Value::Boolean(*value == Value::Nil)),
+        _ => Err("является_ничем() требует ровно 1 аргумент".to_string()),
     }
+}
 
-    fn format_value(v: &Value) -> String {
-        match v {
-            Value::String(s) => s.clone(),
-            Value::Number(n) => n.to_string(),
-            Value::Boolean(b) => b.to_string(),
-            Value::Nil => "ничто".to_string(),
-            Value::Array(arr) => {
-                let items: Vec<String> = arr.borrow().iter().map(format_value).collect();
-                format!("[{}]", items.join(", "))
-            }
-            Value::Dict(dict) => {
-                let items: Vec<String> = dict.borrow().iter().map(|(k, v)| {
-                    let key_str = match k {
-                        crate::ponos::value::ValueKey::String(s) => format!("\"{}\"", s),
-                        crate::ponos::value::ValueKey::Number(n) => n.to_string(),
-                        crate::ponos::value::ValueKey::Boolean(b) => b.to_string(),
-                    };
-                    format!("{}: {}", key_str, format_value(v))
-                }).collect();
-                format!("{{{}}}", items.join(", "))
-            }
-            _ => format!("<объект>"),
+/// является(значение, Класс) -> логический
+///
+/// Экземпляр ли значение класса или его наследника, как `перехват е: Класс`
+fn builtin_is_instance(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [value, Value::Class(class)] => Ok(Value::Boolean(match value {
+            Value::Instance(instance) => instance.borrow().class.is_subclass_of(class),
+            _ => false,
+        })),
+        [_, other] => Err(format!(
+            "является: вторым аргументом должен быть класс, получено: {}",
+            Type::of_value(other)
+        )),
+        _ => Err("является() требует 2 аргумента: значение, класс".to_string()),
+    }
+}
+
+/// копировать(значение) -> глубокая копия значения
+// Позиция элемента по индексу-числу: целое неотрицательное, в том числе
+// вещественное без дробной части
+fn index_position(index: &Value) -> Result<usize, String> {
+    let position = match index {
+        Value::Int(n) => usize::try_from(*n).ok(),
+        Value::Number(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as usize),
+        _ => None,
+    };
+    position.ok_or_else(|| {
+        format!(
+            "Индекс должен быть неотрицательным целым числом, получено: {}",
+            index
+        )
+    })
+}
+
+fn builtin_copy(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [value] => Ok(value::deep_copy(value)),
+        _ => Err("копировать() требует ровно 1 аргумент".to_string()),
+    }
+}
+
+/// Имя служебной функции, с которой начинаются вызовы из Rust и задачи
+const CALL_ENTRY_NAME: &str = "<вызов>";
+
+/// Наибольшая глубина вложенных циклов выполнения (функция Ponos, вызванная
+/// из нативной, которая сама вызвана из Ponos, и т.д.). Выбрана с запасом
+/// для стека в 8 МБ даже в отладочной сборке, где кадры крупнее
+const MAX_NESTED_RUNS: usize = 150;
+
+// Функция-обёртка, которая вызывает `callee(args)`: с неё начинается
+// выполнение вызова из Rust-кода и сопрограммы. Значения уже связаны с
+// глобальными слотами, поэтому link_globals не нужен
+fn call_entry(callee: Value, args: &[Value]) -> Function {
+    let mut constants = Vec::with_capacity(args.len() + 1);
+    constants.push(callee);
+    constants.extend(args.iter().cloned());
+
+    let mut opcodes: Vec<OpCode> = (0..constants.len())
+        .map(|idx| OpCode::Constant(operand(idx)))
+        .collect();
+    opcodes.push(OpCode::Call(operand(args.len())));
+
+    Function {
+        arity: 0,
+        entry_points: Vec::new(),
+        variadic: false,
+        opcodes,
+        constants,
+        name: CALL_ENTRY_NAME.to_string(),
+        upvalue_count: 0,
+        upvalue_descriptors: Vec::new(),
+        source_name: None,
+        lines: LineTable::default(),
+    }
+}
+
+// Сдвинуть индексы стека в кадре сопрограммы: на `offset` вверх при
+// возобновлении (`up`) или вниз при приостановке
+fn rebase(mut frame: CallFrame, offset: usize, up: bool) -> CallFrame {
+    let shift = |idx: &mut usize| {
+        if up {
+            *idx += offset;
+        } else {
+            *idx -= offset;
         }
+    };
+    shift(&mut frame.base);
+    for handler in &mut frame.exception_handlers {
+        shift(&mut handler.stack_size);
+    }
+    frame
+}
+
+/// сопрограмма(функ, ...аргументы) -> задача
+///
+/// Задача создаётся приостановленной и выполняется, когда управление получит
+/// планировщик: в `ждать` или `передать`
+fn builtin_spawn(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let Some((callee, args)) = args.split_first() else {
+        return Err("сопрограмма() требует функцию".to_string());
+    };
+    if !matches!(Type::of_value(callee), Type::Function(_)) {
+        return Err(format!(
+            "сопрограмма: первым аргументом должна быть функция, получено: {}",
+            Type::of_value(callee)
+        ));
+    }
+    let task = ctx.vm.spawn_task(callee.clone(), args, None);
+    Ok(Value::Task(task))
+}
+
+/// таймер_через(мс, функ, ...аргументы) -> задача
+///
+/// Задача вызовет функцию не раньше чем через `мс` миллисекунд
+fn builtin_timer(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let (delay, callee, args) = match args {
+        [delay, callee, args @ ..] => (delay, callee, args),
+        _ => return Err("таймер_через требует 2 аргумента: мс, функция".to_string()),
+    };
+    let delay = match delay.as_number() {
+        Some(ms) if ms >= 0.0 && ms.is_finite() => Duration::from_secs_f64(ms / 1000.0),
+        _ => return Err("таймер_через: задержка должна быть неотрицательным числом".to_string()),
+    };
+    if !matches!(Type::of_value(callee), Type::Function(_)) {
+        return Err(format!(
+            "таймер_через: вторым аргументом должна быть функция, получено: {}",
+            Type::of_value(callee)
+        ));
     }
+    let vm = &mut *ctx.vm;
+    let timer = vm.reactor.add_timer(Instant::now() + delay);
+    let task = vm.spawn_task(callee.clone(), args, Some(Wait::Timer(timer)));
+    Ok(Value::Task(task))
+}
 
-    println!("{}", format_value(&args[0]));
+/// передать() -> ничто
+///
+/// Отдать управление: задача встаёт в конец очереди, а вне задачи
+/// выполняются по одному шагу все готовые задачи
+fn builtin_yield(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("передать() не принимает аргументов".to_string());
+    }
+    if ctx.vm.suspendable_task().is_some() {
+        ctx.vm.suspend = Some(Suspend::Yield);
+    } else {
+        ctx.vm.run_ready_tasks();
+    }
     Ok(Value::Nil)
 }
 
+/// ждать(задача) -> результат задачи
+///
+/// Исключение, на котором задача завершилась, бросается из `ждать`
+fn builtin_await(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let task = match args {
+        [Value::Task(task)] => task.clone(),
+        [other] => {
+            return Err(format!(
+                "ждать: ожидается задача, получено: {}",
+                Type::of_value(other)
+            ));
+        }
+        _ => return Err("ждать() требует ровно 1 аргумент".to_string()),
+    };
+    let vm = &mut *ctx.vm;
+    if let Some((current, _)) = &vm.current_task {
+        if Rc::ptr_eq(current, &task) {
+            return Err("ждать: задача не может ждать сама себя".to_string());
+        }
+        if vm.awaits(&task, current) {
+            return Err(format!(
+                "ждать: задачи {} и {} ждут друг друга",
+                current.id, task.id
+            ));
+        }
+    }
+
+    if !task.is_done() {
+        if vm.suspendable_task().is_some() {
+            // Результат подставит планировщик, когда задача завершится
+            vm.suspend = Some(Suspend::Wait(Wait::Task(task)));
+            return Ok(Value::Nil);
+        }
+        vm.run_until_done(&task)?;
+    }
+    match task.result.borrow().clone() {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(exception)) => {
+            vm.pending_exception = Some(exception);
+            Ok(Value::Nil)
+        }
+        // Программа завершается (система.завершить в задаче)
+        None => Ok(Value::Nil),
+    }
+}
+
+/// построитель_строк() -> построитель
+///
+/// Строка накапливается в одном изменяемом буфере, поэтому сборка большого
+/// текста в цикле занимает линейное время, а не квадратичное, как у `+`
+fn builtin_string_builder(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("построитель_строк() не принимает аргументов".to_string());
+    }
+    Ok(Value::StringBuilder(Rc::new(RefCell::new(String::new()))))
+}
+
+fn builtin_print(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("вывести() требует ровно 1 аргумент".to_string());
+    }
+
+    ctx.write_output(&format!("{}\n", args[0]));
+    Ok(Value::Nil)
+}
+
+/// вывести_ф(шаблон, ...значения) — вывести строку `формат(шаблон, ...значения)`
+fn builtin_print_formatted(ctx: &mut NativeContext, args: &[Value]) -> Result<Value, String> {
+    let text = match args.split_first() {
+        Some((Value::String(template), values)) => {
+            native::strings::format_template(template, values)
+                .map_err(|err| format!("вывести_ф: {}", err))?
+        }
+        Some(_) => return Err("вывести_ф: шаблон должен быть строкой".to_string()),
+        None => return Err("вывести_ф требует шаблон".to_string()),
+    };
+    ctx.write_output(&format!("{}\n", text));
+    Ok(Value::Nil)
+}
+
+/// Размер буфера вывода, после которого он записывается в stdout
+const STDOUT_BUFFER_SIZE: usize = 8192;
+/// Код завершения при закрытом конвейере: 128 + SIGPIPE
+const BROKEN_PIPE_EXIT_CODE: i32 = 141;
+
+/// Сколько верхних значений стека показывает трасса
+const TRACE_STACK_DEPTH: usize = 4;
+/// Длина, до которой в трассе обрезается одно значение
+const TRACE_VALUE_WIDTH: usize = 24;
+
+// Верхушка стека кадра для трассы: строки в кавычках, длинные значения обрезаны
+fn stack_snapshot(stack: &[Value]) -> String {
+    let start = stack.len().saturating_sub(TRACE_STACK_DEPTH);
+    let mut items: Vec<String> = stack[start..]
+        .iter()
+        .map(|value| {
+            let text = match value {
+                Value::String(s) => format!("\"{}\"", s),
+                Value::Function(function) => format!("<функ {}>", function.name),
+                Value::Closure(closure) => format!("<функ {}>", closure.function.name),
+                other => other.to_string(),
+            };
+            if text.chars().count() > TRACE_VALUE_WIDTH {
+                let cut: String = text.chars().take(TRACE_VALUE_WIDTH).collect();
+                format!("{}…", cut)
+            } else {
+                text
+            }
+        })
+        .collect();
+    if start > 0 {
+        items.insert(0, "…".to_string());
+    }
+    format!("[{}]", items.join(", "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1044,7 +3149,7 @@ mod tests {
     #[test]
     fn defines_and_sets_locals() {
         let mut vm = VM::new();
-        let mut constants = vec![Value::Number(1.0), Value::Number(2.0)];
+        let constants = vec![Value::Number(1.0), Value::Number(2.0)];
 
         let opcodes = vec![
             OpCode::Constant(0),
@@ -1054,17 +3159,30 @@ mod tests {
             OpCode::GetLocal(0),
         ];
 
-        vm.execute(opcodes, &mut constants);
+        vm.execute(opcodes, &constants).unwrap();
 
         assert_eq!(vm.stack, vec![Value::Number(2.0)]);
     }
 
+    #[test]
+    fn invalid_bytecode_is_rejected_before_running() {
+        let mut vm = VM::new();
+        let constants = vec![Value::Number(1.0)];
+
+        let opcodes = vec![OpCode::Constant(0), OpCode::Constant(5)];
+
+        let error = vm.execute(opcodes, &constants).unwrap_err();
+
+        assert!(error.starts_with("Некорректный байткод"), "{}", error);
+        assert!(vm.stack.is_empty());
+    }
+
     #[test]
     fn defines_and_gets_global_variable() {
         let mut vm = VM::new();
-        let mut constants = vec![
-            Value::String("x".to_string()), // 0
-            Value::Number(42.0),            // 1
+        let constants = vec![
+            Value::from("x"),    // 0
+            Value::Number(42.0), // 1
         ];
 
         let opcodes = vec![
@@ -1073,18 +3191,19 @@ mod tests {
             OpCode::GetGlobal(0),
         ];
 
-        vm.execute(opcodes, &mut constants);
+        vm.execute(opcodes, &constants).unwrap();
 
         assert_eq!(vm.stack.len(), 1);
         assert_eq!(vm.stack[0], Value::Number(42.0));
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn module_variables_use_mangled_names() {
         let mut vm = VM::new();
-        let mut constants = vec![
-            Value::String("мат::ПИ".to_string()), // 0 - манглированное имя
-            Value::Number(3.14),                  // 1
+        let constants = vec![
+            Value::from("мат::ПИ"), // 0 - манглированное имя
+            Value::Number(3.14),    // 1
         ];
 
         let opcodes = vec![
@@ -1093,10 +3212,169 @@ mod tests {
             OpCode::GetGlobal(0),
         ];
 
-        vm.execute(opcodes, &mut constants);
+        vm.execute(opcodes, &constants).unwrap();
 
         assert_eq!(vm.stack.len(), 1);
         assert_eq!(vm.stack[0], Value::Number(3.14));
-        assert_eq!(vm.globals["мат::ПИ"], Value::Number(3.14));
+        assert_eq!(vm.globals.get("мат::ПИ"), Some(&Value::Number(3.14)));
+    }
+
+    #[test]
+    fn links_globals_inside_nested_functions() {
+        let mut vm = VM::new();
+        let function = Function {
+            arity: 0,
+            entry_points: Vec::new(),
+            variadic: false,
+            opcodes: vec![OpCode::GetGlobal(0), OpCode::Return_],
+            constants: vec![Value::from("x")],
+            name: "получить".to_string(),
+            upvalue_count: 0,
+            upvalue_descriptors: Vec::new(),
+            source_name: None,
+            lines: LineTable::default(),
+        };
+        let constants = vec![
+            Value::from("x"),                   // 0
+            Value::Number(7.0),                 // 1
+            Value::Function(Rc::new(function)), // 2
+        ];
+
+        let opcodes = vec![
+            OpCode::Constant(1),
+            OpCode::DefineGlobal(0),
+            OpCode::Constant(2),
+            OpCode::Call(0),
+        ];
+
+        vm.execute(opcodes, &constants).unwrap();
+
+        assert_eq!(vm.stack, vec![Value::Number(7.0)]);
+        assert_eq!(vm.globals.get("x"), Some(&Value::Number(7.0)));
+    }
+
+    #[test]
+    fn materializes_lazy_global_on_first_reference() {
+        use std::cell::Cell;
+
+        let mut vm = VM::new();
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        vm.define_lazy_global(
+            "мод::значение",
+            Box::new(move || {
+                counter.set(counter.get() + 1);
+                Ok(Value::Number(5.0))
+            }),
+        );
+        assert!(vm.has_global("мод::значение"));
+        assert_eq!(calls.get(), 0);
+
+        let constants = vec![Value::from("мод::значение")];
+        let opcodes = vec![OpCode::GetGlobal(0), OpCode::GetGlobal(0), OpCode::Add];
+
+        vm.execute(opcodes, &constants).unwrap();
+
+        assert_eq!(vm.stack, vec![Value::Number(10.0)]);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn failed_lazy_global_throws_catchable_error() {
+        let mut vm = VM::new();
+        vm.define_lazy_global(
+            "мод::ф",
+            Box::new(|| Err("Ошибка компиляции функции мод::ф: сбой".to_string())),
+        );
+        assert_eq!(vm.get_global("мод::ф"), None);
+
+        let constants = vec![Value::from("мод::ф"), Value::from("сообщение")];
+        let opcodes = vec![
+            OpCode::PushExceptionHandler(4),
+            OpCode::GetGlobal(0),
+            OpCode::PopExceptionHandler,
+            OpCode::Jump(5),
+            OpCode::GetProperty(1),
+        ];
+
+        vm.execute(opcodes, &constants).unwrap();
+
+        assert_eq!(
+            vm.stack,
+            vec![Value::from("Ошибка компиляции функции мод::ф: сбой")]
+        );
+    }
+
+    #[test]
+    fn get_global_keeps_module_value_next_to_unqualified_name() {
+        // Слот "ОСНОВА" зарезервирован программой, но ещё не определён
+        let mut vm = VM::new();
+        vm.set_global("мод::ОСНОВА", Value::Int(10));
+        vm.globals.slot("ОСНОВА");
+
+        assert_eq!(vm.get_global("мод::ОСНОВА"), Some(Value::Int(10)));
+        assert_eq!(vm.get_global("ОСНОВА"), None);
+    }
+
+    // Функция f снимает со стека значение вызывающего кода. Код запускается
+    // в обход проверки байткода, которую отладочная сборка выполняет в execute
+    #[test]
+    #[should_panic(
+        expected = "Строгая проверка стека: функция f, инструкция 0 (Pop) снимает со стека больше значений (1), чем лежит в кадре (0)"
+    )]
+    fn strict_stack_keeps_pops_inside_the_frame() {
+        let f = Function {
+            opcodes: vec![OpCode::Pop, OpCode::Constant(0), OpCode::Return_],
+            constants: vec![Value::Nil],
+            name: "f".to_string(),
+            ..Default::default()
+        };
+        let main = Function {
+            opcodes: vec![OpCode::Constant(0), OpCode::Constant(1), OpCode::Call(0)],
+            constants: vec![Value::Int(1), Value::Function(Rc::new(f))],
+            name: "<главная>".to_string(),
+            ..Default::default()
+        };
+        let mut vm = VM::new();
+        vm.set_strict_stack(true);
+        vm.run(main, 0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Return_ в функции f: возвращаемого значения нет в стеке кадра")]
+    fn return_asserts_value_inside_the_frame() {
+        let f = Function {
+            opcodes: vec![OpCode::Pop, OpCode::Return_],
+            name: "f".to_string(),
+            ..Default::default()
+        };
+        let main = Function {
+            opcodes: vec![OpCode::Constant(0), OpCode::Constant(1), OpCode::Call(0)],
+            constants: vec![Value::Int(1), Value::Function(Rc::new(f))],
+            ..Default::default()
+        };
+        VM::new().run(main, 0);
+    }
+
+    #[test]
+    fn strict_stack_follows_locals_and_exception_handlers() {
+        let mut vm = VM::new();
+        vm.set_strict_stack(true);
+        let constants = vec![Value::Int(1), Value::Int(2)];
+        let opcodes = vec![
+            OpCode::Constant(0),
+            OpCode::DefineLocal(0),
+            OpCode::PushExceptionHandler(6),
+            OpCode::Constant(1),
+            OpCode::Throw,
+            OpCode::PopExceptionHandler,
+            OpCode::SetLocal(0),
+            OpCode::GetLocal(0),
+        ];
+
+        vm.execute(opcodes, &constants).unwrap();
+
+        assert_eq!(vm.stack, vec![Value::Int(2)]);
     }
 }
@@ -0,0 +1,664 @@
+use std::process::Command;
+
+fn ponos(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .args(args)
+        .output()
+        .expect("не удалось запустить интерпретатор")
+}
+
+#[test]
+fn script_receives_arguments_after_its_name() {
+    let output = ponos(&[
+        "run",
+        "tests/fixtures/test_cli_script_args.pns",
+        "один",
+        "--два",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("скрипт: tests/fixtures/test_cli_script_args.pns"));
+    assert!(stdout.contains("аргументы: один, --два"));
+}
+
+#[test]
+fn compile_prints_bytecode_without_running() {
+    let output = ponos(&["compile", "tests/fixtures/test_cli_script_args.pns"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Байткод:"));
+    assert!(!stdout.contains("скрипт: tests"));
+}
+
+#[test]
+fn unknown_flag_is_reported_with_usage() {
+    let output = ponos(&["--быстро", "файл.pns"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("Неизвестный флаг --быстро"));
+    assert!(stderr.contains("Использование:"));
+}
+
+#[test]
+fn check_walks_directory_and_reports_every_file() {
+    let dir = std::env::temp_dir().join(format!("ponos-check-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("вложенный")).unwrap();
+    std::fs::write(dir.join("хороший.pns"), "пер x = 1;\n").unwrap();
+    std::fs::write(dir.join("вложенный/плохой.pns"), "пер = ;\n").unwrap();
+    std::fs::write(
+        dir.join("импорт.pns"),
+        "использовать \"стд/ввод_вывод\" как ио;\nио.нет_функции(1);\n",
+    )
+    .unwrap();
+
+    let output = ponos(&["check", dir.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.contains("плохой.pns"), "{}", stderr);
+    assert!(
+        stderr.contains("Символ 'нет_функции' не найден"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Файлов с ошибками: 2 из 3"), "{}", stderr);
+}
+
+#[test]
+fn compilation_errors_are_reported_without_panicking() {
+    let path = std::env::temp_dir().join(format!("ponos-codegen-{}.pns", std::process::id()));
+    std::fs::write(&path, "функ срез(м, н)\n    возврат м[н:2];\nконец\n").unwrap();
+
+    let output = ponos(&["run", path.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let check = ponos(&["check", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr.contains("Границы среза должны быть числовыми константами"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert_eq!(check.status.code(), Some(1));
+}
+
+#[test]
+fn reexported_symbols_are_reachable_through_facade() {
+    let dir = std::env::temp_dir().join(format!("ponos-reexport-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("база.pns"),
+        "экспорт использовать \"стд/математика\" показать корень;\n\
+         экспорт функ удвоить(х) возврат х * 2; конец\n\
+         экспорт пер ОСНОВА = 10;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("фасад.pns"),
+        "экспорт использовать \"база\";\n\
+         экспорт функ утроить(х) возврат х * 3; конец\n",
+    )
+    .unwrap();
+    let main = dir.join("главный.pns");
+    std::fs::write(
+        &main,
+        "использовать \"фасад\" как ф;\n\
+         использовать \"стд/рефлексия\" как рефлексия;\n\
+         если ф.удвоить(ф.ОСНОВА) != 20 или ф.корень(16) != 4 или ф.утроить(2) != 6\n\
+             исключение \"реэкспорт не работает\";\n\
+         конец\n\
+         пер удвоить = рефлексия.получить_глобал(\"ф::удвоить\");\n\
+         если удвоить(3) != 6 или рефлексия.экспорты(\"ф\") != [\"утроить\", \"удвоить\", \"ОСНОВА\", \"корень\"]\n\
+             исключение \"рефлексия не видит реэкспорт\";\n\
+         конец\n\
+         вывести(\"реэкспорт работает\");\n",
+    )
+    .unwrap();
+    let hidden = dir.join("скрытое.pns");
+    std::fs::write(&hidden, "использовать \"фасад\" как ф;\nф.абс(-1);\n").unwrap();
+
+    let output = ponos(&["run", main.to_str().unwrap()]);
+    let check = ponos(&["check", hidden.to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("реэкспорт работает"), "{}", stdout);
+    let stderr = String::from_utf8_lossy(&check.stderr);
+    assert_eq!(check.status.code(), Some(1));
+    assert!(
+        stderr.contains("Символ 'абс' не найден в модуле 'ф'"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn run_without_file_starts_project_entry_from_manifest() {
+    let dir = std::env::temp_dir().join(format!("ponos-project-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("исх")).unwrap();
+    std::fs::create_dir_all(dir.join("вендор/утилиты")).unwrap();
+    std::fs::write(
+        dir.join("понос.toml"),
+        "entry = \"главный.pns\"\nsources = [\"исх\"]\n\n[dependencies]\nутилиты = \"вендор/утилиты\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("исх/помощник.pns"),
+        "экспорт функ приветствие() возврат \"привет из проекта\"; конец\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("вендор/утилиты/повтор.pns"),
+        "экспорт функ дважды(с) возврат с + с; конец\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("главный.pns"),
+        "использовать \"помощник\";\nиспользовать \"утилиты/повтор\";\n\
+         вывести(повтор.дважды(помощник.приветствие()));\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .arg("run")
+        .current_dir(&dir)
+        .output()
+        .expect("не удалось запустить интерпретатор");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stdout.contains("привет из проектапривет из проекта"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn modules_are_found_in_module_path_flags_and_ponos_path() {
+    let dir = std::env::temp_dir().join(format!("ponos-module-path-{}", std::process::id()));
+    for lib in ["первая", "вторая", "проект"] {
+        std::fs::create_dir_all(dir.join(lib)).unwrap();
+    }
+    // Одноимённый модуль в обоих каталогах: побеждает указанный раньше
+    std::fs::write(
+        dir.join("первая/общий.pns"),
+        "экспорт пер ОТКУДА = \"из флага\";\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("вторая/общий.pns"),
+        "экспорт пер ОТКУДА = \"из окружения\";\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("вторая/только_здесь.pns"),
+        "экспорт пер ИМЯ = \"окружение\";\n",
+    )
+    .unwrap();
+    let main = dir.join("проект/главный.pns");
+    std::fs::write(
+        &main,
+        "использовать \"общий\";\nиспользовать \"только_здесь\";\n\
+         вывести(общий.ОТКУДА);\nвывести(только_здесь.ИМЯ);\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .args(["--module-path", dir.join("первая").to_str().unwrap()])
+        .arg(&main)
+        .env("PONOS_PATH", dir.join("вторая"))
+        .output()
+        .expect("не удалось запустить интерпретатор");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(lines.contains(&"из флага"), "{}", stdout);
+    assert!(lines.contains(&"окружение"), "{}", stdout);
+}
+
+#[test]
+fn exit_code_is_set_by_script_and_failed_assertions() {
+    let dir = std::env::temp_dir().join(format!("ponos-exit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Выход из обратного вызова внутри попытки: ни перехват, ни код после не выполняются
+    std::fs::write(
+        dir.join("выход.pns"),
+        "использовать \"стд/система\" как сис;\n\
+         использовать \"стд/массивы\" как м;\n\
+         функ сравнить(а, б)\n\
+         \x20   сис.завершить(3);\n\
+         \x20   возврат 0;\n\
+         конец\n\
+         функ сортировать()\n\
+         \x20   м.сортировать([2, 1], сравнить);\n\
+         конец\n\
+         попытка\n\
+         \x20   сортировать();\n\
+         перехват е\n\
+         \x20   вывести(\"в_\" + \"перехвате\");\n\
+         конец\n\
+         вывести(\"после_\" + \"выхода\");\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("утверждение.pns"),
+        "использовать \"стд/тест\" как т;\nт.утверждать(1 > 2, \"порядок чисел\");\n",
+    )
+    .unwrap();
+
+    let exited = ponos(&["run", dir.join("выход.pns").to_str().unwrap()]);
+    let failed = ponos(&["run", dir.join("утверждение.pns").to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&exited.stdout);
+    assert_eq!(exited.status.code(), Some(3), "{}", stdout);
+    assert!(!stdout.contains("в_перехвате"), "{}", stdout);
+    assert!(!stdout.contains("после_выхода"), "{}", stdout);
+
+    let stderr = String::from_utf8_lossy(&failed.stderr);
+    assert_eq!(failed.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("Утверждение не выполнено: порядок чисел"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn test_command_runs_discovered_tests_and_reports_failures() {
+    let dir = std::env::temp_dir().join(format!("ponos-tests-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("мат")).unwrap();
+    std::fs::write(
+        dir.join("мат/сложение.pns"),
+        "использовать \"стд/тест\" как т;\n\
+         @Тест\n\
+         функ сумма()\n\
+         \x20   т.утверждать(1 + 1 == 2);\n\
+         конец\n\
+         функ тест_провал()\n\
+         \x20   т.утверждать(1 == 2, \"один не равен двум\");\n\
+         конец\n",
+    )
+    .unwrap();
+    // Файлы без тестов не запускаются
+    std::fs::write(dir.join("скрипт.pns"), "исключение \"не тест\";\n").unwrap();
+
+    let output = ponos(&["test", dir.to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("ок      сумма"), "{}", stdout);
+    assert!(stdout.contains("ПРОВАЛ  тест_провал"), "{}", stdout);
+    assert!(stdout.contains("один не равен двум"), "{}", stdout);
+    assert!(
+        stdout.contains("Тестов: 2, пройдено: 1, провалено: 1"),
+        "{}",
+        stdout
+    );
+    // Провал сообщается один раз, в сводке, со стеком без служебного кадра
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Необработанное исключение"), "{}", stderr);
+    assert!(stdout.contains("#0 тест_провал ("), "{}", stdout);
+    assert!(!stdout.contains("<вызов>"), "{}", stdout);
+}
+
+#[test]
+fn spec_prints_document_without_error_reports() {
+    let output = ponos(&["spec"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("# Семантика Ponos"));
+    // Ошибки из примеров с ожидаемой ошибкой не попадают в stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.is_empty(), "{}", stderr);
+}
+
+#[test]
+fn fmt_checks_and_rewrites_files_in_place() {
+    let path = std::env::temp_dir().join(format!("ponos-fmt-{}.pns", std::process::id()));
+    std::fs::write(&path, "пер x=1+2;\nесли x>2 вывести(x); конец\n").unwrap();
+    let file = path.to_str().unwrap();
+
+    let check = ponos(&["fmt", "--check", file]);
+    assert_eq!(check.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&check.stdout).contains("Требует форматирования"));
+
+    let rewrite = ponos(&["fmt", file]);
+    let formatted = std::fs::read_to_string(&path).unwrap();
+    let check_again = ponos(&["fmt", file, "--check"]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(rewrite.status.success());
+    assert_eq!(
+        formatted,
+        "пер x = 1 + 2;\nесли x > 2\n    вывести(x);\nконец\n"
+    );
+    assert!(check_again.status.success());
+}
+
+#[test]
+fn parse_prints_ast_as_json() {
+    let output = ponos(&[
+        "parse",
+        "--ast-json",
+        "tests/fixtures/test_cli_script_args.pns",
+    ]);
+    assert!(output.status.success());
+    let ast: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ast["kind"], "Program");
+    assert_eq!(ast["file"], "tests/fixtures/test_cli_script_args.pns");
+    assert!(ast["statements"][0]["kind"].is_string(), "{}", ast);
+
+    let path = std::env::temp_dir().join(format!("ponos-parse-{}.pns", std::process::id()));
+    std::fs::write(&path, "пер = ;\n").unwrap();
+    let output = ponos(&["parse", "--ast-json", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn unhandled_exception_stack_trace_shows_source_lines() {
+    let path = std::env::temp_dir().join(format!("ponos-lines-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "функ проверить(x)\n    если x > 1\n        исключение \"слишком много\";\n    конец\nконец\n\nпроверить(5);\n",
+    )
+    .unwrap();
+    let output = ponos(&["run", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("#0 проверить ("), "{}", stderr);
+    assert!(stderr.contains("строка 3)"), "{}", stderr);
+    assert!(stderr.contains("#1 <главная> ("), "{}", stderr);
+    assert!(stderr.contains("строка 7)"), "{}", stderr);
+}
+
+#[test]
+fn run_with_profile_reports_hot_functions() {
+    let path = std::env::temp_dir().join(format!("ponos-profile-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "функ квадрат(x)\n    возврат x * x;\nконец\n\nпер сумма = 0;\nпер x = 1;\nпока x <= 3\n    сумма = сумма + квадрат(x);\n    x = x + 1;\nконец\nвывести(сумма);\n",
+    )
+    .unwrap();
+    let output = ponos(&["run", "--profile", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("14\n"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Профиль"));
+    assert!(stderr.contains("Профиль выполнения:"), "{}", stderr);
+    let line = stderr
+        .lines()
+        .find(|line| line.contains("квадрат ("))
+        .unwrap_or_else(|| panic!("{}", stderr));
+    assert_eq!(line.split_whitespace().next(), Some("3"), "{}", line);
+    assert!(stderr.contains("<главная>"), "{}", stderr);
+}
+
+#[test]
+fn run_with_trace_prints_executed_opcodes() {
+    let path = std::env::temp_dir().join(format!("ponos-trace-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "функ привет(имя)\n    возврат \"привет, \" + имя;\nконец\nвывести(привет(\"мир\"));\n",
+    )
+    .unwrap();
+    let output = ponos(&["run", "--trace", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("привет, мир\n"));
+    let add = stderr
+        .lines()
+        .find(|line| line.contains("Add"))
+        .unwrap_or_else(|| panic!("{}", stderr));
+    assert!(add.contains("привет"), "{}", add);
+    assert!(add.contains("стр. 2"), "{}", add);
+    assert!(add.contains("\"привет, \", \"мир\"]"), "{}", add);
+    assert!(stderr.contains("<функ привет>"), "{}", stderr);
+    assert!(stderr.contains("<главная>"), "{}", stderr);
+}
+
+#[test]
+fn sandbox_flag_rejects_system_modules() {
+    let path = std::env::temp_dir().join(format!("ponos-sandbox-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "использовать \"стд/система\" как система;\nсистема.выполнить(\"echo взлом\");\n",
+    )
+    .unwrap();
+    let sandboxed = ponos(&["--sandbox", "run", path.to_str().unwrap()]);
+    let check = ponos(&["--sandbox", "check", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&sandboxed.stderr);
+    assert!(!sandboxed.status.success());
+    assert!(
+        stderr.contains("Модуль 'стд/система' недоступен: песочница запрещает его импорт"),
+        "{}",
+        stderr
+    );
+    assert_eq!(check.status.code(), Some(1));
+}
+
+#[test]
+fn native_callback_recursion_throws_limit_error() {
+    let path = std::env::temp_dir().join(format!("ponos-nested-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "использовать \"стд/массивы\" как мас;\n\
+         функ через_свёртку(н) возврат мас.свернуть([1], 0, функ(а, б) возврат через_свёртку(н + 1); конец); конец\n\
+         функ через_карту(н) возврат мас.карта([1], функ(а) возврат через_карту(н + 1); конец); конец\n\
+         функ через_сортировку(н) возврат мас.сортировать_по([1, 2], функ(а, б) возврат через_сортировку(н + 1); конец); конец\n\
+         функ проверить(ф)\n\
+         \x20   попытка ф(0); перехват е: ОшибкаЛимита вывести(е.сообщение); конец\n\
+         конец\n\
+         проверить(через_свёртку);\n\
+         проверить(через_карту);\n\
+         проверить(через_сортировку);\n\
+         через_карту(0);\n",
+    )
+    .unwrap();
+    let output = ponos(&[
+        "--sandbox",
+        "--max-depth",
+        "20000",
+        "run",
+        path.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&path).unwrap();
+
+    // Глубокая рекурсия через нативные функции — перехватываемая ошибка,
+    // а не переполнение стека потока
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(
+        stdout
+            .matches("Превышена глубина вложенных вызовов из нативных функций")
+            .count(),
+        3,
+        "{}",
+        stdout
+    );
+    assert!(stderr.contains("ОшибкаЛимита"), "{}", stderr);
+    assert!(!stderr.contains("overflow"), "{}", stderr);
+}
+
+#[test]
+fn scripts_read_piped_stdin_and_write_stderr() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let path = std::env::temp_dir().join(format!("ponos-pipe-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "использовать \"стд/ввод_вывод\" как ио;\n\
+         пер первая = ио.ввести();\n\
+         пер остальное = ио.ввести_все();\n\
+         ио.вывести(первая, длина(остальное));\n\
+         ио.вывести_ошибку(\"конец\", ио.ввести() ?? \"EOF\");\n",
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .args(["run", path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("заголовок\nабв\nгд\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("заголовок 7 \n"), "{}", stdout);
+    assert!(stderr.ends_with("конец EOF \n"), "{}", stderr);
+}
+
+#[test]
+fn buffered_output_survives_unhandled_exception() {
+    let path = std::env::temp_dir().join(format!("ponos-buffered-{}.pns", std::process::id()));
+    std::fs::write(&path, "вывести(\"до ошибки\");\nисключение \"сбой\";\n").unwrap();
+    let output = ponos(&["run", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("до ошибки\n"), "{}", stdout);
+}
+
+#[test]
+fn closed_pipe_ends_script_quietly() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let path = std::env::temp_dir().join(format!("ponos-sigpipe-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "пер и = 0;\nпока и < 200000\n    вывести(и);\n    и = и + 1;\nконец\n",
+    )
+    .unwrap();
+
+    // Читатель закрывает конвейер после первых строк, как `| head`
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .args(["run", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    for line in stdout.lines() {
+        if line.unwrap() == "100" {
+            break;
+        }
+    }
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(141), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_interrupts_running_script() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let path = std::env::temp_dir().join(format!("ponos-interrupt-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "использовать \"стд/ввод_вывод\" как ио;\n\
+         использовать \"стд/система\" как система;\n\
+         ио.без_буфера();\n\
+         функ выйти(код)\n\
+             система.завершить(код);\n\
+         конец\n\
+         функ ждать_прерывания()\n\
+             попытка\n\
+                 вывести(\"го\" + \"тов\");\n\
+                 пока истина\n\
+                 конец\n\
+             перехват е: ПрерываниеПользователя\n\
+                 выйти(3);\n\
+             конец\n\
+         конец\n\
+         ждать_прерывания();\n",
+    )
+    .unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ponos-rs"))
+        .args(["run", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("не удалось запустить интерпретатор");
+
+    // Сигнал посылается, когда скрипт уже крутится в цикле
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    for line in stdout.lines() {
+        if line.unwrap() == "готов" {
+            break;
+        }
+    }
+    let kill = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let status = child.wait().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(kill.success());
+    assert_eq!(status.code(), Some(3));
+}
+
+#[test]
+fn warnings_are_reported_and_denied_on_request() {
+    let path = std::env::temp_dir().join(format!("ponos-lints-{}.pns", std::process::id()));
+    std::fs::write(
+        &path,
+        "функ f()\n    пер лишняя = 1;\n    возврат 2;\nконец\nвывести(f());\n",
+    )
+    .unwrap();
+    let checked = ponos(&["check", path.to_str().unwrap()]);
+    let denied = ponos(&["--deny-warnings", "check", path.to_str().unwrap()]);
+    let run = ponos(&["--deny-warnings", "run", path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8_lossy(&checked.stderr);
+    assert!(checked.status.success(), "{}", stderr);
+    assert!(stderr.contains("Предупреждение:"), "{}", stderr);
+    assert!(
+        stderr.contains("Локальная переменная 'лишняя' не используется"),
+        "{}",
+        stderr
+    );
+    assert_eq!(denied.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&run.stderr);
+    assert_eq!(run.status.code(), Some(1));
+    assert!(stderr.contains("запуск отменён"), "{}", stderr);
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "desktop")]
+
+use ponos_rs::Ponos;
+
+// Тесты модуля стд/буфер_обмена (собирается с фичей desktop).
+// Сами утилиты буфера обмена в CI обычно недоступны, поэтому проверяется
+// только разбор аргументов
+
+#[test]
+fn test_browser_rejects_non_http_urls() {
+    let mut ponos = Ponos::new();
+    let error = ponos
+        .eval(
+            "использовать \"стд/буфер_обмена\" как буфер;
+             буфер.открыть_в_браузере(\"file:///etc/passwd\");",
+        )
+        .unwrap_err();
+
+    assert!(error.contains("http"), "{}", error);
+}
+
+#[test]
+fn test_write_requires_string() {
+    let mut ponos = Ponos::new();
+    let error = ponos
+        .eval(
+            "использовать \"стд/буфер_обмена\" как буфер;
+             буфер.записать(42);",
+        )
+        .unwrap_err();
+
+    assert!(error.contains("строкой"), "{}", error);
+}
@@ -14,7 +14,7 @@ fn test_if_true() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -27,7 +27,7 @@ fn test_if_false() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -42,7 +42,7 @@ fn test_if_with_comparison() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -57,7 +57,7 @@ fn test_if_else_true_branch() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -72,7 +72,7 @@ fn test_if_else_false_branch() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -87,7 +87,7 @@ fn test_if_multiple_statements() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -102,7 +102,7 @@ fn test_nested_if() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -117,7 +117,7 @@ fn test_if_with_logical_operators() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 // === Тесты для while ===
@@ -132,7 +132,7 @@ fn test_while_zero_iterations() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -145,7 +145,7 @@ fn test_while_with_counter() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -160,7 +160,7 @@ fn test_while_with_multiple_statements() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -178,7 +178,7 @@ fn test_nested_while() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -201,7 +201,7 @@ fn test_while_with_if() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -217,5 +217,5 @@ fn test_if_else_with_arithmetic() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
@@ -0,0 +1,816 @@
+use ponos_rs::ponos::native::{NativeModuleRegistry, SAFE_MODULES};
+use ponos_rs::ponos::vm::Limits;
+use ponos_rs::{Ponos, PonosError, Value};
+
+// Тесты API для встраивания интерпретатора в Rust-приложения
+
+#[test]
+fn test_eval_returns_last_expression() {
+    let mut ponos = Ponos::new();
+
+    assert_eq!(ponos.eval("2 + 3 * 4;"), Ok(Value::Int(14)));
+    assert_eq!(ponos.eval("пер x = 1;"), Ok(Value::Nil));
+}
+
+#[test]
+fn test_eval_keeps_globals_between_calls() {
+    let mut ponos = Ponos::new();
+
+    ponos.eval("пер счётчик = 10;").unwrap();
+    ponos.eval("счётчик = счётчик + 5;").unwrap();
+
+    assert_eq!(ponos.get_global("счётчик"), Some(Value::Int(15)));
+    assert_eq!(ponos.get_global("нет_такой"), None);
+}
+
+#[test]
+fn test_run_source_reports_value_and_failing_stage() {
+    let mut ponos = Ponos::new();
+
+    let value = ponos.run_source("пер x = 20; x + 1;".to_string());
+    assert_eq!(value, Ok(Some(Value::Int(21))));
+    assert_eq!(ponos.run_source("пер y = 2;".to_string()), Ok(None));
+
+    let stage = |ponos: &mut Ponos, source: &str| ponos.run_source(source.to_string()).unwrap_err();
+    assert!(matches!(stage(&mut ponos, "пер = ;"), PonosError::Parse(_)));
+    assert!(matches!(
+        stage(&mut ponos, "использовать \"нет/такого\" как н;"),
+        PonosError::Import(_)
+    ));
+    assert!(matches!(
+        stage(&mut ponos, "пер z: число = \"текст\";"),
+        PonosError::Check(_)
+    ));
+    assert!(matches!(
+        stage(&mut ponos, "исключение \"сбой\";"),
+        PonosError::Runtime(_)
+    ));
+
+    // После ошибки выполнения интерпретатор остаётся пригодным
+    assert_eq!(ponos.run_source("x;".to_string()), Ok(Some(Value::Int(20))));
+}
+
+#[test]
+fn test_call_function_from_rust() {
+    let mut ponos = Ponos::new();
+    ponos
+        .eval("функ приветствие(имя) возврат \"Привет, \" + имя; конец")
+        .unwrap();
+
+    let result = ponos.call("приветствие", &["мир".into()]).unwrap();
+    assert_eq!(String::try_from(result), Ok("Привет, мир".to_string()));
+
+    assert!(ponos.call("нет_такой", &[]).is_err());
+}
+
+#[test]
+fn test_call_passes_ponos_functions_as_arguments() {
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(
+            "функ удвоить(x) возврат x * 2; конец
+             функ применить(f, x) возврат f(x); конец",
+        )
+        .unwrap();
+
+    let double = ponos.get_global("удвоить").unwrap();
+    assert_eq!(
+        ponos.call("применить", &[double, 21.into()]),
+        Ok(Value::Int(42))
+    );
+}
+
+#[test]
+fn test_set_global_is_visible_to_scripts() {
+    let mut ponos = Ponos::new();
+    ponos.set_global("множитель", 3);
+
+    assert_eq!(ponos.eval("множитель * 7;"), Ok(Value::Int(21)));
+}
+
+#[test]
+fn test_errors_are_returned_and_interpreter_recovers() {
+    let mut ponos = Ponos::new();
+
+    assert!(ponos.eval("пер = ;").is_err());
+
+    let runtime = ponos.eval("истина - 1;").unwrap_err();
+    assert!(runtime.contains("ОшибкаТипа"), "{}", runtime);
+
+    assert!(ponos.eval("бросить \"сбой\";").is_err());
+
+    assert_eq!(ponos.eval("1 + 1;"), Ok(Value::Int(2)));
+}
+
+#[test]
+fn test_runtime_errors_name_their_error_class() {
+    let mut ponos = Ponos::new();
+
+    let index = ponos.eval("пер м = [1]; м[3];").unwrap_err();
+    assert!(index.contains("ОшибкаИндекса: Индекс 3"), "{}", index);
+
+    let call = ponos.eval("пер ч = 5; ч();").unwrap_err();
+    assert!(call.contains("ОшибкаТипа"), "{}", call);
+
+    let io = ponos
+        .eval("использовать \"стд/фс\" как фс; фс.читать(\"/нет/такого/файла\");")
+        .unwrap_err();
+    assert!(io.contains("ОшибкаИО: Ошибка чтения"), "{}", io);
+}
+
+#[test]
+fn test_value_conversions() {
+    assert_eq!(Value::from(1.5), Value::Number(1.5));
+    assert_eq!(Value::from(true), Value::Boolean(true));
+    assert_eq!(Value::from(None::<f64>), Value::Nil);
+
+    let array = Value::from(vec![1, 2, 3]);
+    assert_eq!(
+        Vec::<Value>::try_from(array),
+        Ok(vec![Value::Int(1), Value::Int(2), Value::Int(3)])
+    );
+
+    assert_eq!(f64::try_from(Value::Number(4.0)), Ok(4.0));
+    assert!(bool::try_from(Value::Nil).is_err());
+}
+
+#[test]
+fn test_register_closure_with_host_state() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let mut ponos = Ponos::new();
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let sink = log.clone();
+    ponos.register_function("записать_в_журнал", move |args| {
+        sink.borrow_mut().extend(args.iter().cloned());
+        Ok(Value::Nil)
+    });
+
+    let prefix = String::from("хост: ");
+    ponos.register_function("подписать", move |args| match args {
+        [Value::String(s)] => Ok(Value::String(format!("{}{}", prefix, s).into())),
+        _ => Err("подписать ожидает строку".to_string()),
+    });
+
+    ponos
+        .eval("записать_в_журнал(1, \"два\"); записать_в_журнал(истина);")
+        .unwrap();
+    assert_eq!(
+        *log.borrow(),
+        vec![Value::Int(1), "два".into(), Value::Boolean(true)]
+    );
+
+    assert_eq!(
+        ponos.eval("подписать(\"привет\");"),
+        Ok("хост: привет".into())
+    );
+    assert!(ponos.eval("подписать(1);").is_err());
+}
+
+#[test]
+fn test_native_function_calls_back_into_ponos() {
+    let mut ponos = Ponos::new();
+    ponos.register_function_with_context("применить_дважды", |ctx, args| match args {
+        [f, x] => {
+            let once = ctx.call(f, std::slice::from_ref(x))?;
+            ctx.call(f, &[once])
+        }
+        _ => Err("применить_дважды(f, x)".to_string()),
+    });
+
+    ponos
+        .eval("функ прибавить_три(x) возврат x + 3; конец")
+        .unwrap();
+    assert_eq!(
+        ponos.eval("применить_дважды(прибавить_три, 1);"),
+        Ok(Value::Int(7))
+    );
+
+    // Исключение из обратного вызова доходит до перехвата в Ponos
+    ponos
+        .eval(
+            "функ сбой(x) исключение \"сбой\"; конец
+             функ попробовать()
+                 попытка
+                     применить_дважды(сбой, 1);
+                 перехват е
+                     возврат е;
+                 конец
+             конец",
+        )
+        .unwrap();
+    assert_eq!(ponos.eval("попробовать();"), Ok("сбой".into()));
+}
+
+#[test]
+fn output_can_be_captured() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    ponos.eval("вывести(\"строка\"); вывести(1 + 1);").unwrap();
+    assert_eq!(ponos.take_output().as_deref(), Some("строка\n2\n"));
+    assert_eq!(ponos.take_output(), None);
+}
+
+#[test]
+fn test_formatted_output() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    ponos
+        .eval("пер x = 3; вывести_ф(\"x = {}, y = {:.2}\", x, 1 / 3);")
+        .unwrap();
+    assert_eq!(ponos.take_output().as_deref(), Some("x = 3, y = 0.33\n"));
+    assert_eq!(
+        ponos.eval("формат(\"[{:>5}|{:-<4}]\", 42, \"аб\");"),
+        Ok("[   42|аб--]".into())
+    );
+    let err = ponos.eval("вывести_ф(\"{} {}\", 1);").unwrap_err();
+    assert!(
+        err.contains("вывести_ф: шаблону нужно значение номер 1"),
+        "{}",
+        err
+    );
+
+    // Программа может перекрыть встроенную функцию своим объявлением
+    ponos.eval("пер формат = \"ГГГГ-ММ-ДД\";").unwrap();
+    assert_eq!(ponos.eval("формат;"), Ok("ГГГГ-ММ-ДД".into()));
+}
+
+#[test]
+fn test_pending_tasks_run_after_program() {
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(
+            "пер журнал = [];
+             таймер_через(5, функ() журнал.добавить(\"таймер\"); конец);
+             сопрограмма(функ() журнал.добавить(\"задача\"); конец);",
+        )
+        .unwrap();
+    assert_eq!(
+        ponos.eval("журнал[0] + \" \" + журнал[1];"),
+        Ok("задача таймер".into())
+    );
+
+    // Исключение задачи, которую никто не ждёт, завершает программу
+    let err = ponos
+        .eval("таймер_через(1, функ() исключение \"сбой в таймере\"; конец);")
+        .unwrap_err();
+    assert!(err.contains("сбой в таймере"), "{}", err);
+    assert_eq!(ponos.eval("1 + 1;"), Ok(Value::Int(2)));
+}
+
+#[test]
+fn test_structured_exception_message() {
+    let mut ponos = Ponos::new();
+    let error = ponos
+        .eval(
+            "использовать \"стд/json\" как json;
+             json.десериализовать(\"{\");",
+        )
+        .unwrap_err();
+
+    assert!(
+        error.contains("ОшибкаJSON: Ошибка разбора JSON"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_check_reports_errors_without_running() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+
+    let source = "использовать \"стд/ввод_вывод\" как ио;\n\
+                  использовать \"нет_такого\" как н;\n\
+                  ио.вывести(1);\n\
+                  ио.нет_функции(1);";
+    let diagnostics = ponos.check(source, None);
+
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert!(diagnostics[0].contains("нет_такого"));
+    assert!(diagnostics[1].contains("Символ 'нет_функции' не найден в модуле 'ио'"));
+    assert_eq!(ponos.take_output(), Some(String::new()));
+    assert!(ponos.check("ио.вывести(1);", None).is_empty());
+}
+
+#[test]
+fn test_type_annotations_are_checked_before_running() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+
+    let error = ponos
+        .eval("функ удвоить(x: число) возврат x * 2; конец вывести(\"до\"); удвоить(\"два\");")
+        .unwrap_err();
+    assert!(
+        error.contains("Аргумент 1 функции 'удвоить' должен иметь тип число"),
+        "{}",
+        error
+    );
+    assert_eq!(ponos.take_output(), Some(String::new()));
+}
+
+#[test]
+fn test_undeclared_names_are_reported_before_running() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    ponos.set_global("от_хоста", 1);
+
+    let error = ponos
+        .eval("вывести(\"до\"); функ f() возврат неизвестное; конец")
+        .unwrap_err();
+    assert!(
+        error.contains("Переменная 'неизвестное' не объявлена"),
+        "{}",
+        error
+    );
+    assert_eq!(ponos.take_output(), Some(String::new()));
+
+    // Глобальные переменные хоста и предыдущих вызовов известны проверке
+    assert!(ponos.eval("пер из_прошлого = от_хоста + 1;").is_ok());
+    assert_eq!(ponos.eval("из_прошлого;"), Ok(Value::Int(2)));
+}
+
+#[test]
+fn test_constants_cannot_be_reassigned() {
+    let mut ponos = Ponos::new();
+
+    let error = ponos
+        .eval("использовать \"стд/математика\" как мат показать ПИ;\nПИ = 3;")
+        .unwrap_err();
+    assert!(
+        error.contains("Нельзя присвоить значение константе 'ПИ'"),
+        "{}",
+        error
+    );
+    let error = ponos.eval("мат.Е = 3;").unwrap_err();
+    assert!(
+        error.contains("Нельзя присвоить значение константе 'мат.Е'"),
+        "{}",
+        error
+    );
+
+    // Константа из предыдущего вызова eval, как в REPL
+    ponos.eval("конст РАЗМЕР = 8;").unwrap();
+    let error = ponos.eval("РАЗМЕР = 9;").unwrap_err();
+    assert!(
+        error.contains("Нельзя присвоить значение константе 'РАЗМЕР'"),
+        "{}",
+        error
+    );
+    assert_eq!(ponos.eval("РАЗМЕР;"), Ok(Value::Int(8)));
+}
+
+#[test]
+fn test_hidden_imports_are_rejected() {
+    let mut ponos = Ponos::new();
+
+    let error = ponos
+        .eval("использовать \"стд/математика\" как мат показать корень;\nмат.абс(-1);")
+        .unwrap_err();
+    assert!(
+        error.contains("Символ 'абс' скрыт при импорте модуля 'мат'"),
+        "{}",
+        error
+    );
+
+    let error = ponos
+        .eval("использовать \"стд/математика\" как м скрыть абс;\nм.абс(-1);")
+        .unwrap_err();
+    assert!(error.contains("Символ 'абс' скрыт"), "{}", error);
+
+    let diagnostics = ponos.check("использовать \"стд/математика\" показать нет_такого;", None);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(
+        diagnostics[0].contains("Модуль не экспортирует 'нет_такого'"),
+        "{}",
+        diagnostics[0]
+    );
+}
+
+#[test]
+fn test_shown_imports_are_usable_without_prefix() {
+    let mut ponos = Ponos::new();
+    let value = ponos
+        .eval(
+            "использовать \"стд/математика\" как мат показать корень;\nкорень(16) + мат.корень(9);",
+        )
+        .unwrap();
+    assert_eq!(value, Value::Number(7.0));
+
+    let diagnostics = ponos.check(
+        "использовать \"стд/математика\" как мат показать корень;\nпер корень = 1;",
+        None,
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(
+        diagnostics[0].contains("Имя 'корень' уже импортировано из модуля 'мат'"),
+        "{}",
+        diagnostics[0]
+    );
+
+    let diagnostics = ponos.check(
+        "использовать \"стд/математика\" как мат показать корень;\nабс(-1);",
+        None,
+    );
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.contains("Переменная 'абс' не объявлена")),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn test_repeated_imports_load_module_once() {
+    let dir = std::env::temp_dir().join(format!("ponos-dedup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("счётчик.pns"),
+        "экспорт пер ЗАГРУЗОК = 0;\nЗАГРУЗОК = ЗАГРУЗОК + 1;\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("фасад.pns"), "экспорт использовать \"счётчик\";\n").unwrap();
+    let main = dir.join("главный.pns");
+    let source = "использовать \"счётчик\";\nиспользовать \"фасад\";\n\
+                  использовать \"счётчик\";\n";
+
+    let mut ponos = Ponos::new();
+    let ctx = ponos.compile(source, Some(main));
+    let count = ponos.eval("счётчик.ЗАГРУЗОК + фасад.ЗАГРУЗОК;");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(ctx.is_ok(), "{:?}", ctx.err());
+    assert_eq!(count.unwrap(), Value::Int(2));
+}
+
+#[test]
+fn test_module_under_two_names_is_initialized_once() {
+    let dir = std::env::temp_dir().join(format!("ponos-aliases-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("настройки.pns"),
+        "вывести(\"инициализация\");\nэкспорт пер ИМЯ = \"основные\";\n",
+    )
+    .unwrap();
+    let main = dir.join("главный.pns");
+    let source = "использовать \"настройки\" как а;\nиспользовать \"настройки\" как б;\n\
+                  вывести(а.ИМЯ == б.ИМЯ);\n";
+
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    let result = ponos.run_source_with_file(source.to_string(), Some(main));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(
+        ponos.take_output().as_deref(),
+        Some("инициализация\ntrue\n")
+    );
+}
+
+#[test]
+fn test_modules_can_import_each_other() {
+    let dir = std::env::temp_dir().join(format!("ponos-cycle-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("чёт.pns"),
+        "использовать \"нечёт\"\nэкспорт функ чётное(н)\n    если н == 0 возврат истина конец\n    \
+         возврат нечёт.нечётное(н - 1)\nконец\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("нечёт.pns"),
+        "использовать \"чёт\"\nэкспорт функ нечётное(н)\n    если н == 0 возврат ложь конец\n    \
+         возврат чёт.чётное(н - 1)\nконец\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("в.pns"),
+        "использовать \"г\"\nэкспорт пер В = г.Г + 1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("г.pns"),
+        "использовать \"в\"\nэкспорт пер Г = в.В + 1\n",
+    )
+    .unwrap();
+    let main = || Some(dir.join("главный.pns"));
+
+    let mut ponos = Ponos::new();
+    let mutual = ponos.run_source_with_file(
+        "использовать \"чёт\"\nчёт.чётное(10) == истина и чёт.чётное(7) == ложь\n".to_string(),
+        main(),
+    );
+    // Импорт модуля виден только его коду
+    let private = ponos.run_source_with_file("нечёт.нечётное(1)\n".to_string(), main());
+    let unresolvable =
+        Ponos::new().run_source_with_file("использовать \"в\"\n".to_string(), main());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(mutual, Ok(Some(Value::Boolean(true))));
+    assert!(private.is_err(), "{:?}", private);
+    let error = unresolvable.unwrap_err().to_string();
+    assert!(
+        error.contains("Символ 'В' модуля 'в' ещё не определён"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_main_function_runs_after_top_level_code() {
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    let value = ponos.run_source(
+        "функ главная()\n    вывести(\"главная\")\n    возврат 7\nконец\nвывести(\"верхний уровень\")\n"
+            .to_string(),
+    );
+    assert_eq!(value, Ok(Some(Value::Int(7))));
+    assert_eq!(
+        ponos.take_output().as_deref(),
+        Some("верхний уровень\nглавная\n")
+    );
+
+    // Программа, которая сама вызывает главная(), не вызывает её второй раз
+    let mut ponos = Ponos::new();
+    ponos.capture_output();
+    ponos
+        .run_source("функ главная() вывести(1) конец\nглавная()\n".to_string())
+        .unwrap();
+    assert_eq!(ponos.take_output().as_deref(), Some("1\n"));
+
+    let required = Ponos::new().run_source("функ главная(х) конец\n".to_string());
+    assert!(
+        matches!(&required, Err(PonosError::Check(errors))
+            if errors[0].contains("Функция 'главная' не должна требовать аргументов")),
+        "{:?}",
+        required
+    );
+}
+
+#[test]
+fn test_reload_module_replaces_its_globals() {
+    let dir = std::env::temp_dir().join(format!("ponos-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = dir.join("версия.pns");
+    std::fs::write(&module, "экспорт функ номер() возврат 1; конец\n").unwrap();
+
+    let mut ponos = Ponos::new();
+    let import = format!("использовать \"{}\" как в;", module.display());
+    ponos.eval(&import).unwrap();
+    ponos
+        .eval("функ через_модуль() возврат в.номер(); конец")
+        .unwrap();
+    assert_eq!(ponos.eval("в.номер();").unwrap(), Value::Int(1));
+
+    std::fs::write(
+        &module,
+        "экспорт функ номер() возврат 2; конец\nэкспорт пер НОВОЕ = \"да\";\n",
+    )
+    .unwrap();
+    ponos.reload_module("в").unwrap();
+    let reloaded = ponos.eval("в.номер() + через_модуль();");
+    let added = ponos.eval("в.НОВОЕ;");
+    let unknown = ponos.reload_module("нет_такого");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Код, скомпилированный до перезагрузки, тоже видит новую версию
+    assert_eq!(reloaded.unwrap(), Value::Int(4));
+    assert_eq!(added.unwrap(), Value::from("да"));
+    assert!(
+        unknown
+            .unwrap_err()
+            .contains("Модуль 'нет_такого' не загружен")
+    );
+}
+
+#[test]
+fn test_namespace_collisions_point_at_both_imports() {
+    let dir = std::env::temp_dir().join(format!("ponos-collision-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("а")).unwrap();
+    std::fs::create_dir_all(dir.join("б")).unwrap();
+    std::fs::write(dir.join("а/утилиты.pns"), "экспорт пер А = 1;\n").unwrap();
+    std::fs::write(dir.join("б/утилиты.pns"), "экспорт пер Б = 2;\n").unwrap();
+    let main = dir.join("главный.pns");
+    let source = "использовать \"./а/утилиты\";\nиспользовать \"./б/утилиты\";\n";
+
+    let diagnostics = Ponos::new().check(source, Some(main));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let diagnostic = &diagnostics[0];
+    assert!(
+        diagnostic.contains("Пространство имён 'утилиты' уже занято модулем"),
+        "{}",
+        diagnostic
+    );
+    assert!(diagnostic.contains("главный.pns:2:1"), "{}", diagnostic);
+    assert!(diagnostic.contains("главный.pns:1:1"), "{}", diagnostic);
+}
+
+#[test]
+fn test_check_uses_signatures_of_imported_functions() {
+    let dir = std::env::temp_dir().join(format!("ponos-types-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("геометрия.pns"),
+        "экспорт функ площадь(ширина: число, высота: число) возврат ширина * высота; конец\n",
+    )
+    .unwrap();
+    let main = dir.join("главный.pns");
+    let source = "использовать \"геометрия\" как г;\nг.площадь(2, \"три\");\n";
+
+    let diagnostics = Ponos::new().check(source, Some(main));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].contains("Аргумент 2 функции 'г.площадь' должен иметь тип число"));
+    assert!(
+        diagnostics[0].contains("главный.pns:2:14"),
+        "{}",
+        diagnostics[0]
+    );
+}
+
+#[test]
+fn test_sandbox_rejects_modules_outside_allow_list() {
+    let mut ponos = Ponos::new();
+    ponos.sandbox(SAFE_MODULES);
+
+    let error = ponos
+        .run_source("использовать \"стд/фс\" как фс;".to_string())
+        .unwrap_err();
+    assert!(matches!(&error, PonosError::Import(_)), "{}", error);
+    assert!(
+        error.to_string().contains("Модуль 'стд/фс' недоступен"),
+        "{}",
+        error
+    );
+
+    let value =
+        ponos.eval("использовать \"стд/строки\" как строки;\nстроки.верхний_регистр(\"а\");");
+    assert_eq!(value, Ok(Value::from("А")));
+
+    // Список заменяется целиком: теперь разрешена только файловая система
+    ponos.sandbox(["стд/фс"]);
+    assert!(ponos.eval("использовать \"стд/фс\" как фс;").is_ok());
+    assert!(ponos.eval("использовать \"стд/сеть\" как сеть;").is_err());
+}
+
+#[test]
+fn test_sandbox_allowed_modules_reach_no_files_or_processes() {
+    let dir = std::env::temp_dir().join(format!("ponos-sandbox-all-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let secret = dir.join("секрет.json");
+    std::fs::write(&secret, "\"тайное содержимое\"").unwrap();
+    let target = dir.join("создан.json");
+    let other = dir.join("другой.json");
+    // Команда — тоже абсолютный путь во временном каталоге: запущенный
+    // скрипт создал бы целевой файл
+    let command = dir.join("запуск.sh");
+    std::fs::write(
+        &command,
+        format!("#!/bin/sh\necho 1 > '{}'\n", target.display()),
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&command, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let arg = |path: &std::path::Path| format!("{:?}", path.display().to_string());
+
+    let mut registry = NativeModuleRegistry::new();
+    registry.allow_only(SAFE_MODULES);
+    for entry in SAFE_MODULES {
+        let path = entry.split_once('.').map_or(entry, |(path, _)| path);
+        let module = registry.get_module(path).unwrap();
+        let allowed = registry.allowed_exports(module);
+        for export in &module.exports {
+            // Ввод читает stdin теста и ждал бы его закрытия
+            if path == "стд/ввод_вывод" && export.starts_with("ввести") {
+                continue;
+            }
+            let mut ponos = Ponos::new();
+            ponos.sandbox(SAFE_MODULES);
+            let mut source = format!("использовать \"{}\" как м;\nпер итог = [];\n", path);
+            for args in [
+                arg(&secret),
+                format!("{}, {}", arg(&secret), arg(&target)),
+                format!("{}, {}", arg(&target), arg(&other)),
+                arg(&command),
+            ] {
+                source.push_str(&format!(
+                    "попытка итог.добавить(м.{}({})); перехват е конец\n",
+                    export, args
+                ));
+            }
+            source.push_str("итог;");
+
+            match ponos.eval(&source) {
+                Ok(value) => {
+                    assert!(allowed.contains(export), "{}.{} доступна", path, export);
+                    assert!(
+                        !value.to_string().contains("тайное"),
+                        "{}.{} прочитала файл: {}",
+                        path,
+                        export,
+                        value
+                    );
+                }
+                Err(error) => assert!(!allowed.contains(export), "{}.{}: {}", path, export, error),
+            }
+            assert!(
+                !target.exists() && !other.exists(),
+                "{}.{} создала файл",
+                path,
+                export
+            );
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_limits_stop_runaway_code() {
+    let mut ponos = Ponos::new();
+    ponos.set_limits(Limits {
+        max_instructions: Some(10_000),
+        max_call_depth: Some(50),
+        max_stack: Some(200),
+    });
+
+    // Глубина вызовов и размер стека — перехватываемые ошибки
+    let depth = ponos.eval(
+        "функ вглубь(н) возврат вглубь(н + 1); конец\n\
+         пер итог = \"\";\n\
+         попытка вглубь(0); перехват е: ОшибкаЛимита итог = е.сообщение; конец\n\
+         итог;",
+    );
+    assert_eq!(depth, Ok(Value::from("Превышена глубина вызовов: 50")));
+    let big_array = vec!["0"; 300].join(", ");
+    let stack = ponos.eval(&format!(
+        "пер переполнение = \"\";\n\
+         попытка [{}]; перехват е: ОшибкаЛимита переполнение = е.сообщение; конец\n\
+         переполнение;",
+        big_array
+    ));
+    assert_eq!(stack, Ok(Value::from("Превышен размер стека: 200")));
+
+    // Исчерпанный запас инструкций прерывает выполнение мимо перехвата
+    let budget = ponos
+        .eval("пока истина попытка 1 + 1; перехват е 0; конец конец")
+        .unwrap_err();
+    assert!(
+        budget.contains("Превышен лимит инструкций: 10000"),
+        "{}",
+        budget
+    );
+
+    // Запас считается заново для каждого запуска, глобальные переменные живы.
+    // Значения выражений-операторов в цикле не копятся на стеке
+    let loop_count = "пер сумма_к = 0; пер к = 0;\n\
+                      пока к < 300 к = к + 1; сумма_к = сумма_к + к; к * 2; конец\n\
+                      сумма_к;";
+    assert_eq!(ponos.eval(loop_count), Ok(Value::Int(45150)));
+    assert_eq!(ponos.eval("сумма_к + 1;"), Ok(Value::Int(45151)));
+}
+
+#[test]
+fn test_interrupt_flag_raises_user_interrupt() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let mut ponos = Ponos::new();
+    let flag = Arc::new(AtomicBool::new(false));
+    ponos.set_interrupt_flag(flag.clone());
+    let raise = flag.clone();
+    ponos.register_function("нажать_ctrl_c", move |_| {
+        raise.store(true, Ordering::Relaxed);
+        Ok(Value::Nil)
+    });
+
+    let caught = ponos.eval(
+        "пер шаги = 0;\n\
+         попытка\n\
+             пока истина шаги = шаги + 1; если шаги == 3 нажать_ctrl_c(); конец конец\n\
+         перехват е: ПрерываниеПользователя\n\
+         конец\n\
+         шаги;",
+    );
+    assert_eq!(caught, Ok(Value::Int(3)));
+    assert!(!flag.load(Ordering::Relaxed));
+
+    // Неперехваченное прерывание завершает запуск с ошибкой
+    let error = ponos
+        .eval("пока истина нажать_ctrl_c(); конец")
+        .unwrap_err();
+    assert!(error.contains("ПрерываниеПользователя"), "{}", error);
+
+    // Флаг, поднятый между запусками, следующий запуск не прерывает
+    flag.store(true, Ordering::Relaxed);
+    assert_eq!(ponos.eval("шаги + 1;"), Ok(Value::Int(4)));
+}
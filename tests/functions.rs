@@ -15,7 +15,7 @@ fn test_simple_function_no_params() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -29,7 +29,7 @@ fn test_function_with_return() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -43,7 +43,7 @@ fn test_function_with_one_param() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -57,7 +57,7 @@ fn test_function_with_two_params() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -73,7 +73,7 @@ fn test_function_with_multiple_statements() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -90,7 +90,7 @@ fn test_recursive_function() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 // TODO: Вызов функций внутри других функций требует исправления в генераторе
@@ -129,7 +129,7 @@ fn test_function_with_local_variables() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -146,7 +146,7 @@ fn test_function_with_if_statement() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -166,5 +166,5 @@ fn test_function_with_while_loop() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
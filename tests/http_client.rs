@@ -0,0 +1,154 @@
+use ponos_rs::{Ponos, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+// Тесты HTTP-клиента стд/сеть против локального сервера на Rust
+
+/// Принять один запрос и ответить `response`; вернуть сырой текст запроса
+fn serve_once(response: &'static str, delay: Duration) -> (u16, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+
+        thread::sleep(delay);
+        let _ = reader.get_mut().write_all(response.as_bytes());
+        request
+    });
+    (port, handle)
+}
+
+#[test]
+fn test_request_sends_headers_and_body_and_returns_metadata() {
+    let (port, server) = serve_once(
+        "HTTP/1.1 404 Not Found\r\nX-Request-Id: 42\r\nContent-Length: 6\r\n\r\nнет",
+        Duration::ZERO,
+    );
+
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(&format!(
+            "использовать \"стд/сеть\" как сеть;
+             пер ответ = сеть.запрос(\"post\", \"http://127.0.0.1:{}/путь\", {{
+                 \"заголовки\": {{\"X-Token\": \"abc\"}},
+                 \"тело\": \"данные\",
+                 \"таймаут_мс\": 5000
+             }});
+             пер статус = ответ[\"статус\"];
+             пер тело = ответ[\"тело\"];
+             пер ид = ответ[\"заголовки\"][\"x-request-id\"];",
+            port
+        ))
+        .unwrap();
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /"), "{}", request);
+    assert!(
+        request.to_ascii_lowercase().contains("x-token: abc"),
+        "{}",
+        request
+    );
+    assert!(request.ends_with("\r\n\r\nданные"), "{}", request);
+
+    assert_eq!(ponos.get_global("статус"), Some(Value::Number(404.0)));
+    assert_eq!(ponos.get_global("тело"), Some(Value::from("нет")));
+    assert_eq!(ponos.get_global("ид"), Some(Value::from("42")));
+}
+
+#[test]
+fn test_request_timeout_is_catchable() {
+    let (port, server) = serve_once(
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        Duration::from_millis(1500),
+    );
+
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(&format!(
+            "использовать \"стд/сеть\" как сеть;
+             функ медленный()
+                 возврат сеть.получить(\"http://127.0.0.1:{}/\", {{\"таймаут_мс\": 200}});
+             конец
+             пер ошибка = \"\";
+             попытка
+                 медленный();
+             перехват е
+                 ошибка = е.сообщение;
+             конец",
+            port
+        ))
+        .unwrap();
+    server.join().unwrap();
+
+    let error = String::try_from(ponos.get_global("ошибка").unwrap()).unwrap();
+    assert!(error.contains("Ошибка HTTP-запроса"), "{}", error);
+}
+
+#[test]
+fn test_requests_in_coroutines_run_concurrently() {
+    let delay = Duration::from_millis(300);
+    let servers: Vec<_> = [
+        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nа",
+        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nб",
+        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nв",
+    ]
+    .into_iter()
+    .map(|response| serve_once(response, delay))
+    .collect();
+    let (refused_port, refused) = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        (listener.local_addr().unwrap().port(), listener)
+    };
+    drop(refused);
+
+    let mut ponos = Ponos::new();
+    let started = std::time::Instant::now();
+    ponos
+        .eval(&format!(
+            "использовать \"стд/сеть\" как сеть;
+             функ тело(порт)
+                 пер ответ = сеть.получить(\"http://127.0.0.1:\" + порт + \"/\");
+                 возврат ответ[\"тело\"];
+             конец
+             пер а = сопрограмма(тело, {});
+             пер б = сопрограмма(тело, {});
+             пер в = сопрограмма(тело, {});
+             пер тела = ждать(а) + ждать(б) + ждать(в);
+             пер ошибка = \"\";
+             попытка
+                 ждать(сопрограмма(тело, {}));
+             перехват е: ОшибкаИО
+                 ошибка = е.сообщение;
+             конец",
+            servers[0].0, servers[1].0, servers[2].0, refused_port
+        ))
+        .unwrap();
+    let elapsed = started.elapsed();
+    for (_, server) in servers {
+        server.join().unwrap();
+    }
+
+    assert_eq!(ponos.get_global("тела"), Some(Value::from("абв")));
+    assert!(elapsed < delay * 2, "запросы шли по очереди: {:?}", elapsed);
+    let error = String::try_from(ponos.get_global("ошибка").unwrap()).unwrap();
+    assert!(error.contains("Ошибка HTTP-запроса"), "{}", error);
+}
@@ -0,0 +1,119 @@
+use ponos_rs::{Ponos, Value};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+// Тесты сеть.слушать: сервер работает в потоке теста, клиент — в отдельном
+// потоке на голом TcpStream
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Отправить запросы по очереди и вернуть сырые ответы. Сервер поднимается
+/// не сразу, поэтому первое подключение повторяется
+fn send_requests(port: u16, requests: Vec<String>) -> thread::JoinHandle<Vec<String>> {
+    thread::spawn(move || {
+        requests
+            .into_iter()
+            .map(|request| {
+                let mut stream = (0..100)
+                    .find_map(|_| {
+                        TcpStream::connect(("127.0.0.1", port)).ok().or_else(|| {
+                            thread::sleep(Duration::from_millis(50));
+                            None
+                        })
+                    })
+                    .expect("сервер не поднялся");
+                stream.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                response
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn test_listen_serves_handler_responses() {
+    let port = free_port();
+    let client = send_requests(
+        port,
+        vec![
+            "GET /привет?имя=%D0%BC%D0%B8%D1%80 HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string(),
+            "POST /данные HTTP/1.1\r\nContent-Length: 8\r\nX-Token: abc\r\n\r\nтело".to_string(),
+        ],
+    );
+
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(&format!(
+            "использовать \"стд/сеть\" как сеть;
+             пер токен = ничто;
+             функ обработчик(запрос)
+                 если запрос[\"метод\"] == \"POST\"
+                     токен = запрос[\"заголовки\"][\"x-token\"];
+                     возврат {{\"статус\": 201, \"json\": {{\"получено\": запрос[\"тело\"]}}}};
+                 конец
+                 возврат запрос[\"путь\"] + \", \" + запрос[\"параметры\"][\"имя\"];
+             конец
+             сеть.слушать({}, обработчик, {{\"макс_запросов\": 2}});",
+            port
+        ))
+        .unwrap();
+
+    let responses = client.join().unwrap();
+    assert!(
+        responses[0].starts_with("HTTP/1.1 200 OK\r\n"),
+        "{}",
+        responses[0]
+    );
+    assert!(
+        responses[0].ends_with("\r\n\r\n/привет, мир"),
+        "{}",
+        responses[0]
+    );
+    assert!(
+        responses[1].starts_with("HTTP/1.1 201 Created\r\n"),
+        "{}",
+        responses[1]
+    );
+    assert!(
+        responses[1].contains("Content-Type: application/json"),
+        "{}",
+        responses[1]
+    );
+    assert!(
+        responses[1].ends_with("{\"получено\":\"тело\"}"),
+        "{}",
+        responses[1]
+    );
+    assert_eq!(ponos.get_global("токен"), Some(Value::from("abc")));
+}
+
+#[test]
+fn test_listen_reports_handler_exception() {
+    let port = free_port();
+    let client = send_requests(port, vec!["GET / HTTP/1.1\r\n\r\n".to_string()]);
+
+    let mut ponos = Ponos::new();
+    let error = ponos
+        .eval(&format!(
+            "использовать \"стд/сеть\" как сеть;
+             функ обработчик(запрос)
+                 исключение \"сломалось\";
+             конец
+             сеть.слушать({}, обработчик);",
+            port
+        ))
+        .unwrap_err();
+
+    assert!(error.contains("сломалось"), "{}", error);
+    let responses = client.join().unwrap();
+    assert!(responses[0].starts_with("HTTP/1.1 500"), "{}", responses[0]);
+}
@@ -3,6 +3,10 @@ use std::path::PathBuf;
 
 /// Вспомогательная функция для запуска .pns файла
 fn run_pns_file(filename: &str) -> Result<(), String> {
+    run_pns_file_in(Ponos::new(), filename)
+}
+
+fn run_pns_file_in(mut ponos: Ponos, filename: &str) -> Result<(), String> {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("tests/fixtures");
     path.push(filename);
@@ -10,25 +14,10 @@ fn run_pns_file(filename: &str) -> Result<(), String> {
     let source = std::fs::read_to_string(&path)
         .map_err(|e| format!("Не удалось прочитать файл {:?}: {}", path, e))?;
 
-    let mut ponos = Ponos::new();
-
-    // Перехватываем панику, если она случится
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        ponos.run_source(source);
-    }));
-
-    match result {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            if let Some(s) = e.downcast_ref::<String>() {
-                Err(format!("Паника при выполнении {}: {}", filename, s))
-            } else if let Some(s) = e.downcast_ref::<&str>() {
-                Err(format!("Паника при выполнении {}: {}", filename, s))
-            } else {
-                Err(format!("Неизвестная паника при выполнении {}", filename))
-            }
-        }
-    }
+    ponos
+        .run_source(source)
+        .map(|_| ())
+        .map_err(|e| format!("Ошибка при выполнении {}: {}", filename, e))
 }
 
 #[test]
@@ -43,6 +32,32 @@ fn test_class_constructor_param() {
         .expect("test_class_constructor_param.pns должен выполниться без ошибок");
 }
 
+#[test]
+fn test_class_static_members() {
+    run_pns_file("test_class_static_members.pns")
+        .expect("Статические поля и методы должны быть доступны через класс");
+}
+
+#[test]
+fn test_method_lambdas() {
+    run_pns_file("test_method_lambdas.pns").expect("Лямбды внутри методов должны захватывать это");
+}
+
+#[test]
+fn test_nil_coalescing() {
+    run_pns_file("test_nil_coalescing.pns").expect("Оператор ?? должен заменять только ничто");
+}
+
+#[test]
+fn test_integers() {
+    run_pns_file("test_integers.pns").expect("Целые числа должны продвигаться к вещественным");
+}
+
+#[test]
+fn test_string_ordering() {
+    run_pns_file("test_string_ordering.pns").expect("Строки должны упорядочиваться");
+}
+
 #[test]
 fn test_phase1_complete() {
     run_pns_file("test_phase1_complete.pns")
@@ -79,6 +94,24 @@ fn test_exception_dynamic() {
         .expect("Динамическое сообщение исключения должно работать");
 }
 
+#[test]
+fn test_exception_classes() {
+    run_pns_file("test_exception_classes.pns")
+        .expect("Ошибки выполнения должны перехватываться как экземпляры классов ошибок");
+}
+
+#[test]
+fn test_exception_finally() {
+    run_pns_file("test_exception_finally.pns")
+        .expect("Блок наконец должен выполняться при любом выходе из попытки");
+}
+
+#[test]
+fn test_exception_typed_catch() {
+    run_pns_file("test_exception_typed_catch.pns")
+        .expect("Ветки перехват должны выбираться по классу исключения");
+}
+
 #[test]
 fn test_exception_unhandled_panics() {
     assert!(
@@ -92,3 +125,206 @@ fn test_json_module() {
     run_pns_file("test_json_module.pns")
         .expect("Нативный модуль JSON должен корректно разбирать и сериализовывать данные");
 }
+
+#[test]
+fn test_os_module() {
+    run_pns_file("test_os_module.pns").expect("Модуль стд/ос должен сообщать сведения о машине");
+}
+
+#[test]
+fn test_assert_module() {
+    run_pns_file("test_assert_module.pns")
+        .expect("стд/тест.утверждать должен бросать исключение при ложном условии");
+}
+
+#[test]
+fn test_arrays_module() {
+    run_pns_file("test_arrays_module.pns")
+        .expect("стд/массивы должен сортировать с компаратором на Ponos");
+}
+
+#[test]
+fn test_string_builder() {
+    run_pns_file("test_string_builder.pns")
+        .expect("Построитель строк должен собирать строку из частей");
+}
+
+#[test]
+fn test_dicts_module() {
+    run_pns_file("test_dicts_module.pns").expect("стд/словари должен работать со словарями");
+}
+
+#[test]
+fn test_reflection() {
+    run_pns_file("test_reflection.pns")
+        .expect("тип, является и атрибуты должны описывать значения и экземпляры");
+}
+
+#[test]
+fn test_dict_keys() {
+    run_pns_file("test_dict_keys.pns").expect(
+        "словари должны принимать числовые и булевы ключи и обходиться по порядку добавления",
+    );
+}
+
+#[test]
+fn test_time_module() {
+    run_pns_file("test_time_module.pns")
+        .expect("Модуль стд/время должен работать с датами и таймерами");
+}
+
+#[test]
+fn test_fs_encodings() {
+    run_pns_file("test_fs_encodings.pns").expect("стд/фс должен читать файлы в разных кодировках");
+}
+
+#[test]
+fn test_math_module() {
+    run_pns_file("test_math_module.pns")
+        .expect("стд/математика должен опираться на нативные функции");
+}
+
+#[test]
+fn test_string_append() {
+    run_pns_file("test_string_append.pns").expect("x = x + строка должно работать как сложение");
+}
+
+#[test]
+fn test_stdlib_ponos_modules() {
+    run_pns_file("test_stdlib_ponos_modules.pns").expect(
+        "стд/коллекции, стд/текст и стд/утилиты должны загружаться из встроенных исходников",
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_system_execute() {
+    run_pns_file("test_system_execute.pns")
+        .expect("система.выполнить должен учитывать параметры запуска");
+}
+
+#[test]
+fn test_random_module() {
+    run_pns_file("test_random_module.pns").expect("стд/случайное должен воспроизводиться по зерну");
+}
+
+#[test]
+fn test_regex_module() {
+    run_pns_file("test_regex_module.pns")
+        .expect("стд/регэксп должен сопоставлять, искать и заменять");
+}
+
+#[test]
+fn test_path_module() {
+    run_pns_file("test_path_module.pns")
+        .expect("стд/путь должен разбирать пути и работать с каталогами");
+}
+
+#[test]
+fn test_sockets_module() {
+    run_pns_file("test_sockets_module.pns").expect("стд/сокеты должен передавать UDP-пакеты");
+}
+
+#[test]
+fn test_encodings_module() {
+    run_pns_file("test_encodings_module.pns")
+        .expect("стд/кодировки должен кодировать base64, hex и URL");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_process_module() {
+    run_pns_file("test_process_module.pns")
+        .expect("процесс.запустить должен разделять вывод и поддерживать потоковый режим");
+}
+
+#[test]
+fn test_import_visibility() {
+    run_pns_file("test_import_visibility.pns")
+        .expect("показать/скрыть должны ограничивать только перечисленные символы");
+}
+
+#[test]
+fn test_import_unprefixed() {
+    run_pns_file("test_import_unprefixed.pns")
+        .expect("имена из списка показать должны быть доступны без префикса");
+}
+
+#[test]
+fn test_default_params() {
+    run_pns_file("test_default_params.pns")
+        .expect("пропущенные аргументы должны получать значения по умолчанию при вызове");
+}
+
+#[test]
+fn test_variadic() {
+    run_pns_file("test_variadic.pns")
+        .expect("...остальные должен собирать лишние аргументы, а ...массив — разворачиваться");
+}
+
+#[test]
+fn test_coroutines() {
+    run_pns_file("test_coroutines.pns")
+        .expect("сопрограммы должны чередоваться на передать() и возвращать результат в ждать()");
+}
+
+#[test]
+fn test_timers() {
+    run_pns_file("test_timers.pns")
+        .expect("таймеры должны срабатывать по срокам, а сон задачи — не мешать другим");
+}
+
+#[test]
+fn test_threads() {
+    run_pns_file("test_threads.pns")
+        .expect("потоки должны выполняться в своих VM и обмениваться копиями значений");
+}
+
+#[test]
+fn test_reflection_module() {
+    run_pns_file("test_reflection_module.pns")
+        .expect("стд/рефлексия должен описывать модули и функции");
+}
+
+#[test]
+fn test_eval() {
+    run_pns_file("test_eval.pns")
+        .expect("выполнить_код должен выполнять код в текущей VM и бросать ошибки разбора");
+}
+
+#[test]
+fn test_module_access_in_blocks() {
+    run_pns_file("test_module_access_in_blocks.pns")
+        .expect("Обращения к модулю должны разрешаться в попытке, классах и коллекциях");
+}
+
+#[test]
+fn test_namespace_shadowing() {
+    run_pns_file("test_namespace_shadowing.pns")
+        .expect("Локальные имена должны скрывать пространство имён модуля");
+}
+
+#[test]
+fn test_newline_statements() {
+    run_pns_file("test_newline_statements.pns").expect("Перевод строки должен завершать оператор");
+}
+
+#[test]
+fn test_strict_stack_mode() {
+    // Строгий режим паникует, если инструкция нарушила стековый эффект
+    for fixture in [
+        "test_phase1_complete.pns",
+        "test_exception_finally.pns",
+        "test_exception_nested.pns",
+        "test_coroutines.pns",
+        "test_default_params.pns",
+        "test_variadic.pns",
+        "test_method_lambdas.pns",
+        "test_eval.pns",
+    ] {
+        let mut ponos = Ponos::new();
+        ponos.set_strict_stack(true);
+        run_pns_file_in(ponos, fixture)
+            .unwrap_or_else(|e| panic!("{} в строгом режиме стека: {}", fixture, e));
+    }
+}
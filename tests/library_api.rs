@@ -0,0 +1,30 @@
+use ponos_rs::ponos::ast::AstNode;
+use ponos_rs::{Generator, PonosParser, VM, Value};
+
+// Тесты публичного API библиотеки: конвейер парсер → генератор → VM без Ponos
+
+#[test]
+fn test_pipeline_through_public_types() {
+    let mut parser = PonosParser::new();
+    let program = parser
+        .parse("пер x = 2; x * 21;".to_string())
+        .expect("Программа должна разбираться");
+
+    let ctx = Generator::new()
+        .generate(AstNode::Program(program))
+        .expect("Программа должна компилироваться");
+
+    let mut vm = VM::new();
+    vm.execute(ctx.opcodes, &ctx.constants)
+        .expect("Байткод должен проходить проверку");
+
+    assert_eq!(vm.stack.last(), Some(&Value::Int(42)));
+}
+
+#[test]
+fn test_parse_error_is_public() {
+    let mut parser = PonosParser::new();
+    let error = parser.parse("пер = ;".to_string()).unwrap_err();
+
+    assert!(!error.format("пер = ;", "<тест>").is_empty());
+}
@@ -9,7 +9,7 @@ fn test_and_operator_both_true() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -19,7 +19,7 @@ fn test_and_operator_first_false() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -29,7 +29,7 @@ fn test_and_operator_second_false() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -39,7 +39,7 @@ fn test_and_operator_both_false() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -49,7 +49,7 @@ fn test_or_operator_both_true() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -59,7 +59,7 @@ fn test_or_operator_first_true() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -69,7 +69,7 @@ fn test_or_operator_second_true() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -79,7 +79,7 @@ fn test_or_operator_both_false() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -92,7 +92,7 @@ fn test_and_short_circuit() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -105,7 +105,7 @@ fn test_or_short_circuit() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -115,7 +115,7 @@ fn test_combined_logical_operators() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -128,5 +128,5 @@ fn test_logical_operators_with_comparison() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
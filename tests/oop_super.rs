@@ -19,7 +19,7 @@ fn test_super_method_call() {
             конец
 
             функ говорить(): строка
-                возврат super.говорить();
+                возврат родитель.говорить();
             конец
         конец
 
@@ -28,7 +28,7 @@ fn test_super_method_call() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -52,7 +52,7 @@ fn test_super_in_override() {
             конец
 
             функ получить(): число
-                возврат super.получить();
+                возврат родитель.получить();
             конец
         конец
 
@@ -61,5 +61,5 @@ fn test_super_in_override() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
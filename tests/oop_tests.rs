@@ -8,7 +8,7 @@ fn test_minimal_class() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
     // Тест просто проверяет, что нет паники
 }
 
@@ -24,7 +24,7 @@ fn test_class_with_constructor() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -42,7 +42,7 @@ fn test_class_with_field_and_constructor() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -65,7 +65,7 @@ fn test_class_with_method() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -88,7 +88,7 @@ fn test_class_method_call() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 // Тесты наследования
@@ -115,7 +115,7 @@ fn test_simple_inheritance() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -144,7 +144,7 @@ fn test_inheritance_with_override() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -174,7 +174,7 @@ fn test_inheritance_with_own_methods() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
 
 #[test]
@@ -203,5 +203,5 @@ fn test_inheritance_with_fields() {
     "#;
 
     let mut ponos = Ponos::new();
-    ponos.run_source(source.to_string());
+    ponos.run_source(source.to_string()).unwrap();
 }
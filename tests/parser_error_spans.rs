@@ -168,9 +168,9 @@ fn parser_points_to_error_in_full_program() {
 }
 
 #[test]
-fn parser_reports_missing_semicolon_before_end() {
+fn parser_reports_missing_semicolon_between_statements_on_one_line() {
     let source = r#"функ f()
-  возврат 1
+  вывести(0) возврат 1
 конец
 "#;
 
@@ -180,13 +180,21 @@ fn parser_reports_missing_semicolon_before_end() {
         .expect_err("должна быть ошибка из-за отсутствующей ;");
 
     let expected_offset = source
-        .find("конец")
-        .expect("пример должен содержать конец");
+        .find("возврат")
+        .expect("пример должен содержать возврат");
     let (expected_line, expected_col) = line_col_at(source, expected_offset);
     let (start, _) = err.span.to_location(source);
     assert_eq!(
         (start.line, start.column),
         (expected_line, expected_col),
-        "span должен указывать на начало 'конец', где парсер ожидает ';'"
+        "span должен указывать на начало 'возврат', где парсер ожидает ';'"
     );
 }
+
+#[test]
+fn parser_accepts_newline_before_end() {
+    let source = "функ f()\n  возврат 1\nконец\n";
+    Ponos::new()
+        .parse_only(source.to_string())
+        .expect("перевод строки завершает оператор перед 'конец'");
+}
@@ -0,0 +1,60 @@
+use ponos_rs::{Ponos, Value};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+// TCP-часть стд/сокеты: эхо-сервер на Rust, клиент — программа Ponos
+
+#[test]
+fn test_tcp_round_trip() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = [0; 64];
+        let read = stream.read(&mut buffer).unwrap();
+        stream.write_all(b"echo: ").unwrap();
+        stream.write_all(&buffer[..read]).unwrap();
+    });
+
+    let mut ponos = Ponos::new();
+    ponos
+        .eval(&format!(
+            "использовать \"стд/сокеты\" как сокеты;
+             пер сокет = сокеты.подключиться(\"127.0.0.1\", {}, 5000);
+             сокеты.отправить(сокет, \"пинг\");
+             пер ответ = \"\";
+             пер часть = сокеты.получить(сокет);
+             пока часть != \"\"
+                 ответ = ответ + часть;
+                 часть = сокеты.получить(сокет);
+             конец
+             сокеты.закрыть(сокет);",
+            port
+        ))
+        .unwrap();
+    server.join().unwrap();
+
+    assert_eq!(ponos.get_global("ответ"), Some(Value::from("echo: пинг")));
+    let error = ponos.eval("сокеты.отправить(сокет, \"ещё\");").unwrap_err();
+    assert!(error.contains("закрыт"), "{}", error);
+}
+
+#[test]
+fn test_connect_to_closed_port_fails() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let mut ponos = Ponos::new();
+    let error = ponos
+        .eval(&format!(
+            "использовать \"стд/сокеты\" как сокеты;
+             сокеты.подключиться(\"127.0.0.1\", {});",
+            port
+        ))
+        .unwrap_err();
+    assert!(error.contains("Не удалось подключиться"), "{}", error);
+}